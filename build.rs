@@ -0,0 +1,36 @@
+use std::env;
+
+// The libvirt releases that introduced APIs wrapped by this crate, newer than
+// the minimum version required by virt-sys. For each of them a
+// `libvirt_<major>_<minor>` cfg is set when the installed library is at least
+// that recent, and the wrappers of these APIs are only built under it.
+const RELEASES: &[(u64, u64)] = &[
+    (6, 2),
+    (7, 1),
+    (7, 2),
+    (7, 3),
+    (7, 7),
+    (7, 8),
+    (8, 5),
+    (9, 7),
+    (10, 1),
+];
+
+fn main() {
+    println!("cargo:rerun-if-env-changed=DEP_VIRT_VERSION_NUMBER");
+
+    // The version is unknown when virt-sys could not find libvirt, e.g. when
+    // building the documentation: keep every API in that case.
+    let version = env::var("DEP_VIRT_VERSION_NUMBER")
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .unwrap_or(u64::MAX);
+
+    for (major, minor) in RELEASES {
+        let name = format!("libvirt_{}_{}", major, minor);
+        println!("cargo:rustc-check-cfg=cfg({})", name);
+        if version >= major * 1_000_000 + minor * 1_000 {
+            println!("cargo:rustc-cfg={}", name);
+        }
+    }
+}
@@ -0,0 +1,192 @@
+/*
+ * This library is free software; you can redistribute it and/or
+ * modify it under the terms of the GNU Lesser General Public
+ * License as published by the Free Software Foundation; either
+ * version 2.1 of the License, or (at your option) any later version.
+ *
+ * This library is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+ * Lesser General Public License for more details.
+ *
+ * You should have received a copy of the GNU Lesser General Public
+ * License along with this library.  If not, see
+ * <https://www.gnu.org/licenses/>.
+ */
+
+//! This file contains example code for a minimal Prometheus exporter
+//! built on top of the `virt::metrics` module.
+//!
+//! Without a listen address, the metrics are printed once on the
+//! standard output:
+//!
+//!   cargo run --example exporter -- test:///default
+//!   cargo run --example exporter -- qemu:///system 127.0.0.1:9177
+
+use std::env;
+use std::fmt::Write as _;
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+
+use virt::connect::Connect;
+use virt::error::Error;
+use virt::metrics::{self, BlockMetrics, DomainMetrics, InterfaceMetrics};
+
+type Series<T> = (&'static str, fn(&T) -> u64);
+
+fn metric(out: &mut String, name: &str, help: &str, kind: &str) {
+    let _ = writeln!(out, "# HELP libvirt_{} {}", name, help);
+    let _ = writeln!(out, "# TYPE libvirt_{} {}", name, kind);
+}
+
+fn render(doms: &[DomainMetrics]) -> String {
+    let mut out = String::new();
+
+    let domain_gauges: &[(&str, Series<DomainMetrics>)] = &[
+        (
+            "The state of the domain.",
+            ("domain_state", |d| d.state as u64),
+        ),
+        (
+            "The number of online vCPUs.",
+            ("domain_vcpus", |d| d.vcpus as u64),
+        ),
+        (
+            "The memory assigned to the domain.",
+            ("domain_memory_current_kibibytes", |d| d.memory_current),
+        ),
+        (
+            "The maximum memory of the domain.",
+            ("domain_memory_maximum_kibibytes", |d| d.memory_maximum),
+        ),
+        (
+            "The resident set size of the domain.",
+            ("domain_memory_rss_kibibytes", |d| d.memory_rss),
+        ),
+    ];
+    for (help, (name, value)) in domain_gauges {
+        metric(&mut out, name, help, "gauge");
+        for d in doms {
+            let _ = writeln!(
+                out,
+                "libvirt_{}{{domain=\"{}\",uuid=\"{}\"}} {}",
+                name,
+                d.name,
+                d.uuid,
+                value(d)
+            );
+        }
+    }
+
+    metric(
+        &mut out,
+        "domain_cpu_time_seconds_total",
+        "The CPU time used by the domain.",
+        "counter",
+    );
+    for d in doms {
+        let _ = writeln!(
+            out,
+            "libvirt_domain_cpu_time_seconds_total{{domain=\"{}\",uuid=\"{}\"}} {}",
+            d.name,
+            d.uuid,
+            d.cpu_time as f64 / 1e9
+        );
+    }
+
+    let block_counters: &[Series<BlockMetrics>] = &[
+        ("domain_block_read_bytes_total", |b| b.read_bytes),
+        ("domain_block_read_requests_total", |b| b.read_requests),
+        ("domain_block_write_bytes_total", |b| b.write_bytes),
+        ("domain_block_write_requests_total", |b| b.write_requests),
+    ];
+    for (name, value) in block_counters {
+        metric(&mut out, name, "Block device I/O.", "counter");
+        for d in doms {
+            for b in &d.blocks {
+                let _ = writeln!(
+                    out,
+                    "libvirt_{}{{domain=\"{}\",device=\"{}\"}} {}",
+                    name,
+                    d.name,
+                    b.name,
+                    value(b)
+                );
+            }
+        }
+    }
+
+    let net_counters: &[Series<InterfaceMetrics>] = &[
+        ("domain_interface_receive_bytes_total", |n| n.rx_bytes),
+        ("domain_interface_receive_packets_total", |n| n.rx_packets),
+        ("domain_interface_transmit_bytes_total", |n| n.tx_bytes),
+        ("domain_interface_transmit_packets_total", |n| n.tx_packets),
+    ];
+    for (name, value) in net_counters {
+        metric(&mut out, name, "Network interface traffic.", "counter");
+        for d in doms {
+            for n in &d.interfaces {
+                let _ = writeln!(
+                    out,
+                    "libvirt_{}{{domain=\"{}\",interface=\"{}\"}} {}",
+                    name,
+                    d.name,
+                    n.name,
+                    value(n)
+                );
+            }
+        }
+    }
+
+    out
+}
+
+fn scrape(conn: &Connect) -> Result<String, Error> {
+    Ok(render(&metrics::collect(conn)?))
+}
+
+fn main() {
+    let uri = env::args().nth(1);
+    let listen = env::args().nth(2);
+
+    let mut conn = match Connect::open(uri.as_deref()) {
+        Ok(c) => c,
+        Err(e) => panic!("No connection to hypervisor: {}", e),
+    };
+
+    match listen {
+        None => match scrape(&conn) {
+            Ok(body) => print!("{}", body),
+            Err(e) => eprintln!("Failed to collect metrics: {}", e),
+        },
+        Some(addr) => {
+            let listener = TcpListener::bind(&addr).expect("failed to bind the listen address");
+            println!("Serving metrics on http://{}/metrics", addr);
+            for stream in listener.incoming() {
+                let mut stream = match stream {
+                    Ok(s) => s,
+                    Err(_) => continue,
+                };
+                // Only the request line matters, any path is answered.
+                let mut request = String::new();
+                if BufReader::new(&stream).read_line(&mut request).is_err() {
+                    continue;
+                }
+                let (status, body) = match scrape(&conn) {
+                    Ok(body) => ("200 OK", body),
+                    Err(e) => ("500 Internal Server Error", format!("{}\n", e)),
+                };
+                let _ = write!(
+                    stream,
+                    "HTTP/1.1 {}\r\nContent-Type: text/plain; version=0.0.4\r\n\
+                     Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status,
+                    body.len(),
+                    body
+                );
+            }
+        }
+    }
+
+    let _ = conn.close();
+}
@@ -1,7 +1,6 @@
 use std::env;
 use virt::connect::Connect;
-use virt::domain::Domain;
-use virt::sys;
+use virt::domain::{Domain, QemuAgentTimeout};
 
 fn main() {
     let uri = env::args().nth(1);
@@ -10,11 +9,8 @@ fn main() {
     let conn = Connect::open(uri.as_deref()).unwrap();
 
     let domain = Domain::lookup_by_name(&conn, &name).unwrap();
-    let result = domain.qemu_agent_command(
-        "{\"execute\": \"guest-info\"}",
-        sys::VIR_DOMAIN_QEMU_AGENT_COMMAND_BLOCK,
-        0,
-    );
+    let result =
+        domain.qemu_agent_command("{\"execute\": \"guest-info\"}", QemuAgentTimeout::Block, 0);
     match result {
         Ok(r) => println!("Result: {}", r),
         Err(e) => eprintln!("Error: {}", e),
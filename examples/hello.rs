@@ -30,15 +30,8 @@ use virt::sys;
 
 fn show_hypervisor_info(conn: &Connect) -> Result<(), Error> {
     if let Ok(hv_type) = conn.get_type() {
-        if let Ok(mut hv_ver) = conn.get_hyp_version() {
-            let major = hv_ver / 1000000;
-            hv_ver %= 1000000;
-            let minor = hv_ver / 1000;
-            let release = hv_ver % 1000;
-            println!(
-                "Hypervisor: '{}' version: {}.{}.{}",
-                hv_type, major, minor, release
-            );
+        if let Ok(hv_ver) = conn.get_hyp_version_triple() {
+            println!("Hypervisor: '{}' version: {}", hv_type, hv_ver);
             return Ok(());
         }
     }
@@ -74,18 +67,35 @@ fn show_domains(conn: &Connect) -> Result<(), Error> {
                     }
                     if let Ok(memtune) = dom.get_memory_parameters(0) {
                         println!("Memory tune:");
-                        println!("    Hard Limit: {}", memtune.hard_limit.unwrap_or(0));
-                        println!("    Soft Limit: {}", memtune.soft_limit.unwrap_or(0));
+                        println!(
+                            "    Hard Limit: {}",
+                            memtune
+                                .hard_limit
+                                .map(|l| l.to_string())
+                                .unwrap_or_default()
+                        );
+                        println!(
+                            "    Soft Limit: {}",
+                            memtune
+                                .soft_limit
+                                .map(|l| l.to_string())
+                                .unwrap_or_default()
+                        );
                         println!("    Min Guarantee: {}", memtune.min_guarantee.unwrap_or(0));
                         println!(
                             "    Swap Hard Limit: {}",
-                            memtune.swap_hard_limit.unwrap_or(0)
+                            memtune
+                                .swap_hard_limit
+                                .map(|l| l.to_string())
+                                .unwrap_or_default()
                         );
                     }
                     if let Ok(numa) = dom.get_numa_parameters(0) {
                         println!("NUMA:");
                         println!("    Node Set: {}", numa.node_set.unwrap_or_default());
-                        println!("    Mode: {}", numa.mode.unwrap_or(0));
+                        if let Some(mode) = numa.mode {
+                            println!("    Mode: {:?}", mode);
+                        }
                     }
 
                     if let Ok((sched_type, nparams)) = dom.get_scheduler_type() {
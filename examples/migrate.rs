@@ -21,9 +21,8 @@
 use std::env;
 
 use virt::connect::Connect;
-use virt::domain::Domain;
 use virt::domain::MigrateParameters;
-use virt::sys;
+use virt::domain::{flags, Domain};
 
 fn main() {
     if env::args().len() < 4 {
@@ -53,13 +52,13 @@ fn main() {
     };
 
     if let Ok(dom) = Domain::lookup_by_name(&conn, &dname) {
-        let flags = sys::VIR_MIGRATE_LIVE;
+        let flags = flags::MIGRATE_LIVE;
         let migrate_parameters = MigrateParameters {
             dest_name: Some(dname.clone()),
             ..Default::default()
         };
         if let Ok(new_dom) = dom.migrate3(&dconn, migrate_parameters, flags) {
-            if let Ok(job_stats) = new_dom.get_job_stats(sys::VIR_DOMAIN_JOB_STATS_COMPLETED) {
+            if let Ok(job_stats) = new_dom.get_completed_job_stats() {
                 println!(
                     "Migration completed in {}ms",
                     job_stats
@@ -0,0 +1,748 @@
+/*
+ * This library is free software; you can redistribute it and/or
+ * modify it under the terms of the GNU Lesser General Public
+ * License as published by the Free Software Foundation; either
+ * version 2.1 of the License, or (at your option) any later version.
+ *
+ * This library is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+ * Lesser General Public License for more details.
+ *
+ * You should have received a copy of the GNU Lesser General Public
+ * License along with this library.  If not, see
+ * <https://www.gnu.org/licenses/>.
+ *
+ * Sahid Orentino Ferdjaoui <sahid.ferdjaoui@redhat.com>
+ */
+
+use std::ffi::CString;
+use std::ptr;
+
+use crate::error::{report_drop_error, Error};
+use crate::typedparams::{from_params, to_params, TypedParamList};
+use crate::util::impl_enum;
+use crate::{param_field_in, param_field_out};
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+/// The transport used by a client to connect to a daemon.
+///
+/// See <https://libvirt.org/html/libvirt-libvirt-admin.html#virClientTransport>
+pub enum ClientTransport {
+    /// Connection via UNIX socket.
+    Unix,
+    /// Connection via unencrypted TCP socket.
+    Tcp,
+    /// Connection via encrypted TCP socket.
+    Tls,
+}
+
+impl_enum! {
+    enum: ClientTransport,
+    raw: sys::virClientTransport,
+    match: {
+        sys::VIR_CLIENT_TRANS_UNIX => ClientTransport::Unix,
+        sys::VIR_CLIENT_TRANS_TCP => ClientTransport::Tcp,
+        sys::VIR_CLIENT_TRANS_TLS => ClientTransport::Tls,
+        _ => ClientTransport::Unix,
+    }
+}
+
+macro_rules! threadpool_parameters_fields {
+    ($dir:ident, $var:ident) => {
+        vec![
+            $dir!(sys::VIR_THREADPOOL_WORKERS_MIN, UInt32, $var.min_workers),
+            $dir!(sys::VIR_THREADPOOL_WORKERS_MAX, UInt32, $var.max_workers),
+            $dir!(
+                sys::VIR_THREADPOOL_WORKERS_PRIORITY,
+                UInt32,
+                $var.priority_workers
+            ),
+            $dir!(sys::VIR_THREADPOOL_WORKERS_FREE, UInt32, $var.free_workers),
+            $dir!(
+                sys::VIR_THREADPOOL_WORKERS_CURRENT,
+                UInt32,
+                $var.current_workers
+            ),
+            $dir!(
+                sys::VIR_THREADPOOL_JOB_QUEUE_DEPTH,
+                UInt32,
+                $var.job_queue_depth
+            ),
+        ]
+    };
+}
+
+/// Parameters of the worker thread pool of a server.
+#[derive(Clone, Debug, Default)]
+pub struct ThreadPoolParameters {
+    /// The minimum number of workers.
+    pub min_workers: Option<u32>,
+    /// The maximum number of workers.
+    pub max_workers: Option<u32>,
+    /// The number of priority workers.
+    pub priority_workers: Option<u32>,
+    /// The number of free workers, read-only.
+    pub free_workers: Option<u32>,
+    /// The current number of workers, read-only.
+    pub current_workers: Option<u32>,
+    /// The current depth of the job queue, read-only.
+    pub job_queue_depth: Option<u32>,
+}
+
+impl ThreadPoolParameters {
+    pub fn from_vec(vec: Vec<sys::virTypedParameter>) -> ThreadPoolParameters {
+        let mut ret = ThreadPoolParameters::default();
+        let fields = threadpool_parameters_fields!(param_field_in, ret);
+        from_params(&vec, fields);
+        ret
+    }
+
+    pub fn to_vec(&self) -> Result<Vec<sys::virTypedParameter>, Error> {
+        let fields = threadpool_parameters_fields!(param_field_out, self);
+        to_params(fields)
+    }
+}
+
+macro_rules! client_limits_fields {
+    ($dir:ident, $var:ident) => {
+        vec![
+            $dir!(sys::VIR_SERVER_CLIENTS_MAX, UInt32, $var.max_clients),
+            $dir!(
+                sys::VIR_SERVER_CLIENTS_CURRENT,
+                UInt32,
+                $var.current_clients
+            ),
+            $dir!(
+                sys::VIR_SERVER_CLIENTS_UNAUTH_MAX,
+                UInt32,
+                $var.max_unauth_clients
+            ),
+            $dir!(
+                sys::VIR_SERVER_CLIENTS_UNAUTH_CURRENT,
+                UInt32,
+                $var.current_unauth_clients
+            ),
+        ]
+    };
+}
+
+/// Limits on the clients connected to a server.
+#[derive(Clone, Debug, Default)]
+pub struct ClientLimits {
+    /// The maximum number of clients.
+    pub max_clients: Option<u32>,
+    /// The current number of clients, read-only.
+    pub current_clients: Option<u32>,
+    /// The maximum number of clients waiting for authentication.
+    pub max_unauth_clients: Option<u32>,
+    /// The current number of clients waiting for authentication,
+    /// read-only.
+    pub current_unauth_clients: Option<u32>,
+}
+
+impl ClientLimits {
+    pub fn from_vec(vec: Vec<sys::virTypedParameter>) -> ClientLimits {
+        let mut ret = ClientLimits::default();
+        let fields = client_limits_fields!(param_field_in, ret);
+        from_params(&vec, fields);
+        ret
+    }
+
+    pub fn to_vec(&self) -> Result<Vec<sys::virTypedParameter>, Error> {
+        let fields = client_limits_fields!(param_field_out, self);
+        to_params(fields)
+    }
+}
+
+macro_rules! client_info_fields {
+    ($dir:ident, $var:ident) => {
+        vec![
+            $dir!(sys::VIR_CLIENT_INFO_READONLY, Bool, $var.readonly),
+            $dir!(sys::VIR_CLIENT_INFO_SOCKET_ADDR, String, $var.socket_addr),
+            $dir!(
+                sys::VIR_CLIENT_INFO_SASL_USER_NAME,
+                String,
+                $var.sasl_user_name
+            ),
+            $dir!(
+                sys::VIR_CLIENT_INFO_X509_DISTINGUISHED_NAME,
+                String,
+                $var.x509_distinguished_name
+            ),
+            $dir!(sys::VIR_CLIENT_INFO_UNIX_USER_ID, Int32, $var.unix_user_id),
+            $dir!(
+                sys::VIR_CLIENT_INFO_UNIX_USER_NAME,
+                String,
+                $var.unix_user_name
+            ),
+            $dir!(
+                sys::VIR_CLIENT_INFO_UNIX_GROUP_ID,
+                Int32,
+                $var.unix_group_id
+            ),
+            $dir!(
+                sys::VIR_CLIENT_INFO_UNIX_GROUP_NAME,
+                String,
+                $var.unix_group_name
+            ),
+            $dir!(
+                sys::VIR_CLIENT_INFO_UNIX_PROCESS_ID,
+                Int32,
+                $var.unix_process_id
+            ),
+            $dir!(
+                sys::VIR_CLIENT_INFO_SELINUX_CONTEXT,
+                String,
+                $var.selinux_context
+            ),
+        ]
+    };
+}
+
+/// Identity information about a client connected to a server.
+#[derive(Clone, Debug, Default)]
+pub struct ClientInfo {
+    /// Whether the client is connected read-only.
+    pub readonly: Option<bool>,
+    /// The socket address of the client.
+    pub socket_addr: Option<String>,
+    /// The SASL user name of the client.
+    pub sasl_user_name: Option<String>,
+    /// The distinguished name of the client TLS certificate.
+    pub x509_distinguished_name: Option<String>,
+    /// The UNIX user ID of the client process.
+    pub unix_user_id: Option<i32>,
+    /// The UNIX user name of the client process.
+    pub unix_user_name: Option<String>,
+    /// The UNIX group ID of the client process.
+    pub unix_group_id: Option<i32>,
+    /// The UNIX group name of the client process.
+    pub unix_group_name: Option<String>,
+    /// The process ID of the client.
+    pub unix_process_id: Option<i32>,
+    /// The SELinux context of the client process.
+    pub selinux_context: Option<String>,
+}
+
+impl ClientInfo {
+    pub fn from_vec(vec: Vec<sys::virTypedParameter>) -> ClientInfo {
+        let mut ret = ClientInfo::default();
+        let fields = client_info_fields!(param_field_in, ret);
+        from_params(&vec, fields);
+        ret
+    }
+}
+
+/// Provides APIs for the management of the libvirt daemons.
+///
+/// See <https://libvirt.org/html/libvirt-libvirt-admin.html>
+#[derive(Debug)]
+pub struct AdmConnect {
+    ptr: Option<sys::virAdmConnectPtr>,
+}
+
+impl Clone for AdmConnect {
+    /// Creates a copy of an admin connection.
+    ///
+    /// Increments the internal reference counter on the given
+    /// connection. For each call to this method, there shall be a
+    /// corresponding call to [`close()`].
+    ///
+    /// [`close()`]: AdmConnect::close
+    fn clone(&self) -> Self {
+        self.add_ref().unwrap()
+    }
+}
+
+unsafe impl Send for AdmConnect {}
+unsafe impl Sync for AdmConnect {}
+
+impl AdmConnect {
+    pub fn as_ptr(&self) -> sys::virAdmConnectPtr {
+        self.ptr.unwrap()
+    }
+
+    /// # Safety
+    ///
+    /// The caller must ensure that the pointer is valid.
+    pub unsafe fn from_ptr(ptr: sys::virAdmConnectPtr) -> AdmConnect {
+        AdmConnect { ptr: Some(ptr) }
+    }
+
+    fn add_ref(&self) -> Result<AdmConnect, Error> {
+        unsafe {
+            if ffi!(sys::virAdmConnectRef(self.as_ptr())) == -1 {
+                return Err(Error::last_error());
+            }
+        }
+
+        Ok(unsafe { AdmConnect::from_ptr(self.as_ptr()) })
+    }
+
+    /// Returns the version of the admin library.
+    pub fn get_version() -> Result<u64, Error> {
+        let mut ver: libc::c_ulonglong = 0;
+        let ret = unsafe { ffi!(sys::virAdmGetVersion(&mut ver)) };
+        if ret == -1 {
+            return Err(Error::last_error());
+        }
+        Ok(ver)
+    }
+
+    /// Opens an admin connection to the daemon at `uri`, e.g.
+    /// "libvirtd:///system" or "virtqemud:///session".
+    ///
+    /// If `uri` is `None`, the LIBVIRT_ADMIN_DEFAULT_URI environment
+    /// variable or the "admin_uri_default" client configuration
+    /// parameter is used, falling back to the libvirtd daemon.
+    ///
+    /// [`close()`] should be used to release the resources after the
+    /// connection is no longer needed.
+    ///
+    /// [`close()`]: AdmConnect::close
+    pub fn open(uri: Option<&str>, flags: u32) -> Result<AdmConnect, Error> {
+        let uri_buf = some_string_to_cstring!(uri);
+        let c = unsafe {
+            ffi!(sys::virAdmConnectOpen(
+                some_cstring_to_c_chars!(uri_buf),
+                flags as libc::c_uint
+            ))
+        };
+        if c.is_null() {
+            return Err(Error::last_error());
+        }
+        Ok(unsafe { AdmConnect::from_ptr(c) })
+    }
+
+    /// Releases the admin connection.
+    ///
+    /// Returns the number of references remaining on the connection.
+    pub fn close(&mut self) -> Result<i32, Error> {
+        let ret = unsafe { ffi!(sys::virAdmConnectClose(self.as_ptr())) };
+        if ret == -1 {
+            return Err(Error::last_error());
+        }
+        // Because of add_ref() we must refrain from using the
+        // connection further.
+        self.ptr = None;
+        Ok(ret)
+    }
+
+    pub fn is_alive(&self) -> Result<bool, Error> {
+        let ret = unsafe { ffi!(sys::virAdmConnectIsAlive(self.as_ptr())) };
+        if ret == -1 {
+            return Err(Error::last_error());
+        }
+        Ok(ret == 1)
+    }
+
+    pub fn get_uri(&self) -> Result<String, Error> {
+        let ret = unsafe { ffi!(sys::virAdmConnectGetURI(self.as_ptr())) };
+        if ret.is_null() {
+            return Err(Error::last_error());
+        }
+        Ok(unsafe { c_chars_to_string!(ret) })
+    }
+
+    /// Returns the version of libvirt used by the daemon.
+    pub fn get_lib_version(&self) -> Result<u64, Error> {
+        let mut ver: libc::c_ulonglong = 0;
+        let ret = unsafe { ffi!(sys::virAdmConnectGetLibVersion(self.as_ptr(), &mut ver)) };
+        if ret == -1 {
+            return Err(Error::last_error());
+        }
+        Ok(ver)
+    }
+
+    /// Lists the servers run by the daemon.
+    pub fn list_servers(&self, flags: u32) -> Result<Vec<AdmServer>, Error> {
+        let mut servers: *mut sys::virAdmServerPtr = ptr::null_mut();
+        let size = unsafe {
+            ffi!(sys::virAdmConnectListServers(
+                self.as_ptr(),
+                &mut servers,
+                flags as libc::c_uint
+            ))
+        };
+        if size == -1 {
+            return Err(Error::last_error());
+        }
+
+        let mut array: Vec<AdmServer> = Vec::with_capacity(size as usize);
+        for x in 0..size as isize {
+            array.push(unsafe { AdmServer::from_ptr(*servers.offset(x)) });
+        }
+        unsafe { libc::free(servers as *mut libc::c_void) };
+
+        Ok(array)
+    }
+
+    pub fn lookup_server(&self, name: &str, flags: u32) -> Result<AdmServer, Error> {
+        let name_buf = CString::new(name)?;
+        let ptr = unsafe {
+            ffi!(sys::virAdmConnectLookupServer(
+                self.as_ptr(),
+                name_buf.as_ptr(),
+                flags as libc::c_uint
+            ))
+        };
+        if ptr.is_null() {
+            return Err(Error::last_error());
+        }
+        Ok(unsafe { AdmServer::from_ptr(ptr) })
+    }
+
+    /// Returns the logging outputs of the daemon, e.g.
+    /// "3:file:/var/log/libvirt/libvirtd.log".
+    pub fn get_logging_outputs(&self, flags: u32) -> Result<String, Error> {
+        let mut outputs: *mut libc::c_char = ptr::null_mut();
+        let ret = unsafe {
+            ffi!(sys::virAdmConnectGetLoggingOutputs(
+                self.as_ptr(),
+                &mut outputs,
+                flags as libc::c_uint
+            ))
+        };
+        if ret == -1 {
+            return Err(Error::last_error());
+        }
+        if outputs.is_null() {
+            return Ok(String::new());
+        }
+        Ok(unsafe { c_chars_to_string!(outputs) })
+    }
+
+    /// Returns the logging filters of the daemon, e.g.
+    /// "1:qemu 3:util".
+    pub fn get_logging_filters(&self, flags: u32) -> Result<String, Error> {
+        let mut filters: *mut libc::c_char = ptr::null_mut();
+        let ret = unsafe {
+            ffi!(sys::virAdmConnectGetLoggingFilters(
+                self.as_ptr(),
+                &mut filters,
+                flags as libc::c_uint
+            ))
+        };
+        if ret == -1 {
+            return Err(Error::last_error());
+        }
+        if filters.is_null() {
+            return Ok(String::new());
+        }
+        Ok(unsafe { c_chars_to_string!(filters) })
+    }
+
+    /// Replaces the logging outputs of the daemon.
+    ///
+    /// If `outputs` is `None`, the default outputs are restored.
+    pub fn set_logging_outputs(&self, outputs: Option<&str>, flags: u32) -> Result<(), Error> {
+        let outputs_buf = some_string_to_cstring!(outputs);
+        let ret = unsafe {
+            ffi!(sys::virAdmConnectSetLoggingOutputs(
+                self.as_ptr(),
+                some_cstring_to_c_chars!(outputs_buf),
+                flags as libc::c_uint,
+            ))
+        };
+        if ret == -1 {
+            return Err(Error::last_error());
+        }
+        Ok(())
+    }
+
+    /// Replaces the logging filters of the daemon.
+    ///
+    /// If `filters` is `None`, all the filters are removed.
+    pub fn set_logging_filters(&self, filters: Option<&str>, flags: u32) -> Result<(), Error> {
+        let filters_buf = some_string_to_cstring!(filters);
+        let ret = unsafe {
+            ffi!(sys::virAdmConnectSetLoggingFilters(
+                self.as_ptr(),
+                some_cstring_to_c_chars!(filters_buf),
+                flags as libc::c_uint,
+            ))
+        };
+        if ret == -1 {
+            return Err(Error::last_error());
+        }
+        Ok(())
+    }
+}
+
+/// A server run by a libvirt daemon, e.g. "libvirtd" or "admin".
+#[derive(Debug)]
+pub struct AdmServer {
+    ptr: Option<sys::virAdmServerPtr>,
+}
+
+unsafe impl Send for AdmServer {}
+unsafe impl Sync for AdmServer {}
+
+impl Drop for AdmServer {
+    fn drop(&mut self) {
+        if self.ptr.is_some() {
+            if let Err(e) = self.free() {
+                report_drop_error("AdmServer", e);
+            }
+        }
+    }
+}
+
+impl AdmServer {
+    /// # Safety
+    ///
+    /// The caller must ensure that the pointer is valid.
+    pub unsafe fn from_ptr(ptr: sys::virAdmServerPtr) -> AdmServer {
+        AdmServer { ptr: Some(ptr) }
+    }
+
+    pub fn as_ptr(&self) -> sys::virAdmServerPtr {
+        self.ptr.unwrap()
+    }
+
+    pub fn free(&mut self) -> Result<(), Error> {
+        let ret = unsafe { ffi!(sys::virAdmServerFree(self.as_ptr())) };
+        if ret == -1 {
+            return Err(Error::last_error());
+        }
+        self.ptr = None;
+        Ok(())
+    }
+
+    pub fn get_name(&self) -> Result<String, Error> {
+        let n = unsafe { ffi!(sys::virAdmServerGetName(self.as_ptr())) };
+        if n.is_null() {
+            return Err(Error::last_error());
+        }
+        Ok(unsafe { c_chars_to_string!(n, nofree) })
+    }
+
+    pub fn get_threadpool_parameters(&self, flags: u32) -> Result<ThreadPoolParameters, Error> {
+        let mut nparams: libc::c_int = 0;
+        let mut params: sys::virTypedParameterPtr = ptr::null_mut();
+        let ret = unsafe {
+            ffi!(sys::virAdmServerGetThreadPoolParameters(
+                self.as_ptr(),
+                &mut params,
+                &mut nparams,
+                flags as libc::c_uint,
+            ))
+        };
+        if ret == -1 {
+            return Err(Error::last_error());
+        }
+
+        let res = unsafe { TypedParamList::from_raw(params, nparams) };
+
+        Ok(ThreadPoolParameters::from_vec(res.params()))
+    }
+
+    /// Changes the parameters of the worker thread pool.
+    ///
+    /// Only `min_workers`, `max_workers` and `priority_workers` can
+    /// be changed.
+    pub fn set_threadpool_parameters(
+        &self,
+        params: ThreadPoolParameters,
+        flags: u32,
+    ) -> Result<(), Error> {
+        let mut cparams = TypedParamList::from_vec(params.to_vec()?);
+        let ret = unsafe {
+            ffi!(sys::virAdmServerSetThreadPoolParameters(
+                self.as_ptr(),
+                cparams.as_mut_ptr(),
+                cparams.len(),
+                flags as libc::c_uint,
+            ))
+        };
+        if ret == -1 {
+            return Err(Error::last_error());
+        }
+        Ok(())
+    }
+
+    pub fn get_client_limits(&self, flags: u32) -> Result<ClientLimits, Error> {
+        let mut nparams: libc::c_int = 0;
+        let mut params: sys::virTypedParameterPtr = ptr::null_mut();
+        let ret = unsafe {
+            ffi!(sys::virAdmServerGetClientLimits(
+                self.as_ptr(),
+                &mut params,
+                &mut nparams,
+                flags as libc::c_uint,
+            ))
+        };
+        if ret == -1 {
+            return Err(Error::last_error());
+        }
+
+        let res = unsafe { TypedParamList::from_raw(params, nparams) };
+
+        Ok(ClientLimits::from_vec(res.params()))
+    }
+
+    /// Changes the limits on the clients of the server.
+    ///
+    /// Only `max_clients` and `max_unauth_clients` can be changed.
+    pub fn set_client_limits(&self, limits: ClientLimits, flags: u32) -> Result<(), Error> {
+        let mut cparams = TypedParamList::from_vec(limits.to_vec()?);
+        let ret = unsafe {
+            ffi!(sys::virAdmServerSetClientLimits(
+                self.as_ptr(),
+                cparams.as_mut_ptr(),
+                cparams.len(),
+                flags as libc::c_uint,
+            ))
+        };
+        if ret == -1 {
+            return Err(Error::last_error());
+        }
+        Ok(())
+    }
+
+    /// Lists the clients connected to the server.
+    pub fn list_clients(&self, flags: u32) -> Result<Vec<AdmClient>, Error> {
+        let mut clients: *mut sys::virAdmClientPtr = ptr::null_mut();
+        let size = unsafe {
+            ffi!(sys::virAdmServerListClients(
+                self.as_ptr(),
+                &mut clients,
+                flags as libc::c_uint
+            ))
+        };
+        if size == -1 {
+            return Err(Error::last_error());
+        }
+
+        let mut array: Vec<AdmClient> = Vec::with_capacity(size as usize);
+        for x in 0..size as isize {
+            array.push(unsafe { AdmClient::from_ptr(*clients.offset(x)) });
+        }
+        unsafe { libc::free(clients as *mut libc::c_void) };
+
+        Ok(array)
+    }
+
+    pub fn lookup_client(&self, id: u64, flags: u32) -> Result<AdmClient, Error> {
+        let ptr = unsafe {
+            ffi!(sys::virAdmServerLookupClient(
+                self.as_ptr(),
+                id as libc::c_ulonglong,
+                flags as libc::c_uint,
+            ))
+        };
+        if ptr.is_null() {
+            return Err(Error::last_error());
+        }
+        Ok(unsafe { AdmClient::from_ptr(ptr) })
+    }
+
+    /// Reloads the TLS certificates and keys of the server.
+    #[cfg(libvirt_6_2)]
+    pub fn update_tls_files(&self, flags: u32) -> Result<(), Error> {
+        let ret = unsafe {
+            ffi!(sys::virAdmServerUpdateTlsFiles(
+                self.as_ptr(),
+                flags as libc::c_uint
+            ))
+        };
+        if ret == -1 {
+            return Err(Error::last_error());
+        }
+        Ok(())
+    }
+}
+
+/// A client connected to a server of a libvirt daemon.
+#[derive(Debug)]
+pub struct AdmClient {
+    ptr: Option<sys::virAdmClientPtr>,
+}
+
+unsafe impl Send for AdmClient {}
+unsafe impl Sync for AdmClient {}
+
+impl Drop for AdmClient {
+    fn drop(&mut self) {
+        if self.ptr.is_some() {
+            if let Err(e) = self.free() {
+                report_drop_error("AdmClient", e);
+            }
+        }
+    }
+}
+
+impl AdmClient {
+    /// # Safety
+    ///
+    /// The caller must ensure that the pointer is valid.
+    pub unsafe fn from_ptr(ptr: sys::virAdmClientPtr) -> AdmClient {
+        AdmClient { ptr: Some(ptr) }
+    }
+
+    pub fn as_ptr(&self) -> sys::virAdmClientPtr {
+        self.ptr.unwrap()
+    }
+
+    pub fn free(&mut self) -> Result<(), Error> {
+        let ret = unsafe { ffi!(sys::virAdmClientFree(self.as_ptr())) };
+        if ret == -1 {
+            return Err(Error::last_error());
+        }
+        self.ptr = None;
+        Ok(())
+    }
+
+    /// Returns the identifier of the client, unique per server.
+    pub fn get_id(&self) -> u64 {
+        unsafe { ffi!(sys::virAdmClientGetID(self.as_ptr())) }
+    }
+
+    /// Returns the time the client connected, in seconds since the
+    /// Epoch.
+    pub fn get_timestamp(&self) -> Result<i64, Error> {
+        let ret = unsafe { ffi!(sys::virAdmClientGetTimestamp(self.as_ptr())) };
+        if ret == -1 {
+            return Err(Error::last_error());
+        }
+        Ok(ret)
+    }
+
+    pub fn get_transport(&self) -> Result<ClientTransport, Error> {
+        let ret = unsafe { ffi!(sys::virAdmClientGetTransport(self.as_ptr())) };
+        if ret == -1 {
+            return Err(Error::last_error());
+        }
+        Ok(ClientTransport::from_raw(ret as sys::virClientTransport))
+    }
+
+    pub fn get_info(&self, flags: u32) -> Result<ClientInfo, Error> {
+        let mut nparams: libc::c_int = 0;
+        let mut params: sys::virTypedParameterPtr = ptr::null_mut();
+        let ret = unsafe {
+            ffi!(sys::virAdmClientGetInfo(
+                self.as_ptr(),
+                &mut params,
+                &mut nparams,
+                flags as libc::c_uint,
+            ))
+        };
+        if ret == -1 {
+            return Err(Error::last_error());
+        }
+
+        let res = unsafe { TypedParamList::from_raw(params, nparams) };
+
+        Ok(ClientInfo::from_vec(res.params()))
+    }
+
+    /// Disconnects the client from the server.
+    pub fn close(&self, flags: u32) -> Result<(), Error> {
+        let ret = unsafe { ffi!(sys::virAdmClientClose(self.as_ptr(), flags as libc::c_uint)) };
+        if ret == -1 {
+            return Err(Error::last_error());
+        }
+        Ok(())
+    }
+}
@@ -16,19 +16,200 @@
  * Sahid Orentino Ferdjaoui <sahid.ferdjaoui@redhat.com>
  */
 
-use std::convert::TryInto;
+use std::convert::{TryFrom, TryInto};
 use std::ffi::CString;
-use std::{mem, ptr, str};
-
-use crate::domain::{Domain, DomainStatsRecord};
-use crate::error::Error;
+use std::os::unix::io::RawFd;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::{fmt, mem, ptr, str};
+
+use crate::cpumap::CpuMap;
+use crate::domain::{
+    BlockThresholdEvent, Domain, DomainCreateFlags, DomainDefineFlags, DomainStatsGroups,
+    DomainStatsRecord, UndefineOptions,
+};
+use crate::error::{self, Error, ErrorNumber};
 use crate::interface::Interface;
 use crate::network::Network;
-use crate::nodedev::NodeDevice;
+use crate::nodedev::{NodeDevice, NodeDeviceCap, NodeDeviceLifecycleEvent};
+use crate::nodeset::NodeSet;
 use crate::nwfilter::NWFilter;
 use crate::secret::Secret;
 use crate::storage_pool::StoragePool;
-use crate::util::c_ulong_to_u64;
+use crate::util::{c_ulong_to_u64, impl_enum, impl_flags, list_array, list_names};
+use crate::xmlutil::{self, Element};
+
+#[cfg(feature = "qemu")]
+type QemuMonitorEventCallback = dyn FnMut(&Domain, &str, i64, u32, Option<&str>) + Send;
+
+#[cfg(feature = "qemu")]
+extern "C" fn qemu_monitor_event_callback(
+    _conn: sys::virConnectPtr,
+    dom: sys::virDomainPtr,
+    event: *const libc::c_char,
+    seconds: libc::c_longlong,
+    micros: libc::c_uint,
+    details: *const libc::c_char,
+    opaque: *mut libc::c_void,
+) {
+    // Safe because opaque is the box allocated by
+    // domain_qemu_monitor_event_register() and only released by
+    // qemu_monitor_event_free().
+    let callback = unsafe { &mut *(opaque as *mut Box<QemuMonitorEventCallback>) };
+    let dom = unsafe {
+        // The domain is only borrowed by libvirt for the duration of
+        // the callback.
+        ffi!(sys::virDomainRef(dom));
+        Domain::from_ptr(dom)
+    };
+    let event = unsafe { c_chars_to_string!(event, nofree) };
+    let details = if details.is_null() {
+        None
+    } else {
+        Some(unsafe { c_chars_to_string!(details, nofree) })
+    };
+    callback(&dom, &event, seconds, micros, details.as_deref());
+}
+
+#[cfg(feature = "qemu")]
+extern "C" fn qemu_monitor_event_free(opaque: *mut libc::c_void) {
+    drop(unsafe { Box::from_raw(opaque as *mut Box<QemuMonitorEventCallback>) });
+}
+
+type BlockThresholdEventCallback = dyn FnMut(&Domain, &BlockThresholdEvent) + Send;
+
+extern "C" fn block_threshold_event_callback(
+    _conn: sys::virConnectPtr,
+    dom: sys::virDomainPtr,
+    dev: *const libc::c_char,
+    path: *const libc::c_char,
+    threshold: libc::c_ulonglong,
+    excess: libc::c_ulonglong,
+    opaque: *mut libc::c_void,
+) {
+    // Safe because opaque is the box allocated by
+    // domain_event_block_threshold_register() and only released by
+    // block_threshold_event_free().
+    let callback = unsafe { &mut *(opaque as *mut Box<BlockThresholdEventCallback>) };
+    let dom = unsafe {
+        // The domain is only borrowed by libvirt for the duration of
+        // the callback.
+        ffi!(sys::virDomainRef(dom));
+        Domain::from_ptr(dom)
+    };
+    let event = BlockThresholdEvent {
+        dev: unsafe { c_chars_to_string!(dev, nofree) },
+        path: if path.is_null() {
+            None
+        } else {
+            Some(unsafe { c_chars_to_string!(path, nofree) })
+        },
+        threshold,
+        excess,
+    };
+    callback(&dom, &event);
+}
+
+extern "C" fn block_threshold_event_free(opaque: *mut libc::c_void) {
+    drop(unsafe { Box::from_raw(opaque as *mut Box<BlockThresholdEventCallback>) });
+}
+
+type SecretEventCallback = dyn FnMut(&Secret) + Send;
+
+extern "C" fn secret_event_callback(
+    _conn: sys::virConnectPtr,
+    secret: sys::virSecretPtr,
+    opaque: *mut libc::c_void,
+) {
+    // Safe because opaque is the box allocated by
+    // secret_event_value_changed_register() and only released by
+    // secret_event_free().
+    let callback = unsafe { &mut *(opaque as *mut Box<SecretEventCallback>) };
+    let secret = unsafe {
+        // The secret is only borrowed by libvirt for the duration of
+        // the callback.
+        ffi!(sys::virSecretRef(secret));
+        Secret::from_ptr(secret)
+    };
+    callback(&secret);
+}
+
+extern "C" fn secret_event_free(opaque: *mut libc::c_void) {
+    drop(unsafe { Box::from_raw(opaque as *mut Box<SecretEventCallback>) });
+}
+
+type NodeDeviceLifecycleEventCallback = dyn FnMut(&NodeDevice, NodeDeviceLifecycleEvent) + Send;
+
+extern "C" fn node_device_lifecycle_event_callback(
+    _conn: sys::virConnectPtr,
+    dev: sys::virNodeDevicePtr,
+    event: libc::c_int,
+    _detail: libc::c_int,
+    opaque: *mut libc::c_void,
+) {
+    // Safe because opaque is the box allocated by
+    // node_device_event_lifecycle_register() and only released by
+    // node_device_lifecycle_event_free().
+    let callback = unsafe { &mut *(opaque as *mut Box<NodeDeviceLifecycleEventCallback>) };
+    let dev = unsafe {
+        // The device is only borrowed by libvirt for the duration of
+        // the callback.
+        ffi!(sys::virNodeDeviceRef(dev));
+        NodeDevice::from_ptr(dev)
+    };
+    callback(
+        &dev,
+        NodeDeviceLifecycleEvent::from_raw(event as sys::virNodeDeviceEventLifecycleType),
+    );
+}
+
+extern "C" fn node_device_lifecycle_event_free(opaque: *mut libc::c_void) {
+    drop(unsafe { Box::from_raw(opaque as *mut Box<NodeDeviceLifecycleEventCallback>) });
+}
+
+type NodeDeviceEventCallback = dyn FnMut(&NodeDevice) + Send;
+
+extern "C" fn node_device_event_callback(
+    _conn: sys::virConnectPtr,
+    dev: sys::virNodeDevicePtr,
+    opaque: *mut libc::c_void,
+) {
+    // Safe because opaque is the box allocated by
+    // node_device_event_update_register() and only released by
+    // node_device_event_free().
+    let callback = unsafe { &mut *(opaque as *mut Box<NodeDeviceEventCallback>) };
+    let dev = unsafe {
+        // The device is only borrowed by libvirt for the duration of
+        // the callback.
+        ffi!(sys::virNodeDeviceRef(dev));
+        NodeDevice::from_ptr(dev)
+    };
+    callback(&dev);
+}
+
+extern "C" fn node_device_event_free(opaque: *mut libc::c_void) {
+    drop(unsafe { Box::from_raw(opaque as *mut Box<NodeDeviceEventCallback>) });
+}
+
+type CloseCallback = dyn FnMut(ConnectCloseReason) + Send;
+
+extern "C" fn close_callback(
+    _conn: sys::virConnectPtr,
+    reason: libc::c_int,
+    opaque: *mut libc::c_void,
+) {
+    // Safe because opaque is the box allocated by
+    // register_close_callback() and only released by
+    // close_callback_free().
+    let callback = unsafe { &mut *(opaque as *mut Box<CloseCallback>) };
+    callback(ConnectCloseReason::from_raw(
+        reason as sys::virConnectCloseReason,
+    ));
+}
+
+extern "C" fn close_callback_free(opaque: *mut libc::c_void) {
+    drop(unsafe { Box::from_raw(opaque as *mut Box<CloseCallback>) });
+}
 
 extern "C" fn connect_callback(
     ccreds: sys::virConnectCredentialPtr,
@@ -70,7 +251,596 @@ extern "C" fn connect_callback(
     0
 }
 
+impl_flags! {
+    /// Filters used when listing domains; an empty set lists them all.
+    ///
+    /// See <https://libvirt.org/html/libvirt-libvirt-domain.html#virConnectListAllDomainsFlags>
+    struct ConnectListAllDomainsFlags: sys::virConnectListAllDomainsFlags {
+        const ACTIVE = sys::VIR_CONNECT_LIST_DOMAINS_ACTIVE;
+        const INACTIVE = sys::VIR_CONNECT_LIST_DOMAINS_INACTIVE;
+        const PERSISTENT = sys::VIR_CONNECT_LIST_DOMAINS_PERSISTENT;
+        const TRANSIENT = sys::VIR_CONNECT_LIST_DOMAINS_TRANSIENT;
+        const RUNNING = sys::VIR_CONNECT_LIST_DOMAINS_RUNNING;
+        const PAUSED = sys::VIR_CONNECT_LIST_DOMAINS_PAUSED;
+        const SHUTOFF = sys::VIR_CONNECT_LIST_DOMAINS_SHUTOFF;
+        const OTHER = sys::VIR_CONNECT_LIST_DOMAINS_OTHER;
+        const MANAGEDSAVE = sys::VIR_CONNECT_LIST_DOMAINS_MANAGEDSAVE;
+        const NO_MANAGEDSAVE = sys::VIR_CONNECT_LIST_DOMAINS_NO_MANAGEDSAVE;
+        const AUTOSTART = sys::VIR_CONNECT_LIST_DOMAINS_AUTOSTART;
+        const NO_AUTOSTART = sys::VIR_CONNECT_LIST_DOMAINS_NO_AUTOSTART;
+        const HAS_SNAPSHOT = sys::VIR_CONNECT_LIST_DOMAINS_HAS_SNAPSHOT;
+        const NO_SNAPSHOT = sys::VIR_CONNECT_LIST_DOMAINS_NO_SNAPSHOT;
+        const HAS_CHECKPOINT = sys::VIR_CONNECT_LIST_DOMAINS_HAS_CHECKPOINT;
+        const NO_CHECKPOINT = sys::VIR_CONNECT_LIST_DOMAINS_NO_CHECKPOINT;
+    }
+}
+
+impl_flags! {
+    /// Filters used when listing network filters. No filter is
+    /// defined yet, the empty set lists them all.
+    ///
+    /// See <https://libvirt.org/html/libvirt-libvirt-nwfilter.html#virConnectListAllNWFilters>
+    struct ConnectListAllNWFiltersFlags: libc::c_uint {}
+}
+
+impl_flags! {
+    /// Filters used when listing networks; an empty set lists them all.
+    ///
+    /// See <https://libvirt.org/html/libvirt-libvirt-network.html#virConnectListAllNetworksFlags>
+    struct ConnectListAllNetworksFlags: sys::virConnectListAllNetworksFlags {
+        const INACTIVE = sys::VIR_CONNECT_LIST_NETWORKS_INACTIVE;
+        const ACTIVE = sys::VIR_CONNECT_LIST_NETWORKS_ACTIVE;
+        const PERSISTENT = sys::VIR_CONNECT_LIST_NETWORKS_PERSISTENT;
+        const TRANSIENT = sys::VIR_CONNECT_LIST_NETWORKS_TRANSIENT;
+        const AUTOSTART = sys::VIR_CONNECT_LIST_NETWORKS_AUTOSTART;
+        const NO_AUTOSTART = sys::VIR_CONNECT_LIST_NETWORKS_NO_AUTOSTART;
+    }
+}
+
+impl_flags! {
+    /// Filters used when listing interfaces; an empty set lists them all.
+    ///
+    /// See <https://libvirt.org/html/libvirt-libvirt-interface.html#virConnectListAllInterfacesFlags>
+    struct ConnectListAllInterfacesFlags: sys::virConnectListAllInterfacesFlags {
+        const INACTIVE = sys::VIR_CONNECT_LIST_INTERFACES_INACTIVE;
+        const ACTIVE = sys::VIR_CONNECT_LIST_INTERFACES_ACTIVE;
+    }
+}
+
+impl_flags! {
+    /// Filters used when listing node devices; an empty set lists them all.
+    ///
+    /// See <https://libvirt.org/html/libvirt-libvirt-nodedev.html#virConnectListAllNodeDeviceFlags>
+    struct ConnectListAllNodeDeviceFlags: sys::virConnectListAllNodeDeviceFlags {
+        const CAP_SYSTEM = sys::VIR_CONNECT_LIST_NODE_DEVICES_CAP_SYSTEM;
+        const CAP_PCI_DEV = sys::VIR_CONNECT_LIST_NODE_DEVICES_CAP_PCI_DEV;
+        const CAP_USB_DEV = sys::VIR_CONNECT_LIST_NODE_DEVICES_CAP_USB_DEV;
+        const CAP_USB_INTERFACE = sys::VIR_CONNECT_LIST_NODE_DEVICES_CAP_USB_INTERFACE;
+        const CAP_NET = sys::VIR_CONNECT_LIST_NODE_DEVICES_CAP_NET;
+        const CAP_SCSI_HOST = sys::VIR_CONNECT_LIST_NODE_DEVICES_CAP_SCSI_HOST;
+        const CAP_SCSI_TARGET = sys::VIR_CONNECT_LIST_NODE_DEVICES_CAP_SCSI_TARGET;
+        const CAP_SCSI = sys::VIR_CONNECT_LIST_NODE_DEVICES_CAP_SCSI;
+        const CAP_STORAGE = sys::VIR_CONNECT_LIST_NODE_DEVICES_CAP_STORAGE;
+        const CAP_FC_HOST = sys::VIR_CONNECT_LIST_NODE_DEVICES_CAP_FC_HOST;
+        const CAP_VPORTS = sys::VIR_CONNECT_LIST_NODE_DEVICES_CAP_VPORTS;
+        const CAP_SCSI_GENERIC = sys::VIR_CONNECT_LIST_NODE_DEVICES_CAP_SCSI_GENERIC;
+        const CAP_DRM = sys::VIR_CONNECT_LIST_NODE_DEVICES_CAP_DRM;
+        const CAP_MDEV_TYPES = sys::VIR_CONNECT_LIST_NODE_DEVICES_CAP_MDEV_TYPES;
+        const CAP_MDEV = sys::VIR_CONNECT_LIST_NODE_DEVICES_CAP_MDEV;
+        const CAP_CCW_DEV = sys::VIR_CONNECT_LIST_NODE_DEVICES_CAP_CCW_DEV;
+        const CAP_CSS_DEV = sys::VIR_CONNECT_LIST_NODE_DEVICES_CAP_CSS_DEV;
+        const CAP_VDPA = sys::VIR_CONNECT_LIST_NODE_DEVICES_CAP_VDPA;
+        const CAP_AP_CARD = sys::VIR_CONNECT_LIST_NODE_DEVICES_CAP_AP_CARD;
+        const CAP_AP_QUEUE = sys::VIR_CONNECT_LIST_NODE_DEVICES_CAP_AP_QUEUE;
+        const CAP_AP_MATRIX = sys::VIR_CONNECT_LIST_NODE_DEVICES_CAP_AP_MATRIX;
+        const CAP_VPD = sys::VIR_CONNECT_LIST_NODE_DEVICES_CAP_VPD;
+        const PERSISTENT = sys::VIR_CONNECT_LIST_NODE_DEVICES_PERSISTENT;
+        const TRANSIENT = sys::VIR_CONNECT_LIST_NODE_DEVICES_TRANSIENT;
+        const INACTIVE = sys::VIR_CONNECT_LIST_NODE_DEVICES_INACTIVE;
+        const ACTIVE = sys::VIR_CONNECT_LIST_NODE_DEVICES_ACTIVE;
+    }
+}
+
+impl_flags! {
+    /// Filters and options used when collecting domain statistics; an
+    /// empty set collects the statistics of all the domains.
+    ///
+    /// See <https://libvirt.org/html/libvirt-libvirt-domain.html#virConnectGetAllDomainStatsFlags>
+    struct DomainStatsFilter: sys::virConnectGetAllDomainStatsFlags {
+        /// Only the running or paused domains.
+        const ACTIVE = sys::VIR_CONNECT_GET_ALL_DOMAINS_STATS_ACTIVE;
+        /// Only the shut off domains.
+        const INACTIVE = sys::VIR_CONNECT_GET_ALL_DOMAINS_STATS_INACTIVE;
+        /// Only the persistent domains.
+        const PERSISTENT = sys::VIR_CONNECT_GET_ALL_DOMAINS_STATS_PERSISTENT;
+        /// Only the transient domains.
+        const TRANSIENT = sys::VIR_CONNECT_GET_ALL_DOMAINS_STATS_TRANSIENT;
+        /// Only the running domains.
+        const RUNNING = sys::VIR_CONNECT_GET_ALL_DOMAINS_STATS_RUNNING;
+        /// Only the paused domains.
+        const PAUSED = sys::VIR_CONNECT_GET_ALL_DOMAINS_STATS_PAUSED;
+        /// Only the shut off domains.
+        const SHUTOFF = sys::VIR_CONNECT_GET_ALL_DOMAINS_STATS_SHUTOFF;
+        /// Only the domains in another state.
+        const OTHER = sys::VIR_CONNECT_GET_ALL_DOMAINS_STATS_OTHER;
+        /// Skip the statistics which would block on a busy domain.
+        const NOWAIT = sys::VIR_CONNECT_GET_ALL_DOMAINS_STATS_NOWAIT;
+        /// Include the backing chains of the block devices.
+        const BACKING = sys::VIR_CONNECT_GET_ALL_DOMAINS_STATS_BACKING;
+    }
+}
+
+impl_flags! {
+    /// Filters used when listing secrets; an empty set lists them all.
+    ///
+    /// See <https://libvirt.org/html/libvirt-libvirt-secret.html#virConnectListAllSecretsFlags>
+    struct ConnectListAllSecretsFlags: sys::virConnectListAllSecretsFlags {
+        const EPHEMERAL = sys::VIR_CONNECT_LIST_SECRETS_EPHEMERAL;
+        const NO_EPHEMERAL = sys::VIR_CONNECT_LIST_SECRETS_NO_EPHEMERAL;
+        const PRIVATE = sys::VIR_CONNECT_LIST_SECRETS_PRIVATE;
+        const NO_PRIVATE = sys::VIR_CONNECT_LIST_SECRETS_NO_PRIVATE;
+    }
+}
+
+impl_flags! {
+    /// Filters used when listing storage pools; an empty set lists them all.
+    ///
+    /// See <https://libvirt.org/html/libvirt-libvirt-storage.html#virConnectListAllStoragePoolsFlags>
+    struct ConnectListAllStoragePoolsFlags: sys::virConnectListAllStoragePoolsFlags {
+        const INACTIVE = sys::VIR_CONNECT_LIST_STORAGE_POOLS_INACTIVE;
+        const ACTIVE = sys::VIR_CONNECT_LIST_STORAGE_POOLS_ACTIVE;
+        const PERSISTENT = sys::VIR_CONNECT_LIST_STORAGE_POOLS_PERSISTENT;
+        const TRANSIENT = sys::VIR_CONNECT_LIST_STORAGE_POOLS_TRANSIENT;
+        const AUTOSTART = sys::VIR_CONNECT_LIST_STORAGE_POOLS_AUTOSTART;
+        const NO_AUTOSTART = sys::VIR_CONNECT_LIST_STORAGE_POOLS_NO_AUTOSTART;
+        const DIR = sys::VIR_CONNECT_LIST_STORAGE_POOLS_DIR;
+        const FS = sys::VIR_CONNECT_LIST_STORAGE_POOLS_FS;
+        const NETFS = sys::VIR_CONNECT_LIST_STORAGE_POOLS_NETFS;
+        const LOGICAL = sys::VIR_CONNECT_LIST_STORAGE_POOLS_LOGICAL;
+        const DISK = sys::VIR_CONNECT_LIST_STORAGE_POOLS_DISK;
+        const ISCSI = sys::VIR_CONNECT_LIST_STORAGE_POOLS_ISCSI;
+        const SCSI = sys::VIR_CONNECT_LIST_STORAGE_POOLS_SCSI;
+        const MPATH = sys::VIR_CONNECT_LIST_STORAGE_POOLS_MPATH;
+        const RBD = sys::VIR_CONNECT_LIST_STORAGE_POOLS_RBD;
+        const SHEEPDOG = sys::VIR_CONNECT_LIST_STORAGE_POOLS_SHEEPDOG;
+        const GLUSTER = sys::VIR_CONNECT_LIST_STORAGE_POOLS_GLUSTER;
+        const ZFS = sys::VIR_CONNECT_LIST_STORAGE_POOLS_ZFS;
+        const VSTORAGE = sys::VIR_CONNECT_LIST_STORAGE_POOLS_VSTORAGE;
+        const ISCSI_DIRECT = sys::VIR_CONNECT_LIST_STORAGE_POOLS_ISCSI_DIRECT;
+    }
+}
+
+/// Returns the text of the `<entry name='...'>` child of an SMBIOS
+/// block of the sysinfo XML.
+fn sysinfo_entry(block: &Element, name: &str) -> Option<String> {
+    block
+        .children("entry")
+        .find(|e| e.attr("name") == Some(name))
+        .map(|e| e.text().to_string())
+}
+
+/// The SMBIOS BIOS information of the host.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SysInfoBios {
+    pub vendor: Option<String>,
+    pub version: Option<String>,
+    pub date: Option<String>,
+    pub release: Option<String>,
+}
+
+/// The SMBIOS system information of the host.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SysInfoSystem {
+    pub manufacturer: Option<String>,
+    pub product: Option<String>,
+    pub version: Option<String>,
+    pub serial: Option<String>,
+    pub uuid: Option<String>,
+    pub sku: Option<String>,
+    pub family: Option<String>,
+}
+
+/// The SMBIOS information of a processor socket of the host.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SysInfoProcessor {
+    pub socket_destination: Option<String>,
+    pub processor_type: Option<String>,
+    pub family: Option<String>,
+    pub manufacturer: Option<String>,
+    pub signature: Option<String>,
+    pub version: Option<String>,
+    pub external_clock: Option<String>,
+    pub max_speed: Option<String>,
+    pub status: Option<String>,
+    pub serial_number: Option<String>,
+    pub part_number: Option<String>,
+}
+
+/// The SMBIOS information of a memory device of the host.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SysInfoMemoryDevice {
+    /// The size, with its unit, e.g. `16 GB`.
+    pub size: Option<String>,
+    pub form_factor: Option<String>,
+    pub locator: Option<String>,
+    pub bank_locator: Option<String>,
+    pub memory_type: Option<String>,
+    pub type_detail: Option<String>,
+    pub speed: Option<String>,
+    pub manufacturer: Option<String>,
+    pub serial_number: Option<String>,
+    pub part_number: Option<String>,
+}
+
+/// The SMBIOS information of the host, as returned by
+/// [`Connect::get_sysinfo()`].
+///
+/// See <https://libvirt.org/formatdomain.html#smbios-system-information>
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SysInfo {
+    pub bios: Option<SysInfoBios>,
+    pub system: Option<SysInfoSystem>,
+    pub processors: Vec<SysInfoProcessor>,
+    pub memory_devices: Vec<SysInfoMemoryDevice>,
+}
+
+impl SysInfo {
+    /// Parses the XML returned by [`Connect::get_sys_info()`].
+    pub fn from_xml(xml: &str) -> Result<SysInfo, Error> {
+        let root = Element::parse(xml)?;
+        let bios = root.child("bios").map(|b| SysInfoBios {
+            vendor: sysinfo_entry(b, "vendor"),
+            version: sysinfo_entry(b, "version"),
+            date: sysinfo_entry(b, "date"),
+            release: sysinfo_entry(b, "release"),
+        });
+        let system = root.child("system").map(|s| SysInfoSystem {
+            manufacturer: sysinfo_entry(s, "manufacturer"),
+            product: sysinfo_entry(s, "product"),
+            version: sysinfo_entry(s, "version"),
+            serial: sysinfo_entry(s, "serial"),
+            uuid: sysinfo_entry(s, "uuid"),
+            sku: sysinfo_entry(s, "sku"),
+            family: sysinfo_entry(s, "family"),
+        });
+        let processors = root
+            .children("processor")
+            .map(|p| SysInfoProcessor {
+                socket_destination: sysinfo_entry(p, "socket_destination"),
+                processor_type: sysinfo_entry(p, "type"),
+                family: sysinfo_entry(p, "family"),
+                manufacturer: sysinfo_entry(p, "manufacturer"),
+                signature: sysinfo_entry(p, "signature"),
+                version: sysinfo_entry(p, "version"),
+                external_clock: sysinfo_entry(p, "external_clock"),
+                max_speed: sysinfo_entry(p, "max_speed"),
+                status: sysinfo_entry(p, "status"),
+                serial_number: sysinfo_entry(p, "serial_number"),
+                part_number: sysinfo_entry(p, "part_number"),
+            })
+            .collect();
+        let memory_devices = root
+            .children("memory_device")
+            .map(|m| SysInfoMemoryDevice {
+                size: sysinfo_entry(m, "size"),
+                form_factor: sysinfo_entry(m, "form_factor"),
+                locator: sysinfo_entry(m, "locator"),
+                bank_locator: sysinfo_entry(m, "bank_locator"),
+                memory_type: sysinfo_entry(m, "type"),
+                type_detail: sysinfo_entry(m, "type_detail"),
+                speed: sysinfo_entry(m, "speed"),
+                manufacturer: sysinfo_entry(m, "manufacturer"),
+                serial_number: sysinfo_entry(m, "serial_number"),
+                part_number: sysinfo_entry(m, "part_number"),
+            })
+            .collect();
+        Ok(SysInfo {
+            bios,
+            system,
+            processors,
+            memory_devices,
+        })
+    }
+}
+
+/// Parses the attribute `name` of `elem`, if present and valid.
+fn parse_attr<T: std::str::FromStr>(elem: &Element, name: &str) -> Option<T> {
+    elem.attr(name).and_then(|v| v.parse().ok())
+}
+
+/// The CPU of the host.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct HostCpu {
+    pub arch: String,
+    pub model: Option<String>,
+    pub vendor: Option<String>,
+    pub sockets: Option<u32>,
+    pub dies: Option<u32>,
+    pub cores: Option<u32>,
+    pub threads: Option<u32>,
+    /// The features on top of the model.
+    pub features: Vec<String>,
+}
+
+/// A logical CPU of a NUMA cell of the host.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct HostCellCpu {
+    pub id: u32,
+    pub socket_id: Option<u32>,
+    pub die_id: Option<u32>,
+    pub core_id: Option<u32>,
+    /// The CPUs sharing the core with this one, including itself.
+    pub siblings: Option<NodeSet>,
+}
+
+/// A NUMA cell of the host.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct HostCell {
+    pub id: u32,
+    /// The memory of the cell, in KiB.
+    pub memory: u64,
+    pub cpus: Vec<HostCellCpu>,
+    /// The distances to the cells, by cell id.
+    pub distances: Vec<(u32, u32)>,
+}
+
+/// An architecture the host can run guests of.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct GuestCapabilities {
+    /// The kind of guest, e.g. `hvm` or `exe`.
+    pub os_type: String,
+    pub arch: String,
+    pub wordsize: Option<u32>,
+    pub emulator: Option<String>,
+    pub machines: Vec<String>,
+    /// The hypervisors which can run the guest, e.g. `qemu` and `kvm`.
+    pub domain_types: Vec<String>,
+}
+
+/// The capabilities of the host, as returned by
+/// [`Connect::host_capabilities()`].
+///
+/// See <https://libvirt.org/formatcaps.html>
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct HostCapabilities {
+    pub uuid: Option<String>,
+    pub cpu: HostCpu,
+    pub cells: Vec<HostCell>,
+    pub guests: Vec<GuestCapabilities>,
+}
+
+impl HostCapabilities {
+    /// Parses the XML returned by [`Connect::get_capabilities()`].
+    pub fn from_xml(xml: &str) -> Result<HostCapabilities, Error> {
+        let root = Element::parse(xml)?;
+        let host = match root.child("host") {
+            Some(host) => host,
+            None => return Err(Error::from_xml_error("missing <host> element".into())),
+        };
+        let text = |elem: &Element, name: &str| elem.child(name).map(|e| e.text().to_string());
+
+        let cpu = host
+            .child("cpu")
+            .map(|c| {
+                let topology = c.child("topology");
+                let topo = |name: &str| topology.and_then(|t| parse_attr(t, name));
+                HostCpu {
+                    arch: text(c, "arch").unwrap_or_default(),
+                    model: text(c, "model"),
+                    vendor: text(c, "vendor"),
+                    sockets: topo("sockets"),
+                    dies: topo("dies"),
+                    cores: topo("cores"),
+                    threads: topo("threads"),
+                    features: c
+                        .children("feature")
+                        .filter_map(|f| f.attr("name").map(str::to_string))
+                        .collect(),
+                }
+            })
+            .unwrap_or_default();
+
+        let cells = host
+            .child("topology")
+            .and_then(|t| t.child("cells"))
+            .map(|cells| {
+                cells
+                    .children("cell")
+                    .map(|cell| HostCell {
+                        id: parse_attr(cell, "id").unwrap_or(0),
+                        memory: cell
+                            .child("memory")
+                            .and_then(|m| m.text().parse().ok())
+                            .unwrap_or(0),
+                        cpus: cell
+                            .child("cpus")
+                            .map(|cpus| {
+                                cpus.children("cpu")
+                                    .map(|c| HostCellCpu {
+                                        id: parse_attr(c, "id").unwrap_or(0),
+                                        socket_id: parse_attr(c, "socket_id"),
+                                        die_id: parse_attr(c, "die_id"),
+                                        core_id: parse_attr(c, "core_id"),
+                                        siblings: parse_attr(c, "siblings"),
+                                    })
+                                    .collect()
+                            })
+                            .unwrap_or_default(),
+                        distances: cell
+                            .child("distances")
+                            .map(|d| {
+                                d.children("sibling")
+                                    .filter_map(|s| {
+                                        Some((parse_attr(s, "id")?, parse_attr(s, "value")?))
+                                    })
+                                    .collect()
+                            })
+                            .unwrap_or_default(),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let guests = root
+            .children("guest")
+            .filter_map(|g| {
+                let arch = g.child("arch")?;
+                Some(GuestCapabilities {
+                    os_type: text(g, "os_type").unwrap_or_default(),
+                    arch: arch.attr("name").unwrap_or_default().to_string(),
+                    wordsize: arch.child("wordsize").and_then(|w| w.text().parse().ok()),
+                    emulator: text(arch, "emulator"),
+                    machines: arch
+                        .children("machine")
+                        .map(|m| m.text().to_string())
+                        .collect(),
+                    domain_types: arch
+                        .children("domain")
+                        .filter_map(|d| d.attr("type").map(str::to_string))
+                        .collect(),
+                })
+            })
+            .collect();
+
+        Ok(HostCapabilities {
+            uuid: text(host, "uuid"),
+            cpu,
+            cells,
+            guests,
+        })
+    }
+
+    /// Returns `true` if the host can run guests of `arch` with the
+    /// `domain_type` hypervisor, e.g. `x86_64` with `kvm`.
+    pub fn supports(&self, arch: &str, domain_type: &str) -> bool {
+        self.guests
+            .iter()
+            .any(|g| g.arch == arch && g.domain_types.iter().any(|t| t == domain_type))
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+/// Whether the hypervisor can run a CPU model on the host.
+pub enum CpuModelUsability {
+    /// The model can be used as is.
+    Usable,
+    /// Some features of the model are missing on the host.
+    Unusable,
+    /// The hypervisor did not report it.
+    Unknown,
+}
+
+/// A CPU model of the `custom` CPU mode of the domain capabilities.
+///
+/// See <https://libvirt.org/formatdomaincaps.html#cpu-configuration>
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DomainCapsCpuModel {
+    pub name: String,
+    pub usable: CpuModelUsability,
+    pub vendor: Option<String>,
+    /// The versioned model the name is an alias of, if any.
+    pub canonical: Option<String>,
+    pub deprecated: bool,
+    /// The host features preventing the model from being usable.
+    pub blockers: Vec<String>,
+}
+
+impl DomainCapsCpuModel {
+    /// Parses the CPU models out of the XML returned by
+    /// [`Connect::get_domain_capabilities()`].
+    pub fn list_from_xml(xml: &str) -> Result<Vec<DomainCapsCpuModel>, Error> {
+        let root = Element::parse(xml)?;
+        let custom = match root.child("cpu").and_then(|cpu| {
+            cpu.children("mode")
+                .find(|m| m.attr("name") == Some("custom"))
+        }) {
+            Some(custom) => custom,
+            None => return Ok(Vec::new()),
+        };
+        Ok(custom
+            .children("model")
+            .map(|m| {
+                let name = m.text().to_string();
+                let blockers = custom
+                    .children("blockers")
+                    .filter(|b| b.attr("model") == Some(name.as_str()))
+                    .flat_map(|b| b.children("feature"))
+                    .filter_map(|f| f.attr("name").map(str::to_string))
+                    .collect();
+                DomainCapsCpuModel {
+                    usable: match m.attr("usable") {
+                        Some("yes") => CpuModelUsability::Usable,
+                        Some("no") => CpuModelUsability::Unusable,
+                        _ => CpuModelUsability::Unknown,
+                    },
+                    vendor: m
+                        .attr("vendor")
+                        .filter(|v| *v != "unknown")
+                        .map(str::to_string),
+                    canonical: m.attr("canonical").map(str::to_string),
+                    deprecated: m.attr("deprecated") == Some("yes"),
+                    name,
+                    blockers,
+                }
+            })
+            .collect())
+    }
+}
+
+/// A version number of libvirt or of a hypervisor.
+///
+/// libvirt packs versions as `major * 1,000,000 + minor * 1,000 +
+/// micro`; versions compare by major, then minor, then micro number.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash)]
+pub struct LibVersion {
+    pub major: u32,
+    pub minor: u32,
+    pub micro: u32,
+}
+
+impl LibVersion {
+    pub fn new(major: u32, minor: u32, micro: u32) -> LibVersion {
+        LibVersion {
+            major,
+            minor,
+            micro,
+        }
+    }
+
+    /// Unpacks a version number as returned by libvirt.
+    pub fn from_raw(raw: u32) -> LibVersion {
+        LibVersion {
+            major: raw / 1_000_000,
+            minor: raw / 1_000 % 1_000,
+            micro: raw % 1_000,
+        }
+    }
+
+    /// Packs the version number the way libvirt does.
+    pub fn to_raw(self) -> u32 {
+        self.major * 1_000_000 + self.minor * 1_000 + self.micro
+    }
+}
+
+impl From<u32> for LibVersion {
+    fn from(raw: u32) -> Self {
+        LibVersion::from_raw(raw)
+    }
+}
+
+impl fmt::Display for LibVersion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.micro)
+    }
+}
+
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct NodeInfo {
     /// Indicating the CPU model.
     pub model: String,
@@ -95,6 +865,185 @@ pub struct NodeInfo {
     pub threads: u32,
 }
 
+impl fmt::Display for NodeInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}, {} CPUs at {} MHz ({} nodes, {} sockets, {} cores, {} threads), {} KiB memory",
+            self.model,
+            self.cpus,
+            self.mhz,
+            self.nodes,
+            self.sockets,
+            self.cores,
+            self.threads,
+            self.memory
+        )
+    }
+}
+
+/// The CPU time counters of the host, in nanoseconds, as returned by
+/// [`Connect::get_node_cpu_stats()`].
+///
+/// The counters are cumulative: see
+/// [`CpuUtilizationTracker`](crate::metrics::CpuUtilizationTracker) to
+/// turn two samples into percentages. Which counters are reported
+/// depends on the host.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NodeCpuStats {
+    pub kernel: Option<u64>,
+    pub user: Option<u64>,
+    pub idle: Option<u64>,
+    pub iowait: Option<u64>,
+    pub intr: Option<u64>,
+    /// The CPU utilization in percent, reported instead of the time
+    /// counters by some hosts.
+    pub utilization: Option<u64>,
+}
+
+impl NodeCpuStats {
+    fn from_stats(stats: &[sys::virNodeCPUStats]) -> NodeCpuStats {
+        let mut ret = NodeCpuStats::default();
+        for stat in stats {
+            let field = unsafe { c_chars_to_string!(stat.field.as_ptr(), nofree) };
+            let value = Some(stat.value);
+            match field.as_str() {
+                "kernel" => ret.kernel = value,
+                "user" => ret.user = value,
+                "idle" => ret.idle = value,
+                "iowait" => ret.iowait = value,
+                "intr" => ret.intr = value,
+                "utilization" => ret.utilization = value,
+                _ => {}
+            }
+        }
+        ret
+    }
+}
+
+/// A problem found by libvirt in an XML document, see
+/// [`Connect::validate_domain_xml()`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct XmlIssue {
+    /// The line of the document the problem is at, if known.
+    pub line: Option<u32>,
+    pub message: String,
+}
+
+impl XmlIssue {
+    /// Returns the issues described by `err`, or `None` if it is not a
+    /// validation error.
+    fn from_error(err: &Error) -> Option<Vec<XmlIssue>> {
+        match err.code() {
+            ErrorNumber::XmlInvalidSchema => {
+                // The first line only names the schema, the following
+                // ones hold the errors reported by the validator.
+                let issues: Vec<XmlIssue> = err
+                    .message()
+                    .lines()
+                    .skip(1)
+                    .map(str::trim)
+                    .filter(|l| !l.is_empty())
+                    .map(|l| XmlIssue {
+                        line: None,
+                        message: l.to_string(),
+                    })
+                    .collect();
+                if issues.is_empty() {
+                    Some(vec![XmlIssue::from_message(err.message())])
+                } else {
+                    Some(issues)
+                }
+            }
+            ErrorNumber::XmlError | ErrorNumber::XmlDetail | ErrorNumber::ConfigUnsupported => {
+                Some(vec![XmlIssue::from_message(err.message())])
+            }
+            _ => None,
+        }
+    }
+
+    /// Extracts the line number from messages such as `at line 3: ...`
+    /// or `(domain_definition):3: ...`.
+    fn from_message(message: &str) -> XmlIssue {
+        let message = message.trim();
+        let line = message
+            .find("at line ")
+            .map(|i| &message[i + 8..])
+            .or_else(|| message.find("):").map(|i| &message[i + 2..]))
+            .and_then(|rest| rest.split(':').next())
+            .and_then(|n| n.trim().parse().ok());
+        XmlIssue {
+            line,
+            message: message.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for XmlIssue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "line {}: {}", line, self.message),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+/// The power management state to enter when suspending a host or
+/// a guest.
+///
+/// See <https://libvirt.org/html/libvirt-libvirt-host.html#virNodeSuspendTarget>
+pub enum SuspendTarget {
+    /// Suspend-to-RAM.
+    Mem,
+    /// Suspend-to-Disk.
+    Disk,
+    /// Hybrid-Suspend, which is a combination of the above two.
+    Hybrid,
+}
+
+impl_enum! {
+    enum: SuspendTarget,
+    raw: sys::virNodeSuspendTarget,
+    match: {
+        sys::VIR_NODE_SUSPEND_TARGET_MEM => SuspendTarget::Mem,
+        sys::VIR_NODE_SUSPEND_TARGET_DISK => SuspendTarget::Disk,
+        sys::VIR_NODE_SUSPEND_TARGET_HYBRID => SuspendTarget::Hybrid,
+        _ => SuspendTarget::Mem,
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+/// The reason why a connection was closed, reported to the callback
+/// registered with [`Connect::register_close_callback()`].
+///
+/// See <https://libvirt.org/html/libvirt-libvirt-host.html#virConnectCloseReason>
+pub enum ConnectCloseReason {
+    /// A misc I/O error.
+    Error,
+    /// The peer closed the socket.
+    Eof,
+    /// The keepalive timer expired.
+    Keepalive,
+    /// The client requested the close.
+    Client,
+    /// A reason unknown to this library, kept as reported by libvirt.
+    Unknown(sys::virConnectCloseReason),
+}
+
+impl_enum! {
+    enum: ConnectCloseReason,
+    raw: sys::virConnectCloseReason,
+    match: {
+        sys::VIR_CONNECT_CLOSE_REASON_ERROR => ConnectCloseReason::Error,
+        sys::VIR_CONNECT_CLOSE_REASON_EOF => ConnectCloseReason::Eof,
+        sys::VIR_CONNECT_CLOSE_REASON_KEEPALIVE => ConnectCloseReason::Keepalive,
+        sys::VIR_CONNECT_CLOSE_REASON_CLIENT => ConnectCloseReason::Client,
+        _ => ConnectCloseReason::Unknown(raw),
+    }
+}
+
 // TODO(sahid): should support closure
 pub type ConnectAuthCallback = fn(creds: &mut Vec<ConnectCredential>);
 
@@ -197,7 +1146,7 @@ impl Connect {
     }
     fn add_ref(&self) -> Result<Connect, Error> {
         unsafe {
-            if sys::virConnectRef(self.as_ptr()) == -1 {
+            if ffi!(sys::virConnectRef(self.as_ptr())) == -1 {
                 return Err(Error::last_error());
             }
         }
@@ -207,13 +1156,18 @@ impl Connect {
 
     pub fn get_version() -> Result<u32, Error> {
         let mut ver: libc::c_ulong = 0;
-        let ret = unsafe { sys::virGetVersion(&mut ver, ptr::null(), ptr::null_mut()) };
+        let ret = unsafe { ffi!(sys::virGetVersion(&mut ver, ptr::null(), ptr::null_mut())) };
         if ret == -1 {
             return Err(Error::last_error());
         }
         Ok(ver as u32)
     }
 
+    /// Returns the version of the libvirt library in use, unpacked.
+    pub fn get_version_triple() -> Result<LibVersion, Error> {
+        Connect::get_version().map(LibVersion::from_raw)
+    }
+
     /// This function should be called first to get a connection to
     /// the Hypervisor and xen store.
     ///
@@ -251,7 +1205,7 @@ impl Connect {
     /// [`close()`]: Connect::close
     pub fn open(uri: Option<&str>) -> Result<Connect, Error> {
         let uri_buf = some_string_to_cstring!(uri);
-        let c = unsafe { sys::virConnectOpen(some_cstring_to_c_chars!(uri_buf)) };
+        let c = unsafe { ffi!(sys::virConnectOpen(some_cstring_to_c_chars!(uri_buf))) };
         if c.is_null() {
             return Err(Error::last_error());
         }
@@ -270,7 +1224,11 @@ impl Connect {
     /// [`open()`]: Connect::open
     pub fn open_read_only(uri: Option<&str>) -> Result<Connect, Error> {
         let uri_buf = some_string_to_cstring!(uri);
-        let c = unsafe { sys::virConnectOpenReadOnly(some_cstring_to_c_chars!(uri_buf)) };
+        let c = unsafe {
+            ffi!(sys::virConnectOpenReadOnly(some_cstring_to_c_chars!(
+                uri_buf
+            )))
+        };
         if c.is_null() {
             return Err(Error::last_error());
         }
@@ -293,11 +1251,11 @@ impl Connect {
         };
         let uri_buf = some_string_to_cstring!(uri);
         let c = unsafe {
-            sys::virConnectOpenAuth(
+            ffi!(sys::virConnectOpenAuth(
                 some_cstring_to_c_chars!(uri_buf),
                 &mut cauth,
                 flags as libc::c_uint,
-            )
+            ))
         };
         if c.is_null() {
             return Err(Error::last_error());
@@ -310,23 +1268,49 @@ impl Connect {
     /// hypervisor are needed especially if there is running domain
     /// which need further monitoring by the application.
     pub fn close(&mut self) -> Result<i32, Error> {
-        let ret = unsafe { sys::virConnectClose(self.as_ptr()) };
+        let ret = unsafe { ffi!(sys::virConnectClose(self.as_ptr())) };
         if ret == -1 {
             return Err(Error::last_error());
         }
+        if ret == 0 {
+            error::remove_conn_error_callback(self.as_ptr(), false);
+        }
         // Because of add_ref() we must refrain from using the
         // connection further.
         self.ptr = None;
         Ok(ret)
     }
 
+    /// Installs a closure receiving the errors raised on this connection.
+    ///
+    /// The closure takes precedence over the process-wide handler set
+    /// with [`error::set_error_callback()`] and is shared by all clones
+    /// of this connection. It is released when the last reference to
+    /// the connection is closed.
+    ///
+    /// See <https://libvirt.org/html/libvirt-virterror.html#virConnSetErrorFunc>
+    pub fn set_error_callback<F>(&self, callback: F)
+    where
+        F: 'static + Fn(&Error) + Send + Sync,
+    {
+        error::set_conn_error_callback(self.as_ptr(), Arc::new(callback));
+    }
+
+    /// Removes the closure installed with [`set_error_callback()`],
+    /// falling back to the process-wide error handler.
+    ///
+    /// [`set_error_callback()`]: Connect::set_error_callback
+    pub fn clear_error_callback(&self) {
+        error::remove_conn_error_callback(self.as_ptr(), true);
+    }
+
     /// This returns a system hostname on which the hypervisor is
     /// running (based on the result of the gethostname system call,
     /// but possibly expanded to a fully-qualified domain name via
     /// getaddrinfo).  If we are connected to a remote system, then
     /// this returns the hostname of the remote system.
     pub fn get_hostname(&self) -> Result<String, Error> {
-        let n = unsafe { sys::virConnectGetHostname(self.as_ptr()) };
+        let n = unsafe { ffi!(sys::virConnectGetHostname(self.as_ptr())) };
         if n.is_null() {
             return Err(Error::last_error());
         }
@@ -334,24 +1318,52 @@ impl Connect {
     }
 
     pub fn get_capabilities(&self) -> Result<String, Error> {
-        let n = unsafe { sys::virConnectGetCapabilities(self.as_ptr()) };
+        let n = unsafe { ffi!(sys::virConnectGetCapabilities(self.as_ptr())) };
         if n.is_null() {
             return Err(Error::last_error());
         }
         Ok(unsafe { c_chars_to_string!(n) })
     }
 
+    /// Returns the capabilities of the host, parsed from
+    /// [`get_capabilities()`](Connect::get_capabilities).
+    pub fn host_capabilities(&self) -> Result<HostCapabilities, Error> {
+        HostCapabilities::from_xml(&self.get_capabilities()?)
+    }
+
     pub fn get_lib_version(&self) -> Result<u32, Error> {
         let mut ver: libc::c_ulong = 0;
-        let ret = unsafe { sys::virConnectGetLibVersion(self.as_ptr(), &mut ver) };
+        let ret = unsafe { ffi!(sys::virConnectGetLibVersion(self.as_ptr(), &mut ver)) };
         if ret == -1 {
             return Err(Error::last_error());
         }
         Ok(ver as u32)
     }
 
+    /// Returns the version of libvirt used by the daemon behind the
+    /// connection, unpacked.
+    pub fn get_lib_version_triple(&self) -> Result<LibVersion, Error> {
+        self.get_lib_version().map(LibVersion::from_raw)
+    }
+
+    /// Returns `true` if both the libvirt library in use and the
+    /// daemon behind the connection are at least at version `min`, so
+    /// that the APIs introduced in `min` can be called.
+    ///
+    /// ```no_run
+    /// use virt::connect::{Connect, LibVersion};
+    ///
+    /// let conn = Connect::open(Some("qemu:///system")).unwrap();
+    /// if conn.has_api(LibVersion::new(9, 0, 0)).unwrap() {
+    ///     // virDomainFDAssociate() is available.
+    /// }
+    /// ```
+    pub fn has_api(&self, min: LibVersion) -> Result<bool, Error> {
+        Ok(Connect::get_version_triple()? >= min && self.get_lib_version_triple()? >= min)
+    }
+
     pub fn get_type(&self) -> Result<String, Error> {
-        let t = unsafe { sys::virConnectGetType(self.as_ptr()) };
+        let t = unsafe { ffi!(sys::virConnectGetType(self.as_ptr())) };
         if t.is_null() {
             return Err(Error::last_error());
         }
@@ -359,7 +1371,7 @@ impl Connect {
     }
 
     pub fn get_uri(&self) -> Result<String, Error> {
-        let t = unsafe { sys::virConnectGetURI(self.as_ptr()) };
+        let t = unsafe { ffi!(sys::virConnectGetURI(self.as_ptr())) };
         if t.is_null() {
             return Err(Error::last_error());
         }
@@ -367,17 +1379,31 @@ impl Connect {
     }
 
     pub fn get_sys_info(&self, flags: u32) -> Result<String, Error> {
-        let sys = unsafe { sys::virConnectGetSysinfo(self.as_ptr(), flags as libc::c_uint) };
+        let sys = unsafe {
+            ffi!(sys::virConnectGetSysinfo(
+                self.as_ptr(),
+                flags as libc::c_uint
+            ))
+        };
         if sys.is_null() {
             return Err(Error::last_error());
         }
         Ok(unsafe { c_chars_to_string!(sys) })
     }
 
+    /// Returns the SMBIOS information of the host, parsed from
+    /// [`get_sys_info()`](Connect::get_sys_info).
+    pub fn get_sysinfo(&self) -> Result<SysInfo, Error> {
+        SysInfo::from_xml(&self.get_sys_info(0)?)
+    }
+
     pub fn get_max_vcpus(&self, domtype: Option<&str>) -> Result<u32, Error> {
         let type_buf = some_string_to_cstring!(domtype);
         let max = unsafe {
-            sys::virConnectGetMaxVcpus(self.as_ptr(), some_cstring_to_c_chars!(type_buf))
+            ffi!(sys::virConnectGetMaxVcpus(
+                self.as_ptr(),
+                some_cstring_to_c_chars!(type_buf)
+            ))
         };
         if max == -1 {
             return Err(Error::last_error());
@@ -387,14 +1413,14 @@ impl Connect {
 
     pub fn get_cpu_models_names(&self, arch: &str, flags: u32) -> Result<Vec<String>, Error> {
         let mut names: *mut *mut libc::c_char = ptr::null_mut();
-        let arch_buf = CString::new(arch).unwrap();
+        let arch_buf = CString::new(arch)?;
         let size = unsafe {
-            sys::virConnectGetCPUModelNames(
+            ffi!(sys::virConnectGetCPUModelNames(
                 self.as_ptr(),
                 arch_buf.as_ptr(),
                 &mut names,
                 flags as libc::c_uint,
-            )
+            ))
         };
         if size == -1 {
             return Err(Error::last_error());
@@ -410,7 +1436,7 @@ impl Connect {
     }
 
     pub fn is_alive(&self) -> Result<bool, Error> {
-        let t = unsafe { sys::virConnectIsAlive(self.as_ptr()) };
+        let t = unsafe { ffi!(sys::virConnectIsAlive(self.as_ptr())) };
         if t == -1 {
             return Err(Error::last_error());
         }
@@ -418,7 +1444,7 @@ impl Connect {
     }
 
     pub fn is_encrypted(&self) -> Result<bool, Error> {
-        let t = unsafe { sys::virConnectIsEncrypted(self.as_ptr()) };
+        let t = unsafe { ffi!(sys::virConnectIsEncrypted(self.as_ptr())) };
         if t == -1 {
             return Err(Error::last_error());
         }
@@ -426,7 +1452,7 @@ impl Connect {
     }
 
     pub fn is_secure(&self) -> Result<bool, Error> {
-        let t = unsafe { sys::virConnectIsSecure(self.as_ptr()) };
+        let t = unsafe { ffi!(sys::virConnectIsSecure(self.as_ptr())) };
         if t == -1 {
             return Err(Error::last_error());
         }
@@ -438,25 +1464,21 @@ impl Connect {
     /// # Examples
     ///
     /// ```
-    /// use virt::connect::Connect;
+    /// use virt::connect::{Connect, ConnectListAllDomainsFlags};
     ///
     /// let conn = Connect::open(Some("test:///default")).unwrap();
-    /// let domains = conn.list_domains().unwrap();
-    /// assert_eq!(domains.len(), 1);
+    /// let domains = conn.domains(ConnectListAllDomainsFlags::ACTIVE).unwrap();
+    /// assert_eq!(domains.count(), 1);
     /// ```
-    #[allow(clippy::needless_range_loop)]
+    #[deprecated(note = "use `domains(ConnectListAllDomainsFlags::ACTIVE)` instead")]
     pub fn list_domains(&self) -> Result<Vec<u32>, Error> {
-        let mut ids: [libc::c_int; 512] = [0; 512];
-        let size = unsafe { sys::virConnectListDomains(self.as_ptr(), ids.as_mut_ptr(), 512) };
-        if size == -1 {
-            return Err(Error::last_error());
-        }
-
-        let mut array: Vec<u32> = Vec::new();
-        for x in 0..size as usize {
-            array.push(ids[x] as u32);
-        }
-        Ok(array)
+        let ids = list_array(
+            0,
+            || unsafe { ffi!(sys::virConnectNumOfDomains(self.as_ptr())) },
+            |ids, len| unsafe { ffi!(sys::virConnectListDomains(self.as_ptr(), ids, len)) },
+            |_| {},
+        )?;
+        Ok(ids.into_iter().map(|id| id as u32).collect())
     }
 
     ///
@@ -464,26 +1486,18 @@ impl Connect {
     /// # Examples
     ///
     /// ```
-    /// use virt::connect::Connect;
+    /// use virt::connect::{Connect, ConnectListAllInterfacesFlags};
     ///
     /// let conn = Connect::open(Some("test:///default")).unwrap();
-    /// let ifaces = conn.list_interfaces().unwrap();
-    /// assert_eq!(ifaces.len(), 1);
+    /// let ifaces = conn.interfaces(ConnectListAllInterfacesFlags::ACTIVE).unwrap();
+    /// assert_eq!(ifaces.count(), 1);
     /// ```
-    #[allow(clippy::needless_range_loop)]
+    #[deprecated(note = "use `interfaces(ConnectListAllInterfacesFlags::ACTIVE)` instead")]
     pub fn list_interfaces(&self) -> Result<Vec<String>, Error> {
-        let mut names: [*mut libc::c_char; 1024] = [ptr::null_mut(); 1024];
-        let size =
-            unsafe { sys::virConnectListInterfaces(self.as_ptr(), names.as_mut_ptr(), 1024) };
-        if size == -1 {
-            return Err(Error::last_error());
-        }
-
-        let mut array: Vec<String> = Vec::new();
-        for x in 0..size as usize {
-            array.push(unsafe { c_chars_to_string!(names[x]) });
-        }
-        Ok(array)
+        list_names(
+            || unsafe { ffi!(sys::virConnectNumOfInterfaces(self.as_ptr())) },
+            |names, len| unsafe { ffi!(sys::virConnectListInterfaces(self.as_ptr(), names, len)) },
+        )
     }
 
     ///
@@ -491,55 +1505,34 @@ impl Connect {
     /// # Examples
     ///
     /// ```
-    /// use virt::connect::Connect;
+    /// use virt::connect::{Connect, ConnectListAllNetworksFlags};
     ///
     /// let conn = Connect::open(Some("test:///default")).unwrap();
-    /// let networks = conn.list_networks().unwrap();
-    /// assert_eq!(networks.len(), 1);
+    /// let networks = conn.networks(ConnectListAllNetworksFlags::ACTIVE).unwrap();
+    /// assert_eq!(networks.count(), 1);
     /// ```
-    #[allow(clippy::needless_range_loop)]
+    #[deprecated(note = "use `networks(ConnectListAllNetworksFlags::ACTIVE)` instead")]
     pub fn list_networks(&self) -> Result<Vec<String>, Error> {
-        let mut names: [*mut libc::c_char; 1024] = [ptr::null_mut(); 1024];
-        let size = unsafe { sys::virConnectListNetworks(self.as_ptr(), names.as_mut_ptr(), 1024) };
-        if size == -1 {
-            return Err(Error::last_error());
-        }
-
-        let mut array: Vec<String> = Vec::new();
-        for x in 0..size as usize {
-            array.push(unsafe { c_chars_to_string!(names[x]) });
-        }
-        Ok(array)
+        list_names(
+            || unsafe { ffi!(sys::virConnectNumOfNetworks(self.as_ptr())) },
+            |names, len| unsafe { ffi!(sys::virConnectListNetworks(self.as_ptr(), names, len)) },
+        )
     }
 
-    #[allow(clippy::needless_range_loop)]
+    #[deprecated(note = "use `nw_filters()` instead")]
     pub fn list_nw_filters(&self) -> Result<Vec<String>, Error> {
-        let mut names: [*mut libc::c_char; 1024] = [ptr::null_mut(); 1024];
-        let size = unsafe { sys::virConnectListNWFilters(self.as_ptr(), names.as_mut_ptr(), 1024) };
-        if size == -1 {
-            return Err(Error::last_error());
-        }
-
-        let mut array: Vec<String> = Vec::new();
-        for x in 0..size as usize {
-            array.push(unsafe { c_chars_to_string!(names[x]) });
-        }
-        Ok(array)
+        list_names(
+            || unsafe { ffi!(sys::virConnectNumOfNWFilters(self.as_ptr())) },
+            |names, len| unsafe { ffi!(sys::virConnectListNWFilters(self.as_ptr(), names, len)) },
+        )
     }
 
-    #[allow(clippy::needless_range_loop)]
+    #[deprecated(note = "use `secrets(ConnectListAllSecretsFlags::empty())` instead")]
     pub fn list_secrets(&self) -> Result<Vec<String>, Error> {
-        let mut names: [*mut libc::c_char; 1024] = [ptr::null_mut(); 1024];
-        let size = unsafe { sys::virConnectListSecrets(self.as_ptr(), names.as_mut_ptr(), 1024) };
-        if size == -1 {
-            return Err(Error::last_error());
-        }
-
-        let mut array: Vec<String> = Vec::new();
-        for x in 0..size as usize {
-            array.push(unsafe { c_chars_to_string!(names[x]) });
-        }
-        Ok(array)
+        list_names(
+            || unsafe { ffi!(sys::virConnectNumOfSecrets(self.as_ptr())) },
+            |names, len| unsafe { ffi!(sys::virConnectListSecrets(self.as_ptr(), names, len)) },
+        )
     }
 
     ///
@@ -547,35 +1540,33 @@ impl Connect {
     /// # Examples
     ///
     /// ```
-    /// use virt::connect::Connect;
+    /// use virt::connect::{Connect, ConnectListAllStoragePoolsFlags};
     ///
     /// let conn = Connect::open(Some("test:///default")).unwrap();
-    /// let pools = conn.list_storage_pools().unwrap();
-    /// assert_eq!(pools.len(), 1);
+    /// let pools = conn.storage_pools(ConnectListAllStoragePoolsFlags::ACTIVE).unwrap();
+    /// assert_eq!(pools.count(), 1);
     /// ```
-    #[allow(clippy::needless_range_loop)]
+    #[deprecated(note = "use `storage_pools(ConnectListAllStoragePoolsFlags::ACTIVE)` instead")]
     pub fn list_storage_pools(&self) -> Result<Vec<String>, Error> {
-        let mut names: [*mut libc::c_char; 1024] = [ptr::null_mut(); 1024];
-        let size =
-            unsafe { sys::virConnectListStoragePools(self.as_ptr(), names.as_mut_ptr(), 1024) };
-        if size == -1 {
-            return Err(Error::last_error());
-        }
-
-        let mut array: Vec<String> = Vec::new();
-        for x in 0..size as usize {
-            array.push(unsafe { c_chars_to_string!(names[x]) });
-        }
-        Ok(array)
+        list_names(
+            || unsafe { ffi!(sys::virConnectNumOfStoragePools(self.as_ptr())) },
+            |names, len| unsafe {
+                ffi!(sys::virConnectListStoragePools(self.as_ptr(), names, len))
+            },
+        )
     }
 
     pub fn list_all_domains(
         &self,
-        flags: sys::virConnectListAllDomainsFlags,
+        flags: impl Into<ConnectListAllDomainsFlags>,
     ) -> Result<Vec<Domain>, Error> {
         let mut domains: *mut sys::virDomainPtr = ptr::null_mut();
         let size = unsafe {
-            sys::virConnectListAllDomains(self.as_ptr(), &mut domains, flags as libc::c_uint)
+            ffi!(sys::virConnectListAllDomains(
+                self.as_ptr(),
+                &mut domains,
+                flags.into().bits() as libc::c_uint,
+            ))
         };
         if size == -1 {
             return Err(Error::last_error());
@@ -592,11 +1583,15 @@ impl Connect {
 
     pub fn list_all_networks(
         &self,
-        flags: sys::virConnectListAllNetworksFlags,
+        flags: impl Into<ConnectListAllNetworksFlags>,
     ) -> Result<Vec<Network>, Error> {
         let mut networks: *mut sys::virNetworkPtr = ptr::null_mut();
         let size = unsafe {
-            sys::virConnectListAllNetworks(self.as_ptr(), &mut networks, flags as libc::c_uint)
+            ffi!(sys::virConnectListAllNetworks(
+                self.as_ptr(),
+                &mut networks,
+                flags.into().bits() as libc::c_uint,
+            ))
         };
         if size == -1 {
             return Err(Error::last_error());
@@ -613,11 +1608,15 @@ impl Connect {
 
     pub fn list_all_interfaces(
         &self,
-        flags: sys::virConnectListAllInterfacesFlags,
+        flags: impl Into<ConnectListAllInterfacesFlags>,
     ) -> Result<Vec<Interface>, Error> {
         let mut interfaces: *mut sys::virInterfacePtr = ptr::null_mut();
         let size = unsafe {
-            sys::virConnectListAllInterfaces(self.as_ptr(), &mut interfaces, flags as libc::c_uint)
+            ffi!(sys::virConnectListAllInterfaces(
+                self.as_ptr(),
+                &mut interfaces,
+                flags.into().bits() as libc::c_uint,
+            ))
         };
         if size == -1 {
             return Err(Error::last_error());
@@ -634,11 +1633,15 @@ impl Connect {
 
     pub fn list_all_node_devices(
         &self,
-        flags: sys::virConnectListAllNodeDeviceFlags,
+        flags: impl Into<ConnectListAllNodeDeviceFlags>,
     ) -> Result<Vec<NodeDevice>, Error> {
         let mut nodedevs: *mut sys::virNodeDevicePtr = ptr::null_mut();
         let size = unsafe {
-            sys::virConnectListAllNodeDevices(self.as_ptr(), &mut nodedevs, flags as libc::c_uint)
+            ffi!(sys::virConnectListAllNodeDevices(
+                self.as_ptr(),
+                &mut nodedevs,
+                flags.into().bits() as libc::c_uint,
+            ))
         };
         if size == -1 {
             return Err(Error::last_error());
@@ -653,13 +1656,29 @@ impl Connect {
         Ok(array)
     }
 
+    /// Lists the node devices with the capability `cap`.
+    ///
+    /// `flags` may further filter the devices, e.g. to only list the
+    /// active ones.
+    pub fn list_node_devices_with_cap(
+        &self,
+        cap: NodeDeviceCap,
+        flags: impl Into<ConnectListAllNodeDeviceFlags>,
+    ) -> Result<Vec<NodeDevice>, Error> {
+        self.list_all_node_devices(flags.into() | cap.list_flag())
+    }
+
     pub fn list_all_secrets(
         &self,
-        flags: sys::virConnectListAllSecretsFlags,
+        flags: impl Into<ConnectListAllSecretsFlags>,
     ) -> Result<Vec<Secret>, Error> {
         let mut secrets: *mut sys::virSecretPtr = ptr::null_mut();
         let size = unsafe {
-            sys::virConnectListAllSecrets(self.as_ptr(), &mut secrets, flags as libc::c_uint)
+            ffi!(sys::virConnectListAllSecrets(
+                self.as_ptr(),
+                &mut secrets,
+                flags.into().bits() as libc::c_uint,
+            ))
         };
         if size == -1 {
             return Err(Error::last_error());
@@ -676,11 +1695,15 @@ impl Connect {
 
     pub fn list_all_storage_pools(
         &self,
-        flags: sys::virConnectListAllStoragePoolsFlags,
+        flags: impl Into<ConnectListAllStoragePoolsFlags>,
     ) -> Result<Vec<StoragePool>, Error> {
         let mut storages: *mut sys::virStoragePoolPtr = ptr::null_mut();
         let size = unsafe {
-            sys::virConnectListAllStoragePools(self.as_ptr(), &mut storages, flags as libc::c_uint)
+            ffi!(sys::virConnectListAllStoragePools(
+                self.as_ptr(),
+                &mut storages,
+                flags.into().bits() as libc::c_uint,
+            ))
         };
         if size == -1 {
             return Err(Error::last_error());
@@ -695,10 +1718,17 @@ impl Connect {
         Ok(array)
     }
 
-    pub fn list_all_nw_filters(&self, flags: u32) -> Result<Vec<NWFilter>, Error> {
+    pub fn list_all_nw_filters(
+        &self,
+        flags: impl Into<ConnectListAllNWFiltersFlags>,
+    ) -> Result<Vec<NWFilter>, Error> {
         let mut filters: *mut sys::virNWFilterPtr = ptr::null_mut();
         let size = unsafe {
-            sys::virConnectListAllNWFilters(self.as_ptr(), &mut filters, flags as libc::c_uint)
+            ffi!(sys::virConnectListAllNWFilters(
+                self.as_ptr(),
+                &mut filters,
+                flags.into().bits()
+            ))
         };
         if size == -1 {
             return Err(Error::last_error());
@@ -713,31 +1743,69 @@ impl Connect {
         Ok(array)
     }
 
+    /// Iterates over the domains matching `flags`.
     ///
-    ///
-    /// # Examples
-    ///
-    /// ```
-    /// use virt::connect::Connect;
-    ///
-    /// let conn = Connect::open(Some("test:///default")).unwrap();
-    /// let domains = conn.list_defined_domains().unwrap();
-    /// assert_eq!(domains.len(), 0);
-    /// ```
-    #[allow(clippy::needless_range_loop)]
-    pub fn list_defined_domains(&self) -> Result<Vec<String>, Error> {
-        let mut names: [*mut libc::c_char; 1024] = [ptr::null_mut(); 1024];
-        let size =
-            unsafe { sys::virConnectListDefinedDomains(self.as_ptr(), names.as_mut_ptr(), 1024) };
-        if size == -1 {
-            return Err(Error::last_error());
-        }
+    /// The domains are all fetched at once with
+    /// [`list_all_domains()`](Connect::list_all_domains): the iterator
+    /// is not affected by domains defined or undefined meanwhile.
+    pub fn domains(
+        &self,
+        flags: impl Into<ConnectListAllDomainsFlags>,
+    ) -> Result<impl Iterator<Item = Domain>, Error> {
+        Ok(self.list_all_domains(flags)?.into_iter())
+    }
 
-        let mut array: Vec<String> = Vec::new();
-        for x in 0..size as usize {
-            array.push(unsafe { c_chars_to_string!(names[x]) });
-        }
-        Ok(array)
+    /// Iterates over the networks matching `flags`, see
+    /// [`domains()`](Connect::domains).
+    pub fn networks(
+        &self,
+        flags: impl Into<ConnectListAllNetworksFlags>,
+    ) -> Result<impl Iterator<Item = Network>, Error> {
+        Ok(self.list_all_networks(flags)?.into_iter())
+    }
+
+    /// Iterates over the host interfaces matching `flags`, see
+    /// [`domains()`](Connect::domains).
+    pub fn interfaces(
+        &self,
+        flags: impl Into<ConnectListAllInterfacesFlags>,
+    ) -> Result<impl Iterator<Item = Interface>, Error> {
+        Ok(self.list_all_interfaces(flags)?.into_iter())
+    }
+
+    /// Iterates over the node devices matching `flags`, see
+    /// [`domains()`](Connect::domains).
+    pub fn node_devices(
+        &self,
+        flags: impl Into<ConnectListAllNodeDeviceFlags>,
+    ) -> Result<impl Iterator<Item = NodeDevice>, Error> {
+        Ok(self.list_all_node_devices(flags)?.into_iter())
+    }
+
+    /// Iterates over the secrets matching `flags`, see
+    /// [`domains()`](Connect::domains).
+    pub fn secrets(
+        &self,
+        flags: impl Into<ConnectListAllSecretsFlags>,
+    ) -> Result<impl Iterator<Item = Secret>, Error> {
+        Ok(self.list_all_secrets(flags)?.into_iter())
+    }
+
+    /// Iterates over the storage pools matching `flags`, see
+    /// [`domains()`](Connect::domains).
+    pub fn storage_pools(
+        &self,
+        flags: impl Into<ConnectListAllStoragePoolsFlags>,
+    ) -> Result<impl Iterator<Item = StoragePool>, Error> {
+        Ok(self.list_all_storage_pools(flags)?.into_iter())
+    }
+
+    /// Iterates over the network filters, see
+    /// [`domains()`](Connect::domains).
+    pub fn nw_filters(&self) -> Result<impl Iterator<Item = NWFilter>, Error> {
+        Ok(self
+            .list_all_nw_filters(ConnectListAllNWFiltersFlags::empty())?
+            .into_iter())
     }
 
     ///
@@ -745,27 +1813,45 @@ impl Connect {
     /// # Examples
     ///
     /// ```
-    /// use virt::connect::Connect;
+    /// use virt::connect::{Connect, ConnectListAllDomainsFlags};
     ///
     /// let conn = Connect::open(Some("test:///default")).unwrap();
-    /// let ifaces = conn.list_defined_interfaces().unwrap();
-    /// assert_eq!(ifaces.len(), 0);
+    /// let domains = conn.domains(ConnectListAllDomainsFlags::INACTIVE).unwrap();
+    /// assert_eq!(domains.count(), 0);
     /// ```
-    #[allow(clippy::needless_range_loop)]
-    pub fn list_defined_interfaces(&self) -> Result<Vec<String>, Error> {
-        let mut names: [*mut libc::c_char; 1024] = [ptr::null_mut(); 1024];
-        let size = unsafe {
-            sys::virConnectListDefinedInterfaces(self.as_ptr(), names.as_mut_ptr(), 1024)
-        };
-        if size == -1 {
-            return Err(Error::last_error());
-        }
+    #[deprecated(note = "use `domains(ConnectListAllDomainsFlags::INACTIVE)` instead")]
+    pub fn list_defined_domains(&self) -> Result<Vec<String>, Error> {
+        list_names(
+            || unsafe { ffi!(sys::virConnectNumOfDefinedDomains(self.as_ptr())) },
+            |names, len| unsafe {
+                ffi!(sys::virConnectListDefinedDomains(self.as_ptr(), names, len))
+            },
+        )
+    }
 
-        let mut array: Vec<String> = Vec::new();
-        for x in 0..size as usize {
-            array.push(unsafe { c_chars_to_string!(names[x]) });
-        }
-        Ok(array)
+    ///
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use virt::connect::{Connect, ConnectListAllInterfacesFlags};
+    ///
+    /// let conn = Connect::open(Some("test:///default")).unwrap();
+    /// let ifaces = conn.interfaces(ConnectListAllInterfacesFlags::INACTIVE).unwrap();
+    /// assert_eq!(ifaces.count(), 0);
+    /// ```
+    #[deprecated(note = "use `interfaces(ConnectListAllInterfacesFlags::INACTIVE)` instead")]
+    pub fn list_defined_interfaces(&self) -> Result<Vec<String>, Error> {
+        list_names(
+            || unsafe { ffi!(sys::virConnectNumOfDefinedInterfaces(self.as_ptr())) },
+            |names, len| unsafe {
+                ffi!(sys::virConnectListDefinedInterfaces(
+                    self.as_ptr(),
+                    names,
+                    len
+                ))
+            },
+        )
     }
 
     ///
@@ -773,27 +1859,24 @@ impl Connect {
     /// # Examples
     ///
     /// ```
-    /// use virt::connect::Connect;
+    /// use virt::connect::{Connect, ConnectListAllStoragePoolsFlags};
     ///
     /// let conn = Connect::open(Some("test:///default")).unwrap();
-    /// let pools = conn.list_defined_storage_pools().unwrap();
-    /// assert_eq!(pools.len(), 0);
+    /// let pools = conn.storage_pools(ConnectListAllStoragePoolsFlags::INACTIVE).unwrap();
+    /// assert_eq!(pools.count(), 0);
     /// ```
-    #[allow(clippy::needless_range_loop)]
+    #[deprecated(note = "use `storage_pools(ConnectListAllStoragePoolsFlags::INACTIVE)` instead")]
     pub fn list_defined_storage_pools(&self) -> Result<Vec<String>, Error> {
-        let mut names: [*mut libc::c_char; 1024] = [ptr::null_mut(); 1024];
-        let size = unsafe {
-            sys::virConnectListDefinedStoragePools(self.as_ptr(), names.as_mut_ptr(), 1024)
-        };
-        if size == -1 {
-            return Err(Error::last_error());
-        }
-
-        let mut array: Vec<String> = Vec::new();
-        for x in 0..size as usize {
-            array.push(unsafe { c_chars_to_string!(names[x]) });
-        }
-        Ok(array)
+        list_names(
+            || unsafe { ffi!(sys::virConnectNumOfDefinedStoragePools(self.as_ptr())) },
+            |names, len| unsafe {
+                ffi!(sys::virConnectListDefinedStoragePools(
+                    self.as_ptr(),
+                    names,
+                    len
+                ))
+            },
+        )
     }
 
     ///
@@ -801,26 +1884,24 @@ impl Connect {
     /// # Examples
     ///
     /// ```
-    /// use virt::connect::Connect;
+    /// use virt::connect::{Connect, ConnectListAllNetworksFlags};
     ///
     /// let conn = Connect::open(Some("test:///default")).unwrap();
-    /// let networks = conn.list_defined_networks().unwrap();
-    /// assert_eq!(networks.len(), 0);
+    /// let networks = conn.networks(ConnectListAllNetworksFlags::INACTIVE).unwrap();
+    /// assert_eq!(networks.count(), 0);
     /// ```
-    #[allow(clippy::needless_range_loop)]
+    #[deprecated(note = "use `networks(ConnectListAllNetworksFlags::INACTIVE)` instead")]
     pub fn list_defined_networks(&self) -> Result<Vec<String>, Error> {
-        let mut names: [*mut libc::c_char; 1024] = [ptr::null_mut(); 1024];
-        let size =
-            unsafe { sys::virConnectListDefinedNetworks(self.as_ptr(), names.as_mut_ptr(), 1024) };
-        if size == -1 {
-            return Err(Error::last_error());
-        }
-
-        let mut array: Vec<String> = Vec::new();
-        for x in 0..size as usize {
-            array.push(unsafe { c_chars_to_string!(names[x]) });
-        }
-        Ok(array)
+        list_names(
+            || unsafe { ffi!(sys::virConnectNumOfDefinedNetworks(self.as_ptr())) },
+            |names, len| unsafe {
+                ffi!(sys::virConnectListDefinedNetworks(
+                    self.as_ptr(),
+                    names,
+                    len
+                ))
+            },
+        )
     }
 
     /// # Examples
@@ -833,7 +1914,7 @@ impl Connect {
     /// assert_eq!(num_domains, 1);
     /// ```
     pub fn num_of_domains(&self) -> Result<u32, Error> {
-        let num = unsafe { sys::virConnectNumOfDomains(self.as_ptr()) };
+        let num = unsafe { ffi!(sys::virConnectNumOfDomains(self.as_ptr())) };
         if num == -1 {
             return Err(Error::last_error());
         }
@@ -850,13 +1931,65 @@ impl Connect {
     /// assert_eq!(num_ifaces, 1);
     /// ```
     pub fn num_of_interfaces(&self) -> Result<u32, Error> {
-        let num = unsafe { sys::virConnectNumOfInterfaces(self.as_ptr()) };
+        let num = unsafe { ffi!(sys::virConnectNumOfInterfaces(self.as_ptr())) };
         if num == -1 {
             return Err(Error::last_error());
         }
         Ok(num as u32)
     }
 
+    /// Start a transaction on the host network configuration.
+    ///
+    /// The current configuration is saved so that the changes made
+    /// to interfaces afterwards can be either committed with
+    /// [`interface_change_commit()`] or reverted with
+    /// [`interface_change_rollback()`].
+    ///
+    /// [`interface_change_commit()`]: Connect::interface_change_commit
+    /// [`interface_change_rollback()`]: Connect::interface_change_rollback
+    pub fn interface_change_begin(&self, flags: u32) -> Result<(), Error> {
+        let ret = unsafe {
+            ffi!(sys::virInterfaceChangeBegin(
+                self.as_ptr(),
+                flags as libc::c_uint
+            ))
+        };
+        if ret == -1 {
+            return Err(Error::last_error());
+        }
+        Ok(())
+    }
+
+    /// Commit the changes made to the host network configuration
+    /// since [`interface_change_begin()`](Connect::interface_change_begin).
+    pub fn interface_change_commit(&self, flags: u32) -> Result<(), Error> {
+        let ret = unsafe {
+            ffi!(sys::virInterfaceChangeCommit(
+                self.as_ptr(),
+                flags as libc::c_uint
+            ))
+        };
+        if ret == -1 {
+            return Err(Error::last_error());
+        }
+        Ok(())
+    }
+
+    /// Revert the host network configuration to its state at the
+    /// time of [`interface_change_begin()`](Connect::interface_change_begin).
+    pub fn interface_change_rollback(&self, flags: u32) -> Result<(), Error> {
+        let ret = unsafe {
+            ffi!(sys::virInterfaceChangeRollback(
+                self.as_ptr(),
+                flags as libc::c_uint
+            ))
+        };
+        if ret == -1 {
+            return Err(Error::last_error());
+        }
+        Ok(())
+    }
+
     /// # Examples
     ///
     /// ```
@@ -867,7 +2000,7 @@ impl Connect {
     /// assert_eq!(num_networks, 1);
     /// ```
     pub fn num_of_networks(&self) -> Result<u32, Error> {
-        let num = unsafe { sys::virConnectNumOfNetworks(self.as_ptr()) };
+        let num = unsafe { ffi!(sys::virConnectNumOfNetworks(self.as_ptr())) };
         if num == -1 {
             return Err(Error::last_error());
         }
@@ -884,7 +2017,7 @@ impl Connect {
     /// assert_eq!(num_pools, 1);
     /// ```
     pub fn num_of_storage_pools(&self) -> Result<u32, Error> {
-        let num = unsafe { sys::virConnectNumOfStoragePools(self.as_ptr()) };
+        let num = unsafe { ffi!(sys::virConnectNumOfStoragePools(self.as_ptr())) };
         if num == -1 {
             return Err(Error::last_error());
         }
@@ -892,7 +2025,7 @@ impl Connect {
     }
 
     pub fn num_of_nw_filters(&self) -> Result<u32, Error> {
-        let num = unsafe { sys::virConnectNumOfNWFilters(self.as_ptr()) };
+        let num = unsafe { ffi!(sys::virConnectNumOfNWFilters(self.as_ptr())) };
         if num == -1 {
             return Err(Error::last_error());
         }
@@ -900,7 +2033,7 @@ impl Connect {
     }
 
     pub fn num_of_secrets(&self) -> Result<u32, Error> {
-        let num = unsafe { sys::virConnectNumOfSecrets(self.as_ptr()) };
+        let num = unsafe { ffi!(sys::virConnectNumOfSecrets(self.as_ptr())) };
         if num == -1 {
             return Err(Error::last_error());
         }
@@ -917,7 +2050,7 @@ impl Connect {
     /// assert_eq!(num_domains, 0);
     /// ```
     pub fn num_of_defined_domains(&self) -> Result<u32, Error> {
-        let num = unsafe { sys::virConnectNumOfDefinedDomains(self.as_ptr()) };
+        let num = unsafe { ffi!(sys::virConnectNumOfDefinedDomains(self.as_ptr())) };
         if num == -1 {
             return Err(Error::last_error());
         }
@@ -934,7 +2067,7 @@ impl Connect {
     /// assert_eq!(num_ifaces, 0);
     /// ```
     pub fn num_of_defined_interfaces(&self) -> Result<u32, Error> {
-        let num = unsafe { sys::virConnectNumOfDefinedInterfaces(self.as_ptr()) };
+        let num = unsafe { ffi!(sys::virConnectNumOfDefinedInterfaces(self.as_ptr())) };
         if num == -1 {
             return Err(Error::last_error());
         }
@@ -951,7 +2084,7 @@ impl Connect {
     /// assert_eq!(num_networks, 0);
     /// ```
     pub fn num_of_defined_networks(&self) -> Result<u32, Error> {
-        let num = unsafe { sys::virConnectNumOfDefinedNetworks(self.as_ptr()) };
+        let num = unsafe { ffi!(sys::virConnectNumOfDefinedNetworks(self.as_ptr())) };
         if num == -1 {
             return Err(Error::last_error());
         }
@@ -968,7 +2101,7 @@ impl Connect {
     /// assert_eq!(num_pools, 0);
     /// ```
     pub fn num_of_defined_storage_pools(&self) -> Result<u32, Error> {
-        let num = unsafe { sys::virConnectNumOfDefinedStoragePools(self.as_ptr()) };
+        let num = unsafe { ffi!(sys::virConnectNumOfDefinedStoragePools(self.as_ptr())) };
         if num == -1 {
             return Err(Error::last_error());
         }
@@ -989,21 +2122,30 @@ impl Connect {
     /// ```
     pub fn get_hyp_version(&self) -> Result<u32, Error> {
         let mut hyver: libc::c_ulong = 0;
-        let ret = unsafe { sys::virConnectGetVersion(self.as_ptr(), &mut hyver) };
+        let ret = unsafe { ffi!(sys::virConnectGetVersion(self.as_ptr(), &mut hyver)) };
         if ret == -1 {
             return Err(Error::last_error());
         }
         Ok(hyver as u32)
     }
 
+    /// Returns the version of the hypervisor, unpacked.
+    pub fn get_hyp_version_triple(&self) -> Result<LibVersion, Error> {
+        self.get_hyp_version().map(LibVersion::from_raw)
+    }
+
     pub fn compare_cpu(
         &self,
         xml: &str,
         flags: sys::virConnectCompareCPUFlags,
     ) -> Result<sys::virCPUCompareResult, Error> {
-        let xml_buf = CString::new(xml).unwrap();
+        let xml_buf = CString::new(xml)?;
         let res = unsafe {
-            sys::virConnectCompareCPU(self.as_ptr(), xml_buf.as_ptr(), flags as libc::c_uint)
+            ffi!(sys::virConnectCompareCPU(
+                self.as_ptr(),
+                xml_buf.as_ptr(),
+                flags as libc::c_uint
+            ))
         };
         if res == sys::VIR_CPU_COMPARE_ERROR {
             return Err(Error::last_error());
@@ -1012,7 +2154,7 @@ impl Connect {
     }
 
     pub fn get_free_memory(&self) -> Result<u64, Error> {
-        let res = unsafe { sys::virNodeGetFreeMemory(self.as_ptr()) };
+        let res = unsafe { ffi!(sys::virNodeGetFreeMemory(self.as_ptr())) };
         if res == 0 {
             return Err(Error::last_error());
         }
@@ -1021,7 +2163,7 @@ impl Connect {
 
     pub fn get_node_info(&self) -> Result<NodeInfo, Error> {
         let mut pinfo = mem::MaybeUninit::uninit();
-        let res = unsafe { sys::virNodeGetInfo(self.as_ptr(), pinfo.as_mut_ptr()) };
+        let res = unsafe { ffi!(sys::virNodeGetInfo(self.as_ptr(), pinfo.as_mut_ptr())) };
         if res == -1 {
             return Err(Error::last_error());
         }
@@ -1038,13 +2180,104 @@ impl Connect {
         })
     }
 
+    /// Get the CPU map of the host.
+    ///
+    /// Returns the total number of CPUs of the host, the map of the
+    /// online CPUs and the number of online CPUs. The total number of
+    /// CPUs is the size to use for maps passed to the pinning APIs.
+    pub fn get_node_cpu_map(&self, flags: u32) -> Result<(u32, CpuMap, u32), Error> {
+        let mut cpumap: *mut libc::c_uchar = ptr::null_mut();
+        let mut online: libc::c_uint = 0;
+        let ret = unsafe {
+            ffi!(sys::virNodeGetCPUMap(
+                self.as_ptr(),
+                &mut cpumap,
+                &mut online,
+                flags as libc::c_uint,
+            ))
+        };
+        if ret == -1 {
+            return Err(Error::last_error());
+        }
+        let total = ret as u32;
+        let map = unsafe {
+            let bytes = std::slice::from_raw_parts(cpumap, CpuMap::maplen(total));
+            let map = CpuMap::from_bytes(bytes);
+            libc::free(cpumap as *mut libc::c_void);
+            map
+        };
+        Ok((total, map, online as u32))
+    }
+
+    /// Gets the CPU time counters of the host CPU `cpu`, or the sum
+    /// over all the CPUs of the host when `cpu` is `None`.
+    pub fn get_node_cpu_stats(&self, cpu: Option<u32>, flags: u32) -> Result<NodeCpuStats, Error> {
+        let cpu_num = match cpu {
+            Some(cpu) => i32::try_from(cpu)
+                .map_err(|_| Error::from_invalid_arg(format!("invalid CPU number {}", cpu)))?,
+            None => sys::VIR_NODE_CPU_STATS_ALL_CPUS,
+        };
+        let mut nparams: libc::c_int = 0;
+        let ret = unsafe {
+            ffi!(sys::virNodeGetCPUStats(
+                self.as_ptr(),
+                cpu_num as libc::c_int,
+                ptr::null_mut(),
+                &mut nparams,
+                flags as libc::c_uint,
+            ))
+        };
+        if ret == -1 {
+            return Err(Error::last_error());
+        }
+        let mut stats: Vec<sys::virNodeCPUStats> = Vec::with_capacity(nparams as usize);
+        let ret = unsafe {
+            ffi!(sys::virNodeGetCPUStats(
+                self.as_ptr(),
+                cpu_num as libc::c_int,
+                stats.as_mut_ptr(),
+                &mut nparams,
+                flags as libc::c_uint,
+            ))
+        };
+        if ret == -1 {
+            return Err(Error::last_error());
+        }
+        unsafe { stats.set_len(nparams as usize) };
+        Ok(NodeCpuStats::from_stats(&stats))
+    }
+
+    /// Suspends the host for the given number of seconds, after
+    /// which it is automatically woken up.
+    ///
+    /// A `duration` of 0 is not supported by every hypervisor.
+    pub fn node_suspend_for_duration(
+        &self,
+        target: SuspendTarget,
+        duration: u64,
+        flags: u32,
+    ) -> Result<(), Error> {
+        let ret = unsafe {
+            ffi!(sys::virNodeSuspendForDuration(
+                self.as_ptr(),
+                target.to_raw() as libc::c_uint,
+                duration as libc::c_ulonglong,
+                flags as libc::c_uint,
+            ))
+        };
+        if ret == -1 {
+            return Err(Error::last_error());
+        }
+        Ok(())
+    }
+
     pub fn set_keep_alive(&self, interval: i32, count: u32) -> Result<i32, Error> {
         let ret = unsafe {
-            sys::virConnectSetKeepAlive(
+            ffi!(sys::virConnectSetKeepAlive(
                 self.as_ptr(),
                 interval as libc::c_int,
                 count as libc::c_uint,
-            )
+            ))
         };
         if ret == -1 {
             return Err(Error::last_error());
@@ -1052,21 +2285,78 @@ impl Connect {
         Ok(ret)
     }
 
+    /// Registers `cb` to be called when the connection is closed for
+    /// another reason than a call to [`close()`], for instance when the
+    /// daemon goes away or the keepalive timer expires.
+    ///
+    /// A connection has at most one close callback, registering a new
+    /// one fails until the previous one is unregistered. Only the
+    /// remote driver ever reports a close, the other drivers accept the
+    /// registration and ignore it, never releasing `cb`.
+    ///
+    /// An event loop implementation must be registered, for instance
+    /// with [`sys::virEventRegisterDefaultImpl()`], and run for the
+    /// callback to be invoked.
+    ///
+    /// [`close()`]: Connect::close
+    pub fn register_close_callback<F>(&self, cb: F) -> Result<(), Error>
+    where
+        F: 'static + FnMut(ConnectCloseReason) + Send,
+    {
+        let cb: Box<Box<CloseCallback>> = Box::new(Box::new(cb));
+        let opaque = Box::into_raw(cb) as *mut libc::c_void;
+        let ret = unsafe {
+            ffi!(sys::virConnectRegisterCloseCallback(
+                self.as_ptr(),
+                Some(close_callback),
+                opaque,
+                Some(close_callback_free),
+            ))
+        };
+        if ret == -1 {
+            // libvirt does not call the free callback on failure.
+            close_callback_free(opaque);
+            return Err(Error::last_error());
+        }
+        Ok(())
+    }
+
+    /// Removes the callback registered with
+    /// [`register_close_callback()`].
+    ///
+    /// The remote driver keeps a reference to the connection while a
+    /// close callback is registered, so the callback must be removed
+    /// before the connection is closed for it to be released.
+    ///
+    /// [`register_close_callback()`]: Connect::register_close_callback
+    pub fn unregister_close_callback(&self) -> Result<(), Error> {
+        let ret = unsafe {
+            ffi!(sys::virConnectUnregisterCloseCallback(
+                self.as_ptr(),
+                Some(close_callback),
+            ))
+        };
+        if ret == -1 {
+            return Err(Error::last_error());
+        }
+        Ok(())
+    }
+
     pub fn domain_xml_from_native(
         &self,
         nformat: &str,
         nconfig: &str,
         flags: u32,
     ) -> Result<String, Error> {
-        let nformat_buf = CString::new(nformat).unwrap();
-        let nconfig_buf = CString::new(nconfig).unwrap();
+        let nformat_buf = CString::new(nformat)?;
+        let nconfig_buf = CString::new(nconfig)?;
         let ret = unsafe {
-            sys::virConnectDomainXMLFromNative(
+            ffi!(sys::virConnectDomainXMLFromNative(
                 self.as_ptr(),
                 nformat_buf.as_ptr(),
                 nconfig_buf.as_ptr(),
                 flags as libc::c_uint,
-            )
+            ))
         };
         if ret.is_null() {
             return Err(Error::last_error());
@@ -1080,15 +2370,35 @@ impl Connect {
         dxml: &str,
         flags: u32,
     ) -> Result<String, Error> {
-        let nformat_buf = CString::new(nformat).unwrap();
-        let dxml_buf = CString::new(dxml).unwrap();
+        let nformat_buf = CString::new(nformat)?;
+        let dxml_buf = CString::new(dxml)?;
         let ret = unsafe {
-            sys::virConnectDomainXMLToNative(
+            ffi!(sys::virConnectDomainXMLToNative(
                 self.as_ptr(),
                 nformat_buf.as_ptr(),
                 dxml_buf.as_ptr(),
                 flags as libc::c_uint,
-            )
+            ))
+        };
+        if ret.is_null() {
+            return Err(Error::last_error());
+        }
+        Ok(unsafe { c_chars_to_string!(ret) })
+    }
+
+    /// Returns an XML document describing the storage pool types
+    /// supported by the connection.
+    ///
+    /// The document can be parsed with
+    /// [`StoragePoolCapabilities::from_xml()`].
+    ///
+    /// [`StoragePoolCapabilities::from_xml()`]: crate::storage_pool::StoragePoolCapabilities::from_xml
+    pub fn get_storage_pool_capabilities(&self, flags: u32) -> Result<String, Error> {
+        let ret = unsafe {
+            ffi!(sys::virConnectGetStoragePoolCapabilities(
+                self.as_ptr(),
+                flags as libc::c_uint
+            ))
         };
         if ret.is_null() {
             return Err(Error::last_error());
@@ -1109,14 +2419,14 @@ impl Connect {
         let machine_buf = some_string_to_cstring!(machine);
         let virttype_buf = some_string_to_cstring!(virttype);
         let ret = unsafe {
-            sys::virConnectGetDomainCapabilities(
+            ffi!(sys::virConnectGetDomainCapabilities(
                 self.as_ptr(),
                 some_cstring_to_c_chars!(emulatorbin_buf),
                 some_cstring_to_c_chars!(arch_buf),
                 some_cstring_to_c_chars!(machine_buf),
                 some_cstring_to_c_chars!(virttype_buf),
                 flags as libc::c_uint,
-            )
+            ))
         };
         if ret.is_null() {
             return Err(Error::last_error());
@@ -1124,19 +2434,35 @@ impl Connect {
         Ok(unsafe { c_chars_to_string!(ret) })
     }
 
+    /// Returns the CPU models the hypervisor offers for domains of
+    /// the given architecture and virtualization type, parsed from
+    /// [`get_domain_capabilities()`](Connect::get_domain_capabilities).
+    ///
+    /// Unlike [`get_cpu_models_names()`](Connect::get_cpu_models_names),
+    /// this tells which models the hypervisor can actually run on the
+    /// host.
+    pub fn get_domain_cpu_models(
+        &self,
+        arch: Option<&str>,
+        virttype: Option<&str>,
+    ) -> Result<Vec<DomainCapsCpuModel>, Error> {
+        let xml = self.get_domain_capabilities(None, arch, None, virttype, 0)?;
+        DomainCapsCpuModel::list_from_xml(&xml)
+    }
+
     pub fn get_all_domain_stats(
         &self,
-        stats: u32,
-        flags: u32,
+        stats: impl Into<DomainStatsGroups>,
+        flags: impl Into<DomainStatsFilter>,
     ) -> Result<Vec<DomainStatsRecord>, Error> {
         let mut record: *mut sys::virDomainStatsRecordPtr = ptr::null_mut();
         let size = unsafe {
-            sys::virConnectGetAllDomainStats(
+            ffi!(sys::virConnectGetAllDomainStats(
                 self.as_ptr(),
-                stats as libc::c_uint,
+                stats.into().bits() as libc::c_uint,
                 &mut record,
-                flags as libc::c_uint,
-            )
+                flags.into().bits() as libc::c_uint,
+            ))
         };
         if size == -1 {
             return Err(Error::last_error());
@@ -1153,6 +2479,99 @@ impl Connect {
         Ok(array)
     }
 
+    /// Checks the domain XML description `xml`, returning the
+    /// problems found. An empty list means the description is valid.
+    ///
+    /// With empty `flags`, the document is only checked to be well
+    /// formed XML describing a domain, and libvirt is not involved.
+    ///
+    /// With [`DomainDefineFlags::VALIDATE`], the description is also
+    /// checked against the libvirt schema and the driver by defining
+    /// it under a throwaway name and without its UUID, then undefining
+    /// it right away. An existing domain with the same name is left
+    /// untouched.
+    ///
+    /// # Side effects
+    ///
+    /// Defining and undefining the throwaway domain is visible to
+    /// other clients of the hypervisor: it emits the `Defined` and
+    /// `Undefined` lifecycle events, and the domain is listed until it
+    /// is undefined. Should undefining fail, the throwaway domain may
+    /// be left defined under its `virt-validate-` prefixed name.
+    pub fn validate_domain_xml(
+        &self,
+        xml: &str,
+        flags: impl Into<DomainDefineFlags>,
+    ) -> Result<Vec<XmlIssue>, Error> {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+        let name = format!(
+            "virt-validate-{}-{}",
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        );
+        let xml = match xmlutil::anonymize_domain_xml(xml, &name) {
+            Ok(xml) => xml,
+            Err((line, message)) => return Ok(vec![XmlIssue { line, message }]),
+        };
+        let root = Element::parse(&xml)?;
+        if root.name != "domain" {
+            return Ok(vec![XmlIssue {
+                line: None,
+                message: format!("expected a <domain> root element, found <{}>", root.name),
+            }]);
+        }
+
+        let flags = flags.into();
+        if !flags.contains(DomainDefineFlags::VALIDATE) {
+            return Ok(Vec::new());
+        }
+        let dom = match Domain::define_xml_flags(self, &xml, flags) {
+            Ok(dom) => dom,
+            Err(e) => return XmlIssue::from_error(&e).ok_or(e),
+        };
+        // The NVRAM and TPM paths may point to files of other domains.
+        let ret = dom.undefine_full(&UndefineOptions {
+            remove_nvram: Some(false),
+            remove_tpm: Some(false),
+            ..Default::default()
+        });
+        if let Err(e) = ret {
+            // Drivers not knowing the flags to keep the NVRAM and TPM
+            // state reject them, while a plain undefine keeps it.
+            dom.undefine().map_err(|_| e)?;
+        }
+        Ok(Vec::new())
+    }
+
+    /// Launch a new guest domain from its XML description, passing
+    /// the open file descriptors `files` to its init process.
+    ///
+    /// Only the LXC driver supports this; see
+    /// [`Domain::create_with_files()`].
+    pub fn create_domain_xml_with_files(
+        &self,
+        xml: &str,
+        files: &[RawFd],
+        flags: impl Into<DomainCreateFlags>,
+    ) -> Result<Domain, Error> {
+        let xml_buf = CString::new(xml)?;
+        let mut files = files.to_vec();
+        let ptr = unsafe {
+            ffi!(sys::virDomainCreateXMLWithFiles(
+                self.as_ptr(),
+                xml_buf.as_ptr(),
+                files.len() as libc::c_uint,
+                files.as_mut_ptr(),
+                flags.into().bits() as libc::c_uint,
+            ))
+        };
+        if ptr.is_null() {
+            return Err(Error::last_error());
+        }
+        Ok(unsafe { Domain::from_ptr(ptr) })
+    }
+
     pub fn baseline_cpu(
         &self,
         xmlcpus: &[&str],
@@ -1161,17 +2580,17 @@ impl Connect {
         let mut xcpus: [*mut CString; 512] = [ptr::null_mut(); 512];
         let mut xcpus_buf: [*const libc::c_char; 512] = [ptr::null(); 512];
         for x in 0..xmlcpus.len() {
-            let mut buf = CString::new(xmlcpus[x]).unwrap();
+            let mut buf = CString::new(xmlcpus[x])?;
             xcpus[x] = &mut buf;
             xcpus_buf[x] = buf.as_ptr()
         }
         let ret = unsafe {
-            sys::virConnectBaselineCPU(
+            ffi!(sys::virConnectBaselineCPU(
                 self.as_ptr(),
                 xcpus_buf.as_mut_ptr(),
                 xmlcpus.len() as libc::c_uint,
                 flags as libc::c_uint,
-            )
+            ))
         };
         if ret.is_null() {
             return Err(Error::last_error());
@@ -1185,15 +2604,15 @@ impl Connect {
         spec: Option<&str>,
         flags: u32,
     ) -> Result<String, Error> {
-        let kind_buf = CString::new(kind).unwrap();
+        let kind_buf = CString::new(kind)?;
         let spec_buf = some_string_to_cstring!(spec);
         let n = unsafe {
-            sys::virConnectFindStoragePoolSources(
+            ffi!(sys::virConnectFindStoragePoolSources(
                 self.as_ptr(),
                 kind_buf.as_ptr(),
                 some_cstring_to_c_chars!(spec_buf),
                 flags as libc::c_uint,
-            )
+            ))
         };
         if n.is_null() {
             return Err(Error::last_error());
@@ -1212,12 +2631,12 @@ impl Connect {
     ) -> Result<Vec<u64>, Error> {
         let mut free_mems: Vec<libc::c_ulonglong> = Vec::with_capacity(max_cells as usize);
         let size: i32 = unsafe {
-            sys::virNodeGetCellsFreeMemory(
+            ffi!(sys::virNodeGetCellsFreeMemory(
                 self.as_ptr(),
                 free_mems.as_mut_ptr(),
                 start_cell as libc::c_int,
                 max_cells as libc::c_int,
-            )
+            ))
         };
         if size < 0 {
             return Err(Error::last_error());
@@ -1233,6 +2652,17 @@ impl Connect {
         Ok(array)
     }
 
+    /// Returns the free memory in bytes of each NUMA node of `nodes`,
+    /// which do not need to be consecutive.
+    pub fn get_nodes_free_memory(&self, nodes: &NodeSet) -> Result<Vec<(u32, u64)>, Error> {
+        let mut ret = Vec::with_capacity(nodes.len());
+        for (start, end) in nodes.ranges() {
+            let free = self.get_cells_free_memory(start as i32, (end - start + 1) as i32)?;
+            ret.extend((start..).zip(free));
+        }
+        Ok(ret)
+    }
+
     /// Connect.get_free_pages should be used to get information on free memory
     /// pages of size `pages` (in KiB) on individual `cell_count` NUMA nodes
     /// starting with `start_cell. Returned is a vector of free page counts for
@@ -1257,7 +2687,7 @@ impl Connect {
         let mut counts = vec![0; nentries];
 
         let size = unsafe {
-            sys::virNodeGetFreePages(
+            ffi!(sys::virNodeGetFreePages(
                 self.as_ptr(),
                 pages.len().try_into().unwrap(),
                 pages.as_ptr() as *mut _,
@@ -1265,7 +2695,7 @@ impl Connect {
                 cell_count,
                 counts.as_mut_ptr(),
                 flags,
-            )
+            ))
         };
         if size < 0 {
             return Err(Error::last_error());
@@ -1275,4 +2705,349 @@ impl Connect {
 
         Ok(counts)
     }
+
+    /// Adopts the externally launched QEMU process `pid` as a domain.
+    ///
+    /// The QEMU driver dropped support for this in libvirt 5.5.0,
+    /// which always reports an error.
+    #[cfg(feature = "qemu")]
+    pub fn domain_qemu_attach(&self, pid: u32, flags: u32) -> Result<Domain, Error> {
+        let ptr = unsafe {
+            ffi!(sys::virDomainQemuAttach(
+                self.as_ptr(),
+                pid as libc::c_uint,
+                flags as libc::c_uint
+            ))
+        };
+        if ptr.is_null() {
+            return Err(Error::last_error());
+        }
+        Ok(unsafe { Domain::from_ptr(ptr) })
+    }
+
+    /// Lists the domains passing extra arguments to QEMU through
+    /// `<qemu:commandline>` in their definition.
+    ///
+    /// The domains whose definition cannot be read, for instance
+    /// because they were undefined after being listed, are skipped.
+    ///
+    /// See [`Domain::get_qemu_commandline()`].
+    #[cfg(feature = "qemu")]
+    pub fn list_qemu_commandline_domains(
+        &self,
+        flags: impl Into<ConnectListAllDomainsFlags>,
+    ) -> Result<Vec<Domain>, Error> {
+        let mut array = Vec::new();
+        for dom in self.list_all_domains(flags)? {
+            match dom.get_qemu_commandline() {
+                Ok(args) if !args.is_empty() => array.push(dom),
+                _ => {}
+            }
+        }
+        Ok(array)
+    }
+
+    /// Registers `cb` to be called for each `BLOCK_THRESHOLD` event,
+    /// see [`Domain::set_block_threshold()`].
+    ///
+    /// * `dom` - only receive the events of this domain, or of all
+    ///   domains if `None`
+    ///
+    /// An event loop implementation must be registered, for instance
+    /// with [`sys::virEventRegisterDefaultImpl()`], and run for the
+    /// events to be delivered.
+    ///
+    /// Returns the callback identifier to pass to
+    /// [`domain_event_deregister_any()`].
+    ///
+    /// [`domain_event_deregister_any()`]: Connect::domain_event_deregister_any
+    pub fn domain_event_block_threshold_register<F>(
+        &self,
+        dom: Option<&Domain>,
+        cb: F,
+    ) -> Result<i32, Error>
+    where
+        F: 'static + FnMut(&Domain, &BlockThresholdEvent) + Send,
+    {
+        let cb: Box<Box<BlockThresholdEventCallback>> = Box::new(Box::new(cb));
+        let opaque = Box::into_raw(cb) as *mut libc::c_void;
+        let ret = unsafe {
+            // libvirt casts the generic callback back to the
+            // signature matching the event ID.
+            let generic = mem::transmute::<
+                extern "C" fn(
+                    sys::virConnectPtr,
+                    sys::virDomainPtr,
+                    *const libc::c_char,
+                    *const libc::c_char,
+                    libc::c_ulonglong,
+                    libc::c_ulonglong,
+                    *mut libc::c_void,
+                ),
+                unsafe extern "C" fn(sys::virConnectPtr, sys::virDomainPtr, *mut libc::c_void),
+            >(block_threshold_event_callback);
+            ffi!(sys::virConnectDomainEventRegisterAny(
+                self.as_ptr(),
+                dom.map_or(ptr::null_mut(), |d| d.as_ptr()),
+                sys::VIR_DOMAIN_EVENT_ID_BLOCK_THRESHOLD as libc::c_int,
+                Some(generic),
+                opaque,
+                Some(block_threshold_event_free),
+            ))
+        };
+        if ret == -1 {
+            // libvirt does not call the free callback on failure.
+            block_threshold_event_free(opaque);
+            return Err(Error::last_error());
+        }
+        Ok(ret)
+    }
+
+    /// Removes a callback registered with one of the
+    /// `domain_event_*_register()` methods.
+    pub fn domain_event_deregister_any(&self, callback_id: i32) -> Result<(), Error> {
+        let ret = unsafe {
+            ffi!(sys::virConnectDomainEventDeregisterAny(
+                self.as_ptr(),
+                callback_id as libc::c_int
+            ))
+        };
+        if ret == -1 {
+            return Err(Error::last_error());
+        }
+        Ok(())
+    }
+
+    /// Registers `cb` to be called each time the value of a secret
+    /// changes.
+    ///
+    /// * `secret` - only receive the events of this secret, or of all
+    ///   secrets if `None`
+    ///
+    /// An event loop implementation must be registered, for instance
+    /// with [`sys::virEventRegisterDefaultImpl()`], and run for the
+    /// events to be delivered.
+    ///
+    /// Returns the callback identifier to pass to
+    /// [`secret_event_deregister_any()`].
+    ///
+    /// [`secret_event_deregister_any()`]: Connect::secret_event_deregister_any
+    pub fn secret_event_value_changed_register<F>(
+        &self,
+        secret: Option<&Secret>,
+        cb: F,
+    ) -> Result<i32, Error>
+    where
+        F: 'static + FnMut(&Secret) + Send,
+    {
+        let cb: Box<Box<SecretEventCallback>> = Box::new(Box::new(cb));
+        let opaque = Box::into_raw(cb) as *mut libc::c_void;
+        let ret = unsafe {
+            ffi!(sys::virConnectSecretEventRegisterAny(
+                self.as_ptr(),
+                secret.map_or(ptr::null_mut(), |s| s.as_ptr()),
+                sys::VIR_SECRET_EVENT_ID_VALUE_CHANGED as libc::c_int,
+                Some(secret_event_callback),
+                opaque,
+                Some(secret_event_free),
+            ))
+        };
+        if ret == -1 {
+            // libvirt does not call the free callback on failure.
+            secret_event_free(opaque);
+            return Err(Error::last_error());
+        }
+        Ok(ret)
+    }
+
+    /// Removes a callback registered with one of the
+    /// `secret_event_*_register()` methods.
+    pub fn secret_event_deregister_any(&self, callback_id: i32) -> Result<(), Error> {
+        let ret = unsafe {
+            ffi!(sys::virConnectSecretEventDeregisterAny(
+                self.as_ptr(),
+                callback_id as libc::c_int
+            ))
+        };
+        if ret == -1 {
+            return Err(Error::last_error());
+        }
+        Ok(())
+    }
+
+    /// Registers `cb` to be called when a node device is created,
+    /// deleted, defined or undefined.
+    ///
+    /// * `dev` - only receive the events of this device, or of all
+    ///   devices if `None`
+    ///
+    /// An event loop implementation must be registered, for instance
+    /// with [`sys::virEventRegisterDefaultImpl()`], and run for the
+    /// events to be delivered.
+    ///
+    /// Returns the callback identifier to pass to
+    /// [`node_device_event_deregister_any()`].
+    ///
+    /// [`node_device_event_deregister_any()`]: Connect::node_device_event_deregister_any
+    pub fn node_device_event_lifecycle_register<F>(
+        &self,
+        dev: Option<&NodeDevice>,
+        cb: F,
+    ) -> Result<i32, Error>
+    where
+        F: 'static + FnMut(&NodeDevice, NodeDeviceLifecycleEvent) + Send,
+    {
+        let cb: Box<Box<NodeDeviceLifecycleEventCallback>> = Box::new(Box::new(cb));
+        let opaque = Box::into_raw(cb) as *mut libc::c_void;
+        let ret = unsafe {
+            // libvirt casts the generic callback back to the
+            // signature matching the event ID.
+            let generic = mem::transmute::<
+                extern "C" fn(
+                    sys::virConnectPtr,
+                    sys::virNodeDevicePtr,
+                    libc::c_int,
+                    libc::c_int,
+                    *mut libc::c_void,
+                ),
+                unsafe extern "C" fn(sys::virConnectPtr, sys::virNodeDevicePtr, *mut libc::c_void),
+            >(node_device_lifecycle_event_callback);
+            ffi!(sys::virConnectNodeDeviceEventRegisterAny(
+                self.as_ptr(),
+                dev.map_or(ptr::null_mut(), |d| d.as_ptr()),
+                sys::VIR_NODE_DEVICE_EVENT_ID_LIFECYCLE as libc::c_int,
+                Some(generic),
+                opaque,
+                Some(node_device_lifecycle_event_free),
+            ))
+        };
+        if ret == -1 {
+            // libvirt does not call the free callback on failure.
+            node_device_lifecycle_event_free(opaque);
+            return Err(Error::last_error());
+        }
+        Ok(ret)
+    }
+
+    /// Registers `cb` to be called when the definition of a node
+    /// device changes.
+    ///
+    /// * `dev` - only receive the events of this device, or of all
+    ///   devices if `None`
+    ///
+    /// Returns the callback identifier to pass to
+    /// [`node_device_event_deregister_any()`].
+    ///
+    /// [`node_device_event_deregister_any()`]: Connect::node_device_event_deregister_any
+    pub fn node_device_event_update_register<F>(
+        &self,
+        dev: Option<&NodeDevice>,
+        cb: F,
+    ) -> Result<i32, Error>
+    where
+        F: 'static + FnMut(&NodeDevice) + Send,
+    {
+        let cb: Box<Box<NodeDeviceEventCallback>> = Box::new(Box::new(cb));
+        let opaque = Box::into_raw(cb) as *mut libc::c_void;
+        let ret = unsafe {
+            ffi!(sys::virConnectNodeDeviceEventRegisterAny(
+                self.as_ptr(),
+                dev.map_or(ptr::null_mut(), |d| d.as_ptr()),
+                sys::VIR_NODE_DEVICE_EVENT_ID_UPDATE as libc::c_int,
+                Some(node_device_event_callback),
+                opaque,
+                Some(node_device_event_free),
+            ))
+        };
+        if ret == -1 {
+            // libvirt does not call the free callback on failure.
+            node_device_event_free(opaque);
+            return Err(Error::last_error());
+        }
+        Ok(ret)
+    }
+
+    /// Removes a callback registered with one of the
+    /// `node_device_event_*_register()` methods.
+    pub fn node_device_event_deregister_any(&self, callback_id: i32) -> Result<(), Error> {
+        let ret = unsafe {
+            ffi!(sys::virConnectNodeDeviceEventDeregisterAny(
+                self.as_ptr(),
+                callback_id as libc::c_int
+            ))
+        };
+        if ret == -1 {
+            return Err(Error::last_error());
+        }
+        Ok(())
+    }
+
+    /// Registers `cb` to be called for each QEMU monitor event.
+    ///
+    /// * `dom` - only receive the events of this domain, or of all
+    ///   domains if `None`
+    /// * `event` - only receive the events with this name, or all the
+    ///   events if `None`
+    /// * `cb` - called with the domain, the event name, the time of
+    ///   the event in seconds and microseconds since the Epoch and
+    ///   the JSON details of the event, if any
+    ///
+    /// An event loop implementation must be registered, for instance
+    /// with [`sys::virEventRegisterDefaultImpl()`], and run for the
+    /// events to be delivered.
+    ///
+    /// Returns the callback identifier to pass to
+    /// [`domain_qemu_monitor_event_deregister()`].
+    ///
+    /// [`domain_qemu_monitor_event_deregister()`]: Connect::domain_qemu_monitor_event_deregister
+    #[cfg(feature = "qemu")]
+    pub fn domain_qemu_monitor_event_register<F>(
+        &self,
+        dom: Option<&Domain>,
+        event: Option<&str>,
+        cb: F,
+        flags: sys::virConnectDomainQemuMonitorEventRegisterFlags,
+    ) -> Result<i32, Error>
+    where
+        F: 'static + FnMut(&Domain, &str, i64, u32, Option<&str>) + Send,
+    {
+        let event_buf = some_string_to_cstring!(event);
+        let cb: Box<Box<QemuMonitorEventCallback>> = Box::new(Box::new(cb));
+        let opaque = Box::into_raw(cb) as *mut libc::c_void;
+        let ret = unsafe {
+            ffi!(sys::virConnectDomainQemuMonitorEventRegister(
+                self.as_ptr(),
+                dom.map_or(ptr::null_mut(), |d| d.as_ptr()),
+                some_cstring_to_c_chars!(event_buf),
+                Some(qemu_monitor_event_callback),
+                opaque,
+                Some(qemu_monitor_event_free),
+                flags as libc::c_uint,
+            ))
+        };
+        if ret == -1 {
+            // libvirt does not call the free callback on failure.
+            qemu_monitor_event_free(opaque);
+            return Err(Error::last_error());
+        }
+        Ok(ret)
+    }
+
+    /// Removes a callback registered with
+    /// [`domain_qemu_monitor_event_register()`].
+    ///
+    /// [`domain_qemu_monitor_event_register()`]: Connect::domain_qemu_monitor_event_register
+    #[cfg(feature = "qemu")]
+    pub fn domain_qemu_monitor_event_deregister(&self, callback_id: i32) -> Result<(), Error> {
+        let ret = unsafe {
+            ffi!(sys::virConnectDomainQemuMonitorEventDeregister(
+                self.as_ptr(),
+                callback_id as libc::c_int,
+            ))
+        };
+        if ret == -1 {
+            return Err(Error::last_error());
+        }
+        Ok(())
+    }
 }
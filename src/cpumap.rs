@@ -0,0 +1,109 @@
+/*
+ * This library is free software; you can redistribute it and/or
+ * modify it under the terms of the GNU Lesser General Public
+ * License as published by the Free Software Foundation; either
+ * version 2.1 of the License, or (at your option) any later version.
+ *
+ * This library is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+ * Lesser General Public License for more details.
+ *
+ * You should have received a copy of the GNU Lesser General Public
+ * License along with this library.  If not, see
+ * <https://www.gnu.org/licenses/>.
+ *
+ * Sahid Orentino Ferdjaoui <sahid.ferdjaoui@redhat.com>
+ */
+
+use std::iter::FromIterator;
+
+/// A bitmap of host CPUs, as used by the pinning APIs.
+///
+/// CPU `n` is represented by bit `n % 8` of byte `n / 8`.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct CpuMap {
+    bytes: Vec<u8>,
+}
+
+impl CpuMap {
+    /// Returns the number of bytes needed to hold `max_cpus` CPUs.
+    ///
+    /// This is the equivalent of the `VIR_CPU_MAPLEN()` C macro.
+    // usize::div_ceil() is not available on the oldest supported
+    // toolchain.
+    #[allow(unknown_lints, clippy::manual_div_ceil)]
+    pub fn maplen(max_cpus: u32) -> usize {
+        (max_cpus as usize + 7) / 8
+    }
+
+    /// Creates an empty map able to hold `max_cpus` CPUs.
+    pub fn new(max_cpus: u32) -> CpuMap {
+        CpuMap {
+            bytes: vec![0; CpuMap::maplen(max_cpus)],
+        }
+    }
+
+    /// Creates a map from its libvirt byte encoding.
+    pub fn from_bytes(bytes: &[u8]) -> CpuMap {
+        CpuMap {
+            bytes: bytes.to_vec(),
+        }
+    }
+
+    /// Returns the libvirt byte encoding of the map.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Returns the number of CPUs the map can hold without growing.
+    pub fn max_cpus(&self) -> u32 {
+        self.bytes.len() as u32 * 8
+    }
+
+    /// Returns `true` if no CPU is part of the map.
+    pub fn is_empty(&self) -> bool {
+        self.bytes.iter().all(|&b| b == 0)
+    }
+
+    /// Returns `true` if `cpu` is part of the map.
+    #[allow(unknown_lints, clippy::unnecessary_map_or)]
+    pub fn is_set(&self, cpu: u32) -> bool {
+        self.bytes
+            .get(cpu as usize / 8)
+            .map_or(false, |b| b & (1 << (cpu % 8)) != 0)
+    }
+
+    /// Adds `cpu` to the map, growing it if needed.
+    pub fn set(&mut self, cpu: u32) {
+        let idx = cpu as usize / 8;
+        if idx >= self.bytes.len() {
+            self.bytes.resize(idx + 1, 0);
+        }
+        self.bytes[idx] |= 1 << (cpu % 8);
+    }
+
+    /// Removes `cpu` from the map.
+    pub fn clear(&mut self, cpu: u32) {
+        if let Some(b) = self.bytes.get_mut(cpu as usize / 8) {
+            *b &= !(1 << (cpu % 8));
+        }
+    }
+
+    /// Returns an iterator over the CPUs part of the map, in
+    /// increasing order.
+    pub fn iter(&self) -> impl Iterator<Item = u32> + '_ {
+        (0..self.max_cpus()).filter(move |&cpu| self.is_set(cpu))
+    }
+}
+
+impl FromIterator<u32> for CpuMap {
+    /// Creates a map from a list of CPUs, sized after the highest one.
+    fn from_iter<I: IntoIterator<Item = u32>>(iter: I) -> CpuMap {
+        let mut map = CpuMap::default();
+        for cpu in iter {
+            map.set(cpu);
+        }
+        map
+    }
+}
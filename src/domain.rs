@@ -16,20 +16,282 @@
  * Sahid Orentino Ferdjaoui <sahid.ferdjaoui@redhat.com>
  */
 
+use std::collections::HashMap;
+use std::convert::TryFrom;
 use std::ffi::CString;
+use std::fs::File;
+use std::io::Write;
+use std::net::IpAddr;
+use std::os::unix::io::{AsRawFd, BorrowedFd, FromRawFd, OwnedFd, RawFd};
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use std::{mem, ptr, str};
 
 use uuid::Uuid;
-
-use crate::connect::Connect;
-use crate::domain_snapshot::DomainSnapshot;
-use crate::error::Error;
+use xml::escape::escape_str_attribute;
+
+use crate::connect::{Connect, SuspendTarget};
+use crate::cpumap::CpuMap;
+use crate::domain_snapshot::{DomainSnapshot, SnapshotListFlags, SnapshotNode};
+use crate::error::{report_drop_error, Error, ErrorNumber};
+pub use crate::flags::DomainModificationImpact;
+use crate::nodeset::NodeSet;
+use crate::storage_pool::StoragePool;
+use crate::storage_vol::StorageVol;
 use crate::stream::Stream;
-use crate::typedparams::{from_params, to_params};
-use crate::util::c_ulong_to_u64;
+use crate::typedparams::{from_params, to_params, TypedParamList, TypedParamsMap, TypedValue};
+use crate::util::{c_ulong_to_u64, impl_enum, impl_flags, impl_identity};
+use crate::xmlutil::{self, Element};
 use crate::{param_field_in, param_field_out};
 
+impl_flags! {
+    /// Flags used when starting a domain.
+    ///
+    /// See <https://libvirt.org/html/libvirt-libvirt-domain.html#virDomainCreateFlags>
+    struct DomainCreateFlags: sys::virDomainCreateFlags {
+        /// Launch the domain in the paused state.
+        const PAUSED = sys::VIR_DOMAIN_START_PAUSED;
+        /// Destroy the domain when the connection is closed.
+        const AUTODESTROY = sys::VIR_DOMAIN_START_AUTODESTROY;
+        /// Avoid the file system cache when restoring a managed save image.
+        const BYPASS_CACHE = sys::VIR_DOMAIN_START_BYPASS_CACHE;
+        /// Discard any managed save image and boot from scratch.
+        const FORCE_BOOT = sys::VIR_DOMAIN_START_FORCE_BOOT;
+        /// Validate the XML document against the schema.
+        const VALIDATE = sys::VIR_DOMAIN_START_VALIDATE;
+        /// Re-initialize the NVRAM from its pristine template.
+        const RESET_NVRAM = sys::VIR_DOMAIN_START_RESET_NVRAM;
+    }
+}
+
+impl_flags! {
+    /// Flags used when dumping the XML description of a domain.
+    ///
+    /// See <https://libvirt.org/html/libvirt-libvirt-domain.html#virDomainXMLFlags>
+    struct DomainXmlFlags: sys::virDomainXMLFlags {
+        /// Include the security sensitive information, such as the
+        /// VNC passwords.
+        const SECURE = sys::VIR_DOMAIN_XML_SECURE;
+        /// Dump the persistent configuration instead of the live one.
+        const INACTIVE = sys::VIR_DOMAIN_XML_INACTIVE;
+        /// Update the guest CPU requirements to match the host CPU.
+        const UPDATE_CPU = sys::VIR_DOMAIN_XML_UPDATE_CPU;
+        /// Dump an XML description suitable for migration.
+        const MIGRATABLE = sys::VIR_DOMAIN_XML_MIGRATABLE;
+    }
+}
+
+impl_flags! {
+    /// Flags used when defining a domain.
+    ///
+    /// See <https://libvirt.org/html/libvirt-libvirt-domain.html#virDomainDefineFlags>
+    struct DomainDefineFlags: sys::virDomainDefineFlags {
+        /// Validate the XML document against the schema.
+        const VALIDATE = sys::VIR_DOMAIN_DEFINE_VALIDATE;
+    }
+}
+
+impl_flags! {
+    /// Flags used when undefining a domain.
+    ///
+    /// See <https://libvirt.org/html/libvirt-libvirt-domain.html#virDomainUndefineFlagsValues>
+    struct DomainUndefineFlags: sys::virDomainUndefineFlagsValues {
+        /// Also remove any managed save image.
+        const MANAGED_SAVE = sys::VIR_DOMAIN_UNDEFINE_MANAGED_SAVE;
+        /// Also remove any snapshot metadata.
+        const SNAPSHOTS_METADATA = sys::VIR_DOMAIN_UNDEFINE_SNAPSHOTS_METADATA;
+        /// Also remove any NVRAM file.
+        const NVRAM = sys::VIR_DOMAIN_UNDEFINE_NVRAM;
+        /// Keep the NVRAM file.
+        const KEEP_NVRAM = sys::VIR_DOMAIN_UNDEFINE_KEEP_NVRAM;
+        /// Also remove any checkpoint metadata.
+        const CHECKPOINTS_METADATA = sys::VIR_DOMAIN_UNDEFINE_CHECKPOINTS_METADATA;
+        /// Also remove any TPM state.
+        const TPM = sys::VIR_DOMAIN_UNDEFINE_TPM;
+        /// Keep the TPM state.
+        const KEEP_TPM = sys::VIR_DOMAIN_UNDEFINE_KEEP_TPM;
+    }
+}
+
+/// Options used by [`Domain::undefine_full()`].
+///
+/// `remove_nvram` and `remove_tpm` remove the state when `Some(true)`,
+/// keep it when `Some(false)` and leave the choice to the driver when
+/// `None`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct UndefineOptions {
+    /// Remove the managed save image.
+    pub managed_save: bool,
+    /// Remove the snapshot metadata.
+    pub snapshots_metadata: bool,
+    /// Remove the checkpoint metadata.
+    pub checkpoints_metadata: bool,
+    /// Remove or keep the NVRAM file.
+    pub remove_nvram: Option<bool>,
+    /// Remove or keep the TPM state.
+    pub remove_tpm: Option<bool>,
+}
+
+impl UndefineOptions {
+    /// Returns options removing every piece of state attached to the
+    /// domain.
+    pub fn all() -> UndefineOptions {
+        UndefineOptions {
+            managed_save: true,
+            snapshots_metadata: true,
+            checkpoints_metadata: true,
+            remove_nvram: Some(true),
+            remove_tpm: Some(true),
+        }
+    }
+
+    /// Returns the flags matching these options.
+    pub fn flags(&self) -> DomainUndefineFlags {
+        let mut flags = DomainUndefineFlags::empty();
+        if self.managed_save {
+            flags |= DomainUndefineFlags::MANAGED_SAVE;
+        }
+        if self.snapshots_metadata {
+            flags |= DomainUndefineFlags::SNAPSHOTS_METADATA;
+        }
+        if self.checkpoints_metadata {
+            flags |= DomainUndefineFlags::CHECKPOINTS_METADATA;
+        }
+        match self.remove_nvram {
+            Some(true) => flags |= DomainUndefineFlags::NVRAM,
+            Some(false) => flags |= DomainUndefineFlags::KEEP_NVRAM,
+            None => {}
+        }
+        match self.remove_tpm {
+            Some(true) => flags |= DomainUndefineFlags::TPM,
+            Some(false) => flags |= DomainUndefineFlags::KEEP_TPM,
+            None => {}
+        }
+        flags
+    }
+}
+
+impl_flags! {
+    /// Flags used when attaching, detaching or updating a device.
+    ///
+    /// See <https://libvirt.org/html/libvirt-libvirt-domain.html#virDomainDeviceModifyFlags>
+    struct DomainDeviceModifyFlags: sys::virDomainDeviceModifyFlags {
+        /// Affect the current domain state.
+        const CURRENT = sys::VIR_DOMAIN_DEVICE_MODIFY_CURRENT;
+        /// Affect the running domain only.
+        const LIVE = sys::VIR_DOMAIN_DEVICE_MODIFY_LIVE;
+        /// Affect the persistent configuration only.
+        const CONFIG = sys::VIR_DOMAIN_DEVICE_MODIFY_CONFIG;
+        /// Forcibly modify the device, e.g. eject a locked CD-ROM.
+        const FORCE = sys::VIR_DOMAIN_DEVICE_MODIFY_FORCE;
+    }
+}
+
+impl_flags! {
+    /// Flags used when changing the memory of a domain.
+    ///
+    /// See <https://libvirt.org/html/libvirt-libvirt-domain.html#virDomainMemoryModFlags>
+    struct DomainMemoryModFlags: sys::virDomainMemoryModFlags {
+        /// Affect the current domain state.
+        const CURRENT = sys::VIR_DOMAIN_MEM_CURRENT;
+        /// Affect the running domain only.
+        const LIVE = sys::VIR_DOMAIN_MEM_LIVE;
+        /// Affect the persistent configuration only.
+        const CONFIG = sys::VIR_DOMAIN_MEM_CONFIG;
+        /// Change the maximum memory instead of the current allocation.
+        const MAXIMUM = sys::VIR_DOMAIN_MEM_MAXIMUM;
+    }
+}
+
+impl_flags! {
+    /// Flags used when querying or changing the vCPUs of a domain.
+    ///
+    /// See <https://libvirt.org/html/libvirt-libvirt-domain.html#virDomainVcpuFlags>
+    struct DomainVcpuFlags: sys::virDomainVcpuFlags {
+        /// Affect the current domain state.
+        const CURRENT = sys::VIR_DOMAIN_VCPU_CURRENT;
+        /// Affect the running domain only.
+        const LIVE = sys::VIR_DOMAIN_VCPU_LIVE;
+        /// Affect the persistent configuration only.
+        const CONFIG = sys::VIR_DOMAIN_VCPU_CONFIG;
+        /// Use the maximum number of vCPUs instead of the current one.
+        const MAXIMUM = sys::VIR_DOMAIN_VCPU_MAXIMUM;
+        /// Change the vCPUs through the guest agent.
+        const GUEST = sys::VIR_DOMAIN_VCPU_GUEST;
+        /// Make the added vCPUs hot-unpluggable.
+        const HOTPLUGGABLE = sys::VIR_DOMAIN_VCPU_HOTPLUGGABLE;
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+/// Which value a memory or vCPU change applies to.
+///
+/// Converts into [`DomainMemoryModFlags`] for
+/// [`Domain::set_memory_flags()`] and into [`DomainVcpuFlags`] for
+/// [`Domain::set_vcpus_flags()`], so the maximum is never changed
+/// without the persistent configuration being selected too.
+pub enum MemoryModification {
+    /// The current domain state, live if running, persistent otherwise.
+    Current,
+    /// The running domain only.
+    Live,
+    /// The persistent configuration only.
+    Config,
+    /// Both the running domain and its persistent configuration.
+    Both,
+    /// The maximum in the persistent configuration, which only takes
+    /// effect on the next boot.
+    Maximum,
+}
+
+impl From<MemoryModification> for DomainMemoryModFlags {
+    fn from(modification: MemoryModification) -> DomainMemoryModFlags {
+        match modification {
+            MemoryModification::Current => DomainMemoryModFlags::CURRENT,
+            MemoryModification::Live => DomainMemoryModFlags::LIVE,
+            MemoryModification::Config => DomainMemoryModFlags::CONFIG,
+            MemoryModification::Both => DomainMemoryModFlags::LIVE | DomainMemoryModFlags::CONFIG,
+            MemoryModification::Maximum => {
+                DomainMemoryModFlags::MAXIMUM | DomainMemoryModFlags::CONFIG
+            }
+        }
+    }
+}
+
+impl From<MemoryModification> for DomainVcpuFlags {
+    fn from(modification: MemoryModification) -> DomainVcpuFlags {
+        match modification {
+            MemoryModification::Current => DomainVcpuFlags::CURRENT,
+            MemoryModification::Live => DomainVcpuFlags::LIVE,
+            MemoryModification::Config => DomainVcpuFlags::CONFIG,
+            MemoryModification::Both => DomainVcpuFlags::LIVE | DomainVcpuFlags::CONFIG,
+            MemoryModification::Maximum => DomainVcpuFlags::MAXIMUM | DomainVcpuFlags::CONFIG,
+        }
+    }
+}
+
+impl From<DomainModificationImpact> for DomainDeviceModifyFlags {
+    fn from(impact: DomainModificationImpact) -> DomainDeviceModifyFlags {
+        DomainDeviceModifyFlags::from_bits(impact.bits())
+    }
+}
+
+impl From<DomainModificationImpact> for DomainMemoryModFlags {
+    fn from(impact: DomainModificationImpact) -> DomainMemoryModFlags {
+        DomainMemoryModFlags::from_bits(impact.bits())
+    }
+}
+
+impl From<DomainModificationImpact> for DomainVcpuFlags {
+    fn from(impact: DomainModificationImpact) -> DomainVcpuFlags {
+        DomainVcpuFlags::from_bits(impact.bits())
+    }
+}
+
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DomainInfo {
     /// The running state, one of virDomainState.
     pub state: sys::virDomainState,
@@ -58,12 +320,214 @@ impl DomainInfo {
     }
 }
 
+impl std::fmt::Display for DomainInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let state = match self.state {
+            sys::VIR_DOMAIN_NOSTATE => "no state",
+            sys::VIR_DOMAIN_RUNNING => "running",
+            sys::VIR_DOMAIN_BLOCKED => "blocked",
+            sys::VIR_DOMAIN_PAUSED => "paused",
+            sys::VIR_DOMAIN_SHUTDOWN => "shutting down",
+            sys::VIR_DOMAIN_SHUTOFF => "shut off",
+            sys::VIR_DOMAIN_CRASHED => "crashed",
+            sys::VIR_DOMAIN_PMSUSPENDED => "pmsuspended",
+            _ => "unknown",
+        };
+        write!(
+            f,
+            "state: {}, max memory: {} KiB, used memory: {} KiB, vCPUs: {}, CPU time: {} ns",
+            state, self.max_mem, self.memory, self.nr_virt_cpu, self.cpu_time
+        )
+    }
+}
+
+impl_flags! {
+    /// The groups of statistics to collect for each domain; an empty set
+    /// collects all the groups supported by the hypervisor.
+    ///
+    /// See <https://libvirt.org/html/libvirt-libvirt-domain.html#virDomainStatsTypes>
+    struct DomainStatsGroups: sys::virDomainStatsTypes {
+        /// The state of the domain.
+        const STATE = sys::VIR_DOMAIN_STATS_STATE;
+        /// The total CPU usage.
+        const CPU_TOTAL = sys::VIR_DOMAIN_STATS_CPU_TOTAL;
+        /// The balloon device.
+        const BALLOON = sys::VIR_DOMAIN_STATS_BALLOON;
+        /// The usage of each vCPU.
+        const VCPU = sys::VIR_DOMAIN_STATS_VCPU;
+        /// The network interfaces.
+        const INTERFACE = sys::VIR_DOMAIN_STATS_INTERFACE;
+        /// The block devices.
+        const BLOCK = sys::VIR_DOMAIN_STATS_BLOCK;
+        /// The perf events.
+        const PERF = sys::VIR_DOMAIN_STATS_PERF;
+        /// The IOThreads.
+        const IOTHREAD = sys::VIR_DOMAIN_STATS_IOTHREAD;
+        /// The memory bandwidth.
+        const MEMORY = sys::VIR_DOMAIN_STATS_MEMORY;
+        /// The memory dirty rate.
+        const DIRTYRATE = sys::VIR_DOMAIN_STATS_DIRTYRATE;
+        /// The hypervisor specific statistics.
+        const VM = sys::VIR_DOMAIN_STATS_VM;
+    }
+}
+
 pub struct DomainStatsRecord {
-    // TODO(sahid): needs to be implemented
     pub ptr: sys::virDomainStatsRecordPtr,
 }
 
+impl DomainStatsRecord {
+    /// Returns the domain the statistics belong to.
+    pub fn domain(&self) -> Result<Domain, Error> {
+        let dom = unsafe { (*self.ptr).dom };
+        if unsafe { ffi!(sys::virDomainRef(dom)) } == -1 {
+            return Err(Error::last_error());
+        }
+        Ok(unsafe { Domain::from_ptr(dom) })
+    }
+
+    /// Returns a copy of the raw statistics; strings still point into
+    /// the record.
+    pub fn params(&self) -> Vec<sys::virTypedParameter> {
+        let (params, nparams) = unsafe { ((*self.ptr).params, (*self.ptr).nparams) };
+        if params.is_null() {
+            return Vec::new();
+        }
+        unsafe { std::slice::from_raw_parts(params, nparams as usize) }.to_vec()
+    }
+
+    /// Returns the dirty rate statistics, collected with
+    /// [`DomainStatsGroups::DIRTYRATE`].
+    pub fn dirty_rate(&self) -> DirtyRateStats {
+        DirtyRateStats::from_vec(self.params())
+    }
+}
+
+impl Drop for DomainStatsRecord {
+    /// Releases the reference on the domain and the statistics held
+    /// by the record.
+    fn drop(&mut self) {
+        if self.ptr.is_null() {
+            return;
+        }
+        unsafe {
+            let dom = (*self.ptr).dom;
+            if !dom.is_null() && ffi!(sys::virDomainFree(dom)) == -1 {
+                report_drop_error("DomainStatsRecord", Error::last_error());
+            }
+            ffi!(sys::virTypedParamsFree(
+                (*self.ptr).params,
+                (*self.ptr).nparams
+            ));
+            libc::free(self.ptr as *mut libc::c_void);
+        }
+        self.ptr = ptr::null_mut();
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+/// The state of a dirty rate measurement.
+///
+/// See <https://libvirt.org/html/libvirt-libvirt-domain.html#virDomainDirtyRateStatus>
+pub enum DirtyRateStatus {
+    /// No measurement was started.
+    Unstarted,
+    /// A measurement is in progress.
+    Measuring,
+    /// The last measurement completed.
+    Measured,
+}
+
+impl_enum! {
+    enum: DirtyRateStatus,
+    raw: sys::virDomainDirtyRateStatus,
+    match: {
+        sys::VIR_DOMAIN_DIRTYRATE_UNSTARTED => DirtyRateStatus::Unstarted,
+        sys::VIR_DOMAIN_DIRTYRATE_MEASURING => DirtyRateStatus::Measuring,
+        sys::VIR_DOMAIN_DIRTYRATE_MEASURED => DirtyRateStatus::Measured,
+        _ => DirtyRateStatus::Unstarted,
+    }
+}
+
+impl_flags! {
+    /// How the dirty rate of the memory of a domain is measured; page
+    /// sampling is used when empty.
+    ///
+    /// See <https://libvirt.org/html/libvirt-libvirt-domain.html#virDomainDirtyRateCalcFlags>
+    struct DirtyRateCalcFlags: sys::virDomainDirtyRateCalcFlags {
+        /// Use the dirty bitmap of the memory.
+        const DIRTY_BITMAP = sys::VIR_DOMAIN_DIRTYRATE_MODE_DIRTY_BITMAP;
+        /// Use the dirty ring of the memory.
+        const DIRTY_RING = sys::VIR_DOMAIN_DIRTYRATE_MODE_DIRTY_RING;
+    }
+}
+
+// The names of the dirty rate statistics, which libvirt does not
+// export as constants.
+const DIRTYRATE_CALC_STATUS: &[u8] = b"dirtyrate.calc_status\0";
+const DIRTYRATE_CALC_START_TIME: &[u8] = b"dirtyrate.calc_start_time\0";
+const DIRTYRATE_CALC_PERIOD: &[u8] = b"dirtyrate.calc_period\0";
+const DIRTYRATE_MEGABYTES_PER_SECOND: &[u8] = b"dirtyrate.megabytes_per_second\0";
+const DIRTYRATE_CALC_MODE: &[u8] = b"dirtyrate.calc_mode\0";
+
+macro_rules! dirty_rate_stats_fields {
+    ($dir:ident, $var:ident, $status:ident) => {
+        vec![
+            $dir!(DIRTYRATE_CALC_STATUS, Int32, $status),
+            $dir!(DIRTYRATE_CALC_START_TIME, Int64, $var.start_time),
+            $dir!(DIRTYRATE_CALC_PERIOD, Int32, $var.period),
+            $dir!(
+                DIRTYRATE_MEGABYTES_PER_SECOND,
+                Int64,
+                $var.megabytes_per_second
+            ),
+            $dir!(DIRTYRATE_CALC_MODE, String, $var.mode),
+        ]
+    };
+}
+
+/// The memory dirty rate of a domain, measured by
+/// [`Domain::start_dirty_rate_calc()`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DirtyRateStats {
+    pub status: Option<DirtyRateStatus>,
+    /// When the measurement started.
+    pub start_time: Option<i64>,
+    /// How long the measurement lasted, in seconds.
+    pub period: Option<i32>,
+    /// The rate at which the memory is dirtied, in MiB/s.
+    pub megabytes_per_second: Option<i64>,
+    /// The measurement mode, e.g. `page-sampling`.
+    pub mode: Option<String>,
+    /// The rate of each vCPU, by vCPU ID, with the dirty ring mode.
+    pub vcpu_megabytes_per_second: Vec<(u32, i64)>,
+}
+
+impl DirtyRateStats {
+    pub fn from_vec(vec: Vec<sys::virTypedParameter>) -> DirtyRateStats {
+        let mut ret = DirtyRateStats::default();
+        let mut status: Option<i32> = None;
+        for param in &vec {
+            let name = unsafe { c_chars_to_string!(param.field.as_ptr(), nofree) };
+            let vcpu = name
+                .strip_prefix("dirtyrate.vcpu.")
+                .and_then(|n| n.strip_suffix(".megabytes_per_second"))
+                .and_then(|n| n.parse::<u32>().ok());
+            if let (Some(vcpu), sys::VIR_TYPED_PARAM_LLONG) = (vcpu, param.type_ as u32) {
+                ret.vcpu_megabytes_per_second
+                    .push((vcpu, unsafe { param.value.l }));
+            }
+        }
+        let fields = dirty_rate_stats_fields!(param_field_in, ret, status);
+        from_params(&vec, fields);
+        ret.status = status.map(|s| DirtyRateStatus::from_raw(s as sys::virDomainDirtyRateStatus));
+        ret.vcpu_megabytes_per_second.sort_unstable();
+        ret
+    }
+}
+
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct BlockInfo {
     /// Logical size in bytes of the image (how much storage the guest
     /// will see).
@@ -76,6 +540,16 @@ pub struct BlockInfo {
     pub physical: u64,
 }
 
+impl std::fmt::Display for BlockInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "capacity: {} B, allocation: {} B, physical: {} B",
+            self.capacity, self.allocation, self.physical
+        )
+    }
+}
+
 impl BlockInfo {
     /// # Safety
     ///
@@ -89,25 +563,326 @@ impl BlockInfo {
     }
 }
 
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+/// The kind of a block job.
+///
+/// See <https://libvirt.org/html/libvirt-libvirt-domain.html#virDomainBlockJobType>
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BlockJobType {
+    Unknown,
+    /// Copying data from the backing chain into the active image.
+    Pull,
+    /// Copying the disk to a new destination.
+    Copy,
+    /// Committing an inactive layer into its backing file.
+    Commit,
+    /// Committing the active layer into its backing file.
+    ActiveCommit,
+    /// Running a backup job.
+    Backup,
+}
+
+impl_enum! {
+    enum: BlockJobType,
+    raw: sys::virDomainBlockJobType,
+    match: {
+        sys::VIR_DOMAIN_BLOCK_JOB_TYPE_UNKNOWN => BlockJobType::Unknown,
+        sys::VIR_DOMAIN_BLOCK_JOB_TYPE_PULL => BlockJobType::Pull,
+        sys::VIR_DOMAIN_BLOCK_JOB_TYPE_COPY => BlockJobType::Copy,
+        sys::VIR_DOMAIN_BLOCK_JOB_TYPE_COMMIT => BlockJobType::Commit,
+        sys::VIR_DOMAIN_BLOCK_JOB_TYPE_ACTIVE_COMMIT => BlockJobType::ActiveCommit,
+        sys::VIR_DOMAIN_BLOCK_JOB_TYPE_BACKUP => BlockJobType::Backup,
+        _ => BlockJobType::Unknown,
+    }
+}
+
+impl_flags! {
+    /// Flags used when querying a block job.
+    ///
+    /// See <https://libvirt.org/html/libvirt-libvirt-domain.html#virDomainBlockJobInfoFlags>
+    struct BlockJobInfoFlags: sys::virDomainBlockJobInfoFlags {
+        /// Report the bandwidth in bytes/s instead of MiB/s.
+        const BANDWIDTH_BYTES = sys::VIR_DOMAIN_BLOCK_JOB_INFO_BANDWIDTH_BYTES;
+    }
+}
+
+impl_flags! {
+    /// Flags used when changing the bandwidth of a block job.
+    ///
+    /// See <https://libvirt.org/html/libvirt-libvirt-domain.html#virDomainBlockJobSetSpeedFlags>
+    struct BlockJobSetSpeedFlags: sys::virDomainBlockJobSetSpeedFlags {
+        /// The bandwidth is given in bytes/s instead of MiB/s.
+        const BANDWIDTH_BYTES = sys::VIR_DOMAIN_BLOCK_JOB_SPEED_BANDWIDTH_BYTES;
+    }
+}
+
+impl_flags! {
+    /// Flags used when aborting a block job.
+    ///
+    /// See <https://libvirt.org/html/libvirt-libvirt-domain.html#virDomainBlockJobAbortFlags>
+    struct BlockJobAbortFlags: sys::virDomainBlockJobAbortFlags {
+        /// Return immediately instead of waiting for the job to end.
+        const ASYNC = sys::VIR_DOMAIN_BLOCK_JOB_ABORT_ASYNC;
+        /// Pivot the disk to the destination of a copy or active
+        /// commit job which is ready.
+        const PIVOT = sys::VIR_DOMAIN_BLOCK_JOB_ABORT_PIVOT;
+    }
+}
+
+impl_flags! {
+    /// Flags used when starting a block copy.
+    ///
+    /// See <https://libvirt.org/html/libvirt-libvirt-domain.html#virDomainBlockCopyFlags>
+    struct BlockCopyFlags: sys::virDomainBlockCopyFlags {
+        /// Only copy the top image of the backing chain, the
+        /// destination then uses the same backing files.
+        const SHALLOW = sys::VIR_DOMAIN_BLOCK_COPY_SHALLOW;
+        /// Reuse the existing destination instead of creating it.
+        const REUSE_EXT = sys::VIR_DOMAIN_BLOCK_COPY_REUSE_EXT;
+        /// Do not require the domain to be transient.
+        const TRANSIENT_JOB = sys::VIR_DOMAIN_BLOCK_COPY_TRANSIENT_JOB;
+        /// Write the guest writes to both the source and the
+        /// destination, so that the copy eventually converges.
+        const SYNCHRONOUS_WRITES = sys::VIR_DOMAIN_BLOCK_COPY_SYNCHRONOUS_WRITES;
+    }
+}
+
+macro_rules! block_copy_parameters_fields {
+    ($dir:ident, $var:ident) => {
+        vec![
+            $dir!(sys::VIR_DOMAIN_BLOCK_COPY_BANDWIDTH, UInt64, $var.bandwidth),
+            $dir!(
+                sys::VIR_DOMAIN_BLOCK_COPY_GRANULARITY,
+                UInt32,
+                $var.granularity
+            ),
+            $dir!(sys::VIR_DOMAIN_BLOCK_COPY_BUF_SIZE, UInt64, $var.buf_size),
+        ]
+    };
+}
+
+/// Tunables of a block copy, used by [`Domain::block_copy()`].
+///
+/// Fields left to `None` use the defaults of the hypervisor.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BlockCopyParameters {
+    /// The bandwidth limit in bytes/s.
+    pub bandwidth: Option<u64>,
+    /// The granularity in bytes of the dirty bitmap, a power of 2.
+    pub granularity: Option<u32>,
+    /// The maximum amount of data in flight, in bytes.
+    pub buf_size: Option<u64>,
+}
+
+impl BlockCopyParameters {
+    pub fn from_vec(vec: Vec<sys::virTypedParameter>) -> BlockCopyParameters {
+        let mut ret = BlockCopyParameters::default();
+        let fields = block_copy_parameters_fields!(param_field_in, ret);
+        from_params(&vec, fields);
+        ret
+    }
+
+    pub fn to_vec(&self) -> Result<Vec<sys::virTypedParameter>, Error> {
+        let fields = block_copy_parameters_fields!(param_field_out, self);
+        to_params(fields)
+    }
+}
+
+/// Options used by [`Domain::block_copy_to_file()`] and
+/// [`Domain::block_copy_to_volume()`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BlockCopyOptions {
+    /// The format of the destination image, e.g. `qcow2`; libvirt
+    /// probes it or uses the format of the source when `None`.
+    pub format: Option<String>,
+    /// Only copy the top image of the backing chain.
+    pub shallow: bool,
+    /// Reuse an existing destination instead of creating it.
+    pub reuse_external: bool,
+    /// Allow the copy on a persistent domain, the job being lost if
+    /// the domain stops.
+    pub transient_job: bool,
+    /// Make the guest writes synchronous to the destination.
+    pub synchronous_writes: bool,
+    /// Wait for the copy to be ready and switch the disk to the
+    /// destination.
+    pub pivot: bool,
+    /// How long to wait for the copy to be ready when pivoting, or
+    /// [`BlockCopyOptions::DEFAULT_PIVOT_TIMEOUT`] if `None`.
+    pub pivot_timeout: Option<Duration>,
+    pub parameters: BlockCopyParameters,
+}
+
+impl BlockCopyOptions {
+    /// The default of [`BlockCopyOptions::pivot_timeout`].
+    pub const DEFAULT_PIVOT_TIMEOUT: Duration = Duration::from_secs(3600);
+
+    /// Returns the flags matching these options.
+    pub fn flags(&self) -> BlockCopyFlags {
+        let mut flags = BlockCopyFlags::empty();
+        if self.shallow {
+            flags |= BlockCopyFlags::SHALLOW;
+        }
+        if self.reuse_external {
+            flags |= BlockCopyFlags::REUSE_EXT;
+        }
+        if self.transient_job {
+            flags |= BlockCopyFlags::TRANSIENT_JOB;
+        }
+        if self.synchronous_writes {
+            flags |= BlockCopyFlags::SYNCHRONOUS_WRITES;
+        }
+        flags
+    }
+
+    /// Returns the destination `<disk>` XML for a source of the given
+    /// type, whose attributes are already formatted.
+    fn disk_xml(&self, disk_type: &str, source: &str) -> String {
+        let mut xml = format!("<disk type='{}'>\n  <source {}/>\n", disk_type, source);
+        if let Some(format) = &self.format {
+            xml.push_str(&format!(
+                "  <driver type='{}'/>\n",
+                escape_str_attribute(format)
+            ));
+        }
+        xml.push_str("</disk>\n");
+        xml
+    }
+}
+
+/// The progress of a block job.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BlockJobInfo {
+    /// The kind of the job.
+    pub job_type: BlockJobType,
+    /// The bandwidth limit, in MiB/s or bytes/s depending on the flags
+    /// used to query it. 0 means unlimited.
+    pub bandwidth: u64,
+    /// The amount of work done so far.
+    pub cur: u64,
+    /// The total amount of work, which may grow while the job runs.
+    pub end: u64,
+}
+
+impl std::fmt::Display for BlockJobInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "{:?} job: {}/{}, bandwidth: {}",
+            self.job_type, self.cur, self.end, self.bandwidth
+        )
+    }
+}
+
+impl BlockJobInfo {
+    /// # Safety
+    ///
+    /// The caller must ensure that the pointer is valid.
+    pub unsafe fn from_ptr(ptr: sys::virDomainBlockJobInfoPtr) -> BlockJobInfo {
+        BlockJobInfo {
+            job_type: BlockJobType::from_raw((*ptr).type_ as sys::virDomainBlockJobType),
+            bandwidth: c_ulong_to_u64((*ptr).bandwidth),
+            cur: (*ptr).cur,
+            end: (*ptr).end,
+        }
+    }
+
+    /// Returns true if a copy or active commit job has caught up with
+    /// the guest writes and can be pivoted or aborted.
+    pub fn is_ready(&self) -> bool {
+        matches!(
+            self.job_type,
+            BlockJobType::Copy | BlockJobType::ActiveCommit
+        ) && self.end > 0
+            && self.cur == self.end
+    }
+}
+
+/// The result of [`Domain::wait_for_block_job()`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BlockJobOutcome {
+    /// A copy or active commit job reached its synchronized phase and
+    /// keeps running until it is pivoted or aborted.
+    Ready(BlockJobInfo),
+    /// The job is no longer running. Polling cannot tell whether it
+    /// completed or failed; block job events carry that information.
+    Ended,
+}
+
+/// A memory tuning limit, in kibibytes.
+///
+/// When used in a setter, wrapping the limit in `None` leaves it
+/// unchanged, while `Some(Limit::Unlimited)` removes it.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Limit {
+    /// The limit is set to the given value.
+    Value(u64),
+    /// No limit is enforced.
+    Unlimited,
+}
+
+impl Limit {
+    /// Converts a libvirt memory parameter to a limit; values equal to
+    /// or above `VIR_DOMAIN_MEMORY_PARAM_UNLIMITED` mean unlimited.
+    pub fn from_raw(raw: u64) -> Limit {
+        if raw >= sys::VIR_DOMAIN_MEMORY_PARAM_UNLIMITED {
+            Limit::Unlimited
+        } else {
+            Limit::Value(raw)
+        }
+    }
+
+    /// Converts the limit to a libvirt memory parameter.
+    pub fn to_raw(self) -> u64 {
+        match self {
+            Limit::Value(v) => v.min(sys::VIR_DOMAIN_MEMORY_PARAM_UNLIMITED),
+            Limit::Unlimited => sys::VIR_DOMAIN_MEMORY_PARAM_UNLIMITED,
+        }
+    }
+
+    /// Returns the value of the limit, or `None` if unlimited.
+    pub fn value(self) -> Option<u64> {
+        match self {
+            Limit::Value(v) => Some(v),
+            Limit::Unlimited => None,
+        }
+    }
+}
+
+impl std::fmt::Display for Limit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Limit::Value(v) => write!(f, "{}", v),
+            Limit::Unlimited => write!(f, "unlimited"),
+        }
+    }
+}
+
 #[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct MemoryParameters {
     /// Represents the maximum memory the guest can use.
-    pub hard_limit: Option<u64>,
+    pub hard_limit: Option<Limit>,
     /// Represents the memory upper limit enforced during memory
     /// contention.
-    pub soft_limit: Option<u64>,
+    pub soft_limit: Option<Limit>,
     /// Represents the minimum memory guaranteed to be reserved for
     /// the guest.
     pub min_guarantee: Option<u64>,
     /// Represents the maximum swap plus memory the guest can use.
-    pub swap_hard_limit: Option<u64>,
+    pub swap_hard_limit: Option<Limit>,
+    /// The parameters the other fields do not cover, or whose type was
+    /// not the expected one. They are not passed back by `to_vec()`.
+    pub extra: TypedParamsMap,
 }
 
 macro_rules! memory_parameters_fields {
     ($dir:ident, $var:ident) => {
         vec![
-            $dir!(sys::VIR_DOMAIN_MEMORY_HARD_LIMIT, UInt64, $var.hard_limit),
-            $dir!(sys::VIR_DOMAIN_MEMORY_SOFT_LIMIT, UInt64, $var.soft_limit),
+            $dir!(sys::VIR_DOMAIN_MEMORY_HARD_LIMIT, Limit, $var.hard_limit),
+            $dir!(sys::VIR_DOMAIN_MEMORY_SOFT_LIMIT, Limit, $var.soft_limit),
             $dir!(
                 sys::VIR_DOMAIN_MEMORY_MIN_GUARANTEE,
                 UInt64,
@@ -115,34 +890,150 @@ macro_rules! memory_parameters_fields {
             ),
             $dir!(
                 sys::VIR_DOMAIN_MEMORY_SWAP_HARD_LIMIT,
-                UInt64,
+                Limit,
                 $var.swap_hard_limit
             ),
         ]
     };
 }
 
+impl std::fmt::Display for MemoryParameters {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        fn limit(l: Option<Limit>) -> String {
+            l.map_or_else(|| "-".to_string(), |l| l.to_string())
+        }
+        write!(
+            f,
+            "hard limit: {}, soft limit: {}, swap hard limit: {}, min guarantee: {}",
+            limit(self.hard_limit),
+            limit(self.soft_limit),
+            limit(self.swap_hard_limit),
+            self.min_guarantee
+                .map_or_else(|| "-".to_string(), |v| v.to_string())
+        )
+    }
+}
+
 impl MemoryParameters {
     pub const VALUE_UNLIMITED: u64 = sys::VIR_DOMAIN_MEMORY_PARAM_UNLIMITED;
 
     pub fn from_vec(vec: Vec<sys::virTypedParameter>) -> MemoryParameters {
         let mut ret = MemoryParameters::default();
         let fields = memory_parameters_fields!(param_field_in, ret);
-        from_params(vec, fields);
+        ret.extra = from_params(&vec, fields);
         ret
     }
 
-    pub fn to_vec(&self) -> Vec<sys::virTypedParameter> {
+    pub fn to_vec(&self) -> Result<Vec<sys::virTypedParameter>, Error> {
         let fields = memory_parameters_fields!(param_field_out, self);
         to_params(fields)
     }
 }
 
-macro_rules! numa_parameters_fields {
+macro_rules! iothread_parameters_fields {
     ($dir:ident, $var:ident) => {
         vec![
-            $dir!(sys::VIR_DOMAIN_NUMA_NODESET, String, $var.node_set),
-            $dir!(sys::VIR_DOMAIN_NUMA_MODE, Int32, $var.mode),
+            $dir!(
+                sys::VIR_DOMAIN_IOTHREAD_POLL_MAX_NS,
+                UInt64,
+                $var.poll_max_ns
+            ),
+            $dir!(sys::VIR_DOMAIN_IOTHREAD_POLL_GROW, UInt32, $var.poll_grow),
+            $dir!(
+                sys::VIR_DOMAIN_IOTHREAD_POLL_SHRINK,
+                UInt32,
+                $var.poll_shrink
+            ),
+            $dir!(
+                sys::VIR_DOMAIN_IOTHREAD_THREAD_POOL_MIN,
+                Int32,
+                $var.thread_pool_min
+            ),
+            $dir!(
+                sys::VIR_DOMAIN_IOTHREAD_THREAD_POOL_MAX,
+                Int32,
+                $var.thread_pool_max
+            ),
+        ]
+    };
+}
+
+/// An IOThread of a domain, as returned by
+/// [`Domain::get_iothread_info()`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IOThreadInfo {
+    pub iothread_id: u32,
+    /// The host CPUs the IOThread is pinned to.
+    pub cpumap: CpuMap,
+}
+
+/// Tunables of an IOThread, set with [`Domain::set_iothread_params()`].
+///
+/// Fields left to `None` are not changed.
+#[derive(Clone, Debug, Default)]
+pub struct IOThreadParameters {
+    /// The maximum polling time in nanoseconds; 0 disables polling.
+    pub poll_max_ns: Option<u64>,
+    /// How much the polling time grows when polling succeeds.
+    pub poll_grow: Option<u32>,
+    /// How much the polling time shrinks when polling fails.
+    pub poll_shrink: Option<u32>,
+    /// The lower bound of the worker thread pool; -1 for the default.
+    pub thread_pool_min: Option<i32>,
+    /// The upper bound of the worker thread pool; -1 for the default.
+    pub thread_pool_max: Option<i32>,
+}
+
+impl IOThreadParameters {
+    pub fn from_vec(vec: Vec<sys::virTypedParameter>) -> IOThreadParameters {
+        let mut ret = IOThreadParameters::default();
+        let fields = iothread_parameters_fields!(param_field_in, ret);
+        from_params(&vec, fields);
+        ret
+    }
+
+    pub fn to_vec(&self) -> Result<Vec<sys::virTypedParameter>, Error> {
+        let fields = iothread_parameters_fields!(param_field_out, self);
+        to_params(fields)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+/// The policy used to allocate the memory of a domain on the NUMA
+/// nodes of its node set.
+///
+/// See <https://libvirt.org/html/libvirt-libvirt-domain.html#virDomainNumatuneMemMode>
+pub enum NumatuneMemMode {
+    /// Only allocate from the node set, failing otherwise.
+    Strict,
+    /// Prefer the node set, falling back to other nodes.
+    Preferred,
+    /// Interleave the allocations across the node set.
+    Interleave,
+    /// Restrict the allocations with cgroups only, letting the guest
+    /// kernel pick the nodes.
+    Restrictive,
+    /// A mode unknown to this library, kept as returned by libvirt.
+    Unknown(sys::virDomainNumatuneMemMode),
+}
+
+impl_enum! {
+    enum: NumatuneMemMode,
+    raw: sys::virDomainNumatuneMemMode,
+    match: {
+        sys::VIR_DOMAIN_NUMATUNE_MEM_STRICT => NumatuneMemMode::Strict,
+        sys::VIR_DOMAIN_NUMATUNE_MEM_PREFERRED => NumatuneMemMode::Preferred,
+        sys::VIR_DOMAIN_NUMATUNE_MEM_INTERLEAVE => NumatuneMemMode::Interleave,
+        sys::VIR_DOMAIN_NUMATUNE_MEM_RESTRICTIVE => NumatuneMemMode::Restrictive,
+        _ => NumatuneMemMode::Unknown(raw),
+    }
+}
+
+macro_rules! numa_parameters_fields {
+    ($dir:ident, $node_set:ident, $mode:ident) => {
+        vec![
+            $dir!(sys::VIR_DOMAIN_NUMA_NODESET, String, $node_set),
+            $dir!(sys::VIR_DOMAIN_NUMA_MODE, Int32, $mode),
         ]
     };
 }
@@ -150,30 +1041,219 @@ macro_rules! numa_parameters_fields {
 #[derive(Clone, Debug, Default)]
 pub struct NUMAParameters {
     /// Lists the numa nodeset of a domain.
-    pub node_set: Option<String>,
-    /// Numa mode of a domain, as an int containing a
-    /// DomainNumatuneMemMode value.
-    pub mode: Option<i32>,
+    pub node_set: Option<NodeSet>,
+    /// The memory allocation policy of the domain.
+    pub mode: Option<NumatuneMemMode>,
+    /// The parameters the other fields do not cover, or whose type was
+    /// not the expected one. They are not passed back by `to_vec()`.
+    pub extra: TypedParamsMap,
 }
 
 impl NUMAParameters {
     pub fn from_vec(vec: Vec<sys::virTypedParameter>) -> NUMAParameters {
-        let mut ret = NUMAParameters::default();
-        let fields = numa_parameters_fields!(param_field_in, ret);
-        from_params(vec, fields);
-        ret
+        let mut node_set: Option<String> = None;
+        let mut mode: Option<i32> = None;
+        let fields = numa_parameters_fields!(param_field_in, node_set, mode);
+        let mut extra = from_params(&vec, fields);
+        // A node set which does not parse is kept as it was returned.
+        let node_set = node_set.and_then(|s| match s.parse() {
+            Ok(node_set) => Some(node_set),
+            Err(_) => {
+                let name = sys::VIR_DOMAIN_NUMA_NODESET.to_string_lossy();
+                extra.append(&name, TypedValue::String(s));
+                None
+            }
+        });
+        NUMAParameters {
+            node_set,
+            mode: mode.map(|m| NumatuneMemMode::from_raw(m as sys::virDomainNumatuneMemMode)),
+            extra,
+        }
     }
 
-    pub fn to_vec(&self) -> Vec<sys::virTypedParameter> {
-        let fields = numa_parameters_fields!(param_field_out, self);
+    pub fn to_vec(&self) -> Result<Vec<sys::virTypedParameter>, Error> {
+        let node_set = self.node_set.as_ref().map(|n| n.to_string());
+        let mode = self.mode.map(|m| m.to_raw() as i32);
+        let fields = numa_parameters_fields!(param_field_out, node_set, mode);
         to_params(fields)
     }
 }
 
-macro_rules! migrate_parameters_fields {
-    ($dir:ident, $var:ident) => {
-        vec![
-            $dir!(
+/// The vCPUs of a guest, as reported by its agent.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct GuestVcpus {
+    /// The vCPUs known to the guest.
+    pub vcpus: NodeSet,
+    /// The vCPUs online in the guest.
+    pub online: NodeSet,
+    /// The vCPUs the guest allows to take offline.
+    pub offlinable: NodeSet,
+}
+
+impl GuestVcpus {
+    pub fn from_vec(vec: Vec<sys::virTypedParameter>) -> GuestVcpus {
+        let map = TypedParamsMap::from_vec(vec);
+        let parse = |name| {
+            map.get(name)
+                .and_then(|v| v.as_str())
+                .and_then(|s| s.parse().ok())
+                .unwrap_or_default()
+        };
+        GuestVcpus {
+            vcpus: parse("vcpus"),
+            online: parse("online"),
+            offlinable: parse("offlinable"),
+        }
+    }
+}
+
+macro_rules! perf_events_fields {
+    ($dir:ident, $var:ident) => {
+        vec![
+            $dir!(sys::VIR_PERF_PARAM_CMT, Bool, $var.cmt),
+            $dir!(sys::VIR_PERF_PARAM_MBMT, Bool, $var.mbmt),
+            $dir!(sys::VIR_PERF_PARAM_MBML, Bool, $var.mbml),
+            $dir!(sys::VIR_PERF_PARAM_CACHE_MISSES, Bool, $var.cache_misses),
+            $dir!(
+                sys::VIR_PERF_PARAM_CACHE_REFERENCES,
+                Bool,
+                $var.cache_references
+            ),
+            $dir!(sys::VIR_PERF_PARAM_INSTRUCTIONS, Bool, $var.instructions),
+            $dir!(sys::VIR_PERF_PARAM_CPU_CYCLES, Bool, $var.cpu_cycles),
+            $dir!(
+                sys::VIR_PERF_PARAM_BRANCH_INSTRUCTIONS,
+                Bool,
+                $var.branch_instructions
+            ),
+            $dir!(sys::VIR_PERF_PARAM_BRANCH_MISSES, Bool, $var.branch_misses),
+            $dir!(sys::VIR_PERF_PARAM_BUS_CYCLES, Bool, $var.bus_cycles),
+            $dir!(
+                sys::VIR_PERF_PARAM_STALLED_CYCLES_FRONTEND,
+                Bool,
+                $var.stalled_cycles_frontend
+            ),
+            $dir!(
+                sys::VIR_PERF_PARAM_STALLED_CYCLES_BACKEND,
+                Bool,
+                $var.stalled_cycles_backend
+            ),
+            $dir!(
+                sys::VIR_PERF_PARAM_REF_CPU_CYCLES,
+                Bool,
+                $var.ref_cpu_cycles
+            ),
+            $dir!(sys::VIR_PERF_PARAM_CPU_CLOCK, Bool, $var.cpu_clock),
+            $dir!(sys::VIR_PERF_PARAM_TASK_CLOCK, Bool, $var.task_clock),
+            $dir!(sys::VIR_PERF_PARAM_PAGE_FAULTS, Bool, $var.page_faults),
+            $dir!(
+                sys::VIR_PERF_PARAM_CONTEXT_SWITCHES,
+                Bool,
+                $var.context_switches
+            ),
+            $dir!(
+                sys::VIR_PERF_PARAM_CPU_MIGRATIONS,
+                Bool,
+                $var.cpu_migrations
+            ),
+            $dir!(
+                sys::VIR_PERF_PARAM_PAGE_FAULTS_MIN,
+                Bool,
+                $var.page_faults_min
+            ),
+            $dir!(
+                sys::VIR_PERF_PARAM_PAGE_FAULTS_MAJ,
+                Bool,
+                $var.page_faults_maj
+            ),
+            $dir!(
+                sys::VIR_PERF_PARAM_ALIGNMENT_FAULTS,
+                Bool,
+                $var.alignment_faults
+            ),
+            $dir!(
+                sys::VIR_PERF_PARAM_EMULATION_FAULTS,
+                Bool,
+                $var.emulation_faults
+            ),
+        ]
+    };
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct PerfEvents {
+    /// Cache usage in bytes by applications running on the platform.
+    pub cmt: Option<bool>,
+    /// Total system bandwidth from one level of cache.
+    pub mbmt: Option<bool>,
+    /// Bandwidth of memory traffic for a memory controller.
+    pub mbml: Option<bool>,
+    /// Count of cache misses by applications running on the platform.
+    pub cache_misses: Option<bool>,
+    /// Count of cache hits by applications running on the platform.
+    pub cache_references: Option<bool>,
+    /// Count of instructions by applications running on the platform.
+    pub instructions: Option<bool>,
+    /// Count of CPU cycles (total/elapsed).
+    pub cpu_cycles: Option<bool>,
+    /// Count of branch instructions by applications running on the
+    /// platform.
+    pub branch_instructions: Option<bool>,
+    /// Count of branch misses by applications running on the platform.
+    pub branch_misses: Option<bool>,
+    /// Count of bus cycles by applications running on the platform.
+    pub bus_cycles: Option<bool>,
+    /// Count of stalled CPU cycles in the frontend of the instruction
+    /// processor pipeline.
+    pub stalled_cycles_frontend: Option<bool>,
+    /// Count of stalled CPU cycles in the backend of the instruction
+    /// processor pipeline.
+    pub stalled_cycles_backend: Option<bool>,
+    /// Count of total CPU cycles not affected by CPU frequency scaling.
+    pub ref_cpu_cycles: Option<bool>,
+    /// Count of CPU clock time, as measured by a monotonic high-
+    /// resolution per-CPU timer.
+    pub cpu_clock: Option<bool>,
+    /// Count of task clock time, as measured by a monotonic high-
+    /// resolution CPU timer, specific to the task that is run.
+    pub task_clock: Option<bool>,
+    /// Count of page faults.
+    pub page_faults: Option<bool>,
+    /// Count of context switches.
+    pub context_switches: Option<bool>,
+    /// Count of CPU migrations, that is, where the process moved from one
+    /// logical processor to another.
+    pub cpu_migrations: Option<bool>,
+    /// Count of minor page faults, that is, where the page was present in
+    /// the page cache.
+    pub page_faults_min: Option<bool>,
+    /// Count of major page faults, that is, where the page was not
+    /// present in the page cache.
+    pub page_faults_maj: Option<bool>,
+    /// Count of alignment faults.
+    pub alignment_faults: Option<bool>,
+    /// Count of emulation faults.
+    pub emulation_faults: Option<bool>,
+}
+
+impl PerfEvents {
+    pub fn from_vec(vec: Vec<sys::virTypedParameter>) -> PerfEvents {
+        let mut ret = PerfEvents::default();
+        let fields = perf_events_fields!(param_field_in, ret);
+        from_params(&vec, fields);
+        ret
+    }
+
+    pub fn to_vec(&self) -> Result<Vec<sys::virTypedParameter>, Error> {
+        let fields = perf_events_fields!(param_field_out, self);
+        to_params(fields)
+    }
+}
+
+macro_rules! migrate_parameters_fields {
+    ($dir:ident, $var:ident) => {
+        vec![
+            $dir!(
                 sys::VIR_MIGRATE_PARAM_AUTO_CONVERGE_INCREMENT,
                 Int32,
                 $var.auto_converge_increment
@@ -189,7 +1269,11 @@ macro_rules! migrate_parameters_fields {
                 UInt64,
                 $var.bandwidth_postcopy
             ),
-            $dir!(sys::VIR_MIGRATE_PARAM_COMPRESSION, String, $var.compression),
+            $dir!(
+                sys::VIR_MIGRATE_PARAM_COMPRESSION,
+                VecString,
+                $var.compression
+            ),
             $dir!(
                 sys::VIR_MIGRATE_PARAM_COMPRESSION_MT_DTHREADS,
                 Int32,
@@ -255,63 +1339,868 @@ macro_rules! migrate_parameters_fields {
     };
 }
 
-#[derive(Clone, Debug, Default)]
-pub struct MigrateParameters {
-    pub auto_converge_increment: Option<i32>,
-    pub auto_converge_initial: Option<i32>,
-    pub bandwidth: Option<u64>,
-    pub bandwidth_postcopy: Option<u64>,
-    pub compression: Option<String>,
-    pub compression_mt_dthreads: Option<i32>,
-    pub compression_mt_level: Option<i32>,
-    pub compression_mt_threads: Option<i32>,
-    pub compression_xbzrle_cache: Option<u64>,
-    pub compression_zlib_level: Option<i32>,
-    pub compression_zstd_level: Option<i32>,
-    pub dest_name: Option<String>,
-    pub dest_xml: Option<String>,
-    pub disks_port: Option<i32>,
-    pub disks_uri: Option<String>,
-    pub graphics_uri: Option<String>,
-    pub listen_address: Option<String>,
-    pub migrate_disks: Vec<String>,
-    pub parallel_connections: Option<i32>,
-    pub persist_xml: Option<String>,
-    pub tls_destination: Option<String>,
-    pub uri: Option<String>,
+#[derive(Clone, Debug, Default)]
+pub struct MigrateParameters {
+    pub auto_converge_increment: Option<i32>,
+    pub auto_converge_initial: Option<i32>,
+    pub bandwidth: Option<u64>,
+    pub bandwidth_postcopy: Option<u64>,
+    /// The compression methods, see [`MigrationCompression`].
+    pub compression: Vec<String>,
+    pub compression_mt_dthreads: Option<i32>,
+    pub compression_mt_level: Option<i32>,
+    pub compression_mt_threads: Option<i32>,
+    pub compression_xbzrle_cache: Option<u64>,
+    pub compression_zlib_level: Option<i32>,
+    pub compression_zstd_level: Option<i32>,
+    pub dest_name: Option<String>,
+    pub dest_xml: Option<String>,
+    pub disks_port: Option<i32>,
+    pub disks_uri: Option<String>,
+    pub graphics_uri: Option<String>,
+    pub listen_address: Option<String>,
+    pub migrate_disks: Vec<String>,
+    pub parallel_connections: Option<i32>,
+    pub persist_xml: Option<String>,
+    pub tls_destination: Option<String>,
+    pub uri: Option<String>,
+}
+
+impl MigrateParameters {
+    pub fn from_vec(vec: Vec<sys::virTypedParameter>) -> MigrateParameters {
+        let mut ret = MigrateParameters::default();
+        let fields = migrate_parameters_fields!(param_field_in, ret);
+        from_params(&vec, fields);
+        ret
+    }
+
+    pub fn to_vec(&self) -> Result<Vec<sys::virTypedParameter>, Error> {
+        let fields = migrate_parameters_fields!(param_field_out, self);
+        to_params(fields)
+    }
+
+    /// Replaces the compression parameters with `compression`.
+    pub fn set_compression(&mut self, compression: &MigrationCompression) {
+        self.compression = compression
+            .methods
+            .iter()
+            .map(|m| m.as_str().to_string())
+            .collect();
+        self.compression_mt_level = compression.level;
+        self.compression_mt_threads = compression.threads;
+        self.compression_mt_dthreads = compression.dthreads;
+        self.compression_xbzrle_cache = compression.xbzrle_cache;
+    }
+
+    /// Returns the compression settings of the parameters. Unknown
+    /// methods are left out.
+    pub fn get_compression(&self) -> MigrationCompression {
+        MigrationCompression {
+            methods: self
+                .compression
+                .iter()
+                .filter_map(|name| MigrationCompressionMethod::from_name(name))
+                .collect(),
+            level: self.compression_mt_level,
+            threads: self.compression_mt_threads,
+            dthreads: self.compression_mt_dthreads,
+            xbzrle_cache: self.compression_xbzrle_cache,
+        }
+    }
+}
+
+/// A method compressing the data sent by a migration.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum MigrationCompressionMethod {
+    /// Only send the changed parts of the pages already sent, using a
+    /// cache of these pages.
+    Xbzrle,
+    /// Compress the pages with zlib in several threads.
+    Mt,
+    /// Compress the multifd streams with zlib.
+    Zlib,
+    /// Compress the multifd streams with zstd.
+    Zstd,
+}
+
+impl MigrationCompressionMethod {
+    /// Returns the name of the method, as passed to libvirt.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MigrationCompressionMethod::Xbzrle => "xbzrle",
+            MigrationCompressionMethod::Mt => "mt",
+            MigrationCompressionMethod::Zlib => "zlib",
+            MigrationCompressionMethod::Zstd => "zstd",
+        }
+    }
+
+    /// Returns the method named `name`, or `None` if it is unknown.
+    pub fn from_name(name: &str) -> Option<MigrationCompressionMethod> {
+        match name {
+            "xbzrle" => Some(MigrationCompressionMethod::Xbzrle),
+            "mt" => Some(MigrationCompressionMethod::Mt),
+            "zlib" => Some(MigrationCompressionMethod::Zlib),
+            "zstd" => Some(MigrationCompressionMethod::Zstd),
+            _ => None,
+        }
+    }
+}
+
+/// The compression settings of a migration.
+///
+/// The settings are passed to [`Domain::migrate3()`] and
+/// [`Domain::migrate_to_uri3()`] through
+/// [`MigrateParameters::set_compression()`], along with the
+/// [`flags::MIGRATE_COMPRESSED`] flag. The settings left unset use the
+/// defaults of the hypervisor.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MigrationCompression {
+    methods: Vec<MigrationCompressionMethod>,
+    level: Option<i32>,
+    threads: Option<i32>,
+    dthreads: Option<i32>,
+    xbzrle_cache: Option<u64>,
+}
+
+impl MigrationCompression {
+    /// Returns settings using the default compression methods of the
+    /// hypervisor.
+    pub fn new() -> MigrationCompression {
+        MigrationCompression::default()
+    }
+
+    /// Adds the xbzrle method.
+    pub fn xbzrle(self) -> MigrationCompression {
+        self.method(MigrationCompressionMethod::Xbzrle)
+    }
+
+    /// Adds the multithreaded method.
+    pub fn mt(self) -> MigrationCompression {
+        self.method(MigrationCompressionMethod::Mt)
+    }
+
+    /// Adds `method`, unless it is already used.
+    pub fn method(mut self, method: MigrationCompressionMethod) -> MigrationCompression {
+        if !self.methods.contains(&method) {
+            self.methods.push(method);
+        }
+        self
+    }
+
+    /// Sets the compression level of the multithreaded method, from 0
+    /// (none) to 9 (best).
+    pub fn level(mut self, level: i32) -> MigrationCompression {
+        self.level = Some(level);
+        self
+    }
+
+    /// Sets the number of compression threads of the multithreaded
+    /// method, on the source host.
+    pub fn threads(mut self, threads: i32) -> MigrationCompression {
+        self.threads = Some(threads);
+        self
+    }
+
+    /// Sets the number of decompression threads of the multithreaded
+    /// method, on the destination host.
+    pub fn dthreads(mut self, dthreads: i32) -> MigrationCompression {
+        self.dthreads = Some(dthreads);
+        self
+    }
+
+    /// Sets the size in bytes of the page cache of the xbzrle method.
+    pub fn xbzrle_cache(mut self, bytes: u64) -> MigrationCompression {
+        self.xbzrle_cache = Some(bytes);
+        self
+    }
+
+    pub fn methods(&self) -> &[MigrationCompressionMethod] {
+        &self.methods
+    }
+
+    pub fn get_level(&self) -> Option<i32> {
+        self.level
+    }
+
+    pub fn get_threads(&self) -> Option<i32> {
+        self.threads
+    }
+
+    pub fn get_dthreads(&self) -> Option<i32> {
+        self.dthreads
+    }
+
+    pub fn get_xbzrle_cache(&self) -> Option<u64> {
+        self.xbzrle_cache
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct IPAddress {
+    pub typed: i64,
+    pub addr: String,
+    pub prefix: u64,
+}
+
+impl IPAddress {
+    /// # Safety
+    ///
+    /// The caller must ensure that the pointer is valid.
+    pub unsafe fn from_ptr(ptr: sys::virDomainIPAddressPtr) -> IPAddress {
+        IPAddress {
+            typed: (*ptr).type_ as i64,
+            addr: c_chars_to_string!((*ptr).addr),
+            prefix: (*ptr).prefix as u64,
+        }
+    }
+}
+
+/// A hardware (MAC) address.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct MacAddr(pub [u8; 6]);
+
+impl MacAddr {
+    /// Parses a MAC address in the "aa:bb:cc:dd:ee:ff" notation, as
+    /// reported by libvirt.
+    ///
+    /// Returns `None` if `s` is not a valid MAC address.
+    pub fn parse(s: &str) -> Option<MacAddr> {
+        let mut octets = [0u8; 6];
+        let mut parts = s.split([':', '-']);
+        for octet in octets.iter_mut() {
+            let part = parts.next()?;
+            if part.is_empty() || part.len() > 2 {
+                return None;
+            }
+            *octet = u8::from_str_radix(part, 16).ok()?;
+        }
+        if parts.next().is_some() {
+            return None;
+        }
+        Some(MacAddr(octets))
+    }
+
+    pub fn octets(&self) -> [u8; 6] {
+        self.0
+    }
+}
+
+impl std::fmt::Display for MacAddr {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let o = &self.0;
+        write!(
+            f,
+            "{:02x}:{:02x}:{:02x}:{:02x}:{:02x}:{:02x}",
+            o[0], o[1], o[2], o[3], o[4], o[5]
+        )
+    }
+}
+
+/// Where the data of a disk attached with [`Domain::attach_disk()`]
+/// is stored on the host.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DiskSource {
+    /// An image file.
+    File(String),
+    /// A block device.
+    Block(String),
+    /// A volume of a storage pool.
+    Volume { pool: String, volume: String },
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+/// The bus a disk is attached to in the guest.
+pub enum DiskBus {
+    Virtio,
+    Scsi,
+    Sata,
+    Ide,
+    Usb,
+}
+
+impl DiskBus {
+    fn as_str(self) -> &'static str {
+        match self {
+            DiskBus::Virtio => "virtio",
+            DiskBus::Scsi => "scsi",
+            DiskBus::Sata => "sata",
+            DiskBus::Ide => "ide",
+            DiskBus::Usb => "usb",
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+/// The host cache mode of a disk.
+///
+/// See <https://libvirt.org/formatdomain.html#hard-drives-floppy-disks-cdroms>
+pub enum DiskCache {
+    /// Bypass the host page cache.
+    None,
+    Writethrough,
+    Writeback,
+    /// Bypass the host page cache and flush every write.
+    Directsync,
+    /// Ignore the flush requests of the guest.
+    Unsafe,
+}
+
+impl DiskCache {
+    fn as_str(self) -> &'static str {
+        match self {
+            DiskCache::None => "none",
+            DiskCache::Writethrough => "writethrough",
+            DiskCache::Writeback => "writeback",
+            DiskCache::Directsync => "directsync",
+            DiskCache::Unsafe => "unsafe",
+        }
+    }
+}
+
+/// Describes a disk to attach with [`Domain::attach_disk()`].
+///
+/// See <https://libvirt.org/formatdomain.html#hard-drives-floppy-disks-cdroms>
+///
+/// ```
+/// use virt::domain::{DiskBus, DiskSpec};
+///
+/// let spec = DiskSpec::file("/var/lib/libvirt/images/data.qcow2", "vdb")
+///     .bus(DiskBus::Virtio)
+///     .format("qcow2");
+/// assert!(spec.to_xml().contains("<target dev='vdb' bus='virtio'/>"));
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DiskSpec {
+    source: DiskSource,
+    target: String,
+    bus: Option<DiskBus>,
+    format: Option<String>,
+    cache: Option<DiskCache>,
+    read_only: bool,
+}
+
+impl DiskSpec {
+    /// Describes a disk `target` backed by the image file `path`.
+    pub fn file(path: &str, target: &str) -> DiskSpec {
+        DiskSpec::new(DiskSource::File(path.to_string()), target)
+    }
+
+    /// Describes a disk `target` backed by the block device `path`.
+    pub fn block(path: &str, target: &str) -> DiskSpec {
+        DiskSpec::new(DiskSource::Block(path.to_string()), target)
+    }
+
+    /// Describes a disk `target` backed by the volume `volume` of the
+    /// storage pool `pool`.
+    pub fn volume(pool: &str, volume: &str, target: &str) -> DiskSpec {
+        DiskSpec::new(
+            DiskSource::Volume {
+                pool: pool.to_string(),
+                volume: volume.to_string(),
+            },
+            target,
+        )
+    }
+
+    /// Describes a disk `target`, the device name in the guest such as
+    /// `vdb`, backed by `source`.
+    pub fn new(source: DiskSource, target: &str) -> DiskSpec {
+        DiskSpec {
+            source,
+            target: target.to_string(),
+            bus: None,
+            format: None,
+            cache: None,
+            read_only: false,
+        }
+    }
+
+    /// Sets the bus, which libvirt otherwise guesses from the target
+    /// name.
+    pub fn bus(mut self, bus: DiskBus) -> DiskSpec {
+        self.bus = Some(bus);
+        self
+    }
+
+    /// Sets the format of the image, e.g. `raw` or `qcow2`.
+    pub fn format(mut self, format: &str) -> DiskSpec {
+        self.format = Some(format.to_string());
+        self
+    }
+
+    /// Sets the host cache mode.
+    pub fn cache(mut self, cache: DiskCache) -> DiskSpec {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Makes the disk read-only in the guest.
+    pub fn read_only(mut self, read_only: bool) -> DiskSpec {
+        self.read_only = read_only;
+        self
+    }
+
+    /// Returns the device name of the disk in the guest.
+    pub fn target(&self) -> &str {
+        &self.target
+    }
+
+    /// Returns the `<disk>` XML element matching this description.
+    pub fn to_xml(&self) -> String {
+        let (kind, source) = match &self.source {
+            DiskSource::File(path) => ("file", format!("file='{}'", escape_str_attribute(path))),
+            DiskSource::Block(path) => ("block", format!("dev='{}'", escape_str_attribute(path))),
+            DiskSource::Volume { pool, volume } => (
+                "volume",
+                format!(
+                    "pool='{}' volume='{}'",
+                    escape_str_attribute(pool),
+                    escape_str_attribute(volume)
+                ),
+            ),
+        };
+        let mut xml = format!("<disk type='{}' device='disk'>\n", kind);
+        if self.format.is_some() || self.cache.is_some() {
+            xml.push_str("  <driver");
+            if let Some(format) = &self.format {
+                xml.push_str(&format!(" type='{}'", escape_str_attribute(format)));
+            }
+            if let Some(cache) = self.cache {
+                xml.push_str(&format!(" cache='{}'", cache.as_str()));
+            }
+            xml.push_str("/>\n");
+        }
+        xml.push_str(&format!("  <source {}/>\n", source));
+        xml.push_str(&format!(
+            "  <target dev='{}'",
+            escape_str_attribute(&self.target)
+        ));
+        if let Some(bus) = self.bus {
+            xml.push_str(&format!(" bus='{}'", bus.as_str()));
+        }
+        xml.push_str("/>\n");
+        if self.read_only {
+            xml.push_str("  <readonly/>\n");
+        }
+        xml.push_str("</disk>\n");
+        xml
+    }
+}
+
+/// What the network interface attached with
+/// [`Domain::attach_interface()`] is connected to on the host.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum InterfaceSource {
+    /// A virtual network managed by libvirt, e.g. `default`.
+    Network(String),
+    /// An existing bridge of the host, e.g. `br0`.
+    Bridge(String),
+}
+
+/// Describes a network interface to attach with
+/// [`Domain::attach_interface()`].
+///
+/// See <https://libvirt.org/formatdomain.html#network-interfaces>
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InterfaceSpec {
+    source: InterfaceSource,
+    model: Option<String>,
+    mac: Option<MacAddr>,
+}
+
+impl InterfaceSpec {
+    /// Describes an interface connected to the virtual network `name`.
+    pub fn network(name: &str) -> InterfaceSpec {
+        InterfaceSpec::new(InterfaceSource::Network(name.to_string()))
+    }
+
+    /// Describes an interface connected to the host bridge `name`.
+    pub fn bridge(name: &str) -> InterfaceSpec {
+        InterfaceSpec::new(InterfaceSource::Bridge(name.to_string()))
+    }
+
+    /// Describes an interface connected to `source`.
+    pub fn new(source: InterfaceSource) -> InterfaceSpec {
+        InterfaceSpec {
+            source,
+            model: None,
+            mac: None,
+        }
+    }
+
+    /// Sets the device model, e.g. `virtio` or `e1000`.
+    pub fn model(mut self, model: &str) -> InterfaceSpec {
+        self.model = Some(model.to_string());
+        self
+    }
+
+    /// Sets the MAC address, which libvirt otherwise generates.
+    pub fn mac(mut self, mac: MacAddr) -> InterfaceSpec {
+        self.mac = Some(mac);
+        self
+    }
+
+    /// Returns the `<interface>` XML element matching this description.
+    pub fn to_xml(&self) -> String {
+        let (kind, name) = match &self.source {
+            InterfaceSource::Network(name) => ("network", name),
+            InterfaceSource::Bridge(name) => ("bridge", name),
+        };
+        let mut xml = format!("<interface type='{}'>\n", kind);
+        if let Some(mac) = &self.mac {
+            xml.push_str(&format!("  <mac address='{}'/>\n", mac));
+        }
+        xml.push_str(&format!(
+            "  <source {}='{}'/>\n",
+            kind,
+            escape_str_attribute(name)
+        ));
+        if let Some(model) = &self.model {
+            xml.push_str(&format!(
+                "  <model type='{}'/>\n",
+                escape_str_attribute(model)
+            ));
+        }
+        xml.push_str("</interface>\n");
+        xml
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+/// The model of a hotpluggable memory device.
+///
+/// See <https://libvirt.org/formatdomain.html#memory-devices>
+pub enum MemoryModel {
+    /// A DIMM module, plugged and unplugged as a whole.
+    Dimm,
+    /// A virtio-mem device, resizable in blocks while plugged.
+    VirtioMem,
+}
+
+impl MemoryModel {
+    fn as_str(self) -> &'static str {
+        match self {
+            MemoryModel::Dimm => "dimm",
+            MemoryModel::VirtioMem => "virtio-mem",
+        }
+    }
+}
+
+/// Describes a memory device to attach with
+/// [`Domain::attach_memory_device()`].
+///
+/// The sizes are in bytes, libvirt rounds them up to whole KiB.
+///
+/// See <https://libvirt.org/formatdomain.html#memory-devices>
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MemoryDeviceSpec {
+    model: MemoryModel,
+    size: u64,
+    node: Option<u32>,
+    block_size: Option<u64>,
+    requested: Option<u64>,
+}
+
+impl MemoryDeviceSpec {
+    /// Describes a DIMM of `size` bytes.
+    pub fn dimm(size: u64) -> MemoryDeviceSpec {
+        MemoryDeviceSpec {
+            model: MemoryModel::Dimm,
+            size,
+            node: None,
+            block_size: None,
+            requested: None,
+        }
+    }
+
+    /// Describes a virtio-mem device of up to `size` bytes on the guest
+    /// NUMA node `node`, split in blocks of `block_size` bytes, and
+    /// fully requested.
+    pub fn virtio_mem(size: u64, node: u32, block_size: u64) -> MemoryDeviceSpec {
+        MemoryDeviceSpec {
+            model: MemoryModel::VirtioMem,
+            size,
+            node: Some(node),
+            block_size: Some(block_size),
+            requested: Some(size),
+        }
+    }
+
+    /// Sets the guest NUMA node to plug the memory in.
+    pub fn node(mut self, node: u32) -> MemoryDeviceSpec {
+        self.node = Some(node);
+        self
+    }
+
+    /// Sets the number of bytes of a virtio-mem device the guest
+    /// should use.
+    pub fn requested(mut self, bytes: u64) -> MemoryDeviceSpec {
+        self.requested = Some(bytes);
+        self
+    }
+
+    /// Returns the `<memory>` XML element matching this description.
+    pub fn to_xml(&self) -> String {
+        let mut xml = format!("<memory model='{}'>\n  <target>\n", self.model.as_str());
+        xml.push_str(&format!("    <size unit='bytes'>{}</size>\n", self.size));
+        if let Some(node) = self.node {
+            xml.push_str(&format!("    <node>{}</node>\n", node));
+        }
+        if let Some(block_size) = self.block_size {
+            xml.push_str(&format!("    <block unit='bytes'>{}</block>\n", block_size));
+        }
+        if let Some(requested) = self.requested {
+            xml.push_str(&format!(
+                "    <requested unit='bytes'>{}</requested>\n",
+                requested
+            ));
+        }
+        xml.push_str("  </target>\n</memory>\n");
+        xml
+    }
+}
+
+/// A memory device of a domain, as returned by
+/// [`Domain::memory_devices()`].
+///
+/// The sizes are in bytes.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MemoryDevice {
+    /// The model, e.g. `dimm` or `virtio-mem`.
+    pub model: String,
+    pub alias: Option<String>,
+    pub size: u64,
+    pub node: Option<u32>,
+    pub requested: Option<u64>,
+    /// The amount of memory of a virtio-mem device the guest currently
+    /// uses.
+    pub current: Option<u64>,
+}
+
+impl MemoryDevice {
+    /// Parses the memory devices out of a domain XML document.
+    pub fn list_from_xml(xml: &str) -> Result<Vec<MemoryDevice>, Error> {
+        let root = Element::parse(xml)?;
+        let devices = match root.child("devices") {
+            Some(devices) => devices,
+            None => return Ok(Vec::new()),
+        };
+        Ok(devices
+            .children("memory")
+            .map(|mem| {
+                let target = mem.child("target");
+                let size = |name: &str| target.and_then(|t| t.child(name)).and_then(to_bytes);
+                MemoryDevice {
+                    model: mem.attr("model").unwrap_or_default().to_string(),
+                    alias: mem
+                        .child("alias")
+                        .and_then(|a| a.attr("name"))
+                        .map(str::to_string),
+                    size: size("size").unwrap_or(0),
+                    node: target
+                        .and_then(|t| t.child("node"))
+                        .and_then(|n| n.text().parse().ok()),
+                    requested: size("requested"),
+                    current: size("current"),
+                }
+            })
+            .collect())
+    }
+}
+
+/// Reads a scaled integer element such as `<size unit='MiB'>`, in
+/// bytes.
+fn to_bytes(elem: &Element) -> Option<u64> {
+    let value: u64 = elem.text().parse().ok()?;
+    let unit: u64 = match elem.attr("unit").unwrap_or("KiB") {
+        "b" | "bytes" => 1,
+        "KB" => 1000,
+        "k" | "KiB" => 1 << 10,
+        "MB" => 1000 * 1000,
+        "M" | "MiB" => 1 << 20,
+        "GB" => 1000 * 1000 * 1000,
+        "G" | "GiB" => 1 << 30,
+        "TB" => 1000 * 1000 * 1000 * 1000,
+        "T" | "TiB" => 1 << 40,
+        _ => return None,
+    };
+    value.checked_mul(unit)
+}
+
+#[derive(Debug)]
+enum DeviceChange {
+    Attached {
+        alias: String,
+        flags: DomainDeviceModifyFlags,
+    },
+    Detached {
+        xml: String,
+        flags: DomainDeviceModifyFlags,
+    },
+}
+
+/// Returns a new user alias for a device attached within a
+/// [`DeviceTransaction`].
+fn transaction_alias() -> String {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_nanos());
+    format!(
+        "ua-txn-{:x}-{:x}-{:x}",
+        std::process::id(),
+        nanos,
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    )
+}
+
+/// Records the devices attached and detached within
+/// [`Domain::with_device_transaction()`], to undo them on failure.
+///
+/// The changes are undone when the transaction is dropped without
+/// having been committed, which includes a panic in the closure.
+#[derive(Debug)]
+pub struct DeviceTransaction<'a> {
+    dom: &'a Domain,
+    changes: Vec<DeviceChange>,
+    committed: bool,
+}
+
+impl DeviceTransaction<'_> {
+    /// Attach the device described by `xml`, see
+    /// [`Domain::attach_device_flags()`].
+    ///
+    /// The device is detached by its user alias on rollback. If `xml`
+    /// has no alias, a `ua-` prefixed one is generated for it.
+    pub fn attach_device(
+        &mut self,
+        xml: &str,
+        flags: impl Into<DomainDeviceModifyFlags>,
+    ) -> Result<(), Error> {
+        let flags = flags.into();
+        let root = Element::parse(xml)?;
+        let (xml, alias) = match root.child("alias").and_then(|a| a.attr("name")) {
+            Some(alias) => (xml.to_string(), alias.to_string()),
+            None => {
+                let alias = transaction_alias();
+                (xmlutil::add_device_alias(xml, &alias)?, alias)
+            }
+        };
+        self.dom.attach_device_flags(&xml, flags)?;
+        self.changes.push(DeviceChange::Attached { alias, flags });
+        Ok(())
+    }
+
+    /// Attach the disk described by `spec`.
+    pub fn attach_disk(
+        &mut self,
+        spec: &DiskSpec,
+        flags: impl Into<DomainDeviceModifyFlags>,
+    ) -> Result<(), Error> {
+        self.attach_device(&spec.to_xml(), flags)
+    }
+
+    /// Attach the network interface described by `spec`.
+    pub fn attach_interface(
+        &mut self,
+        spec: &InterfaceSpec,
+        flags: impl Into<DomainDeviceModifyFlags>,
+    ) -> Result<(), Error> {
+        self.attach_device(&spec.to_xml(), flags)
+    }
+
+    /// Attach the memory device described by `spec`.
+    pub fn attach_memory_device(
+        &mut self,
+        spec: &MemoryDeviceSpec,
+        flags: impl Into<DomainDeviceModifyFlags>,
+    ) -> Result<(), Error> {
+        self.attach_device(&spec.to_xml(), flags)
+    }
+
+    /// Detach the device described by `xml`, see
+    /// [`Domain::detach_device_flags()`]. It is attached again on
+    /// rollback.
+    pub fn detach_device(
+        &mut self,
+        xml: &str,
+        flags: impl Into<DomainDeviceModifyFlags>,
+    ) -> Result<(), Error> {
+        let flags = flags.into();
+        self.dom.detach_device_flags(xml, flags)?;
+        self.changes.push(DeviceChange::Detached {
+            xml: xml.to_string(),
+            flags,
+        });
+        Ok(())
+    }
+
+    /// Undoes the recorded changes, most recent first. Failures are
+    /// ignored so that as much as possible is undone.
+    fn rollback(&mut self) {
+        while let Some(change) = self.changes.pop() {
+            let _ = match change {
+                DeviceChange::Attached { alias, flags } => {
+                    self.dom.detach_device_alias(&alias, flags)
+                }
+                DeviceChange::Detached { xml, flags } => {
+                    self.dom.attach_device_flags(&xml, flags).map(|_| ())
+                }
+            };
+        }
+    }
 }
 
-impl MigrateParameters {
-    pub fn from_vec(vec: Vec<sys::virTypedParameter>) -> MigrateParameters {
-        let mut ret = MigrateParameters::default();
-        let fields = migrate_parameters_fields!(param_field_in, ret);
-        from_params(vec, fields);
-        ret
+impl Drop for DeviceTransaction<'_> {
+    fn drop(&mut self) {
+        if !self.committed {
+            self.rollback();
+        }
     }
+}
 
-    pub fn to_vec(&self) -> Vec<sys::virTypedParameter> {
-        let fields = migrate_parameters_fields!(param_field_out, self);
-        to_params(fields)
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+/// The source used to query the IP addresses of a domain interface.
+///
+/// See <https://libvirt.org/html/libvirt-libvirt-domain.html#virDomainInterfaceAddressesSource>
+pub enum InterfaceAddressSource {
+    /// Parse DHCP lease file.
+    Lease,
+    /// Query the guest agent.
+    Agent,
+    /// Query the host ARP table.
+    Arp,
+}
+
+impl_enum! {
+    enum: InterfaceAddressSource,
+    raw: sys::virDomainInterfaceAddressesSource,
+    match: {
+        sys::VIR_DOMAIN_INTERFACE_ADDRESSES_SRC_LEASE => InterfaceAddressSource::Lease,
+        sys::VIR_DOMAIN_INTERFACE_ADDRESSES_SRC_AGENT => InterfaceAddressSource::Agent,
+        sys::VIR_DOMAIN_INTERFACE_ADDRESSES_SRC_ARP => InterfaceAddressSource::Arp,
+        _ => InterfaceAddressSource::Lease,
     }
 }
 
-#[derive(Clone, Debug)]
-pub struct IPAddress {
-    pub typed: i64,
-    pub addr: String,
-    pub prefix: u64,
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+/// The source used to query the hostname of a domain.
+///
+/// See <https://libvirt.org/html/libvirt-libvirt-domain.html#virDomainGetHostnameFlags>
+pub enum HostnameSource {
+    /// Look up the DHCP leases of the libvirt managed networks.
+    Lease,
+    /// Query the guest agent.
+    Agent,
 }
 
-impl IPAddress {
-    /// # Safety
-    ///
-    /// The caller must ensure that the pointer is valid.
-    pub unsafe fn from_ptr(ptr: sys::virDomainIPAddressPtr) -> IPAddress {
-        IPAddress {
-            typed: (*ptr).type_ as i64,
-            addr: c_chars_to_string!((*ptr).addr),
-            prefix: (*ptr).prefix as u64,
-        }
+impl_enum! {
+    enum: HostnameSource,
+    raw: sys::virDomainGetHostnameFlags,
+    match: {
+        sys::VIR_DOMAIN_GET_HOSTNAME_LEASE => HostnameSource::Lease,
+        sys::VIR_DOMAIN_GET_HOSTNAME_AGENT => HostnameSource::Agent,
+        _ => HostnameSource::Lease,
     }
 }
 
@@ -319,6 +2208,8 @@ impl IPAddress {
 pub struct Interface {
     pub name: String,
     pub hwaddr: String,
+    /// The hardware address, if `hwaddr` could be parsed.
+    pub mac: Option<MacAddr>,
     pub naddrs: u64,
     pub addrs: Vec<IPAddress>,
 }
@@ -333,9 +2224,11 @@ impl Interface {
         for x in 0..naddrs as isize {
             addrs.push(IPAddress::from_ptr((*ptr).addrs.offset(x)));
         }
+        let hwaddr = c_chars_to_string!((*ptr).hwaddr);
         Interface {
             name: c_chars_to_string!((*ptr).name),
-            hwaddr: c_chars_to_string!((*ptr).hwaddr),
+            mac: MacAddr::parse(&hwaddr),
+            hwaddr,
             naddrs: naddrs as u64,
             addrs,
         }
@@ -343,6 +2236,7 @@ impl Interface {
 }
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct InterfaceStats {
     pub rx_bytes: i64,
     pub rx_packets: i64,
@@ -354,6 +2248,24 @@ pub struct InterfaceStats {
     pub tx_drop: i64,
 }
 
+impl std::fmt::Display for InterfaceStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "rx: {} B, {} packets, {} errors, {} dropped; \
+             tx: {} B, {} packets, {} errors, {} dropped",
+            self.rx_bytes,
+            self.rx_packets,
+            self.rx_errs,
+            self.rx_drop,
+            self.tx_bytes,
+            self.tx_packets,
+            self.tx_errs,
+            self.tx_drop
+        )
+    }
+}
+
 impl InterfaceStats {
     /// # Safety
     ///
@@ -372,12 +2284,174 @@ impl InterfaceStats {
     }
 }
 
+/// The details of a `BLOCK_THRESHOLD` event, delivered to the
+/// callbacks registered with
+/// [`Connect::domain_event_block_threshold_register()`] once the
+/// threshold set by [`Domain::set_block_threshold()`] is exceeded.
+///
+/// The threshold is disarmed once the event fires and must be set
+/// again to receive another event.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BlockThresholdEvent {
+    /// The target name of the disk, e.g. `vda`, or the name of one of
+    /// its backing images, e.g. `vda[1]`.
+    pub dev: String,
+    /// The path of the image, if any.
+    pub path: Option<String>,
+    /// The threshold in bytes which was exceeded.
+    pub threshold: u64,
+    /// How many bytes were written beyond the threshold.
+    pub excess: u64,
+}
+
+impl_flags! {
+    /// Flags used when opening a graphics connection to a domain.
+    ///
+    /// See <https://libvirt.org/html/libvirt-libvirt-domain.html#virDomainOpenGraphicsFlags>
+    struct OpenGraphicsFlags: sys::virDomainOpenGraphicsFlags {
+        /// Skip the authentication of the graphics server.
+        const SKIPAUTH = sys::VIR_DOMAIN_OPEN_GRAPHICS_SKIPAUTH;
+    }
+}
+
+/// How to connect to a graphics device of a domain, as returned by
+/// [`Domain::graphics_info()`].
+///
+/// See <https://libvirt.org/formatdomain.html#graphical-framebuffers>
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct GraphicsInfo {
+    /// The kind of device, e.g. `vnc` or `spice`.
+    pub graphics_type: String,
+    /// The TCP port, if allocated.
+    pub port: Option<u16>,
+    /// The TCP port used for TLS connections, if allocated.
+    pub tls_port: Option<u16>,
+    /// The address the server listens on, if any.
+    pub listen: Option<String>,
+    /// The UNIX socket the server listens on, if any.
+    pub socket: Option<String>,
+}
+
+impl GraphicsInfo {
+    /// Parses the graphics devices out of the XML description of a
+    /// domain.
+    pub fn from_xml(xml: &str) -> Result<Vec<GraphicsInfo>, Error> {
+        fn port(graphics: &Element, name: &str) -> Option<u16> {
+            // Ports which are not allocated yet are reported as -1.
+            graphics.attr(name).and_then(|p| p.parse().ok())
+        }
+
+        let root = Element::parse(xml)?;
+        let devices = match root.child("devices") {
+            Some(devices) => devices,
+            None => return Ok(Vec::new()),
+        };
+        Ok(devices
+            .children("graphics")
+            .map(|graphics| {
+                let listen = graphics.child("listen");
+                GraphicsInfo {
+                    graphics_type: graphics.attr("type").unwrap_or_default().to_string(),
+                    port: port(graphics, "port"),
+                    tls_port: port(graphics, "tlsPort"),
+                    listen: graphics
+                        .attr("listen")
+                        .or_else(|| listen.and_then(|l| l.attr("address")))
+                        .map(String::from),
+                    socket: graphics
+                        .attr("socket")
+                        .or_else(|| listen.and_then(|l| l.attr("socket")))
+                        .map(String::from),
+                }
+            })
+            .collect())
+    }
+}
+
+/// I/O statistics of a block device.
+///
+/// Fields set to -1 are not supported by the hypervisor.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BlockStats {
+    /// Number of read requests.
+    pub rd_req: i64,
+    /// Number of bytes read.
+    pub rd_bytes: i64,
+    /// Number of write requests.
+    pub wr_req: i64,
+    /// Number of bytes written.
+    pub wr_bytes: i64,
+    /// Number of errors.
+    pub errs: i64,
+}
+
+impl std::fmt::Display for BlockStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "read: {} requests, {} B; written: {} requests, {} B; errors: {}",
+            self.rd_req, self.rd_bytes, self.wr_req, self.wr_bytes, self.errs
+        )
+    }
+}
+
+impl BlockStats {
+    /// # Safety
+    ///
+    /// The caller must ensure that the pointer is valid.
+    pub unsafe fn from_ptr(ptr: sys::virDomainBlockStatsPtr) -> BlockStats {
+        BlockStats {
+            rd_req: (*ptr).rd_req,
+            rd_bytes: (*ptr).rd_bytes,
+            wr_req: (*ptr).wr_req,
+            wr_bytes: (*ptr).wr_bytes,
+            errs: (*ptr).errs,
+        }
+    }
+
+    /// Adds up the statistics of several block devices.
+    ///
+    /// Unsupported fields are skipped; a field is only reported as
+    /// unsupported if it is unsupported for every device.
+    pub fn total<'a>(stats: impl IntoIterator<Item = &'a BlockStats>) -> BlockStats {
+        fn add(total: &mut i64, value: i64) {
+            if value >= 0 {
+                *total = (*total).max(0) + value;
+            }
+        }
+
+        let mut total = BlockStats {
+            rd_req: -1,
+            rd_bytes: -1,
+            wr_req: -1,
+            wr_bytes: -1,
+            errs: -1,
+        };
+        for s in stats {
+            add(&mut total.rd_req, s.rd_req);
+            add(&mut total.rd_bytes, s.rd_bytes);
+            add(&mut total.wr_req, s.wr_req);
+            add(&mut total.wr_bytes, s.wr_bytes);
+            add(&mut total.errs, s.errs);
+        }
+        total
+    }
+}
+
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct MemoryStat {
     pub tag: u32,
     pub val: u64,
 }
 
+impl std::fmt::Display for MemoryStat {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}: {}", self.tag, self.val)
+    }
+}
+
 impl MemoryStat {
     /// # Safety
     ///
@@ -390,9 +2464,245 @@ impl MemoryStat {
     }
 }
 
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+/// The kind of I/O error reported for a disk.
+///
+/// See <https://libvirt.org/html/libvirt-libvirt-domain.html#virDomainDiskErrorCode>
+pub enum DiskErrorCode {
+    /// No error.
+    None,
+    /// Unspecified I/O error.
+    Unspec,
+    /// No space left on the device.
+    NoSpace,
+}
+
+impl_enum! {
+    enum: DiskErrorCode,
+    raw: sys::virDomainDiskErrorCode,
+    match: {
+        sys::VIR_DOMAIN_DISK_ERROR_NONE => DiskErrorCode::None,
+        sys::VIR_DOMAIN_DISK_ERROR_UNSPEC => DiskErrorCode::Unspec,
+        sys::VIR_DOMAIN_DISK_ERROR_NO_SPACE => DiskErrorCode::NoSpace,
+        _ => DiskErrorCode::Unspec,
+    }
+}
+
+/// Memory statistics of a domain, built from the [`MemoryStat`]
+/// entries returned by [`Domain::memory_stats()`].
+///
+/// Statistics not reported by the hypervisor are left as `None`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MemoryStats {
+    /// The total amount of data read from swap space, in KiB.
+    pub swap_in: Option<u64>,
+    /// The total amount of memory written out to swap space, in KiB.
+    pub swap_out: Option<u64>,
+    /// The number of page faults which required disk IO.
+    pub major_fault: Option<u64>,
+    /// The number of page faults which were handled without disk IO.
+    pub minor_fault: Option<u64>,
+    /// The amount of memory left completely unused by the system, in KiB.
+    pub unused: Option<u64>,
+    /// The total amount of usable memory as seen by the domain, in KiB.
+    pub available: Option<u64>,
+    /// The current balloon value, in KiB.
+    pub actual_balloon: Option<u64>,
+    /// The resident set size of the running domain process, in KiB.
+    pub rss: Option<u64>,
+    /// The amount of memory which can be reclaimed by the balloon without
+    /// causing the guest to swap, in KiB.
+    pub usable: Option<u64>,
+    /// The timestamp of the last update of the statistics, in seconds.
+    pub last_update: Option<u64>,
+    /// The amount of memory that can be quickly reclaimed without
+    /// additional I/O, in KiB.
+    pub disk_caches: Option<u64>,
+    /// The number of successful huge page allocations from inside the
+    /// domain.
+    pub hugetlb_pgalloc: Option<u64>,
+    /// The number of failed huge page allocations from inside the
+    /// domain.
+    pub hugetlb_pgfail: Option<u64>,
+}
+
+impl MemoryStats {
+    pub fn from_stats(stats: &[MemoryStat]) -> MemoryStats {
+        let mut ret = MemoryStats::default();
+        for stat in stats {
+            let field = match stat.tag {
+                sys::VIR_DOMAIN_MEMORY_STAT_SWAP_IN => &mut ret.swap_in,
+                sys::VIR_DOMAIN_MEMORY_STAT_SWAP_OUT => &mut ret.swap_out,
+                sys::VIR_DOMAIN_MEMORY_STAT_MAJOR_FAULT => &mut ret.major_fault,
+                sys::VIR_DOMAIN_MEMORY_STAT_MINOR_FAULT => &mut ret.minor_fault,
+                sys::VIR_DOMAIN_MEMORY_STAT_UNUSED => &mut ret.unused,
+                sys::VIR_DOMAIN_MEMORY_STAT_AVAILABLE => &mut ret.available,
+                sys::VIR_DOMAIN_MEMORY_STAT_ACTUAL_BALLOON => &mut ret.actual_balloon,
+                sys::VIR_DOMAIN_MEMORY_STAT_RSS => &mut ret.rss,
+                sys::VIR_DOMAIN_MEMORY_STAT_USABLE => &mut ret.usable,
+                sys::VIR_DOMAIN_MEMORY_STAT_LAST_UPDATE => &mut ret.last_update,
+                sys::VIR_DOMAIN_MEMORY_STAT_DISK_CACHES => &mut ret.disk_caches,
+                sys::VIR_DOMAIN_MEMORY_STAT_HUGETLB_PGALLOC => &mut ret.hugetlb_pgalloc,
+                sys::VIR_DOMAIN_MEMORY_STAT_HUGETLB_PGFAIL => &mut ret.hugetlb_pgfail,
+                _ => continue,
+            };
+            *field = Some(stat.val);
+        }
+        ret
+    }
+}
+
+/// An I/O error reported for a disk of a domain.
+#[derive(Clone, Debug)]
+pub struct DiskError {
+    /// The disk target, as seen in the domain XML.
+    pub disk: String,
+    pub error: DiskErrorCode,
+}
+
+impl DiskError {
+    /// # Safety
+    ///
+    /// The caller must ensure that the pointer is valid. The disk
+    /// string is released.
+    pub unsafe fn from_ptr(ptr: sys::virDomainDiskErrorPtr) -> DiskError {
+        DiskError {
+            disk: c_chars_to_string!((*ptr).disk),
+            error: DiskErrorCode::from_raw((*ptr).error as sys::virDomainDiskErrorCode),
+        }
+    }
+}
+
+/// Information about a mounted file system of a guest.
+#[derive(Clone, Debug)]
+pub struct FsInfo {
+    /// Path to mount point.
+    pub mountpoint: String,
+    /// Device name in the guest (e.g. "sda1").
+    pub name: String,
+    /// File system type.
+    pub fstype: String,
+    /// Aliases of the disk devices backing the file system.
+    pub devices: Vec<String>,
+}
+
+impl FsInfo {
+    /// # Safety
+    ///
+    /// The caller must ensure that the pointer is valid. The strings
+    /// are copied, the structure still needs to be freed by the
+    /// caller.
+    pub unsafe fn from_ptr(ptr: sys::virDomainFSInfoPtr) -> FsInfo {
+        let mut devices = Vec::with_capacity((*ptr).ndevAlias);
+        for x in 0..(*ptr).ndevAlias {
+            devices.push(c_chars_to_string!(*(*ptr).devAlias.add(x), nofree));
+        }
+        FsInfo {
+            mountpoint: c_chars_to_string!((*ptr).mountpoint, nofree),
+            name: c_chars_to_string!((*ptr).name, nofree),
+            fstype: c_chars_to_string!((*ptr).fstype, nofree),
+            devices,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+/// The kind of background job running on a domain.
+///
+/// See <https://libvirt.org/html/libvirt-libvirt-domain.html#virDomainJobType>
+pub enum JobType {
+    /// No job is active.
+    None,
+    /// The job has a finite completion time.
+    Bounded,
+    /// The job has no finite completion time.
+    Unbounded,
+    /// The job has finished successfully.
+    Completed,
+    /// The job hit an error, but cleanup has finished.
+    Failed,
+    /// The job was aborted, but cleanup has finished.
+    Cancelled,
+}
+
+impl_enum! {
+    enum: JobType,
+    raw: sys::virDomainJobType,
+    match: {
+        sys::VIR_DOMAIN_JOB_NONE => JobType::None,
+        sys::VIR_DOMAIN_JOB_BOUNDED => JobType::Bounded,
+        sys::VIR_DOMAIN_JOB_UNBOUNDED => JobType::Unbounded,
+        sys::VIR_DOMAIN_JOB_COMPLETED => JobType::Completed,
+        sys::VIR_DOMAIN_JOB_FAILED => JobType::Failed,
+        sys::VIR_DOMAIN_JOB_CANCELLED => JobType::Cancelled,
+        _ => JobType::None,
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+/// The operation which started a background job.
+///
+/// See <https://libvirt.org/html/libvirt-libvirt-domain.html#virDomainJobOperation>
+pub enum JobOperation {
+    Unknown,
+    /// Starting the domain.
+    Start,
+    /// Saving the domain state to a file.
+    Save,
+    /// Restoring the domain from a saved state.
+    Restore,
+    /// Incoming migration.
+    MigrationIn,
+    /// Outgoing migration.
+    MigrationOut,
+    /// Creating a snapshot.
+    Snapshot,
+    /// Reverting to a snapshot.
+    SnapshotRevert,
+    /// Dumping the domain memory.
+    Dump,
+    /// Running a backup job.
+    Backup,
+    /// Deleting a snapshot.
+    SnapshotDelete,
+}
+
+impl_enum! {
+    enum: JobOperation,
+    raw: sys::virDomainJobOperation,
+    match: {
+        sys::VIR_DOMAIN_JOB_OPERATION_UNKNOWN => JobOperation::Unknown,
+        sys::VIR_DOMAIN_JOB_OPERATION_START => JobOperation::Start,
+        sys::VIR_DOMAIN_JOB_OPERATION_SAVE => JobOperation::Save,
+        sys::VIR_DOMAIN_JOB_OPERATION_RESTORE => JobOperation::Restore,
+        sys::VIR_DOMAIN_JOB_OPERATION_MIGRATION_IN => JobOperation::MigrationIn,
+        sys::VIR_DOMAIN_JOB_OPERATION_MIGRATION_OUT => JobOperation::MigrationOut,
+        sys::VIR_DOMAIN_JOB_OPERATION_SNAPSHOT => JobOperation::Snapshot,
+        sys::VIR_DOMAIN_JOB_OPERATION_SNAPSHOT_REVERT => JobOperation::SnapshotRevert,
+        sys::VIR_DOMAIN_JOB_OPERATION_DUMP => JobOperation::Dump,
+        sys::VIR_DOMAIN_JOB_OPERATION_BACKUP => JobOperation::Backup,
+        sys::VIR_DOMAIN_JOB_OPERATION_SNAPSHOT_DELETE => JobOperation::SnapshotDelete,
+        _ => JobOperation::Unknown,
+    }
+}
+
+impl_flags! {
+    /// Flags used when querying job statistics.
+    ///
+    /// See <https://libvirt.org/html/libvirt-libvirt-domain.html#virDomainGetJobStatsFlags>
+    struct JobStatsFlags: sys::virDomainGetJobStatsFlags {
+        /// Return statistics of the most recently completed job.
+        const COMPLETED = sys::VIR_DOMAIN_JOB_STATS_COMPLETED;
+        /// Do not discard the statistics of the completed job once
+        /// they have been returned.
+        const KEEP_COMPLETED = sys::VIR_DOMAIN_JOB_STATS_KEEP_COMPLETED;
+    }
+}
+
 /// Information about the progress of a background job that is
 /// affecting a domain.
 #[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct JobStats {
     pub r#type: i32,
 
@@ -441,6 +2751,10 @@ pub struct JobStats {
     pub time_elapsed: Option<u64>,
     pub time_elapsed_net: Option<u64>,
     pub time_remaining: Option<u64>,
+
+    /// The statistics the other fields do not cover, or whose type was
+    /// not the expected one.
+    pub extra: TypedParamsMap,
 }
 
 macro_rules! job_stats_fields {
@@ -573,6 +2887,44 @@ macro_rules! job_stats_fields {
     };
 }
 
+impl JobStats {
+    /// Returns the kind of the job.
+    pub fn job_type(&self) -> JobType {
+        JobType::from_raw(self.r#type as sys::virDomainJobType)
+    }
+
+    /// Returns the operation which started the job, if reported.
+    pub fn job_operation(&self) -> Option<JobOperation> {
+        self.operation
+            .map(|op| JobOperation::from_raw(op as sys::virDomainJobOperation))
+    }
+}
+
+impl std::fmt::Display for JobStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{:?} job", self.job_type())?;
+        if let Some(op) = self.job_operation() {
+            write!(f, " ({:?})", op)?;
+        }
+        if let Some(elapsed) = self.time_elapsed {
+            write!(f, ", elapsed: {} ms", elapsed)?;
+        }
+        if let Some(remaining) = self.time_remaining {
+            write!(f, ", remaining: {} ms", remaining)?;
+        }
+        if let (Some(processed), Some(total)) = (self.data_processed, self.data_total) {
+            write!(f, ", data: {}/{} B", processed, total)?;
+        }
+        if let Some(bps) = self.mem_bps {
+            write!(f, ", memory bandwidth: {} B/s", bps)?;
+        }
+        if let Some(msg) = &self.error_message {
+            write!(f, ", error: {}", msg)?;
+        }
+        Ok(())
+    }
+}
+
 impl From<(i32, Vec<sys::virTypedParameter>)> for JobStats {
     fn from((r#type, params): (i32, Vec<sys::virTypedParameter>)) -> Self {
         let mut stats = Self {
@@ -582,7 +2934,7 @@ impl From<(i32, Vec<sys::virTypedParameter>)> for JobStats {
 
         let fields = job_stats_fields!(param_field_in, stats);
 
-        from_params(params, fields);
+        stats.extra = from_params(&params, fields);
 
         stats
     }
@@ -619,6 +2971,9 @@ pub struct SchedulerInfo {
     pub limit: Option<i64>,
     // Allocation scheduler shares
     pub shares: Option<i32>,
+    /// The parameters the other fields do not cover, or whose type was
+    /// not the expected one. They are not passed back by `to_vec()`.
+    pub extra: TypedParamsMap,
 }
 
 macro_rules! scheduler_info_fields {
@@ -689,22 +3044,376 @@ impl SchedulerInfo {
             ..Default::default()
         };
         let fields = scheduler_info_fields!(param_field_in, ret);
-        from_params(vec, fields);
+        ret.extra = from_params(&vec, fields);
         ret
     }
 
-    pub fn to_vec(&self) -> Vec<sys::virTypedParameter> {
+    pub fn to_vec(&self) -> Result<Vec<sys::virTypedParameter>, Error> {
         let fields = scheduler_info_fields!(param_field_out, self);
         to_params(fields)
     }
 }
 
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy, Hash)]
+/// The format of a domain core dump.
+///
+/// See <https://libvirt.org/html/libvirt-libvirt-domain.html#virDomainCoreDumpFormat>
+pub enum DumpFormat {
+    /// Dump guest memory in raw format.
+    #[default]
+    Raw,
+    /// Dump guest memory in kdump-compressed format, with zlib compression.
+    KdumpZlib,
+    /// Dump guest memory in kdump-compressed format, with lzo compression.
+    KdumpLzo,
+    /// Dump guest memory in kdump-compressed format, with snappy compression.
+    KdumpSnappy,
+    /// Dump guest memory in Windows crash dump format.
+    WinDmp,
+}
+
+impl_enum! {
+    enum: DumpFormat,
+    raw: sys::virDomainCoreDumpFormat,
+    match: {
+        sys::VIR_DOMAIN_CORE_DUMP_FORMAT_RAW => DumpFormat::Raw,
+        sys::VIR_DOMAIN_CORE_DUMP_FORMAT_KDUMP_ZLIB => DumpFormat::KdumpZlib,
+        sys::VIR_DOMAIN_CORE_DUMP_FORMAT_KDUMP_LZO => DumpFormat::KdumpLzo,
+        sys::VIR_DOMAIN_CORE_DUMP_FORMAT_KDUMP_SNAPPY => DumpFormat::KdumpSnappy,
+        sys::VIR_DOMAIN_CORE_DUMP_FORMAT_WIN_DMP => DumpFormat::WinDmp,
+        _ => DumpFormat::Raw,
+    }
+}
+
+impl_flags! {
+    /// Flags used when dumping the core of a domain.
+    ///
+    /// See <https://libvirt.org/html/libvirt-libvirt-domain.html#virDomainCoreDumpFlags>
+    struct DomainCoreDumpFlags: sys::virDomainCoreDumpFlags {
+        /// Crash the domain after dumping.
+        const CRASH = sys::VIR_DUMP_CRASH;
+        /// Do not pause the domain while dumping.
+        const LIVE = sys::VIR_DUMP_LIVE;
+        /// Avoid the file system cache when saving.
+        const BYPASS_CACHE = sys::VIR_DUMP_BYPASS_CACHE;
+        /// Reset the domain after dumping.
+        const RESET = sys::VIR_DUMP_RESET;
+        /// Only dump the guest memory.
+        const MEMORY_ONLY = sys::VIR_DUMP_MEMORY_ONLY;
+    }
+}
+
+/// Options used by [`Domain::core_dump_params()`].
+#[derive(Clone, Debug, Default)]
+pub struct CoreDumpOptions {
+    /// The format of the dump file.
+    ///
+    /// Formats other than [`DumpFormat::Raw`] require `memory_only`.
+    pub format: DumpFormat,
+    /// Crash the domain after dumping.
+    pub crash: bool,
+    /// Do not pause the domain while dumping.
+    pub live: bool,
+    /// Avoid the file system cache when saving.
+    pub bypass_cache: bool,
+    /// Reset the domain after dumping.
+    pub reset: bool,
+    /// Only dump the guest memory.
+    pub memory_only: bool,
+}
+
+impl CoreDumpOptions {
+    /// Returns the flags matching these options.
+    pub fn flags(&self) -> DomainCoreDumpFlags {
+        let mut flags = DomainCoreDumpFlags::empty();
+        if self.crash {
+            flags |= DomainCoreDumpFlags::CRASH;
+        }
+        if self.live {
+            flags |= DomainCoreDumpFlags::LIVE;
+        }
+        if self.bypass_cache {
+            flags |= DomainCoreDumpFlags::BYPASS_CACHE;
+        }
+        if self.reset {
+            flags |= DomainCoreDumpFlags::RESET;
+        }
+        if self.memory_only {
+            flags |= DomainCoreDumpFlags::MEMORY_ONLY;
+        }
+        flags
+    }
+}
+
+/// Options used by [`Domain::migrate_set_max_speed()`] and
+/// [`Domain::migrate_get_max_speed()`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MigrateMaxSpeedOptions {
+    /// Apply to the post-copy phase of the migration instead of the
+    /// pre-copy one.
+    pub postcopy: bool,
+}
+
+impl MigrateMaxSpeedOptions {
+    /// Returns the `VIR_DOMAIN_MIGRATE_MAX_SPEED_*` flags matching these
+    /// options.
+    pub fn flags(&self) -> sys::virDomainMigrateMaxSpeedFlags {
+        let mut flags = 0;
+        if self.postcopy {
+            flags |= sys::VIR_DOMAIN_MIGRATE_MAX_SPEED_POSTCOPY;
+        }
+        flags
+    }
+}
+
+impl From<sys::virDomainMigrateMaxSpeedFlags> for MigrateMaxSpeedOptions {
+    fn from(flags: sys::virDomainMigrateMaxSpeedFlags) -> MigrateMaxSpeedOptions {
+        MigrateMaxSpeedOptions {
+            postcopy: flags & sys::VIR_DOMAIN_MIGRATE_MAX_SPEED_POSTCOPY != 0,
+        }
+    }
+}
+
+/// How long to wait for the reply of a guest agent command.
+///
+/// See <https://libvirt.org/html/libvirt-libvirt-qemu.html#virDomainQemuAgentCommandTimeoutValues>
+#[cfg(feature = "qemu")]
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum QemuAgentTimeout {
+    /// Wait until the agent replies.
+    Block,
+    /// Wait for the default timeout of libvirt.
+    Default,
+    /// Do not wait for a reply.
+    NoWait,
+    /// Wait for the given number of seconds.
+    Seconds(u32),
+}
+
+#[cfg(feature = "qemu")]
+impl QemuAgentTimeout {
+    /// Converts the timeout to the value expected by libvirt.
+    pub fn to_raw(self) -> libc::c_int {
+        match self {
+            QemuAgentTimeout::Block => sys::VIR_DOMAIN_QEMU_AGENT_COMMAND_BLOCK,
+            QemuAgentTimeout::Default => sys::VIR_DOMAIN_QEMU_AGENT_COMMAND_DEFAULT,
+            QemuAgentTimeout::NoWait => sys::VIR_DOMAIN_QEMU_AGENT_COMMAND_NOWAIT,
+            QemuAgentTimeout::Seconds(s) => s.min(libc::c_int::MAX as u32) as libc::c_int,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+/// The code set of the keycodes sent to a domain.
+///
+/// See <https://libvirt.org/html/libvirt-libvirt-domain.html#virKeycodeSet>
+pub enum KeycodeSet {
+    /// Linux input event codes, see [`linux_keycode()`].
+    Linux,
+    Xt,
+    AtSet1,
+    AtSet2,
+    AtSet3,
+    Osx,
+    XtKbd,
+    Usb,
+    Win32,
+    Qnum,
+}
+
+impl_enum! {
+    enum: KeycodeSet,
+    raw: sys::virKeycodeSet,
+    match: {
+        sys::VIR_KEYCODE_SET_LINUX => KeycodeSet::Linux,
+        sys::VIR_KEYCODE_SET_XT => KeycodeSet::Xt,
+        sys::VIR_KEYCODE_SET_ATSET1 => KeycodeSet::AtSet1,
+        sys::VIR_KEYCODE_SET_ATSET2 => KeycodeSet::AtSet2,
+        sys::VIR_KEYCODE_SET_ATSET3 => KeycodeSet::AtSet3,
+        sys::VIR_KEYCODE_SET_OSX => KeycodeSet::Osx,
+        sys::VIR_KEYCODE_SET_XT_KBD => KeycodeSet::XtKbd,
+        sys::VIR_KEYCODE_SET_USB => KeycodeSet::Usb,
+        sys::VIR_KEYCODE_SET_WIN32 => KeycodeSet::Win32,
+        sys::VIR_KEYCODE_SET_QNUM => KeycodeSet::Qnum,
+        _ => KeycodeSet::Linux,
+    }
+}
+
+const LINUX_KEYCODES: &[(&str, u32)] = &[
+    ("ESC", 1),
+    ("1", 2),
+    ("2", 3),
+    ("3", 4),
+    ("4", 5),
+    ("5", 6),
+    ("6", 7),
+    ("7", 8),
+    ("8", 9),
+    ("9", 10),
+    ("0", 11),
+    ("MINUS", 12),
+    ("EQUAL", 13),
+    ("BACKSPACE", 14),
+    ("TAB", 15),
+    ("Q", 16),
+    ("W", 17),
+    ("E", 18),
+    ("R", 19),
+    ("T", 20),
+    ("Y", 21),
+    ("U", 22),
+    ("I", 23),
+    ("O", 24),
+    ("P", 25),
+    ("LEFTBRACE", 26),
+    ("RIGHTBRACE", 27),
+    ("ENTER", 28),
+    ("LEFTCTRL", 29),
+    ("A", 30),
+    ("S", 31),
+    ("D", 32),
+    ("F", 33),
+    ("G", 34),
+    ("H", 35),
+    ("J", 36),
+    ("K", 37),
+    ("L", 38),
+    ("SEMICOLON", 39),
+    ("APOSTROPHE", 40),
+    ("GRAVE", 41),
+    ("LEFTSHIFT", 42),
+    ("BACKSLASH", 43),
+    ("Z", 44),
+    ("X", 45),
+    ("C", 46),
+    ("V", 47),
+    ("B", 48),
+    ("N", 49),
+    ("M", 50),
+    ("COMMA", 51),
+    ("DOT", 52),
+    ("SLASH", 53),
+    ("RIGHTSHIFT", 54),
+    ("KPASTERISK", 55),
+    ("LEFTALT", 56),
+    ("SPACE", 57),
+    ("CAPSLOCK", 58),
+    ("F1", 59),
+    ("F2", 60),
+    ("F3", 61),
+    ("F4", 62),
+    ("F5", 63),
+    ("F6", 64),
+    ("F7", 65),
+    ("F8", 66),
+    ("F9", 67),
+    ("F10", 68),
+    ("NUMLOCK", 69),
+    ("SCROLLLOCK", 70),
+    ("F11", 87),
+    ("F12", 88),
+    ("RIGHTCTRL", 97),
+    ("SYSRQ", 99),
+    ("RIGHTALT", 100),
+    ("HOME", 102),
+    ("UP", 103),
+    ("PAGEUP", 104),
+    ("LEFT", 105),
+    ("RIGHT", 106),
+    ("END", 107),
+    ("DOWN", 108),
+    ("PAGEDOWN", 109),
+    ("INSERT", 110),
+    ("DELETE", 111),
+    ("PAUSE", 119),
+    ("LEFTMETA", 125),
+    ("RIGHTMETA", 126),
+    ("COMPOSE", 127),
+];
+
+/// Translates the name of a Linux key to its keycode, for use with
+/// [`KeycodeSet::Linux`].
+///
+/// Names follow `linux/input-event-codes.h` and are matched without
+/// regard to case, with or without their `KEY_` prefix, e.g.
+/// `KEY_LEFTCTRL`, `leftctrl` or `a`. Returns `None` for unknown
+/// names.
+pub fn linux_keycode(name: &str) -> Option<u32> {
+    let name = name.to_ascii_uppercase();
+    let name = name.strip_prefix("KEY_").unwrap_or(&name);
+    LINUX_KEYCODES
+        .iter()
+        .find(|(n, _)| *n == name)
+        .map(|(_, code)| *code)
+}
+
+impl_flags! {
+    /// Flags used when resizing a block device.
+    ///
+    /// See <https://libvirt.org/html/libvirt-libvirt-domain.html#virDomainBlockResizeFlags>
+    struct BlockResizeFlags: sys::virDomainBlockResizeFlags {
+        /// The size is given in bytes instead of KiB.
+        const BYTES = sys::VIR_DOMAIN_BLOCK_RESIZE_BYTES;
+        /// Grow the device to the full capacity of its backing storage; the size must then be 0.
+        const CAPACITY = sys::VIR_DOMAIN_BLOCK_RESIZE_CAPACITY;
+    }
+}
+
+impl_flags! {
+    /// The methods a hypervisor may use to shut down or reboot a
+    /// domain. When empty, the hypervisor picks the method it
+    /// considers best.
+    ///
+    /// See <https://libvirt.org/html/libvirt-libvirt-domain.html#virDomainShutdownFlagValues>
+    struct ShutdownMethod: sys::virDomainShutdownFlagValues {
+        /// Send an ACPI power button event.
+        const ACPI_POWER_BTN = sys::VIR_DOMAIN_SHUTDOWN_ACPI_POWER_BTN;
+        /// Ask the guest agent.
+        const GUEST_AGENT = sys::VIR_DOMAIN_SHUTDOWN_GUEST_AGENT;
+        /// Talk to the init process of a container.
+        const INITCTL = sys::VIR_DOMAIN_SHUTDOWN_INITCTL;
+        /// Send a signal to the init process of a container.
+        const SIGNAL = sys::VIR_DOMAIN_SHUTDOWN_SIGNAL;
+        /// Use a paravirtualized event.
+        const PARAVIRT = sys::VIR_DOMAIN_SHUTDOWN_PARAVIRT;
+    }
+}
+
+/// The methods a hypervisor may use to reboot a domain.
+///
+/// The reboot flags share their values with the shutdown ones.
+///
+/// See <https://libvirt.org/html/libvirt-libvirt-domain.html#virDomainRebootFlagValues>
+pub type RebootMethod = ShutdownMethod;
+
+impl_flags! {
+    /// The kinds of messages returned by [`Domain::get_messages()`].
+    ///
+    /// See <https://libvirt.org/html/libvirt-libvirt-domain.html#virDomainMessageType>
+    struct DomainMessageType: sys::virDomainMessageType {
+        /// Use of deprecated features of the hypervisor.
+        const DEPRECATION = sys::VIR_DOMAIN_MESSAGE_DEPRECATION;
+        /// Actions which taint the domain, making it unsupported.
+        const TAINTING = sys::VIR_DOMAIN_MESSAGE_TAINTING;
+    }
+}
+
+/// How a domain was stopped by [`Domain::shutdown_and_wait()`].
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum ShutdownOutcome {
+    /// The guest shut down by itself.
+    ShutOff,
+    /// The guest did not shut down in time and was destroyed.
+    Destroyed,
+}
+
 /// Provides APIs for the management of domains.
 ///
 /// See <https://libvirt.org/html/libvirt-libvirt-domain.html>
 #[derive(Debug)]
 pub struct Domain {
     ptr: Option<sys::virDomainPtr>,
+    identity: Option<Uuid>,
 }
 
 unsafe impl Send for Domain {}
@@ -714,7 +3423,7 @@ impl Drop for Domain {
     fn drop(&mut self) {
         if self.ptr.is_some() {
             if let Err(e) = self.free() {
-                panic!("Unable to drop memory for Domain: {}", e)
+                report_drop_error("Domain", e);
             }
         }
     }
@@ -733,17 +3442,23 @@ impl Clone for Domain {
     }
 }
 
+impl_identity!(Domain, get_uuid);
+
 impl Domain {
     /// # Safety
     ///
     /// The caller must ensure that the pointer is valid.
     pub unsafe fn from_ptr(ptr: sys::virDomainPtr) -> Domain {
-        Domain { ptr: Some(ptr) }
+        Domain {
+            ptr: Some(ptr),
+            identity: Default::default(),
+        }
+        .load_identity()
     }
 
     fn add_ref(&self) -> Result<Domain, Error> {
         unsafe {
-            if sys::virDomainRef(self.as_ptr()) == -1 {
+            if ffi!(sys::virDomainRef(self.as_ptr())) == -1 {
                 return Err(Error::last_error());
             }
         }
@@ -756,7 +3471,7 @@ impl Domain {
     }
 
     pub fn get_connect(&self) -> Result<Connect, Error> {
-        let ptr = unsafe { sys::virDomainGetConnect(self.as_ptr()) };
+        let ptr = unsafe { ffi!(sys::virDomainGetConnect(self.as_ptr())) };
         if ptr.is_null() {
             return Err(Error::last_error());
         }
@@ -764,7 +3479,7 @@ impl Domain {
     }
 
     pub fn lookup_by_id(conn: &Connect, id: u32) -> Result<Domain, Error> {
-        let ptr = unsafe { sys::virDomainLookupByID(conn.as_ptr(), id as libc::c_int) };
+        let ptr = unsafe { ffi!(sys::virDomainLookupByID(conn.as_ptr(), id as libc::c_int)) };
         if ptr.is_null() {
             return Err(Error::last_error());
         }
@@ -772,8 +3487,8 @@ impl Domain {
     }
 
     pub fn lookup_by_name(conn: &Connect, id: &str) -> Result<Domain, Error> {
-        let id_buf = CString::new(id).unwrap();
-        let ptr = unsafe { sys::virDomainLookupByName(conn.as_ptr(), id_buf.as_ptr()) };
+        let id_buf = CString::new(id)?;
+        let ptr = unsafe { ffi!(sys::virDomainLookupByName(conn.as_ptr(), id_buf.as_ptr())) };
         if ptr.is_null() {
             return Err(Error::last_error());
         }
@@ -781,7 +3496,12 @@ impl Domain {
     }
 
     pub fn lookup_by_uuid(conn: &Connect, uuid: Uuid) -> Result<Domain, Error> {
-        let ptr = unsafe { sys::virDomainLookupByUUID(conn.as_ptr(), uuid.as_bytes().as_ptr()) };
+        let ptr = unsafe {
+            ffi!(sys::virDomainLookupByUUID(
+                conn.as_ptr(),
+                uuid.as_bytes().as_ptr()
+            ))
+        };
         if ptr.is_null() {
             return Err(Error::last_error());
         }
@@ -789,14 +3509,37 @@ impl Domain {
     }
 
     pub fn lookup_by_uuid_string(conn: &Connect, uuid: &str) -> Result<Domain, Error> {
-        let uuid_buf = CString::new(uuid).unwrap();
-        let ptr = unsafe { sys::virDomainLookupByUUIDString(conn.as_ptr(), uuid_buf.as_ptr()) };
+        let uuid_buf = CString::new(uuid)?;
+        let ptr = unsafe {
+            ffi!(sys::virDomainLookupByUUIDString(
+                conn.as_ptr(),
+                uuid_buf.as_ptr()
+            ))
+        };
         if ptr.is_null() {
             return Err(Error::last_error());
         }
         Ok(unsafe { Domain::from_ptr(ptr) })
     }
 
+    /// Look up a domain the way `virsh` does: by ID if `identifier`
+    /// is numeric, then by UUID if it parses as one, then by name.
+    ///
+    /// The error of the last lookup is returned when none matches.
+    pub fn lookup(conn: &Connect, identifier: &str) -> Result<Domain, Error> {
+        if let Ok(id) = identifier.parse::<u32>() {
+            if let Ok(dom) = Domain::lookup_by_id(conn, id) {
+                return Ok(dom);
+            }
+        }
+        if let Ok(uuid) = Uuid::parse_str(identifier) {
+            if let Ok(dom) = Domain::lookup_by_uuid(conn, uuid) {
+                return Ok(dom);
+            }
+        }
+        Domain::lookup_by_name(conn, identifier)
+    }
+
     /// Extracts domain state.
     ///
     /// Each state can be accompanied with a reason (if known) which
@@ -804,7 +3547,14 @@ impl Domain {
     pub fn get_state(&self) -> Result<(sys::virDomainState, i32), Error> {
         let mut state: libc::c_int = -1;
         let mut reason: libc::c_int = -1;
-        let ret = unsafe { sys::virDomainGetState(self.as_ptr(), &mut state, &mut reason, 0) };
+        let ret = unsafe {
+            ffi!(sys::virDomainGetState(
+                self.as_ptr(),
+                &mut state,
+                &mut reason,
+                0
+            ))
+        };
         if ret == -1 {
             return Err(Error::last_error());
         }
@@ -813,7 +3563,7 @@ impl Domain {
 
     /// Get the public name of the domain.
     pub fn get_name(&self) -> Result<String, Error> {
-        let n = unsafe { sys::virDomainGetName(self.as_ptr()) };
+        let n = unsafe { ffi!(sys::virDomainGetName(self.as_ptr())) };
         if n.is_null() {
             return Err(Error::last_error());
         }
@@ -822,7 +3572,7 @@ impl Domain {
 
     /// Get the type of domain operating system.
     pub fn get_os_type(&self) -> Result<String, Error> {
-        let n = unsafe { sys::virDomainGetOSType(self.as_ptr()) };
+        let n = unsafe { ffi!(sys::virDomainGetOSType(self.as_ptr())) };
         if n.is_null() {
             return Err(Error::last_error());
         }
@@ -830,8 +3580,16 @@ impl Domain {
     }
 
     /// Get the hostname for that domain.
-    pub fn get_hostname(&self, flags: u32) -> Result<String, Error> {
-        let n = unsafe { sys::virDomainGetHostname(self.as_ptr(), flags as libc::c_uint) };
+    ///
+    /// With `None`, the hypervisor picks the source it supports.
+    pub fn get_hostname(&self, source: Option<HostnameSource>) -> Result<String, Error> {
+        let flags = source.map_or(0, |source| source.to_raw());
+        let n = unsafe {
+            ffi!(sys::virDomainGetHostname(
+                self.as_ptr(),
+                flags as libc::c_uint
+            ))
+        };
         if n.is_null() {
             return Err(Error::last_error());
         }
@@ -841,7 +3599,7 @@ impl Domain {
     pub fn get_uuid(&self) -> Result<Uuid, Error> {
         let mut uuid: [libc::c_uchar; sys::VIR_UUID_BUFLEN as usize] =
             [0; sys::VIR_UUID_BUFLEN as usize];
-        let ret = unsafe { sys::virDomainGetUUID(self.as_ptr(), uuid.as_mut_ptr()) };
+        let ret = unsafe { ffi!(sys::virDomainGetUUID(self.as_ptr(), uuid.as_mut_ptr())) };
         if ret == -1 {
             return Err(Error::last_error());
         }
@@ -854,7 +3612,12 @@ impl Domain {
     pub fn get_uuid_string(&self) -> Result<String, Error> {
         let mut uuid: [libc::c_char; sys::VIR_UUID_STRING_BUFLEN as usize] =
             [0; sys::VIR_UUID_STRING_BUFLEN as usize];
-        let ret = unsafe { sys::virDomainGetUUIDString(self.as_ptr(), uuid.as_mut_ptr()) };
+        let ret = unsafe {
+            ffi!(sys::virDomainGetUUIDString(
+                self.as_ptr(),
+                uuid.as_mut_ptr()
+            ))
+        };
         if ret == -1 {
             return Err(Error::last_error());
         }
@@ -863,7 +3626,7 @@ impl Domain {
 
     /// Get the hypervisor ID number for the domain
     pub fn get_id(&self) -> Option<u32> {
-        let ret = unsafe { sys::virDomainGetID(self.as_ptr()) };
+        let ret = unsafe { ffi!(sys::virDomainGetID(self.as_ptr())) };
         if ret as i32 == -1 {
             return None;
         }
@@ -874,14 +3637,31 @@ impl Domain {
     /// be reused later to relaunch the domain with [`create_xml()`].
     ///
     /// [`create_xml()`]: Domain::create_xml
-    pub fn get_xml_desc(&self, flags: sys::virDomainCreateFlags) -> Result<String, Error> {
-        let xml = unsafe { sys::virDomainGetXMLDesc(self.as_ptr(), flags) };
+    pub fn get_xml_desc(&self, flags: impl Into<DomainXmlFlags>) -> Result<String, Error> {
+        let xml = unsafe {
+            ffi!(sys::virDomainGetXMLDesc(
+                self.as_ptr(),
+                flags.into().bits() as libc::c_uint
+            ))
+        };
         if xml.is_null() {
             return Err(Error::last_error());
         }
         Ok(unsafe { c_chars_to_string!(xml) })
     }
 
+    /// Provide the XML description of the persistent configuration
+    /// of the domain, which takes effect on its next boot.
+    pub fn get_xml_inactive(&self) -> Result<String, Error> {
+        self.get_xml_desc(DomainXmlFlags::INACTIVE)
+    }
+
+    /// Provide an XML description of the domain suitable for defining
+    /// it on another host, e.g. as the destination XML of a migration.
+    pub fn get_xml_migratable(&self) -> Result<String, Error> {
+        self.get_xml_desc(DomainXmlFlags::MIGRATABLE)
+    }
+
     /// Launch a defined domain. If the call succeeds the domain moves
     /// from the defined to the running domains pools. The domain will
     /// be paused only if restoring from managed state created from a
@@ -889,7 +3669,7 @@ impl Domain {
     ///
     /// [`create_with_flags()`]: Domain::create_with_flags
     pub fn create(&self) -> Result<u32, Error> {
-        let ret = unsafe { sys::virDomainCreate(self.as_ptr()) };
+        let ret = unsafe { ffi!(sys::virDomainCreate(self.as_ptr())) };
         if ret == -1 {
             return Err(Error::last_error());
         }
@@ -898,8 +3678,39 @@ impl Domain {
 
     /// Launch a defined domain. If the call succeeds the domain moves
     /// from the defined to the running domains pools.
-    pub fn create_with_flags(&self, flags: sys::virDomainCreateFlags) -> Result<u32, Error> {
-        let res = unsafe { sys::virDomainCreateWithFlags(self.as_ptr(), flags as libc::c_uint) };
+    pub fn create_with_flags(&self, flags: impl Into<DomainCreateFlags>) -> Result<u32, Error> {
+        let res = unsafe {
+            ffi!(sys::virDomainCreateWithFlags(
+                self.as_ptr(),
+                flags.into().bits() as libc::c_uint
+            ))
+        };
+        if res == -1 {
+            return Err(Error::last_error());
+        }
+        Ok(res as u32)
+    }
+
+    /// Launch a defined domain, passing the open file descriptors
+    /// `files` to its init process.
+    ///
+    /// Only the LXC driver supports this; the descriptors show up in
+    /// the container as fd 3, 4, and so on. They are not closed by
+    /// this call.
+    pub fn create_with_files(
+        &self,
+        files: &[RawFd],
+        flags: impl Into<DomainCreateFlags>,
+    ) -> Result<u32, Error> {
+        let mut files = files.to_vec();
+        let res = unsafe {
+            ffi!(sys::virDomainCreateWithFiles(
+                self.as_ptr(),
+                files.len() as libc::c_uint,
+                files.as_mut_ptr(),
+                flags.into().bits() as libc::c_uint,
+            ))
+        };
         if res == -1 {
             return Err(Error::last_error());
         }
@@ -911,7 +3722,7 @@ impl Domain {
     /// set of the information can be extracted.
     pub fn get_info(&self) -> Result<DomainInfo, Error> {
         let mut pinfo = mem::MaybeUninit::uninit();
-        let res = unsafe { sys::virDomainGetInfo(self.as_ptr(), pinfo.as_mut_ptr()) };
+        let res = unsafe { ffi!(sys::virDomainGetInfo(self.as_ptr(), pinfo.as_mut_ptr())) };
         if res == -1 {
             return Err(Error::last_error());
         }
@@ -932,11 +3743,15 @@ impl Domain {
     pub fn create_xml(
         conn: &Connect,
         xml: &str,
-        flags: sys::virDomainCreateFlags,
+        flags: impl Into<DomainCreateFlags>,
     ) -> Result<Domain, Error> {
-        let xml_buf = CString::new(xml).unwrap();
+        let xml_buf = CString::new(xml)?;
         let ptr = unsafe {
-            sys::virDomainCreateXML(conn.as_ptr(), xml_buf.as_ptr(), flags as libc::c_uint)
+            ffi!(sys::virDomainCreateXML(
+                conn.as_ptr(),
+                xml_buf.as_ptr(),
+                flags.into().bits() as libc::c_uint,
+            ))
         };
         if ptr.is_null() {
             return Err(Error::last_error());
@@ -958,8 +3773,8 @@ impl Domain {
     ///
     /// [`undefine()`]: Domain::undefine
     pub fn define_xml(conn: &Connect, xml: &str) -> Result<Domain, Error> {
-        let xml_buf = CString::new(xml).unwrap();
-        let ptr = unsafe { sys::virDomainDefineXML(conn.as_ptr(), xml_buf.as_ptr()) };
+        let xml_buf = CString::new(xml)?;
+        let ptr = unsafe { ffi!(sys::virDomainDefineXML(conn.as_ptr(), xml_buf.as_ptr())) };
         if ptr.is_null() {
             return Err(Error::last_error());
         }
@@ -982,11 +3797,15 @@ impl Domain {
     pub fn define_xml_flags(
         conn: &Connect,
         xml: &str,
-        flags: sys::virDomainDefineFlags,
+        flags: impl Into<DomainDefineFlags>,
     ) -> Result<Domain, Error> {
-        let xml_buf = CString::new(xml).unwrap();
+        let xml_buf = CString::new(xml)?;
         let ptr = unsafe {
-            sys::virDomainDefineXMLFlags(conn.as_ptr(), xml_buf.as_ptr(), flags as libc::c_uint)
+            ffi!(sys::virDomainDefineXMLFlags(
+                conn.as_ptr(),
+                xml_buf.as_ptr(),
+                flags.into().bits() as libc::c_uint
+            ))
         };
         if ptr.is_null() {
             return Err(Error::last_error());
@@ -999,7 +3818,7 @@ impl Domain {
     /// the hypervisor. This does not free the associated virDomainPtr
     /// object. This function may require privileged access.
     pub fn destroy(&self) -> Result<(), Error> {
-        let ret = unsafe { sys::virDomainDestroy(self.as_ptr()) };
+        let ret = unsafe { ffi!(sys::virDomainDestroy(self.as_ptr())) };
         if ret == -1 {
             return Err(Error::last_error());
         }
@@ -1014,7 +3833,14 @@ impl Domain {
     /// Note that there is a risk of data loss caused by reset without
     /// any guest OS shutdown.
     pub fn reset(&self) -> Result<u32, Error> {
-        let ret = unsafe { sys::virDomainReset(self.as_ptr(), 0) };
+        self.reset_flags(0)
+    }
+
+    /// Reset a domain immediately, like [`reset()`](Domain::reset).
+    ///
+    /// * `flags` - Extra flags; not used yet, so callers should always pass 0.
+    pub fn reset_flags(&self, flags: u32) -> Result<u32, Error> {
+        let ret = unsafe { ffi!(sys::virDomainReset(self.as_ptr(), flags as libc::c_uint)) };
         if ret == -1 {
             return Err(Error::last_error());
         }
@@ -1026,7 +3852,7 @@ impl Domain {
     /// the hypervisor. This does not free the associated virDomainPtr
     /// object. This function may require privileged access.
     pub fn destroy_flags(&self, flags: sys::virDomainDestroyFlagsValues) -> Result<u32, Error> {
-        let ret = unsafe { sys::virDomainDestroyFlags(self.as_ptr(), flags) };
+        let ret = unsafe { ffi!(sys::virDomainDestroyFlags(self.as_ptr(), flags)) };
         if ret == -1 {
             return Err(Error::last_error());
         }
@@ -1048,7 +3874,7 @@ impl Domain {
     ///
     /// [`destroy()`]: Domain::destroy
     pub fn shutdown(&self) -> Result<u32, Error> {
-        let ret = unsafe { sys::virDomainShutdown(self.as_ptr()) };
+        let ret = unsafe { ffi!(sys::virDomainShutdown(self.as_ptr())) };
         if ret == -1 {
             return Err(Error::last_error());
         }
@@ -1070,27 +3896,76 @@ impl Domain {
     /// (see virDomainSnapshotNum()), then that metadata will automatically
     /// be deleted when the domain quits.
     ///
-    /// If flags is set to zero, then the hypervisor will choose the method of
+    /// If flags is empty, then the hypervisor will choose the method of
     /// shutdown it considers best. To have greater control pass one or more of
-    /// the [`sys::virDomainShutdownFlagValues`]. The order in which the hypervisor tries
+    /// the [`ShutdownMethod`] flags. The order in which the hypervisor tries
     /// each shutdown method is undefined, and a hypervisor is not required to
     /// support all methods.
     ///
-    /// To use guest agent [`sys::VIR_DOMAIN_SHUTDOWN_GUEST_AGENT`] the domain XML must
+    /// To use guest agent [`ShutdownMethod::GUEST_AGENT`] the domain XML must
     /// have \<channel\> configured.
-    pub fn shutdown_flags(&self, flags: sys::virDomainShutdownFlagValues) -> Result<u32, Error> {
-        let ret = unsafe { sys::virDomainShutdownFlags(self.as_ptr(), flags as libc::c_uint) };
+    pub fn shutdown_flags(&self, flags: impl Into<ShutdownMethod>) -> Result<u32, Error> {
+        let ret = unsafe {
+            ffi!(sys::virDomainShutdownFlags(
+                self.as_ptr(),
+                flags.into().bits() as libc::c_uint
+            ))
+        };
         if ret == -1 {
             return Err(Error::last_error());
         }
         Ok(ret as u32)
     }
 
+    /// Shutdown a domain and wait until it is shut off.
+    ///
+    /// The shutdown request is issued with [`shutdown_flags()`] and
+    /// the state of the domain is then polled. If the domain is still
+    /// running after `timeout`, it is destroyed when
+    /// `destroy_on_timeout` is set, otherwise an
+    /// [`ErrorNumber::OperationTimeout`] error is returned.
+    ///
+    /// [`shutdown_flags()`]: Domain::shutdown_flags
+    /// [`ErrorNumber::OperationTimeout`]: crate::error::ErrorNumber::OperationTimeout
+    pub fn shutdown_and_wait(
+        &self,
+        timeout: Duration,
+        flags: impl Into<ShutdownMethod>,
+        destroy_on_timeout: bool,
+    ) -> Result<ShutdownOutcome, Error> {
+        const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+        self.shutdown_flags(flags)?;
+        let deadline = Instant::now() + timeout;
+        loop {
+            match self.is_active() {
+                Ok(false) => return Ok(ShutdownOutcome::ShutOff),
+                Ok(true) => {}
+                // A transient domain is gone once it is shut off.
+                Err(e) if e.code() == ErrorNumber::NoDomain => return Ok(ShutdownOutcome::ShutOff),
+                Err(e) => return Err(e),
+            }
+            let now = Instant::now();
+            if now >= deadline {
+                break;
+            }
+            thread::sleep(POLL_INTERVAL.min(deadline - now));
+        }
+        if !destroy_on_timeout {
+            return Err(Error::from_timeout(format!(
+                "domain did not shut down within {:?}",
+                timeout
+            )));
+        }
+        self.destroy()?;
+        Ok(ShutdownOutcome::Destroyed)
+    }
+
     /// Reboot a domain.
     ///
     /// The domain object is still usable thereafter.
-    pub fn reboot(&self, flags: sys::virDomainRebootFlagValues) -> Result<(), Error> {
-        let ret = unsafe { sys::virDomainReboot(self.as_ptr(), flags) };
+    pub fn reboot(&self, flags: impl Into<RebootMethod>) -> Result<(), Error> {
+        let ret = unsafe { ffi!(sys::virDomainReboot(self.as_ptr(), flags.into().bits())) };
         if ret == -1 {
             return Err(Error::last_error());
         }
@@ -1109,7 +3984,7 @@ impl Domain {
     ///
     /// [`VIR_DOMAIN_PMSUSPENDED`]: sys::VIR_DOMAIN_PMSUSPENDED
     pub fn suspend(&self) -> Result<u32, Error> {
-        let ret = unsafe { sys::virDomainSuspend(self.as_ptr()) };
+        let ret = unsafe { ffi!(sys::virDomainSuspend(self.as_ptr())) };
         if ret == -1 {
             return Err(Error::last_error());
         }
@@ -1126,16 +4001,53 @@ impl Domain {
     /// [`suspend()`]: Domain::suspend
     /// [`VIR_DOMAIN_PMSUSPENDED`]: sys::VIR_DOMAIN_PMSUSPENDED
     pub fn resume(&self) -> Result<u32, Error> {
-        let ret = unsafe { sys::virDomainResume(self.as_ptr()) };
+        let ret = unsafe { ffi!(sys::virDomainResume(self.as_ptr())) };
         if ret == -1 {
             return Err(Error::last_error());
         }
         Ok(ret as u32)
     }
 
+    /// Suspends the guest into the power management state `target`,
+    /// using the guest agent.
+    ///
+    /// The `duration` is the number of seconds to stay suspended, 0
+    /// meaning until woken up by [`pm_wakeup()`] or another event.
+    ///
+    /// [`pm_wakeup()`]: Domain::pm_wakeup
+    pub fn pm_suspend_for_duration(
+        &self,
+        target: SuspendTarget,
+        duration: u64,
+        flags: u32,
+    ) -> Result<(), Error> {
+        let ret = unsafe {
+            ffi!(sys::virDomainPMSuspendForDuration(
+                self.as_ptr(),
+                target.to_raw() as libc::c_uint,
+                duration as libc::c_ulonglong,
+                flags as libc::c_uint,
+            ))
+        };
+        if ret == -1 {
+            return Err(Error::last_error());
+        }
+        Ok(())
+    }
+
+    /// Injects a wakeup into a guest that is in a power management
+    /// suspended state.
+    pub fn pm_wakeup(&self, flags: u32) -> Result<(), Error> {
+        let ret = unsafe { ffi!(sys::virDomainPMWakeup(self.as_ptr(), flags as libc::c_uint)) };
+        if ret == -1 {
+            return Err(Error::last_error());
+        }
+        Ok(())
+    }
+
     /// Determine if the domain is currently running.
     pub fn is_active(&self) -> Result<bool, Error> {
-        let ret = unsafe { sys::virDomainIsActive(self.as_ptr()) };
+        let ret = unsafe { ffi!(sys::virDomainIsActive(self.as_ptr())) };
         if ret == -1 {
             return Err(Error::last_error());
         }
@@ -1145,7 +4057,7 @@ impl Domain {
     /// Determine if the domain has a persistent configuration which means it will still exist
     /// after shutting down.
     pub fn is_persistent(&self) -> Result<bool, Error> {
-        let ret = unsafe { sys::virDomainIsPersistent(self.as_ptr()) };
+        let ret = unsafe { ffi!(sys::virDomainIsPersistent(self.as_ptr())) };
         if ret == -1 {
             return Err(Error::last_error());
         }
@@ -1158,7 +4070,7 @@ impl Domain {
     /// without stopping it. If the domain is inactive, the domain
     /// configuration is removed.
     pub fn undefine(&self) -> Result<(), Error> {
-        let ret = unsafe { sys::virDomainUndefine(self.as_ptr()) };
+        let ret = unsafe { ffi!(sys::virDomainUndefine(self.as_ptr())) };
         if ret == -1 {
             return Err(Error::last_error());
         }
@@ -1170,20 +4082,64 @@ impl Domain {
     /// If the domain is running, it's converted to transient domain,
     /// without stopping it. If the domain is inactive, the domain
     /// configuration is removed.
-    pub fn undefine_flags(&self, flags: sys::virDomainUndefineFlagsValues) -> Result<(), Error> {
-        let ret = unsafe { sys::virDomainUndefineFlags(self.as_ptr(), flags) };
+    pub fn undefine_flags(&self, flags: impl Into<DomainUndefineFlags>) -> Result<(), Error> {
+        let ret = unsafe {
+            ffi!(sys::virDomainUndefineFlags(
+                self.as_ptr(),
+                flags.into().bits()
+            ))
+        };
         if ret == -1 {
             return Err(Error::last_error());
         }
         Ok(())
     }
 
+    /// Undefine a domain together with the state selected by
+    /// `options`.
+    ///
+    /// Drivers reject the flags they do not know, even when the
+    /// matching state does not exist. If the driver refuses the flags,
+    /// the call is retried with only the flags needed for the state
+    /// which actually exists, the NVRAM and TPM choices being left to
+    /// the driver, and finally with [`undefine()`].
+    ///
+    /// [`undefine()`]: Domain::undefine
+    pub fn undefine_full(&self, options: &UndefineOptions) -> Result<(), Error> {
+        let flags = options.flags();
+        let err = match self.undefine_flags(flags) {
+            Err(e) if Domain::is_flags_rejection(&e) => e,
+            res => return res,
+        };
+
+        let mut needed = DomainUndefineFlags::empty();
+        if flags.contains(DomainUndefineFlags::MANAGED_SAVE) && self.has_managed_save(0)? {
+            needed |= DomainUndefineFlags::MANAGED_SAVE;
+        }
+        if flags.contains(DomainUndefineFlags::SNAPSHOTS_METADATA)
+            && DomainSnapshot::num(self, SnapshotListFlags::empty())? > 0
+        {
+            needed |= DomainUndefineFlags::SNAPSHOTS_METADATA;
+        }
+        if needed == flags {
+            return Err(err);
+        }
+        match self.undefine_flags(needed) {
+            Err(e) if needed.is_empty() && Domain::is_flags_rejection(&e) => self.undefine(),
+            res => res,
+        }
+    }
+
+    fn is_flags_rejection(err: &Error) -> bool {
+        err.is_unsupported() || err.code() == ErrorNumber::InvalidArg
+    }
+
     /// Free the domain object.
     ///
     /// The running instance is kept alive. The data structure is
     /// freed and should not be used thereafter.
     pub fn free(&mut self) -> Result<(), Error> {
-        let ret = unsafe { sys::virDomainFree(self.as_ptr()) };
+        let ret = unsafe { ffi!(sys::virDomainFree(self.as_ptr())) };
         if ret == -1 {
             return Err(Error::last_error());
         }
@@ -1192,7 +4148,7 @@ impl Domain {
     }
 
     pub fn is_updated(&self) -> Result<bool, Error> {
-        let ret = unsafe { sys::virDomainIsUpdated(self.as_ptr()) };
+        let ret = unsafe { ffi!(sys::virDomainIsUpdated(self.as_ptr())) };
         if ret == -1 {
             return Err(Error::last_error());
         }
@@ -1201,7 +4157,7 @@ impl Domain {
 
     pub fn get_autostart(&self) -> Result<bool, Error> {
         let mut autostart: libc::c_int = 0;
-        let ret = unsafe { sys::virDomainGetAutostart(self.as_ptr(), &mut autostart) };
+        let ret = unsafe { ffi!(sys::virDomainGetAutostart(self.as_ptr(), &mut autostart)) };
         if ret == -1 {
             return Err(Error::last_error());
         }
@@ -1209,7 +4165,12 @@ impl Domain {
     }
 
     pub fn set_autostart(&self, autostart: bool) -> Result<bool, Error> {
-        let ret = unsafe { sys::virDomainSetAutostart(self.as_ptr(), autostart as libc::c_int) };
+        let ret = unsafe {
+            ffi!(sys::virDomainSetAutostart(
+                self.as_ptr(),
+                autostart as libc::c_int
+            ))
+        };
         if ret == -1 {
             return Err(Error::last_error());
         }
@@ -1217,7 +4178,12 @@ impl Domain {
     }
 
     pub fn set_max_memory(&self, memory: u64) -> Result<bool, Error> {
-        let ret = unsafe { sys::virDomainSetMaxMemory(self.as_ptr(), memory as libc::c_ulong) };
+        let ret = unsafe {
+            ffi!(sys::virDomainSetMaxMemory(
+                self.as_ptr(),
+                memory as libc::c_ulong
+            ))
+        };
         if ret == -1 {
             return Err(Error::last_error());
         }
@@ -1225,23 +4191,34 @@ impl Domain {
     }
 
     pub fn get_max_memory(&self) -> Result<u64, Error> {
-        let ret = unsafe { sys::virDomainGetMaxMemory(self.as_ptr()) };
+        let ret = unsafe { ffi!(sys::virDomainGetMaxMemory(self.as_ptr())) };
         if ret == 0 {
             return Err(Error::last_error());
         }
         Ok(c_ulong_to_u64(ret))
     }
 
+    /// Get the maximum number of vCPUs of the running domain.
+    ///
+    /// This fails if the domain is inactive, see
+    /// [`get_vcpus_max_config()`] for inactive domains.
+    ///
+    /// [`get_vcpus_max_config()`]: Domain::get_vcpus_max_config
     pub fn get_max_vcpus(&self) -> Result<u64, Error> {
-        let ret = unsafe { sys::virDomainGetMaxVcpus(self.as_ptr()) };
-        if ret == 0 {
+        let ret = unsafe { ffi!(sys::virDomainGetMaxVcpus(self.as_ptr())) };
+        if ret == -1 {
             return Err(Error::last_error());
         }
         Ok(ret as u64)
     }
 
     pub fn set_memory(&self, memory: u64) -> Result<bool, Error> {
-        let ret = unsafe { sys::virDomainSetMemory(self.as_ptr(), memory as libc::c_ulong) };
+        let ret = unsafe {
+            ffi!(sys::virDomainSetMemory(
+                self.as_ptr(),
+                memory as libc::c_ulong
+            ))
+        };
         if ret == -1 {
             return Err(Error::last_error());
         }
@@ -1251,14 +4228,14 @@ impl Domain {
     pub fn set_memory_flags(
         &self,
         memory: u64,
-        flags: sys::virDomainMemoryModFlags,
+        flags: impl Into<DomainMemoryModFlags>,
     ) -> Result<bool, Error> {
         let ret = unsafe {
-            sys::virDomainSetMemoryFlags(
+            ffi!(sys::virDomainSetMemoryFlags(
                 self.as_ptr(),
                 memory as libc::c_ulong,
-                flags as libc::c_uint,
-            )
+                flags.into().bits() as libc::c_uint,
+            ))
         };
         if ret == -1 {
             return Err(Error::last_error());
@@ -1269,14 +4246,14 @@ impl Domain {
     pub fn set_memory_stats_period(
         &self,
         period: i32,
-        flags: sys::virDomainMemoryModFlags,
+        flags: impl Into<DomainModificationImpact>,
     ) -> Result<bool, Error> {
         let ret = unsafe {
-            sys::virDomainSetMemoryStatsPeriod(
+            ffi!(sys::virDomainSetMemoryStatsPeriod(
                 self.as_ptr(),
                 period as libc::c_int,
-                flags as libc::c_uint,
-            )
+                flags.into().bits() as libc::c_uint,
+            ))
         };
         if ret == -1 {
             return Err(Error::last_error());
@@ -1285,7 +4262,7 @@ impl Domain {
     }
 
     pub fn set_vcpus(&self, vcpus: u32) -> Result<bool, Error> {
-        let ret = unsafe { sys::virDomainSetVcpus(self.as_ptr(), vcpus as libc::c_uint) };
+        let ret = unsafe { ffi!(sys::virDomainSetVcpus(self.as_ptr(), vcpus as libc::c_uint)) };
         if ret == -1 {
             return Err(Error::last_error());
         }
@@ -1295,10 +4272,14 @@ impl Domain {
     pub fn set_vcpus_flags(
         &self,
         vcpus: u32,
-        flags: sys::virDomainVcpuFlags,
+        flags: impl Into<DomainVcpuFlags>,
     ) -> Result<bool, Error> {
         let ret = unsafe {
-            sys::virDomainSetVcpusFlags(self.as_ptr(), vcpus as libc::c_uint, flags as libc::c_uint)
+            ffi!(sys::virDomainSetVcpusFlags(
+                self.as_ptr(),
+                vcpus as libc::c_uint,
+                flags.into().bits() as libc::c_uint
+            ))
         };
         if ret == -1 {
             return Err(Error::last_error());
@@ -1306,9 +4287,46 @@ impl Domain {
         Ok(ret == 1)
     }
 
+    /// Gets the vCPUs of the guest, as seen by the guest agent.
+    pub fn get_guest_vcpus(&self, flags: u32) -> Result<GuestVcpus, Error> {
+        let mut nparams: libc::c_uint = 0;
+        let mut params: sys::virTypedParameterPtr = ptr::null_mut();
+        let ret = unsafe {
+            ffi!(sys::virDomainGetGuestVcpus(
+                self.as_ptr(),
+                &mut params,
+                &mut nparams,
+                flags as libc::c_uint,
+            ))
+        };
+        if ret == -1 {
+            return Err(Error::last_error());
+        }
+        let res = unsafe { TypedParamList::from_raw(params, nparams as libc::c_int) };
+        Ok(GuestVcpus::from_vec(res.params()))
+    }
+
+    /// Enables or disables the vCPUs of `cpumap` in the guest, using
+    /// the guest agent.
+    pub fn set_guest_vcpus(&self, cpumap: &NodeSet, state: bool, flags: u32) -> Result<(), Error> {
+        let cpumap_buf = CString::new(cpumap.to_string())?;
+        let ret = unsafe {
+            ffi!(sys::virDomainSetGuestVcpus(
+                self.as_ptr(),
+                cpumap_buf.as_ptr(),
+                state as libc::c_int,
+                flags as libc::c_uint,
+            ))
+        };
+        if ret == -1 {
+            return Err(Error::last_error());
+        }
+        Ok(())
+    }
+
     pub fn domain_restore(conn: &Connect, path: &str) -> Result<(), Error> {
-        let path_buf = CString::new(path).unwrap();
-        let ret = unsafe { sys::virDomainRestore(conn.as_ptr(), path_buf.as_ptr()) };
+        let path_buf = CString::new(path)?;
+        let ret = unsafe { ffi!(sys::virDomainRestore(conn.as_ptr(), path_buf.as_ptr())) };
         if ret == -1 {
             return Err(Error::last_error());
         }
@@ -1321,15 +4339,282 @@ impl Domain {
         dxml: Option<&str>,
         flags: sys::virDomainSaveRestoreFlags,
     ) -> Result<(), Error> {
-        let path_buf = CString::new(path).unwrap();
+        let path_buf = CString::new(path)?;
         let dxml_buf = some_string_to_cstring!(dxml);
         let ret = unsafe {
-            sys::virDomainRestoreFlags(
+            ffi!(sys::virDomainRestoreFlags(
                 conn.as_ptr(),
                 path_buf.as_ptr(),
                 some_cstring_to_c_chars!(dxml_buf),
                 flags,
-            )
+            ))
+        };
+        if ret == -1 {
+            return Err(Error::last_error());
+        }
+        Ok(())
+    }
+
+    pub fn get_vcpus_flags(&self, flags: impl Into<DomainVcpuFlags>) -> Result<u32, Error> {
+        let ret = unsafe {
+            ffi!(sys::virDomainGetVcpusFlags(
+                self.as_ptr(),
+                flags.into().bits() as libc::c_uint
+            ))
+        };
+        if ret == -1 {
+            return Err(Error::last_error());
+        }
+        Ok(ret as u32)
+    }
+
+    /// Get the maximum number of vCPUs of the running domain.
+    ///
+    /// This fails if the domain is inactive.
+    pub fn get_vcpus_max_live(&self) -> Result<u32, Error> {
+        self.get_vcpus_flags(DomainVcpuFlags::MAXIMUM | DomainVcpuFlags::LIVE)
+    }
+
+    /// Get the maximum number of vCPUs of the persistent configuration
+    /// of the domain. This works on inactive domains too.
+    pub fn get_vcpus_max_config(&self) -> Result<u32, Error> {
+        self.get_vcpus_flags(DomainVcpuFlags::MAXIMUM | DomainVcpuFlags::CONFIG)
+    }
+
+    /// Set the maximum bandwidth, in MiB/s, used by the migration of
+    /// this domain.
+    pub fn migrate_set_max_speed(
+        &self,
+        bandwidth: u64,
+        options: impl Into<MigrateMaxSpeedOptions>,
+    ) -> Result<u32, Error> {
+        let ret = unsafe {
+            ffi!(sys::virDomainMigrateSetMaxSpeed(
+                self.as_ptr(),
+                bandwidth as libc::c_ulong,
+                options.into().flags() as libc::c_uint,
+            ))
+        };
+        if ret == -1 {
+            return Err(Error::last_error());
+        }
+        Ok(ret as u32)
+    }
+
+    /// Get the maximum bandwidth, in MiB/s, used by the migration of
+    /// this domain.
+    pub fn migrate_get_max_speed(
+        &self,
+        options: impl Into<MigrateMaxSpeedOptions>,
+    ) -> Result<u64, Error> {
+        let mut bandwidth: libc::c_ulong = 0;
+        let ret = unsafe {
+            ffi!(sys::virDomainMigrateGetMaxSpeed(
+                self.as_ptr(),
+                &mut bandwidth,
+                options.into().flags() as libc::c_uint,
+            ))
+        };
+        if ret == -1 {
+            return Err(Error::last_error());
+        }
+        Ok(c_ulong_to_u64(bandwidth))
+    }
+
+    pub fn migrate_set_compression_cache(&self, size: u64, flags: u32) -> Result<u32, Error> {
+        let ret = unsafe {
+            ffi!(sys::virDomainMigrateSetCompressionCache(
+                self.as_ptr(),
+                size as libc::c_ulonglong,
+                flags as libc::c_uint,
+            ))
+        };
+        if ret == -1 {
+            return Err(Error::last_error());
+        }
+        Ok(ret as u32)
+    }
+
+    pub fn migrate_get_compression_cache(&self, flags: u32) -> Result<u64, Error> {
+        let mut size: libc::c_ulonglong = 0;
+        let ret = unsafe {
+            ffi!(sys::virDomainMigrateGetCompressionCache(
+                self.as_ptr(),
+                &mut size,
+                flags as libc::c_uint,
+            ))
+        };
+        if ret == -1 {
+            return Err(Error::last_error());
+        }
+        Ok(size)
+    }
+
+    /// Get the size in bytes of the page cache used by the xbzrle
+    /// compression of the migration.
+    ///
+    /// Returns `None` when the hypervisor does not support xbzrle
+    /// compression, other failures are reported as errors.
+    pub fn migration_xbzrle_cache(&self) -> Result<Option<u64>, Error> {
+        match self.migrate_get_compression_cache(0) {
+            Ok(size) => Ok(Some(size)),
+            Err(e) if e.is_unsupported() => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Set the size in bytes of the page cache used by the xbzrle
+    /// compression of the migration, which may be changed while the
+    /// migration runs.
+    pub fn set_migration_xbzrle_cache(&self, bytes: u64) -> Result<(), Error> {
+        self.migrate_set_compression_cache(bytes, 0).map(|_| ())
+    }
+
+    pub fn migrate_set_max_downtime(&self, downtime: u64, flags: u32) -> Result<u32, Error> {
+        let ret = unsafe {
+            ffi!(sys::virDomainMigrateSetMaxDowntime(
+                self.as_ptr(),
+                downtime as libc::c_ulonglong,
+                flags as libc::c_uint,
+            ))
+        };
+        if ret == -1 {
+            return Err(Error::last_error());
+        }
+        Ok(ret as u32)
+    }
+
+    /// Get the maximum tolerable downtime, in milliseconds, of the
+    /// migration of this domain.
+    pub fn migrate_get_max_downtime(&self, flags: u32) -> Result<u64, Error> {
+        let mut downtime: libc::c_ulonglong = 0;
+        let ret = unsafe {
+            ffi!(sys::virDomainMigrateGetMaxDowntime(
+                self.as_ptr(),
+                &mut downtime,
+                flags as libc::c_uint
+            ))
+        };
+        if ret == -1 {
+            return Err(Error::last_error());
+        }
+        Ok(downtime)
+    }
+
+    pub fn set_time(&self, seconds: i64, nseconds: i32, flags: u32) -> Result<u32, Error> {
+        let ret = unsafe {
+            ffi!(sys::virDomainSetTime(
+                self.as_ptr(),
+                seconds as libc::c_longlong,
+                nseconds as libc::c_uint,
+                flags as libc::c_uint,
+            ))
+        };
+        if ret == -1 {
+            return Err(Error::last_error());
+        }
+        Ok(ret as u32)
+    }
+
+    pub fn get_time(&self, flags: u32) -> Result<(i64, i32), Error> {
+        let mut seconds: libc::c_longlong = 0;
+        let mut nseconds: libc::c_uint = 0;
+        let ret = unsafe {
+            ffi!(sys::virDomainGetTime(
+                self.as_ptr(),
+                &mut seconds,
+                &mut nseconds,
+                flags as libc::c_uint,
+            ))
+        };
+        if ret == -1 {
+            return Err(Error::last_error());
+        }
+        Ok((seconds, nseconds as i32))
+    }
+
+    /// Get the time of the guest as a [`SystemTime`].
+    ///
+    /// This relies on the guest agent.
+    pub fn get_time_std(&self) -> Result<SystemTime, Error> {
+        let (seconds, nseconds) = self.get_time(0)?;
+        let nseconds = Duration::from_nanos(nseconds as u64);
+        Ok(if seconds >= 0 {
+            UNIX_EPOCH + Duration::from_secs(seconds as u64) + nseconds
+        } else {
+            UNIX_EPOCH - Duration::from_secs(seconds.unsigned_abs()) + nseconds
+        })
+    }
+
+    /// Set the time of the guest from a [`SystemTime`].
+    ///
+    /// This relies on the guest agent.
+    pub fn set_time_std(&self, time: SystemTime) -> Result<(), Error> {
+        let (seconds, nseconds) = match time.duration_since(UNIX_EPOCH) {
+            Ok(d) => (d.as_secs() as i64, d.subsec_nanos()),
+            Err(e) => {
+                let d = e.duration();
+                if d.subsec_nanos() == 0 {
+                    (-(d.as_secs() as i64), 0)
+                } else {
+                    (-(d.as_secs() as i64) - 1, 1_000_000_000 - d.subsec_nanos())
+                }
+            }
+        };
+        self.set_time(seconds, nseconds as i32, 0).map(|_| ())
+    }
+
+    /// Resynchronize the time of the guest from the real time clock
+    /// of the guest, which typically follows the host.
+    ///
+    /// This is meant to be called after the domain is resumed, when
+    /// the guest clock is behind. It relies on the guest agent.
+    pub fn sync_time_from_host(&self) -> Result<(), Error> {
+        self.set_time(0, 0, sys::VIR_DOMAIN_TIME_SYNC).map(|_| ())
+    }
+
+    /// Get the tainting and deprecation messages of the domain.
+    ///
+    /// `flags` selects the kinds of messages; all of them are returned
+    /// when empty.
+    #[cfg(libvirt_7_1)]
+    pub fn get_messages(&self, flags: impl Into<DomainMessageType>) -> Result<Vec<String>, Error> {
+        let mut msgs: *mut *mut libc::c_char = ptr::null_mut();
+        let ret = unsafe {
+            ffi!(sys::virDomainGetMessages(
+                self.as_ptr(),
+                &mut msgs,
+                flags.into().bits() as libc::c_uint,
+            ))
+        };
+        if ret == -1 {
+            return Err(Error::last_error());
+        }
+        let mut array: Vec<String> = Vec::with_capacity(ret as usize);
+        for x in 0..ret as isize {
+            array.push(unsafe { c_chars_to_string!(*msgs.offset(x)) });
+        }
+        unsafe { libc::free(msgs as *mut libc::c_void) };
+        Ok(array)
+    }
+
+    /// Start measuring the rate at which the domain dirties its
+    /// memory, during `seconds`.
+    ///
+    /// The result is reported by the [`DomainStatsGroups::DIRTYRATE`]
+    /// statistics, see [`DomainStatsRecord::dirty_rate()`].
+    #[cfg(libvirt_7_2)]
+    pub fn start_dirty_rate_calc(
+        &self,
+        seconds: i32,
+        flags: impl Into<DirtyRateCalcFlags>,
+    ) -> Result<(), Error> {
+        let ret = unsafe {
+            ffi!(sys::virDomainStartDirtyRateCalc(
+                self.as_ptr(),
+                seconds as libc::c_int,
+                flags.into().bits() as libc::c_uint,
+            ))
         };
         if ret == -1 {
             return Err(Error::last_error());
@@ -1337,46 +4622,141 @@ impl Domain {
         Ok(())
     }
 
-    pub fn get_vcpus_flags(&self, flags: sys::virDomainVcpuFlags) -> Result<u32, Error> {
-        let ret = unsafe { sys::virDomainGetVcpusFlags(self.as_ptr(), flags as libc::c_uint) };
+    pub fn get_block_info(&self, disk: &str, flags: u32) -> Result<BlockInfo, Error> {
+        let mut pinfo = mem::MaybeUninit::uninit();
+        let disk_buf = CString::new(disk)?;
+        let ret = unsafe {
+            ffi!(sys::virDomainGetBlockInfo(
+                self.as_ptr(),
+                disk_buf.as_ptr(),
+                pinfo.as_mut_ptr(),
+                flags as libc::c_uint,
+            ))
+        };
         if ret == -1 {
             return Err(Error::last_error());
         }
-        Ok(ret as u32)
+        Ok(unsafe { BlockInfo::from_ptr(&mut pinfo.assume_init()) })
+    }
+
+    /// Get the size information of a disk, given by target device or
+    /// source path, whatever the state of the domain.
+    ///
+    /// libvirt only reports the configured sizes of the disks of an
+    /// inactive domain, in which case the information is read from
+    /// the storage volume backing the disk instead. Disks which are
+    /// not storage volumes fall back to [`get_block_info()`].
+    ///
+    /// [`get_block_info()`]: Domain::get_block_info
+    pub fn get_block_info_or_volume(&self, disk: &str) -> Result<BlockInfo, Error> {
+        if self.is_active()? {
+            return self.get_block_info(disk, 0);
+        }
+        let vol = match self.disk_volume(disk)? {
+            Some(vol) => vol,
+            None => return self.get_block_info(disk, 0),
+        };
+        let info = vol.get_info_flags(sys::VIR_STORAGE_VOL_USE_ALLOCATION)?;
+        let physical = vol.get_info_flags(sys::VIR_STORAGE_VOL_GET_PHYSICAL)?;
+        Ok(BlockInfo {
+            capacity: info.capacity,
+            allocation: info.allocation,
+            physical: physical.allocation,
+        })
+    }
+
+    /// Returns the storage volume backing a disk, or `None` if the
+    /// disk is not found or is not a volume of a storage pool.
+    fn disk_volume(&self, disk: &str) -> Result<Option<StorageVol>, Error> {
+        let root = Element::parse(&self.get_xml_desc(0)?)?;
+        let disks = match root.child("devices") {
+            Some(devices) => devices.children("disk"),
+            None => return Ok(None),
+        };
+        for elem in disks {
+            let source = match elem.child("source") {
+                Some(source) => source,
+                None => continue,
+            };
+            let path = source.attr("file").or_else(|| source.attr("dev"));
+            let target = elem.child("target").and_then(|t| t.attr("dev"));
+            if target != Some(disk) && path != Some(disk) {
+                continue;
+            }
+            let conn = self.get_connect()?;
+            let found = match (path, source.attr("pool"), source.attr("volume")) {
+                (Some(path), _, _) => StorageVol::lookup_by_path(&conn, path),
+                (None, Some(pool), Some(volume)) => StoragePool::lookup_by_name(&conn, pool)
+                    .and_then(|pool| StorageVol::lookup_by_name(&pool, volume)),
+                _ => return Ok(None),
+            };
+            return match found {
+                Ok(vol) => Ok(Some(vol)),
+                Err(e) if e.is_not_found() => Ok(None),
+                Err(e) => Err(e),
+            };
+        }
+        Ok(None)
     }
 
-    pub fn migrate_set_max_speed(&self, bandwidth: u64, flags: u32) -> Result<u32, Error> {
+    /// Reads `size` bytes of the domain memory, starting at `start`.
+    ///
+    /// Either [`sys::VIR_MEMORY_VIRTUAL`] or
+    /// [`sys::VIR_MEMORY_PHYSICAL`] must be set in `flags`.
+    pub fn memory_peek(
+        &self,
+        start: u64,
+        size: usize,
+        flags: sys::virDomainMemoryFlags,
+    ) -> Result<Vec<u8>, Error> {
+        let mut buffer: Vec<u8> = vec![0; size];
         let ret = unsafe {
-            sys::virDomainMigrateSetMaxSpeed(
+            ffi!(sys::virDomainMemoryPeek(
                 self.as_ptr(),
-                bandwidth as libc::c_ulong,
+                start as libc::c_ulonglong,
+                size,
+                buffer.as_mut_ptr() as *mut libc::c_void,
                 flags as libc::c_uint,
-            )
+            ))
         };
         if ret == -1 {
             return Err(Error::last_error());
         }
-        Ok(ret as u32)
+        Ok(buffer)
     }
 
-    pub fn migrate_get_max_speed(&self, flags: u32) -> Result<u64, Error> {
-        let mut bandwidth: libc::c_ulong = 0;
+    /// Reads `size` bytes of the block device `disk`, starting at
+    /// `offset`.
+    ///
+    /// The `disk` is either the device target shown in the domain XML,
+    /// or the unique source path of the disk.
+    pub fn block_peek(&self, disk: &str, offset: u64, size: usize) -> Result<Vec<u8>, Error> {
+        let disk_buf = CString::new(disk)?;
+        let mut buffer: Vec<u8> = vec![0; size];
         let ret = unsafe {
-            sys::virDomainMigrateGetMaxSpeed(self.as_ptr(), &mut bandwidth, flags as libc::c_uint)
+            ffi!(sys::virDomainBlockPeek(
+                self.as_ptr(),
+                disk_buf.as_ptr(),
+                offset as libc::c_ulonglong,
+                size,
+                buffer.as_mut_ptr() as *mut libc::c_void,
+                0,
+            ))
         };
         if ret == -1 {
             return Err(Error::last_error());
         }
-        Ok(c_ulong_to_u64(bandwidth))
+        Ok(buffer)
     }
 
-    pub fn migrate_set_compression_cache(&self, size: u64, flags: u32) -> Result<u32, Error> {
+    pub fn pin_vcpu(&self, vcpu: u32, cpumap: &CpuMap) -> Result<u32, Error> {
         let ret = unsafe {
-            sys::virDomainMigrateSetCompressionCache(
+            ffi!(sys::virDomainPinVcpu(
                 self.as_ptr(),
-                size as libc::c_ulonglong,
-                flags as libc::c_uint,
-            )
+                vcpu as libc::c_uint,
+                cpumap.as_bytes().as_ptr() as *mut _,
+                cpumap.as_bytes().len() as libc::c_int,
+            ))
         };
         if ret == -1 {
             return Err(Error::last_error());
@@ -1384,28 +4764,39 @@ impl Domain {
         Ok(ret as u32)
     }
 
-    pub fn migrate_get_compression_cache(&self, flags: u32) -> Result<u64, Error> {
-        let mut size: libc::c_ulonglong = 0;
+    pub fn pin_vcpu_flags(
+        &self,
+        vcpu: u32,
+        cpumap: &CpuMap,
+        flags: impl Into<DomainModificationImpact>,
+    ) -> Result<u32, Error> {
         let ret = unsafe {
-            sys::virDomainMigrateGetCompressionCache(
+            ffi!(sys::virDomainPinVcpuFlags(
                 self.as_ptr(),
-                &mut size,
-                flags as libc::c_uint,
-            )
+                vcpu as libc::c_uint,
+                cpumap.as_bytes().as_ptr() as *mut _,
+                cpumap.as_bytes().len() as libc::c_int,
+                flags.into().bits() as libc::c_uint,
+            ))
         };
         if ret == -1 {
             return Err(Error::last_error());
         }
-        Ok(size)
+        Ok(ret as u32)
     }
 
-    pub fn migrate_set_max_downtime(&self, downtime: u64, flags: u32) -> Result<u32, Error> {
+    pub fn pin_emulator(
+        &self,
+        cpumap: &CpuMap,
+        flags: impl Into<DomainModificationImpact>,
+    ) -> Result<u32, Error> {
         let ret = unsafe {
-            sys::virDomainMigrateSetMaxDowntime(
+            ffi!(sys::virDomainPinEmulator(
                 self.as_ptr(),
-                downtime as libc::c_ulonglong,
-                flags as libc::c_uint,
-            )
+                cpumap.as_bytes().as_ptr() as *mut _,
+                cpumap.as_bytes().len() as libc::c_int,
+                flags.into().bits() as libc::c_uint,
+            ))
         };
         if ret == -1 {
             return Err(Error::last_error());
@@ -1413,105 +4804,212 @@ impl Domain {
         Ok(ret as u32)
     }
 
-    pub fn set_time(&self, seconds: i64, nseconds: i32, flags: u32) -> Result<u32, Error> {
+    /// Enable or disable individual vCPUs of the domain.
+    ///
+    /// `vcpumap` lists the vCPU IDs with the syntax of cpusets, e.g.
+    /// `1,3-5`. Unlike [`set_vcpus_flags()`], this allows picking
+    /// which vCPUs are hot(un)plugged.
+    ///
+    /// [`set_vcpus_flags()`]: Domain::set_vcpus_flags
+    pub fn set_vcpu(
+        &self,
+        vcpumap: &str,
+        state: bool,
+        flags: impl Into<DomainModificationImpact>,
+    ) -> Result<(), Error> {
+        let vcpumap_buf = CString::new(vcpumap)?;
         let ret = unsafe {
-            sys::virDomainSetTime(
+            ffi!(sys::virDomainSetVcpu(
                 self.as_ptr(),
-                seconds as libc::c_longlong,
-                nseconds as libc::c_uint,
-                flags as libc::c_uint,
-            )
+                vcpumap_buf.as_ptr(),
+                state as libc::c_int,
+                flags.into().bits() as libc::c_uint,
+            ))
         };
         if ret == -1 {
             return Err(Error::last_error());
         }
-        Ok(ret as u32)
+        Ok(())
     }
 
-    pub fn get_time(&self, flags: u32) -> Result<(i64, i32), Error> {
-        let mut seconds: libc::c_longlong = 0;
-        let mut nseconds: libc::c_uint = 0;
+    /// Tune an IOThread of the domain.
+    pub fn set_iothread_params(
+        &self,
+        iothread_id: u32,
+        params: IOThreadParameters,
+        flags: impl Into<DomainModificationImpact>,
+    ) -> Result<(), Error> {
+        let mut cparams = TypedParamList::from_vec(params.to_vec()?);
         let ret = unsafe {
-            sys::virDomainGetTime(
+            ffi!(sys::virDomainSetIOThreadParams(
                 self.as_ptr(),
-                &mut seconds,
-                &mut nseconds,
-                flags as libc::c_uint,
-            )
+                iothread_id as libc::c_uint,
+                cparams.as_mut_ptr(),
+                cparams.len(),
+                flags.into().bits() as libc::c_uint,
+            ))
         };
         if ret == -1 {
             return Err(Error::last_error());
         }
-        Ok((seconds, nseconds as i32))
+        Ok(())
     }
 
-    pub fn get_block_info(&self, disk: &str, flags: u32) -> Result<BlockInfo, Error> {
-        let mut pinfo = mem::MaybeUninit::uninit();
-        let disk_buf = CString::new(disk).unwrap();
+    /// Pin an IOThread of the domain to the given host CPUs.
+    pub fn pin_iothread(
+        &self,
+        iothread_id: u32,
+        cpumap: &CpuMap,
+        flags: impl Into<DomainModificationImpact>,
+    ) -> Result<(), Error> {
         let ret = unsafe {
-            sys::virDomainGetBlockInfo(
+            ffi!(sys::virDomainPinIOThread(
                 self.as_ptr(),
-                disk_buf.as_ptr(),
-                pinfo.as_mut_ptr(),
-                flags as libc::c_uint,
-            )
+                iothread_id as libc::c_uint,
+                cpumap.as_bytes().as_ptr() as *mut _,
+                cpumap.as_bytes().len() as libc::c_int,
+                flags.into().bits() as libc::c_uint,
+            ))
         };
         if ret == -1 {
             return Err(Error::last_error());
         }
-        Ok(unsafe { BlockInfo::from_ptr(&mut pinfo.assume_init()) })
+        Ok(())
     }
 
-    pub fn pin_vcpu(&self, vcpu: u32, cpumap: &[u8]) -> Result<u32, Error> {
+    /// Get the host CPUs the emulator threads of the domain are
+    /// pinned to.
+    ///
+    /// `maplen_hint` is the size in bytes of the returned map; when
+    /// `None`, it is computed from the number of CPUs of the host.
+    pub fn get_emulator_pin_info(
+        &self,
+        maplen_hint: Option<usize>,
+        flags: impl Into<DomainModificationImpact>,
+    ) -> Result<CpuMap, Error> {
+        let maplen = self.pin_maplen(maplen_hint)?;
+        let mut cpumap = vec![0u8; maplen];
         let ret = unsafe {
-            sys::virDomainPinVcpu(
+            ffi!(sys::virDomainGetEmulatorPinInfo(
                 self.as_ptr(),
-                vcpu as libc::c_uint,
-                cpumap.as_ptr() as *mut _,
-                cpumap.len() as libc::c_int,
-            )
+                cpumap.as_mut_ptr(),
+                maplen as libc::c_int,
+                flags.into().bits() as libc::c_uint,
+            ))
         };
         if ret == -1 {
             return Err(Error::last_error());
         }
-        Ok(ret as u32)
+        Ok(CpuMap::from_bytes(&cpumap))
     }
 
-    pub fn pin_vcpu_flags(&self, vcpu: u32, cpumap: &[u8], flags: u32) -> Result<u32, Error> {
+    /// Get the host CPUs each vCPU of the domain is pinned to, indexed
+    /// by vCPU number.
+    ///
+    /// `maplen_hint` is the size in bytes of the returned maps, see
+    /// [`get_emulator_pin_info()`](Domain::get_emulator_pin_info).
+    pub fn get_vcpu_pin_info(
+        &self,
+        maplen_hint: Option<usize>,
+        flags: impl Into<DomainModificationImpact>,
+    ) -> Result<Vec<CpuMap>, Error> {
+        let flags = flags.into();
+        let maplen = self.pin_maplen(maplen_hint)?;
+        let ncpumaps =
+            self.get_vcpus_flags(DomainVcpuFlags::from(flags) | DomainVcpuFlags::MAXIMUM)? as usize;
+        let mut cpumaps = vec![0u8; ncpumaps * maplen];
         let ret = unsafe {
-            sys::virDomainPinVcpuFlags(
+            ffi!(sys::virDomainGetVcpuPinInfo(
                 self.as_ptr(),
-                vcpu as libc::c_uint,
-                cpumap.as_ptr() as *mut _,
-                cpumap.len() as libc::c_int,
-                flags as libc::c_uint,
-            )
+                ncpumaps as libc::c_int,
+                cpumaps.as_mut_ptr(),
+                maplen as libc::c_int,
+                flags.bits() as libc::c_uint,
+            ))
         };
         if ret == -1 {
             return Err(Error::last_error());
         }
-        Ok(ret as u32)
+        Ok(cpumaps
+            .chunks(maplen)
+            .take(ret as usize)
+            .map(CpuMap::from_bytes)
+            .collect())
     }
 
-    pub fn pin_emulator(&self, cpumap: &[u8], flags: u32) -> Result<u32, Error> {
+    /// Get the IOThreads of the domain and the host CPUs they are
+    /// pinned to.
+    pub fn get_iothread_info(
+        &self,
+        flags: impl Into<DomainModificationImpact>,
+    ) -> Result<Vec<IOThreadInfo>, Error> {
+        let mut info: *mut sys::virDomainIOThreadInfoPtr = ptr::null_mut();
         let ret = unsafe {
-            sys::virDomainPinEmulator(
+            ffi!(sys::virDomainGetIOThreadInfo(
                 self.as_ptr(),
-                cpumap.as_ptr() as *mut _,
-                cpumap.len() as libc::c_int,
-                flags as libc::c_uint,
-            )
+                &mut info,
+                flags.into().bits() as libc::c_uint,
+            ))
         };
         if ret == -1 {
             return Err(Error::last_error());
         }
-        Ok(ret as u32)
+        let mut array: Vec<IOThreadInfo> = Vec::with_capacity(ret as usize);
+        for x in 0..ret as usize {
+            unsafe {
+                let ptr = *info.add(x);
+                let cpumap = std::slice::from_raw_parts((*ptr).cpumap, (*ptr).cpumaplen as usize);
+                array.push(IOThreadInfo {
+                    iothread_id: (*ptr).iothread_id as u32,
+                    cpumap: CpuMap::from_bytes(cpumap),
+                });
+                ffi!(sys::virDomainIOThreadInfoFree(ptr));
+            }
+        }
+        unsafe { libc::free(info as *mut libc::c_void) };
+        Ok(array)
+    }
+
+    /// The size of the CPU maps of the pinning APIs: `maplen_hint` if
+    /// given, else computed from the number of CPUs of the host.
+    fn pin_maplen(&self, maplen_hint: Option<usize>) -> Result<usize, Error> {
+        if let Some(maplen) = maplen_hint {
+            return Ok(maplen);
+        }
+        let info = self.get_connect()?.get_node_info()?;
+        let max_cpus = info.nodes * info.sockets * info.cores * info.threads;
+        Ok(CpuMap::maplen(max_cpus.max(info.cpus)))
+    }
+
+    /// Renames the domain, which must be inactive.
+    ///
+    /// The flags of [`rename()`] are reserved, so none are taken here.
+    /// An active domain is rejected up front with
+    /// [`ErrorNumber::OperationInvalid`] and a message pointing at the
+    /// cause, rather than with the generic failure of the hypervisor.
+    ///
+    /// [`rename()`]: Domain::rename
+    pub fn rename_inactive(&self, new_name: &str) -> Result<(), Error> {
+        if self.is_active()? {
+            return Err(Error::from_operation_invalid(format!(
+                "cannot rename domain '{}' while it is running, shut it down first",
+                self.get_name()?
+            )));
+        }
+        self.rename(new_name, 0).map(|_| ())
     }
 
+    /// Renames the domain.
+    ///
+    /// See <https://libvirt.org/html/libvirt-libvirt-domain.html#virDomainRename>
     pub fn rename(&self, new_name: &str, flags: u32) -> Result<u32, Error> {
-        let new_name_buf = CString::new(new_name).unwrap();
+        let new_name_buf = CString::new(new_name)?;
         let ret = unsafe {
-            sys::virDomainRename(self.as_ptr(), new_name_buf.as_ptr(), flags as libc::c_uint)
+            ffi!(sys::virDomainRename(
+                self.as_ptr(),
+                new_name_buf.as_ptr(),
+                flags as libc::c_uint
+            ))
         };
         if ret == -1 {
             return Err(Error::last_error());
@@ -1520,15 +5018,15 @@ impl Domain {
     }
 
     pub fn set_user_password(&self, user: &str, password: &str, flags: u32) -> Result<u32, Error> {
-        let user_buf = CString::new(user).unwrap();
-        let password_buf = CString::new(password).unwrap();
+        let user_buf = CString::new(user)?;
+        let password_buf = CString::new(password)?;
         let ret = unsafe {
-            sys::virDomainSetUserPassword(
+            ffi!(sys::virDomainSetUserPassword(
                 self.as_ptr(),
                 user_buf.as_ptr(),
                 password_buf.as_ptr(),
                 flags as libc::c_uint,
-            )
+            ))
         };
         if ret == -1 {
             return Err(Error::last_error());
@@ -1536,15 +5034,21 @@ impl Domain {
         Ok(ret as u32)
     }
 
+    /// Set the write threshold of a block device, in bytes.
+    ///
+    /// Once a write reaches the threshold, a `BLOCK_THRESHOLD` event
+    /// is emitted, see
+    /// [`Connect::domain_event_block_threshold_register()`]. The
+    /// threshold is then cleared; a threshold of 0 also clears it.
     pub fn set_block_threshold(&self, dev: &str, threshold: u64, flags: u32) -> Result<u32, Error> {
-        let dev_buf = CString::new(dev).unwrap();
+        let dev_buf = CString::new(dev)?;
         let ret = unsafe {
-            sys::virDomainSetBlockThreshold(
+            ffi!(sys::virDomainSetBlockThreshold(
                 self.as_ptr(),
                 dev_buf.as_ptr(),
                 threshold as libc::c_ulonglong,
                 flags as libc::c_uint,
-            )
+            ))
         };
         if ret == -1 {
             return Err(Error::last_error());
@@ -1552,14 +5056,21 @@ impl Domain {
         Ok(ret as u32)
     }
 
-    pub fn open_graphics(&self, idx: u32, fd: i32, flags: u32) -> Result<u32, Error> {
+    /// Connects the graphics device `idx` of the running domain to
+    /// the socket `fd`, which the caller keeps ownership of.
+    pub fn open_graphics(
+        &self,
+        idx: u32,
+        fd: BorrowedFd<'_>,
+        flags: impl Into<OpenGraphicsFlags>,
+    ) -> Result<u32, Error> {
         let ret = unsafe {
-            sys::virDomainOpenGraphics(
+            ffi!(sys::virDomainOpenGraphics(
                 self.as_ptr(),
                 idx as libc::c_uint,
-                fd as libc::c_int,
-                flags as libc::c_uint,
-            )
+                fd.as_raw_fd() as libc::c_int,
+                flags.into().bits() as libc::c_uint,
+            ))
         };
         if ret == -1 {
             return Err(Error::last_error());
@@ -1567,14 +5078,33 @@ impl Domain {
         Ok(ret as u32)
     }
 
-    pub fn open_graphics_fd(&self, idx: u32, flags: u32) -> Result<u32, Error> {
+    /// Opens a socket connected to the graphics device `idx` of the
+    /// running domain.
+    ///
+    /// The descriptor is closed when the returned value is dropped.
+    pub fn open_graphics_fd(
+        &self,
+        idx: u32,
+        flags: impl Into<OpenGraphicsFlags>,
+    ) -> Result<OwnedFd, Error> {
         let ret = unsafe {
-            sys::virDomainOpenGraphicsFD(self.as_ptr(), idx as libc::c_uint, flags as libc::c_uint)
+            ffi!(sys::virDomainOpenGraphicsFD(
+                self.as_ptr(),
+                idx as libc::c_uint,
+                flags.into().bits() as libc::c_uint,
+            ))
         };
         if ret == -1 {
             return Err(Error::last_error());
         }
-        Ok(ret as u32)
+        Ok(unsafe { OwnedFd::from_raw_fd(ret) })
+    }
+
+    /// Returns how to connect to the graphics devices of the running
+    /// domain, in the order expected by the `idx` argument of
+    /// [`open_graphics_fd()`](Domain::open_graphics_fd).
+    pub fn graphics_info(&self) -> Result<Vec<GraphicsInfo>, Error> {
+        GraphicsInfo::from_xml(&self.get_xml_desc(0)?)
     }
 
     pub fn open_channel(
@@ -1585,12 +5115,12 @@ impl Domain {
     ) -> Result<u32, Error> {
         let name_buf = some_string_to_cstring!(name);
         let ret = unsafe {
-            sys::virDomainOpenChannel(
+            ffi!(sys::virDomainOpenChannel(
                 self.as_ptr(),
                 some_cstring_to_c_chars!(name_buf),
                 stream.as_ptr(),
                 flags as libc::c_uint,
-            )
+            ))
         };
         if ret == -1 {
             return Err(Error::last_error());
@@ -1606,12 +5136,12 @@ impl Domain {
     ) -> Result<u32, Error> {
         let name_buf = some_string_to_cstring!(name);
         let ret = unsafe {
-            sys::virDomainOpenConsole(
+            ffi!(sys::virDomainOpenConsole(
                 self.as_ptr(),
                 some_cstring_to_c_chars!(name_buf),
                 stream.as_ptr(),
                 flags as libc::c_uint,
-            )
+            ))
         };
         if ret == -1 {
             return Err(Error::last_error());
@@ -1621,12 +5151,17 @@ impl Domain {
 
     pub fn interface_addresses(
         &self,
-        source: sys::virDomainInterfaceAddressesSource,
+        source: InterfaceAddressSource,
         flags: u32,
     ) -> Result<Vec<Interface>, Error> {
         let mut addresses: *mut sys::virDomainInterfacePtr = ptr::null_mut();
         let size = unsafe {
-            sys::virDomainInterfaceAddresses(self.as_ptr(), &mut addresses, source, flags)
+            ffi!(sys::virDomainInterfaceAddresses(
+                self.as_ptr(),
+                &mut addresses,
+                source.to_raw() as libc::c_uint,
+                flags as libc::c_uint,
+            ))
         };
         if size == -1 {
             return Err(Error::last_error());
@@ -1641,16 +5176,75 @@ impl Domain {
         Ok(array)
     }
 
+    /// Gets the interface addresses from the DHCP leases of the
+    /// libvirt managed networks.
+    pub fn interface_addresses_from_lease(&self) -> Result<Vec<Interface>, Error> {
+        self.interface_addresses(InterfaceAddressSource::Lease, 0)
+    }
+
+    /// Gets the interface addresses by querying the guest agent.
+    pub fn interface_addresses_from_agent(&self) -> Result<Vec<Interface>, Error> {
+        self.interface_addresses(InterfaceAddressSource::Agent, 0)
+    }
+
+    /// Gets the interface addresses from the ARP table of the host.
+    pub fn interface_addresses_from_arp(&self) -> Result<Vec<Interface>, Error> {
+        self.interface_addresses(InterfaceAddressSource::Arp, 0)
+    }
+
+    /// Get the address the domain is most likely reachable at.
+    ///
+    /// The guest agent is queried first, then the DHCP leases and
+    /// finally the host ARP table. The first IPv4 address found is
+    /// preferred over IPv6 ones, and loopback and link-local addresses
+    /// are ignored. Returns `None` if no source reports an address, or
+    /// the error of the last source if all of them failed.
+    pub fn primary_ip_address(&self) -> Result<Option<IpAddr>, Error> {
+        let mut last_err = None;
+        let mut succeeded = false;
+        for source in [
+            InterfaceAddressSource::Agent,
+            InterfaceAddressSource::Lease,
+            InterfaceAddressSource::Arp,
+        ] {
+            let ifaces = match self.interface_addresses(source, 0) {
+                Ok(ifaces) => ifaces,
+                Err(e) => {
+                    last_err = Some(e);
+                    continue;
+                }
+            };
+            succeeded = true;
+            let addrs: Vec<IpAddr> = ifaces
+                .iter()
+                .filter(|iface| iface.name != "lo")
+                .flat_map(|iface| iface.addrs.iter())
+                .filter_map(|addr| addr.addr.parse::<IpAddr>().ok())
+                .filter(|addr| match addr {
+                    IpAddr::V4(v4) => !v4.is_loopback() && !v4.is_link_local(),
+                    IpAddr::V6(v6) => !v6.is_loopback() && (v6.segments()[0] & 0xffc0) != 0xfe80,
+                })
+                .collect();
+            if let Some(addr) = addrs.iter().find(|addr| addr.is_ipv4()).or(addrs.first()) {
+                return Ok(Some(*addr));
+            }
+        }
+        match last_err {
+            Some(e) if !succeeded => Err(e),
+            _ => Ok(None),
+        }
+    }
+
     pub fn interface_stats(&self, path: &str) -> Result<InterfaceStats, Error> {
         let mut pinfo = mem::MaybeUninit::uninit();
-        let path_buf = CString::new(path).unwrap();
+        let path_buf = CString::new(path)?;
         let ret = unsafe {
-            sys::virDomainInterfaceStats(
+            ffi!(sys::virDomainInterfaceStats(
                 self.as_ptr(),
                 path_buf.as_ptr(),
                 pinfo.as_mut_ptr(),
                 mem::size_of::<sys::virDomainInterfaceStatsStruct>(),
-            )
+            ))
         };
         if ret == -1 {
             return Err(Error::last_error());
@@ -1658,16 +5252,98 @@ impl Domain {
         Ok(unsafe { InterfaceStats::from_ptr(&mut pinfo.assume_init()) })
     }
 
+    /// Get the traffic statistics of the network interface of the
+    /// domain whose MAC address is `mac`.
+    ///
+    /// The host side device of the interface, such as `vnet0`, is
+    /// taken from the live XML description of the domain, so the
+    /// domain must be running.
+    pub fn interface_stats_by_mac(&self, mac: &str) -> Result<InterfaceStats, Error> {
+        let wanted = MacAddr::parse(mac)
+            .ok_or_else(|| Error::from_invalid_arg(format!("invalid MAC address '{}'", mac)))?;
+        let root = Element::parse(&self.get_xml_desc(0)?)?;
+        let iface = root
+            .child("devices")
+            .into_iter()
+            .flat_map(|devices| devices.children("interface"))
+            .find(|iface| {
+                iface
+                    .child("mac")
+                    .and_then(|m| m.attr("address"))
+                    .and_then(MacAddr::parse)
+                    == Some(wanted)
+            })
+            .ok_or_else(|| {
+                Error::from_device_missing(format!("no interface with MAC address {}", wanted))
+            })?;
+        match iface.child("target").and_then(|t| t.attr("dev")) {
+            Some(dev) => self.interface_stats(dev),
+            None => Err(Error::from_device_missing(format!(
+                "interface {} has no host side device",
+                wanted
+            ))),
+        }
+    }
+
+    /// Get the I/O statistics of a block device of the domain.
+    ///
+    /// `disk` is the target of the disk, such as `vda`, or the path
+    /// of its source.
+    pub fn block_stats(&self, disk: &str) -> Result<BlockStats, Error> {
+        let mut pinfo = mem::MaybeUninit::uninit();
+        let disk_buf = CString::new(disk)?;
+        let ret = unsafe {
+            ffi!(sys::virDomainBlockStats(
+                self.as_ptr(),
+                disk_buf.as_ptr(),
+                pinfo.as_mut_ptr(),
+                mem::size_of::<sys::virDomainBlockStatsStruct>(),
+            ))
+        };
+        if ret == -1 {
+            return Err(Error::last_error());
+        }
+        Ok(unsafe { BlockStats::from_ptr(&mut pinfo.assume_init()) })
+    }
+
+    /// Get the I/O statistics of every disk of the domain, indexed by
+    /// target device.
+    ///
+    /// The disks are taken from the XML description of the domain,
+    /// obtained with `flags`.
+    pub fn all_block_stats(
+        &self,
+        flags: impl Into<DomainXmlFlags>,
+    ) -> Result<HashMap<String, BlockStats>, Error> {
+        let root = Element::parse(&self.get_xml_desc(flags)?)?;
+        let mut stats = HashMap::new();
+        if let Some(devices) = root.child("devices") {
+            for disk in devices.children("disk") {
+                if let Some(dev) = disk.child("target").and_then(|t| t.attr("dev")) {
+                    stats.insert(dev.to_string(), self.block_stats(dev)?);
+                }
+            }
+        }
+        Ok(stats)
+    }
+
+    /// Gets the memory statistics of the domain as tag and value
+    /// pairs. `flags` is reserved and must be 0.
+    ///
+    /// See [`memory_stats_with_period()`] to have the balloon driver
+    /// report its statistics.
+    ///
+    /// [`memory_stats_with_period()`]: Domain::memory_stats_with_period
     pub fn memory_stats(&self, flags: u32) -> Result<Vec<MemoryStat>, Error> {
         let mut pinfo: Vec<sys::virDomainMemoryStatStruct> =
             Vec::with_capacity(sys::VIR_DOMAIN_MEMORY_STAT_NR as usize);
         let ret = unsafe {
-            sys::virDomainMemoryStats(
+            ffi!(sys::virDomainMemoryStats(
                 self.as_ptr(),
                 pinfo.as_mut_ptr(),
                 sys::VIR_DOMAIN_MEMORY_STAT_NR,
                 flags as libc::c_uint,
-            )
+            ))
         };
         if ret == -1 {
             return Err(Error::last_error());
@@ -1683,9 +5359,95 @@ impl Domain {
         Ok(stats)
     }
 
+    /// Gets the disks of the domain which encountered an I/O error.
+    pub fn get_disk_errors(&self, flags: u32) -> Result<Vec<DiskError>, Error> {
+        let ret = unsafe {
+            ffi!(sys::virDomainGetDiskErrors(
+                self.as_ptr(),
+                ptr::null_mut(),
+                0,
+                flags as libc::c_uint
+            ))
+        };
+        if ret == -1 {
+            return Err(Error::last_error());
+        }
+        let mut errors: Vec<sys::virDomainDiskError> = Vec::with_capacity(ret as usize);
+        let ret = unsafe {
+            ffi!(sys::virDomainGetDiskErrors(
+                self.as_ptr(),
+                errors.as_mut_ptr(),
+                errors.capacity() as libc::c_uint,
+                flags as libc::c_uint,
+            ))
+        };
+        if ret == -1 {
+            return Err(Error::last_error());
+        }
+        unsafe { errors.set_len(ret as usize) };
+
+        Ok(errors
+            .iter_mut()
+            .map(|e| unsafe { DiskError::from_ptr(e) })
+            .collect())
+    }
+
+    /// Gets the memory statistics of the domain, as a [`MemoryStats`]
+    /// struct.
+    ///
+    /// See [`memory_stats()`] for the raw tag and value pairs.
+    ///
+    /// [`memory_stats()`]: Domain::memory_stats
+    pub fn get_memory_stats(&self, flags: u32) -> Result<MemoryStats, Error> {
+        Ok(MemoryStats::from_stats(&self.memory_stats(flags)?))
+    }
+
+    /// Makes the balloon driver of the guest refresh its statistics
+    /// every `period` seconds, then gets the memory statistics of the
+    /// domain.
+    ///
+    /// The balloon statistics, such as [`MemoryStats::usable`], are
+    /// only reported once a period is set. As the guest refreshes them
+    /// asynchronously, the first call may return statistics up to one
+    /// period old. A `period` of 0 disables the refresh.
+    pub fn memory_stats_with_period(&self, period: u32) -> Result<MemoryStats, Error> {
+        let period = i32::try_from(period)
+            .map_err(|_| Error::from_invalid_arg(format!("invalid stats period {}", period)))?;
+        self.set_memory_stats_period(period, DomainModificationImpact::LIVE)?;
+        self.get_memory_stats(0)
+    }
+
+    /// Gets the list of mounted file systems of the guest, using the
+    /// guest agent.
+    pub fn get_fs_info(&self, flags: u32) -> Result<Vec<FsInfo>, Error> {
+        let mut info: *mut sys::virDomainFSInfoPtr = ptr::null_mut();
+        let ret = unsafe {
+            ffi!(sys::virDomainGetFSInfo(
+                self.as_ptr(),
+                &mut info,
+                flags as libc::c_uint
+            ))
+        };
+        if ret == -1 {
+            return Err(Error::last_error());
+        }
+
+        let mut array: Vec<FsInfo> = Vec::with_capacity(ret as usize);
+        for x in 0..ret as usize {
+            unsafe {
+                let ptr = *info.add(x);
+                array.push(FsInfo::from_ptr(ptr));
+                ffi!(sys::virDomainFSInfoFree(ptr));
+            }
+        }
+        unsafe { libc::free(info as *mut libc::c_void) };
+
+        Ok(array)
+    }
+
     /// Get progress statistics about a background job running on this domain.
     /// This method will return an error if the domain isn't active
-    pub fn get_job_stats(&self, flags: sys::virDomainGetJobStatsFlags) -> Result<JobStats, Error> {
+    pub fn get_job_stats(&self, flags: impl Into<JobStatsFlags>) -> Result<JobStats, Error> {
         let mut r#type: libc::c_int = 0;
 
         // We allow libvirt to allocate the params structure for us. libvirt will populate
@@ -1694,23 +5456,28 @@ impl Domain {
         let mut params: sys::virTypedParameterPtr = ptr::null_mut();
 
         let ret = unsafe {
-            sys::virDomainGetJobStats(
+            ffi!(sys::virDomainGetJobStats(
                 self.as_ptr(),
                 &mut r#type,
                 &mut params,
                 &mut nparams,
-                flags as libc::c_uint,
-            )
+                flags.into().bits() as libc::c_uint,
+            ))
         };
 
         if ret == -1 {
             return Err(Error::last_error());
         }
 
-        let res: Vec<sys::virTypedParameter> =
-            unsafe { Vec::from_raw_parts(params, nparams as usize, nparams as usize) };
+        let res = unsafe { TypedParamList::from_raw(params, nparams) };
 
-        Ok((r#type, res).into())
+        Ok((r#type, res.params()).into())
+    }
+
+    /// Get statistics about the most recently completed background job
+    /// of this domain, for instance after a migration has finished.
+    pub fn get_completed_job_stats(&self) -> Result<JobStats, Error> {
+        self.get_job_stats(JobStatsFlags::COMPLETED)
     }
 
     /// Get progress information about a background job running on this domain.
@@ -1719,7 +5486,10 @@ impl Domain {
     pub fn get_job_info(&self) -> Result<JobStats, Error> {
         unsafe {
             let mut job_info = mem::MaybeUninit::uninit();
-            let ret = sys::virDomainGetJobInfo(self.as_ptr(), job_info.as_mut_ptr());
+            let ret = ffi!(sys::virDomainGetJobInfo(
+                self.as_ptr(),
+                job_info.as_mut_ptr()
+            ));
 
             if ret == -1 {
                 return Err(Error::last_error());
@@ -1745,18 +5515,64 @@ impl Domain {
         }
     }
 
+    /// Abort the background job currently running on this domain,
+    /// such as a migration or a save.
+    pub fn abort_job(&self) -> Result<(), Error> {
+        let ret = unsafe { ffi!(sys::virDomainAbortJob(self.as_ptr())) };
+        if ret == -1 {
+            return Err(Error::last_error());
+        }
+        Ok(())
+    }
+
+    /// Abort the background job currently running on this domain.
+    ///
+    /// Passing `VIR_DOMAIN_ABORT_JOB_POSTCOPY` allows to interrupt a
+    /// migration in post-copy mode, which is refused otherwise.
+    #[cfg(libvirt_8_5)]
+    pub fn abort_job_flags(&self, flags: sys::virDomainAbortJobFlagsValues) -> Result<(), Error> {
+        let ret = unsafe {
+            ffi!(sys::virDomainAbortJobFlags(
+                self.as_ptr(),
+                flags as libc::c_uint
+            ))
+        };
+        if ret == -1 {
+            return Err(Error::last_error());
+        }
+        Ok(())
+    }
+
+    /// Switch the outgoing migration of this domain to post-copy
+    /// mode.
+    ///
+    /// The migration must have been started with the
+    /// `VIR_MIGRATE_POSTCOPY` flag.
+    pub fn migrate_start_post_copy(&self, flags: u32) -> Result<(), Error> {
+        let ret = unsafe {
+            ffi!(sys::virDomainMigrateStartPostCopy(
+                self.as_ptr(),
+                flags as libc::c_uint
+            ))
+        };
+        if ret == -1 {
+            return Err(Error::last_error());
+        }
+        Ok(())
+    }
+
     pub fn save_image_get_xml_desc(
         conn: &Connect,
         file: &str,
         flags: u32,
     ) -> Result<String, Error> {
-        let file_buf = CString::new(file).unwrap();
+        let file_buf = CString::new(file)?;
         let ptr = unsafe {
-            sys::virDomainSaveImageGetXMLDesc(
+            ffi!(sys::virDomainSaveImageGetXMLDesc(
                 conn.as_ptr(),
                 file_buf.as_ptr(),
                 flags as libc::c_uint,
-            )
+            ))
         };
         if ptr.is_null() {
             return Err(Error::last_error());
@@ -1770,15 +5586,15 @@ impl Domain {
         dxml: &str,
         flags: u32,
     ) -> Result<u32, Error> {
-        let file_buf = CString::new(file).unwrap();
-        let dxml_buf = CString::new(dxml).unwrap();
+        let file_buf = CString::new(file)?;
+        let dxml_buf = CString::new(dxml)?;
         let ret = unsafe {
-            sys::virDomainSaveImageDefineXML(
+            ffi!(sys::virDomainSaveImageDefineXML(
                 conn.as_ptr(),
                 file_buf.as_ptr(),
                 dxml_buf.as_ptr(),
                 flags as libc::c_uint,
-            )
+            ))
         };
         if ret == -1 {
             return Err(Error::last_error());
@@ -1787,18 +5603,26 @@ impl Domain {
     }
 
     pub fn attach_device(&self, xml: &str) -> Result<u32, Error> {
-        let xml_buf = CString::new(xml).unwrap();
-        let ret = unsafe { sys::virDomainAttachDevice(self.as_ptr(), xml_buf.as_ptr()) };
+        let xml_buf = CString::new(xml)?;
+        let ret = unsafe { ffi!(sys::virDomainAttachDevice(self.as_ptr(), xml_buf.as_ptr())) };
         if ret == -1 {
             return Err(Error::last_error());
         }
         Ok(ret as u32)
     }
 
-    pub fn attach_device_flags(&self, xml: &str, flags: u32) -> Result<u32, Error> {
-        let xml_buf = CString::new(xml).unwrap();
+    pub fn attach_device_flags(
+        &self,
+        xml: &str,
+        flags: impl Into<DomainDeviceModifyFlags>,
+    ) -> Result<u32, Error> {
+        let xml_buf = CString::new(xml)?;
         let ret = unsafe {
-            sys::virDomainAttachDeviceFlags(self.as_ptr(), xml_buf.as_ptr(), flags as libc::c_uint)
+            ffi!(sys::virDomainAttachDeviceFlags(
+                self.as_ptr(),
+                xml_buf.as_ptr(),
+                flags.into().bits() as libc::c_uint,
+            ))
         };
         if ret == -1 {
             return Err(Error::last_error());
@@ -1806,30 +5630,133 @@ impl Domain {
         Ok(ret as u32)
     }
 
+    /// Attach the disk described by `spec` to the domain.
+    pub fn attach_disk(
+        &self,
+        spec: &DiskSpec,
+        flags: impl Into<DomainDeviceModifyFlags>,
+    ) -> Result<(), Error> {
+        self.attach_device_flags(&spec.to_xml(), flags).map(|_| ())
+    }
+
+    /// Attach the network interface described by `spec` to the
+    /// domain.
+    pub fn attach_interface(
+        &self,
+        spec: &InterfaceSpec,
+        flags: impl Into<DomainDeviceModifyFlags>,
+    ) -> Result<(), Error> {
+        self.attach_device_flags(&spec.to_xml(), flags).map(|_| ())
+    }
+
+    /// Attach the memory device described by `spec` to the domain.
+    ///
+    /// The domain needs a `<maxMemory>` with free slots for the device
+    /// to be hotplugged.
+    pub fn attach_memory_device(
+        &self,
+        spec: &MemoryDeviceSpec,
+        flags: impl Into<DomainDeviceModifyFlags>,
+    ) -> Result<(), Error> {
+        self.attach_device_flags(&spec.to_xml(), flags).map(|_| ())
+    }
+
+    /// Returns the memory devices of the domain, as described by its
+    /// live XML.
+    pub fn memory_devices(&self) -> Result<Vec<MemoryDevice>, Error> {
+        MemoryDevice::list_from_xml(&self.get_xml_desc(DomainXmlFlags::empty())?)
+    }
+
+    /// Runs `f`, which attaches and detaches devices through the given
+    /// [`DeviceTransaction`], and undoes the changes it made if it
+    /// fails or panics, so that a multi-device hotplug is all or
+    /// nothing.
+    ///
+    /// The error returned by `f` is passed through. Note that a live
+    /// detach only asks the guest to release the device, so a rolled
+    /// back attach may linger until the guest complies.
+    pub fn with_device_transaction<T, F>(&self, f: F) -> Result<T, Error>
+    where
+        F: FnOnce(&mut DeviceTransaction) -> Result<T, Error>,
+    {
+        let mut txn = DeviceTransaction {
+            dom: self,
+            changes: Vec::new(),
+            committed: false,
+        };
+        let ret = f(&mut txn)?;
+        txn.committed = true;
+        Ok(ret)
+    }
+
     pub fn detach_device(&self, xml: &str) -> Result<u32, Error> {
-        let xml_buf = CString::new(xml).unwrap();
-        let ret = unsafe { sys::virDomainDetachDevice(self.as_ptr(), xml_buf.as_ptr()) };
+        let xml_buf = CString::new(xml)?;
+        let ret = unsafe { ffi!(sys::virDomainDetachDevice(self.as_ptr(), xml_buf.as_ptr())) };
+        if ret == -1 {
+            return Err(Error::last_error());
+        }
+        Ok(ret as u32)
+    }
+
+    pub fn detach_device_flags(
+        &self,
+        xml: &str,
+        flags: impl Into<DomainDeviceModifyFlags>,
+    ) -> Result<u32, Error> {
+        let xml_buf = CString::new(xml)?;
+        let ret = unsafe {
+            ffi!(sys::virDomainDetachDeviceFlags(
+                self.as_ptr(),
+                xml_buf.as_ptr(),
+                flags.into().bits() as libc::c_uint,
+            ))
+        };
         if ret == -1 {
             return Err(Error::last_error());
         }
         Ok(ret as u32)
     }
 
-    pub fn detach_device_flags(&self, xml: &str, flags: u32) -> Result<u32, Error> {
-        let xml_buf = CString::new(xml).unwrap();
+    /// Detach the device identified by its alias, e.g. `ua-disk1`,
+    /// from the domain.
+    ///
+    /// Unlike [`detach_device_flags()`], this does not require the
+    /// exact XML of the device. With a live domain, the function only
+    /// asks the guest to release the device; the removal is complete
+    /// once the `DEVICE_REMOVED` event is received.
+    ///
+    /// [`detach_device_flags()`]: Domain::detach_device_flags
+    pub fn detach_device_alias(
+        &self,
+        alias: &str,
+        flags: impl Into<DomainDeviceModifyFlags>,
+    ) -> Result<(), Error> {
+        let alias_buf = CString::new(alias)?;
         let ret = unsafe {
-            sys::virDomainDetachDeviceFlags(self.as_ptr(), xml_buf.as_ptr(), flags as libc::c_uint)
+            ffi!(sys::virDomainDetachDeviceAlias(
+                self.as_ptr(),
+                alias_buf.as_ptr(),
+                flags.into().bits() as libc::c_uint,
+            ))
         };
         if ret == -1 {
             return Err(Error::last_error());
         }
-        Ok(ret as u32)
+        Ok(())
     }
 
-    pub fn update_device_flags(&self, xml: &str, flags: u32) -> Result<u32, Error> {
-        let xml_buf = CString::new(xml).unwrap();
+    pub fn update_device_flags(
+        &self,
+        xml: &str,
+        flags: impl Into<DomainDeviceModifyFlags>,
+    ) -> Result<u32, Error> {
+        let xml_buf = CString::new(xml)?;
         let ret = unsafe {
-            sys::virDomainUpdateDeviceFlags(self.as_ptr(), xml_buf.as_ptr(), flags as libc::c_uint)
+            ffi!(sys::virDomainUpdateDeviceFlags(
+                self.as_ptr(),
+                xml_buf.as_ptr(),
+                flags.into().bits() as libc::c_uint,
+            ))
         };
         if ret == -1 {
             return Err(Error::last_error());
@@ -1838,7 +5765,12 @@ impl Domain {
     }
 
     pub fn managed_save(&self, flags: u32) -> Result<u32, Error> {
-        let ret = unsafe { sys::virDomainManagedSave(self.as_ptr(), flags as libc::c_uint) };
+        let ret = unsafe {
+            ffi!(sys::virDomainManagedSave(
+                self.as_ptr(),
+                flags as libc::c_uint
+            ))
+        };
         if ret == -1 {
             return Err(Error::last_error());
         }
@@ -1846,8 +5778,12 @@ impl Domain {
     }
 
     pub fn has_managed_save(&self, flags: u32) -> Result<bool, Error> {
-        let ret =
-            unsafe { sys::virDomainHasManagedSaveImage(self.as_ptr(), flags as libc::c_uint) };
+        let ret = unsafe {
+            ffi!(sys::virDomainHasManagedSaveImage(
+                self.as_ptr(),
+                flags as libc::c_uint
+            ))
+        };
         if ret == -1 {
             return Err(Error::last_error());
         }
@@ -1855,7 +5791,12 @@ impl Domain {
     }
 
     pub fn managed_save_remove(&self, flags: u32) -> Result<u32, Error> {
-        let ret = unsafe { sys::virDomainManagedSaveRemove(self.as_ptr(), flags as libc::c_uint) };
+        let ret = unsafe {
+            ffi!(sys::virDomainManagedSaveRemove(
+                self.as_ptr(),
+                flags as libc::c_uint
+            ))
+        };
         if ret == -1 {
             return Err(Error::last_error());
         }
@@ -1863,9 +5804,13 @@ impl Domain {
     }
 
     pub fn core_dump(&self, to: &str, flags: u32) -> Result<u32, Error> {
-        let to_buf = CString::new(to).unwrap();
+        let to_buf = CString::new(to)?;
         let ret = unsafe {
-            sys::virDomainCoreDump(self.as_ptr(), to_buf.as_ptr(), flags as libc::c_uint)
+            ffi!(sys::virDomainCoreDump(
+                self.as_ptr(),
+                to_buf.as_ptr(),
+                flags as libc::c_uint
+            ))
         };
         if ret == -1 {
             return Err(Error::last_error());
@@ -1873,15 +5818,20 @@ impl Domain {
         Ok(ret as u32)
     }
 
-    pub fn core_dump_with_format(&self, to: &str, format: u32, flags: u32) -> Result<u32, Error> {
-        let to_buf = CString::new(to).unwrap();
+    pub fn core_dump_with_format(
+        &self,
+        to: &str,
+        format: DumpFormat,
+        flags: impl Into<DomainCoreDumpFlags>,
+    ) -> Result<u32, Error> {
+        let to_buf = CString::new(to)?;
         let ret = unsafe {
-            sys::virDomainCoreDumpWithFormat(
+            ffi!(sys::virDomainCoreDumpWithFormat(
                 self.as_ptr(),
                 to_buf.as_ptr(),
-                format as libc::c_uint,
-                flags as libc::c_uint,
-            )
+                format.to_raw() as libc::c_uint,
+                flags.into().bits() as libc::c_uint,
+            ))
         };
         if ret == -1 {
             return Err(Error::last_error());
@@ -1889,26 +5839,37 @@ impl Domain {
         Ok(ret as u32)
     }
 
+    /// Dumps the domain core to the file `to`, as described by `options`.
+    ///
+    /// This is equivalent to [`core_dump_with_format()`] with the
+    /// format and flags built from `options`.
+    ///
+    /// [`core_dump_with_format()`]: Domain::core_dump_with_format
+    pub fn core_dump_params(&self, to: &str, options: &CoreDumpOptions) -> Result<(), Error> {
+        self.core_dump_with_format(to, options.format, options.flags())?;
+        Ok(())
+    }
+
     pub fn set_metadata(
         &self,
         kind: i32,
         metadata: Option<&str>,
         key: Option<&str>,
         uri: Option<&str>,
-        flags: u32,
+        flags: impl Into<DomainModificationImpact>,
     ) -> Result<u32, Error> {
         let metadata_buf = some_string_to_cstring!(metadata);
         let key_buf = some_string_to_cstring!(key);
         let uri_buf = some_string_to_cstring!(uri);
         let ret = unsafe {
-            sys::virDomainSetMetadata(
+            ffi!(sys::virDomainSetMetadata(
                 self.as_ptr(),
                 kind as libc::c_int,
                 some_cstring_to_c_chars!(metadata_buf),
                 some_cstring_to_c_chars!(key_buf),
                 some_cstring_to_c_chars!(uri_buf),
-                flags as libc::c_uint,
-            )
+                flags.into().bits() as libc::c_uint,
+            ))
         };
         if ret == -1 {
             return Err(Error::last_error());
@@ -1916,15 +5877,20 @@ impl Domain {
         Ok(ret as u32)
     }
 
-    pub fn get_metadata(&self, kind: i32, uri: Option<&str>, flags: u32) -> Result<String, Error> {
+    pub fn get_metadata(
+        &self,
+        kind: i32,
+        uri: Option<&str>,
+        flags: impl Into<DomainModificationImpact>,
+    ) -> Result<String, Error> {
         let uri_buf = some_string_to_cstring!(uri);
         let n = unsafe {
-            sys::virDomainGetMetadata(
+            ffi!(sys::virDomainGetMetadata(
                 self.as_ptr(),
                 kind as libc::c_int,
                 some_cstring_to_c_chars!(uri_buf),
-                flags as libc::c_uint,
-            )
+                flags.into().bits() as libc::c_uint,
+            ))
         };
         if n.is_null() {
             return Err(Error::last_error());
@@ -1932,15 +5898,23 @@ impl Domain {
         Ok(unsafe { c_chars_to_string!(n) })
     }
 
-    pub fn block_resize(&self, disk: &str, size: u64, flags: u32) -> Result<u32, Error> {
-        let disk_buf = CString::new(disk).unwrap();
+    /// Resize a block device of the domain.
+    ///
+    /// `size` is in KiB, unless [`BlockResizeFlags::BYTES`] is given.
+    pub fn block_resize(
+        &self,
+        disk: &str,
+        size: u64,
+        flags: impl Into<BlockResizeFlags>,
+    ) -> Result<u32, Error> {
+        let disk_buf = CString::new(disk)?;
         let ret = unsafe {
-            sys::virDomainBlockResize(
+            ffi!(sys::virDomainBlockResize(
                 self.as_ptr(),
                 disk_buf.as_ptr(),
                 size as libc::c_ulonglong,
-                flags as libc::c_uint,
-            )
+                flags.into().bits() as libc::c_uint,
+            ))
         };
         if ret == -1 {
             return Err(Error::last_error());
@@ -1948,49 +5922,292 @@ impl Domain {
         Ok(ret as u32)
     }
 
-    pub fn get_memory_parameters(&self, flags: u32) -> Result<MemoryParameters, Error> {
+    /// Grow a block device of the domain to the full capacity of its
+    /// backing storage, e.g. after the volume has been enlarged.
+    pub fn block_grow_to_capacity(&self, disk: &str) -> Result<(), Error> {
+        self.block_resize(disk, 0, BlockResizeFlags::CAPACITY)
+            .map(|_| ())
+    }
+
+    /// Get the progress of the block job running on `disk`.
+    ///
+    /// Returns `None` if no job is running.
+    pub fn get_block_job_info(
+        &self,
+        disk: &str,
+        flags: impl Into<BlockJobInfoFlags>,
+    ) -> Result<Option<BlockJobInfo>, Error> {
+        let mut info = mem::MaybeUninit::uninit();
+        let disk_buf = CString::new(disk)?;
+        let ret = unsafe {
+            ffi!(sys::virDomainGetBlockJobInfo(
+                self.as_ptr(),
+                disk_buf.as_ptr(),
+                info.as_mut_ptr(),
+                flags.into().bits() as libc::c_uint,
+            ))
+        };
+        if ret == -1 {
+            return Err(Error::last_error());
+        }
+        if ret == 0 {
+            return Ok(None);
+        }
+        Ok(Some(unsafe {
+            BlockJobInfo::from_ptr(&mut info.assume_init())
+        }))
+    }
+
+    /// Change the bandwidth limit of the block job running on `disk`.
+    ///
+    /// `bandwidth` is in MiB/s, unless
+    /// [`BlockJobSetSpeedFlags::BANDWIDTH_BYTES`] is given.
+    pub fn block_job_set_speed(
+        &self,
+        disk: &str,
+        bandwidth: u64,
+        flags: impl Into<BlockJobSetSpeedFlags>,
+    ) -> Result<(), Error> {
+        let disk_buf = CString::new(disk)?;
+        let ret = unsafe {
+            ffi!(sys::virDomainBlockJobSetSpeed(
+                self.as_ptr(),
+                disk_buf.as_ptr(),
+                bandwidth as libc::c_ulong,
+                flags.into().bits() as libc::c_uint,
+            ))
+        };
+        if ret == -1 {
+            return Err(Error::last_error());
+        }
+        Ok(())
+    }
+
+    /// Cancel the block job running on `disk`, or pivot to its
+    /// destination with [`BlockJobAbortFlags::PIVOT`].
+    pub fn block_job_abort(
+        &self,
+        disk: &str,
+        flags: impl Into<BlockJobAbortFlags>,
+    ) -> Result<(), Error> {
+        let disk_buf = CString::new(disk)?;
+        let ret = unsafe {
+            ffi!(sys::virDomainBlockJobAbort(
+                self.as_ptr(),
+                disk_buf.as_ptr(),
+                flags.into().bits() as libc::c_uint,
+            ))
+        };
+        if ret == -1 {
+            return Err(Error::last_error());
+        }
+        Ok(())
+    }
+
+    /// Start copying `disk` to the destination described by the
+    /// `<disk>` element `destxml`.
+    ///
+    /// The copy runs as a block job; once ready it keeps mirroring the
+    /// guest writes until it is pivoted or aborted with
+    /// [`Domain::block_job_abort()`].
+    pub fn block_copy(
+        &self,
+        disk: &str,
+        destxml: &str,
+        params: BlockCopyParameters,
+        flags: impl Into<BlockCopyFlags>,
+    ) -> Result<(), Error> {
+        let disk_buf = CString::new(disk)?;
+        let destxml_buf = CString::new(destxml)?;
+        let mut cparams = TypedParamList::from_vec(params.to_vec()?);
+        let ret = unsafe {
+            ffi!(sys::virDomainBlockCopy(
+                self.as_ptr(),
+                disk_buf.as_ptr(),
+                destxml_buf.as_ptr(),
+                cparams.as_mut_ptr(),
+                cparams.len(),
+                flags.into().bits() as libc::c_uint,
+            ))
+        };
+        if ret == -1 {
+            return Err(Error::last_error());
+        }
+        Ok(())
+    }
+
+    /// Copy `disk` to the file `dest_path`.
+    ///
+    /// With [`BlockCopyOptions::pivot`], this waits for the copy to be
+    /// ready and switches the disk to the new file; otherwise it
+    /// returns as soon as the job is started.
+    pub fn block_copy_to_file(
+        &self,
+        disk: &str,
+        dest_path: &str,
+        options: &BlockCopyOptions,
+    ) -> Result<(), Error> {
+        let source = format!("file='{}'", escape_str_attribute(dest_path));
+        self.block_copy_and_pivot(disk, &options.disk_xml("file", &source), options)
+    }
+
+    /// Copy `disk` to the storage volume `vol`, see
+    /// [`Domain::block_copy_to_file()`].
+    pub fn block_copy_to_volume(
+        &self,
+        disk: &str,
+        vol: &StorageVol,
+        options: &BlockCopyOptions,
+    ) -> Result<(), Error> {
+        let pool = StoragePool::lookup_by_volume(vol)?;
+        let source = format!(
+            "pool='{}' volume='{}'",
+            escape_str_attribute(&pool.get_name()?),
+            escape_str_attribute(&vol.get_name()?)
+        );
+        self.block_copy_and_pivot(disk, &options.disk_xml("volume", &source), options)
+    }
+
+    fn block_copy_and_pivot(
+        &self,
+        disk: &str,
+        destxml: &str,
+        options: &BlockCopyOptions,
+    ) -> Result<(), Error> {
+        self.block_copy(disk, destxml, options.parameters.clone(), options.flags())?;
+        if !options.pivot {
+            return Ok(());
+        }
+        let timeout = options
+            .pivot_timeout
+            .unwrap_or(BlockCopyOptions::DEFAULT_PIVOT_TIMEOUT);
+        let ret =
+            match self.wait_for_block_job(disk, Duration::from_millis(500), timeout, |_, _| {}) {
+                Ok(BlockJobOutcome::Ready(_)) => {
+                    self.block_job_abort(disk, BlockJobAbortFlags::PIVOT)
+                }
+                Ok(BlockJobOutcome::Ended) => {
+                    return Err(Error::from_operation_invalid(format!(
+                        "block copy of {} ended before it was ready",
+                        disk
+                    )))
+                }
+                Err(e) => Err(e),
+            };
+        if let Err(e) = ret {
+            // Do not leave the copy mirroring the guest writes.
+            let _ = self.block_job_abort(disk, BlockJobAbortFlags::empty());
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    /// Wait for the block job running on `disk` to become ready or to
+    /// end, polling its progress every `poll_interval`.
+    ///
+    /// `progress` is called with the `cur` and `end` counters of the
+    /// job after every poll. Fails with
+    /// [`ErrorNumber::OperationTimeout`] if the job is still running
+    /// after `timeout`; the job is left running.
+    pub fn wait_for_block_job<F>(
+        &self,
+        disk: &str,
+        poll_interval: Duration,
+        timeout: Duration,
+        mut progress: F,
+    ) -> Result<BlockJobOutcome, Error>
+    where
+        F: FnMut(u64, u64),
+    {
+        let deadline = Instant::now() + timeout;
+        loop {
+            match self.get_block_job_info(disk, BlockJobInfoFlags::empty())? {
+                None => return Ok(BlockJobOutcome::Ended),
+                Some(info) => {
+                    progress(info.cur, info.end);
+                    if info.is_ready() {
+                        return Ok(BlockJobOutcome::Ready(info));
+                    }
+                }
+            }
+            let now = Instant::now();
+            if now >= deadline {
+                return Err(Error::from_timeout(format!(
+                    "block job on {} still running after {:?}",
+                    disk, timeout
+                )));
+            }
+            thread::sleep(poll_interval.min(deadline - now));
+        }
+    }
+
+    pub fn get_memory_parameters(
+        &self,
+        flags: impl Into<DomainModificationImpact>,
+    ) -> Result<MemoryParameters, Error> {
+        Ok(MemoryParameters::from_vec(
+            self.memory_parameters_list(flags.into())?.params(),
+        ))
+    }
+
+    /// Get the memory parameters of the domain by name, including the
+    /// ones [`MemoryParameters`] does not cover.
+    pub fn get_memory_parameters_raw(
+        &self,
+        flags: impl Into<DomainModificationImpact>,
+    ) -> Result<TypedParamsMap, Error> {
+        Ok(TypedParamsMap::from_vec(
+            self.memory_parameters_list(flags.into())?.params(),
+        ))
+    }
+
+    fn memory_parameters_list(
+        &self,
+        flags: DomainModificationImpact,
+    ) -> Result<TypedParamList, Error> {
+        let flags = flags.bits();
         let mut nparams: libc::c_int = 0;
         let ret = unsafe {
-            sys::virDomainGetMemoryParameters(
+            ffi!(sys::virDomainGetMemoryParameters(
                 self.as_ptr(),
                 ptr::null_mut(),
                 &mut nparams,
                 flags as libc::c_uint,
-            )
+            ))
         };
         if ret == -1 {
             return Err(Error::last_error());
         }
-        let mut params: Vec<sys::virTypedParameter> = Vec::with_capacity(nparams as usize);
+        let mut params = TypedParamList::with_capacity(nparams);
         let ret = unsafe {
-            sys::virDomainGetMemoryParameters(
+            ffi!(sys::virDomainGetMemoryParameters(
                 self.as_ptr(),
                 params.as_mut_ptr(),
                 &mut nparams,
                 flags as libc::c_uint,
-            )
+            ))
         };
         if ret == -1 {
             return Err(Error::last_error());
         }
-        unsafe { params.set_len(nparams as usize) };
-        Ok(MemoryParameters::from_vec(params))
+        unsafe { params.set_len(nparams) };
+        Ok(params)
     }
 
     pub fn set_memory_parameters(
         &self,
         params: MemoryParameters,
-        flags: u32,
+        flags: impl Into<DomainModificationImpact>,
     ) -> Result<u32, Error> {
-        let mut cparams = params.to_vec();
+        let mut cparams = TypedParamList::from_vec(params.to_vec()?);
 
         let ret = unsafe {
-            sys::virDomainSetMemoryParameters(
+            ffi!(sys::virDomainSetMemoryParameters(
                 self.as_ptr(),
                 cparams.as_mut_ptr(),
-                cparams.len() as libc::c_int,
-                flags as libc::c_uint,
-            )
+                cparams.len(),
+                flags.into().bits() as libc::c_uint,
+            ))
         };
         if ret == -1 {
             return Err(Error::last_error());
@@ -1998,6 +6215,57 @@ impl Domain {
         Ok(ret as u32)
     }
 
+    /// Gets the enablement state of the perf events of the domain.
+    pub fn get_perf_events(
+        &self,
+        flags: impl Into<DomainModificationImpact>,
+    ) -> Result<PerfEvents, Error> {
+        // libvirt allocates the params structure and populates
+        // nparams with the number of typed params returned.
+        let mut nparams: libc::c_int = 0;
+        let mut params: sys::virTypedParameterPtr = ptr::null_mut();
+
+        let ret = unsafe {
+            ffi!(sys::virDomainGetPerfEvents(
+                self.as_ptr(),
+                &mut params,
+                &mut nparams,
+                flags.into().bits() as libc::c_uint,
+            ))
+        };
+        if ret == -1 {
+            return Err(Error::last_error());
+        }
+
+        let res = unsafe { TypedParamList::from_raw(params, nparams) };
+
+        Ok(PerfEvents::from_vec(res.params()))
+    }
+
+    /// Enables or disables the perf events of the domain.
+    ///
+    /// Events left as `None` in `events` are not modified.
+    pub fn set_perf_events(
+        &self,
+        events: PerfEvents,
+        flags: impl Into<DomainModificationImpact>,
+    ) -> Result<(), Error> {
+        let mut cparams = TypedParamList::from_vec(events.to_vec()?);
+
+        let ret = unsafe {
+            ffi!(sys::virDomainSetPerfEvents(
+                self.as_ptr(),
+                cparams.as_mut_ptr(),
+                cparams.len(),
+                flags.into().bits() as libc::c_uint,
+            ))
+        };
+        if ret == -1 {
+            return Err(Error::last_error());
+        }
+        Ok(())
+    }
+
     pub fn migrate(
         &self,
         dconn: &Connect,
@@ -2009,14 +6277,14 @@ impl Domain {
         let dname_buf = some_string_to_cstring!(dname);
         let uri_buf = some_string_to_cstring!(uri);
         let ptr = unsafe {
-            sys::virDomainMigrate(
+            ffi!(sys::virDomainMigrate(
                 self.as_ptr(),
                 dconn.as_ptr(),
                 flags as libc::c_ulong,
                 some_cstring_to_c_chars!(dname_buf),
                 some_cstring_to_c_chars!(uri_buf),
                 bandwidth as libc::c_ulong,
-            )
+            ))
         };
         if ptr.is_null() {
             return Err(Error::last_error());
@@ -2037,7 +6305,7 @@ impl Domain {
         let dname_buf = some_string_to_cstring!(dname);
         let uri_buf = some_string_to_cstring!(uri);
         let ptr = unsafe {
-            sys::virDomainMigrate2(
+            ffi!(sys::virDomainMigrate2(
                 self.as_ptr(),
                 dconn.as_ptr(),
                 some_cstring_to_c_chars!(dxml_buf),
@@ -2045,7 +6313,7 @@ impl Domain {
                 some_cstring_to_c_chars!(dname_buf),
                 some_cstring_to_c_chars!(uri_buf),
                 bandwidth as libc::c_ulong,
-            )
+            ))
         };
         if ptr.is_null() {
             return Err(Error::last_error());
@@ -2059,15 +6327,15 @@ impl Domain {
         parameters: MigrateParameters,
         flags: u32,
     ) -> Result<Domain, Error> {
-        let params = parameters.to_vec();
+        let mut params = TypedParamList::from_vec(parameters.to_vec()?);
         let ptr = unsafe {
-            sys::virDomainMigrate3(
+            ffi!(sys::virDomainMigrate3(
                 self.as_ptr(),
                 dconn.as_ptr(),
-                params.clone().as_mut_ptr(),
+                params.as_mut_ptr(),
                 params.len() as libc::c_uint,
                 flags as libc::c_uint,
-            )
+            ))
         };
         if ptr.is_null() {
             return Err(Error::last_error());
@@ -2082,16 +6350,16 @@ impl Domain {
         dname: Option<&str>,
         bandwidth: u64,
     ) -> Result<(), Error> {
-        let duri_buf = CString::new(duri).unwrap();
+        let duri_buf = CString::new(duri)?;
         let dname_buf = some_string_to_cstring!(dname);
         let ret = unsafe {
-            sys::virDomainMigrateToURI(
+            ffi!(sys::virDomainMigrateToURI(
                 self.as_ptr(),
                 duri_buf.as_ptr(),
                 flags as libc::c_ulong,
                 some_cstring_to_c_chars!(dname_buf),
                 bandwidth as libc::c_ulong,
-            )
+            ))
         };
         if ret == -1 {
             return Err(Error::last_error());
@@ -2113,7 +6381,7 @@ impl Domain {
         let dxml_buf = some_string_to_cstring!(dxml);
         let dname_buf = some_string_to_cstring!(dname);
         let ret = unsafe {
-            sys::virDomainMigrateToURI2(
+            ffi!(sys::virDomainMigrateToURI2(
                 self.as_ptr(),
                 some_cstring_to_c_chars!(dconn_uri_buf),
                 some_cstring_to_c_chars!(mig_uri_buf),
@@ -2121,7 +6389,7 @@ impl Domain {
                 flags as libc::c_ulong,
                 some_cstring_to_c_chars!(dname_buf),
                 bandwidth as libc::c_ulong,
-            )
+            ))
         };
         if ret == -1 {
             return Err(Error::last_error());
@@ -2135,16 +6403,16 @@ impl Domain {
         parameters: MigrateParameters,
         flags: u32,
     ) -> Result<(), Error> {
-        let params = parameters.to_vec();
+        let mut params = TypedParamList::from_vec(parameters.to_vec()?);
         let dconn_uri_buf = some_string_to_cstring!(dconn_uri);
         let ret = unsafe {
-            sys::virDomainMigrateToURI3(
+            ffi!(sys::virDomainMigrateToURI3(
                 self.as_ptr(),
                 some_cstring_to_c_chars!(dconn_uri_buf),
-                params.clone().as_mut_ptr(),
+                params.as_mut_ptr(),
                 params.len() as libc::c_uint,
                 flags as libc::c_uint,
-            )
+            ))
         };
         if ret == -1 {
             return Err(Error::last_error());
@@ -2152,59 +6420,88 @@ impl Domain {
         Ok(())
     }
 
-    pub fn get_numa_parameters(&self, flags: u32) -> Result<NUMAParameters, Error> {
+    pub fn get_numa_parameters(
+        &self,
+        flags: impl Into<DomainModificationImpact>,
+    ) -> Result<NUMAParameters, Error> {
+        let params = self.numa_parameters_list(flags.into())?;
+        Ok(NUMAParameters::from_vec(params.params()))
+    }
+
+    /// Get the NUMA parameters of the domain by name, including the
+    /// ones [`NUMAParameters`] does not cover.
+    pub fn get_numa_parameters_raw(
+        &self,
+        flags: impl Into<DomainModificationImpact>,
+    ) -> Result<TypedParamsMap, Error> {
+        let params = self.numa_parameters_list(flags.into())?;
+        Ok(TypedParamsMap::from_vec(params.params()))
+    }
+
+    fn numa_parameters_list(
+        &self,
+        flags: DomainModificationImpact,
+    ) -> Result<TypedParamList, Error> {
+        let flags = flags.bits();
         let mut nparams: libc::c_int = 0;
         let ret = unsafe {
-            sys::virDomainGetNumaParameters(
+            ffi!(sys::virDomainGetNumaParameters(
                 self.as_ptr(),
                 ptr::null_mut(),
                 &mut nparams,
                 flags as libc::c_uint,
-            )
+            ))
         };
         if ret == -1 {
             return Err(Error::last_error());
         }
-        let mut params: Vec<sys::virTypedParameter> = Vec::with_capacity(nparams as usize);
+        let mut params = TypedParamList::with_capacity(nparams);
         let ret = unsafe {
-            sys::virDomainGetNumaParameters(
+            ffi!(sys::virDomainGetNumaParameters(
                 self.as_ptr(),
                 params.as_mut_ptr(),
                 &mut nparams,
                 flags as libc::c_uint,
-            )
+            ))
         };
         if ret == -1 {
             return Err(Error::last_error());
         }
-        unsafe { params.set_len(nparams as usize) };
-        let nparams = NUMAParameters::from_vec(params.clone());
-        unsafe { typed_params_release_c_chars!(params) };
-
-        Ok(nparams)
+        unsafe { params.set_len(nparams) };
+        Ok(params)
     }
 
-    pub fn set_numa_parameters(&self, params: NUMAParameters, flags: u32) -> Result<u32, Error> {
-        let mut cparams = params.to_vec();
+    pub fn set_numa_parameters(
+        &self,
+        params: NUMAParameters,
+        flags: impl Into<DomainModificationImpact>,
+    ) -> Result<u32, Error> {
+        let mut cparams = TypedParamList::from_vec(params.to_vec()?);
         let ret = unsafe {
-            sys::virDomainSetNumaParameters(
+            ffi!(sys::virDomainSetNumaParameters(
                 self.as_ptr(),
                 cparams.as_mut_ptr(),
-                cparams.len() as libc::c_int,
-                flags as libc::c_uint,
-            )
+                cparams.len(),
+                flags.into().bits() as libc::c_uint,
+            ))
         };
-        unsafe { typed_params_release_c_chars!(cparams) };
         if ret == -1 {
             return Err(Error::last_error());
         }
         Ok(ret as u32)
     }
 
-    pub fn list_all_snapshots(&self, flags: u32) -> Result<Vec<DomainSnapshot>, Error> {
+    pub fn list_all_snapshots(
+        &self,
+        flags: impl Into<SnapshotListFlags>,
+    ) -> Result<Vec<DomainSnapshot>, Error> {
         let mut snaps: *mut sys::virDomainSnapshotPtr = ptr::null_mut();
         let size = unsafe {
-            sys::virDomainListAllSnapshots(self.as_ptr(), &mut snaps, flags as libc::c_uint)
+            ffi!(sys::virDomainListAllSnapshots(
+                self.as_ptr(),
+                &mut snaps,
+                flags.into().bits() as libc::c_uint,
+            ))
         };
         if size == -1 {
             return Err(Error::last_error());
@@ -2219,10 +6516,31 @@ impl Domain {
         Ok(array)
     }
 
+    /// Returns the snapshots of the domain arranged by parent, one
+    /// tree per root snapshot.
+    ///
+    /// The snapshots are listed once and each parent is looked up once,
+    /// so the cost is linear in the number of snapshots.
+    pub fn snapshot_tree(&self) -> Result<Vec<SnapshotNode>, Error> {
+        let snaps = self.list_all_snapshots(SnapshotListFlags::empty())?;
+        let mut entries = Vec::with_capacity(snaps.len());
+        for snap in snaps {
+            let name = snap.get_name()?;
+            let parent = match snap.get_parent(0) {
+                Ok(parent) => Some(parent.get_name()?),
+                Err(e) if e.code() == ErrorNumber::NoDomainSnapshot => None,
+                Err(e) => return Err(e),
+            };
+            entries.push((snap, name, parent));
+        }
+        Ok(SnapshotNode::build(entries))
+    }
+
     /// Get the cpu scheduler type for the domain
     pub fn get_scheduler_type(&self) -> Result<(String, i32), Error> {
         let mut nparams: libc::c_int = -1;
-        let sched_type = unsafe { sys::virDomainGetSchedulerType(self.as_ptr(), &mut nparams) };
+        let sched_type =
+            unsafe { ffi!(sys::virDomainGetSchedulerType(self.as_ptr(), &mut nparams)) };
         if sched_type.is_null() {
             return Err(Error::last_error());
         }
@@ -2233,15 +6551,19 @@ impl Domain {
     /// Get the scheduler parameters for the domain.
     pub fn get_scheduler_parameters(&self) -> Result<SchedulerInfo, Error> {
         let (sched_type, mut nparams) = self.get_scheduler_type()?;
-        let mut params: Vec<sys::virTypedParameter> = Vec::with_capacity(nparams as usize);
+        let mut params = TypedParamList::with_capacity(nparams);
         let ret = unsafe {
-            sys::virDomainGetSchedulerParameters(self.as_ptr(), params.as_mut_ptr(), &mut nparams)
+            ffi!(sys::virDomainGetSchedulerParameters(
+                self.as_ptr(),
+                params.as_mut_ptr(),
+                &mut nparams
+            ))
         };
         if ret == -1 {
             return Err(Error::last_error());
         }
-        unsafe { params.set_len(nparams as usize) };
-        Ok(SchedulerInfo::from_vec(params, sched_type))
+        unsafe { params.set_len(nparams) };
+        Ok(SchedulerInfo::from_vec(params.params(), sched_type))
     }
 
     /// Get the scheduler parameters for the domain for the configuration
@@ -2257,34 +6579,54 @@ impl Domain {
     /// [`VIR_DOMAIN_AFFECT_CONFIG`]: sys::VIR_DOMAIN_AFFECT_CONFIG
     pub fn get_scheduler_parameters_flags(
         &self,
-        flags: sys::virDomainModificationImpact,
+        flags: impl Into<DomainModificationImpact>,
     ) -> Result<SchedulerInfo, Error> {
-        let (sched_type, mut nparams) = self.get_scheduler_type()?;
-        let mut params: Vec<sys::virTypedParameter> = Vec::with_capacity(nparams as usize);
+        let (sched_type, nparams) = self.get_scheduler_type()?;
+        let params = self.scheduler_parameters_list(nparams, flags.into())?;
+        Ok(SchedulerInfo::from_vec(params.params(), sched_type))
+    }
+
+    /// Get the scheduler parameters of the domain by name, including
+    /// the ones [`SchedulerInfo`] does not cover.
+    pub fn get_scheduler_parameters_raw(
+        &self,
+        flags: impl Into<DomainModificationImpact>,
+    ) -> Result<TypedParamsMap, Error> {
+        let (_, nparams) = self.get_scheduler_type()?;
+        let params = self.scheduler_parameters_list(nparams, flags.into())?;
+        Ok(TypedParamsMap::from_vec(params.params()))
+    }
+
+    fn scheduler_parameters_list(
+        &self,
+        mut nparams: libc::c_int,
+        flags: DomainModificationImpact,
+    ) -> Result<TypedParamList, Error> {
+        let mut params = TypedParamList::with_capacity(nparams);
         let ret = unsafe {
-            sys::virDomainGetSchedulerParametersFlags(
+            ffi!(sys::virDomainGetSchedulerParametersFlags(
                 self.as_ptr(),
                 params.as_mut_ptr(),
                 &mut nparams,
-                flags as libc::c_uint,
-            )
+                flags.bits() as libc::c_uint,
+            ))
         };
         if ret == -1 {
             return Err(Error::last_error());
         }
-        unsafe { params.set_len(nparams as usize) };
-        Ok(SchedulerInfo::from_vec(params, sched_type))
+        unsafe { params.set_len(nparams) };
+        Ok(params)
     }
 
     /// Set the scheduler parameters for the domain.
     pub fn set_scheduler_parameters(&self, sched_info: &SchedulerInfo) -> Result<i32, Error> {
-        let mut params = sched_info.to_vec();
+        let mut params = TypedParamList::from_vec(sched_info.to_vec()?);
         let ret = unsafe {
-            sys::virDomainSetSchedulerParameters(
+            ffi!(sys::virDomainSetSchedulerParameters(
                 self.as_ptr(),
                 params.as_mut_ptr(),
-                params.len() as libc::c_int,
-            )
+                params.len(),
+            ))
         };
         if ret == -1 {
             return Err(Error::last_error());
@@ -2306,16 +6648,16 @@ impl Domain {
     pub fn set_scheduler_parameters_flags(
         &self,
         sched_info: &SchedulerInfo,
-        flags: sys::virDomainModificationImpact,
+        flags: impl Into<DomainModificationImpact>,
     ) -> Result<i32, Error> {
-        let mut params = sched_info.to_vec();
+        let mut params = TypedParamList::from_vec(sched_info.to_vec()?);
         let ret = unsafe {
-            sys::virDomainSetSchedulerParametersFlags(
+            ffi!(sys::virDomainSetSchedulerParametersFlags(
                 self.as_ptr(),
                 params.as_mut_ptr(),
-                params.len() as libc::c_int,
-                flags as libc::c_uint,
-            )
+                params.len(),
+                flags.into().bits() as libc::c_uint,
+            ))
         };
         if ret == -1 {
             return Err(Error::last_error());
@@ -2328,26 +6670,25 @@ impl Domain {
     ///
     /// * `codeset` - Specifies the code set of keycodes.
     /// * `holdtime` - Specifies the duration (in milliseconds) that the keys will be held.
-    /// * `keycodes` - Specifies the array of keycodes.
-    /// * `nkeycodes` - Specifies the number of keycodes.
+    /// * `keycodes` - Specifies the keycodes, pressed together.
     /// * `flags` - Extra flags; not used yet, so callers should always pass 0..
     pub fn send_key(
         &self,
-        codeset: sys::virKeycodeSet,
+        codeset: KeycodeSet,
         holdtime: u32,
-        keycodes: *mut u32,
-        nkeycodes: i32,
+        keycodes: &[u32],
         flags: u32,
     ) -> Result<(), Error> {
+        let mut keycodes = keycodes.to_vec();
         let ret = unsafe {
-            sys::virDomainSendKey(
+            ffi!(sys::virDomainSendKey(
                 self.as_ptr(),
-                codeset as libc::c_uint,
+                codeset.to_raw() as libc::c_uint,
                 holdtime as libc::c_uint,
-                keycodes as *mut libc::c_uint,
-                nkeycodes as libc::c_int,
+                keycodes.as_mut_ptr() as *mut libc::c_uint,
+                keycodes.len() as libc::c_int,
                 flags as libc::c_uint,
-            )
+            ))
         };
         if ret == -1 {
             return Err(Error::last_error());
@@ -2365,12 +6706,12 @@ impl Domain {
     /// * `flags` - extra flags; not used yet, so callers should always pass 0
     pub fn screenshot(&self, stream: &Stream, screen: u32, flags: u32) -> Result<String, Error> {
         let n = unsafe {
-            sys::virDomainScreenshot(
+            ffi!(sys::virDomainScreenshot(
                 self.as_ptr(),
                 stream.as_ptr(),
                 screen as libc::c_uint,
                 flags as libc::c_uint,
-            )
+            ))
         };
         if n.is_null() {
             return Err(Error::last_error());
@@ -2378,6 +6719,54 @@ impl Domain {
         Ok(unsafe { c_chars_to_string!(n) })
     }
 
+    /// Take a screenshot of `screen` and write the image into `writer`.
+    ///
+    /// Creates a blocking stream, receives the whole image and finishes
+    /// the stream. The stream is aborted if receiving or writing fails.
+    /// Returns the mime-type of the image format.
+    pub fn screenshot_to_writer<W: Write>(
+        &self,
+        writer: &mut W,
+        screen: u32,
+    ) -> Result<String, Error> {
+        let stream = Stream::new(&self.get_connect()?, 0)?;
+        let mime = self.screenshot(&stream, screen, 0)?;
+        let mut buf = vec![0u8; 64 * 1024];
+        loop {
+            let res = stream.recv(&mut buf).and_then(|n| {
+                writer
+                    .write_all(&buf[..n])
+                    .map(|_| n)
+                    .map_err(Error::from_io_error)
+            });
+            match res {
+                Ok(0) => break,
+                Ok(_) => {}
+                Err(e) => {
+                    let _ = stream.abort();
+                    return Err(e);
+                }
+            }
+        }
+        stream.finish()?;
+        Ok(mime)
+    }
+
+    /// Take a screenshot of `screen` and save the image to `path`.
+    ///
+    /// The file is created or truncated. Returns the mime-type of the
+    /// image format, which callers can use to pick a file extension.
+    pub fn screenshot_to_file<P: AsRef<Path>>(
+        &self,
+        path: P,
+        screen: u32,
+    ) -> Result<String, Error> {
+        let mut file = File::create(path).map_err(Error::from_io_error)?;
+        let mime = self.screenshot_to_writer(&mut file, screen)?;
+        file.flush().map_err(Error::from_io_error)?;
+        Ok(mime)
+    }
+
     /// Send an arbitrary monitor command cmd to domain through the QEMU monitor.
     ///
     /// * `cmd` - the QEMU monitor command string
@@ -2387,14 +6776,14 @@ impl Domain {
     #[cfg(feature = "qemu")]
     pub fn qemu_monitor_command(&self, cmd: &str, flags: u32) -> Result<String, Error> {
         let mut result: *mut libc::c_char = std::ptr::null_mut();
-        let cmd_buf = CString::new(cmd).unwrap();
+        let cmd_buf = CString::new(cmd)?;
         let ret = unsafe {
-            sys::virDomainQemuMonitorCommand(
+            ffi!(sys::virDomainQemuMonitorCommand(
                 self.as_ptr(),
                 cmd_buf.as_ptr(),
                 &mut result,
                 flags as libc::c_uint,
-            )
+            ))
         };
         if ret == -1 {
             return Err(Error::last_error());
@@ -2405,20 +6794,25 @@ impl Domain {
     /// Send an arbitrary agent command to the domain through the QEMU guest agent.
     ///
     /// * `cmd` - the QEMU guest agent command string
+    /// * `timeout` - how long to wait for the reply
     /// * `flags` - bitwise-or of supported execution flags
-    /// * `timeout` - the timeout in seconds, or one of the [`virDomainQemuAgentCommandTimeoutValues`] flags
     ///
-    /// [`virDomainQemuAgentCommandTimeoutValues`]: sys::virDomainQemuAgentCommandTimeoutValues
+    /// Returns the JSON reply of the agent.
     #[cfg(feature = "qemu")]
-    pub fn qemu_agent_command(&self, cmd: &str, timeout: i32, flags: u32) -> Result<String, Error> {
-        let cmd_buf = CString::new(cmd).unwrap();
+    pub fn qemu_agent_command(
+        &self,
+        cmd: &str,
+        timeout: QemuAgentTimeout,
+        flags: u32,
+    ) -> Result<String, Error> {
+        let cmd_buf = CString::new(cmd)?;
         let ret = unsafe {
-            sys::virDomainQemuAgentCommand(
+            ffi!(sys::virDomainQemuAgentCommand(
                 self.as_ptr(),
                 cmd_buf.as_ptr(),
-                timeout as libc::c_int,
+                timeout.to_raw(),
                 flags as libc::c_uint,
-            )
+            ))
         };
 
         if ret.is_null() {
@@ -2426,4 +6820,295 @@ impl Domain {
         }
         Ok(unsafe { c_chars_to_string!(ret) })
     }
+
+    /// Returns the extra arguments passed to QEMU through
+    /// `<qemu:commandline>` in the domain definition.
+    #[cfg(feature = "qemu")]
+    pub fn get_qemu_commandline(&self) -> Result<Vec<String>, Error> {
+        let root = Element::parse(&self.get_xml_desc(0)?)?;
+        Ok(root
+            .children("commandline")
+            .flat_map(|c| c.children("arg"))
+            .filter_map(|a| a.attr("value"))
+            .map(String::from)
+            .collect())
+    }
+
+    /// Opens the namespaces of the container domain, returning one
+    /// file descriptor per namespace.
+    ///
+    /// The caller is responsible for closing the returned file
+    /// descriptors.
+    #[cfg(feature = "lxc")]
+    pub fn lxc_open_namespace(&self, flags: u32) -> Result<Vec<i32>, Error> {
+        let mut fdlist: *mut libc::c_int = ptr::null_mut();
+        let ret = unsafe {
+            ffi!(sys::virDomainLxcOpenNamespace(
+                self.as_ptr(),
+                &mut fdlist,
+                flags as libc::c_uint
+            ))
+        };
+        if ret == -1 {
+            return Err(Error::last_error());
+        }
+        let fds = unsafe { std::slice::from_raw_parts(fdlist, ret as usize) }.to_vec();
+        unsafe { libc::free(fdlist as *mut libc::c_void) };
+        Ok(fds)
+    }
+
+    /// Attaches the calling process to the namespaces given by `fds`,
+    /// as returned by [`lxc_open_namespace()`].
+    ///
+    /// Returns the file descriptors of the namespaces the process
+    /// was previously attached to.
+    ///
+    /// [`lxc_open_namespace()`]: Domain::lxc_open_namespace
+    #[cfg(feature = "lxc")]
+    pub fn lxc_enter_namespace(&self, fds: &[i32], flags: u32) -> Result<Vec<i32>, Error> {
+        let mut fdlist = fds.to_vec();
+        let mut noldfdlist: libc::c_uint = 0;
+        let mut oldfdlist: *mut libc::c_int = ptr::null_mut();
+        let ret = unsafe {
+            ffi!(sys::virDomainLxcEnterNamespace(
+                self.as_ptr(),
+                fdlist.len() as libc::c_uint,
+                fdlist.as_mut_ptr(),
+                &mut noldfdlist,
+                &mut oldfdlist,
+                flags as libc::c_uint,
+            ))
+        };
+        if ret == -1 {
+            return Err(Error::last_error());
+        }
+        if oldfdlist.is_null() {
+            return Ok(Vec::new());
+        }
+        let fds = unsafe { std::slice::from_raw_parts(oldfdlist, noldfdlist as usize) }.to_vec();
+        unsafe { libc::free(oldfdlist as *mut libc::c_void) };
+        Ok(fds)
+    }
+
+    /// Attaches the calling process to the cgroups of the container
+    /// domain.
+    #[cfg(feature = "lxc")]
+    pub fn lxc_enter_cgroup(&self, flags: u32) -> Result<(), Error> {
+        let ret = unsafe {
+            ffi!(sys::virDomainLxcEnterCGroup(
+                self.as_ptr(),
+                flags as libc::c_uint
+            ))
+        };
+        if ret == -1 {
+            return Err(Error::last_error());
+        }
+        Ok(())
+    }
+}
+
+/// The raw `VIR_DOMAIN_*` flag values, grouped by the calls accepting
+/// them.
+///
+/// These are plain `u32` bits: they can be combined with `|` and passed
+/// both to the calls taking raw flags, such as [`Domain::migrate()`],
+/// and to the ones taking a flags type, such as
+/// [`Domain::undefine_flags()`].
+///
+/// The flag types shared by several objects live in [`crate::flags`].
+pub mod flags {
+    // Creation, see Domain::create_with_flags().
+    /// Launch the domain in the paused state.
+    pub const START_PAUSED: u32 = sys::VIR_DOMAIN_START_PAUSED;
+    /// Destroy the domain when the connection is closed.
+    pub const START_AUTODESTROY: u32 = sys::VIR_DOMAIN_START_AUTODESTROY;
+    /// Avoid the file system cache when restoring a managed save.
+    pub const START_BYPASS_CACHE: u32 = sys::VIR_DOMAIN_START_BYPASS_CACHE;
+    /// Boot, discarding any managed save.
+    pub const START_FORCE_BOOT: u32 = sys::VIR_DOMAIN_START_FORCE_BOOT;
+    /// Validate the XML document against the schema.
+    pub const START_VALIDATE: u32 = sys::VIR_DOMAIN_START_VALIDATE;
+    /// Re-initialize the NVRAM from its template.
+    pub const START_RESET_NVRAM: u32 = sys::VIR_DOMAIN_START_RESET_NVRAM;
+
+    // Definition, see Domain::define_xml_flags().
+    /// Validate the XML document against the schema.
+    pub const DEFINE_VALIDATE: u32 = sys::VIR_DOMAIN_DEFINE_VALIDATE;
+
+    // Undefinition, see Domain::undefine_flags().
+    /// Also remove any managed save image.
+    pub const UNDEFINE_MANAGED_SAVE: u32 = sys::VIR_DOMAIN_UNDEFINE_MANAGED_SAVE;
+    /// Also remove the metadata of any snapshot.
+    pub const UNDEFINE_SNAPSHOTS_METADATA: u32 = sys::VIR_DOMAIN_UNDEFINE_SNAPSHOTS_METADATA;
+    /// Also remove the NVRAM file.
+    pub const UNDEFINE_NVRAM: u32 = sys::VIR_DOMAIN_UNDEFINE_NVRAM;
+    /// Keep the NVRAM file.
+    pub const UNDEFINE_KEEP_NVRAM: u32 = sys::VIR_DOMAIN_UNDEFINE_KEEP_NVRAM;
+    /// Also remove the metadata of any checkpoint.
+    pub const UNDEFINE_CHECKPOINTS_METADATA: u32 = sys::VIR_DOMAIN_UNDEFINE_CHECKPOINTS_METADATA;
+    /// Also remove the TPM state.
+    pub const UNDEFINE_TPM: u32 = sys::VIR_DOMAIN_UNDEFINE_TPM;
+    /// Keep the TPM state.
+    pub const UNDEFINE_KEEP_TPM: u32 = sys::VIR_DOMAIN_UNDEFINE_KEEP_TPM;
+
+    // Destruction, see Domain::destroy_flags().
+    /// Send SIGTERM, then SIGKILL if the process does not exit.
+    pub const DESTROY_DEFAULT: u32 = sys::VIR_DOMAIN_DESTROY_DEFAULT;
+    /// Only send SIGTERM.
+    pub const DESTROY_GRACEFUL: u32 = sys::VIR_DOMAIN_DESTROY_GRACEFUL;
+    /// Remove the log files of the domain.
+    pub const DESTROY_REMOVE_LOGS: u32 = sys::VIR_DOMAIN_DESTROY_REMOVE_LOGS;
+
+    // Shutdown, see Domain::shutdown_flags().
+    /// Let the hypervisor pick the method.
+    pub const SHUTDOWN_DEFAULT: u32 = sys::VIR_DOMAIN_SHUTDOWN_DEFAULT;
+    /// Press the ACPI power button.
+    pub const SHUTDOWN_ACPI_POWER_BTN: u32 = sys::VIR_DOMAIN_SHUTDOWN_ACPI_POWER_BTN;
+    /// Ask the guest agent.
+    pub const SHUTDOWN_GUEST_AGENT: u32 = sys::VIR_DOMAIN_SHUTDOWN_GUEST_AGENT;
+    /// Use the init control of a container.
+    pub const SHUTDOWN_INITCTL: u32 = sys::VIR_DOMAIN_SHUTDOWN_INITCTL;
+    /// Send a signal to a container.
+    pub const SHUTDOWN_SIGNAL: u32 = sys::VIR_DOMAIN_SHUTDOWN_SIGNAL;
+    /// Use a paravirtualized event.
+    pub const SHUTDOWN_PARAVIRT: u32 = sys::VIR_DOMAIN_SHUTDOWN_PARAVIRT;
+
+    // Reboot, see Domain::reboot().
+    /// Let the hypervisor pick the method.
+    pub const REBOOT_DEFAULT: u32 = sys::VIR_DOMAIN_REBOOT_DEFAULT;
+    /// Press the ACPI power button.
+    pub const REBOOT_ACPI_POWER_BTN: u32 = sys::VIR_DOMAIN_REBOOT_ACPI_POWER_BTN;
+    /// Ask the guest agent.
+    pub const REBOOT_GUEST_AGENT: u32 = sys::VIR_DOMAIN_REBOOT_GUEST_AGENT;
+    /// Use the init control of a container.
+    pub const REBOOT_INITCTL: u32 = sys::VIR_DOMAIN_REBOOT_INITCTL;
+    /// Send a signal to a container.
+    pub const REBOOT_SIGNAL: u32 = sys::VIR_DOMAIN_REBOOT_SIGNAL;
+    /// Use a paravirtualized event.
+    pub const REBOOT_PARAVIRT: u32 = sys::VIR_DOMAIN_REBOOT_PARAVIRT;
+
+    // Modification impact, see the parameter and device setters.
+    /// The current domain state, live if running, persistent otherwise.
+    pub const AFFECT_CURRENT: u32 = sys::VIR_DOMAIN_AFFECT_CURRENT;
+    /// The running domain.
+    pub const AFFECT_LIVE: u32 = sys::VIR_DOMAIN_AFFECT_LIVE;
+    /// The persistent configuration.
+    pub const AFFECT_CONFIG: u32 = sys::VIR_DOMAIN_AFFECT_CONFIG;
+
+    // XML description, see Domain::get_xml_desc().
+    /// Include security sensitive information.
+    pub const XML_SECURE: u32 = sys::VIR_DOMAIN_XML_SECURE;
+    /// Describe the persistent configuration.
+    pub const XML_INACTIVE: u32 = sys::VIR_DOMAIN_XML_INACTIVE;
+    /// Update the guest CPU from the host CPU.
+    pub const XML_UPDATE_CPU: u32 = sys::VIR_DOMAIN_XML_UPDATE_CPU;
+    /// Describe a configuration suitable for migration.
+    pub const XML_MIGRATABLE: u32 = sys::VIR_DOMAIN_XML_MIGRATABLE;
+
+    // Save and restore, see Domain::managed_save() and
+    // Domain::save_image_define_xml().
+    /// Avoid the file system cache.
+    pub const SAVE_BYPASS_CACHE: u32 = sys::VIR_DOMAIN_SAVE_BYPASS_CACHE;
+    /// Leave the domain running on restore.
+    pub const SAVE_RUNNING: u32 = sys::VIR_DOMAIN_SAVE_RUNNING;
+    /// Leave the domain paused on restore.
+    pub const SAVE_PAUSED: u32 = sys::VIR_DOMAIN_SAVE_PAUSED;
+    /// Re-initialize the NVRAM from its template on restore.
+    pub const SAVE_RESET_NVRAM: u32 = sys::VIR_DOMAIN_SAVE_RESET_NVRAM;
+    /// Include security sensitive information in the image XML.
+    pub const SAVE_IMAGE_XML_SECURE: u32 = sys::VIR_DOMAIN_SAVE_IMAGE_XML_SECURE;
+
+    // Core dumps, see Domain::core_dump().
+    /// Crash the domain after the dump.
+    pub const DUMP_CRASH: u32 = sys::VIR_DUMP_CRASH;
+    /// Dump without pausing the domain.
+    pub const DUMP_LIVE: u32 = sys::VIR_DUMP_LIVE;
+    /// Avoid the file system cache.
+    pub const DUMP_BYPASS_CACHE: u32 = sys::VIR_DUMP_BYPASS_CACHE;
+    /// Reset the domain after the dump.
+    pub const DUMP_RESET: u32 = sys::VIR_DUMP_RESET;
+    /// Only dump the memory of the guest.
+    pub const DUMP_MEMORY_ONLY: u32 = sys::VIR_DUMP_MEMORY_ONLY;
+
+    // Consoles and channels, see Domain::open_console() and
+    // Domain::open_channel().
+    /// Take over an already open console.
+    pub const CONSOLE_FORCE: u32 = sys::VIR_DOMAIN_CONSOLE_FORCE;
+    /// Fail if the console is already open.
+    pub const CONSOLE_SAFE: u32 = sys::VIR_DOMAIN_CONSOLE_SAFE;
+    /// Take over an already open channel.
+    pub const CHANNEL_FORCE: u32 = sys::VIR_DOMAIN_CHANNEL_FORCE;
+
+    // Guest agent, see Domain::set_user_password() and
+    // Domain::set_time().
+    /// The password is already encrypted.
+    pub const PASSWORD_ENCRYPTED: u32 = sys::VIR_DOMAIN_PASSWORD_ENCRYPTED;
+    /// Synchronize the guest clock with the host.
+    pub const TIME_SYNC: u32 = sys::VIR_DOMAIN_TIME_SYNC;
+
+    // Migration, see Domain::migrate() and its variants.
+    /// Do not pause the domain during the migration.
+    pub const MIGRATE_LIVE: u32 = sys::VIR_MIGRATE_LIVE;
+    /// Let the source host contact the destination directly.
+    pub const MIGRATE_PEER2PEER: u32 = sys::VIR_MIGRATE_PEER2PEER;
+    /// Tunnel the migration data over the libvirt connection.
+    pub const MIGRATE_TUNNELLED: u32 = sys::VIR_MIGRATE_TUNNELLED;
+    /// Define the domain on the destination host.
+    pub const MIGRATE_PERSIST_DEST: u32 = sys::VIR_MIGRATE_PERSIST_DEST;
+    /// Undefine the domain on the source host.
+    pub const MIGRATE_UNDEFINE_SOURCE: u32 = sys::VIR_MIGRATE_UNDEFINE_SOURCE;
+    /// Leave the domain paused on the destination host.
+    pub const MIGRATE_PAUSED: u32 = sys::VIR_MIGRATE_PAUSED;
+    /// Copy the full disk images.
+    pub const MIGRATE_NON_SHARED_DISK: u32 = sys::VIR_MIGRATE_NON_SHARED_DISK;
+    /// Copy the disk images incrementally over a shared base.
+    pub const MIGRATE_NON_SHARED_INC: u32 = sys::VIR_MIGRATE_NON_SHARED_INC;
+    /// Prevent configuration changes during the migration.
+    pub const MIGRATE_CHANGE_PROTECTION: u32 = sys::VIR_MIGRATE_CHANGE_PROTECTION;
+    /// Migrate even if the configuration is deemed unsafe.
+    pub const MIGRATE_UNSAFE: u32 = sys::VIR_MIGRATE_UNSAFE;
+    /// Only migrate the persistent configuration.
+    pub const MIGRATE_OFFLINE: u32 = sys::VIR_MIGRATE_OFFLINE;
+    /// Compress the migration data.
+    pub const MIGRATE_COMPRESSED: u32 = sys::VIR_MIGRATE_COMPRESSED;
+    /// Cancel the migration on soft I/O errors.
+    pub const MIGRATE_ABORT_ON_ERROR: u32 = sys::VIR_MIGRATE_ABORT_ON_ERROR;
+    /// Throttle the vCPUs to make the migration converge.
+    pub const MIGRATE_AUTO_CONVERGE: u32 = sys::VIR_MIGRATE_AUTO_CONVERGE;
+    /// Pin all the guest memory for RDMA transfers.
+    pub const MIGRATE_RDMA_PIN_ALL: u32 = sys::VIR_MIGRATE_RDMA_PIN_ALL;
+    /// Allow switching to post-copy mode.
+    pub const MIGRATE_POSTCOPY: u32 = sys::VIR_MIGRATE_POSTCOPY;
+    /// Encrypt the migration data with TLS.
+    pub const MIGRATE_TLS: u32 = sys::VIR_MIGRATE_TLS;
+    /// Send the migration data over several connections.
+    pub const MIGRATE_PARALLEL: u32 = sys::VIR_MIGRATE_PARALLEL;
+    /// Resume a failed post-copy migration.
+    pub const MIGRATE_POSTCOPY_RESUME: u32 = sys::VIR_MIGRATE_POSTCOPY_RESUME;
+    /// Send the memory pages without copying them.
+    pub const MIGRATE_ZEROCOPY: u32 = sys::VIR_MIGRATE_ZEROCOPY;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BlockCopyOptions;
+
+    #[test]
+    fn test_block_copy_disk_xml() {
+        let options = BlockCopyOptions::default();
+        assert_eq!(
+            "<disk type='file'>\n  <source file='/tmp/copy.img'/>\n</disk>\n",
+            options.disk_xml("file", "file='/tmp/copy.img'")
+        );
+
+        let options = BlockCopyOptions {
+            format: Some("qcow2\"'".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            "<disk type='volume'>\n  <source pool='default' volume='copy'/>\n  \
+             <driver type='qcow2&quot;&apos;'/>\n</disk>\n",
+            options.disk_xml("volume", "pool='default' volume='copy'")
+        );
+    }
 }
@@ -16,12 +16,401 @@
  * Sahid Orentino Ferdjaoui <sahid.ferdjaoui@redhat.com>
  */
 
+use std::collections::HashMap;
 use std::ffi::CString;
+use std::sync::Mutex;
 use std::{ptr, str};
 
+use xml::escape::{escape_str_attribute, escape_str_pcdata};
+
 use crate::connect::Connect;
 use crate::domain::Domain;
-use crate::error::Error;
+use crate::error::{report_drop_error, Error};
+use crate::util::impl_flags;
+use crate::xmlutil::Element;
+
+impl_flags! {
+    /// Filters used when counting or listing domain snapshots.
+    ///
+    /// Flags can be combined with `|`. See
+    /// <https://libvirt.org/html/libvirt-libvirt-domain-snapshot.html#virDomainSnapshotListFlags>
+    struct SnapshotListFlags: sys::virDomainSnapshotListFlags {
+        /// Only list snapshots without a parent. Only valid when
+        /// listing the snapshots of a domain.
+        const ROOTS = sys::VIR_DOMAIN_SNAPSHOT_LIST_ROOTS;
+        /// List all descendants, not just direct children. Only valid
+        /// when listing the children of a snapshot.
+        const DESCENDANTS = sys::VIR_DOMAIN_SNAPSHOT_LIST_DESCENDANTS;
+        /// Only list snapshots without children.
+        const LEAVES = sys::VIR_DOMAIN_SNAPSHOT_LIST_LEAVES;
+        /// Only list snapshots with children.
+        const NO_LEAVES = sys::VIR_DOMAIN_SNAPSHOT_LIST_NO_LEAVES;
+        /// Only list snapshots with libvirt metadata.
+        const METADATA = sys::VIR_DOMAIN_SNAPSHOT_LIST_METADATA;
+        /// Only list snapshots without libvirt metadata.
+        const NO_METADATA = sys::VIR_DOMAIN_SNAPSHOT_LIST_NO_METADATA;
+        /// Only list snapshots taken while the domain was shut off.
+        const INACTIVE = sys::VIR_DOMAIN_SNAPSHOT_LIST_INACTIVE;
+        /// Only list snapshots taken while the domain was running.
+        const ACTIVE = sys::VIR_DOMAIN_SNAPSHOT_LIST_ACTIVE;
+        /// Only list disk-only snapshots.
+        const DISK_ONLY = sys::VIR_DOMAIN_SNAPSHOT_LIST_DISK_ONLY;
+        /// Only list snapshots stored internally to disk images.
+        const INTERNAL = sys::VIR_DOMAIN_SNAPSHOT_LIST_INTERNAL;
+        /// Only list snapshots using external files.
+        const EXTERNAL = sys::VIR_DOMAIN_SNAPSHOT_LIST_EXTERNAL;
+        /// Ensure parents are listed before their children.
+        const TOPOLOGICAL = sys::VIR_DOMAIN_SNAPSHOT_LIST_TOPOLOGICAL;
+    }
+}
+
+impl_flags! {
+    /// Flags used when creating a domain snapshot.
+    ///
+    /// See <https://libvirt.org/html/libvirt-libvirt-domain-snapshot.html#virDomainSnapshotCreateFlags>
+    struct SnapshotCreateFlags: sys::virDomainSnapshotCreateFlags {
+        /// Restore or alter the metadata of an existing snapshot.
+        const REDEFINE = sys::VIR_DOMAIN_SNAPSHOT_CREATE_REDEFINE;
+        /// With `REDEFINE`, make the snapshot the current one.
+        const CURRENT = sys::VIR_DOMAIN_SNAPSHOT_CREATE_CURRENT;
+        /// Do not keep any libvirt metadata for the snapshot.
+        const NO_METADATA = sys::VIR_DOMAIN_SNAPSHOT_CREATE_NO_METADATA;
+        /// Stop the domain once the snapshot is taken.
+        const HALT = sys::VIR_DOMAIN_SNAPSHOT_CREATE_HALT;
+        /// Only snapshot the disks, not the memory state.
+        const DISK_ONLY = sys::VIR_DOMAIN_SNAPSHOT_CREATE_DISK_ONLY;
+        /// Reuse any existing external file.
+        const REUSE_EXT = sys::VIR_DOMAIN_SNAPSHOT_CREATE_REUSE_EXT;
+        /// Use the guest agent to quiesce the file systems.
+        const QUIESCE = sys::VIR_DOMAIN_SNAPSHOT_CREATE_QUIESCE;
+        /// Either take all the disk snapshots or none.
+        const ATOMIC = sys::VIR_DOMAIN_SNAPSHOT_CREATE_ATOMIC;
+        /// Take the snapshot while the domain keeps running.
+        const LIVE = sys::VIR_DOMAIN_SNAPSHOT_CREATE_LIVE;
+        /// Validate the XML document against the schema.
+        const VALIDATE = sys::VIR_DOMAIN_SNAPSHOT_CREATE_VALIDATE;
+    }
+}
+
+impl_flags! {
+    /// Flags used when reverting to a domain snapshot.
+    ///
+    /// See <https://libvirt.org/html/libvirt-libvirt-domain-snapshot.html#virDomainSnapshotRevertFlags>
+    struct SnapshotRevertFlags: sys::virDomainSnapshotRevertFlags {
+        /// Run the domain after reverting.
+        const RUNNING = sys::VIR_DOMAIN_SNAPSHOT_REVERT_RUNNING;
+        /// Pause the domain after reverting.
+        const PAUSED = sys::VIR_DOMAIN_SNAPSHOT_REVERT_PAUSED;
+        /// Allow risky reverts.
+        const FORCE = sys::VIR_DOMAIN_SNAPSHOT_REVERT_FORCE;
+        /// Re-initialize the NVRAM from its pristine template.
+        const RESET_NVRAM = sys::VIR_DOMAIN_SNAPSHOT_REVERT_RESET_NVRAM;
+    }
+}
+
+impl_flags! {
+    /// Flags used when deleting a domain snapshot.
+    ///
+    /// See <https://libvirt.org/html/libvirt-libvirt-domain-snapshot.html#virDomainSnapshotDeleteFlags>
+    struct SnapshotDeleteFlags: sys::virDomainSnapshotDeleteFlags {
+        /// Also delete the descendants of the snapshot.
+        const CHILDREN = sys::VIR_DOMAIN_SNAPSHOT_DELETE_CHILDREN;
+        /// Only delete the libvirt metadata.
+        const METADATA_ONLY = sys::VIR_DOMAIN_SNAPSHOT_DELETE_METADATA_ONLY;
+        /// Only delete the descendants of the snapshot.
+        const CHILDREN_ONLY = sys::VIR_DOMAIN_SNAPSHOT_DELETE_CHILDREN_ONLY;
+    }
+}
+
+/// How a disk is handled by a snapshot.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum SnapshotDiskMode {
+    /// The disk is left out of the snapshot.
+    No,
+    /// The snapshot is stored inside the disk image.
+    Internal,
+    /// The snapshot is stored in a new overlay file.
+    External,
+    /// The snapshot is taken by the user, for instance by a storage
+    /// array, once libvirt has paused the domain.
+    Manual,
+}
+
+impl SnapshotDiskMode {
+    fn as_str(self) -> &'static str {
+        match self {
+            SnapshotDiskMode::No => "no",
+            SnapshotDiskMode::Internal => "internal",
+            SnapshotDiskMode::External => "external",
+            SnapshotDiskMode::Manual => "manual",
+        }
+    }
+}
+
+/// Per-disk settings of a snapshot.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SnapshotDiskSpec {
+    /// The target (e.g. `vda`) or source path of the disk.
+    pub name: String,
+    pub mode: SnapshotDiskMode,
+    /// The format of the overlay of an external snapshot, e.g. `qcow2`.
+    pub driver_type: Option<String>,
+    /// The path of the overlay of an external snapshot.
+    pub file: Option<String>,
+}
+
+impl SnapshotDiskSpec {
+    /// Creates the settings of a disk using the given mode.
+    pub fn new(name: &str, mode: SnapshotDiskMode) -> SnapshotDiskSpec {
+        SnapshotDiskSpec {
+            name: name.to_string(),
+            mode,
+            driver_type: None,
+            file: None,
+        }
+    }
+
+    /// Creates the settings of an external snapshot of a disk, stored
+    /// as a qcow2 overlay in `file`.
+    pub fn external(name: &str, file: &str) -> SnapshotDiskSpec {
+        SnapshotDiskSpec {
+            driver_type: Some("qcow2".to_string()),
+            file: Some(file.to_string()),
+            ..SnapshotDiskSpec::new(name, SnapshotDiskMode::External)
+        }
+    }
+}
+
+/// Options used by [`DomainSnapshot::create()`].
+///
+/// The options are turned into a snapshot XML document and the
+/// matching creation flags. See <https://libvirt.org/formatsnapshot.html>
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SnapshotCreateOptions {
+    /// The name of the snapshot; generated by libvirt when `None`.
+    pub name: Option<String>,
+    pub description: Option<String>,
+    /// Save the memory state to this file, making an external
+    /// snapshot of the memory.
+    pub memory_file: Option<String>,
+    /// Per-disk settings; the disks left out use the default policy
+    /// of the domain.
+    pub disks: Vec<SnapshotDiskSpec>,
+    /// Use the guest agent to quiesce the file systems.
+    pub quiesce: bool,
+    /// Only snapshot the disks, not the memory state.
+    pub disk_only: bool,
+    /// Either take all the disk snapshots or none.
+    pub atomic: bool,
+    /// Take the snapshot while the domain keeps running.
+    pub live: bool,
+}
+
+impl SnapshotCreateOptions {
+    /// Returns the flags matching these options.
+    pub fn flags(&self) -> SnapshotCreateFlags {
+        let mut flags = SnapshotCreateFlags::empty();
+        if self.quiesce {
+            flags |= SnapshotCreateFlags::QUIESCE;
+        }
+        if self.disk_only {
+            flags |= SnapshotCreateFlags::DISK_ONLY;
+        }
+        if self.atomic {
+            flags |= SnapshotCreateFlags::ATOMIC;
+        }
+        if self.live {
+            flags |= SnapshotCreateFlags::LIVE;
+        }
+        flags
+    }
+
+    /// Returns the snapshot XML document matching these options.
+    pub fn to_xml(&self) -> String {
+        let mut xml = String::from("<domainsnapshot>\n");
+        if let Some(name) = &self.name {
+            xml.push_str(&format!("  <name>{}</name>\n", escape_str_pcdata(name)));
+        }
+        if let Some(description) = &self.description {
+            xml.push_str(&format!(
+                "  <description>{}</description>\n",
+                escape_str_pcdata(description)
+            ));
+        }
+        if let Some(file) = &self.memory_file {
+            xml.push_str(&format!(
+                "  <memory snapshot='external' file='{}'/>\n",
+                escape_str_attribute(file)
+            ));
+        }
+        if !self.disks.is_empty() {
+            xml.push_str("  <disks>\n");
+            for disk in &self.disks {
+                xml.push_str(&format!(
+                    "    <disk name='{}' snapshot='{}'",
+                    escape_str_attribute(&disk.name),
+                    disk.mode.as_str()
+                ));
+                if disk.driver_type.is_none() && disk.file.is_none() {
+                    xml.push_str("/>\n");
+                    continue;
+                }
+                xml.push_str(">\n");
+                if let Some(driver_type) = &disk.driver_type {
+                    xml.push_str(&format!(
+                        "      <driver type='{}'/>\n",
+                        escape_str_attribute(driver_type)
+                    ));
+                }
+                if let Some(file) = &disk.file {
+                    xml.push_str(&format!(
+                        "      <source file='{}'/>\n",
+                        escape_str_attribute(file)
+                    ));
+                }
+                xml.push_str("    </disk>\n");
+            }
+            xml.push_str("  </disks>\n");
+        }
+        xml.push_str("</domainsnapshot>\n");
+        xml
+    }
+}
+
+/// A snapshot and the snapshots taken on top of it, as returned by
+/// [`Domain::snapshot_tree()`].
+#[derive(Debug)]
+pub struct SnapshotNode {
+    pub snapshot: DomainSnapshot,
+    pub name: String,
+    pub children: Vec<SnapshotNode>,
+}
+
+impl SnapshotNode {
+    /// Builds the trees out of `(snapshot, name, parent name)` entries,
+    /// keeping the order of the entries among siblings. Entries whose
+    /// parent is not listed become roots.
+    pub(crate) fn build(
+        entries: Vec<(DomainSnapshot, String, Option<String>)>,
+    ) -> Vec<SnapshotNode> {
+        let index: HashMap<&str, usize> = entries
+            .iter()
+            .enumerate()
+            .map(|(i, (_, name, _))| (name.as_str(), i))
+            .collect();
+        let mut children = vec![Vec::new(); entries.len()];
+        let mut roots = Vec::new();
+        for (i, (_, _, parent)) in entries.iter().enumerate() {
+            match parent.as_deref().and_then(|p| index.get(p)) {
+                Some(&p) => children[p].push(i),
+                None => roots.push(i),
+            }
+        }
+
+        let mut slots: Vec<_> = entries
+            .into_iter()
+            .map(|(snapshot, name, _)| Some((snapshot, name)))
+            .collect();
+        roots
+            .into_iter()
+            .map(|i| SnapshotNode::take(i, &mut slots, &children))
+            .collect()
+    }
+
+    fn take(
+        i: usize,
+        slots: &mut [Option<(DomainSnapshot, String)>],
+        children: &[Vec<usize>],
+    ) -> SnapshotNode {
+        // Every entry has a single parent, hence is only reached once.
+        let (snapshot, name) = slots[i].take().unwrap();
+        SnapshotNode {
+            snapshot,
+            name,
+            children: children[i]
+                .iter()
+                .map(|&c| SnapshotNode::take(c, slots, children))
+                .collect(),
+        }
+    }
+
+    /// Returns the node of the snapshot called `name` in this tree.
+    pub fn find(&self, name: &str) -> Option<&SnapshotNode> {
+        if self.name == name {
+            return Some(self);
+        }
+        self.children.iter().find_map(|c| c.find(name))
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+/// The state of the domain when a snapshot was taken.
+///
+/// See <https://libvirt.org/formatsnapshot.html#snapshot-xml>
+pub enum SnapshotState {
+    NoState,
+    Running,
+    Blocked,
+    Paused,
+    Shutdown,
+    Shutoff,
+    Crashed,
+    PmSuspended,
+    /// The snapshot only covers the disks, not the memory.
+    DiskSnapshot,
+}
+
+impl SnapshotState {
+    fn from_xml(state: &str) -> SnapshotState {
+        match state {
+            "running" => SnapshotState::Running,
+            "blocked" => SnapshotState::Blocked,
+            "paused" => SnapshotState::Paused,
+            "shutdown" => SnapshotState::Shutdown,
+            "shutoff" => SnapshotState::Shutoff,
+            "crashed" => SnapshotState::Crashed,
+            "pmsuspended" => SnapshotState::PmSuspended,
+            "disk-snapshot" => SnapshotState::DiskSnapshot,
+            _ => SnapshotState::NoState,
+        }
+    }
+}
+
+/// The description of a snapshot, parsed out of its XML.
+///
+/// See <https://libvirt.org/formatsnapshot.html#snapshot-xml>
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SnapshotInfo {
+    pub name: String,
+    pub description: Option<String>,
+    /// The name of the snapshot this one was taken on top of.
+    pub parent: Option<String>,
+    /// The creation time, in seconds since the epoch.
+    pub creation_time: i64,
+    pub state: SnapshotState,
+}
+
+impl SnapshotInfo {
+    /// Parses the XML returned by [`DomainSnapshot::get_xml_desc()`].
+    pub fn from_xml(xml: &str) -> Result<SnapshotInfo, Error> {
+        let root = Element::parse(xml)?;
+        let text = |name: &str| root.child(name).map(|e| e.text().to_string());
+        Ok(SnapshotInfo {
+            name: text("name").unwrap_or_default(),
+            description: text("description"),
+            parent: root
+                .child("parent")
+                .and_then(|p| p.child("name"))
+                .map(|n| n.text().to_string()),
+            creation_time: text("creationTime")
+                .and_then(|t| t.parse().ok())
+                .unwrap_or(0),
+            state: SnapshotState::from_xml(
+                root.child("state").map(|e| e.text()).unwrap_or_default(),
+            ),
+        })
+    }
+}
 
 /// Provides APIs for the management of domain snapshots.
 ///
@@ -29,6 +418,7 @@ use crate::error::Error;
 #[derive(Debug)]
 pub struct DomainSnapshot {
     ptr: Option<sys::virDomainSnapshotPtr>,
+    info: Mutex<Option<SnapshotInfo>>,
 }
 
 unsafe impl Send for DomainSnapshot {}
@@ -38,7 +428,7 @@ impl Drop for DomainSnapshot {
     fn drop(&mut self) {
         if self.ptr.is_some() {
             if let Err(e) = self.free() {
-                panic!("Unable to drop memory for DomainSnapshot: {}", e)
+                report_drop_error("DomainSnapshot", e);
             }
         }
     }
@@ -62,12 +452,15 @@ impl DomainSnapshot {
     ///
     /// The caller must ensure that the pointer is valid.
     pub unsafe fn from_ptr(ptr: sys::virDomainSnapshotPtr) -> DomainSnapshot {
-        DomainSnapshot { ptr: Some(ptr) }
+        DomainSnapshot {
+            ptr: Some(ptr),
+            info: Mutex::new(None),
+        }
     }
 
     fn add_ref(&self) -> Result<DomainSnapshot, Error> {
         unsafe {
-            if sys::virDomainSnapshotRef(self.as_ptr()) == -1 {
+            if ffi!(sys::virDomainSnapshotRef(self.as_ptr())) == -1 {
                 return Err(Error::last_error());
             }
         }
@@ -80,7 +473,7 @@ impl DomainSnapshot {
     }
 
     pub fn get_connect(&self) -> Result<Connect, Error> {
-        let ptr = unsafe { sys::virDomainSnapshotGetConnect(self.as_ptr()) };
+        let ptr = unsafe { ffi!(sys::virDomainSnapshotGetConnect(self.as_ptr())) };
         if ptr.is_null() {
             return Err(Error::last_error());
         }
@@ -88,7 +481,7 @@ impl DomainSnapshot {
     }
 
     pub fn get_domain(&self) -> Result<Domain, Error> {
-        let ptr = unsafe { sys::virDomainSnapshotGetDomain(self.as_ptr()) };
+        let ptr = unsafe { ffi!(sys::virDomainSnapshotGetDomain(self.as_ptr())) };
         if ptr.is_null() {
             return Err(Error::last_error());
         }
@@ -96,7 +489,7 @@ impl DomainSnapshot {
     }
 
     pub fn get_name(&self) -> Result<String, Error> {
-        let n = unsafe { sys::virDomainSnapshotGetName(self.as_ptr()) };
+        let n = unsafe { ffi!(sys::virDomainSnapshotGetName(self.as_ptr())) };
         if n.is_null() {
             return Err(Error::last_error());
         }
@@ -105,13 +498,13 @@ impl DomainSnapshot {
 
     /// Get a handle to a named snapshot.
     pub fn lookup_by_name(dom: &Domain, name: &str, flags: u32) -> Result<DomainSnapshot, Error> {
-        let name_buf = CString::new(name).unwrap();
+        let name_buf = CString::new(name)?;
         let ptr = unsafe {
-            sys::virDomainSnapshotLookupByName(
+            ffi!(sys::virDomainSnapshotLookupByName(
                 dom.as_ptr(),
                 name_buf.as_ptr(),
                 flags as libc::c_uint,
-            )
+            ))
         };
         if ptr.is_null() {
             return Err(Error::last_error());
@@ -119,19 +512,71 @@ impl DomainSnapshot {
         Ok(unsafe { DomainSnapshot::from_ptr(ptr) })
     }
 
+    /// Returns the description of the snapshot.
+    ///
+    /// The XML of the snapshot is fetched and parsed on the first call
+    /// only, the accessors below share the result.
+    pub fn info(&self) -> Result<SnapshotInfo, Error> {
+        self.with_info(SnapshotInfo::clone)
+    }
+
+    /// Returns the creation time of the snapshot, in seconds since the
+    /// epoch.
+    pub fn creation_time(&self) -> Result<i64, Error> {
+        self.with_info(|info| info.creation_time)
+    }
+
+    /// Returns the state of the domain when the snapshot was taken.
+    pub fn state(&self) -> Result<SnapshotState, Error> {
+        self.with_info(|info| info.state)
+    }
+
+    /// Returns `true` if the snapshot only covers the disks.
+    pub fn is_disk_only(&self) -> Result<bool, Error> {
+        self.with_info(|info| info.state == SnapshotState::DiskSnapshot)
+    }
+
+    /// Returns the name of the parent snapshot, if any, without looking
+    /// the parent up.
+    pub fn parent_name(&self) -> Result<Option<String>, Error> {
+        self.with_info(|info| info.parent.clone())
+    }
+
+    fn with_info<T>(&self, f: impl FnOnce(&SnapshotInfo) -> T) -> Result<T, Error> {
+        let mut cached = self.info.lock().unwrap_or_else(|e| e.into_inner());
+        let info = match cached.take() {
+            Some(info) => info,
+            None => SnapshotInfo::from_xml(&self.get_xml_desc(0)?)?,
+        };
+        Ok(f(cached.insert(info)))
+    }
+
     /// Dump the XML of a snapshot.
     pub fn get_xml_desc(&self, flags: u32) -> Result<String, Error> {
-        let xml = unsafe { sys::virDomainSnapshotGetXMLDesc(self.as_ptr(), flags as libc::c_uint) };
+        let xml = unsafe {
+            ffi!(sys::virDomainSnapshotGetXMLDesc(
+                self.as_ptr(),
+                flags as libc::c_uint
+            ))
+        };
         if xml.is_null() {
             return Err(Error::last_error());
         }
         Ok(unsafe { c_chars_to_string!(xml) })
     }
 
-    pub fn create_xml(dom: &Domain, xml: &str, flags: u32) -> Result<DomainSnapshot, Error> {
-        let xml_buf = CString::new(xml).unwrap();
+    pub fn create_xml(
+        dom: &Domain,
+        xml: &str,
+        flags: impl Into<SnapshotCreateFlags>,
+    ) -> Result<DomainSnapshot, Error> {
+        let xml_buf = CString::new(xml)?;
         let ptr = unsafe {
-            sys::virDomainSnapshotCreateXML(dom.as_ptr(), xml_buf.as_ptr(), flags as libc::c_uint)
+            ffi!(sys::virDomainSnapshotCreateXML(
+                dom.as_ptr(),
+                xml_buf.as_ptr(),
+                flags.into().bits() as libc::c_uint,
+            ))
         };
         if ptr.is_null() {
             return Err(Error::last_error());
@@ -139,9 +584,19 @@ impl DomainSnapshot {
         Ok(unsafe { DomainSnapshot::from_ptr(ptr) })
     }
 
+    /// Create a snapshot of a domain from typed options.
+    pub fn create(dom: &Domain, options: &SnapshotCreateOptions) -> Result<DomainSnapshot, Error> {
+        DomainSnapshot::create_xml(dom, &options.to_xml(), options.flags())
+    }
+
     /// Get a handle to the current snapshot
     pub fn current(dom: &Domain, flags: u32) -> Result<DomainSnapshot, Error> {
-        let ptr = unsafe { sys::virDomainSnapshotCurrent(dom.as_ptr(), flags as libc::c_uint) };
+        let ptr = unsafe {
+            ffi!(sys::virDomainSnapshotCurrent(
+                dom.as_ptr(),
+                flags as libc::c_uint
+            ))
+        };
         if ptr.is_null() {
             return Err(Error::last_error());
         }
@@ -150,7 +605,12 @@ impl DomainSnapshot {
 
     /// Get a handle to the parent snapshot, if one exists.
     pub fn get_parent(&self, flags: u32) -> Result<DomainSnapshot, Error> {
-        let ptr = unsafe { sys::virDomainSnapshotGetParent(self.as_ptr(), flags as libc::c_uint) };
+        let ptr = unsafe {
+            ffi!(sys::virDomainSnapshotGetParent(
+                self.as_ptr(),
+                flags as libc::c_uint
+            ))
+        };
         if ptr.is_null() {
             return Err(Error::last_error());
         }
@@ -158,8 +618,13 @@ impl DomainSnapshot {
     }
 
     /// Revert a snapshot.
-    pub fn revert(&self, flags: u32) -> Result<(), Error> {
-        let ret = unsafe { sys::virDomainRevertToSnapshot(self.as_ptr(), flags as libc::c_uint) };
+    pub fn revert(&self, flags: impl Into<SnapshotRevertFlags>) -> Result<(), Error> {
+        let ret = unsafe {
+            ffi!(sys::virDomainRevertToSnapshot(
+                self.as_ptr(),
+                flags.into().bits() as libc::c_uint
+            ))
+        };
         if ret == -1 {
             return Err(Error::last_error());
         }
@@ -167,8 +632,13 @@ impl DomainSnapshot {
     }
 
     /// Delete a snapshot.
-    pub fn delete(&self, flags: u32) -> Result<(), Error> {
-        let ret = unsafe { sys::virDomainSnapshotDelete(self.as_ptr(), flags as libc::c_uint) };
+    pub fn delete(&self, flags: impl Into<SnapshotDeleteFlags>) -> Result<(), Error> {
+        let ret = unsafe {
+            ffi!(sys::virDomainSnapshotDelete(
+                self.as_ptr(),
+                flags.into().bits() as libc::c_uint
+            ))
+        };
         if ret == -1 {
             return Err(Error::last_error());
         }
@@ -176,8 +646,13 @@ impl DomainSnapshot {
     }
 
     /// Return the number of snapshots for this domain.
-    pub fn num(dom: &Domain, flags: u32) -> Result<u32, Error> {
-        let ret = unsafe { sys::virDomainSnapshotNum(dom.as_ptr(), flags as libc::c_uint) };
+    pub fn num(dom: &Domain, flags: impl Into<SnapshotListFlags>) -> Result<u32, Error> {
+        let ret = unsafe {
+            ffi!(sys::virDomainSnapshotNum(
+                dom.as_ptr(),
+                flags.into().bits() as libc::c_uint
+            ))
+        };
         if ret == -1 {
             return Err(Error::last_error());
         }
@@ -185,9 +660,13 @@ impl DomainSnapshot {
     }
 
     /// Return the number of child snapshots for this snapshot.
-    pub fn num_children(&self, flags: u32) -> Result<u32, Error> {
-        let ret =
-            unsafe { sys::virDomainSnapshotNumChildren(self.as_ptr(), flags as libc::c_uint) };
+    pub fn num_children(&self, flags: impl Into<SnapshotListFlags>) -> Result<u32, Error> {
+        let ret = unsafe {
+            ffi!(sys::virDomainSnapshotNumChildren(
+                self.as_ptr(),
+                flags.into().bits() as libc::c_uint
+            ))
+        };
         if ret == -1 {
             return Err(Error::last_error());
         }
@@ -196,7 +675,12 @@ impl DomainSnapshot {
 
     /// Determine if a snapshot is the current snapshot of its domain.
     pub fn is_current(&self, flags: u32) -> Result<bool, Error> {
-        let ret = unsafe { sys::virDomainSnapshotIsCurrent(self.as_ptr(), flags as libc::c_uint) };
+        let ret = unsafe {
+            ffi!(sys::virDomainSnapshotIsCurrent(
+                self.as_ptr(),
+                flags as libc::c_uint
+            ))
+        };
         if ret == -1 {
             return Err(Error::last_error());
         }
@@ -206,8 +690,12 @@ impl DomainSnapshot {
     /// Determine if a snapshot has associated libvirt metadata that
     /// would prevent the deletion of its domain.
     pub fn has_metadata(&self, flags: u32) -> Result<bool, Error> {
-        let ret =
-            unsafe { sys::virDomainSnapshotHasMetadata(self.as_ptr(), flags as libc::c_uint) };
+        let ret = unsafe {
+            ffi!(sys::virDomainSnapshotHasMetadata(
+                self.as_ptr(),
+                flags as libc::c_uint
+            ))
+        };
         if ret == -1 {
             return Err(Error::last_error());
         }
@@ -215,10 +703,17 @@ impl DomainSnapshot {
     }
 
     /// Get all snapshot object children for this snapshot.
-    pub fn list_all_children(&self, flags: u32) -> Result<Vec<DomainSnapshot>, Error> {
+    pub fn list_all_children(
+        &self,
+        flags: impl Into<SnapshotListFlags>,
+    ) -> Result<Vec<DomainSnapshot>, Error> {
         let mut snaps: *mut sys::virDomainSnapshotPtr = ptr::null_mut();
         let size = unsafe {
-            sys::virDomainSnapshotListAllChildren(self.as_ptr(), &mut snaps, flags as libc::c_uint)
+            ffi!(sys::virDomainSnapshotListAllChildren(
+                self.as_ptr(),
+                &mut snaps,
+                flags.into().bits() as libc::c_uint,
+            ))
         };
         if size == -1 {
             return Err(Error::last_error());
@@ -233,8 +728,69 @@ impl DomainSnapshot {
         Ok(array)
     }
 
+    /// Get the names of the snapshots of a domain.
+    ///
+    /// The list is only a snapshot in time: prefer
+    /// [`Domain::list_all_snapshots()`] which avoids the race between
+    /// counting and listing.
+    pub fn list_names(
+        dom: &Domain,
+        flags: impl Into<SnapshotListFlags>,
+    ) -> Result<Vec<String>, Error> {
+        let flags = flags.into();
+        let num = DomainSnapshot::num(dom, flags)?;
+        let mut names: Vec<*mut libc::c_char> = vec![ptr::null_mut(); num as usize];
+        let size = unsafe {
+            ffi!(sys::virDomainSnapshotListNames(
+                dom.as_ptr(),
+                names.as_mut_ptr(),
+                num as libc::c_int,
+                flags.bits() as libc::c_uint,
+            ))
+        };
+        if size == -1 {
+            return Err(Error::last_error());
+        }
+
+        let mut array: Vec<String> = Vec::new();
+        for name in names.into_iter().take(size as usize) {
+            array.push(unsafe { c_chars_to_string!(name) });
+        }
+        Ok(array)
+    }
+
+    /// Get the names of the children of this snapshot.
+    ///
+    /// Pass [`SnapshotListFlags::DESCENDANTS`] to list the whole
+    /// subtree instead of the direct children only.
+    pub fn list_children_names(
+        &self,
+        flags: impl Into<SnapshotListFlags>,
+    ) -> Result<Vec<String>, Error> {
+        let flags = flags.into();
+        let num = self.num_children(flags)?;
+        let mut names: Vec<*mut libc::c_char> = vec![ptr::null_mut(); num as usize];
+        let size = unsafe {
+            ffi!(sys::virDomainSnapshotListChildrenNames(
+                self.as_ptr(),
+                names.as_mut_ptr(),
+                num as libc::c_int,
+                flags.bits() as libc::c_uint,
+            ))
+        };
+        if size == -1 {
+            return Err(Error::last_error());
+        }
+
+        let mut array: Vec<String> = Vec::new();
+        for name in names.into_iter().take(size as usize) {
+            array.push(unsafe { c_chars_to_string!(name) });
+        }
+        Ok(array)
+    }
+
     pub fn free(&mut self) -> Result<(), Error> {
-        let ret = unsafe { sys::virDomainSnapshotFree(self.as_ptr()) };
+        let ret = unsafe { ffi!(sys::virDomainSnapshotFree(self.as_ptr())) };
         if ret == -1 {
             return Err(Error::last_error());
         }
@@ -16,9 +16,11 @@
  * Sahid Orentino Ferdjaoui <sahid.ferdjaoui@redhat.com>
  */
 
+use std::cell::RefCell;
 use std::error::Error as StdError;
-use std::ffi::CStr;
+use std::ffi::{CStr, NulError};
 use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::sync::{Arc, Mutex};
 
 use crate::util::impl_enum;
 
@@ -641,6 +643,11 @@ pub struct Error {
     domain: sys::virErrorDomain,
     message: String,
     level: sys::virErrorLevel,
+    str1: Option<String>,
+    str2: Option<String>,
+    str3: Option<String>,
+    int1: i32,
+    int2: i32,
 }
 
 extern "C" fn noop(_data: *mut libc::c_void, _error: sys::virErrorPtr) {}
@@ -652,18 +659,80 @@ impl Error {
     /// used if you call [virt_sys] functions directly.
     pub fn last_error() -> Error {
         let ptr: sys::virErrorPtr = unsafe { sys::virGetLastError() };
-        if ptr.is_null() {
-            Error {
-                code: sys::VIR_ERR_INTERNAL_ERROR,
-                domain: sys::VIR_FROM_NONE,
-                message: "an unknown libvirt error occurred".into(),
-                level: sys::VIR_ERR_ERROR,
-            }
+        let err = if ptr.is_null() {
+            Error::new(
+                sys::VIR_ERR_INTERNAL_ERROR,
+                sys::VIR_FROM_NONE,
+                "an unknown libvirt error occurred".into(),
+            )
         } else {
             unsafe { Error::from_raw(ptr) }
+        };
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            function = crate::util::FfiCall::last(),
+            code = ?err.code(),
+            domain = ?err.domain(),
+            message = %err.message,
+            "libvirt call failed"
+        );
+        err
+    }
+
+    fn new(code: sys::virErrorNumber, domain: sys::virErrorDomain, message: String) -> Error {
+        Error {
+            code,
+            domain,
+            message,
+            level: sys::VIR_ERR_ERROR,
+            str1: None,
+            str2: None,
+            str3: None,
+            int1: 0,
+            int2: 0,
         }
     }
 
+    /// Builds an error for an XML document which could not be parsed.
+    pub(crate) fn from_xml_error(message: String) -> Error {
+        Error::new(sys::VIR_ERR_XML_ERROR, sys::VIR_FROM_XML, message)
+    }
+
+    /// Builds an error for an argument rejected on the Rust side.
+    pub(crate) fn from_invalid_arg(message: String) -> Error {
+        Error::new(sys::VIR_ERR_INVALID_ARG, sys::VIR_FROM_NONE, message)
+    }
+
+    /// Builds an error for a domain operation refused on the Rust side
+    /// because of the state of the domain.
+    pub(crate) fn from_operation_invalid(message: String) -> Error {
+        Error::new(
+            sys::VIR_ERR_OPERATION_INVALID,
+            sys::VIR_FROM_DOMAIN,
+            message,
+        )
+    }
+
+    /// Builds an error for a device of a domain which could not be
+    /// found on the Rust side.
+    pub(crate) fn from_device_missing(message: String) -> Error {
+        Error::new(sys::VIR_ERR_DEVICE_MISSING, sys::VIR_FROM_DOMAIN, message)
+    }
+
+    /// Builds an error for an operation which did not complete in time.
+    pub(crate) fn from_timeout(message: String) -> Error {
+        Error::new(sys::VIR_ERR_OPERATION_TIMEOUT, sys::VIR_FROM_NONE, message)
+    }
+
+    /// Builds an error for an I/O failure on the Rust side of a stream.
+    pub(crate) fn from_io_error(err: std::io::Error) -> Error {
+        Error::new(
+            sys::VIR_ERR_SYSTEM_ERROR,
+            sys::VIR_FROM_STREAMS,
+            err.to_string(),
+        )
+    }
+
     unsafe fn from_raw(ptr: sys::virErrorPtr) -> Error {
         let code = (*ptr).code as sys::virErrorNumber;
         let domain = (*ptr).domain as sys::virErrorDomain;
@@ -671,11 +740,23 @@ impl Error {
             .to_string_lossy()
             .into_owned();
         let level = (*ptr).level;
+        let opt_str = |p: *const libc::c_char| {
+            if p.is_null() {
+                None
+            } else {
+                Some(CStr::from_ptr(p).to_string_lossy().into_owned())
+            }
+        };
         Error {
             code,
             domain,
             message,
             level,
+            str1: opt_str((*ptr).str1),
+            str2: opt_str((*ptr).str2),
+            str3: opt_str((*ptr).str3),
+            int1: (*ptr).int1,
+            int2: (*ptr).int2,
         }
     }
 
@@ -698,6 +779,112 @@ impl Error {
     pub fn level(&self) -> ErrorLevel {
         ErrorLevel::from_raw(self.level)
     }
+
+    /// Returns the first extra string information, if any.
+    pub fn str1(&self) -> Option<&str> {
+        self.str1.as_deref()
+    }
+
+    /// Returns the second extra string information, if any.
+    pub fn str2(&self) -> Option<&str> {
+        self.str2.as_deref()
+    }
+
+    /// Returns the third extra string information, if any.
+    pub fn str3(&self) -> Option<&str> {
+        self.str3.as_deref()
+    }
+
+    /// Returns the first extra number information.
+    pub fn int1(&self) -> i32 {
+        self.int1
+    }
+
+    /// Returns the second extra number information.
+    pub fn int2(&self) -> i32 {
+        self.int2
+    }
+
+    /// Returns true if the error reports a missing object, such as an
+    /// unknown domain, network, storage pool, volume or device.
+    pub fn is_not_found(&self) -> bool {
+        matches!(
+            self.code(),
+            ErrorNumber::NoDomain
+                | ErrorNumber::NoNetwork
+                | ErrorNumber::NoStoragePool
+                | ErrorNumber::NoStorageVolume
+                | ErrorNumber::NoNodeDevice
+                | ErrorNumber::NoInterface
+                | ErrorNumber::NoSecret
+                | ErrorNumber::NoNwfilter
+                | ErrorNumber::NoNwfilterBinding
+                | ErrorNumber::NoDomainSnapshot
+                | ErrorNumber::NoDomainCheckpoint
+                | ErrorNumber::NoDomainBackup
+                | ErrorNumber::NoDomainMetadata
+                | ErrorNumber::NoNetworkMetadata
+                | ErrorNumber::NoNetworkPort
+                | ErrorNumber::NoDevice
+                | ErrorNumber::DeviceMissing
+                | ErrorNumber::NoServer
+                | ErrorNumber::NoClient
+        )
+    }
+
+    /// Returns true if the error reports an object which already exists.
+    pub fn is_already_exists(&self) -> bool {
+        matches!(
+            self.code(),
+            ErrorNumber::DomExist
+                | ErrorNumber::NetworkExist
+                | ErrorNumber::StorageVolExist
+                | ErrorNumber::NetworkPortExists
+        )
+    }
+
+    /// Returns true if the operation failed because a resource was
+    /// temporarily unavailable. Such operations may succeed when retried.
+    pub fn is_busy(&self) -> bool {
+        matches!(
+            self.code(),
+            ErrorNumber::ResourceBusy
+                | ErrorNumber::BlockCopyActive
+                | ErrorNumber::AgentUnresponsive
+                | ErrorNumber::AgentUnsynced
+                | ErrorNumber::OperationTimeout
+        )
+    }
+
+    /// Returns true if the driver or hypervisor does not support the
+    /// requested operation, argument or configuration.
+    pub fn is_unsupported(&self) -> bool {
+        matches!(
+            self.code(),
+            ErrorNumber::NoSupport
+                | ErrorNumber::OperationUnsupported
+                | ErrorNumber::ArgumentUnsupported
+                | ErrorNumber::ConfigUnsupported
+        )
+    }
+
+    /// Returns true if the connection to the hypervisor or daemon was
+    /// lost or is no longer usable. The connection must be reopened.
+    pub fn is_disconnect(&self) -> bool {
+        match self.code() {
+            ErrorNumber::NoConnect | ErrorNumber::InvalidConn | ErrorNumber::Rpc => true,
+            ErrorNumber::SystemError => {
+                matches!(self.domain(), ErrorDomain::Rpc | ErrorDomain::Remote)
+            }
+            _ => false,
+        }
+    }
+}
+
+impl From<NulError> for Error {
+    fn from(err: NulError) -> Error {
+        Error::from_invalid_arg(err.to_string())
+    }
 }
 
 impl StdError for Error {}
@@ -721,11 +908,152 @@ impl Display for Error {
     }
 }
 
+/// A closure receiving errors reported by libvirt.
+///
+/// libvirt may invoke error callbacks from any thread, including
+/// concurrently, hence the `Send + Sync` bounds.
+pub type ErrorCallback = dyn Fn(&Error) + Send + Sync;
+
+static GLOBAL_ERROR_CALLBACK: Mutex<Option<Arc<ErrorCallback>>> = Mutex::new(None);
+
+// Per-connection callbacks keyed by the address of the virConnect
+// object. The address is handed to libvirt as the opaque pointer, so a
+// late invocation after the callback was removed simply finds nothing.
+static CONN_ERROR_CALLBACKS: Mutex<Vec<(usize, Arc<ErrorCallback>)>> = Mutex::new(Vec::new());
+
+extern "C" fn global_error_callback(_data: *mut libc::c_void, error: sys::virErrorPtr) {
+    // Clone the closure out so it runs without the lock held and may
+    // itself call into libvirt.
+    let cb = match GLOBAL_ERROR_CALLBACK.lock() {
+        Ok(guard) => guard.clone(),
+        Err(_) => return,
+    };
+    if let Some(cb) = cb {
+        if !error.is_null() {
+            cb(&unsafe { Error::from_raw(error) });
+        }
+    }
+}
+
+extern "C" fn conn_error_callback(data: *mut libc::c_void, error: sys::virErrorPtr) {
+    let cb = match CONN_ERROR_CALLBACKS.lock() {
+        Ok(guard) => guard
+            .iter()
+            .find(|(key, _)| *key == data as usize)
+            .map(|(_, cb)| cb.clone()),
+        Err(_) => return,
+    };
+    if let Some(cb) = cb {
+        if !error.is_null() {
+            cb(&unsafe { Error::from_raw(error) });
+        }
+    }
+}
+
 /// Clears the libvirt error callback.
 ///
 /// Use this to disable libvirt's default handler, which prints all errors to stdout
 pub fn clear_error_callback() {
     unsafe {
-        sys::virSetErrorFunc(std::ptr::null_mut(), Some(noop));
+        ffi!(sys::virSetErrorFunc(std::ptr::null_mut(), Some(noop)));
+    }
+    if let Ok(mut guard) = GLOBAL_ERROR_CALLBACK.lock() {
+        *guard = None;
+    }
+}
+
+/// Installs a closure as the process-wide libvirt error handler.
+///
+/// The closure replaces libvirt's default handler, which prints all
+/// errors to stderr, and can be used to route them into the
+/// application's own logging instead. Errors are still returned from
+/// the failing calls as usual.
+///
+/// See <https://libvirt.org/html/libvirt-virterror.html#virSetErrorFunc>
+pub fn set_error_callback<F>(callback: F)
+where
+    F: 'static + Fn(&Error) + Send + Sync,
+{
+    if let Ok(mut guard) = GLOBAL_ERROR_CALLBACK.lock() {
+        *guard = Some(Arc::new(callback));
+    }
+    unsafe {
+        ffi!(sys::virSetErrorFunc(
+            std::ptr::null_mut(),
+            Some(global_error_callback)
+        ));
+    }
+}
+
+/// Restores libvirt's default error handler.
+pub fn reset_error_callback() {
+    unsafe {
+        ffi!(sys::virSetErrorFunc(std::ptr::null_mut(), None));
+    }
+    if let Ok(mut guard) = GLOBAL_ERROR_CALLBACK.lock() {
+        *guard = None;
+    }
+}
+
+pub(crate) fn set_conn_error_callback(conn: sys::virConnectPtr, callback: Arc<ErrorCallback>) {
+    if let Ok(mut guard) = CONN_ERROR_CALLBACKS.lock() {
+        guard.retain(|(key, _)| *key != conn as usize);
+        guard.push((conn as usize, callback));
+    }
+    unsafe {
+        ffi!(sys::virConnSetErrorFunc(
+            conn,
+            conn as *mut libc::c_void,
+            Some(conn_error_callback)
+        ));
+    }
+}
+
+pub(crate) fn remove_conn_error_callback(conn: sys::virConnectPtr, unregister: bool) {
+    if unregister {
+        unsafe {
+            ffi!(sys::virConnSetErrorFunc(conn, std::ptr::null_mut(), None));
+        }
+    }
+    if let Ok(mut guard) = CONN_ERROR_CALLBACKS.lock() {
+        guard.retain(|(key, _)| *key != conn as usize);
+    }
+}
+
+thread_local! {
+    static DROP_ERROR: RefCell<Option<Error>> = const { RefCell::new(None) };
+}
+
+/// Returns the error of the last failed release of a libvirt object
+/// dropped on this thread, if any, and forgets it.
+///
+/// Dropping an object can not report an error, and panicking there
+/// could abort the process while unwinding, so failures are kept here
+/// instead, and logged when the `tracing` feature is enabled. Call the
+/// `free()` method of an object to release it and get the error
+/// directly.
+pub fn take_drop_error() -> Option<Error> {
+    DROP_ERROR.with(|e| e.borrow_mut().take())
+}
+
+pub(crate) fn report_drop_error(object: &'static str, err: Error) {
+    #[cfg(feature = "tracing")]
+    tracing::warn!(object, error = %err, "unable to release libvirt object");
+    #[cfg(not(feature = "tracing"))]
+    let _ = object;
+    DROP_ERROR.with(|e| *e.borrow_mut() = Some(err));
+}
+
+#[cfg(test)]
+mod test {
+    use super::{report_drop_error, take_drop_error, Error};
+
+    #[test]
+    fn test_take_drop_error() {
+        assert_eq!(None, take_drop_error());
+        let err = Error::from_invalid_arg("bad pointer".into());
+        report_drop_error("Domain", err.clone());
+        assert_eq!(Some(err), take_drop_error());
+        assert_eq!(None, take_drop_error());
     }
 }
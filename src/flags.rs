@@ -0,0 +1,40 @@
+/*
+ * This library is free software; you can redistribute it and/or
+ * modify it under the terms of the GNU Lesser General Public
+ * License as published by the Free Software Foundation; either
+ * version 2.1 of the License, or (at your option) any later version.
+ *
+ * This library is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+ * Lesser General Public License for more details.
+ *
+ * You should have received a copy of the GNU Lesser General Public
+ * License along with this library.  If not, see
+ * <https://www.gnu.org/licenses/>.
+ */
+
+//! Types shared by the flags of several APIs.
+
+use crate::util::impl_flags;
+
+impl_flags! {
+    /// Selects whether a change applies to the running domain, to its
+    /// persistent configuration, or both.
+    ///
+    /// The setters of the domain parameters, such as
+    /// [`Domain::set_memory_parameters()`], take it, and it converts into
+    /// the flags of the device, memory and vCPU setters.
+    ///
+    /// See <https://libvirt.org/html/libvirt-libvirt-domain.html#virDomainModificationImpact>
+    ///
+    /// [`Domain::set_memory_parameters()`]: crate::domain::Domain::set_memory_parameters
+    struct DomainModificationImpact: sys::virDomainModificationImpact {
+        /// Affect the current domain state.
+        const CURRENT = sys::VIR_DOMAIN_AFFECT_CURRENT;
+        /// Affect the running domain only.
+        const LIVE = sys::VIR_DOMAIN_AFFECT_LIVE;
+        /// Affect the persistent configuration only.
+        const CONFIG = sys::VIR_DOMAIN_AFFECT_CONFIG;
+    }
+}
@@ -20,7 +20,8 @@ use std::ffi::CString;
 use std::str;
 
 use crate::connect::Connect;
-use crate::error::Error;
+use crate::error::{report_drop_error, Error};
+use crate::util::impl_identity;
 
 /// Provides APIs for the management of interfaces.
 ///
@@ -28,6 +29,7 @@ use crate::error::Error;
 #[derive(Debug)]
 pub struct Interface {
     ptr: Option<sys::virInterfacePtr>,
+    identity: Option<String>,
 }
 
 unsafe impl Send for Interface {}
@@ -37,7 +39,7 @@ impl Drop for Interface {
     fn drop(&mut self) {
         if self.ptr.is_some() {
             if let Err(e) = self.free() {
-                panic!("Unable to drop memory for Interface: {}", e)
+                report_drop_error("Interface", e);
             }
         }
     }
@@ -56,17 +58,23 @@ impl Clone for Interface {
     }
 }
 
+impl_identity!(Interface, get_name);
+
 impl Interface {
     /// # Safety
     ///
     /// The caller must ensure that the pointer is valid.
     pub unsafe fn from_ptr(ptr: sys::virInterfacePtr) -> Interface {
-        Interface { ptr: Some(ptr) }
+        Interface {
+            ptr: Some(ptr),
+            identity: Default::default(),
+        }
+        .load_identity()
     }
 
     fn add_ref(&self) -> Result<Interface, Error> {
         unsafe {
-            if sys::virInterfaceRef(self.as_ptr()) == -1 {
+            if ffi!(sys::virInterfaceRef(self.as_ptr())) == -1 {
                 return Err(Error::last_error());
             }
         }
@@ -79,7 +87,7 @@ impl Interface {
     }
 
     pub fn get_connect(&self) -> Result<Connect, Error> {
-        let ptr = unsafe { sys::virInterfaceGetConnect(self.as_ptr()) };
+        let ptr = unsafe { ffi!(sys::virInterfaceGetConnect(self.as_ptr())) };
         if ptr.is_null() {
             return Err(Error::last_error());
         }
@@ -87,8 +95,13 @@ impl Interface {
     }
 
     pub fn lookup_by_name(conn: &Connect, id: &str) -> Result<Interface, Error> {
-        let id_buf = CString::new(id).unwrap();
-        let ptr = unsafe { sys::virInterfaceLookupByName(conn.as_ptr(), id_buf.as_ptr()) };
+        let id_buf = CString::new(id)?;
+        let ptr = unsafe {
+            ffi!(sys::virInterfaceLookupByName(
+                conn.as_ptr(),
+                id_buf.as_ptr()
+            ))
+        };
         if ptr.is_null() {
             return Err(Error::last_error());
         }
@@ -96,9 +109,13 @@ impl Interface {
     }
 
     pub fn define_xml(conn: &Connect, xml: &str, flags: u32) -> Result<Interface, Error> {
-        let xml_buf = CString::new(xml).unwrap();
+        let xml_buf = CString::new(xml)?;
         let ptr = unsafe {
-            sys::virInterfaceDefineXML(conn.as_ptr(), xml_buf.as_ptr(), flags as libc::c_uint)
+            ffi!(sys::virInterfaceDefineXML(
+                conn.as_ptr(),
+                xml_buf.as_ptr(),
+                flags as libc::c_uint
+            ))
         };
         if ptr.is_null() {
             return Err(Error::last_error());
@@ -107,8 +124,13 @@ impl Interface {
     }
 
     pub fn lookup_by_mac_string(conn: &Connect, id: &str) -> Result<Interface, Error> {
-        let id_buf = CString::new(id).unwrap();
-        let ptr = unsafe { sys::virInterfaceLookupByMACString(conn.as_ptr(), id_buf.as_ptr()) };
+        let id_buf = CString::new(id)?;
+        let ptr = unsafe {
+            ffi!(sys::virInterfaceLookupByMACString(
+                conn.as_ptr(),
+                id_buf.as_ptr()
+            ))
+        };
         if ptr.is_null() {
             return Err(Error::last_error());
         }
@@ -116,7 +138,7 @@ impl Interface {
     }
 
     pub fn get_name(&self) -> Result<String, Error> {
-        let n = unsafe { sys::virInterfaceGetName(self.as_ptr()) };
+        let n = unsafe { ffi!(sys::virInterfaceGetName(self.as_ptr())) };
         if n.is_null() {
             return Err(Error::last_error());
         }
@@ -124,7 +146,7 @@ impl Interface {
     }
 
     pub fn get_mac_string(&self) -> Result<String, Error> {
-        let mac = unsafe { sys::virInterfaceGetMACString(self.as_ptr()) };
+        let mac = unsafe { ffi!(sys::virInterfaceGetMACString(self.as_ptr())) };
         if mac.is_null() {
             return Err(Error::last_error());
         }
@@ -132,7 +154,7 @@ impl Interface {
     }
 
     pub fn get_xml_desc(&self, flags: sys::virInterfaceXMLFlags) -> Result<String, Error> {
-        let xml = unsafe { sys::virInterfaceGetXMLDesc(self.as_ptr(), flags) };
+        let xml = unsafe { ffi!(sys::virInterfaceGetXMLDesc(self.as_ptr(), flags)) };
         if xml.is_null() {
             return Err(Error::last_error());
         }
@@ -140,7 +162,7 @@ impl Interface {
     }
 
     pub fn create(&self, flags: sys::virInterfaceXMLFlags) -> Result<u32, Error> {
-        let ret = unsafe { sys::virInterfaceCreate(self.as_ptr(), flags) };
+        let ret = unsafe { ffi!(sys::virInterfaceCreate(self.as_ptr(), flags)) };
         if ret == -1 {
             return Err(Error::last_error());
         }
@@ -148,7 +170,7 @@ impl Interface {
     }
 
     pub fn destroy(&self, flags: u32) -> Result<(), Error> {
-        let ret = unsafe { sys::virInterfaceDestroy(self.as_ptr(), flags) };
+        let ret = unsafe { ffi!(sys::virInterfaceDestroy(self.as_ptr(), flags)) };
         if ret == -1 {
             return Err(Error::last_error());
         }
@@ -156,7 +178,7 @@ impl Interface {
     }
 
     pub fn undefine(&self) -> Result<(), Error> {
-        let ret = unsafe { sys::virInterfaceUndefine(self.as_ptr()) };
+        let ret = unsafe { ffi!(sys::virInterfaceUndefine(self.as_ptr())) };
         if ret == -1 {
             return Err(Error::last_error());
         }
@@ -164,7 +186,7 @@ impl Interface {
     }
 
     pub fn free(&mut self) -> Result<(), Error> {
-        let ret = unsafe { sys::virInterfaceFree(self.as_ptr()) };
+        let ret = unsafe { ffi!(sys::virInterfaceFree(self.as_ptr())) };
         if ret == -1 {
             return Err(Error::last_error());
         }
@@ -173,7 +195,7 @@ impl Interface {
     }
 
     pub fn is_active(&self) -> Result<bool, Error> {
-        let ret = unsafe { sys::virInterfaceIsActive(self.as_ptr()) };
+        let ret = unsafe { ffi!(sys::virInterfaceIsActive(self.as_ptr())) };
         if ret == -1 {
             return Err(Error::last_error());
         }
@@ -60,7 +60,9 @@
 //!
 //! For each methods accepting or returning a virTypedParameter array
 //! a new Rust struct has been defined where each attribute is
-//! handling a type Option.
+//! handling a type Option. The strings of the parameters returned by
+//! their `to_vec()` methods are allocated with `malloc()` and must be
+//! released with `virTypedParamsClear()`.
 //!
 //! ```
 //! use virt::connect::Connect;
@@ -80,6 +82,20 @@
 
 pub extern crate virt_sys as sys;
 
+// Every call into libvirt goes through this macro so that it can be
+// instrumented when the `tracing` feature is enabled, except for
+// virGetLastError() which reports the error of the previous call:
+//
+//   let ret = unsafe { ffi!(sys::virDomainCreate(self.as_ptr())) };
+//
+macro_rules! ffi {
+    (sys::$func:ident($($arg:expr),* $(,)?)) => {{
+        #[cfg(feature = "tracing")]
+        let _call = $crate::util::FfiCall::enter(stringify!($func));
+        sys::$func($($arg),*)
+    }};
+}
+
 macro_rules! c_chars_to_string {
     ($x:expr) => {{
         let ret = ::std::ffi::CStr::from_ptr($x)
@@ -96,16 +112,6 @@ macro_rules! c_chars_to_string {
     }};
 }
 
-// The caller must do 'let _ptr_cleanup = CString::from_raw(ptr)'
-// to release the memory associated with the returned pointer.
-// Also note it is not valid to use C's free(ptr) call, it must
-// be released via the CString API.
-macro_rules! string_to_mut_c_chars {
-    ($x:expr) => {
-        ::std::ffi::CString::new($x).unwrap().into_raw() as *mut libc::c_char
-    };
-}
-
 // To be used when handling Option<&str> parameters which need
 // to be passed to libvirt. General usage pattern is:
 //
@@ -119,7 +125,7 @@ macro_rules! string_to_mut_c_chars {
 //
 macro_rules! some_string_to_cstring {
     ($x:expr) => {
-        $x.map(|s| CString::new(s).unwrap())
+        $x.map(CString::new).transpose()?
     };
 }
 
@@ -129,28 +135,30 @@ macro_rules! some_cstring_to_c_chars {
     };
 }
 
-macro_rules! typed_params_release_c_chars {
-    ($x:expr) => {
-        for p in $x {
-            if p.type_ == sys::VIR_TYPED_PARAM_STRING as libc::c_int {
-                let _cleanup = CString::from_raw(p.value.s);
-            }
-        }
-    };
-}
-
 mod typedparams;
 mod util;
+mod xmlutil;
 
+#[cfg(feature = "admin")]
+pub mod admin;
 pub mod connect;
+pub mod cpumap;
 pub mod domain;
 pub mod domain_snapshot;
 pub mod error;
+pub mod flags;
 pub mod interface;
+pub mod metrics;
 pub mod network;
 pub mod nodedev;
+pub mod nodeset;
 pub mod nwfilter;
+pub mod pool;
 pub mod secret;
 pub mod storage_pool;
 pub mod storage_vol;
 pub mod stream;
+#[cfg(feature = "testing")]
+pub mod testing;
+
+pub use crate::typedparams::{TypedParamsMap, TypedValue};
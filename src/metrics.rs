@@ -0,0 +1,430 @@
+/*
+ * This library is free software; you can redistribute it and/or
+ * modify it under the terms of the GNU Lesser General Public
+ * License as published by the Free Software Foundation; either
+ * version 2.1 of the License, or (at your option) any later version.
+ *
+ * This library is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+ * Lesser General Public License for more details.
+ *
+ * You should have received a copy of the GNU Lesser General Public
+ * License along with this library.  If not, see
+ * <https://www.gnu.org/licenses/>.
+ */
+
+//! Snapshots of the usage of every domain, meant for monitoring
+//! exporters.
+//!
+//! [`collect()`] gathers the CPU, memory, block and network statistics
+//! of the domains with a single call to
+//! [`Connect::get_all_domain_stats()`] and flattens them into plain
+//! structs. Statistics the hypervisor does not report are left to 0,
+//! so that the values can be exported as they are.
+//!
+//! [`CpuUtilizationTracker`] turns the cumulative CPU time counters of
+//! the host into utilization percentages.
+
+use crate::connect::{Connect, DomainStatsFilter, NodeCpuStats};
+use crate::domain::{DomainStatsGroups, DomainStatsRecord};
+use crate::error::Error;
+
+/// The statistics of a block device of a domain.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BlockMetrics {
+    /// The target name of the device, e.g. `vda`.
+    pub name: String,
+    /// The source path of the device, empty if it has none.
+    pub path: String,
+    pub read_requests: u64,
+    pub read_bytes: u64,
+    pub write_requests: u64,
+    pub write_bytes: u64,
+    pub flush_requests: u64,
+    /// Logical size in bytes, as seen by the guest.
+    pub capacity: u64,
+    /// Host storage in bytes occupied by the image.
+    pub allocation: u64,
+    /// Host physical size in bytes of the image container.
+    pub physical: u64,
+}
+
+/// The statistics of a network interface of a domain.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct InterfaceMetrics {
+    /// The name of the host side of the interface, e.g. `vnet0`.
+    pub name: String,
+    pub rx_bytes: u64,
+    pub rx_packets: u64,
+    pub rx_errors: u64,
+    pub rx_drops: u64,
+    pub tx_bytes: u64,
+    pub tx_packets: u64,
+    pub tx_errors: u64,
+    pub tx_drops: u64,
+}
+
+/// A snapshot of the statistics of a domain.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DomainMetrics {
+    pub name: String,
+    pub uuid: String,
+    /// The running state, one of virDomainState.
+    pub state: sys::virDomainState,
+    /// The total CPU time used, in nanoseconds.
+    pub cpu_time: u64,
+    /// The CPU time spent in user mode, in nanoseconds.
+    pub cpu_user: u64,
+    /// The CPU time spent in kernel mode, in nanoseconds.
+    pub cpu_system: u64,
+    /// The number of online vCPUs.
+    pub vcpus: u32,
+    /// The maximum number of vCPUs.
+    pub vcpus_maximum: u32,
+    /// The memory currently assigned through the balloon, in KiB.
+    pub memory_current: u64,
+    /// The maximum memory the balloon can grow to, in KiB.
+    pub memory_maximum: u64,
+    /// The resident set size of the hypervisor process, in KiB.
+    pub memory_rss: u64,
+    /// The memory usable by the guest, as reported by its balloon
+    /// driver, in KiB.
+    pub memory_usable: u64,
+    /// The memory left unused by the guest, in KiB.
+    pub memory_unused: u64,
+    pub blocks: Vec<BlockMetrics>,
+    pub interfaces: Vec<InterfaceMetrics>,
+}
+
+/// The statistics groups read by [`collect()`].
+pub fn stats_groups() -> DomainStatsGroups {
+    DomainStatsGroups::STATE
+        | DomainStatsGroups::CPU_TOTAL
+        | DomainStatsGroups::BALLOON
+        | DomainStatsGroups::VCPU
+        | DomainStatsGroups::INTERFACE
+        | DomainStatsGroups::BLOCK
+}
+
+/// Collects the statistics of every domain.
+pub fn collect(conn: &Connect) -> Result<Vec<DomainMetrics>, Error> {
+    collect_filtered(conn, DomainStatsFilter::empty())
+}
+
+/// Collects the statistics of the domains matching `filter`.
+pub fn collect_filtered(
+    conn: &Connect,
+    filter: impl Into<DomainStatsFilter>,
+) -> Result<Vec<DomainMetrics>, Error> {
+    conn.get_all_domain_stats(stats_groups(), filter)?
+        .iter()
+        .map(DomainMetrics::from_record)
+        .collect()
+}
+
+impl DomainMetrics {
+    /// Builds the metrics of a domain out of its statistics record.
+    pub fn from_record(record: &DomainStatsRecord) -> Result<DomainMetrics, Error> {
+        let dom = record.domain()?;
+        let mut metrics = DomainMetrics::from_params(&record.params());
+        metrics.name = dom.get_name()?;
+        metrics.uuid = dom.get_uuid_string()?;
+        Ok(metrics)
+    }
+
+    fn from_params(params: &[sys::virTypedParameter]) -> DomainMetrics {
+        let mut ret = DomainMetrics::default();
+        for param in params {
+            let name = unsafe { c_chars_to_string!(param.field.as_ptr(), nofree) };
+            if let Some(rest) = name.strip_prefix("block.") {
+                if let Some((idx, key)) = split_index(rest) {
+                    let block = nth(&mut ret.blocks, idx);
+                    match key {
+                        "name" => block.name = param_string(param),
+                        "path" => block.path = param_string(param),
+                        "rd.reqs" => block.read_requests = param_u64(param),
+                        "rd.bytes" => block.read_bytes = param_u64(param),
+                        "wr.reqs" => block.write_requests = param_u64(param),
+                        "wr.bytes" => block.write_bytes = param_u64(param),
+                        "fl.reqs" => block.flush_requests = param_u64(param),
+                        "capacity" => block.capacity = param_u64(param),
+                        "allocation" => block.allocation = param_u64(param),
+                        "physical" => block.physical = param_u64(param),
+                        _ => {}
+                    }
+                }
+                continue;
+            }
+            if let Some(rest) = name.strip_prefix("net.") {
+                if let Some((idx, key)) = split_index(rest) {
+                    let net = nth(&mut ret.interfaces, idx);
+                    match key {
+                        "name" => net.name = param_string(param),
+                        "rx.bytes" => net.rx_bytes = param_u64(param),
+                        "rx.pkts" => net.rx_packets = param_u64(param),
+                        "rx.errs" => net.rx_errors = param_u64(param),
+                        "rx.drop" => net.rx_drops = param_u64(param),
+                        "tx.bytes" => net.tx_bytes = param_u64(param),
+                        "tx.pkts" => net.tx_packets = param_u64(param),
+                        "tx.errs" => net.tx_errors = param_u64(param),
+                        "tx.drop" => net.tx_drops = param_u64(param),
+                        _ => {}
+                    }
+                }
+                continue;
+            }
+            match name.as_str() {
+                "state.state" => ret.state = param_u64(param) as sys::virDomainState,
+                "cpu.time" => ret.cpu_time = param_u64(param),
+                "cpu.user" => ret.cpu_user = param_u64(param),
+                "cpu.system" => ret.cpu_system = param_u64(param),
+                "vcpu.current" => ret.vcpus = param_u64(param) as u32,
+                "vcpu.maximum" => ret.vcpus_maximum = param_u64(param) as u32,
+                "balloon.current" => ret.memory_current = param_u64(param),
+                "balloon.maximum" => ret.memory_maximum = param_u64(param),
+                "balloon.rss" => ret.memory_rss = param_u64(param),
+                "balloon.usable" => ret.memory_usable = param_u64(param),
+                "balloon.unused" => ret.memory_unused = param_u64(param),
+                _ => {}
+            }
+        }
+        ret
+    }
+}
+
+/// The utilization of the host CPUs between two samples, in percent.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CpuUtilization {
+    pub user: f64,
+    pub kernel: f64,
+    pub iowait: f64,
+    pub idle: f64,
+    /// The share of the time the CPUs were neither idle nor waiting
+    /// for I/O.
+    pub busy: f64,
+}
+
+/// Computes the utilization of the host CPUs out of successive samples
+/// of [`Connect::get_node_cpu_stats()`].
+///
+/// The first sample only sets the baseline. A counter going backwards,
+/// e.g. after a CPU was unplugged, starts a new baseline instead of
+/// producing a bogus value.
+#[derive(Clone, Debug, Default)]
+pub struct CpuUtilizationTracker {
+    cpu: Option<u32>,
+    previous: Option<NodeCpuStats>,
+}
+
+impl CpuUtilizationTracker {
+    /// Tracks the host CPU `cpu`, or all the CPUs of the host when
+    /// `cpu` is `None`.
+    pub fn new(cpu: Option<u32>) -> CpuUtilizationTracker {
+        CpuUtilizationTracker {
+            cpu,
+            previous: None,
+        }
+    }
+
+    /// Reads the counters of the host and returns the utilization
+    /// since the previous sample.
+    pub fn sample(&mut self, conn: &Connect) -> Result<Option<CpuUtilization>, Error> {
+        let stats = conn.get_node_cpu_stats(self.cpu, 0)?;
+        Ok(self.update(stats))
+    }
+
+    /// Records `stats` and returns the utilization since the previous
+    /// sample, if there is one.
+    ///
+    /// Hosts reporting a utilization percentage instead of time
+    /// counters do not need a baseline.
+    pub fn update(&mut self, stats: NodeCpuStats) -> Option<CpuUtilization> {
+        if stats.idle.is_none() {
+            if let Some(utilization) = stats.utilization {
+                let busy = utilization.min(100) as f64;
+                return Some(CpuUtilization {
+                    idle: 100.0 - busy,
+                    busy,
+                    ..Default::default()
+                });
+            }
+        }
+
+        let previous = self.previous.replace(stats.clone())?;
+        let kernel = counter_delta(stats.kernel, previous.kernel)?;
+        let user = counter_delta(stats.user, previous.user)?;
+        let idle = counter_delta(stats.idle, previous.idle)?;
+        let iowait = counter_delta(stats.iowait, previous.iowait)?;
+        let intr = counter_delta(stats.intr, previous.intr)?;
+
+        let total = kernel + user + idle + iowait + intr;
+        if total == 0 {
+            return None;
+        }
+        let percent = |delta: u64| delta as f64 * 100.0 / total as f64;
+        Some(CpuUtilization {
+            user: percent(user),
+            kernel: percent(kernel),
+            iowait: percent(iowait),
+            idle: percent(idle),
+            busy: percent(total - idle - iowait),
+        })
+    }
+
+    /// Forgets the previous sample.
+    pub fn reset(&mut self) {
+        self.previous = None;
+    }
+}
+
+/// The increase of a counter, 0 if it is not reported and `None` if it
+/// went backwards.
+fn counter_delta(current: Option<u64>, previous: Option<u64>) -> Option<u64> {
+    match (current, previous) {
+        (Some(current), Some(previous)) => current.checked_sub(previous),
+        _ => Some(0),
+    }
+}
+
+/// Splits `"<index>.<key>"` into its parts.
+fn split_index(name: &str) -> Option<(usize, &str)> {
+    let (idx, key) = name.split_once('.')?;
+    Some((idx.parse().ok()?, key))
+}
+
+fn nth<T: Default>(vec: &mut Vec<T>, idx: usize) -> &mut T {
+    if vec.len() <= idx {
+        vec.resize_with(idx + 1, T::default);
+    }
+    &mut vec[idx]
+}
+
+/// Reads a numeric parameter, clamping negative values to 0.
+fn param_u64(param: &sys::virTypedParameter) -> u64 {
+    unsafe {
+        match param.type_ as u32 {
+            sys::VIR_TYPED_PARAM_INT => param.value.i.max(0) as u64,
+            sys::VIR_TYPED_PARAM_UINT => param.value.ui as u64,
+            sys::VIR_TYPED_PARAM_LLONG => param.value.l.max(0) as u64,
+            sys::VIR_TYPED_PARAM_ULLONG => param.value.ul,
+            sys::VIR_TYPED_PARAM_DOUBLE => param.value.d.max(0.0) as u64,
+            sys::VIR_TYPED_PARAM_BOOLEAN => param.value.b as u64,
+            _ => 0,
+        }
+    }
+}
+
+fn param_string(param: &sys::virTypedParameter) -> String {
+    if param.type_ as u32 != sys::VIR_TYPED_PARAM_STRING {
+        return String::new();
+    }
+    unsafe { c_chars_to_string!(param.value.s, nofree) }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{BlockMetrics, CpuUtilizationTracker, DomainMetrics, InterfaceMetrics};
+    use crate::connect::NodeCpuStats;
+    use crate::typedparams::{to_params, FieldOut, ParamOut};
+
+    #[test]
+    fn test_from_params() {
+        let state = Some(1);
+        let cpu_time = Some(42u64);
+        let vcpus = Some(2u32);
+        let balloon = Some(1024u64);
+        let block_name = Some("vda".to_string());
+        let block_rd = Some(7u64);
+        let net_name = Some("vnet0".to_string());
+        let net_rx = Some(100u64);
+        let net_drop = Some(-1i64);
+        let fields = vec![
+            ("state.state", ParamOut::Int32(&state)),
+            ("cpu.time", ParamOut::UInt64(&cpu_time)),
+            ("vcpu.current", ParamOut::UInt32(&vcpus)),
+            ("balloon.current", ParamOut::UInt64(&balloon)),
+            ("block.0.name", ParamOut::String(&block_name)),
+            ("block.0.rd.bytes", ParamOut::UInt64(&block_rd)),
+            ("net.1.name", ParamOut::String(&net_name)),
+            ("net.1.rx.bytes", ParamOut::UInt64(&net_rx)),
+            ("net.1.rx.drop", ParamOut::Int64(&net_drop)),
+        ]
+        .into_iter()
+        .map(|(name, value)| FieldOut {
+            name: name.to_string(),
+            value,
+        })
+        .collect();
+        let params = to_params(fields).unwrap();
+
+        let metrics = DomainMetrics::from_params(&params);
+        assert_eq!(1, metrics.state);
+        assert_eq!(42, metrics.cpu_time);
+        assert_eq!(2, metrics.vcpus);
+        assert_eq!(1024, metrics.memory_current);
+        assert_eq!(
+            vec![BlockMetrics {
+                name: "vda".to_string(),
+                read_bytes: 7,
+                ..Default::default()
+            }],
+            metrics.blocks
+        );
+        assert_eq!(
+            vec![
+                InterfaceMetrics::default(),
+                InterfaceMetrics {
+                    name: "vnet0".to_string(),
+                    rx_bytes: 100,
+                    ..Default::default()
+                }
+            ],
+            metrics.interfaces
+        );
+    }
+
+    fn cpu_stats(kernel: u64, user: u64, idle: u64, iowait: u64) -> NodeCpuStats {
+        NodeCpuStats {
+            kernel: Some(kernel),
+            user: Some(user),
+            idle: Some(idle),
+            iowait: Some(iowait),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_cpu_utilization() {
+        let mut tracker = CpuUtilizationTracker::new(None);
+        assert_eq!(None, tracker.update(cpu_stats(100, 200, 1000, 50)));
+
+        let usage = tracker.update(cpu_stats(110, 240, 1040, 60)).unwrap();
+        assert_eq!(10.0, usage.kernel);
+        assert_eq!(40.0, usage.user);
+        assert_eq!(40.0, usage.idle);
+        assert_eq!(10.0, usage.iowait);
+        assert_eq!(50.0, usage.busy);
+
+        // Nothing elapsed.
+        assert_eq!(None, tracker.update(cpu_stats(110, 240, 1040, 60)));
+
+        // The counters went backwards: only a new baseline.
+        assert_eq!(None, tracker.update(cpu_stats(10, 20, 30, 0)));
+        let usage = tracker.update(cpu_stats(20, 20, 30, 0)).unwrap();
+        assert_eq!(100.0, usage.busy);
+
+        let usage = tracker
+            .update(NodeCpuStats {
+                utilization: Some(25),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(25.0, usage.busy);
+        assert_eq!(75.0, usage.idle);
+    }
+}
@@ -17,12 +17,188 @@
  */
 
 use std::ffi::CString;
-use std::str;
+use std::{ptr, str};
 
 use uuid::Uuid;
+use xml::escape::escape_str_attribute;
 
 use crate::connect::Connect;
-use crate::error::Error;
+use crate::error::{report_drop_error, Error};
+use crate::util::{impl_enum, impl_flags, impl_identity};
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+/// The kind of metadata of a network.
+///
+/// See <https://libvirt.org/html/libvirt-libvirt-network.html#virNetworkMetadataType>
+pub enum NetworkMetadataType {
+    /// The `<description>` element.
+    Description,
+    /// The `<title>` element.
+    Title,
+    /// A custom element in `<metadata>`, identified by its namespace
+    /// URI.
+    Element,
+}
+
+impl_enum! {
+    enum: NetworkMetadataType,
+    raw: sys::virNetworkMetadataType,
+    match: {
+        sys::VIR_NETWORK_METADATA_DESCRIPTION => NetworkMetadataType::Description,
+        sys::VIR_NETWORK_METADATA_TITLE => NetworkMetadataType::Title,
+        sys::VIR_NETWORK_METADATA_ELEMENT => NetworkMetadataType::Element,
+        _ => NetworkMetadataType::Description,
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+/// The change made by [`Network::update()`].
+///
+/// See <https://libvirt.org/html/libvirt-libvirt-network.html#virNetworkUpdateCommand>
+pub enum NetworkUpdateCommand {
+    /// Invalid.
+    None,
+    /// Modify an existing element.
+    Modify,
+    /// Delete an existing element.
+    Delete,
+    /// Add an element at the end of the list.
+    AddLast,
+    /// Add an element at the beginning of the list.
+    AddFirst,
+}
+
+impl_enum! {
+    enum: NetworkUpdateCommand,
+    raw: sys::virNetworkUpdateCommand,
+    match: {
+        sys::VIR_NETWORK_UPDATE_COMMAND_NONE => NetworkUpdateCommand::None,
+        sys::VIR_NETWORK_UPDATE_COMMAND_MODIFY => NetworkUpdateCommand::Modify,
+        sys::VIR_NETWORK_UPDATE_COMMAND_DELETE => NetworkUpdateCommand::Delete,
+        sys::VIR_NETWORK_UPDATE_COMMAND_ADD_LAST => NetworkUpdateCommand::AddLast,
+        sys::VIR_NETWORK_UPDATE_COMMAND_ADD_FIRST => NetworkUpdateCommand::AddFirst,
+        _ => NetworkUpdateCommand::None,
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+/// The part of the network definition changed by [`Network::update()`].
+///
+/// See <https://libvirt.org/html/libvirt-libvirt-network.html#virNetworkUpdateSection>
+pub enum NetworkUpdateSection {
+    /// Invalid.
+    None,
+    /// The `<bridge>` element.
+    Bridge,
+    /// The `<domain>` element.
+    Domain,
+    /// An `<ip>` element.
+    Ip,
+    /// A `<host>` element in `<ip><dhcp>`.
+    IpDhcpHost,
+    /// A `<range>` element in `<ip><dhcp>`.
+    IpDhcpRange,
+    /// The `<forward>` element.
+    Forward,
+    /// An `<interface>` element in `<forward>`.
+    ForwardInterface,
+    /// A `<pf>` element in `<forward>`.
+    ForwardPf,
+    /// A `<portgroup>` element.
+    Portgroup,
+    /// A `<host>` element in `<dns>`.
+    DnsHost,
+    /// A `<txt>` element in `<dns>`.
+    DnsTxt,
+    /// A `<srv>` element in `<dns>`.
+    DnsSrv,
+}
+
+impl_enum! {
+    enum: NetworkUpdateSection,
+    raw: sys::virNetworkUpdateSection,
+    match: {
+        sys::VIR_NETWORK_SECTION_NONE => NetworkUpdateSection::None,
+        sys::VIR_NETWORK_SECTION_BRIDGE => NetworkUpdateSection::Bridge,
+        sys::VIR_NETWORK_SECTION_DOMAIN => NetworkUpdateSection::Domain,
+        sys::VIR_NETWORK_SECTION_IP => NetworkUpdateSection::Ip,
+        sys::VIR_NETWORK_SECTION_IP_DHCP_HOST => NetworkUpdateSection::IpDhcpHost,
+        sys::VIR_NETWORK_SECTION_IP_DHCP_RANGE => NetworkUpdateSection::IpDhcpRange,
+        sys::VIR_NETWORK_SECTION_FORWARD => NetworkUpdateSection::Forward,
+        sys::VIR_NETWORK_SECTION_FORWARD_INTERFACE => NetworkUpdateSection::ForwardInterface,
+        sys::VIR_NETWORK_SECTION_FORWARD_PF => NetworkUpdateSection::ForwardPf,
+        sys::VIR_NETWORK_SECTION_PORTGROUP => NetworkUpdateSection::Portgroup,
+        sys::VIR_NETWORK_SECTION_DNS_HOST => NetworkUpdateSection::DnsHost,
+        sys::VIR_NETWORK_SECTION_DNS_TXT => NetworkUpdateSection::DnsTxt,
+        sys::VIR_NETWORK_SECTION_DNS_SRV => NetworkUpdateSection::DnsSrv,
+        _ => NetworkUpdateSection::None,
+    }
+}
+
+impl_flags! {
+    /// Selects whether [`Network::update()`] and the metadata setter
+    /// and getter apply to the running network, to its persistent
+    /// configuration, or both.
+    ///
+    /// See <https://libvirt.org/html/libvirt-libvirt-network.html#virNetworkUpdateFlags>
+    struct NetworkUpdateFlags: sys::virNetworkUpdateFlags {
+        /// Affect the current network state.
+        const CURRENT = sys::VIR_NETWORK_UPDATE_AFFECT_CURRENT;
+        /// Affect the running network only.
+        const LIVE = sys::VIR_NETWORK_UPDATE_AFFECT_LIVE;
+        /// Affect the persistent configuration only.
+        const CONFIG = sys::VIR_NETWORK_UPDATE_AFFECT_CONFIG;
+    }
+}
+
+/// A DHCP lease handed out by a network.
+#[derive(Clone, Debug)]
+pub struct NetworkDHCPLease {
+    /// The name of the interface of the network.
+    pub iface: String,
+    /// The expiry time of the lease, in seconds since the epoch.
+    pub expiry_time: i64,
+    /// One of the `VIR_IP_ADDR_TYPE_*` constants.
+    pub typed: i32,
+    /// The MAC address of the client.
+    pub mac: Option<String>,
+    /// The IAID of the client, for DHCPv6.
+    pub iaid: Option<String>,
+    /// The leased IP address.
+    pub ip_addr: String,
+    /// The prefix length of the address.
+    pub prefix: u32,
+    /// The hostname announced by the client.
+    pub hostname: Option<String>,
+    /// The client identifier.
+    pub client_id: Option<String>,
+}
+
+impl NetworkDHCPLease {
+    /// # Safety
+    ///
+    /// The caller must ensure that the pointer is valid.
+    pub unsafe fn from_ptr(ptr: sys::virNetworkDHCPLeasePtr) -> NetworkDHCPLease {
+        let opt = |p: *mut libc::c_char| {
+            if p.is_null() {
+                None
+            } else {
+                Some(c_chars_to_string!(p, nofree))
+            }
+        };
+        NetworkDHCPLease {
+            iface: opt((*ptr).iface).unwrap_or_default(),
+            expiry_time: (*ptr).expirytime,
+            typed: (*ptr).type_,
+            mac: opt((*ptr).mac),
+            iaid: opt((*ptr).iaid),
+            ip_addr: opt((*ptr).ipaddr).unwrap_or_default(),
+            prefix: (*ptr).prefix,
+            hostname: opt((*ptr).hostname),
+            client_id: opt((*ptr).clientid),
+        }
+    }
+}
 
 /// Provides APIs for the management of networks.
 ///
@@ -30,6 +206,7 @@ use crate::error::Error;
 #[derive(Debug)]
 pub struct Network {
     ptr: Option<sys::virNetworkPtr>,
+    identity: Option<Uuid>,
 }
 
 unsafe impl Send for Network {}
@@ -39,7 +216,7 @@ impl Drop for Network {
     fn drop(&mut self) {
         if self.ptr.is_some() {
             if let Err(e) = self.free() {
-                panic!("Unable to drop memory for Network: {}", e)
+                report_drop_error("Network", e);
             }
         }
     }
@@ -58,17 +235,23 @@ impl Clone for Network {
     }
 }
 
+impl_identity!(Network, get_uuid);
+
 impl Network {
     /// # Safety
     ///
     /// The caller must ensure that the pointer is valid.
     pub unsafe fn from_ptr(ptr: sys::virNetworkPtr) -> Network {
-        Network { ptr: Some(ptr) }
+        Network {
+            ptr: Some(ptr),
+            identity: Default::default(),
+        }
+        .load_identity()
     }
 
     fn add_ref(&self) -> Result<Network, Error> {
         unsafe {
-            if sys::virNetworkRef(self.as_ptr()) == -1 {
+            if ffi!(sys::virNetworkRef(self.as_ptr())) == -1 {
                 return Err(Error::last_error());
             }
         }
@@ -81,7 +264,7 @@ impl Network {
     }
 
     pub fn get_connect(&self) -> Result<Connect, Error> {
-        let ptr = unsafe { sys::virNetworkGetConnect(self.as_ptr()) };
+        let ptr = unsafe { ffi!(sys::virNetworkGetConnect(self.as_ptr())) };
         if ptr.is_null() {
             return Err(Error::last_error());
         }
@@ -89,8 +272,8 @@ impl Network {
     }
 
     pub fn lookup_by_name(conn: &Connect, id: &str) -> Result<Network, Error> {
-        let id_buf = CString::new(id).unwrap();
-        let ptr = unsafe { sys::virNetworkLookupByName(conn.as_ptr(), id_buf.as_ptr()) };
+        let id_buf = CString::new(id)?;
+        let ptr = unsafe { ffi!(sys::virNetworkLookupByName(conn.as_ptr(), id_buf.as_ptr())) };
         if ptr.is_null() {
             return Err(Error::last_error());
         }
@@ -98,7 +281,12 @@ impl Network {
     }
 
     pub fn lookup_by_uuid(conn: &Connect, uuid: Uuid) -> Result<Network, Error> {
-        let ptr = unsafe { sys::virNetworkLookupByUUID(conn.as_ptr(), uuid.as_bytes().as_ptr()) };
+        let ptr = unsafe {
+            ffi!(sys::virNetworkLookupByUUID(
+                conn.as_ptr(),
+                uuid.as_bytes().as_ptr()
+            ))
+        };
         if ptr.is_null() {
             return Err(Error::last_error());
         }
@@ -106,16 +294,34 @@ impl Network {
     }
 
     pub fn lookup_by_uuid_string(conn: &Connect, uuid: &str) -> Result<Network, Error> {
-        let uuid_buf = CString::new(uuid).unwrap();
-        let ptr = unsafe { sys::virNetworkLookupByUUIDString(conn.as_ptr(), uuid_buf.as_ptr()) };
+        let uuid_buf = CString::new(uuid)?;
+        let ptr = unsafe {
+            ffi!(sys::virNetworkLookupByUUIDString(
+                conn.as_ptr(),
+                uuid_buf.as_ptr()
+            ))
+        };
         if ptr.is_null() {
             return Err(Error::last_error());
         }
         Ok(unsafe { Network::from_ptr(ptr) })
     }
 
+    /// Look up a network the way `virsh` does: by UUID if
+    /// `identifier` parses as one, then by name.
+    ///
+    /// The error of the last lookup is returned when none matches.
+    pub fn lookup(conn: &Connect, identifier: &str) -> Result<Network, Error> {
+        if let Ok(uuid) = Uuid::parse_str(identifier) {
+            if let Ok(net) = Network::lookup_by_uuid(conn, uuid) {
+                return Ok(net);
+            }
+        }
+        Network::lookup_by_name(conn, identifier)
+    }
+
     pub fn get_name(&self) -> Result<String, Error> {
-        let n = unsafe { sys::virNetworkGetName(self.as_ptr()) };
+        let n = unsafe { ffi!(sys::virNetworkGetName(self.as_ptr())) };
         if n.is_null() {
             return Err(Error::last_error());
         }
@@ -125,7 +331,7 @@ impl Network {
     pub fn get_uuid(&self) -> Result<Uuid, Error> {
         let mut uuid: [libc::c_uchar; sys::VIR_UUID_BUFLEN as usize] =
             [0; sys::VIR_UUID_BUFLEN as usize];
-        let ret = unsafe { sys::virNetworkGetUUID(self.as_ptr(), uuid.as_mut_ptr()) };
+        let ret = unsafe { ffi!(sys::virNetworkGetUUID(self.as_ptr(), uuid.as_mut_ptr())) };
         if ret == -1 {
             return Err(Error::last_error());
         }
@@ -135,7 +341,12 @@ impl Network {
     pub fn get_uuid_string(&self) -> Result<String, Error> {
         let mut uuid: [libc::c_char; sys::VIR_UUID_STRING_BUFLEN as usize] =
             [0; sys::VIR_UUID_STRING_BUFLEN as usize];
-        let ret = unsafe { sys::virNetworkGetUUIDString(self.as_ptr(), uuid.as_mut_ptr()) };
+        let ret = unsafe {
+            ffi!(sys::virNetworkGetUUIDString(
+                self.as_ptr(),
+                uuid.as_mut_ptr()
+            ))
+        };
         if ret == -1 {
             return Err(Error::last_error());
         }
@@ -143,7 +354,7 @@ impl Network {
     }
 
     pub fn get_bridge_name(&self) -> Result<String, Error> {
-        let n = unsafe { sys::virNetworkGetBridgeName(self.as_ptr()) };
+        let n = unsafe { ffi!(sys::virNetworkGetBridgeName(self.as_ptr())) };
         if n.is_null() {
             return Err(Error::last_error());
         }
@@ -151,7 +362,7 @@ impl Network {
     }
 
     pub fn get_xml_desc(&self, flags: sys::virNetworkXMLFlags) -> Result<String, Error> {
-        let xml = unsafe { sys::virNetworkGetXMLDesc(self.as_ptr(), flags) };
+        let xml = unsafe { ffi!(sys::virNetworkGetXMLDesc(self.as_ptr(), flags)) };
         if xml.is_null() {
             return Err(Error::last_error());
         }
@@ -159,7 +370,7 @@ impl Network {
     }
 
     pub fn create(&self) -> Result<u32, Error> {
-        let ret = unsafe { sys::virNetworkCreate(self.as_ptr()) };
+        let ret = unsafe { ffi!(sys::virNetworkCreate(self.as_ptr())) };
         if ret == -1 {
             return Err(Error::last_error());
         }
@@ -167,8 +378,8 @@ impl Network {
     }
 
     pub fn define_xml(conn: &Connect, xml: &str) -> Result<Network, Error> {
-        let xml_buf = CString::new(xml).unwrap();
-        let ptr = unsafe { sys::virNetworkDefineXML(conn.as_ptr(), xml_buf.as_ptr()) };
+        let xml_buf = CString::new(xml)?;
+        let ptr = unsafe { ffi!(sys::virNetworkDefineXML(conn.as_ptr(), xml_buf.as_ptr())) };
         if ptr.is_null() {
             return Err(Error::last_error());
         }
@@ -176,8 +387,8 @@ impl Network {
     }
 
     pub fn create_xml(conn: &Connect, xml: &str) -> Result<Network, Error> {
-        let xml_buf = CString::new(xml).unwrap();
-        let ptr = unsafe { sys::virNetworkCreateXML(conn.as_ptr(), xml_buf.as_ptr()) };
+        let xml_buf = CString::new(xml)?;
+        let ptr = unsafe { ffi!(sys::virNetworkCreateXML(conn.as_ptr(), xml_buf.as_ptr())) };
         if ptr.is_null() {
             return Err(Error::last_error());
         }
@@ -185,7 +396,7 @@ impl Network {
     }
 
     pub fn destroy(&self) -> Result<(), Error> {
-        let ret = unsafe { sys::virNetworkDestroy(self.as_ptr()) };
+        let ret = unsafe { ffi!(sys::virNetworkDestroy(self.as_ptr())) };
         if ret == -1 {
             return Err(Error::last_error());
         }
@@ -193,7 +404,7 @@ impl Network {
     }
 
     pub fn undefine(&self) -> Result<(), Error> {
-        let ret = unsafe { sys::virNetworkUndefine(self.as_ptr()) };
+        let ret = unsafe { ffi!(sys::virNetworkUndefine(self.as_ptr())) };
         if ret == -1 {
             return Err(Error::last_error());
         }
@@ -201,7 +412,7 @@ impl Network {
     }
 
     pub fn free(&mut self) -> Result<(), Error> {
-        let ret = unsafe { sys::virNetworkFree(self.as_ptr()) };
+        let ret = unsafe { ffi!(sys::virNetworkFree(self.as_ptr())) };
         if ret == -1 {
             return Err(Error::last_error());
         }
@@ -210,7 +421,7 @@ impl Network {
     }
 
     pub fn is_active(&self) -> Result<bool, Error> {
-        let ret = unsafe { sys::virNetworkIsActive(self.as_ptr()) };
+        let ret = unsafe { ffi!(sys::virNetworkIsActive(self.as_ptr())) };
         if ret == -1 {
             return Err(Error::last_error());
         }
@@ -218,7 +429,7 @@ impl Network {
     }
 
     pub fn is_persistent(&self) -> Result<bool, Error> {
-        let ret = unsafe { sys::virNetworkIsPersistent(self.as_ptr()) };
+        let ret = unsafe { ffi!(sys::virNetworkIsPersistent(self.as_ptr())) };
         if ret == -1 {
             return Err(Error::last_error());
         }
@@ -227,7 +438,7 @@ impl Network {
 
     pub fn get_autostart(&self) -> Result<bool, Error> {
         let mut auto = 0;
-        let ret = unsafe { sys::virNetworkGetAutostart(self.as_ptr(), &mut auto) };
+        let ret = unsafe { ffi!(sys::virNetworkGetAutostart(self.as_ptr(), &mut auto)) };
         if ret == -1 {
             return Err(Error::last_error());
         }
@@ -235,35 +446,223 @@ impl Network {
     }
 
     pub fn set_autostart(&self, autostart: bool) -> Result<u32, Error> {
-        let ret = unsafe { sys::virNetworkSetAutostart(self.as_ptr(), autostart as libc::c_int) };
+        let ret = unsafe {
+            ffi!(sys::virNetworkSetAutostart(
+                self.as_ptr(),
+                autostart as libc::c_int
+            ))
+        };
         if ret == -1 {
             return Err(Error::last_error());
         }
         Ok(ret as u32)
     }
 
+    /// Change a section of the network definition without
+    /// redefining it.
+    ///
+    /// `index` selects the parent element of the section when there
+    /// are several, for instance which `<ip>` a DHCP host belongs to;
+    /// -1 lets libvirt pick the first matching one.
     pub fn update(
         &self,
-        cmd: sys::virNetworkUpdateCommand,
-        section: sys::virNetworkUpdateSection,
+        cmd: NetworkUpdateCommand,
+        section: NetworkUpdateSection,
         index: i32,
         xml: &str,
-        flags: sys::virNetworkUpdateFlags,
+        flags: impl Into<NetworkUpdateFlags>,
     ) -> Result<(), Error> {
-        let xml_buf = CString::new(xml).unwrap();
+        let xml_buf = CString::new(xml)?;
         let ret = unsafe {
-            sys::virNetworkUpdate(
+            ffi!(sys::virNetworkUpdate(
                 self.as_ptr(),
-                cmd,
-                section,
+                cmd.to_raw() as libc::c_uint,
+                section.to_raw() as libc::c_uint,
                 index as libc::c_int,
                 xml_buf.as_ptr(),
-                flags,
-            )
+                flags.into().bits() as libc::c_uint,
+            ))
+        };
+        if ret == -1 {
+            return Err(Error::last_error());
+        }
+        Ok(())
+    }
+
+    fn dhcp_host_xml(mac: &str, name: Option<&str>, ip: Option<&str>) -> String {
+        let mut xml = format!("<host mac='{}'", escape_str_attribute(mac));
+        if let Some(name) = name {
+            xml.push_str(&format!(" name='{}'", escape_str_attribute(name)));
+        }
+        if let Some(ip) = ip {
+            xml.push_str(&format!(" ip='{}'", escape_str_attribute(ip)));
+        }
+        xml.push_str("/>");
+        xml
+    }
+
+    /// Add a static DHCP reservation of `ip` for the host with the
+    /// MAC address `mac`.
+    pub fn add_dhcp_host(
+        &self,
+        mac: &str,
+        name: Option<&str>,
+        ip: &str,
+        flags: impl Into<NetworkUpdateFlags>,
+    ) -> Result<(), Error> {
+        self.update(
+            NetworkUpdateCommand::AddLast,
+            NetworkUpdateSection::IpDhcpHost,
+            -1,
+            &Network::dhcp_host_xml(mac, name, Some(ip)),
+            flags,
+        )
+    }
+
+    /// Change the static DHCP reservation of the host with the MAC
+    /// address `mac`.
+    pub fn modify_dhcp_host(
+        &self,
+        mac: &str,
+        name: Option<&str>,
+        ip: &str,
+        flags: impl Into<NetworkUpdateFlags>,
+    ) -> Result<(), Error> {
+        self.update(
+            NetworkUpdateCommand::Modify,
+            NetworkUpdateSection::IpDhcpHost,
+            -1,
+            &Network::dhcp_host_xml(mac, name, Some(ip)),
+            flags,
+        )
+    }
+
+    /// Remove the static DHCP reservation of the host with the MAC
+    /// address `mac`.
+    pub fn delete_dhcp_host(
+        &self,
+        mac: &str,
+        flags: impl Into<NetworkUpdateFlags>,
+    ) -> Result<(), Error> {
+        self.update(
+            NetworkUpdateCommand::Delete,
+            NetworkUpdateSection::IpDhcpHost,
+            -1,
+            &Network::dhcp_host_xml(mac, None, None),
+            flags,
+        )
+    }
+
+    /// Get the DHCP leases handed out by the network, optionally only
+    /// those of the client with the MAC address `mac`.
+    pub fn get_dhcp_leases(&self, mac: Option<&str>) -> Result<Vec<NetworkDHCPLease>, Error> {
+        let mac_buf = some_string_to_cstring!(mac);
+        let mut leases: *mut sys::virNetworkDHCPLeasePtr = ptr::null_mut();
+        let size = unsafe {
+            ffi!(sys::virNetworkGetDHCPLeases(
+                self.as_ptr(),
+                some_cstring_to_c_chars!(mac_buf),
+                &mut leases,
+                0,
+            ))
+        };
+        if size == -1 {
+            return Err(Error::last_error());
+        }
+
+        let mut array: Vec<NetworkDHCPLease> = Vec::new();
+        for x in 0..size as isize {
+            unsafe {
+                let lease = *leases.offset(x);
+                array.push(NetworkDHCPLease::from_ptr(lease));
+                ffi!(sys::virNetworkDHCPLeaseFree(lease));
+            }
+        }
+        unsafe { libc::free(leases as *mut libc::c_void) };
+
+        Ok(array)
+    }
+
+    /// Set the metadata of the network.
+    ///
+    /// For [`NetworkMetadataType::Element`], `key` is the namespace
+    /// prefix and `uri` the namespace URI of the element; they are
+    /// ignored otherwise. Passing `None` as `metadata` removes it.
+    #[cfg(libvirt_9_7)]
+    pub fn set_metadata(
+        &self,
+        kind: NetworkMetadataType,
+        metadata: Option<&str>,
+        key: Option<&str>,
+        uri: Option<&str>,
+        flags: impl Into<NetworkUpdateFlags>,
+    ) -> Result<(), Error> {
+        let metadata_buf = some_string_to_cstring!(metadata);
+        let key_buf = some_string_to_cstring!(key);
+        let uri_buf = some_string_to_cstring!(uri);
+        let ret = unsafe {
+            ffi!(sys::virNetworkSetMetadata(
+                self.as_ptr(),
+                kind.to_raw() as libc::c_int,
+                some_cstring_to_c_chars!(metadata_buf),
+                some_cstring_to_c_chars!(key_buf),
+                some_cstring_to_c_chars!(uri_buf),
+                flags.into().bits() as libc::c_uint,
+            ))
         };
         if ret == -1 {
             return Err(Error::last_error());
         }
         Ok(())
     }
+
+    /// Get the metadata of the network.
+    ///
+    /// For [`NetworkMetadataType::Element`], `uri` selects the
+    /// namespace of the element to return.
+    #[cfg(libvirt_9_7)]
+    pub fn get_metadata(
+        &self,
+        kind: NetworkMetadataType,
+        uri: Option<&str>,
+        flags: impl Into<NetworkUpdateFlags>,
+    ) -> Result<String, Error> {
+        let uri_buf = some_string_to_cstring!(uri);
+        let n = unsafe {
+            ffi!(sys::virNetworkGetMetadata(
+                self.as_ptr(),
+                kind.to_raw() as libc::c_int,
+                some_cstring_to_c_chars!(uri_buf),
+                flags.into().bits() as libc::c_uint,
+            ))
+        };
+        if n.is_null() {
+            return Err(Error::last_error());
+        }
+        Ok(unsafe { c_chars_to_string!(n) })
+    }
+}
+
+/// The raw `VIR_NETWORK_*` flag values, grouped by the calls accepting
+/// them.
+///
+/// These are plain `u32` bits: they can be combined with `|` and passed
+/// both to the calls taking raw flags, such as
+/// [`Network::get_xml_desc()`], and to the ones taking a flags type,
+/// such as [`Network::update()`].
+///
+/// The flag types shared by several objects live in [`crate::flags`].
+pub mod flags {
+    // XML description, see Network::get_xml_desc().
+    /// Describe the persistent configuration.
+    pub const XML_INACTIVE: u32 = sys::VIR_NETWORK_XML_INACTIVE;
+
+    // Modification impact, see Network::update() and
+    // Network::set_metadata().
+    /// The current network state, live if active, persistent otherwise.
+    pub const UPDATE_AFFECT_CURRENT: u32 = sys::VIR_NETWORK_UPDATE_AFFECT_CURRENT;
+    /// The running network.
+    pub const UPDATE_AFFECT_LIVE: u32 = sys::VIR_NETWORK_UPDATE_AFFECT_LIVE;
+    /// The persistent configuration.
+    pub const UPDATE_AFFECT_CONFIG: u32 = sys::VIR_NETWORK_UPDATE_AFFECT_CONFIG;
 }
@@ -16,11 +16,559 @@
  * Sahid Orentino Ferdjaoui <sahid.ferdjaoui@redhat.com>
  */
 
+use std::collections::HashMap;
 use std::ffi::CString;
+use std::sync::{Arc, Mutex};
 use std::{ptr, str};
 
-use crate::connect::Connect;
-use crate::error::Error;
+use uuid::Uuid;
+use xml::escape::{escape_str_attribute, escape_str_pcdata};
+
+use crate::connect::{Connect, ConnectListAllNodeDeviceFlags};
+use crate::error::{report_drop_error, Error};
+use crate::util::{impl_enum, impl_identity, list_names};
+use crate::xmlutil::Element;
+
+/// A capability used to select node devices.
+///
+/// See <https://libvirt.org/formatnode.html>
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum NodeDeviceCap {
+    /// The host system.
+    System,
+    /// PCI devices.
+    Pci,
+    /// USB devices.
+    UsbDevice,
+    /// USB interfaces.
+    UsbInterface,
+    /// Network interfaces.
+    Net,
+    /// SCSI host adapters.
+    ScsiHost,
+    /// SCSI targets.
+    ScsiTarget,
+    /// SCSI devices.
+    Scsi,
+    /// Storage devices.
+    Storage,
+    /// Fibre Channel hosts.
+    FcHost,
+    /// Hosts capable of creating NPIV vports.
+    Vports,
+    /// SCSI generic devices.
+    ScsiGeneric,
+    /// DRM devices.
+    Drm,
+    /// Parents of mediated devices.
+    MdevTypes,
+    /// Mediated devices.
+    Mdev,
+    /// CCW devices.
+    CcwDevice,
+    /// CSS devices.
+    CssDevice,
+    /// vDPA devices.
+    Vdpa,
+    /// s390 AP cards.
+    ApCard,
+    /// s390 AP queues.
+    ApQueue,
+    /// s390 AP matrix devices.
+    ApMatrix,
+    /// Devices with Vital Product Data.
+    Vpd,
+}
+
+impl NodeDeviceCap {
+    /// Returns the name of the capability, as used by
+    /// [`NodeDevice::num_of_devices()`] and in the XML descriptions.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            NodeDeviceCap::System => "system",
+            NodeDeviceCap::Pci => "pci",
+            NodeDeviceCap::UsbDevice => "usb_device",
+            NodeDeviceCap::UsbInterface => "usb",
+            NodeDeviceCap::Net => "net",
+            NodeDeviceCap::ScsiHost => "scsi_host",
+            NodeDeviceCap::ScsiTarget => "scsi_target",
+            NodeDeviceCap::Scsi => "scsi",
+            NodeDeviceCap::Storage => "storage",
+            NodeDeviceCap::FcHost => "fc_host",
+            NodeDeviceCap::Vports => "vports",
+            NodeDeviceCap::ScsiGeneric => "scsi_generic",
+            NodeDeviceCap::Drm => "drm",
+            NodeDeviceCap::MdevTypes => "mdev_types",
+            NodeDeviceCap::Mdev => "mdev",
+            NodeDeviceCap::CcwDevice => "ccw",
+            NodeDeviceCap::CssDevice => "css",
+            NodeDeviceCap::Vdpa => "vdpa",
+            NodeDeviceCap::ApCard => "ap_card",
+            NodeDeviceCap::ApQueue => "ap_queue",
+            NodeDeviceCap::ApMatrix => "ap_matrix",
+            NodeDeviceCap::Vpd => "vpd",
+        }
+    }
+
+    /// Returns the capability called `name`, the reverse of
+    /// [`as_str()`](NodeDeviceCap::as_str).
+    pub fn from_name(name: &str) -> Option<NodeDeviceCap> {
+        Some(match name {
+            "system" => NodeDeviceCap::System,
+            "pci" => NodeDeviceCap::Pci,
+            "usb_device" => NodeDeviceCap::UsbDevice,
+            "usb" => NodeDeviceCap::UsbInterface,
+            "net" => NodeDeviceCap::Net,
+            "scsi_host" => NodeDeviceCap::ScsiHost,
+            "scsi_target" => NodeDeviceCap::ScsiTarget,
+            "scsi" => NodeDeviceCap::Scsi,
+            "storage" => NodeDeviceCap::Storage,
+            "fc_host" => NodeDeviceCap::FcHost,
+            "vports" => NodeDeviceCap::Vports,
+            "scsi_generic" => NodeDeviceCap::ScsiGeneric,
+            "drm" => NodeDeviceCap::Drm,
+            "mdev_types" => NodeDeviceCap::MdevTypes,
+            "mdev" => NodeDeviceCap::Mdev,
+            "ccw" => NodeDeviceCap::CcwDevice,
+            "css" => NodeDeviceCap::CssDevice,
+            "vdpa" => NodeDeviceCap::Vdpa,
+            "ap_card" => NodeDeviceCap::ApCard,
+            "ap_queue" => NodeDeviceCap::ApQueue,
+            "ap_matrix" => NodeDeviceCap::ApMatrix,
+            "vpd" => NodeDeviceCap::Vpd,
+            _ => return None,
+        })
+    }
+
+    /// Returns the filter selecting the devices with this capability
+    /// in [`Connect::list_all_node_devices()`].
+    pub fn list_flag(self) -> ConnectListAllNodeDeviceFlags {
+        match self {
+            NodeDeviceCap::System => ConnectListAllNodeDeviceFlags::CAP_SYSTEM,
+            NodeDeviceCap::Pci => ConnectListAllNodeDeviceFlags::CAP_PCI_DEV,
+            NodeDeviceCap::UsbDevice => ConnectListAllNodeDeviceFlags::CAP_USB_DEV,
+            NodeDeviceCap::UsbInterface => ConnectListAllNodeDeviceFlags::CAP_USB_INTERFACE,
+            NodeDeviceCap::Net => ConnectListAllNodeDeviceFlags::CAP_NET,
+            NodeDeviceCap::ScsiHost => ConnectListAllNodeDeviceFlags::CAP_SCSI_HOST,
+            NodeDeviceCap::ScsiTarget => ConnectListAllNodeDeviceFlags::CAP_SCSI_TARGET,
+            NodeDeviceCap::Scsi => ConnectListAllNodeDeviceFlags::CAP_SCSI,
+            NodeDeviceCap::Storage => ConnectListAllNodeDeviceFlags::CAP_STORAGE,
+            NodeDeviceCap::FcHost => ConnectListAllNodeDeviceFlags::CAP_FC_HOST,
+            NodeDeviceCap::Vports => ConnectListAllNodeDeviceFlags::CAP_VPORTS,
+            NodeDeviceCap::ScsiGeneric => ConnectListAllNodeDeviceFlags::CAP_SCSI_GENERIC,
+            NodeDeviceCap::Drm => ConnectListAllNodeDeviceFlags::CAP_DRM,
+            NodeDeviceCap::MdevTypes => ConnectListAllNodeDeviceFlags::CAP_MDEV_TYPES,
+            NodeDeviceCap::Mdev => ConnectListAllNodeDeviceFlags::CAP_MDEV,
+            NodeDeviceCap::CcwDevice => ConnectListAllNodeDeviceFlags::CAP_CCW_DEV,
+            NodeDeviceCap::CssDevice => ConnectListAllNodeDeviceFlags::CAP_CSS_DEV,
+            NodeDeviceCap::Vdpa => ConnectListAllNodeDeviceFlags::CAP_VDPA,
+            NodeDeviceCap::ApCard => ConnectListAllNodeDeviceFlags::CAP_AP_CARD,
+            NodeDeviceCap::ApQueue => ConnectListAllNodeDeviceFlags::CAP_AP_QUEUE,
+            NodeDeviceCap::ApMatrix => ConnectListAllNodeDeviceFlags::CAP_AP_MATRIX,
+            NodeDeviceCap::Vpd => ConnectListAllNodeDeviceFlags::CAP_VPD,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+/// The kind of a node device lifecycle event.
+///
+/// See <https://libvirt.org/html/libvirt-libvirt-nodedev.html#virNodeDeviceEventLifecycleType>
+pub enum NodeDeviceLifecycleEvent {
+    /// The device appeared on the host or was started.
+    Created,
+    /// The device disappeared from the host or was stopped.
+    Deleted,
+    /// A persistent definition was added.
+    Defined,
+    /// The persistent definition was removed.
+    Undefined,
+    /// An event unknown to this library, kept as reported by libvirt.
+    Unknown(sys::virNodeDeviceEventLifecycleType),
+}
+
+impl_enum! {
+    enum: NodeDeviceLifecycleEvent,
+    raw: sys::virNodeDeviceEventLifecycleType,
+    match: {
+        sys::VIR_NODE_DEVICE_EVENT_CREATED => NodeDeviceLifecycleEvent::Created,
+        sys::VIR_NODE_DEVICE_EVENT_DELETED => NodeDeviceLifecycleEvent::Deleted,
+        sys::VIR_NODE_DEVICE_EVENT_DEFINED => NodeDeviceLifecycleEvent::Defined,
+        sys::VIR_NODE_DEVICE_EVENT_UNDEFINED => NodeDeviceLifecycleEvent::Undefined,
+        _ => NodeDeviceLifecycleEvent::Unknown(raw),
+    }
+}
+
+/// A change of the devices of the host, reported by a
+/// [`NodeDeviceWatcher`] along with the capabilities of the device.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum NodeDeviceEvent {
+    /// A device was plugged in or started.
+    Added {
+        name: String,
+        caps: Vec<NodeDeviceCap>,
+    },
+    /// A device was unplugged or stopped.
+    Removed {
+        name: String,
+        caps: Vec<NodeDeviceCap>,
+    },
+    /// The description of a device changed.
+    Changed {
+        name: String,
+        caps: Vec<NodeDeviceCap>,
+    },
+}
+
+impl NodeDeviceEvent {
+    /// Returns the name of the device.
+    pub fn name(&self) -> &str {
+        match self {
+            NodeDeviceEvent::Added { name, .. }
+            | NodeDeviceEvent::Removed { name, .. }
+            | NodeDeviceEvent::Changed { name, .. } => name,
+        }
+    }
+
+    /// Returns the capabilities of the device.
+    pub fn caps(&self) -> &[NodeDeviceCap] {
+        match self {
+            NodeDeviceEvent::Added { caps, .. }
+            | NodeDeviceEvent::Removed { caps, .. }
+            | NodeDeviceEvent::Changed { caps, .. } => caps,
+        }
+    }
+
+    /// Returns `true` if the device has the capability `cap`.
+    pub fn has_cap(&self, cap: NodeDeviceCap) -> bool {
+        self.caps().contains(&cap)
+    }
+}
+
+type NodeDeviceWatcherCallback = dyn FnMut(&NodeDeviceEvent) + Send;
+
+/// Reports the node devices appearing, disappearing and changing on
+/// the host, for instance to attach USB or PCI devices to domains as
+/// they are plugged in.
+///
+/// The capabilities of the known devices are remembered, so that they
+/// are still reported once a device is gone. The callbacks are
+/// deregistered when the watcher is dropped.
+///
+/// An event loop implementation must be registered, for instance with
+/// [`sys::virEventRegisterDefaultImpl()`], and run for the events to be
+/// delivered.
+#[derive(Debug)]
+pub struct NodeDeviceWatcher {
+    conn: Option<Connect>,
+    callback_ids: Vec<i32>,
+}
+
+fn device_caps(dev: &NodeDevice) -> Vec<NodeDeviceCap> {
+    dev.list_caps()
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|c| NodeDeviceCap::from_name(c))
+        .collect()
+}
+
+impl NodeDeviceWatcher {
+    /// Starts calling `cb` for each change of the devices of the host.
+    pub fn new<F>(conn: &Connect, cb: F) -> Result<NodeDeviceWatcher, Error>
+    where
+        F: 'static + FnMut(&NodeDeviceEvent) + Send,
+    {
+        let mut known = HashMap::new();
+        for dev in conn.list_all_node_devices(ConnectListAllNodeDeviceFlags::empty())? {
+            known.insert(dev.get_name()?, device_caps(&dev));
+        }
+        let known = Arc::new(Mutex::new(known));
+        let cb: Arc<Mutex<Box<NodeDeviceWatcherCallback>>> = Arc::new(Mutex::new(Box::new(cb)));
+
+        // The clone keeps the connection open for as long as the
+        // watcher lives.
+        let mut watcher = NodeDeviceWatcher {
+            conn: Some(conn.clone()),
+            callback_ids: Vec::new(),
+        };
+
+        let (lifecycle_known, lifecycle_cb) = (known.clone(), cb.clone());
+        watcher
+            .callback_ids
+            .push(
+                conn.node_device_event_lifecycle_register(None, move |dev, event| {
+                    let name = match dev.get_name() {
+                        Ok(name) => name,
+                        Err(_) => return,
+                    };
+                    let mut known = match lifecycle_known.lock() {
+                        Ok(known) => known,
+                        Err(_) => return,
+                    };
+                    let event = match event {
+                        NodeDeviceLifecycleEvent::Created => {
+                            let caps = device_caps(dev);
+                            known.insert(name.clone(), caps.clone());
+                            NodeDeviceEvent::Added { name, caps }
+                        }
+                        NodeDeviceLifecycleEvent::Deleted => {
+                            let caps = known.remove(&name).unwrap_or_default();
+                            NodeDeviceEvent::Removed { name, caps }
+                        }
+                        _ => return,
+                    };
+                    drop(known);
+                    if let Ok(mut cb) = lifecycle_cb.lock() {
+                        cb(&event);
+                    }
+                })?,
+            );
+
+        watcher
+            .callback_ids
+            .push(conn.node_device_event_update_register(None, move |dev| {
+                let name = match dev.get_name() {
+                    Ok(name) => name,
+                    Err(_) => return,
+                };
+                let caps = device_caps(dev);
+                if let Ok(mut known) = known.lock() {
+                    known.insert(name.clone(), caps.clone());
+                }
+                if let Ok(mut cb) = cb.lock() {
+                    cb(&NodeDeviceEvent::Changed { name, caps });
+                }
+            })?);
+
+        Ok(watcher)
+    }
+
+    /// Deregisters the callbacks, reporting any error.
+    pub fn unwatch(mut self) -> Result<(), Error> {
+        self.release()
+    }
+
+    fn release(&mut self) -> Result<(), Error> {
+        let mut conn = match self.conn.take() {
+            Some(conn) => conn,
+            None => return Ok(()),
+        };
+        let mut ret = Ok(());
+        for id in self.callback_ids.drain(..) {
+            if let Err(e) = conn.node_device_event_deregister_any(id) {
+                ret = Err(e);
+            }
+        }
+        conn.close()?;
+        ret
+    }
+}
+
+impl Drop for NodeDeviceWatcher {
+    fn drop(&mut self) {
+        let _ = self.release();
+    }
+}
+
+/// A mediated device type offered by a parent device.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MdevType {
+    /// The identifier of the type, e.g. `nvidia-11`.
+    pub id: String,
+    /// The human readable name of the type, if any.
+    pub name: Option<String>,
+    /// The API exposed by devices of this type, e.g. `vfio-pci`.
+    pub device_api: String,
+    /// How many more devices of this type can be created.
+    pub available_instances: u32,
+}
+
+/// A capability of a node device, parsed from its XML description.
+///
+/// See <https://libvirt.org/formatnode.html>
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum NodeDeviceCapability {
+    /// A PCI device.
+    Pci {
+        domain: u32,
+        bus: u32,
+        slot: u32,
+        function: u32,
+        vendor_id: Option<u32>,
+        product_id: Option<u32>,
+        /// The IOMMU group the device belongs to.
+        iommu_group: Option<u32>,
+        /// The mediated device types this device can create.
+        mdev_types: Vec<MdevType>,
+    },
+    /// A USB device.
+    UsbDevice {
+        bus: u32,
+        device: u32,
+        vendor_id: Option<u32>,
+        product_id: Option<u32>,
+    },
+    /// A SCSI host adapter.
+    ScsiHost { host: u32, unique_id: Option<u32> },
+    /// A mediated device.
+    Mdev {
+        /// The identifier of the mediated device type.
+        type_id: String,
+        uuid: Option<String>,
+        iommu_group: Option<u32>,
+    },
+    /// Any other capability, by name.
+    Other(String),
+}
+
+fn parse_number(s: &str) -> Option<u32> {
+    let s = s.trim();
+    match s.strip_prefix("0x") {
+        Some(hex) => u32::from_str_radix(hex, 16).ok(),
+        None => s.parse().ok(),
+    }
+}
+
+fn child_number(elem: &Element, name: &str) -> Option<u32> {
+    elem.child(name).and_then(|e| parse_number(e.text()))
+}
+
+fn id_number(elem: &Element, name: &str) -> Option<u32> {
+    elem.child(name)
+        .and_then(|e| e.attr("id"))
+        .and_then(parse_number)
+}
+
+impl NodeDeviceCapability {
+    /// Parses the capabilities out of the XML description of a node
+    /// device, as returned by [`NodeDevice::get_xml_desc()`].
+    pub fn from_xml(xml: &str) -> Result<Vec<NodeDeviceCapability>, Error> {
+        let root = Element::parse(xml)?;
+        Ok(root
+            .children("capability")
+            .map(NodeDeviceCapability::from_element)
+            .collect())
+    }
+
+    fn from_element(cap: &Element) -> NodeDeviceCapability {
+        let iommu_group = || {
+            cap.child("iommuGroup")
+                .and_then(|g| g.attr("number"))
+                .and_then(parse_number)
+        };
+        match cap.attr("type").unwrap_or_default() {
+            "pci" => NodeDeviceCapability::Pci {
+                domain: child_number(cap, "domain").unwrap_or_default(),
+                bus: child_number(cap, "bus").unwrap_or_default(),
+                slot: child_number(cap, "slot").unwrap_or_default(),
+                function: child_number(cap, "function").unwrap_or_default(),
+                vendor_id: id_number(cap, "vendor"),
+                product_id: id_number(cap, "product"),
+                iommu_group: iommu_group(),
+                mdev_types: cap
+                    .children("capability")
+                    .filter(|c| c.attr("type") == Some("mdev_types"))
+                    .flat_map(|c| c.children("type"))
+                    .map(|t| MdevType {
+                        id: t.attr("id").unwrap_or_default().to_string(),
+                        name: t.child("name").map(|n| n.text().to_string()),
+                        device_api: t
+                            .child("deviceAPI")
+                            .map(|d| d.text().to_string())
+                            .unwrap_or_default(),
+                        available_instances: child_number(t, "availableInstances")
+                            .unwrap_or_default(),
+                    })
+                    .collect(),
+            },
+            "usb_device" => NodeDeviceCapability::UsbDevice {
+                bus: child_number(cap, "bus").unwrap_or_default(),
+                device: child_number(cap, "device").unwrap_or_default(),
+                vendor_id: id_number(cap, "vendor"),
+                product_id: id_number(cap, "product"),
+            },
+            "scsi_host" => NodeDeviceCapability::ScsiHost {
+                host: child_number(cap, "host").unwrap_or_default(),
+                unique_id: child_number(cap, "unique_id"),
+            },
+            "mdev" => NodeDeviceCapability::Mdev {
+                type_id: cap
+                    .child("type")
+                    .and_then(|t| t.attr("id"))
+                    .unwrap_or_default()
+                    .to_string(),
+                uuid: cap.child("uuid").map(|u| u.text().to_string()),
+                iommu_group: iommu_group(),
+            },
+            other => NodeDeviceCapability::Other(other.to_string()),
+        }
+    }
+}
+
+/// A mediated device to create on top of a parent device, such as a
+/// vGPU on a physical GPU.
+///
+/// See <https://libvirt.org/drvnodedev.html#mediated-devices-mdevs>
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MdevSpec {
+    /// The name of the parent node device, e.g. `pci_0000_02_00_0`.
+    pub parent: String,
+    /// The mediated device type, one of the [`MdevType`] ids offered by
+    /// the parent.
+    pub type_id: String,
+    /// The UUID of the device, generated by libvirt when `None`.
+    pub uuid: Option<Uuid>,
+    /// The vendor specific attributes, as name and value pairs.
+    pub attributes: Vec<(String, String)>,
+}
+
+impl MdevSpec {
+    /// Describes a mediated device of type `type_id` on `parent`.
+    pub fn new(parent: &str, type_id: &str) -> MdevSpec {
+        MdevSpec {
+            parent: parent.to_string(),
+            type_id: type_id.to_string(),
+            uuid: None,
+            attributes: Vec::new(),
+        }
+    }
+
+    /// Returns the node device XML matching this description.
+    pub fn to_xml(&self) -> String {
+        let mut xml = format!(
+            "<device>\n  <parent>{}</parent>\n  <capability type='mdev'>\n",
+            escape_str_pcdata(&self.parent)
+        );
+        xml.push_str(&format!(
+            "    <type id='{}'/>\n",
+            escape_str_attribute(&self.type_id)
+        ));
+        if let Some(uuid) = &self.uuid {
+            xml.push_str(&format!("    <uuid>{}</uuid>\n", uuid));
+        }
+        for (name, value) in &self.attributes {
+            xml.push_str(&format!(
+                "    <attr name='{}' value='{}'/>\n",
+                escape_str_attribute(name),
+                escape_str_attribute(value)
+            ));
+        }
+        xml.push_str("  </capability>\n</device>\n");
+        xml
+    }
+
+    /// Defines the device persistently, without starting it.
+    #[cfg(libvirt_7_3)]
+    pub fn define(&self, conn: &Connect) -> Result<NodeDevice, Error> {
+        NodeDevice::define_xml(conn, &self.to_xml(), 0)
+    }
+
+    /// Creates and starts a transient device.
+    pub fn create(&self, conn: &Connect) -> Result<NodeDevice, Error> {
+        NodeDevice::create_xml(conn, &self.to_xml(), 0)
+    }
+}
 
 /// Provides APIs for the management of nodedevs.
 ///
@@ -28,6 +576,7 @@ use crate::error::Error;
 #[derive(Debug)]
 pub struct NodeDevice {
     ptr: Option<sys::virNodeDevicePtr>,
+    identity: Option<String>,
 }
 
 unsafe impl Send for NodeDevice {}
@@ -37,7 +586,7 @@ impl Drop for NodeDevice {
     fn drop(&mut self) {
         if self.ptr.is_some() {
             if let Err(e) = self.free() {
-                panic!("Unable to drop memory for NodeDevice: {}", e)
+                report_drop_error("NodeDevice", e);
             }
         }
     }
@@ -56,17 +605,23 @@ impl Clone for NodeDevice {
     }
 }
 
+impl_identity!(NodeDevice, get_name);
+
 impl NodeDevice {
     /// # Safety
     ///
     /// The caller must ensure that the pointer is valid.
     pub unsafe fn from_ptr(ptr: sys::virNodeDevicePtr) -> NodeDevice {
-        NodeDevice { ptr: Some(ptr) }
+        NodeDevice {
+            ptr: Some(ptr),
+            identity: Default::default(),
+        }
+        .load_identity()
     }
 
     fn add_ref(&self) -> Result<NodeDevice, Error> {
         unsafe {
-            if sys::virNodeDeviceRef(self.as_ptr()) == -1 {
+            if ffi!(sys::virNodeDeviceRef(self.as_ptr())) == -1 {
                 return Err(Error::last_error());
             }
         }
@@ -79,8 +634,13 @@ impl NodeDevice {
     }
 
     pub fn lookup_by_name(conn: &Connect, id: &str) -> Result<NodeDevice, Error> {
-        let id_buf = CString::new(id).unwrap();
-        let ptr = unsafe { sys::virNodeDeviceLookupByName(conn.as_ptr(), id_buf.as_ptr()) };
+        let id_buf = CString::new(id)?;
+        let ptr = unsafe {
+            ffi!(sys::virNodeDeviceLookupByName(
+                conn.as_ptr(),
+                id_buf.as_ptr()
+            ))
+        };
         if ptr.is_null() {
             return Err(Error::last_error());
         }
@@ -93,15 +653,15 @@ impl NodeDevice {
         wwpn: &str,
         flags: u32,
     ) -> Result<NodeDevice, Error> {
-        let wwnn_buf = CString::new(wwnn).unwrap();
-        let wwpn_buf = CString::new(wwpn).unwrap();
+        let wwnn_buf = CString::new(wwnn)?;
+        let wwpn_buf = CString::new(wwpn)?;
         let ptr = unsafe {
-            sys::virNodeDeviceLookupSCSIHostByWWN(
+            ffi!(sys::virNodeDeviceLookupSCSIHostByWWN(
                 conn.as_ptr(),
                 wwnn_buf.as_ptr(),
                 wwpn_buf.as_ptr(),
                 flags as libc::c_uint,
-            )
+            ))
         };
         if ptr.is_null() {
             return Err(Error::last_error());
@@ -110,9 +670,35 @@ impl NodeDevice {
     }
 
     pub fn create_xml(conn: &Connect, xml: &str, flags: u32) -> Result<NodeDevice, Error> {
-        let xml_buf = CString::new(xml).unwrap();
+        let xml_buf = CString::new(xml)?;
+        let ptr = unsafe {
+            ffi!(sys::virNodeDeviceCreateXML(
+                conn.as_ptr(),
+                xml_buf.as_ptr(),
+                flags as libc::c_uint
+            ))
+        };
+        if ptr.is_null() {
+            return Err(Error::last_error());
+        }
+        Ok(unsafe { NodeDevice::from_ptr(ptr) })
+    }
+
+    /// Define a persistent node device, such as a mediated device,
+    /// without starting it.
+    #[cfg(libvirt_7_3)]
+    pub fn define_xml(
+        conn: &Connect,
+        xml: &str,
+        flags: sys::virNodeDeviceDefineXMLFlags,
+    ) -> Result<NodeDevice, Error> {
+        let xml_buf = CString::new(xml)?;
         let ptr = unsafe {
-            sys::virNodeDeviceCreateXML(conn.as_ptr(), xml_buf.as_ptr(), flags as libc::c_uint)
+            ffi!(sys::virNodeDeviceDefineXML(
+                conn.as_ptr(),
+                xml_buf.as_ptr(),
+                flags as libc::c_uint
+            ))
         };
         if ptr.is_null() {
             return Err(Error::last_error());
@@ -120,8 +706,81 @@ impl NodeDevice {
         Ok(unsafe { NodeDevice::from_ptr(ptr) })
     }
 
+    /// Undefine a persistent node device. An active device becomes
+    /// transient.
+    #[cfg(libvirt_7_3)]
+    pub fn undefine(&self, flags: u32) -> Result<(), Error> {
+        let ret = unsafe {
+            ffi!(sys::virNodeDeviceUndefine(
+                self.as_ptr(),
+                flags as libc::c_uint
+            ))
+        };
+        if ret == -1 {
+            return Err(Error::last_error());
+        }
+        Ok(())
+    }
+
+    /// Start a defined but inactive node device.
+    #[cfg(libvirt_7_3)]
+    pub fn create(&self, flags: u32) -> Result<(), Error> {
+        let ret = unsafe {
+            ffi!(sys::virNodeDeviceCreate(
+                self.as_ptr(),
+                flags as libc::c_uint
+            ))
+        };
+        if ret == -1 {
+            return Err(Error::last_error());
+        }
+        Ok(())
+    }
+
+    #[cfg(libvirt_7_8)]
+    pub fn is_active(&self) -> Result<bool, Error> {
+        let ret = unsafe { ffi!(sys::virNodeDeviceIsActive(self.as_ptr())) };
+        if ret == -1 {
+            return Err(Error::last_error());
+        }
+        Ok(ret == 1)
+    }
+
+    #[cfg(libvirt_7_8)]
+    pub fn is_persistent(&self) -> Result<bool, Error> {
+        let ret = unsafe { ffi!(sys::virNodeDeviceIsPersistent(self.as_ptr())) };
+        if ret == -1 {
+            return Err(Error::last_error());
+        }
+        Ok(ret == 1)
+    }
+
+    #[cfg(libvirt_7_8)]
+    pub fn get_autostart(&self) -> Result<bool, Error> {
+        let mut auto = 0;
+        let ret = unsafe { ffi!(sys::virNodeDeviceGetAutostart(self.as_ptr(), &mut auto)) };
+        if ret == -1 {
+            return Err(Error::last_error());
+        }
+        Ok(auto == 1)
+    }
+
+    #[cfg(libvirt_7_8)]
+    pub fn set_autostart(&self, autostart: bool) -> Result<(), Error> {
+        let ret = unsafe {
+            ffi!(sys::virNodeDeviceSetAutostart(
+                self.as_ptr(),
+                autostart as libc::c_int
+            ))
+        };
+        if ret == -1 {
+            return Err(Error::last_error());
+        }
+        Ok(())
+    }
+
     pub fn get_name(&self) -> Result<String, Error> {
-        let n = unsafe { sys::virNodeDeviceGetName(self.as_ptr()) };
+        let n = unsafe { ffi!(sys::virNodeDeviceGetName(self.as_ptr())) };
         if n.is_null() {
             return Err(Error::last_error());
         }
@@ -129,23 +788,76 @@ impl NodeDevice {
     }
 
     pub fn get_parent(&self) -> Result<String, Error> {
-        let n = unsafe { sys::virNodeDeviceGetParent(self.as_ptr()) };
+        let n = unsafe { ffi!(sys::virNodeDeviceGetParent(self.as_ptr())) };
         if n.is_null() {
             return Err(Error::last_error());
         }
         Ok(unsafe { c_chars_to_string!(n, nofree) })
     }
 
+    /// Returns the closest PCI device among the ancestors of the
+    /// device, such as the physical GPU of a mediated device, or `None`
+    /// if there is none.
+    pub fn pci_parent(&self, conn: &Connect) -> Result<Option<NodeDevice>, Error> {
+        let mut xml = self.get_xml_desc(0)?;
+        loop {
+            let parent = match Element::parse(&xml)?.child("parent") {
+                Some(parent) => parent.text().to_string(),
+                None => return Ok(None),
+            };
+            let dev = NodeDevice::lookup_by_name(conn, &parent)?;
+            xml = dev.get_xml_desc(0)?;
+            if NodeDeviceCapability::from_xml(&xml)?
+                .iter()
+                .any(|c| matches!(c, NodeDeviceCapability::Pci { .. }))
+            {
+                return Ok(Some(dev));
+            }
+        }
+    }
+
     pub fn get_xml_desc(&self, flags: u32) -> Result<String, Error> {
-        let xml = unsafe { sys::virNodeDeviceGetXMLDesc(self.as_ptr(), flags as libc::c_uint) };
+        let xml = unsafe {
+            ffi!(sys::virNodeDeviceGetXMLDesc(
+                self.as_ptr(),
+                flags as libc::c_uint
+            ))
+        };
         if xml.is_null() {
             return Err(Error::last_error());
         }
         Ok(unsafe { c_chars_to_string!(xml) })
     }
 
+    /// Update the definition of a node device.
+    #[cfg(libvirt_10_1)]
+    pub fn update(&self, xml: &str, flags: sys::virNodeDeviceUpdateFlags) -> Result<(), Error> {
+        let xml_buf = CString::new(xml)?;
+        let ret = unsafe {
+            ffi!(sys::virNodeDeviceUpdate(
+                self.as_ptr(),
+                xml_buf.as_ptr(),
+                flags as libc::c_uint
+            ))
+        };
+        if ret == -1 {
+            return Err(Error::last_error());
+        }
+        Ok(())
+    }
+
+    /// Get the typed capabilities of the device.
+    ///
+    /// This contains one entry per name returned by
+    /// [`list_caps()`](NodeDevice::list_caps), except for nested
+    /// capabilities such as the mediated device types of a PCI
+    /// device, which are reported within their parent.
+    pub fn get_capabilities(&self) -> Result<Vec<NodeDeviceCapability>, Error> {
+        NodeDeviceCapability::from_xml(&self.get_xml_desc(0)?)
+    }
+
     pub fn destroy(&self) -> Result<u32, Error> {
-        let ret = unsafe { sys::virNodeDeviceDestroy(self.as_ptr()) };
+        let ret = unsafe { ffi!(sys::virNodeDeviceDestroy(self.as_ptr())) };
         if ret == -1 {
             return Err(Error::last_error());
         }
@@ -153,7 +865,7 @@ impl NodeDevice {
     }
 
     pub fn detach(&self) -> Result<u32, Error> {
-        let ret = unsafe { sys::virNodeDeviceDettach(self.as_ptr()) };
+        let ret = unsafe { ffi!(sys::virNodeDeviceDettach(self.as_ptr())) };
         if ret == -1 {
             return Err(Error::last_error());
         }
@@ -161,7 +873,7 @@ impl NodeDevice {
     }
 
     pub fn reset(&self) -> Result<u32, Error> {
-        let ret = unsafe { sys::virNodeDeviceReset(self.as_ptr()) };
+        let ret = unsafe { ffi!(sys::virNodeDeviceReset(self.as_ptr())) };
         if ret == -1 {
             return Err(Error::last_error());
         }
@@ -169,7 +881,7 @@ impl NodeDevice {
     }
 
     pub fn reattach(&self) -> Result<u32, Error> {
-        let ret = unsafe { sys::virNodeDeviceReAttach(self.as_ptr()) };
+        let ret = unsafe { ffi!(sys::virNodeDeviceReAttach(self.as_ptr())) };
         if ret == -1 {
             return Err(Error::last_error());
         }
@@ -179,11 +891,11 @@ impl NodeDevice {
     pub fn detach_flags(&self, driver: Option<&str>, flags: u32) -> Result<u32, Error> {
         let driver_buf = some_string_to_cstring!(driver);
         let ret = unsafe {
-            sys::virNodeDeviceDetachFlags(
+            ffi!(sys::virNodeDeviceDetachFlags(
                 self.as_ptr(),
                 some_cstring_to_c_chars!(driver_buf),
                 flags as libc::c_uint,
-            )
+            ))
         };
         if ret == -1 {
             return Err(Error::last_error());
@@ -192,7 +904,7 @@ impl NodeDevice {
     }
 
     pub fn free(&mut self) -> Result<(), Error> {
-        let ret = unsafe { sys::virNodeDeviceFree(self.as_ptr()) };
+        let ret = unsafe { ffi!(sys::virNodeDeviceFree(self.as_ptr())) };
         if ret == -1 {
             return Err(Error::last_error());
         }
@@ -203,11 +915,11 @@ impl NodeDevice {
     pub fn num_of_devices(conn: &Connect, cap: Option<&str>, flags: u32) -> Result<u32, Error> {
         let cap_buf = some_string_to_cstring!(cap);
         let num = unsafe {
-            sys::virNodeNumOfDevices(
+            ffi!(sys::virNodeNumOfDevices(
                 conn.as_ptr(),
                 some_cstring_to_c_chars!(cap_buf),
                 flags as libc::c_uint,
-            )
+            ))
         };
         if num == -1 {
             return Err(Error::last_error());
@@ -216,25 +928,17 @@ impl NodeDevice {
     }
 
     pub fn num_of_caps(&self) -> Result<u32, Error> {
-        let num = unsafe { sys::virNodeDeviceNumOfCaps(self.as_ptr()) };
+        let num = unsafe { ffi!(sys::virNodeDeviceNumOfCaps(self.as_ptr())) };
         if num == -1 {
             return Err(Error::last_error());
         }
         Ok(num as u32)
     }
 
-    #[allow(clippy::needless_range_loop)]
     pub fn list_caps(&self) -> Result<Vec<String>, Error> {
-        let mut names: [*mut libc::c_char; 1024] = [ptr::null_mut(); 1024];
-        let size = unsafe { sys::virNodeDeviceListCaps(self.as_ptr(), names.as_mut_ptr(), 1024) };
-        if size == -1 {
-            return Err(Error::last_error());
-        }
-
-        let mut array: Vec<String> = Vec::new();
-        for x in 0..size as usize {
-            array.push(unsafe { c_chars_to_string!(names[x]) });
-        }
-        Ok(array)
+        list_names(
+            || unsafe { ffi!(sys::virNodeDeviceNumOfCaps(self.as_ptr())) },
+            |names, len| unsafe { ffi!(sys::virNodeDeviceListCaps(self.as_ptr(), names, len)) },
+        )
     }
 }
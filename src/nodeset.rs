@@ -0,0 +1,206 @@
+/*
+ * This library is free software; you can redistribute it and/or
+ * modify it under the terms of the GNU Lesser General Public
+ * License as published by the Free Software Foundation; either
+ * version 2.1 of the License, or (at your option) any later version.
+ *
+ * This library is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+ * Lesser General Public License for more details.
+ *
+ * You should have received a copy of the GNU Lesser General Public
+ * License along with this library.  If not, see
+ * <https://www.gnu.org/licenses/>.
+ */
+
+use std::collections::BTreeSet;
+use std::fmt;
+use std::iter::FromIterator;
+use std::str::FromStr;
+
+use crate::error::Error;
+
+/// A set of host NUMA nodes, or CPUs, in the range syntax used by
+/// libvirt, e.g. `0-3,^2,5`.
+///
+/// Parsing accepts single IDs, inclusive ranges and exclusions,
+/// which remove IDs added by the previous entries, up to
+/// [`NodeSet::MAX_ID`]. Formatting always
+/// produces the shortest list of ranges, without exclusions.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct NodeSet {
+    nodes: BTreeSet<u32>,
+}
+
+impl NodeSet {
+    /// The highest ID accepted when parsing a set, as libvirt caps its
+    /// CPU and node masks to 16384 entries.
+    pub const MAX_ID: u32 = 16383;
+
+    /// Creates an empty set.
+    pub fn new() -> NodeSet {
+        NodeSet::default()
+    }
+
+    /// Returns the number of nodes in the set.
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Returns `true` if the set holds no node.
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Returns `true` if `node` is part of the set.
+    pub fn contains(&self, node: u32) -> bool {
+        self.nodes.contains(&node)
+    }
+
+    /// Adds `node` to the set, returning `false` if it was already
+    /// part of it.
+    pub fn insert(&mut self, node: u32) -> bool {
+        self.nodes.insert(node)
+    }
+
+    /// Removes `node` from the set, returning `false` if it was not
+    /// part of it.
+    pub fn remove(&mut self, node: u32) -> bool {
+        self.nodes.remove(&node)
+    }
+
+    /// Returns the lowest node of the set.
+    pub fn first(&self) -> Option<u32> {
+        self.nodes.iter().next().copied()
+    }
+
+    /// Returns the highest node of the set.
+    pub fn last(&self) -> Option<u32> {
+        self.nodes.iter().next_back().copied()
+    }
+
+    /// Returns an iterator over the nodes, in increasing order.
+    pub fn iter(&self) -> impl Iterator<Item = u32> + '_ {
+        self.nodes.iter().copied()
+    }
+
+    /// Returns the inclusive ranges of consecutive nodes, in increasing
+    /// order.
+    pub fn ranges(&self) -> Vec<(u32, u32)> {
+        let mut ranges: Vec<(u32, u32)> = Vec::new();
+        for node in self.iter() {
+            match ranges.last_mut() {
+                Some((_, end)) if *end + 1 == node => *end = node,
+                _ => ranges.push((node, node)),
+            }
+        }
+        ranges
+    }
+
+    /// Returns the nodes part of `self` or `other`.
+    pub fn union(&self, other: &NodeSet) -> NodeSet {
+        self.nodes.union(&other.nodes).copied().collect()
+    }
+
+    /// Returns the nodes part of both `self` and `other`.
+    pub fn intersection(&self, other: &NodeSet) -> NodeSet {
+        self.nodes.intersection(&other.nodes).copied().collect()
+    }
+
+    /// Returns the nodes part of `self` but not of `other`.
+    pub fn difference(&self, other: &NodeSet) -> NodeSet {
+        self.nodes.difference(&other.nodes).copied().collect()
+    }
+
+    /// Returns `true` if every node of `self` is part of `other`.
+    pub fn is_subset(&self, other: &NodeSet) -> bool {
+        self.nodes.is_subset(&other.nodes)
+    }
+}
+
+impl FromStr for NodeSet {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<NodeSet, Error> {
+        let invalid = || Error::from_invalid_arg(format!("invalid node set '{}'", s));
+        let parse = |n: &str| {
+            let id = n.trim().parse::<u32>().map_err(|_| invalid())?;
+            if id > NodeSet::MAX_ID {
+                return Err(Error::from_invalid_arg(format!(
+                    "node {} of node set '{}' is above the maximum of {}",
+                    id,
+                    s,
+                    NodeSet::MAX_ID
+                )));
+            }
+            Ok(id)
+        };
+
+        let mut set = NodeSet::new();
+        for entry in s.split(',').map(str::trim) {
+            if entry.is_empty() {
+                if s.trim().is_empty() {
+                    continue;
+                }
+                return Err(invalid());
+            }
+            if let Some(excluded) = entry.strip_prefix('^') {
+                set.remove(parse(excluded)?);
+                continue;
+            }
+            match entry.split_once('-') {
+                Some((start, end)) => {
+                    let (start, end) = (parse(start)?, parse(end)?);
+                    if start > end {
+                        return Err(invalid());
+                    }
+                    set.nodes.extend(start..=end);
+                }
+                None => {
+                    set.insert(parse(entry)?);
+                }
+            }
+        }
+        Ok(set)
+    }
+}
+
+impl fmt::Display for NodeSet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, (start, end)) in self.ranges().into_iter().enumerate() {
+            if i > 0 {
+                write!(f, ",")?;
+            }
+            if start == end {
+                write!(f, "{}", start)?;
+            } else {
+                write!(f, "{}-{}", start, end)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromIterator<u32> for NodeSet {
+    fn from_iter<I: IntoIterator<Item = u32>>(iter: I) -> NodeSet {
+        NodeSet {
+            nodes: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl Extend<u32> for NodeSet {
+    fn extend<I: IntoIterator<Item = u32>>(&mut self, iter: I) {
+        self.nodes.extend(iter)
+    }
+}
+
+impl<'a> IntoIterator for &'a NodeSet {
+    type Item = u32;
+    type IntoIter = std::iter::Copied<std::collections::btree_set::Iter<'a, u32>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.nodes.iter().copied()
+    }
+}
@@ -22,7 +22,18 @@ use std::str;
 use uuid::Uuid;
 
 use crate::connect::Connect;
-use crate::error::Error;
+use crate::error::{report_drop_error, Error};
+use crate::util::{impl_flags, impl_identity};
+
+impl_flags! {
+    /// Flags used when defining a network filter.
+    ///
+    /// See <https://libvirt.org/html/libvirt-libvirt-nwfilter.html#virNWFilterDefineFlags>
+    struct NWFilterDefineFlags: sys::virNWFilterDefineFlags {
+        /// Validate the XML document against the schema.
+        const VALIDATE = sys::VIR_NWFILTER_DEFINE_VALIDATE;
+    }
+}
 
 /// Provides APIs for the management for network filters.
 ///
@@ -30,6 +41,7 @@ use crate::error::Error;
 #[derive(Debug)]
 pub struct NWFilter {
     ptr: Option<sys::virNWFilterPtr>,
+    identity: Option<Uuid>,
 }
 
 unsafe impl Send for NWFilter {}
@@ -39,7 +51,7 @@ impl Drop for NWFilter {
     fn drop(&mut self) {
         if self.ptr.is_some() {
             if let Err(e) = self.free() {
-                panic!("Unable to drop memory for NWFilter: {}", e)
+                report_drop_error("NWFilter", e);
             }
         }
     }
@@ -58,17 +70,23 @@ impl Clone for NWFilter {
     }
 }
 
+impl_identity!(NWFilter, get_uuid);
+
 impl NWFilter {
     /// # Safety
     ///
     /// The caller must ensure that the pointer is valid.
     pub unsafe fn from_ptr(ptr: sys::virNWFilterPtr) -> NWFilter {
-        NWFilter { ptr: Some(ptr) }
+        NWFilter {
+            ptr: Some(ptr),
+            identity: Default::default(),
+        }
+        .load_identity()
     }
 
     fn add_ref(&self) -> Result<NWFilter, Error> {
         unsafe {
-            if sys::virNWFilterRef(self.as_ptr()) == -1 {
+            if ffi!(sys::virNWFilterRef(self.as_ptr())) == -1 {
                 return Err(Error::last_error());
             }
         }
@@ -81,8 +99,8 @@ impl NWFilter {
     }
 
     pub fn lookup_by_name(conn: &Connect, id: &str) -> Result<NWFilter, Error> {
-        let id_buf = CString::new(id).unwrap();
-        let ptr = unsafe { sys::virNWFilterLookupByName(conn.as_ptr(), id_buf.as_ptr()) };
+        let id_buf = CString::new(id)?;
+        let ptr = unsafe { ffi!(sys::virNWFilterLookupByName(conn.as_ptr(), id_buf.as_ptr())) };
         if ptr.is_null() {
             return Err(Error::last_error());
         }
@@ -90,7 +108,12 @@ impl NWFilter {
     }
 
     pub fn lookup_by_uuid(conn: &Connect, uuid: Uuid) -> Result<NWFilter, Error> {
-        let ptr = unsafe { sys::virNWFilterLookupByUUID(conn.as_ptr(), uuid.as_bytes().as_ptr()) };
+        let ptr = unsafe {
+            ffi!(sys::virNWFilterLookupByUUID(
+                conn.as_ptr(),
+                uuid.as_bytes().as_ptr()
+            ))
+        };
         if ptr.is_null() {
             return Err(Error::last_error());
         }
@@ -98,16 +121,34 @@ impl NWFilter {
     }
 
     pub fn lookup_by_uuid_string(conn: &Connect, uuid: &str) -> Result<NWFilter, Error> {
-        let uuid_buf = CString::new(uuid).unwrap();
-        let ptr = unsafe { sys::virNWFilterLookupByUUIDString(conn.as_ptr(), uuid_buf.as_ptr()) };
+        let uuid_buf = CString::new(uuid)?;
+        let ptr = unsafe {
+            ffi!(sys::virNWFilterLookupByUUIDString(
+                conn.as_ptr(),
+                uuid_buf.as_ptr()
+            ))
+        };
         if ptr.is_null() {
             return Err(Error::last_error());
         }
         Ok(unsafe { NWFilter::from_ptr(ptr) })
     }
 
+    /// Look up a network filter the way `virsh` does: by UUID if
+    /// `identifier` parses as one, then by name.
+    ///
+    /// The error of the last lookup is returned when none matches.
+    pub fn lookup(conn: &Connect, identifier: &str) -> Result<NWFilter, Error> {
+        if let Ok(uuid) = Uuid::parse_str(identifier) {
+            if let Ok(filter) = NWFilter::lookup_by_uuid(conn, uuid) {
+                return Ok(filter);
+            }
+        }
+        NWFilter::lookup_by_name(conn, identifier)
+    }
+
     pub fn get_name(&self) -> Result<String, Error> {
-        let n = unsafe { sys::virNWFilterGetName(self.as_ptr()) };
+        let n = unsafe { ffi!(sys::virNWFilterGetName(self.as_ptr())) };
         if n.is_null() {
             return Err(Error::last_error());
         }
@@ -117,7 +158,7 @@ impl NWFilter {
     pub fn get_uuid(&self) -> Result<Uuid, Error> {
         let mut uuid: [libc::c_uchar; sys::VIR_UUID_BUFLEN as usize] =
             [0; sys::VIR_UUID_BUFLEN as usize];
-        let ret = unsafe { sys::virNWFilterGetUUID(self.as_ptr(), uuid.as_mut_ptr()) };
+        let ret = unsafe { ffi!(sys::virNWFilterGetUUID(self.as_ptr(), uuid.as_mut_ptr())) };
         if ret == -1 {
             return Err(Error::last_error());
         }
@@ -127,7 +168,12 @@ impl NWFilter {
     pub fn get_uuid_string(&self) -> Result<String, Error> {
         let mut uuid: [libc::c_char; sys::VIR_UUID_STRING_BUFLEN as usize] =
             [0; sys::VIR_UUID_STRING_BUFLEN as usize];
-        let ret = unsafe { sys::virNWFilterGetUUIDString(self.as_ptr(), uuid.as_mut_ptr()) };
+        let ret = unsafe {
+            ffi!(sys::virNWFilterGetUUIDString(
+                self.as_ptr(),
+                uuid.as_mut_ptr()
+            ))
+        };
         if ret == -1 {
             return Err(Error::last_error());
         }
@@ -135,7 +181,12 @@ impl NWFilter {
     }
 
     pub fn get_xml_desc(&self, flags: u32) -> Result<String, Error> {
-        let xml = unsafe { sys::virNWFilterGetXMLDesc(self.as_ptr(), flags as libc::c_uint) };
+        let xml = unsafe {
+            ffi!(sys::virNWFilterGetXMLDesc(
+                self.as_ptr(),
+                flags as libc::c_uint
+            ))
+        };
         if xml.is_null() {
             return Err(Error::last_error());
         }
@@ -143,8 +194,30 @@ impl NWFilter {
     }
 
     pub fn define_xml(conn: &Connect, xml: &str) -> Result<NWFilter, Error> {
-        let xml_buf = CString::new(xml).unwrap();
-        let ptr = unsafe { sys::virNWFilterDefineXML(conn.as_ptr(), xml_buf.as_ptr()) };
+        let xml_buf = CString::new(xml)?;
+        let ptr = unsafe { ffi!(sys::virNWFilterDefineXML(conn.as_ptr(), xml_buf.as_ptr())) };
+        if ptr.is_null() {
+            return Err(Error::last_error());
+        }
+        Ok(unsafe { NWFilter::from_ptr(ptr) })
+    }
+
+    /// Define a network filter, validating the XML document first
+    /// when [`NWFilterDefineFlags::VALIDATE`] is given.
+    #[cfg(libvirt_7_7)]
+    pub fn define_xml_flags(
+        conn: &Connect,
+        xml: &str,
+        flags: impl Into<NWFilterDefineFlags>,
+    ) -> Result<NWFilter, Error> {
+        let xml_buf = CString::new(xml)?;
+        let ptr = unsafe {
+            ffi!(sys::virNWFilterDefineXMLFlags(
+                conn.as_ptr(),
+                xml_buf.as_ptr(),
+                flags.into().bits() as libc::c_uint,
+            ))
+        };
         if ptr.is_null() {
             return Err(Error::last_error());
         }
@@ -152,7 +225,7 @@ impl NWFilter {
     }
 
     pub fn undefine(&self) -> Result<(), Error> {
-        let ret = unsafe { sys::virNWFilterUndefine(self.as_ptr()) };
+        let ret = unsafe { ffi!(sys::virNWFilterUndefine(self.as_ptr())) };
         if ret == -1 {
             return Err(Error::last_error());
         }
@@ -160,7 +233,7 @@ impl NWFilter {
     }
 
     pub fn free(&mut self) -> Result<(), Error> {
-        let ret = unsafe { sys::virNWFilterFree(self.as_ptr()) };
+        let ret = unsafe { ffi!(sys::virNWFilterFree(self.as_ptr())) };
         if ret == -1 {
             return Err(Error::last_error());
         }
@@ -0,0 +1,310 @@
+/*
+ * This library is free software; you can redistribute it and/or
+ * modify it under the terms of the GNU Lesser General Public
+ * License as published by the Free Software Foundation; either
+ * version 2.1 of the License, or (at your option) any later version.
+ *
+ * This library is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+ * Lesser General Public License for more details.
+ *
+ * You should have received a copy of the GNU Lesser General Public
+ * License along with this library.  If not, see
+ * <https://www.gnu.org/licenses/>.
+ */
+
+//! A small pool of connections to a single hypervisor URI.
+//!
+//! Long running services usually want to share a bounded number of
+//! connections between threads and to recover transparently when the
+//! daemon restarts. [`ConnectPool`] hands out [`PooledConnect`] guards
+//! which return the connection to the pool when dropped.
+//!
+//! The pool registers a close callback on every connection it opens,
+//! see [`Connect::register_close_callback()`], and drops the
+//! connections reported closed instead of handing them out again.
+//! Idle connections are also checked with [`Connect::is_alive()`], and
+//! with the [`health_check()`] of the pool if any, before being reused.
+//! A new connection is opened in place of a dead one.
+//!
+//! [`health_check()`]: ConnectPool::health_check
+
+use std::ops::Deref;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use crate::connect::Connect;
+use crate::error::Error;
+
+// A connection opened by the pool, along with the flag set by its
+// close callback.
+#[derive(Debug)]
+struct Slot {
+    conn: Connect,
+    closed: Arc<AtomicBool>,
+}
+
+impl Slot {
+    fn is_closed(&self) -> bool {
+        self.closed.load(Ordering::SeqCst)
+    }
+
+    fn close(mut self) {
+        let _ = self.conn.unregister_close_callback();
+        let _ = self.conn.close();
+    }
+}
+
+#[derive(Debug, Default)]
+struct PoolState {
+    idle: Vec<Slot>,
+    in_use: usize,
+}
+
+/// A bounded pool of connections to the same URI.
+#[derive(Debug)]
+pub struct ConnectPool {
+    uri: Option<String>,
+    size: usize,
+    read_only: bool,
+    keep_alive: Option<(i32, u32)>,
+    health_check: Option<fn(&Connect) -> bool>,
+    state: Mutex<PoolState>,
+    released: Condvar,
+}
+
+impl ConnectPool {
+    /// Creates a pool holding at most `size` connections to `uri`.
+    ///
+    /// Connections are opened lazily, on first use.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is zero.
+    pub fn new(uri: Option<&str>, size: usize) -> ConnectPool {
+        assert!(size > 0, "a connection pool needs at least one slot");
+        ConnectPool {
+            uri: uri.map(String::from),
+            size,
+            read_only: false,
+            keep_alive: None,
+            health_check: None,
+            state: Mutex::new(PoolState::default()),
+            released: Condvar::new(),
+        }
+    }
+
+    /// Opens connections with [`Connect::open_read_only()`].
+    pub fn read_only(mut self, read_only: bool) -> ConnectPool {
+        self.read_only = read_only;
+        self
+    }
+
+    /// Enables keepalive messages on every connection opened by the
+    /// pool, see [`Connect::set_keep_alive()`].
+    ///
+    /// Keepalive requires an event loop implementation to be registered
+    /// and run by the application.
+    pub fn keep_alive(mut self, interval: i32, count: u32) -> ConnectPool {
+        self.keep_alive = Some((interval, count));
+        self
+    }
+
+    /// Sets a check run on idle connections before handing them out
+    /// again, for instance a cheap call to the daemon. The connections
+    /// failing the check are closed and replaced by new ones.
+    pub fn health_check(mut self, check: fn(&Connect) -> bool) -> ConnectPool {
+        self.health_check = Some(check);
+        self
+    }
+
+    /// Returns the maximum number of connections of the pool.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Returns the number of connections currently handed out.
+    pub fn in_use(&self) -> usize {
+        self.lock().in_use
+    }
+
+    /// Takes a connection from the pool, blocking until one is
+    /// available.
+    pub fn get(&self) -> Result<PooledConnect<'_>, Error> {
+        let mut state = self.lock();
+        loop {
+            if let Some(conn) = self.reserve(&mut state) {
+                drop(state);
+                return self.checkout(conn);
+            }
+            state = self.released.wait(state).unwrap_or_else(|e| e.into_inner());
+        }
+    }
+
+    /// Takes a connection from the pool, waiting at most `timeout` for
+    /// one to become available.
+    pub fn get_timeout(&self, timeout: Duration) -> Result<PooledConnect<'_>, Error> {
+        let deadline = Instant::now() + timeout;
+        let mut state = self.lock();
+        loop {
+            if let Some(conn) = self.reserve(&mut state) {
+                drop(state);
+                return self.checkout(conn);
+            }
+            let now = Instant::now();
+            if now >= deadline {
+                return Err(Error::from_timeout(format!(
+                    "no connection available in the pool after {:?}",
+                    timeout
+                )));
+            }
+            state = self
+                .released
+                .wait_timeout(state, deadline - now)
+                .unwrap_or_else(|e| e.into_inner())
+                .0;
+        }
+    }
+
+    /// Takes a connection from the pool if one is available without
+    /// waiting.
+    pub fn try_get(&self) -> Option<Result<PooledConnect<'_>, Error>> {
+        let mut state = self.lock();
+        let conn = self.reserve(&mut state)?;
+        drop(state);
+        Some(self.checkout(conn))
+    }
+
+    fn lock(&self) -> MutexGuard<'_, PoolState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    // Reserves a slot. The outer option is None when the pool is
+    // exhausted, the inner one is None when a new connection must be
+    // opened for the slot.
+    fn reserve(&self, state: &mut PoolState) -> Option<Option<Slot>> {
+        if let Some(slot) = state.idle.pop() {
+            state.in_use += 1;
+            return Some(Some(slot));
+        }
+        if state.in_use < self.size {
+            state.in_use += 1;
+            return Some(None);
+        }
+        None
+    }
+
+    fn checkout(&self, slot: Option<Slot>) -> Result<PooledConnect<'_>, Error> {
+        let slot = match slot {
+            Some(slot) if self.is_usable(&slot) => Ok(slot),
+            Some(slot) => {
+                slot.close();
+                self.connect()
+            }
+            None => self.connect(),
+        };
+        match slot {
+            Ok(slot) => Ok(PooledConnect {
+                pool: self,
+                slot: Some(slot),
+            }),
+            Err(e) => {
+                self.release(None);
+                Err(e)
+            }
+        }
+    }
+
+    fn is_usable(&self, slot: &Slot) -> bool {
+        if slot.is_closed() || !slot.conn.is_alive().unwrap_or(false) {
+            return false;
+        }
+        match self.health_check {
+            Some(check) => check(&slot.conn),
+            None => true,
+        }
+    }
+
+    fn connect(&self) -> Result<Slot, Error> {
+        let mut conn = if self.read_only {
+            Connect::open_read_only(self.uri.as_deref())?
+        } else {
+            Connect::open(self.uri.as_deref())?
+        };
+        if let Some((interval, count)) = self.keep_alive {
+            if let Err(e) = conn.set_keep_alive(interval, count) {
+                let _ = conn.close();
+                return Err(e);
+            }
+        }
+        let closed = Arc::new(AtomicBool::new(false));
+        let flag = Arc::clone(&closed);
+        if let Err(e) = conn.register_close_callback(move |_| flag.store(true, Ordering::SeqCst)) {
+            let _ = conn.close();
+            return Err(e);
+        }
+        Ok(Slot { conn, closed })
+    }
+
+    fn release(&self, slot: Option<Slot>) {
+        let mut state = self.lock();
+        state.in_use -= 1;
+        if let Some(slot) = slot {
+            state.idle.push(slot);
+        }
+        drop(state);
+        self.released.notify_one();
+    }
+}
+
+impl Drop for ConnectPool {
+    fn drop(&mut self) {
+        for slot in self.lock().idle.drain(..) {
+            slot.close();
+        }
+    }
+}
+
+/// A connection borrowed from a [`ConnectPool`].
+///
+/// The connection goes back to the pool when the guard is dropped.
+#[derive(Debug)]
+pub struct PooledConnect<'a> {
+    pool: &'a ConnectPool,
+    slot: Option<Slot>,
+}
+
+impl PooledConnect<'_> {
+    /// Closes the connection instead of returning it to the pool, for
+    /// instance after an error which left it in an unknown state. The
+    /// pool opens a new connection for the slot when needed.
+    pub fn discard(mut self) {
+        if let Some(slot) = self.slot.take() {
+            slot.close();
+        }
+        self.pool.release(None);
+    }
+}
+
+impl Deref for PooledConnect<'_> {
+    type Target = Connect;
+
+    fn deref(&self) -> &Connect {
+        &self.slot.as_ref().unwrap().conn
+    }
+}
+
+impl Drop for PooledConnect<'_> {
+    fn drop(&mut self) {
+        if let Some(slot) = self.slot.take() {
+            if slot.is_closed() {
+                slot.close();
+                self.pool.release(None);
+            } else {
+                self.pool.release(Some(slot));
+            }
+        }
+    }
+}
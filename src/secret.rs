@@ -21,7 +21,41 @@ use std::ffi::CString;
 use uuid::Uuid;
 
 use crate::connect::Connect;
-use crate::error::Error;
+use crate::error::{report_drop_error, Error};
+use crate::util::{impl_enum, impl_identity};
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+/// The kind of object a secret is associated with.
+///
+/// See <https://libvirt.org/html/libvirt-libvirt-secret.html#virSecretUsageType>
+pub enum SecretUsageType {
+    /// The secret is not associated with any object.
+    None,
+    /// The secret is used to decrypt a storage volume.
+    Volume,
+    /// The secret is used to authenticate to a Ceph cluster.
+    Ceph,
+    /// The secret is used to authenticate to an iSCSI target.
+    Iscsi,
+    /// The secret protects the private key of a TLS certificate.
+    Tls,
+    /// The secret is used to encrypt the state of a virtual TPM.
+    Vtpm,
+}
+
+impl_enum! {
+    enum: SecretUsageType,
+    raw: sys::virSecretUsageType,
+    match: {
+        sys::VIR_SECRET_USAGE_TYPE_NONE => SecretUsageType::None,
+        sys::VIR_SECRET_USAGE_TYPE_VOLUME => SecretUsageType::Volume,
+        sys::VIR_SECRET_USAGE_TYPE_CEPH => SecretUsageType::Ceph,
+        sys::VIR_SECRET_USAGE_TYPE_ISCSI => SecretUsageType::Iscsi,
+        sys::VIR_SECRET_USAGE_TYPE_TLS => SecretUsageType::Tls,
+        sys::VIR_SECRET_USAGE_TYPE_VTPM => SecretUsageType::Vtpm,
+        _ => SecretUsageType::None,
+    }
+}
 
 /// Provides APIs for the management of secrets.
 ///
@@ -29,6 +63,7 @@ use crate::error::Error;
 #[derive(Debug)]
 pub struct Secret {
     ptr: Option<sys::virSecretPtr>,
+    identity: Option<Uuid>,
 }
 
 unsafe impl Send for Secret {}
@@ -38,7 +73,7 @@ impl Drop for Secret {
     fn drop(&mut self) {
         if self.ptr.is_some() {
             if let Err(e) = self.free() {
-                panic!("Unable to drop memory for Secret: {}", e)
+                report_drop_error("Secret", e);
             }
         }
     }
@@ -57,17 +92,56 @@ impl Clone for Secret {
     }
 }
 
+impl_identity!(Secret, get_uuid);
+
+/// A registration for the value change events of a secret, see
+/// [`Secret::watch_value_changes()`].
+///
+/// The callback is deregistered when the guard is dropped.
+#[derive(Debug)]
+pub struct SecretWatch {
+    conn: Connect,
+    callback_id: i32,
+}
+
+impl SecretWatch {
+    /// Deregisters the callback, reporting any error.
+    pub fn unwatch(mut self) -> Result<(), Error> {
+        self.release()
+    }
+
+    fn release(&mut self) -> Result<(), Error> {
+        if self.callback_id < 0 {
+            return Ok(());
+        }
+        let id = std::mem::replace(&mut self.callback_id, -1);
+        let ret = self.conn.secret_event_deregister_any(id);
+        self.conn.close()?;
+        ret
+    }
+}
+
+impl Drop for SecretWatch {
+    fn drop(&mut self) {
+        let _ = self.release();
+    }
+}
+
 impl Secret {
     /// # Safety
     ///
     /// The caller must ensure that the pointer is valid.
     pub unsafe fn from_ptr(ptr: sys::virSecretPtr) -> Secret {
-        Secret { ptr: Some(ptr) }
+        Secret {
+            ptr: Some(ptr),
+            identity: Default::default(),
+        }
+        .load_identity()
     }
 
     fn add_ref(&self) -> Result<Secret, Error> {
         unsafe {
-            if sys::virSecretRef(self.as_ptr()) == -1 {
+            if ffi!(sys::virSecretRef(self.as_ptr())) == -1 {
                 return Err(Error::last_error());
             }
         }
@@ -80,17 +154,44 @@ impl Secret {
     }
 
     pub fn get_connect(&self) -> Result<Connect, Error> {
-        let ptr = unsafe { sys::virSecretGetConnect(self.as_ptr()) };
+        let ptr = unsafe { ffi!(sys::virSecretGetConnect(self.as_ptr())) };
         if ptr.is_null() {
             return Err(Error::last_error());
         }
         Ok(unsafe { Connect::from_ptr(ptr) })
     }
 
+    /// Calls `cb` each time the value of this secret changes, for
+    /// instance to follow the rotation of a TLS or Ceph key.
+    ///
+    /// An event loop implementation must be registered, for instance
+    /// with [`sys::virEventRegisterDefaultImpl()`], and run for the
+    /// events to be delivered.
+    pub fn watch_value_changes<F>(&self, cb: F) -> Result<SecretWatch, Error>
+    where
+        F: 'static + FnMut(&Secret) + Send,
+    {
+        // get_connect() does not take a reference on the connection,
+        // the clone keeps it open for as long as the watch lives.
+        let conn = self.get_connect()?.clone();
+        match conn.secret_event_value_changed_register(Some(self), cb) {
+            Ok(callback_id) => Ok(SecretWatch { conn, callback_id }),
+            Err(e) => {
+                let mut conn = conn;
+                let _ = conn.close();
+                Err(e)
+            }
+        }
+    }
+
     pub fn define_xml(conn: &Connect, xml: &str, flags: u32) -> Result<Secret, Error> {
-        let xml_buf = CString::new(xml).unwrap();
+        let xml_buf = CString::new(xml)?;
         let ptr = unsafe {
-            sys::virSecretDefineXML(conn.as_ptr(), xml_buf.as_ptr(), flags as libc::c_uint)
+            ffi!(sys::virSecretDefineXML(
+                conn.as_ptr(),
+                xml_buf.as_ptr(),
+                flags as libc::c_uint
+            ))
         };
         if ptr.is_null() {
             return Err(Error::last_error());
@@ -99,7 +200,12 @@ impl Secret {
     }
 
     pub fn lookup_by_uuid(conn: &Connect, uuid: Uuid) -> Result<Secret, Error> {
-        let ptr = unsafe { sys::virSecretLookupByUUID(conn.as_ptr(), uuid.as_bytes().as_ptr()) };
+        let ptr = unsafe {
+            ffi!(sys::virSecretLookupByUUID(
+                conn.as_ptr(),
+                uuid.as_bytes().as_ptr()
+            ))
+        };
         if ptr.is_null() {
             return Err(Error::last_error());
         }
@@ -107,22 +213,35 @@ impl Secret {
     }
 
     pub fn lookup_by_uuid_string(conn: &Connect, uuid: &str) -> Result<Secret, Error> {
-        let uuid_buf = CString::new(uuid).unwrap();
-        let ptr = unsafe { sys::virSecretLookupByUUIDString(conn.as_ptr(), uuid_buf.as_ptr()) };
+        let uuid_buf = CString::new(uuid)?;
+        let ptr = unsafe {
+            ffi!(sys::virSecretLookupByUUIDString(
+                conn.as_ptr(),
+                uuid_buf.as_ptr()
+            ))
+        };
         if ptr.is_null() {
             return Err(Error::last_error());
         }
         Ok(unsafe { Secret::from_ptr(ptr) })
     }
 
-    pub fn lookup_by_usage(conn: &Connect, usagetype: i32, usageid: &str) -> Result<Secret, Error> {
-        let usageid_buf = CString::new(usageid).unwrap();
+    /// Look up a secret by the object it is associated with.
+    ///
+    /// `usage_id` identifies the object, for instance the path of a
+    /// volume or the name of a Ceph user.
+    pub fn lookup_by_usage(
+        conn: &Connect,
+        usage_type: SecretUsageType,
+        usage_id: &str,
+    ) -> Result<Secret, Error> {
+        let usageid_buf = CString::new(usage_id)?;
         let ptr = unsafe {
-            sys::virSecretLookupByUsage(
+            ffi!(sys::virSecretLookupByUsage(
                 conn.as_ptr(),
-                usagetype as libc::c_int,
+                usage_type.to_raw() as libc::c_int,
                 usageid_buf.as_ptr(),
-            )
+            ))
         };
         if ptr.is_null() {
             return Err(Error::last_error());
@@ -130,26 +249,51 @@ impl Secret {
         Ok(unsafe { Secret::from_ptr(ptr) })
     }
 
+    /// Look up a secret by UUID if `identifier` parses as one, then
+    /// by the usage ID of each [`SecretUsageType`] in turn.
+    ///
+    /// The error of the last lookup is returned when none matches.
+    pub fn lookup(conn: &Connect, identifier: &str) -> Result<Secret, Error> {
+        if let Ok(uuid) = Uuid::parse_str(identifier) {
+            if let Ok(secret) = Secret::lookup_by_uuid(conn, uuid) {
+                return Ok(secret);
+            }
+        }
+        let mut result = Secret::lookup_by_usage(conn, SecretUsageType::Volume, identifier);
+        for usage_type in [
+            SecretUsageType::Ceph,
+            SecretUsageType::Iscsi,
+            SecretUsageType::Tls,
+            SecretUsageType::Vtpm,
+        ] {
+            if result.is_ok() {
+                break;
+            }
+            result = Secret::lookup_by_usage(conn, usage_type, identifier);
+        }
+        result
+    }
+
     pub fn get_usage_id(&self) -> Result<String, Error> {
-        let n = unsafe { sys::virSecretGetUsageID(self.as_ptr()) };
+        let n = unsafe { ffi!(sys::virSecretGetUsageID(self.as_ptr())) };
         if n.is_null() {
             return Err(Error::last_error());
         }
         Ok(unsafe { c_chars_to_string!(n) })
     }
 
-    pub fn get_usage_type(&self) -> Result<u32, Error> {
-        let t = unsafe { sys::virSecretGetUsageType(self.as_ptr()) };
+    pub fn get_usage_type(&self) -> Result<SecretUsageType, Error> {
+        let t = unsafe { ffi!(sys::virSecretGetUsageType(self.as_ptr())) };
         if t == -1 {
             return Err(Error::last_error());
         }
-        Ok(t as u32)
+        Ok(SecretUsageType::from_raw(t as sys::virSecretUsageType))
     }
 
     pub fn get_uuid(&self) -> Result<Uuid, Error> {
         let mut uuid: [libc::c_uchar; sys::VIR_UUID_BUFLEN as usize] =
             [0; sys::VIR_UUID_BUFLEN as usize];
-        let ret = unsafe { sys::virSecretGetUUID(self.as_ptr(), uuid.as_mut_ptr()) };
+        let ret = unsafe { ffi!(sys::virSecretGetUUID(self.as_ptr(), uuid.as_mut_ptr())) };
         if ret == -1 {
             return Err(Error::last_error());
         }
@@ -159,7 +303,12 @@ impl Secret {
     pub fn get_uuid_string(&self) -> Result<String, Error> {
         let mut uuid: [libc::c_char; sys::VIR_UUID_STRING_BUFLEN as usize] =
             [0; sys::VIR_UUID_STRING_BUFLEN as usize];
-        let ret = unsafe { sys::virSecretGetUUIDString(self.as_ptr(), uuid.as_mut_ptr()) };
+        let ret = unsafe {
+            ffi!(sys::virSecretGetUUIDString(
+                self.as_ptr(),
+                uuid.as_mut_ptr()
+            ))
+        };
         if ret == -1 {
             return Err(Error::last_error());
         }
@@ -167,7 +316,7 @@ impl Secret {
     }
 
     pub fn get_xml_desc(&self, flags: u32) -> Result<String, Error> {
-        let xml = unsafe { sys::virSecretGetXMLDesc(self.as_ptr(), flags) };
+        let xml = unsafe { ffi!(sys::virSecretGetXMLDesc(self.as_ptr(), flags)) };
         if xml.is_null() {
             return Err(Error::last_error());
         }
@@ -175,8 +324,14 @@ impl Secret {
     }
 
     pub fn set_value(&self, value: &[u8], flags: u32) -> Result<(), Error> {
-        let ret =
-            unsafe { sys::virSecretSetValue(self.as_ptr(), value.as_ptr(), value.len(), flags) };
+        let ret = unsafe {
+            ffi!(sys::virSecretSetValue(
+                self.as_ptr(),
+                value.as_ptr(),
+                value.len(),
+                flags
+            ))
+        };
         if ret == -1 {
             return Err(Error::last_error());
         }
@@ -185,7 +340,13 @@ impl Secret {
 
     pub fn get_value(&self, flags: u32) -> Result<Vec<u8>, Error> {
         let mut size: usize = 0;
-        let n = unsafe { sys::virSecretGetValue(self.as_ptr(), &mut size, flags as libc::c_uint) };
+        let n = unsafe {
+            ffi!(sys::virSecretGetValue(
+                self.as_ptr(),
+                &mut size,
+                flags as libc::c_uint
+            ))
+        };
         if n.is_null() {
             return Err(Error::last_error());
         }
@@ -198,7 +359,7 @@ impl Secret {
     }
 
     pub fn undefine(&self) -> Result<(), Error> {
-        let ret = unsafe { sys::virSecretUndefine(self.as_ptr()) };
+        let ret = unsafe { ffi!(sys::virSecretUndefine(self.as_ptr())) };
         if ret == -1 {
             return Err(Error::last_error());
         }
@@ -206,7 +367,7 @@ impl Secret {
     }
 
     pub fn free(&mut self) -> Result<(), Error> {
-        let ret = unsafe { sys::virSecretFree(self.as_ptr()) };
+        let ret = unsafe { ffi!(sys::virSecretFree(self.as_ptr())) };
         if ret == -1 {
             return Err(Error::last_error());
         }
@@ -17,15 +17,43 @@
  */
 
 use std::ffi::CString;
-use std::{mem, ptr, str};
+use std::{fmt, mem, ptr, str};
 
 use uuid::Uuid;
+use xml::escape::{escape_str_attribute, escape_str_pcdata};
 
 use crate::connect::Connect;
-use crate::error::Error;
+use crate::error::{report_drop_error, Error};
 use crate::storage_vol::StorageVol;
+use crate::util::{impl_flags, impl_identity, list_names};
+use crate::xmlutil::Element;
+
+impl_flags! {
+    /// Flags used when starting a storage pool.
+    ///
+    /// See <https://libvirt.org/html/libvirt-libvirt-storage.html#virStoragePoolCreateFlags>
+    struct StoragePoolCreateFlags: sys::virStoragePoolCreateFlags {
+        /// Build the pool before starting it.
+        const BUILD = sys::VIR_STORAGE_POOL_CREATE_WITH_BUILD;
+        /// Build the pool, overwriting any existing data.
+        const BUILD_OVERWRITE = sys::VIR_STORAGE_POOL_CREATE_WITH_BUILD_OVERWRITE;
+        /// Build the pool, failing if it would overwrite existing data.
+        const BUILD_NO_OVERWRITE = sys::VIR_STORAGE_POOL_CREATE_WITH_BUILD_NO_OVERWRITE;
+    }
+}
+
+impl_flags! {
+    /// Flags used when dumping the XML of a storage pool.
+    ///
+    /// See <https://libvirt.org/html/libvirt-libvirt-storage.html#virStorageXMLFlags>
+    struct StorageXmlFlags: sys::virStorageXMLFlags {
+        /// Dump the inactive configuration.
+        const INACTIVE = sys::VIR_STORAGE_XML_INACTIVE;
+    }
+}
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct StoragePoolInfo {
     /// A `StoragePoolState` flags
     pub state: u32,
@@ -37,6 +65,16 @@ pub struct StoragePoolInfo {
     pub available: u64,
 }
 
+impl fmt::Display for StoragePoolInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "state: {}, capacity: {} B, allocation: {} B, available: {} B",
+            self.state, self.capacity, self.allocation, self.available
+        )
+    }
+}
+
 impl StoragePoolInfo {
     /// # Safety
     ///
@@ -51,12 +89,335 @@ impl StoragePoolInfo {
     }
 }
 
+/// Capabilities of a storage pool type, as reported by
+/// [`Connect::get_storage_pool_capabilities()`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct StoragePoolTypeCapabilities {
+    /// The pool type, e.g. "dir", "logical" or "rbd".
+    pub pool_type: String,
+    /// Whether the driver supports this pool type.
+    pub supported: bool,
+    /// The default format of the pool source, if any.
+    pub default_pool_format: Option<String>,
+    /// The supported formats of the pool source.
+    pub pool_formats: Vec<String>,
+    /// The default format of the volumes, if any.
+    pub default_volume_format: Option<String>,
+    /// The supported formats of the volumes.
+    pub volume_formats: Vec<String>,
+}
+
+/// Storage pool capabilities of a connection.
+///
+/// See <https://libvirt.org/formatstoragecaps.html>
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct StoragePoolCapabilities {
+    pub pools: Vec<StoragePoolTypeCapabilities>,
+}
+
+impl StoragePoolCapabilities {
+    /// Parses the XML returned by
+    /// [`Connect::get_storage_pool_capabilities()`].
+    pub fn from_xml(xml: &str) -> Result<StoragePoolCapabilities, Error> {
+        fn formats(options: Option<&Element>) -> (Option<String>, Vec<String>) {
+            match options {
+                Some(options) => (
+                    options
+                        .child("defaultFormat")
+                        .and_then(|f| f.attr("type"))
+                        .map(String::from),
+                    options
+                        .children("enum")
+                        .flat_map(|e| e.children("value"))
+                        .map(|v| v.text().to_string())
+                        .collect(),
+                ),
+                None => (None, Vec::new()),
+            }
+        }
+
+        let root = Element::parse(xml)?;
+        let pools = root
+            .children("pool")
+            .map(|pool| {
+                let (default_pool_format, pool_formats) = formats(pool.child("poolOptions"));
+                let (default_volume_format, volume_formats) = formats(pool.child("volOptions"));
+                StoragePoolTypeCapabilities {
+                    pool_type: pool.attr("type").unwrap_or_default().to_string(),
+                    supported: pool.attr("supported") == Some("yes"),
+                    default_pool_format,
+                    pool_formats,
+                    default_volume_format,
+                    volume_formats,
+                }
+            })
+            .collect();
+        Ok(StoragePoolCapabilities { pools })
+    }
+
+    /// Returns the capabilities of the pool type `pool_type`.
+    pub fn pool(&self, pool_type: &str) -> Option<&StoragePoolTypeCapabilities> {
+        self.pools.iter().find(|p| p.pool_type == pool_type)
+    }
+
+    /// Returns whether the pool type `pool_type` is supported.
+    #[allow(unknown_lints, clippy::unnecessary_map_or)]
+    pub fn is_supported(&self, pool_type: &str) -> bool {
+        self.pool(pool_type).map_or(false, |p| p.supported)
+    }
+}
+
+impl_flags! {
+    /// Flags used when building a storage pool.
+    ///
+    /// See <https://libvirt.org/html/libvirt-libvirt-storage.html#virStoragePoolBuildFlags>
+    struct StoragePoolBuildFlags: sys::virStoragePoolBuildFlags {
+        /// Build a new pool; this is the default.
+        const NEW = sys::VIR_STORAGE_POOL_BUILD_NEW;
+        /// Repair the pool.
+        const REPAIR = sys::VIR_STORAGE_POOL_BUILD_REPAIR;
+        /// Extend the pool.
+        const RESIZE = sys::VIR_STORAGE_POOL_BUILD_RESIZE;
+        /// Fail if the build would overwrite existing data.
+        const NO_OVERWRITE = sys::VIR_STORAGE_POOL_BUILD_NO_OVERWRITE;
+        /// Overwrite any existing data.
+        const OVERWRITE = sys::VIR_STORAGE_POOL_BUILD_OVERWRITE;
+    }
+}
+
+impl_flags! {
+    /// Flags used when defining a storage pool.
+    ///
+    /// See <https://libvirt.org/html/libvirt-libvirt-storage.html#virStoragePoolDefineFlags>
+    struct StoragePoolDefineFlags: sys::virStoragePoolDefineFlags {
+        /// Validate the XML document against the schema.
+        const VALIDATE = sys::VIR_STORAGE_POOL_DEFINE_VALIDATE;
+    }
+}
+
+/// The source of a storage pool described by a [`StoragePoolDefinition`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum StoragePoolSource {
+    /// A directory on the host.
+    Dir,
+    /// A block device holding a file system, mounted on the target.
+    Fs {
+        device: String,
+        /// The file system type, e.g. `ext4`; probed when `None`.
+        format: Option<String>,
+    },
+    /// An LVM volume group.
+    Logical {
+        /// The name of the volume group.
+        name: String,
+        /// The physical volumes, needed to build a new group.
+        devices: Vec<String>,
+    },
+    /// A directory exported by a network file server, mounted on
+    /// the target.
+    Netfs {
+        host: String,
+        dir: String,
+        /// The protocol, e.g. `nfs` or `cifs`; probed when `None`.
+        format: Option<String>,
+    },
+    /// A Ceph RBD pool.
+    Rbd {
+        /// The Ceph monitors.
+        hosts: Vec<String>,
+        /// The name of the RBD pool.
+        name: String,
+        /// The Ceph user and the UUID of the secret holding its key.
+        auth: Option<(String, Uuid)>,
+    },
+}
+
+/// A storage pool description, turned into the XML expected by
+/// [`StoragePool::define_xml()`] or [`StoragePool::create_xml()`].
+///
+/// See <https://libvirt.org/formatstorage.html>
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StoragePoolDefinition {
+    pub name: String,
+    pub source: StoragePoolSource,
+    /// Where the pool is exposed on the host; not used by `rbd` pools.
+    pub target_path: Option<String>,
+}
+
+impl StoragePoolDefinition {
+    /// Describes a pool of files in the directory `path`.
+    pub fn dir(name: &str, path: &str) -> StoragePoolDefinition {
+        StoragePoolDefinition {
+            name: name.to_string(),
+            source: StoragePoolSource::Dir,
+            target_path: Some(path.to_string()),
+        }
+    }
+
+    /// Describes a pool of files on the file system of `device`,
+    /// mounted on `path`.
+    pub fn fs(name: &str, device: &str, path: &str) -> StoragePoolDefinition {
+        StoragePoolDefinition {
+            name: name.to_string(),
+            source: StoragePoolSource::Fs {
+                device: device.to_string(),
+                format: None,
+            },
+            target_path: Some(path.to_string()),
+        }
+    }
+
+    /// Describes a pool of the logical volumes of the volume group
+    /// `vg`, made of `devices`.
+    pub fn logical(name: &str, vg: &str, devices: &[&str]) -> StoragePoolDefinition {
+        StoragePoolDefinition {
+            name: name.to_string(),
+            source: StoragePoolSource::Logical {
+                name: vg.to_string(),
+                devices: devices.iter().map(|d| d.to_string()).collect(),
+            },
+            target_path: Some(format!("/dev/{}", vg)),
+        }
+    }
+
+    /// Describes a pool of files in the NFS export `host:dir`,
+    /// mounted on `path`.
+    pub fn nfs(name: &str, host: &str, dir: &str, path: &str) -> StoragePoolDefinition {
+        StoragePoolDefinition {
+            name: name.to_string(),
+            source: StoragePoolSource::Netfs {
+                host: host.to_string(),
+                dir: dir.to_string(),
+                format: Some("nfs".to_string()),
+            },
+            target_path: Some(path.to_string()),
+        }
+    }
+
+    /// Describes a pool of the images of the RBD pool `pool`,
+    /// reachable through the Ceph monitors `hosts`.
+    pub fn rbd(name: &str, hosts: &[&str], pool: &str) -> StoragePoolDefinition {
+        StoragePoolDefinition {
+            name: name.to_string(),
+            source: StoragePoolSource::Rbd {
+                hosts: hosts.iter().map(|h| h.to_string()).collect(),
+                name: pool.to_string(),
+                auth: None,
+            },
+            target_path: None,
+        }
+    }
+
+    /// Returns the pool type, as used in the XML and by
+    /// [`StoragePoolCapabilities::is_supported()`].
+    pub fn pool_type(&self) -> &'static str {
+        match self.source {
+            StoragePoolSource::Dir => "dir",
+            StoragePoolSource::Fs { .. } => "fs",
+            StoragePoolSource::Logical { .. } => "logical",
+            StoragePoolSource::Netfs { .. } => "netfs",
+            StoragePoolSource::Rbd { .. } => "rbd",
+        }
+    }
+
+    /// Returns whether the pool type is supported according to the
+    /// capabilities of a connection.
+    pub fn is_supported_by(&self, caps: &StoragePoolCapabilities) -> bool {
+        caps.is_supported(self.pool_type())
+    }
+
+    /// Returns the XML description of the pool.
+    pub fn to_xml(&self) -> String {
+        fn format(xml: &mut String, format: &Option<String>) {
+            if let Some(format) = format {
+                xml.push_str(&format!(
+                    "    <format type='{}'/>\n",
+                    escape_str_attribute(format)
+                ));
+            }
+        }
+
+        let mut xml = format!(
+            "<pool type='{}'>\n  <name>{}</name>\n",
+            self.pool_type(),
+            escape_str_pcdata(&self.name)
+        );
+        match &self.source {
+            StoragePoolSource::Dir => {}
+            StoragePoolSource::Fs { device, format: f } => {
+                xml.push_str("  <source>\n");
+                xml.push_str(&format!(
+                    "    <device path='{}'/>\n",
+                    escape_str_attribute(device)
+                ));
+                format(&mut xml, f);
+                xml.push_str("  </source>\n");
+            }
+            StoragePoolSource::Logical { name, devices } => {
+                xml.push_str("  <source>\n");
+                xml.push_str(&format!("    <name>{}</name>\n", escape_str_pcdata(name)));
+                for device in devices {
+                    xml.push_str(&format!(
+                        "    <device path='{}'/>\n",
+                        escape_str_attribute(device)
+                    ));
+                }
+                xml.push_str("  </source>\n");
+            }
+            StoragePoolSource::Netfs {
+                host,
+                dir,
+                format: f,
+            } => {
+                xml.push_str("  <source>\n");
+                xml.push_str(&format!(
+                    "    <host name='{}'/>\n",
+                    escape_str_attribute(host)
+                ));
+                xml.push_str(&format!(
+                    "    <dir path='{}'/>\n",
+                    escape_str_attribute(dir)
+                ));
+                format(&mut xml, f);
+                xml.push_str("  </source>\n");
+            }
+            StoragePoolSource::Rbd { hosts, name, auth } => {
+                xml.push_str("  <source>\n");
+                xml.push_str(&format!("    <name>{}</name>\n", escape_str_pcdata(name)));
+                for host in hosts {
+                    xml.push_str(&format!(
+                        "    <host name='{}'/>\n",
+                        escape_str_attribute(host)
+                    ));
+                }
+                if let Some((username, secret)) = auth {
+                    xml.push_str(&format!(
+                        "    <auth type='ceph' username='{}'>\n      <secret uuid='{}'/>\n    </auth>\n",
+                        escape_str_attribute(username),
+                        secret
+                    ));
+                }
+                xml.push_str("  </source>\n");
+            }
+        }
+        if let Some(path) = &self.target_path {
+            xml.push_str(&format!(
+                "  <target>\n    <path>{}</path>\n  </target>\n",
+                escape_str_pcdata(path)
+            ));
+        }
+        xml.push_str("</pool>\n");
+        xml
+    }
+}
+
 /// Provides APIs for the management of storage pools.
 ///
 /// See <https://libvirt.org/html/libvirt-libvirt-storage.html>
 #[derive(Debug)]
 pub struct StoragePool {
     ptr: Option<sys::virStoragePoolPtr>,
+    identity: Option<Uuid>,
 }
 
 unsafe impl Send for StoragePool {}
@@ -66,7 +427,7 @@ impl Drop for StoragePool {
     fn drop(&mut self) {
         if self.ptr.is_some() {
             if let Err(e) = self.free() {
-                panic!("Unable to drop memory for StoragePool: {}", e)
+                report_drop_error("StoragePool", e);
             }
         }
     }
@@ -85,17 +446,23 @@ impl Clone for StoragePool {
     }
 }
 
+impl_identity!(StoragePool, get_uuid);
+
 impl StoragePool {
     /// # Safety
     ///
     /// The caller must ensure that the pointer is valid.
     pub unsafe fn from_ptr(ptr: sys::virStoragePoolPtr) -> StoragePool {
-        StoragePool { ptr: Some(ptr) }
+        StoragePool {
+            ptr: Some(ptr),
+            identity: Default::default(),
+        }
+        .load_identity()
     }
 
     fn add_ref(&self) -> Result<StoragePool, Error> {
         unsafe {
-            if sys::virStoragePoolRef(self.as_ptr()) == -1 {
+            if ffi!(sys::virStoragePoolRef(self.as_ptr())) == -1 {
                 return Err(Error::last_error());
             }
         }
@@ -108,17 +475,25 @@ impl StoragePool {
     }
 
     pub fn get_connect(&self) -> Result<Connect, Error> {
-        let ptr = unsafe { sys::virStoragePoolGetConnect(self.as_ptr()) };
+        let ptr = unsafe { ffi!(sys::virStoragePoolGetConnect(self.as_ptr())) };
         if ptr.is_null() {
             return Err(Error::last_error());
         }
         Ok(unsafe { Connect::from_ptr(ptr) })
     }
 
-    pub fn define_xml(conn: &Connect, xml: &str, flags: u32) -> Result<StoragePool, Error> {
-        let xml_buf = CString::new(xml).unwrap();
+    pub fn define_xml(
+        conn: &Connect,
+        xml: &str,
+        flags: impl Into<StoragePoolDefineFlags>,
+    ) -> Result<StoragePool, Error> {
+        let xml_buf = CString::new(xml)?;
         let ptr = unsafe {
-            sys::virStoragePoolDefineXML(conn.as_ptr(), xml_buf.as_ptr(), flags as libc::c_uint)
+            ffi!(sys::virStoragePoolDefineXML(
+                conn.as_ptr(),
+                xml_buf.as_ptr(),
+                flags.into().bits() as libc::c_uint,
+            ))
         };
         if ptr.is_null() {
             return Err(Error::last_error());
@@ -126,14 +501,23 @@ impl StoragePool {
         Ok(unsafe { StoragePool::from_ptr(ptr) })
     }
 
+    /// Define a persistent storage pool from a [`StoragePoolDefinition`].
+    pub fn define(conn: &Connect, def: &StoragePoolDefinition) -> Result<StoragePool, Error> {
+        StoragePool::define_xml(conn, &def.to_xml(), StoragePoolDefineFlags::empty())
+    }
+
     pub fn create_xml(
         conn: &Connect,
         xml: &str,
-        flags: sys::virStoragePoolCreateFlags,
+        flags: impl Into<StoragePoolCreateFlags>,
     ) -> Result<StoragePool, Error> {
-        let xml_buf = CString::new(xml).unwrap();
+        let xml_buf = CString::new(xml)?;
         let ptr = unsafe {
-            sys::virStoragePoolCreateXML(conn.as_ptr(), xml_buf.as_ptr(), flags as libc::c_uint)
+            ffi!(sys::virStoragePoolCreateXML(
+                conn.as_ptr(),
+                xml_buf.as_ptr(),
+                flags.into().bits() as libc::c_uint,
+            ))
         };
         if ptr.is_null() {
             return Err(Error::last_error());
@@ -142,8 +526,13 @@ impl StoragePool {
     }
 
     pub fn lookup_by_name(conn: &Connect, id: &str) -> Result<StoragePool, Error> {
-        let id_buf = CString::new(id).unwrap();
-        let ptr = unsafe { sys::virStoragePoolLookupByName(conn.as_ptr(), id_buf.as_ptr()) };
+        let id_buf = CString::new(id)?;
+        let ptr = unsafe {
+            ffi!(sys::virStoragePoolLookupByName(
+                conn.as_ptr(),
+                id_buf.as_ptr()
+            ))
+        };
         if ptr.is_null() {
             return Err(Error::last_error());
         }
@@ -151,9 +540,13 @@ impl StoragePool {
     }
 
     pub fn lookup_by_target_path(conn: &Connect, path: &str) -> Result<StoragePool, Error> {
-        let path_buf = CString::new(path).unwrap();
-        let ptr =
-            unsafe { sys::virStoragePoolLookupByTargetPath(conn.as_ptr(), path_buf.as_ptr()) };
+        let path_buf = CString::new(path)?;
+        let ptr = unsafe {
+            ffi!(sys::virStoragePoolLookupByTargetPath(
+                conn.as_ptr(),
+                path_buf.as_ptr()
+            ))
+        };
         if ptr.is_null() {
             return Err(Error::last_error());
         }
@@ -161,7 +554,7 @@ impl StoragePool {
     }
 
     pub fn lookup_by_volume(vol: &StorageVol) -> Result<StoragePool, Error> {
-        let ptr = unsafe { sys::virStoragePoolLookupByVolume(vol.as_ptr()) };
+        let ptr = unsafe { ffi!(sys::virStoragePoolLookupByVolume(vol.as_ptr())) };
         if ptr.is_null() {
             return Err(Error::last_error());
         }
@@ -169,8 +562,12 @@ impl StoragePool {
     }
 
     pub fn lookup_by_uuid(conn: &Connect, uuid: Uuid) -> Result<StoragePool, Error> {
-        let ptr =
-            unsafe { sys::virStoragePoolLookupByUUID(conn.as_ptr(), uuid.as_bytes().as_ptr()) };
+        let ptr = unsafe {
+            ffi!(sys::virStoragePoolLookupByUUID(
+                conn.as_ptr(),
+                uuid.as_bytes().as_ptr()
+            ))
+        };
         if ptr.is_null() {
             return Err(Error::last_error());
         }
@@ -178,17 +575,34 @@ impl StoragePool {
     }
 
     pub fn lookup_by_uuid_string(conn: &Connect, uuid: &str) -> Result<StoragePool, Error> {
-        let uuid_buf = CString::new(uuid).unwrap();
-        let ptr =
-            unsafe { sys::virStoragePoolLookupByUUIDString(conn.as_ptr(), uuid_buf.as_ptr()) };
+        let uuid_buf = CString::new(uuid)?;
+        let ptr = unsafe {
+            ffi!(sys::virStoragePoolLookupByUUIDString(
+                conn.as_ptr(),
+                uuid_buf.as_ptr()
+            ))
+        };
         if ptr.is_null() {
             return Err(Error::last_error());
         }
         Ok(unsafe { StoragePool::from_ptr(ptr) })
     }
 
+    /// Look up a storage pool the way `virsh` does: by UUID if
+    /// `identifier` parses as one, then by name.
+    ///
+    /// The error of the last lookup is returned when none matches.
+    pub fn lookup(conn: &Connect, identifier: &str) -> Result<StoragePool, Error> {
+        if let Ok(uuid) = Uuid::parse_str(identifier) {
+            if let Ok(pool) = StoragePool::lookup_by_uuid(conn, uuid) {
+                return Ok(pool);
+            }
+        }
+        StoragePool::lookup_by_name(conn, identifier)
+    }
+
     pub fn get_name(&self) -> Result<String, Error> {
-        let n = unsafe { sys::virStoragePoolGetName(self.as_ptr()) };
+        let n = unsafe { ffi!(sys::virStoragePoolGetName(self.as_ptr())) };
         if n.is_null() {
             return Err(Error::last_error());
         }
@@ -196,33 +610,28 @@ impl StoragePool {
     }
 
     pub fn num_of_volumes(&self) -> Result<u32, Error> {
-        let ret = unsafe { sys::virStoragePoolNumOfVolumes(self.as_ptr()) };
+        let ret = unsafe { ffi!(sys::virStoragePoolNumOfVolumes(self.as_ptr())) };
         if ret == -1 {
             return Err(Error::last_error());
         }
         Ok(ret as u32)
     }
 
-    #[allow(clippy::needless_range_loop)]
     pub fn list_volumes(&self) -> Result<Vec<String>, Error> {
-        let mut names: [*mut libc::c_char; 1024] = [ptr::null_mut(); 1024];
-        let size =
-            unsafe { sys::virStoragePoolListVolumes(self.as_ptr(), names.as_mut_ptr(), 1024) };
-        if size == -1 {
-            return Err(Error::last_error());
-        }
-
-        let mut array: Vec<String> = Vec::new();
-        for x in 0..size as usize {
-            array.push(unsafe { c_chars_to_string!(names[x]) });
-        }
-        Ok(array)
+        list_names(
+            || unsafe { ffi!(sys::virStoragePoolNumOfVolumes(self.as_ptr())) },
+            |names, len| unsafe { ffi!(sys::virStoragePoolListVolumes(self.as_ptr(), names, len)) },
+        )
     }
 
     pub fn list_all_volumes(&self, flags: u32) -> Result<Vec<StorageVol>, Error> {
         let mut volumes: *mut sys::virStorageVolPtr = ptr::null_mut();
         let size = unsafe {
-            sys::virStoragePoolListAllVolumes(self.as_ptr(), &mut volumes, flags as libc::c_uint)
+            ffi!(sys::virStoragePoolListAllVolumes(
+                self.as_ptr(),
+                &mut volumes,
+                flags as libc::c_uint
+            ))
         };
         if size == -1 {
             return Err(Error::last_error());
@@ -240,7 +649,7 @@ impl StoragePool {
     pub fn get_uuid(&self) -> Result<Uuid, Error> {
         let mut uuid: [libc::c_uchar; sys::VIR_UUID_BUFLEN as usize] =
             [0; sys::VIR_UUID_BUFLEN as usize];
-        let ret = unsafe { sys::virStoragePoolGetUUID(self.as_ptr(), uuid.as_mut_ptr()) };
+        let ret = unsafe { ffi!(sys::virStoragePoolGetUUID(self.as_ptr(), uuid.as_mut_ptr())) };
         if ret == -1 {
             return Err(Error::last_error());
         }
@@ -250,31 +659,46 @@ impl StoragePool {
     pub fn get_uuid_string(&self) -> Result<String, Error> {
         let mut uuid: [libc::c_char; sys::VIR_UUID_STRING_BUFLEN as usize] =
             [0; sys::VIR_UUID_STRING_BUFLEN as usize];
-        let ret = unsafe { sys::virStoragePoolGetUUIDString(self.as_ptr(), uuid.as_mut_ptr()) };
+        let ret = unsafe {
+            ffi!(sys::virStoragePoolGetUUIDString(
+                self.as_ptr(),
+                uuid.as_mut_ptr()
+            ))
+        };
         if ret == -1 {
             return Err(Error::last_error());
         }
         Ok(unsafe { c_chars_to_string!(uuid.as_ptr(), nofree) })
     }
 
-    pub fn get_xml_desc(&self, flags: sys::virStorageXMLFlags) -> Result<String, Error> {
-        let xml = unsafe { sys::virStoragePoolGetXMLDesc(self.as_ptr(), flags) };
+    pub fn get_xml_desc(&self, flags: impl Into<StorageXmlFlags>) -> Result<String, Error> {
+        let xml = unsafe {
+            ffi!(sys::virStoragePoolGetXMLDesc(
+                self.as_ptr(),
+                flags.into().bits()
+            ))
+        };
         if xml.is_null() {
             return Err(Error::last_error());
         }
         Ok(unsafe { c_chars_to_string!(xml) })
     }
 
-    pub fn create(&self, flags: sys::virStoragePoolCreateFlags) -> Result<u32, Error> {
-        let ret = unsafe { sys::virStoragePoolCreate(self.as_ptr(), flags) };
+    pub fn create(&self, flags: impl Into<StoragePoolCreateFlags>) -> Result<u32, Error> {
+        let ret = unsafe {
+            ffi!(sys::virStoragePoolCreate(
+                self.as_ptr(),
+                flags.into().bits()
+            ))
+        };
         if ret == -1 {
             return Err(Error::last_error());
         }
         Ok(ret as u32)
     }
 
-    pub fn build(&self, flags: u32) -> Result<u32, Error> {
-        let ret = unsafe { sys::virStoragePoolBuild(self.as_ptr(), flags) };
+    pub fn build(&self, flags: impl Into<StoragePoolBuildFlags>) -> Result<u32, Error> {
+        let ret = unsafe { ffi!(sys::virStoragePoolBuild(self.as_ptr(), flags.into().bits())) };
         if ret == -1 {
             return Err(Error::last_error());
         }
@@ -282,7 +706,7 @@ impl StoragePool {
     }
 
     pub fn destroy(&self) -> Result<(), Error> {
-        let ret = unsafe { sys::virStoragePoolDestroy(self.as_ptr()) };
+        let ret = unsafe { ffi!(sys::virStoragePoolDestroy(self.as_ptr())) };
         if ret == -1 {
             return Err(Error::last_error());
         }
@@ -290,7 +714,12 @@ impl StoragePool {
     }
 
     pub fn delete(&self, flags: u32) -> Result<(), Error> {
-        let ret = unsafe { sys::virStoragePoolDelete(self.as_ptr(), flags as libc::c_uint) };
+        let ret = unsafe {
+            ffi!(sys::virStoragePoolDelete(
+                self.as_ptr(),
+                flags as libc::c_uint
+            ))
+        };
         if ret == -1 {
             return Err(Error::last_error());
         }
@@ -298,7 +727,7 @@ impl StoragePool {
     }
 
     pub fn undefine(&self) -> Result<(), Error> {
-        let ret = unsafe { sys::virStoragePoolUndefine(self.as_ptr()) };
+        let ret = unsafe { ffi!(sys::virStoragePoolUndefine(self.as_ptr())) };
         if ret == -1 {
             return Err(Error::last_error());
         }
@@ -306,7 +735,7 @@ impl StoragePool {
     }
 
     pub fn free(&mut self) -> Result<(), Error> {
-        let ret = unsafe { sys::virStoragePoolFree(self.as_ptr()) };
+        let ret = unsafe { ffi!(sys::virStoragePoolFree(self.as_ptr())) };
         if ret == -1 {
             return Err(Error::last_error());
         }
@@ -315,7 +744,7 @@ impl StoragePool {
     }
 
     pub fn is_active(&self) -> Result<bool, Error> {
-        let ret = unsafe { sys::virStoragePoolIsActive(self.as_ptr()) };
+        let ret = unsafe { ffi!(sys::virStoragePoolIsActive(self.as_ptr())) };
         if ret == -1 {
             return Err(Error::last_error());
         }
@@ -323,7 +752,7 @@ impl StoragePool {
     }
 
     pub fn is_persistent(&self) -> Result<bool, Error> {
-        let ret = unsafe { sys::virStoragePoolIsPersistent(self.as_ptr()) };
+        let ret = unsafe { ffi!(sys::virStoragePoolIsPersistent(self.as_ptr())) };
         if ret == -1 {
             return Err(Error::last_error());
         }
@@ -331,7 +760,12 @@ impl StoragePool {
     }
 
     pub fn refresh(&self, flags: u32) -> Result<u32, Error> {
-        let ret = unsafe { sys::virStoragePoolRefresh(self.as_ptr(), flags as libc::c_uint) };
+        let ret = unsafe {
+            ffi!(sys::virStoragePoolRefresh(
+                self.as_ptr(),
+                flags as libc::c_uint
+            ))
+        };
         if ret == -1 {
             return Err(Error::last_error());
         }
@@ -339,7 +773,7 @@ impl StoragePool {
     }
     pub fn get_autostart(&self) -> Result<bool, Error> {
         let mut auto = 0;
-        let ret = unsafe { sys::virStoragePoolGetAutostart(self.as_ptr(), &mut auto) };
+        let ret = unsafe { ffi!(sys::virStoragePoolGetAutostart(self.as_ptr(), &mut auto)) };
         if ret == -1 {
             return Err(Error::last_error());
         }
@@ -347,8 +781,12 @@ impl StoragePool {
     }
 
     pub fn set_autostart(&self, autostart: bool) -> Result<u32, Error> {
-        let ret =
-            unsafe { sys::virStoragePoolSetAutostart(self.as_ptr(), autostart as libc::c_int) };
+        let ret = unsafe {
+            ffi!(sys::virStoragePoolSetAutostart(
+                self.as_ptr(),
+                autostart as libc::c_int
+            ))
+        };
         if ret == -1 {
             return Err(Error::last_error());
         }
@@ -357,7 +795,12 @@ impl StoragePool {
 
     pub fn get_info(&self) -> Result<StoragePoolInfo, Error> {
         let mut pinfo = mem::MaybeUninit::uninit();
-        let res = unsafe { sys::virStoragePoolGetInfo(self.as_ptr(), pinfo.as_mut_ptr()) };
+        let res = unsafe {
+            ffi!(sys::virStoragePoolGetInfo(
+                self.as_ptr(),
+                pinfo.as_mut_ptr()
+            ))
+        };
         if res == -1 {
             return Err(Error::last_error());
         }
@@ -17,14 +17,160 @@
  */
 
 use std::ffi::CString;
-use std::{mem, str};
+use std::{fmt, mem, str};
+
+use xml::escape::{escape_str_attribute, escape_str_pcdata};
 
 use crate::connect::Connect;
-use crate::error::Error;
+use crate::error::{report_drop_error, Error};
 use crate::storage_pool::StoragePool;
 use crate::stream::Stream;
+use crate::util::{impl_flags, impl_identity};
+
+impl_flags! {
+    /// Flags used when creating a storage volume.
+    ///
+    /// See <https://libvirt.org/html/libvirt-libvirt-storage.html#virStorageVolCreateFlags>
+    struct StorageVolCreateFlags: sys::virStorageVolCreateFlags {
+        /// Preallocate the metadata of the volume.
+        const PREALLOC_METADATA = sys::VIR_STORAGE_VOL_CREATE_PREALLOC_METADATA;
+        /// Perform a copy-on-write clone when cloning a volume.
+        const REFLINK = sys::VIR_STORAGE_VOL_CREATE_REFLINK;
+        /// Validate the XML document against the schema.
+        const VALIDATE = sys::VIR_STORAGE_VOL_CREATE_VALIDATE;
+    }
+}
+
+/// Describes a storage volume to create with [`StorageVol::create()`].
+///
+/// The description is turned into a volume XML document and the
+/// matching creation flags. See <https://libvirt.org/formatstorage.html#storage-volume-xml>
+///
+/// ```
+/// use virt::storage_vol::VolumeSpec;
+///
+/// let spec = VolumeSpec::new("disk.qcow2", 10 << 30)
+///     .format("qcow2")
+///     .backing_store("/var/lib/libvirt/images/base.qcow2", "qcow2");
+/// assert!(spec.to_xml().contains("<backingStore>"));
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VolumeSpec {
+    name: String,
+    capacity: u64,
+    allocation: Option<u64>,
+    format: Option<String>,
+    backing_store: Option<(String, Option<String>)>,
+    prealloc_metadata: bool,
+    reflink: bool,
+}
+
+impl VolumeSpec {
+    /// Describes a volume `name` of `capacity` bytes, allocated
+    /// according to the defaults of the pool.
+    pub fn new(name: &str, capacity: u64) -> VolumeSpec {
+        VolumeSpec {
+            name: name.to_string(),
+            capacity,
+            allocation: None,
+            format: None,
+            backing_store: None,
+            prealloc_metadata: false,
+            reflink: false,
+        }
+    }
+
+    /// Sets the number of bytes allocated up front; 0 makes a sparse
+    /// volume where the pool supports it.
+    pub fn allocation(mut self, bytes: u64) -> VolumeSpec {
+        self.allocation = Some(bytes);
+        self
+    }
+
+    /// Sets the format of the volume, e.g. `raw` or `qcow2`.
+    pub fn format(mut self, format: &str) -> VolumeSpec {
+        self.format = Some(format.to_string());
+        self
+    }
+
+    /// Makes the volume a copy-on-write overlay of the image at `path`,
+    /// whose format is `format`.
+    pub fn backing_store(mut self, path: &str, format: &str) -> VolumeSpec {
+        self.backing_store = Some((path.to_string(), Some(format.to_string())));
+        self
+    }
+
+    /// Preallocates the metadata of the volume, for the formats
+    /// supporting it such as qcow2.
+    pub fn prealloc_metadata(mut self, prealloc: bool) -> VolumeSpec {
+        self.prealloc_metadata = prealloc;
+        self
+    }
+
+    /// Performs a copy-on-write clone when created from another
+    /// volume with [`StorageVol::create_from()`].
+    pub fn reflink(mut self, reflink: bool) -> VolumeSpec {
+        self.reflink = reflink;
+        self
+    }
+
+    /// Returns the name of the volume.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the flags matching this description.
+    pub fn flags(&self) -> StorageVolCreateFlags {
+        let mut flags = StorageVolCreateFlags::empty();
+        if self.prealloc_metadata {
+            flags |= StorageVolCreateFlags::PREALLOC_METADATA;
+        }
+        if self.reflink {
+            flags |= StorageVolCreateFlags::REFLINK;
+        }
+        flags
+    }
+
+    /// Returns the XML description of the volume.
+    pub fn to_xml(&self) -> String {
+        fn format(xml: &mut String, format: &Option<String>) {
+            if let Some(format) = format {
+                xml.push_str(&format!(
+                    "    <format type='{}'/>\n",
+                    escape_str_attribute(format)
+                ));
+            }
+        }
+
+        let mut xml = format!(
+            "<volume>\n  <name>{}</name>\n  <capacity unit='bytes'>{}</capacity>\n",
+            escape_str_pcdata(&self.name),
+            self.capacity
+        );
+        if let Some(allocation) = self.allocation {
+            xml.push_str(&format!(
+                "  <allocation unit='bytes'>{}</allocation>\n",
+                allocation
+            ));
+        }
+        if self.format.is_some() {
+            xml.push_str("  <target>\n");
+            format(&mut xml, &self.format);
+            xml.push_str("  </target>\n");
+        }
+        if let Some((path, backing_format)) = &self.backing_store {
+            xml.push_str("  <backingStore>\n");
+            xml.push_str(&format!("    <path>{}</path>\n", escape_str_pcdata(path)));
+            format(&mut xml, backing_format);
+            xml.push_str("  </backingStore>\n");
+        }
+        xml.push_str("</volume>\n");
+        xml
+    }
+}
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct StorageVolInfo {
     /// See: `virStorageVolType` flags
     pub kind: u32,
@@ -34,6 +180,16 @@ pub struct StorageVolInfo {
     pub allocation: u64,
 }
 
+impl fmt::Display for StorageVolInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "type: {}, capacity: {} B, allocation: {} B",
+            self.kind, self.capacity, self.allocation
+        )
+    }
+}
+
 impl StorageVolInfo {
     /// # Safety
     ///
@@ -53,6 +209,7 @@ impl StorageVolInfo {
 #[derive(Debug)]
 pub struct StorageVol {
     ptr: Option<sys::virStorageVolPtr>,
+    identity: Option<String>,
 }
 
 unsafe impl Send for StorageVol {}
@@ -62,7 +219,7 @@ impl Drop for StorageVol {
     fn drop(&mut self) {
         if self.ptr.is_some() {
             if let Err(e) = self.free() {
-                panic!("Unable to drop memory for StorageVol: {}", e)
+                report_drop_error("StorageVol", e);
             }
         }
     }
@@ -81,17 +238,23 @@ impl Clone for StorageVol {
     }
 }
 
+impl_identity!(StorageVol, get_key);
+
 impl StorageVol {
     /// # Safety
     ///
     /// The caller must ensure that the pointer is valid.
     pub unsafe fn from_ptr(ptr: sys::virStorageVolPtr) -> StorageVol {
-        StorageVol { ptr: Some(ptr) }
+        StorageVol {
+            ptr: Some(ptr),
+            identity: Default::default(),
+        }
+        .load_identity()
     }
 
     fn add_ref(&self) -> Result<StorageVol, Error> {
         unsafe {
-            if sys::virStorageVolRef(self.as_ptr()) == -1 {
+            if ffi!(sys::virStorageVolRef(self.as_ptr())) == -1 {
                 return Err(Error::last_error());
             }
         }
@@ -104,7 +267,7 @@ impl StorageVol {
     }
 
     pub fn get_connect(&self) -> Result<Connect, Error> {
-        let ptr = unsafe { sys::virStorageVolGetConnect(self.as_ptr()) };
+        let ptr = unsafe { ffi!(sys::virStorageVolGetConnect(self.as_ptr())) };
         if ptr.is_null() {
             return Err(Error::last_error());
         }
@@ -114,11 +277,15 @@ impl StorageVol {
     pub fn create_xml(
         pool: &StoragePool,
         xml: &str,
-        flags: sys::virStorageVolCreateFlags,
+        flags: impl Into<StorageVolCreateFlags>,
     ) -> Result<StorageVol, Error> {
-        let xml_buf = CString::new(xml).unwrap();
+        let xml_buf = CString::new(xml)?;
         let ptr = unsafe {
-            sys::virStorageVolCreateXML(pool.as_ptr(), xml_buf.as_ptr(), flags as libc::c_uint)
+            ffi!(sys::virStorageVolCreateXML(
+                pool.as_ptr(),
+                xml_buf.as_ptr(),
+                flags.into().bits() as libc::c_uint,
+            ))
         };
         if ptr.is_null() {
             return Err(Error::last_error());
@@ -126,20 +293,35 @@ impl StorageVol {
         Ok(unsafe { StorageVol::from_ptr(ptr) })
     }
 
+    /// Creates a volume in `pool` from its description.
+    pub fn create(pool: &StoragePool, spec: &VolumeSpec) -> Result<StorageVol, Error> {
+        StorageVol::create_xml(pool, &spec.to_xml(), spec.flags())
+    }
+
+    /// Creates a volume in `pool` from its description, filled with
+    /// the content of `vol`.
+    pub fn create_from(
+        pool: &StoragePool,
+        spec: &VolumeSpec,
+        vol: &StorageVol,
+    ) -> Result<StorageVol, Error> {
+        StorageVol::create_xml_from(pool, &spec.to_xml(), vol, spec.flags())
+    }
+
     pub fn create_xml_from(
         pool: &StoragePool,
         xml: &str,
         vol: &StorageVol,
-        flags: sys::virStorageVolCreateFlags,
+        flags: impl Into<StorageVolCreateFlags>,
     ) -> Result<StorageVol, Error> {
-        let xml_buf = CString::new(xml).unwrap();
+        let xml_buf = CString::new(xml)?;
         let ptr = unsafe {
-            sys::virStorageVolCreateXMLFrom(
+            ffi!(sys::virStorageVolCreateXMLFrom(
                 pool.as_ptr(),
                 xml_buf.as_ptr(),
                 vol.as_ptr(),
-                flags as libc::c_uint,
-            )
+                flags.into().bits() as libc::c_uint,
+            ))
         };
         if ptr.is_null() {
             return Err(Error::last_error());
@@ -148,8 +330,13 @@ impl StorageVol {
     }
 
     pub fn lookup_by_name(pool: &StoragePool, name: &str) -> Result<StorageVol, Error> {
-        let name_buf = CString::new(name).unwrap();
-        let ptr = unsafe { sys::virStorageVolLookupByName(pool.as_ptr(), name_buf.as_ptr()) };
+        let name_buf = CString::new(name)?;
+        let ptr = unsafe {
+            ffi!(sys::virStorageVolLookupByName(
+                pool.as_ptr(),
+                name_buf.as_ptr()
+            ))
+        };
         if ptr.is_null() {
             return Err(Error::last_error());
         }
@@ -157,8 +344,13 @@ impl StorageVol {
     }
 
     pub fn lookup_by_key(conn: &Connect, key: &str) -> Result<StorageVol, Error> {
-        let key_buf = CString::new(key).unwrap();
-        let ptr = unsafe { sys::virStorageVolLookupByKey(conn.as_ptr(), key_buf.as_ptr()) };
+        let key_buf = CString::new(key)?;
+        let ptr = unsafe {
+            ffi!(sys::virStorageVolLookupByKey(
+                conn.as_ptr(),
+                key_buf.as_ptr()
+            ))
+        };
         if ptr.is_null() {
             return Err(Error::last_error());
         }
@@ -166,8 +358,13 @@ impl StorageVol {
     }
 
     pub fn lookup_by_path(conn: &Connect, path: &str) -> Result<StorageVol, Error> {
-        let path_buf = CString::new(path).unwrap();
-        let ptr = unsafe { sys::virStorageVolLookupByPath(conn.as_ptr(), path_buf.as_ptr()) };
+        let path_buf = CString::new(path)?;
+        let ptr = unsafe {
+            ffi!(sys::virStorageVolLookupByPath(
+                conn.as_ptr(),
+                path_buf.as_ptr()
+            ))
+        };
         if ptr.is_null() {
             return Err(Error::last_error());
         }
@@ -175,7 +372,7 @@ impl StorageVol {
     }
 
     pub fn get_name(&self) -> Result<String, Error> {
-        let n = unsafe { sys::virStorageVolGetName(self.as_ptr()) };
+        let n = unsafe { ffi!(sys::virStorageVolGetName(self.as_ptr())) };
         if n.is_null() {
             return Err(Error::last_error());
         }
@@ -183,7 +380,7 @@ impl StorageVol {
     }
 
     pub fn get_key(&self) -> Result<String, Error> {
-        let n = unsafe { sys::virStorageVolGetKey(self.as_ptr()) };
+        let n = unsafe { ffi!(sys::virStorageVolGetKey(self.as_ptr())) };
         if n.is_null() {
             return Err(Error::last_error());
         }
@@ -191,7 +388,7 @@ impl StorageVol {
     }
 
     pub fn get_path(&self) -> Result<String, Error> {
-        let n = unsafe { sys::virStorageVolGetPath(self.as_ptr()) };
+        let n = unsafe { ffi!(sys::virStorageVolGetPath(self.as_ptr())) };
         if n.is_null() {
             return Err(Error::last_error());
         }
@@ -199,7 +396,7 @@ impl StorageVol {
     }
 
     pub fn get_xml_desc(&self, flags: u32) -> Result<String, Error> {
-        let xml = unsafe { sys::virStorageVolGetXMLDesc(self.as_ptr(), flags) };
+        let xml = unsafe { ffi!(sys::virStorageVolGetXMLDesc(self.as_ptr(), flags)) };
         if xml.is_null() {
             return Err(Error::last_error());
         }
@@ -207,7 +404,12 @@ impl StorageVol {
     }
 
     pub fn delete(&self, flags: u32) -> Result<(), Error> {
-        let ret = unsafe { sys::virStorageVolDelete(self.as_ptr(), flags as libc::c_uint) };
+        let ret = unsafe {
+            ffi!(sys::virStorageVolDelete(
+                self.as_ptr(),
+                flags as libc::c_uint
+            ))
+        };
         if ret == -1 {
             return Err(Error::last_error());
         }
@@ -215,7 +417,7 @@ impl StorageVol {
     }
 
     pub fn wipe(&self, flags: u32) -> Result<(), Error> {
-        let ret = unsafe { sys::virStorageVolWipe(self.as_ptr(), flags as libc::c_uint) };
+        let ret = unsafe { ffi!(sys::virStorageVolWipe(self.as_ptr(), flags as libc::c_uint)) };
         if ret == -1 {
             return Err(Error::last_error());
         }
@@ -228,11 +430,11 @@ impl StorageVol {
         flags: u32,
     ) -> Result<(), Error> {
         let ret = unsafe {
-            sys::virStorageVolWipePattern(
+            ffi!(sys::virStorageVolWipePattern(
                 self.as_ptr(),
                 algo as libc::c_uint,
                 flags as libc::c_uint,
-            )
+            ))
         };
         if ret == -1 {
             return Err(Error::last_error());
@@ -241,7 +443,7 @@ impl StorageVol {
     }
 
     pub fn free(&mut self) -> Result<(), Error> {
-        let ret = unsafe { sys::virStorageVolFree(self.as_ptr()) };
+        let ret = unsafe { ffi!(sys::virStorageVolFree(self.as_ptr())) };
         if ret == -1 {
             return Err(Error::last_error());
         }
@@ -251,11 +453,11 @@ impl StorageVol {
 
     pub fn resize(&self, capacity: u64, flags: u32) -> Result<u32, Error> {
         let ret = unsafe {
-            sys::virStorageVolResize(
+            ffi!(sys::virStorageVolResize(
                 self.as_ptr(),
                 capacity as libc::c_ulonglong,
                 flags as libc::c_uint,
-            )
+            ))
         };
         if ret == -1 {
             return Err(Error::last_error());
@@ -265,7 +467,7 @@ impl StorageVol {
 
     pub fn get_info(&self) -> Result<StorageVolInfo, Error> {
         let mut pinfo = mem::MaybeUninit::uninit();
-        let res = unsafe { sys::virStorageVolGetInfo(self.as_ptr(), pinfo.as_mut_ptr()) };
+        let res = unsafe { ffi!(sys::virStorageVolGetInfo(self.as_ptr(), pinfo.as_mut_ptr())) };
         if res == -1 {
             return Err(Error::last_error());
         }
@@ -275,7 +477,11 @@ impl StorageVol {
     pub fn get_info_flags(&self, flags: u32) -> Result<StorageVolInfo, Error> {
         let mut pinfo = mem::MaybeUninit::uninit();
         let res = unsafe {
-            sys::virStorageVolGetInfoFlags(self.as_ptr(), pinfo.as_mut_ptr(), flags as libc::c_uint)
+            ffi!(sys::virStorageVolGetInfoFlags(
+                self.as_ptr(),
+                pinfo.as_mut_ptr(),
+                flags as libc::c_uint
+            ))
         };
         if res == -1 {
             return Err(Error::last_error());
@@ -291,13 +497,13 @@ impl StorageVol {
         flags: u32,
     ) -> Result<(), Error> {
         let ret = unsafe {
-            sys::virStorageVolDownload(
+            ffi!(sys::virStorageVolDownload(
                 self.as_ptr(),
                 stream.as_ptr(),
                 offset as libc::c_ulonglong,
                 length as libc::c_ulonglong,
                 flags as libc::c_uint,
-            )
+            ))
         };
         if ret == -1 {
             return Err(Error::last_error());
@@ -313,13 +519,13 @@ impl StorageVol {
         flags: u32,
     ) -> Result<(), Error> {
         let ret = unsafe {
-            sys::virStorageVolUpload(
+            ffi!(sys::virStorageVolUpload(
                 self.as_ptr(),
                 stream.as_ptr(),
                 offset as libc::c_ulonglong,
                 length as libc::c_ulonglong,
                 flags as libc::c_uint,
-            )
+            ))
         };
         if ret == -1 {
             return Err(Error::last_error());
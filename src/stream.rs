@@ -19,7 +19,7 @@
 use std::convert::TryFrom;
 
 use crate::connect::Connect;
-use crate::error::Error;
+use crate::error::{report_drop_error, Error};
 
 // wrapper for callbacks
 extern "C" fn event_callback(c: sys::virStreamPtr, flags: libc::c_int, opaque: *mut libc::c_void) {
@@ -28,7 +28,7 @@ extern "C" fn event_callback(c: sys::virStreamPtr, flags: libc::c_int, opaque: *
     if let Some(callback) = &mut shadow_self.callback {
         callback(
             unsafe {
-                sys::virStreamRef(c);
+                ffi!(sys::virStreamRef(c));
                 &Stream::from_ptr(c)
             },
             flags,
@@ -53,12 +53,12 @@ impl Drop for Stream {
     fn drop(&mut self) {
         if self.callback.is_some() {
             if let Err(e) = self.event_remove_callback() {
-                panic!("Unable to remove event callback for Stream: {}", e)
+                report_drop_error("Stream", e);
             }
         }
         if self.ptr.is_some() {
             if let Err(e) = self.free() {
-                panic!("Unable to drop memory for Stream: {}", e)
+                report_drop_error("Stream", e);
             }
         }
     }
@@ -79,7 +79,7 @@ impl Clone for Stream {
 
 impl Stream {
     pub fn new(conn: &Connect, flags: sys::virStreamFlags) -> Result<Stream, Error> {
-        let ptr = unsafe { sys::virStreamNew(conn.as_ptr(), flags as libc::c_uint) };
+        let ptr = unsafe { ffi!(sys::virStreamNew(conn.as_ptr(), flags as libc::c_uint)) };
         if ptr.is_null() {
             return Err(Error::last_error());
         }
@@ -88,7 +88,7 @@ impl Stream {
 
     fn add_ref(&self) -> Result<Stream, Error> {
         unsafe {
-            if sys::virStreamRef(self.as_ptr()) == -1 {
+            if ffi!(sys::virStreamRef(self.as_ptr())) == -1 {
                 return Err(Error::last_error());
             }
         }
@@ -108,7 +108,7 @@ impl Stream {
     }
 
     pub fn free(&mut self) -> Result<(), Error> {
-        let ret = unsafe { sys::virStreamFree(self.as_ptr()) };
+        let ret = unsafe { ffi!(sys::virStreamFree(self.as_ptr())) };
         if ret == -1 {
             return Err(Error::last_error());
         }
@@ -117,7 +117,7 @@ impl Stream {
     }
 
     pub fn finish(self) -> Result<(), Error> {
-        let ret = unsafe { sys::virStreamFinish(self.as_ptr()) };
+        let ret = unsafe { ffi!(sys::virStreamFinish(self.as_ptr())) };
         if ret == -1 {
             return Err(Error::last_error());
         }
@@ -125,7 +125,7 @@ impl Stream {
     }
 
     pub fn abort(self) -> Result<(), Error> {
-        let ret = unsafe { sys::virStreamAbort(self.as_ptr()) };
+        let ret = unsafe { ffi!(sys::virStreamAbort(self.as_ptr())) };
         if ret == -1 {
             return Err(Error::last_error());
         }
@@ -134,22 +134,22 @@ impl Stream {
 
     pub fn send(&self, data: &[u8]) -> Result<usize, Error> {
         let ret = unsafe {
-            sys::virStreamSend(
+            ffi!(sys::virStreamSend(
                 self.as_ptr(),
                 data.as_ptr() as *mut libc::c_char,
                 data.len(),
-            )
+            ))
         };
         usize::try_from(ret).map_err(|_| Error::last_error())
     }
 
     pub fn recv(&self, buf: &mut [u8]) -> Result<usize, Error> {
         let ret = unsafe {
-            sys::virStreamRecv(
+            ffi!(sys::virStreamRecv(
                 self.as_ptr(),
                 buf.as_mut_ptr() as *mut libc::c_char,
                 buf.len(),
-            )
+            ))
         };
         usize::try_from(ret).map_err(|_| Error::last_error())
     }
@@ -161,13 +161,13 @@ impl Stream {
     ) -> Result<(), Error> {
         let ret = unsafe {
             let ptr = self as *mut _ as *mut _;
-            sys::virStreamEventAddCallback(
+            ffi!(sys::virStreamEventAddCallback(
                 self.as_ptr(),
                 events as libc::c_int,
                 Some(event_callback),
                 ptr,
                 Some(event_free),
-            )
+            ))
         };
         if ret == -1 {
             return Err(Error::last_error());
@@ -177,8 +177,12 @@ impl Stream {
     }
 
     pub fn event_update_callback(&self, events: sys::virStreamEventType) -> Result<(), Error> {
-        let ret =
-            unsafe { sys::virStreamEventUpdateCallback(self.as_ptr(), events as libc::c_int) };
+        let ret = unsafe {
+            ffi!(sys::virStreamEventUpdateCallback(
+                self.as_ptr(),
+                events as libc::c_int
+            ))
+        };
         if ret == -1 {
             return Err(Error::last_error());
         }
@@ -186,7 +190,7 @@ impl Stream {
     }
 
     pub fn event_remove_callback(&self) -> Result<(), Error> {
-        let ret = unsafe { sys::virStreamEventRemoveCallback(self.as_ptr()) };
+        let ret = unsafe { ffi!(sys::virStreamEventRemoveCallback(self.as_ptr())) };
         if ret == -1 {
             return Err(Error::last_error());
         }
@@ -0,0 +1,257 @@
+/*
+ * This library is free software; you can redistribute it and/or
+ * modify it under the terms of the GNU Lesser General Public
+ * License as published by the Free Software Foundation; either
+ * version 2.1 of the License, or (at your option) any later version.
+ *
+ * This library is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+ * Lesser General Public License for more details.
+ *
+ * You should have received a copy of the GNU Lesser General Public
+ * License along with this library.  If not, see
+ * <https://www.gnu.org/licenses/>.
+ */
+
+//! Fixtures for integration tests against the libvirt test driver.
+//!
+//! The `test:///default` URI connects to an in-memory hypervisor,
+//! shared by all the connections of the process and pre-populated
+//! with a running domain named `test`, a network and a storage pool
+//! named `default`. The helpers of this module create extra objects
+//! named `libvirt-rs-test-<name>`, which are destroyed and undefined
+//! when the returned [`Fixture`] is dropped. They panic on failure,
+//! as is usual in tests.
+//!
+//! ```no_run
+//! use virt::testing;
+//!
+//! let conn = testing::conn();
+//! let dom = testing::build_test_domain(&conn, "example", true);
+//! assert!(dom.is_active().unwrap());
+//! ```
+
+use std::ops::{Deref, DerefMut};
+
+use crate::connect::Connect;
+use crate::domain::Domain;
+use crate::interface::Interface;
+use crate::network::Network;
+use crate::storage_pool::StoragePool;
+use crate::storage_vol::StorageVol;
+
+/// The URI of the default configuration of the test driver.
+pub const TEST_URI: &str = "test:///default";
+
+/// An object removed from the hypervisor by a [`Fixture`].
+pub trait Teardown {
+    /// Destroys and undefines the object, ignoring the errors.
+    fn teardown(&self);
+}
+
+impl Teardown for Domain {
+    fn teardown(&self) {
+        let _ = self.destroy();
+        let _ = self.undefine();
+    }
+}
+
+impl Teardown for Network {
+    fn teardown(&self) {
+        let _ = self.destroy();
+        let _ = self.undefine();
+    }
+}
+
+impl Teardown for StoragePool {
+    fn teardown(&self) {
+        let _ = self.destroy();
+        let _ = self.undefine();
+    }
+}
+
+impl Teardown for StorageVol {
+    fn teardown(&self) {
+        let _ = self.delete(0);
+    }
+}
+
+impl Teardown for Interface {
+    fn teardown(&self) {
+        let _ = self.destroy(0);
+        let _ = self.undefine();
+    }
+}
+
+/// An object created for a test, torn down when dropped.
+#[derive(Debug)]
+pub struct Fixture<T: Teardown> {
+    inner: Option<T>,
+}
+
+impl<T: Teardown> Fixture<T> {
+    /// Wraps `inner`, to be torn down when the fixture is dropped.
+    pub fn new(inner: T) -> Fixture<T> {
+        Fixture { inner: Some(inner) }
+    }
+
+    /// Returns the object, which is then left on the hypervisor.
+    pub fn into_inner(mut self) -> T {
+        self.inner.take().unwrap()
+    }
+}
+
+impl<T: Teardown> Deref for Fixture<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.inner.as_ref().unwrap()
+    }
+}
+
+impl<T: Teardown> DerefMut for Fixture<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.inner.as_mut().unwrap()
+    }
+}
+
+impl<T: Teardown> Drop for Fixture<T> {
+    fn drop(&mut self) {
+        if let Some(inner) = self.inner.take() {
+            inner.teardown();
+        }
+    }
+}
+
+/// Opens a connection to [`TEST_URI`].
+pub fn conn() -> Connect {
+    Connect::open(Some(TEST_URI)).unwrap()
+}
+
+fn fixture_name(name: &str) -> String {
+    format!("libvirt-rs-test-{}", name)
+}
+
+/// Creates a minimal domain, started if `transient`, defined otherwise.
+///
+/// A leftover domain with the same name is removed first.
+pub fn build_test_domain(conn: &Connect, name: &str, transient: bool) -> Fixture<Domain> {
+    let name = fixture_name(name);
+    if let Ok(dom) = Domain::lookup_by_name(conn, &name) {
+        dom.teardown();
+    }
+
+    let xml = format!(
+        "<domain type='test'>
+  <name>{}</name>
+  <memory unit='KiB'>128</memory>
+  <features>
+    <acpi/>
+    <apic/>
+  </features>
+  <os>
+    <type>hvm</type>
+  </os>
+</domain>",
+        name
+    );
+    let dom = if transient {
+        Domain::create_xml(conn, &xml, 0)
+    } else {
+        Domain::define_xml(conn, &xml)
+    };
+    Fixture::new(dom.unwrap())
+}
+
+/// Creates a NATed network, started if `transient`, defined otherwise.
+///
+/// The bridge is named after the network, `libvirt-rs-test-<name>-br`,
+/// so several network fixtures can coexist. A leftover network with
+/// the same name is removed first.
+pub fn build_network(conn: &Connect, name: &str, transient: bool) -> Fixture<Network> {
+    let name = fixture_name(name);
+    if let Ok(net) = Network::lookup_by_name(conn, &name) {
+        net.teardown();
+    }
+
+    let xml = format!(
+        "<network>
+  <name>{}</name>
+  <bridge name='{}-br'/>
+  <forward/>
+  <ip address='192.168.0.1' netmask='255.255.255.0'/>
+</network>",
+        name, name
+    );
+    let net = if transient {
+        Network::create_xml(conn, &xml)
+    } else {
+        Network::define_xml(conn, &xml)
+    };
+    Fixture::new(net.unwrap())
+}
+
+/// Creates a directory pool, started if `transient`, defined otherwise.
+///
+/// A leftover pool with the same name is removed first.
+pub fn build_storage_pool(conn: &Connect, name: &str, transient: bool) -> Fixture<StoragePool> {
+    let name = fixture_name(name);
+    if let Ok(pool) = StoragePool::lookup_by_name(conn, &name) {
+        pool.teardown();
+    }
+
+    let xml = format!(
+        "<pool type='dir'>
+  <name>{}</name>
+  <target>
+    <path>/var/lib/libvirt/images</path>
+  </target>
+</pool>",
+        name
+    );
+    let pool = if transient {
+        StoragePool::create_xml(conn, &xml, 0)
+    } else {
+        StoragePool::define_xml(conn, &xml, 0)
+    };
+    Fixture::new(pool.unwrap())
+}
+
+/// Creates a fully allocated volume of `size` KiB in `pool`.
+///
+/// A leftover volume with the same name is removed first.
+pub fn build_storage_vol(pool: &StoragePool, name: &str, size: u64) -> Fixture<StorageVol> {
+    let name = fixture_name(name);
+    if let Ok(vol) = StorageVol::lookup_by_name(pool, &name) {
+        vol.teardown();
+    }
+
+    let xml = format!(
+        "<volume type='file'>
+  <name>{}</name>
+  <allocation unit='KiB'>{}</allocation>
+  <capacity unit='KiB'>{}</capacity>
+</volume>",
+        name, size, size
+    );
+    Fixture::new(StorageVol::create_xml(pool, &xml, 0).unwrap())
+}
+
+/// Defines an ethernet interface.
+///
+/// A leftover interface with the same name is removed first.
+pub fn build_interface(conn: &Connect, name: &str) -> Fixture<Interface> {
+    let name = fixture_name(name);
+    if let Ok(iface) = Interface::lookup_by_name(conn, &name) {
+        iface.teardown();
+    }
+
+    let xml = format!(
+        "<interface type='ethernet' name='{}'>
+  <mac address='aa:bb:cc:dd:ee:ff'/>
+</interface>",
+        name
+    );
+    Fixture::new(Interface::define_xml(conn, &xml, 0).unwrap())
+}
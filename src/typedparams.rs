@@ -1,11 +1,16 @@
-use std::ffi::CStr;
-use std::str;
+use std::convert::TryFrom;
+use std::ffi::CString;
+use std::mem;
+
+use crate::domain::Limit;
+use crate::error::Error;
 
 pub enum ParamIn<'a> {
     Int32(&'a mut Option<i32>),
     UInt32(&'a mut Option<u32>),
     Int64(&'a mut Option<i64>),
     UInt64(&'a mut Option<u64>),
+    Limit(&'a mut Option<Limit>),
     #[allow(dead_code)]
     Float64(&'a mut Option<f64>),
     #[allow(dead_code)]
@@ -20,6 +25,7 @@ pub enum ParamOut<'a> {
     UInt32(&'a Option<u32>),
     Int64(&'a Option<i64>),
     UInt64(&'a Option<u64>),
+    Limit(&'a Option<Limit>),
     #[allow(dead_code)]
     Float64(&'a Option<f64>),
     #[allow(dead_code)]
@@ -60,66 +66,169 @@ macro_rules! param_field_out {
     };
 }
 
-macro_rules! valid_type {
-    ($got:expr, $want:expr, $name:expr) => {
-        if $got != $want {
-            panic!(
-                "Expected typed param type {} not {} for {}",
-                $got, $want, $name
-            );
+/// A list of typed parameters owning the strings of its values, which
+/// are released when it is dropped.
+///
+/// The strings must have been allocated with `malloc()`, as done by
+/// libvirt and by [`to_params()`].
+pub struct TypedParamList {
+    params: Vec<sys::virTypedParameter>,
+}
+
+impl TypedParamList {
+    /// Takes ownership of the strings of `params`.
+    pub fn from_vec(params: Vec<sys::virTypedParameter>) -> TypedParamList {
+        TypedParamList { params }
+    }
+
+    /// Allocates an empty list with room for `nparams` parameters, to
+    /// be filled by libvirt through [`as_mut_ptr()`] and
+    /// [`set_len()`].
+    ///
+    /// [`as_mut_ptr()`]: TypedParamList::as_mut_ptr
+    /// [`set_len()`]: TypedParamList::set_len
+    pub fn with_capacity(nparams: libc::c_int) -> TypedParamList {
+        TypedParamList {
+            params: Vec::with_capacity(nparams.max(0) as usize),
         }
-    };
+    }
+
+    /// Takes ownership of an array of parameters allocated by libvirt,
+    /// as `virTypedParamsFree()` would.
+    ///
+    /// # Safety
+    ///
+    /// The caller must ensure that `ptr` is null or points to
+    /// `nparams` parameters allocated by libvirt.
+    pub unsafe fn from_raw(ptr: sys::virTypedParameterPtr, nparams: libc::c_int) -> TypedParamList {
+        if ptr.is_null() {
+            return TypedParamList::from_vec(Vec::new());
+        }
+        let params = std::slice::from_raw_parts(ptr, nparams.max(0) as usize).to_vec();
+        // The strings now belong to the list, only the array is freed.
+        libc::free(ptr as *mut libc::c_void);
+        TypedParamList::from_vec(params)
+    }
+
+    /// Sets the number of parameters filled by libvirt.
+    ///
+    /// # Safety
+    ///
+    /// The caller must ensure that the first `nparams` parameters are
+    /// initialized, within the capacity of the list.
+    pub unsafe fn set_len(&mut self, nparams: libc::c_int) {
+        self.params.set_len(nparams.max(0) as usize);
+    }
+
+    pub fn as_mut_ptr(&mut self) -> sys::virTypedParameterPtr {
+        self.params.as_mut_ptr()
+    }
+
+    /// Returns a copy of the parameters; strings still point into the
+    /// list.
+    pub fn params(&self) -> Vec<sys::virTypedParameter> {
+        self.params.clone()
+    }
+
+    pub fn len(&self) -> libc::c_int {
+        self.params.len() as libc::c_int
+    }
 }
 
-pub fn from_params(mut params: Vec<sys::virTypedParameter>, mut fields: Vec<FieldIn>) {
-    for param in params.iter_mut() {
-        let param_name =
-            unsafe { str::from_utf8(CStr::from_ptr(param.field.as_ptr()).to_bytes()).unwrap() };
-        for field in fields.iter_mut() {
-            if field.name == param_name {
-                match &mut field.value {
-                    ParamIn::Int32(i) => {
-                        valid_type!(param.type_ as u32, sys::VIR_TYPED_PARAM_INT, param_name);
-                        **i = unsafe { Some(param.value.i) }
-                    }
-                    ParamIn::UInt32(i) => {
-                        valid_type!(param.type_ as u32, sys::VIR_TYPED_PARAM_UINT, param_name);
-                        **i = unsafe { Some(param.value.ui) }
-                    }
-                    ParamIn::Int64(i) => {
-                        valid_type!(param.type_ as u32, sys::VIR_TYPED_PARAM_LLONG, param_name);
-                        **i = unsafe { Some(param.value.l) }
-                    }
-                    ParamIn::UInt64(i) => {
-                        valid_type!(param.type_ as u32, sys::VIR_TYPED_PARAM_ULLONG, param_name);
-                        **i = unsafe { Some(param.value.ul) }
-                    }
-                    ParamIn::Float64(d) => {
-                        valid_type!(param.type_ as u32, sys::VIR_TYPED_PARAM_DOUBLE, param_name);
-                        **d = unsafe { Some(param.value.d) }
-                    }
-                    ParamIn::Bool(b) => {
-                        valid_type!(param.type_ as u32, sys::VIR_TYPED_PARAM_BOOLEAN, param_name);
-                        **b = unsafe { Some(param.value.b != 0) }
-                    }
-                    ParamIn::String(s) => {
-                        valid_type!(param.type_ as u32, sys::VIR_TYPED_PARAM_STRING, param_name);
-                        **s = unsafe { Some(c_chars_to_string!(param.value.s, nofree)) }
-                    }
-                    ParamIn::VecString(v) => {
-                        valid_type!(param.type_ as u32, sys::VIR_TYPED_PARAM_STRING, param_name);
-                        let s = unsafe { c_chars_to_string!(param.value.s, nofree) };
-                        v.push(s);
-                    }
-                }
-                break;
+impl Drop for TypedParamList {
+    fn drop(&mut self) {
+        // Release the strings as virTypedParamsClear() does.
+        for param in &self.params {
+            if param.type_ as u32 == sys::VIR_TYPED_PARAM_STRING {
+                unsafe { libc::free(param.value.s as *mut libc::c_void) };
+            }
+        }
+    }
+}
+
+/// Returns a copy of `s` allocated with `malloc()`, as the strings of
+/// typed parameters must be, or an error if `s` contains a nul byte.
+fn to_malloc_c_chars(s: &str) -> Result<*mut libc::c_char, Error> {
+    let s = CString::new(s)?;
+    Ok(unsafe { libc::strdup(s.as_ptr()) })
+}
+
+/// Fills `fields` from `params`. The parameters matching no field, or
+/// whose type is not the one of their field, are returned instead.
+pub fn from_params(params: &[sys::virTypedParameter], mut fields: Vec<FieldIn>) -> TypedParamsMap {
+    let mut extra = TypedParamsMap::new();
+    for param in params {
+        let name = unsafe { c_chars_to_string!(param.field.as_ptr(), nofree) };
+        let set = match fields.iter_mut().find(|f| f.name == name) {
+            Some(field) => set_field(&mut field.value, param),
+            None => false,
+        };
+        if !set {
+            if let Some(value) = typed_value(param) {
+                extra.append(&name, value);
+            }
+        }
+    }
+    extra
+}
+
+/// Stores the value of `param` into `value`, returning false if it
+/// does not have the expected type.
+fn set_field(value: &mut ParamIn, param: &sys::virTypedParameter) -> bool {
+    let type_ = param.type_ as u32;
+    unsafe {
+        match value {
+            ParamIn::Int32(i) if type_ == sys::VIR_TYPED_PARAM_INT => **i = Some(param.value.i),
+            ParamIn::UInt32(i) if type_ == sys::VIR_TYPED_PARAM_UINT => **i = Some(param.value.ui),
+            ParamIn::Int64(i) if type_ == sys::VIR_TYPED_PARAM_LLONG => **i = Some(param.value.l),
+            ParamIn::UInt64(i) if type_ == sys::VIR_TYPED_PARAM_ULLONG => {
+                **i = Some(param.value.ul)
+            }
+            ParamIn::Limit(i) if type_ == sys::VIR_TYPED_PARAM_ULLONG => {
+                **i = Some(Limit::from_raw(param.value.ul))
+            }
+            ParamIn::Float64(d) if type_ == sys::VIR_TYPED_PARAM_DOUBLE => {
+                **d = Some(param.value.d)
+            }
+            ParamIn::Bool(b) if type_ == sys::VIR_TYPED_PARAM_BOOLEAN => {
+                **b = Some(param.value.b != 0)
             }
+            ParamIn::String(s) if type_ == sys::VIR_TYPED_PARAM_STRING => {
+                **s = Some(c_chars_to_string!(param.value.s, nofree))
+            }
+            ParamIn::VecString(v) if type_ == sys::VIR_TYPED_PARAM_STRING => {
+                v.push(c_chars_to_string!(param.value.s, nofree))
+            }
+            _ => return false,
         }
     }
+    true
+}
+
+/// Returns the value of `param`, or `None` if its type is unknown.
+fn typed_value(param: &sys::virTypedParameter) -> Option<TypedValue> {
+    unsafe {
+        Some(match param.type_ as u32 {
+            sys::VIR_TYPED_PARAM_INT => TypedValue::Int(param.value.i),
+            sys::VIR_TYPED_PARAM_UINT => TypedValue::UInt(param.value.ui),
+            sys::VIR_TYPED_PARAM_LLONG => TypedValue::LLong(param.value.l),
+            sys::VIR_TYPED_PARAM_ULLONG => TypedValue::ULLong(param.value.ul),
+            sys::VIR_TYPED_PARAM_DOUBLE => TypedValue::Double(param.value.d),
+            sys::VIR_TYPED_PARAM_BOOLEAN => TypedValue::Boolean(param.value.b != 0),
+            sys::VIR_TYPED_PARAM_STRING => {
+                TypedValue::String(c_chars_to_string!(param.value.s, nofree))
+            }
+            _ => return None,
+        })
+    }
 }
 
-pub fn to_params(mut fields: Vec<FieldOut>) -> Vec<sys::virTypedParameter> {
-    let mut params: Vec<sys::virTypedParameter> = Vec::new();
+/// Builds the libvirt parameters from the fields which are set.
+///
+/// Fails if a string contains a nul byte.
+pub fn to_params(mut fields: Vec<FieldOut>) -> Result<Vec<sys::virTypedParameter>, Error> {
+    // Releases the strings already copied if a later one fails.
+    let mut params = TypedParamList::from_vec(Vec::new());
 
     for field in fields.iter_mut() {
         match &mut field.value {
@@ -129,7 +238,7 @@ pub fn to_params(mut fields: Vec<FieldOut>) -> Vec<sys::virTypedParameter> {
                     type_: sys::VIR_TYPED_PARAM_INT as i32,
                     value: sys::_virTypedParameterValue { i: v },
                 };
-                params.push(p);
+                params.params.push(p);
             }),
             ParamOut::UInt32(i) => i.map(|v| {
                 let p = sys::virTypedParameter {
@@ -137,7 +246,7 @@ pub fn to_params(mut fields: Vec<FieldOut>) -> Vec<sys::virTypedParameter> {
                     type_: sys::VIR_TYPED_PARAM_UINT as i32,
                     value: sys::_virTypedParameterValue { ui: v },
                 };
-                params.push(p);
+                params.params.push(p);
             }),
             ParamOut::Int64(i) => i.map(|v| {
                 let p = sys::virTypedParameter {
@@ -145,7 +254,7 @@ pub fn to_params(mut fields: Vec<FieldOut>) -> Vec<sys::virTypedParameter> {
                     type_: sys::VIR_TYPED_PARAM_LLONG as i32,
                     value: sys::_virTypedParameterValue { l: v },
                 };
-                params.push(p);
+                params.params.push(p);
             }),
             ParamOut::UInt64(i) => i.map(|v| {
                 let p = sys::virTypedParameter {
@@ -153,7 +262,15 @@ pub fn to_params(mut fields: Vec<FieldOut>) -> Vec<sys::virTypedParameter> {
                     type_: sys::VIR_TYPED_PARAM_ULLONG as i32,
                     value: sys::_virTypedParameterValue { ul: v },
                 };
-                params.push(p);
+                params.params.push(p);
+            }),
+            ParamOut::Limit(i) => i.map(|v| {
+                let p = sys::virTypedParameter {
+                    field: to_arr(&field.name),
+                    type_: sys::VIR_TYPED_PARAM_ULLONG as i32,
+                    value: sys::_virTypedParameterValue { ul: v.to_raw() },
+                };
+                params.params.push(p);
             }),
             ParamOut::Float64(i) => i.map(|v| {
                 let p = sys::virTypedParameter {
@@ -161,7 +278,7 @@ pub fn to_params(mut fields: Vec<FieldOut>) -> Vec<sys::virTypedParameter> {
                     type_: sys::VIR_TYPED_PARAM_DOUBLE as i32,
                     value: sys::_virTypedParameterValue { d: v },
                 };
-                params.push(p);
+                params.params.push(p);
             }),
             ParamOut::Bool(i) => i.map(|v| {
                 let p = sys::virTypedParameter {
@@ -171,31 +288,218 @@ pub fn to_params(mut fields: Vec<FieldOut>) -> Vec<sys::virTypedParameter> {
                         b: v as libc::c_char,
                     },
                 };
-                params.push(p);
-            }),
-            ParamOut::String(i) => i.clone().map(|v| {
-                let p = sys::virTypedParameter {
-                    field: to_arr(&field.name),
-                    type_: sys::VIR_TYPED_PARAM_STRING as i32,
-                    value: sys::_virTypedParameterValue {
-                        s: string_to_mut_c_chars!(v),
-                    },
-                };
-                params.push(p);
+                params.params.push(p);
             }),
+            ParamOut::String(i) => {
+                if let Some(v) = i {
+                    let p = sys::virTypedParameter {
+                        field: to_arr(&field.name),
+                        type_: sys::VIR_TYPED_PARAM_STRING as i32,
+                        value: sys::_virTypedParameterValue {
+                            s: to_malloc_c_chars(v)?,
+                        },
+                    };
+                    params.params.push(p);
+                }
+                None
+            }
             ParamOut::VecString(v) => {
-                params.extend(v.clone().into_iter().map(|s| sys::virTypedParameter {
-                    field: to_arr(&field.name),
-                    type_: sys::VIR_TYPED_PARAM_STRING as i32,
-                    value: sys::_virTypedParameterValue {
-                        s: string_to_mut_c_chars!(s),
-                    },
-                }));
+                for s in v.iter() {
+                    params.params.push(sys::virTypedParameter {
+                        field: to_arr(&field.name),
+                        type_: sys::VIR_TYPED_PARAM_STRING as i32,
+                        value: sys::_virTypedParameterValue {
+                            s: to_malloc_c_chars(s)?,
+                        },
+                    });
+                }
                 None
             }
         };
     }
-    params
+    Ok(mem::take(&mut params.params))
+}
+
+/// The value of a typed parameter.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TypedValue {
+    Int(i32),
+    UInt(u32),
+    LLong(i64),
+    ULLong(u64),
+    Double(f64),
+    Boolean(bool),
+    String(String),
+}
+
+impl TypedValue {
+    /// Returns the value as an unsigned integer, or `None` if it is
+    /// not an integer or is negative.
+    pub fn as_u64(&self) -> Option<u64> {
+        match *self {
+            TypedValue::Int(v) => u64::try_from(v).ok(),
+            TypedValue::UInt(v) => Some(v as u64),
+            TypedValue::LLong(v) => u64::try_from(v).ok(),
+            TypedValue::ULLong(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Returns the value as a signed integer, or `None` if it is not
+    /// an integer or does not fit.
+    pub fn as_i64(&self) -> Option<i64> {
+        match *self {
+            TypedValue::Int(v) => Some(v as i64),
+            TypedValue::UInt(v) => Some(v as i64),
+            TypedValue::LLong(v) => Some(v),
+            TypedValue::ULLong(v) => i64::try_from(v).ok(),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match *self {
+            TypedValue::Double(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match *self {
+            TypedValue::Boolean(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            TypedValue::String(v) => Some(v),
+            _ => None,
+        }
+    }
+}
+
+/// Typed parameters by name, giving access to the parameters which
+/// the parameter structs, such as `MemoryParameters`, do not cover.
+///
+/// The parameters keep the order in which libvirt returned them. A
+/// name may appear several times, as some string parameters do.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TypedParamsMap {
+    params: Vec<(String, TypedValue)>,
+}
+
+impl TypedParamsMap {
+    pub fn new() -> TypedParamsMap {
+        TypedParamsMap::default()
+    }
+
+    /// Copies the parameters out of `vec`, skipping the ones of an
+    /// unknown type. The strings of `vec` are not released.
+    pub fn from_vec(vec: Vec<sys::virTypedParameter>) -> TypedParamsMap {
+        let params = vec
+            .iter()
+            .filter_map(|param| {
+                let name = unsafe { c_chars_to_string!(param.field.as_ptr(), nofree) };
+                typed_value(param).map(|value| (name, value))
+            })
+            .collect();
+        TypedParamsMap { params }
+    }
+
+    /// Builds the libvirt parameters. The strings are allocated with
+    /// `malloc()` and must be released with `virTypedParamsClear()`.
+    ///
+    /// Fails if a string contains a nul byte.
+    pub fn to_vec(&self) -> Result<Vec<sys::virTypedParameter>, Error> {
+        // Releases the strings already copied if a later one fails.
+        let mut params = TypedParamList::from_vec(Vec::new());
+        for (name, value) in &self.params {
+            let (type_, value) = match value {
+                TypedValue::Int(v) => (
+                    sys::VIR_TYPED_PARAM_INT,
+                    sys::_virTypedParameterValue { i: *v },
+                ),
+                TypedValue::UInt(v) => (
+                    sys::VIR_TYPED_PARAM_UINT,
+                    sys::_virTypedParameterValue { ui: *v },
+                ),
+                TypedValue::LLong(v) => (
+                    sys::VIR_TYPED_PARAM_LLONG,
+                    sys::_virTypedParameterValue { l: *v },
+                ),
+                TypedValue::ULLong(v) => (
+                    sys::VIR_TYPED_PARAM_ULLONG,
+                    sys::_virTypedParameterValue { ul: *v },
+                ),
+                TypedValue::Double(v) => (
+                    sys::VIR_TYPED_PARAM_DOUBLE,
+                    sys::_virTypedParameterValue { d: *v },
+                ),
+                TypedValue::Boolean(v) => (
+                    sys::VIR_TYPED_PARAM_BOOLEAN,
+                    sys::_virTypedParameterValue {
+                        b: *v as libc::c_char,
+                    },
+                ),
+                TypedValue::String(v) => (
+                    sys::VIR_TYPED_PARAM_STRING,
+                    sys::_virTypedParameterValue {
+                        s: to_malloc_c_chars(v)?,
+                    },
+                ),
+            };
+            params.params.push(sys::virTypedParameter {
+                field: to_arr(name),
+                type_: type_ as libc::c_int,
+                value,
+            });
+        }
+        Ok(mem::take(&mut params.params))
+    }
+
+    /// Returns the first value of the parameter `name`.
+    pub fn get(&self, name: &str) -> Option<&TypedValue> {
+        self.params.iter().find(|(n, _)| n == name).map(|(_, v)| v)
+    }
+
+    /// Returns every value of the parameter `name`.
+    pub fn get_all<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a TypedValue> + 'a {
+        self.params
+            .iter()
+            .filter(move |(n, _)| n == name)
+            .map(|(_, v)| v)
+    }
+
+    /// Sets the parameter `name`, replacing all its values.
+    pub fn insert(&mut self, name: &str, value: TypedValue) {
+        self.remove(name);
+        self.params.push((name.to_string(), value));
+    }
+
+    /// Adds a value to the parameter `name`, keeping the existing ones.
+    pub fn append(&mut self, name: &str, value: TypedValue) {
+        self.params.push((name.to_string(), value));
+    }
+
+    /// Removes all the values of the parameter `name`.
+    pub fn remove(&mut self, name: &str) {
+        self.params.retain(|(n, _)| n != name);
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &TypedValue)> + '_ {
+        self.params.iter().map(|(n, v)| (n.as_str(), v))
+    }
+
+    pub fn len(&self) -> usize {
+        self.params.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.params.is_empty()
+    }
 }
 
 fn to_arr(name: &str) -> [libc::c_char; 80] {
@@ -208,8 +512,8 @@ fn to_arr(name: &str) -> [libc::c_char; 80] {
 
 #[cfg(test)]
 mod test {
-
-    use crate::typedparams::{from_params, to_params};
+    use crate::domain::Limit;
+    use crate::typedparams::{from_params, to_params, TypedParamList, TypedParamsMap, TypedValue};
 
     #[derive(PartialEq, Debug)]
     struct Demo {
@@ -217,6 +521,7 @@ mod test {
         vu32: Option<u32>,
         vi64: Option<i64>,
         vu64: Option<u64>,
+        vlimit: Option<Limit>,
         vf64: Option<f64>,
         vbool: Option<bool>,
         vstring: Option<String>,
@@ -229,6 +534,7 @@ mod test {
                 $dir!(b"uint32\0", UInt32, $var.vu32),
                 $dir!(b"int64\0", Int64, $var.vi64),
                 $dir!(b"uint64\0", UInt64, $var.vu64),
+                $dir!(b"limit\0", Limit, $var.vlimit),
                 $dir!(b"float64\0", Float64, $var.vf64),
                 $dir!(b"bool\0", Bool, $var.vbool),
                 $dir!(b"string\0", String, $var.vstring),
@@ -238,19 +544,20 @@ mod test {
 
     fn roundtrip(demoout: Demo) {
         let fieldsout = fields!(param_field_out, demoout);
-        let params: Vec<sys::virTypedParameter> = to_params(fieldsout);
+        let params = TypedParamList::from_vec(to_params(fieldsout).unwrap());
 
         let mut demoin: Demo = Demo {
             vi32: None,
             vu32: None,
             vi64: None,
             vu64: None,
+            vlimit: None,
             vf64: None,
             vbool: None,
             vstring: None,
         };
         let fieldsin = fields!(param_field_in, demoin);
-        from_params(params, fieldsin);
+        from_params(&params.params(), fieldsin);
 
         assert!(demoin == demoout);
     }
@@ -262,6 +569,7 @@ mod test {
             vu32: Some(1729),
             vi64: Some(-87539319),
             vu64: Some(87539319),
+            vlimit: Some(Limit::Unlimited),
             vf64: Some(87539319.0),
             vbool: Some(true),
             vstring: Some("it is a very interesting number".to_string()),
@@ -276,10 +584,75 @@ mod test {
             vu32: None,
             vi64: Some(-87539319),
             vu64: Some(87539319),
+            vlimit: Some(Limit::Value(1729)),
             vf64: None,
             vbool: None,
             vstring: Some("it is a very interesting number".to_string()),
         };
         roundtrip(demo);
     }
+
+    #[test]
+    fn test_map_roundtrip() {
+        let mut map = TypedParamsMap::new();
+        map.insert("vendor.int", TypedValue::Int(-3));
+        map.insert("vendor.ullong", TypedValue::ULLong(1 << 40));
+        map.insert("vendor.bool", TypedValue::Boolean(true));
+        map.append("vendor.string", TypedValue::String("a".to_string()));
+        map.append("vendor.string", TypedValue::String("b".to_string()));
+        map.insert("vendor.int", TypedValue::Int(7));
+
+        let params = TypedParamList::from_vec(map.to_vec().unwrap());
+        let copy = TypedParamsMap::from_vec(params.params());
+
+        assert_eq!(5, copy.len());
+        assert_eq!(Some(7), copy.get("vendor.int").and_then(|v| v.as_i64()));
+        assert_eq!(
+            Some(1 << 40),
+            copy.get("vendor.ullong").and_then(|v| v.as_u64())
+        );
+        assert_eq!(
+            Some(true),
+            copy.get("vendor.bool").and_then(|v| v.as_bool())
+        );
+        assert_eq!(
+            vec!["a", "b"],
+            copy.get_all("vendor.string")
+                .filter_map(|v| v.as_str())
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(None, copy.get("missing"));
+        assert_eq!(None, TypedValue::Int(-1).as_u64());
+    }
+
+    #[test]
+    fn test_unexpected_params() {
+        let mut map = TypedParamsMap::new();
+        map.insert("int32", TypedValue::String("not a number".to_string()));
+        map.insert("uint64", TypedValue::ULLong(42));
+        map.insert("vendor.extra", TypedValue::UInt(1));
+        let params = TypedParamList::from_vec(map.to_vec().unwrap());
+
+        let mut demo = Demo {
+            vi32: None,
+            vu32: None,
+            vi64: None,
+            vu64: None,
+            vlimit: None,
+            vf64: None,
+            vbool: None,
+            vstring: None,
+        };
+        let fields = fields!(param_field_in, demo);
+        let extra = from_params(&params.params(), fields);
+
+        assert_eq!(None, demo.vi32);
+        assert_eq!(Some(42), demo.vu64);
+        assert_eq!(2, extra.len());
+        assert_eq!(
+            Some("not a number"),
+            extra.get("int32").and_then(|v| v.as_str())
+        );
+        assert_eq!(Some(1), extra.get("vendor.extra").and_then(|v| v.as_u64()));
+    }
 }
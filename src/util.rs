@@ -1,3 +1,7 @@
+use std::ptr;
+
+use crate::error::Error;
+
 macro_rules! impl_enum {
     (enum: $type:ty, raw: $raw:ty, match: { $($match_arms:tt)* }) => {
         impl $type {
@@ -15,6 +19,9 @@ macro_rules! impl_enum {
 }
 
 macro_rules! impl_enum_from {
+    (@acc ($e:expr, _ => $($variant:ident)::+ ($v:ident),) -> ($($body:tt)*)) => {
+        $crate::util::impl_enum_from!(@final ($e) -> ($($body)* $v => $($variant)::+($v),))
+    };
     (@acc ($e:expr, _ => $type:path => $_raw:path,) -> ($($body:tt)*)) => {
         $crate::util::impl_enum_from!(@final ($e) -> ($($body)* _ => $type,))
     };
@@ -33,6 +40,9 @@ macro_rules! impl_enum_from {
 }
 
 macro_rules! impl_enum_to {
+    (@acc ($e:expr, _ => $($variant:ident)::+ ($v:ident),) -> ($($body:tt)*)) => {
+        $crate::util::impl_enum_to!(@final ($e) -> ($($body)* $($variant)::+($v) => $v,))
+    };
     (@acc ($e:expr, _ => $type:path => $raw:path,) -> ($($body:tt)*)) => {
         $crate::util::impl_enum_to!(@final ($e) -> ($($body)* $type => $raw,))
     };
@@ -50,9 +60,260 @@ macro_rules! impl_enum_to {
     };
 }
 
+macro_rules! impl_flags {
+    (
+        $(#[$outer:meta])*
+        struct $name:ident: $raw:ty {
+            $(
+                $(#[$inner:meta])*
+                const $flag:ident = $value:path;
+            )*
+        }
+    ) => {
+        $(#[$outer])*
+        #[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, Default)]
+        pub struct $name($raw);
+
+        impl $name {
+            $(
+                $(#[$inner])*
+                pub const $flag: $name = $name($value as $raw);
+            )*
+
+            /// Returns an empty set of flags.
+            pub const fn empty() -> Self {
+                $name(0)
+            }
+
+            /// Builds the flags from raw libvirt bits, keeping unknown
+            /// bits so that newer libvirt flags can still be passed.
+            pub const fn from_bits(bits: $raw) -> Self {
+                $name(bits)
+            }
+
+            /// Returns the raw libvirt bits.
+            pub const fn bits(self) -> $raw {
+                self.0
+            }
+
+            /// Returns `true` if no flag is set.
+            pub const fn is_empty(self) -> bool {
+                self.0 == 0
+            }
+
+            /// Returns `true` if all the flags in `other` are set.
+            pub const fn contains(self, other: Self) -> bool {
+                self.0 & other.0 == other.0
+            }
+
+            /// Sets the flags in `other`.
+            pub fn insert(&mut self, other: Self) {
+                self.0 |= other.0;
+            }
+
+            /// Clears the flags in `other`.
+            pub fn remove(&mut self, other: Self) {
+                self.0 &= !other.0;
+            }
+        }
+
+        impl ::std::ops::BitOr for $name {
+            type Output = Self;
+
+            fn bitor(self, other: Self) -> Self {
+                $name(self.0 | other.0)
+            }
+        }
+
+        impl ::std::ops::BitOrAssign for $name {
+            fn bitor_assign(&mut self, other: Self) {
+                self.0 |= other.0;
+            }
+        }
+
+        impl ::std::ops::BitAnd for $name {
+            type Output = Self;
+
+            fn bitand(self, other: Self) -> Self {
+                $name(self.0 & other.0)
+            }
+        }
+
+        impl From<$raw> for $name {
+            fn from(bits: $raw) -> $name {
+                $name(bits)
+            }
+        }
+
+        impl From<$name> for $raw {
+            fn from(flags: $name) -> $raw {
+                flags.0
+            }
+        }
+    };
+}
+
+/// Implements `PartialEq`, `Eq` and `Hash` for a libvirt object
+/// based on a stable identifier returned by `$id`. The identifier is
+/// read once by `load_identity()` when the object is created and kept
+/// in its `identity` field, so that comparing or hashing an object
+/// never calls into libvirt. When it cannot be read, the object is
+/// identified by its pointer instead. Objects identified by `get_uuid` also get
+/// a `TryFrom<&$type> for Uuid` conversion.
+macro_rules! impl_identity {
+    ($type:ident, get_uuid) => {
+        $crate::util::impl_identity!(@eq $type, get_uuid);
+
+        impl ::std::convert::TryFrom<&$type> for ::uuid::Uuid {
+            type Error = $crate::error::Error;
+
+            fn try_from(obj: &$type) -> Result<Self, Self::Error> {
+                obj.get_uuid()
+            }
+        }
+    };
+    ($type:ident, $id:ident) => {
+        $crate::util::impl_identity!(@eq $type, $id);
+    };
+    (@eq $type:ident, $id:ident) => {
+        impl $type {
+            /// Reads the identifier compared by `PartialEq` and `Hash`,
+            /// which libvirt keeps in the handle itself.
+            fn load_identity(mut self) -> Self {
+                self.identity = self.$id().ok();
+                self
+            }
+        }
+
+        #[doc = concat!(
+            "Two objects are equal when their `",
+            stringify!($id),
+            "()`, read when they were created, match. An object whose `",
+            stringify!($id),
+            "()` failed only equals the handles sharing its pointer."
+        )]
+        impl PartialEq for $type {
+            fn eq(&self, other: &Self) -> bool {
+                match (&self.identity, &other.identity) {
+                    (Some(id), Some(other_id)) => id == other_id,
+                    (None, None) => self.ptr == other.ptr,
+                    _ => false,
+                }
+            }
+        }
+
+        impl Eq for $type {}
+
+        impl ::std::hash::Hash for $type {
+            fn hash<H: ::std::hash::Hasher>(&self, state: &mut H) {
+                match &self.identity {
+                    Some(id) => id.hash(state),
+                    None => self.ptr.hash(state),
+                }
+            }
+        }
+    };
+}
+
 pub(crate) use impl_enum;
 pub(crate) use impl_enum_from;
 pub(crate) use impl_enum_to;
+pub(crate) use impl_flags;
+pub(crate) use impl_identity;
+
+#[cfg(feature = "tracing")]
+thread_local! {
+    static LAST_FFI_CALL: std::cell::Cell<&'static str> = const { std::cell::Cell::new("") };
+}
+
+/// A call into libvirt in progress, see the `ffi!` macro.
+///
+/// Holds a trace-level span for the duration of the call and records
+/// the elapsed time when dropped.
+#[cfg(feature = "tracing")]
+pub(crate) struct FfiCall {
+    span: tracing::span::EnteredSpan,
+    function: &'static str,
+    start: std::time::Instant,
+}
+
+#[cfg(feature = "tracing")]
+impl FfiCall {
+    pub(crate) fn enter(function: &'static str) -> FfiCall {
+        LAST_FFI_CALL.with(|last| last.set(function));
+        FfiCall {
+            span: tracing::trace_span!("libvirt", function).entered(),
+            function,
+            start: std::time::Instant::now(),
+        }
+    }
+
+    /// Returns the name of the last libvirt function called on this thread.
+    pub(crate) fn last() -> &'static str {
+        LAST_FFI_CALL.with(|last| last.get())
+    }
+}
+
+#[cfg(feature = "tracing")]
+impl Drop for FfiCall {
+    fn drop(&mut self) {
+        let elapsed = self.start.elapsed();
+        tracing::trace!(
+            parent: self.span.id(),
+            function = self.function,
+            elapsed_us = elapsed.as_micros() as u64,
+            "libvirt call returned"
+        );
+    }
+}
+
+/// Calls one of the listing APIs filling an array allocated by the
+/// caller, sizing the array with the result of `count`, i.e. the
+/// matching `NumOf` API.
+///
+/// The array has a spare entry: when it is filled anyway, the list grew
+/// between the two calls and is fetched again rather than truncated.
+/// `release` frees the entries of such a discarded array.
+pub(crate) fn list_array<T: Copy>(
+    empty: T,
+    count: impl Fn() -> libc::c_int,
+    list: impl Fn(*mut T, libc::c_int) -> libc::c_int,
+    release: impl Fn(&[T]),
+) -> Result<Vec<T>, Error> {
+    loop {
+        let num = count();
+        if num == -1 {
+            return Err(Error::last_error());
+        }
+        let mut array = vec![empty; num as usize + 1];
+        let size = list(array.as_mut_ptr(), array.len() as libc::c_int);
+        if size == -1 {
+            return Err(Error::last_error());
+        }
+        let size = size as usize;
+        if size < array.len() {
+            array.truncate(size);
+            return Ok(array);
+        }
+        release(&array);
+    }
+}
+
+/// Calls one of the listing APIs returning names, see [`list_array()`].
+pub(crate) fn list_names(
+    count: impl Fn() -> libc::c_int,
+    list: impl Fn(*mut *mut libc::c_char, libc::c_int) -> libc::c_int,
+) -> Result<Vec<String>, Error> {
+    let names = list_array(ptr::null_mut(), count, list, |names| {
+        for name in names {
+            unsafe { libc::free(*name as *mut libc::c_void) };
+        }
+    })?;
+    Ok(names
+        .into_iter()
+        .map(|name| unsafe { c_chars_to_string!(name) })
+        .collect())
+}
 
 #[cfg(all(target_pointer_width = "64", not(windows)))]
 pub fn c_ulong_to_u64(val: ::libc::c_ulong) -> u64 {
@@ -66,7 +327,6 @@ pub fn c_ulong_to_u64(val: ::libc::c_ulong) -> u64 {
 
 #[cfg(test)]
 mod tests {
-    use super::*;
 
     const FOO: u32 = 0;
     const BAR: u32 = 1;
@@ -87,6 +347,14 @@ mod tests {
         Last,
     }
 
+    impl_flags! {
+        struct TestFlags: u32 {
+            const FOO = FOO;
+            const BAR = BAR;
+            const BAZ = BAZ;
+        }
+    }
+
     impl_enum! {
         enum: WithoutLast,
         raw: u32,
@@ -163,4 +431,19 @@ mod tests {
             assert_eq!(variant.to_raw(), expected);
         }
     }
+
+    #[test]
+    fn test_flags_ops() {
+        let mut flags = TestFlags::BAR | TestFlags::BAZ;
+        assert_eq!(flags.bits(), BAR | BAZ);
+        assert!(flags.contains(TestFlags::BAR));
+        assert!(TestFlags::FOO.is_empty());
+
+        flags.remove(TestFlags::BAR);
+        assert_eq!(u32::from(flags), BAZ);
+        flags.insert(TestFlags::from_bits(8));
+        assert_eq!(flags, TestFlags::from_bits(BAZ | 8));
+        assert_eq!(flags & TestFlags::BAZ, TestFlags::BAZ);
+        assert_eq!(TestFlags::default(), TestFlags::empty());
+    }
 }
@@ -0,0 +1,261 @@
+/*
+ * This library is free software; you can redistribute it and/or
+ * modify it under the terms of the GNU Lesser General Public
+ * License as published by the Free Software Foundation; either
+ * version 2.1 of the License, or (at your option) any later version.
+ *
+ * This library is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+ * Lesser General Public License for more details.
+ *
+ * You should have received a copy of the GNU Lesser General Public
+ * License along with this library.  If not, see
+ * <https://www.gnu.org/licenses/>.
+ *
+ * Sahid Orentino Ferdjaoui <sahid.ferdjaoui@redhat.com>
+ */
+
+//! Minimal XML tree used to give typed access to the XML documents
+//! returned by libvirt.
+
+use xml::common::Position;
+use xml::reader::{EventReader, XmlEvent};
+use xml::writer::EmitterConfig;
+
+use crate::error::Error;
+
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Element {
+    pub name: String,
+    pub attrs: Vec<(String, String)>,
+    pub children: Vec<Element>,
+    pub text: String,
+}
+
+impl Element {
+    /// Parses `xml` and returns its root element.
+    pub fn parse(xml: &str) -> Result<Element, Error> {
+        let mut stack: Vec<Element> = Vec::new();
+        for event in EventReader::from_str(xml) {
+            match event.map_err(|e| Error::from_xml_error(e.to_string()))? {
+                XmlEvent::StartElement {
+                    name, attributes, ..
+                } => stack.push(Element {
+                    name: name.local_name,
+                    attrs: attributes
+                        .into_iter()
+                        .map(|a| (a.name.local_name, a.value))
+                        .collect(),
+                    ..Default::default()
+                }),
+                XmlEvent::EndElement { .. } => {
+                    let elem = stack.pop().unwrap();
+                    match stack.last_mut() {
+                        Some(parent) => parent.children.push(elem),
+                        None => return Ok(elem),
+                    }
+                }
+                XmlEvent::Characters(s) | XmlEvent::CData(s) => {
+                    if let Some(elem) = stack.last_mut() {
+                        elem.text.push_str(&s);
+                    }
+                }
+                _ => {}
+            }
+        }
+        Err(Error::from_xml_error("no root element found".into()))
+    }
+
+    /// Returns the value of the attribute `name`.
+    pub fn attr(&self, name: &str) -> Option<&str> {
+        self.attrs
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Returns the first child element named `name`.
+    pub fn child(&self, name: &str) -> Option<&Element> {
+        self.children.iter().find(|c| c.name == name)
+    }
+
+    /// Returns all the child elements named `name`.
+    pub fn children<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Element> + 'a {
+        self.children.iter().filter(move |c| c.name == name)
+    }
+
+    /// Returns the trimmed text content of the element.
+    pub fn text(&self) -> &str {
+        self.text.trim()
+    }
+}
+
+/// Replaces the name of the domain described by `xml` and drops its
+/// UUID, so that defining it cannot clash with an existing domain.
+///
+/// On failure, returns the line the document is malformed at, if
+/// known, and the reason.
+pub(crate) fn anonymize_domain_xml(xml: &str, name: &str) -> Result<String, (Option<u32>, String)> {
+    let mut out = Vec::new();
+    let mut writer = EmitterConfig::new()
+        .write_document_declaration(false)
+        .create_writer(&mut out);
+    let mut depth = 0;
+    // The depth of the top level element being rewritten, and whether
+    // it is dropped altogether.
+    let mut replacing: Option<(usize, bool)> = None;
+    for event in EventReader::from_str(xml) {
+        let event = event.map_err(|e| (Some(e.position().row as u32 + 1), e.msg().to_string()))?;
+        let mut extra = None;
+        match &event {
+            XmlEvent::StartDocument { .. } | XmlEvent::EndDocument => continue,
+            XmlEvent::StartElement { name: elem, .. } => {
+                depth += 1;
+                if replacing.is_some() {
+                    continue;
+                }
+                if depth == 2 {
+                    match elem.local_name.as_str() {
+                        "name" => {
+                            replacing = Some((depth, false));
+                            extra = Some(name);
+                        }
+                        "uuid" => {
+                            replacing = Some((depth, true));
+                            continue;
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            XmlEvent::EndElement { .. } => {
+                depth -= 1;
+                if let Some((d, dropped)) = replacing {
+                    if depth >= d {
+                        continue;
+                    }
+                    replacing = None;
+                    if dropped {
+                        continue;
+                    }
+                }
+            }
+            _ => {
+                if replacing.is_some() {
+                    continue;
+                }
+            }
+        }
+        if let Some(ev) = event.as_writer_event() {
+            writer.write(ev).map_err(|e| (None, e.to_string()))?;
+        }
+        if let Some(text) = extra {
+            writer
+                .write(xml::writer::XmlEvent::characters(text))
+                .map_err(|e| (None, e.to_string()))?;
+        }
+    }
+    Ok(String::from_utf8_lossy(&out).into_owned())
+}
+
+/// Adds an `<alias name='...'/>` element to the device described by
+/// `xml`, as the last child of its root element.
+pub(crate) fn add_device_alias(xml: &str, alias: &str) -> Result<String, Error> {
+    let mut out = Vec::new();
+    let mut writer = EmitterConfig::new()
+        .write_document_declaration(false)
+        .create_writer(&mut out);
+    let mut depth = 0;
+    for event in EventReader::from_str(xml) {
+        let event = event.map_err(|e| Error::from_xml_error(e.to_string()))?;
+        match &event {
+            XmlEvent::StartDocument { .. } | XmlEvent::EndDocument => continue,
+            XmlEvent::StartElement { .. } => depth += 1,
+            XmlEvent::EndElement { .. } => {
+                depth -= 1;
+                if depth == 0 {
+                    writer
+                        .write(xml::writer::XmlEvent::start_element("alias").attr("name", alias))
+                        .and_then(|_| writer.write(xml::writer::XmlEvent::end_element()))
+                        .map_err(|e| Error::from_xml_error(e.to_string()))?;
+                }
+            }
+            _ => {}
+        }
+        if let Some(ev) = event.as_writer_event() {
+            writer
+                .write(ev)
+                .map_err(|e| Error::from_xml_error(e.to_string()))?;
+        }
+    }
+    Ok(String::from_utf8_lossy(&out).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{add_device_alias, anonymize_domain_xml, Element};
+
+    #[test]
+    fn test_parse() {
+        let xml = "<root a='1'>\n  <item>one</item>\n  <item b=\"x\">two</item>\n  <sub><leaf>&lt;3</leaf></sub>\n</root>";
+        let root = Element::parse(xml).unwrap();
+        assert_eq!("root", root.name);
+        assert_eq!(Some("1"), root.attr("a"));
+        assert_eq!(None, root.attr("b"));
+        let items: Vec<&str> = root.children("item").map(|i| i.text()).collect();
+        assert_eq!(vec!["one", "two"], items);
+        assert_eq!(
+            Some("x"),
+            root.child("item").and_then(|_| root.children[1].attr("b"))
+        );
+        let leaf = root.child("sub").and_then(|s| s.child("leaf"));
+        assert_eq!(Some("<3"), leaf.map(|e| e.text()));
+        assert!(root.child("none").is_none());
+    }
+
+    #[test]
+    fn test_anonymize_domain_xml() {
+        let xml = "<domain type='test'>\n  <name>foo</name>\n  <uuid>1234</uuid>\n  \
+                   <metadata><name>bar</name></metadata>\n</domain>";
+        let out = anonymize_domain_xml(xml, "tmp").unwrap();
+        let root = Element::parse(&out).unwrap();
+        assert_eq!(Some("tmp"), root.child("name").map(|e| e.text()));
+        assert!(root.child("uuid").is_none());
+        let meta = root.child("metadata").and_then(|m| m.child("name"));
+        assert_eq!(Some("bar"), meta.map(|e| e.text()));
+
+        let err = anonymize_domain_xml("<domain>\n<name>\n</domain>", "tmp").unwrap_err();
+        assert_eq!(Some(3), err.0);
+    }
+
+    #[test]
+    fn test_add_device_alias() {
+        let out = add_device_alias("<disk type='file'><target dev='vdb'/></disk>", "ua-1").unwrap();
+        let root = Element::parse(&out).unwrap();
+        assert_eq!("disk", root.name);
+        assert_eq!(
+            Some("vdb"),
+            root.child("target").and_then(|t| t.attr("dev"))
+        );
+        assert_eq!(
+            Some("ua-1"),
+            root.child("alias").and_then(|a| a.attr("name"))
+        );
+
+        let out = add_device_alias("<watchdog model='i6300esb'/>", "ua-2").unwrap();
+        let root = Element::parse(&out).unwrap();
+        assert_eq!(
+            Some("ua-2"),
+            root.child("alias").and_then(|a| a.attr("name"))
+        );
+
+        assert!(add_device_alias("<disk>", "ua-3").is_err());
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        assert!(Element::parse("<root>").is_err());
+        assert!(Element::parse("").is_err());
+    }
+}
@@ -27,9 +27,10 @@ use virt::interface::Interface;
 use virt::network::Network;
 use virt::storage_pool::StoragePool;
 use virt::storage_vol::StorageVol;
+use virt::testing;
 
 pub fn conn() -> Connect {
-    Connect::open(Some("test:///default")).unwrap()
+    testing::conn()
 }
 
 pub fn qemu_conn() -> Connect {
@@ -101,117 +102,21 @@ pub fn build_qemu_domain(conn: &Connect, name: &str, transient: bool) -> Domain
 }
 
 pub fn build_test_domain(conn: &Connect, name: &str, transient: bool) -> Domain {
-    let name = format!("libvirt-rs-test-{}", name);
-
-    if let Ok(dom) = Domain::lookup_by_name(conn, &name) {
-        clean(dom);
-    }
-
-    let xml = format!(
-        "<domain type=\"test\">
-		         <name>{}</name>
-                         <memory unit=\"KiB\">128</memory>
-                         <features>
-                           <acpi/>
-                           <apic/>
-                         </features>
-                         <os>
-                           <type>hvm</type>
-                         </os>
-                       </domain>",
-        name
-    );
-
-    let result: Result<Domain, Error> = if transient {
-        Domain::create_xml(conn, &xml, 0)
-    } else {
-        Domain::define_xml(conn, &xml)
-    };
-
-    result.unwrap()
+    testing::build_test_domain(conn, name, transient).into_inner()
 }
 
 pub fn build_storage_pool(conn: &Connect, name: &str, transient: bool) -> StoragePool {
-    let name = format!("libvirt-rs-test-{}", name);
-
-    if let Ok(pool) = StoragePool::lookup_by_name(conn, &name) {
-        clean_pool(pool);
-    }
-
-    let xml = format!(
-        "<pool type='dir'>
-                          <name>{}</name>
-                            <target>
-                              <path>/var/lib/libvirt/images</path>
-                            </target>
-                          </pool>",
-        name
-    );
-
-    let result: Result<StoragePool, Error> = if transient {
-        StoragePool::create_xml(conn, &xml, 0)
-    } else {
-        StoragePool::define_xml(conn, &xml, 0)
-    };
-
-    result.unwrap()
+    testing::build_storage_pool(conn, name, transient).into_inner()
 }
 
 pub fn build_storage_vol(pool: &StoragePool, name: &str, size: u64) -> StorageVol {
-    if let Ok(vol) = StorageVol::lookup_by_name(pool, name) {
-        return vol;
-    }
-
-    let xml = format!(
-        "<volume type='file'>
-                         <name>{}</name>
-                         <allocation unit='Kib'>{}</allocation>
-                         <capacity unit='Kib'>{}</capacity>
-                       </volume>",
-        name, size, size
-    );
-    StorageVol::create_xml(pool, &xml, 0).unwrap()
+    testing::build_storage_vol(pool, name, size).into_inner()
 }
 
 pub fn build_network(conn: &Connect, name: &str, transient: bool) -> Network {
-    let name = format!("libvirt-rs-test-{}", name);
-
-    if let Ok(net) = Network::lookup_by_name(conn, &name) {
-        clean_net(net);
-    }
-
-    let xml = format!(
-        "<network>
-                         <name>{}</name>
-                         <bridge name='testbr0'/>
-                         <forward/>
-                         <ip address='192.168.0.1' netmask='255.255.255.0'></ip>
-                       </network>",
-        name
-    );
-
-    let result: Result<Network, Error> = if transient {
-        Network::create_xml(conn, &xml)
-    } else {
-        Network::define_xml(conn, &xml)
-    };
-
-    result.unwrap()
+    testing::build_network(conn, name, transient).into_inner()
 }
 
 pub fn build_interface(conn: &Connect, name: &str) -> Interface {
-    let name = format!("libvirt-rs-test-{}", name);
-
-    if let Ok(iface) = Interface::lookup_by_name(conn, &name) {
-        clean_iface(iface);
-    }
-
-    let xml = format!(
-        "<interface type='ethernet' name='{}'>
-                         <mac address='aa:bb:cc:dd:ee:ff'/>
-                       </interface>",
-        name
-    );
-
-    Interface::define_xml(conn, &xml, 0).unwrap()
+    testing::build_interface(conn, name).into_inner()
 }
@@ -18,7 +18,37 @@
 
 mod common;
 
-use virt::connect::Connect;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use virt::connect::{
+    Connect, ConnectListAllDomainsFlags, ConnectListAllInterfacesFlags,
+    ConnectListAllNetworksFlags, ConnectListAllNodeDeviceFlags, ConnectListAllSecretsFlags,
+    ConnectListAllStoragePoolsFlags, CpuModelUsability, DomainCapsCpuModel, DomainStatsFilter,
+    HostCapabilities, LibVersion, SysInfo,
+};
+use virt::domain::{Domain, DomainDefineFlags, DomainStatsGroups};
+use virt::error::ErrorNumber;
+use virt::sys;
+
+#[test]
+fn test_conn_error_callback() {
+    let c = common::conn();
+    let count = Arc::new(AtomicUsize::new(0));
+    let seen = count.clone();
+    c.set_error_callback(move |e| {
+        if e.is_not_found() {
+            seen.fetch_add(1, Ordering::SeqCst);
+        }
+    });
+    assert!(virt::domain::Domain::lookup_by_name(&c, "no-such-domain").is_err());
+    assert_eq!(1, count.load(Ordering::SeqCst));
+
+    c.clear_error_callback();
+    assert!(virt::domain::Domain::lookup_by_name(&c, "no-such-domain").is_err());
+    assert_eq!(1, count.load(Ordering::SeqCst));
+    common::close(c);
+}
 
 #[test]
 fn test_version() {
@@ -133,6 +163,16 @@ fn test_get_node_info() {
     common::close(c);
 }
 
+#[test]
+fn test_get_node_cpu_map() {
+    let c = common::conn();
+    let (total, map, online) = c.get_node_cpu_map(0).unwrap();
+    assert!(total > 0);
+    assert!(map.max_cpus() >= total);
+    assert_eq!(online as usize, map.iter().count());
+    common::close(c);
+}
+
 #[test]
 fn test_hostname() {
     let c = common::conn();
@@ -159,6 +199,7 @@ fn test_lib_version() {
 }
 
 #[test]
+#[allow(deprecated)]
 fn test_list_domains() {
     let c = common::conn();
     assert!(
@@ -169,6 +210,7 @@ fn test_list_domains() {
 }
 
 #[test]
+#[allow(deprecated)]
 fn test_list_interfaces() {
     let c = common::conn();
     assert!(
@@ -179,6 +221,7 @@ fn test_list_interfaces() {
 }
 
 #[test]
+#[allow(deprecated)]
 fn test_list_networks() {
     let c = common::conn();
     assert!(
@@ -189,6 +232,7 @@ fn test_list_networks() {
 }
 
 #[test]
+#[allow(deprecated)]
 fn test_list_storage_pools() {
     let c = common::conn();
     assert!(
@@ -198,6 +242,50 @@ fn test_list_storage_pools() {
     common::close(c);
 }
 
+#[test]
+fn test_iterators() {
+    let c = common::conn();
+    let names = c
+        .domains(ConnectListAllDomainsFlags::ACTIVE)
+        .unwrap()
+        .map(|d| d.get_name().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(vec!["test".to_string()], names);
+    assert_eq!(
+        0,
+        c.domains(ConnectListAllDomainsFlags::INACTIVE)
+            .unwrap()
+            .count()
+    );
+    assert!(
+        c.networks(ConnectListAllNetworksFlags::empty())
+            .unwrap()
+            .count()
+            > 0
+    );
+    assert!(
+        c.interfaces(ConnectListAllInterfacesFlags::empty())
+            .unwrap()
+            .count()
+            > 0
+    );
+    assert!(
+        c.storage_pools(ConnectListAllStoragePoolsFlags::empty())
+            .unwrap()
+            .count()
+            > 0
+    );
+    assert!(
+        c.node_devices(ConnectListAllNodeDeviceFlags::empty())
+            .unwrap()
+            .count()
+            > 0
+    );
+    assert!(c.secrets(ConnectListAllSecretsFlags::empty()).is_ok());
+    assert!(c.nw_filters().is_ok());
+    common::close(c);
+}
+
 #[test]
 fn test_list_all_domains() {
     let c = common::conn();
@@ -207,6 +295,31 @@ fn test_list_all_domains() {
     common::close(c);
 }
 
+#[test]
+fn test_list_all_domains_filtered() {
+    let c = common::conn();
+    let d = common::build_test_domain(&c, "list_all_filtered", false);
+
+    let flags = ConnectListAllDomainsFlags::INACTIVE | ConnectListAllDomainsFlags::PERSISTENT;
+    let names: Vec<String> = c
+        .list_all_domains(flags)
+        .unwrap()
+        .iter()
+        .map(|dom| dom.get_name().unwrap())
+        .collect();
+    assert!(names.contains(&"list_all_filtered".to_string()));
+    assert!(!names.contains(&"test".to_string()));
+
+    let running = c
+        .list_all_domains(ConnectListAllDomainsFlags::RUNNING)
+        .unwrap();
+    assert!(running.iter().all(|dom| dom.is_active().unwrap()));
+    drop(running);
+
+    common::clean(d);
+    common::close(c);
+}
+
 /* Travis is failing on this test
 #[test]
 fn test_get_cpu_models_names() {
@@ -234,3 +347,279 @@ fn test_get_cells_free_memory() {
     assert!(free[1] == 4194304, "Invalid free pages for NUMA node 1");
     common::close(c);
 }
+
+#[test]
+fn test_sysinfo_from_xml() {
+    let xml = "<sysinfo type='smbios'>
+  <bios>
+    <entry name='vendor'>LENOVO</entry>
+    <entry name='version'>N1CET86W (1.54 )</entry>
+  </bios>
+  <system>
+    <entry name='manufacturer'>LENOVO</entry>
+    <entry name='uuid'>d5f09b2a-3a6c-4b2e-8c9e-5d8b0f2c3a11</entry>
+  </system>
+  <processor>
+    <entry name='socket_destination'>CPU0</entry>
+    <entry name='type'>Central Processor</entry>
+  </processor>
+  <processor>
+    <entry name='socket_destination'>CPU1</entry>
+  </processor>
+  <memory_device>
+    <entry name='size'>16 GB</entry>
+    <entry name='type'>DDR4</entry>
+  </memory_device>
+</sysinfo>";
+    let info = SysInfo::from_xml(xml).unwrap();
+    let bios = info.bios.unwrap();
+    assert_eq!(Some("LENOVO".to_string()), bios.vendor);
+    assert_eq!(Some("N1CET86W (1.54 )".to_string()), bios.version);
+    assert_eq!(None, bios.date);
+    assert_eq!(
+        Some("d5f09b2a-3a6c-4b2e-8c9e-5d8b0f2c3a11".to_string()),
+        info.system.unwrap().uuid
+    );
+    assert_eq!(2, info.processors.len());
+    assert_eq!(
+        Some("Central Processor".to_string()),
+        info.processors[0].processor_type
+    );
+    assert_eq!(
+        Some("CPU1".to_string()),
+        info.processors[1].socket_destination
+    );
+    assert_eq!(1, info.memory_devices.len());
+    assert_eq!(Some("16 GB".to_string()), info.memory_devices[0].size);
+    assert_eq!(Some("DDR4".to_string()), info.memory_devices[0].memory_type);
+}
+
+#[test]
+fn test_domain_event_block_threshold_register() {
+    assert_eq!(0, unsafe { sys::virEventRegisterDefaultImpl() });
+    let c = common::conn();
+    let id = c
+        .domain_event_block_threshold_register(None, |_dom, _event| {})
+        .unwrap();
+    assert_eq!(Ok(()), c.domain_event_deregister_any(id));
+    assert!(c.domain_event_deregister_any(id).is_err());
+    common::close(c);
+}
+
+#[test]
+fn test_secret_event_value_changed_register() {
+    assert_eq!(0, unsafe { sys::virEventRegisterDefaultImpl() });
+    let c = common::conn();
+    // The test driver has no secret driver.
+    let ret = c.secret_event_value_changed_register(None, |_secret| {});
+    assert_eq!(Some(ErrorNumber::NoSupport), ret.err().map(|e| e.code()));
+    assert_eq!(
+        Some(ErrorNumber::NoSupport),
+        c.secret_event_deregister_any(0).err().map(|e| e.code())
+    );
+    common::close(c);
+}
+
+#[test]
+fn test_lib_version_triple() {
+    let v = LibVersion::from_raw(9_010_002);
+    assert_eq!(LibVersion::new(9, 10, 2), v);
+    assert_eq!(9_010_002, v.to_raw());
+    assert_eq!("9.10.2", v.to_string());
+    assert!(LibVersion::new(10, 0, 0) > v);
+    assert!(LibVersion::new(9, 9, 99) < v);
+
+    let c = common::conn();
+    assert_eq!(
+        Ok(LibVersion::from_raw(c.get_hyp_version().unwrap())),
+        c.get_hyp_version_triple()
+    );
+    assert_eq!(
+        Connect::get_version().map(LibVersion::from),
+        Connect::get_version_triple()
+    );
+    common::close(c);
+}
+
+#[test]
+fn test_get_all_domain_stats_typed() {
+    let c = common::conn();
+    let records = c
+        .get_all_domain_stats(
+            DomainStatsGroups::STATE | DomainStatsGroups::CPU_TOTAL,
+            DomainStatsFilter::ACTIVE,
+        )
+        .unwrap();
+    assert!(!records.is_empty());
+    drop(records);
+    common::close(c);
+}
+
+#[test]
+fn test_validate_domain_xml() {
+    let c = common::conn();
+    let xml = "<domain type=\"test\">
+                 <name>test</name>
+                 <memory unit=\"KiB\">128</memory>
+                 <os>
+                   <type>hvm</type>
+                 </os>
+               </domain>";
+    let empty = DomainDefineFlags::empty();
+    assert_eq!(Ok(vec![]), c.validate_domain_xml(xml, empty));
+    assert!(c
+        .validate_domain_xml(xml, DomainDefineFlags::VALIDATE)
+        .unwrap()
+        .is_empty());
+    // The existing "test" domain must be left alone, and the throwaway
+    // one must be gone.
+    assert!(Domain::lookup_by_name(&c, "test")
+        .unwrap()
+        .is_active()
+        .unwrap());
+    assert!(!c
+        .domains(ConnectListAllDomainsFlags::empty())
+        .unwrap()
+        .any(|d| d.get_name().unwrap().starts_with("virt-validate-")));
+
+    let issues = c
+        .validate_domain_xml("<domain>\n<name>\n</domain>", empty)
+        .unwrap();
+    assert_eq!(Some(3), issues[0].line);
+    let issues = c.validate_domain_xml("<network/>", empty).unwrap();
+    assert_eq!(1, issues.len());
+    assert_eq!(None, issues[0].line);
+
+    let invalid = xml.replace("<os>", "<bogus/><os>");
+    assert_eq!(Ok(vec![]), c.validate_domain_xml(&invalid, empty));
+    assert!(!c
+        .validate_domain_xml(&invalid, DomainDefineFlags::VALIDATE)
+        .unwrap()
+        .is_empty());
+    common::close(c);
+}
+
+#[test]
+fn test_domain_caps_cpu_models_from_xml() {
+    let xml = "<domainCapabilities>
+  <cpu>
+    <mode name='host-passthrough' supported='yes'/>
+    <mode name='custom' supported='yes'>
+      <model usable='yes' vendor='Intel' canonical='Skylake-Client-v1'>Skylake-Client</model>
+      <model usable='no' vendor='AMD' deprecated='yes'>EPYC</model>
+      <model usable='unknown' vendor='unknown'>qemu64</model>
+      <blockers model='EPYC'>
+        <feature name='sha-ni'/>
+        <feature name='xsaves'/>
+      </blockers>
+    </mode>
+  </cpu>
+</domainCapabilities>";
+    let models = DomainCapsCpuModel::list_from_xml(xml).unwrap();
+    assert_eq!(3, models.len());
+    assert_eq!("Skylake-Client", models[0].name);
+    assert_eq!(CpuModelUsability::Usable, models[0].usable);
+    assert_eq!(Some("Skylake-Client-v1"), models[0].canonical.as_deref());
+    assert!(models[0].blockers.is_empty());
+    assert_eq!(CpuModelUsability::Unusable, models[1].usable);
+    assert!(models[1].deprecated);
+    assert_eq!(vec!["sha-ni", "xsaves"], models[1].blockers);
+    assert_eq!(CpuModelUsability::Unknown, models[2].usable);
+    assert_eq!(None, models[2].vendor);
+
+    assert!(DomainCapsCpuModel::list_from_xml("<domainCapabilities/>")
+        .unwrap()
+        .is_empty());
+}
+
+#[test]
+fn test_host_capabilities_from_xml() {
+    let xml = "<capabilities>
+  <host>
+    <uuid>7a2f4c1e-0b1d-4c5e-9f3a-2d6b8e1c0f44</uuid>
+    <cpu>
+      <arch>x86_64</arch>
+      <model>Skylake-Client-IBRS</model>
+      <vendor>Intel</vendor>
+      <topology sockets='1' dies='1' cores='4' threads='2'/>
+      <feature name='ds'/>
+      <feature name='vmx'/>
+    </cpu>
+    <topology>
+      <cells num='1'>
+        <cell id='0'>
+          <memory unit='KiB'>16284140</memory>
+          <distances>
+            <sibling id='0' value='10'/>
+          </distances>
+          <cpus num='2'>
+            <cpu id='0' socket_id='0' die_id='0' core_id='0' siblings='0-1'/>
+            <cpu id='1' socket_id='0' die_id='0' core_id='0' siblings='0-1'/>
+          </cpus>
+        </cell>
+      </cells>
+    </topology>
+  </host>
+  <guest>
+    <os_type>hvm</os_type>
+    <arch name='x86_64'>
+      <wordsize>64</wordsize>
+      <emulator>/usr/bin/qemu-system-x86_64</emulator>
+      <machine maxCpus='255'>pc-q35-8.2</machine>
+      <machine canonical='pc-q35-8.2' maxCpus='255'>q35</machine>
+      <domain type='qemu'/>
+      <domain type='kvm'/>
+    </arch>
+  </guest>
+</capabilities>";
+    let caps = HostCapabilities::from_xml(xml).unwrap();
+    assert_eq!(
+        Some("7a2f4c1e-0b1d-4c5e-9f3a-2d6b8e1c0f44".to_string()),
+        caps.uuid
+    );
+    assert_eq!("x86_64", caps.cpu.arch);
+    assert_eq!(Some("Intel".to_string()), caps.cpu.vendor);
+    assert_eq!(Some(4), caps.cpu.cores);
+    assert_eq!(vec!["ds", "vmx"], caps.cpu.features);
+    assert_eq!(1, caps.cells.len());
+    assert_eq!(16284140, caps.cells[0].memory);
+    assert_eq!(vec![(0, 10)], caps.cells[0].distances);
+    assert_eq!(2, caps.cells[0].cpus.len());
+    assert_eq!(Some("0-1".parse().unwrap()), caps.cells[0].cpus[1].siblings);
+    assert_eq!(vec!["pc-q35-8.2", "q35"], caps.guests[0].machines);
+    assert_eq!(Some(64), caps.guests[0].wordsize);
+    assert!(caps.supports("x86_64", "kvm"));
+    assert!(!caps.supports("aarch64", "kvm"));
+}
+
+#[test]
+fn test_host_capabilities() {
+    let c = common::conn();
+    let caps = c.host_capabilities().unwrap();
+    assert!(!caps.cells.is_empty());
+    assert!(caps.supports("i686", "test"));
+    common::close(c);
+}
+
+#[test]
+fn test_has_api() {
+    let c = common::conn();
+    let version = c.get_lib_version_triple().unwrap();
+    assert_eq!(Ok(true), c.has_api(LibVersion::new(0, 9, 0)));
+    assert_eq!(Ok(true), c.has_api(version));
+    assert_eq!(
+        Ok(false),
+        c.has_api(LibVersion::new(version.major + 1, 0, 0))
+    );
+    common::close(c);
+}
+
+#[test]
+fn test_get_node_cpu_stats() {
+    let c = common::conn();
+    match c.get_node_cpu_stats(None, 0) {
+        Ok(_) => {}
+        Err(e) => assert!(e.is_unsupported(), "{}", e),
+    }
+    common::close(c);
+}
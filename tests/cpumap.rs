@@ -0,0 +1,59 @@
+/*
+ * This library is free software; you can redistribute it and/or
+ * modify it under the terms of the GNU Lesser General Public
+ * License as published by the Free Software Foundation; either
+ * version 2.1 of the License, or (at your option) any later version.
+ *
+ * This library is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+ * Lesser General Public License for more details.
+ *
+ * You should have received a copy of the GNU Lesser General Public
+ * License along with this library.  If not, see
+ * <https://www.gnu.org/licenses/>.
+ *
+ * Sahid Orentino Ferdjaoui <sahid.ferdjaoui@redhat.com>
+ */
+
+use virt::cpumap::CpuMap;
+
+#[test]
+fn test_cpumap_bits() {
+    let mut map = CpuMap::new(12);
+    assert_eq!(&[0, 0], map.as_bytes());
+
+    map.set(0);
+    map.set(9);
+    assert_eq!(&[0b0000_0001, 0b0000_0010], map.as_bytes());
+    assert!(map.is_set(9));
+    assert!(!map.is_set(8));
+    assert!(!map.is_set(64));
+
+    map.set(17);
+    assert_eq!(3, map.as_bytes().len());
+    assert_eq!(vec![0, 9, 17], map.iter().collect::<Vec<u32>>());
+}
+
+#[test]
+fn test_cpumap_from_bytes() {
+    let map = CpuMap::from_bytes(&[0xf0, 0x01]);
+    assert_eq!(vec![4, 5, 6, 7, 8], map.iter().collect::<Vec<u32>>());
+}
+
+#[test]
+fn test_cpumap_clear_and_collect() {
+    assert_eq!(0, CpuMap::maplen(0));
+    assert_eq!(1, CpuMap::maplen(8));
+    assert_eq!(2, CpuMap::maplen(9));
+
+    let mut map: CpuMap = vec![1, 3, 10].into_iter().collect();
+    assert_eq!(16, map.max_cpus());
+    map.clear(3);
+    map.clear(100);
+    assert_eq!(vec![1, 10], map.iter().collect::<Vec<u32>>());
+
+    map.clear(1);
+    map.clear(10);
+    assert!(map.is_empty());
+}
@@ -18,11 +18,34 @@
 
 mod common;
 
+use std::collections::HashSet;
+use std::convert::TryFrom;
+use std::net::IpAddr;
+use std::time::{Duration, UNIX_EPOCH};
+
 use uuid::Uuid;
 
-use virt::domain::{Domain, MemoryParameters, NUMAParameters, SchedulerInfo};
+use virt::cpumap::CpuMap;
+use virt::domain::{
+    flags, linux_keycode, BlockCopyFlags, BlockCopyOptions, BlockCopyParameters, BlockJobInfo,
+    BlockJobType, BlockStats, CoreDumpOptions, DirtyRateStats, DirtyRateStatus, DiskBus, DiskCache,
+    DiskSpec, Domain, DomainCoreDumpFlags, DomainDeviceModifyFlags, DomainMemoryModFlags,
+    DomainUndefineFlags, DomainVcpuFlags, DomainXmlFlags, DumpFormat, GraphicsInfo, HostnameSource,
+    IOThreadParameters, InterfaceAddressSource, InterfaceSpec, JobOperation, JobStats,
+    JobStatsFlags, JobType, KeycodeSet, Limit, MacAddr, MemoryDevice, MemoryDeviceSpec,
+    MemoryModification, MemoryParameters, MemoryStat, MemoryStats, MigrateMaxSpeedOptions,
+    MigrateParameters, MigrationCompression, MigrationCompressionMethod, NUMAParameters,
+    NumatuneMemMode, RebootMethod, SchedulerInfo, ShutdownOutcome, UndefineOptions,
+};
+use virt::domain_snapshot::{
+    DomainSnapshot, SnapshotCreateFlags, SnapshotCreateOptions, SnapshotDeleteFlags,
+    SnapshotDiskMode, SnapshotDiskSpec, SnapshotInfo, SnapshotListFlags, SnapshotState,
+};
 use virt::error::ErrorNumber;
+use virt::flags::DomainModificationImpact;
+use virt::nodeset::NodeSet;
 use virt::sys;
+use virt::{TypedParamsMap, TypedValue};
 
 fn tdom(exec_test: fn(dom: Domain)) {
     let c = common::conn();
@@ -35,6 +58,141 @@ fn tdom(exec_test: fn(dom: Domain)) {
     }
 }
 
+#[test]
+fn test_lookup_error_classification() {
+    let c = common::conn();
+    let e = Domain::lookup_by_name(&c, "no-such-domain").unwrap_err();
+    assert_eq!(ErrorNumber::NoDomain, e.code());
+    assert!(e.is_not_found());
+    assert!(!e.is_busy());
+    assert!(!e.is_unsupported());
+    assert!(!e.is_disconnect());
+    common::close(c);
+}
+
+#[test]
+fn test_rename_inactive() {
+    fn t(dom: Domain) {
+        let e = dom.rename_inactive("renamed").unwrap_err();
+        assert_eq!(ErrorNumber::OperationInvalid, e.code());
+        assert_eq!(Ok("test".to_string()), dom.get_name());
+    }
+    tdom(t);
+
+    let c = common::conn();
+    let d = common::build_test_domain(&c, "rename_inactive", false);
+    assert_eq!(Ok(()), d.rename_inactive("rename_inactive_new"));
+    assert_eq!(Ok("rename_inactive_new".to_string()), d.get_name());
+    common::clean(d);
+    common::close(c);
+}
+
+#[test]
+fn test_lookup_interior_nul() {
+    let c = common::conn();
+    let e = Domain::lookup_by_name(&c, "te\0st").unwrap_err();
+    assert_eq!(ErrorNumber::InvalidArg, e.code());
+    common::close(c);
+}
+
+#[test]
+fn test_job_stats_typed() {
+    let stats = JobStats {
+        r#type: sys::VIR_DOMAIN_JOB_COMPLETED as i32,
+        operation: Some(sys::VIR_DOMAIN_JOB_OPERATION_MIGRATION_OUT as i32),
+        ..Default::default()
+    };
+    assert_eq!(JobType::Completed, stats.job_type());
+    assert_eq!(Some(JobOperation::MigrationOut), stats.job_operation());
+    assert_eq!(None, JobStats::default().job_operation());
+
+    fn t(dom: Domain) {
+        // The test driver never runs jobs and takes no flags.
+        let stats = dom.get_job_stats(JobStatsFlags::empty()).unwrap();
+        assert_eq!(JobType::None, stats.job_type());
+        assert_eq!(None, stats.job_operation());
+        assert_eq!(
+            Some(ErrorNumber::InvalidArg),
+            dom.get_completed_job_stats().err().map(|e| e.code())
+        );
+    }
+    tdom(t);
+}
+
+#[test]
+fn test_block_job_info() {
+    let info = BlockJobInfo {
+        job_type: BlockJobType::Copy,
+        bandwidth: 0,
+        cur: 10,
+        end: 10,
+    };
+    assert!(info.is_ready());
+    assert!(!BlockJobInfo {
+        job_type: BlockJobType::Pull,
+        ..info.clone()
+    }
+    .is_ready());
+
+    fn t(dom: Domain) {
+        let timeout = Duration::from_secs(1);
+        // The test driver does not implement block jobs.
+        let ret = dom.wait_for_block_job("vda", Duration::from_millis(10), timeout, |_, _| {});
+        assert_eq!(Some(ErrorNumber::NoSupport), ret.err().map(|e| e.code()));
+    }
+    tdom(t);
+}
+
+#[test]
+fn test_primary_ip_address() {
+    fn t(dom: Domain) {
+        // The test driver reports one IPv4 address per interface,
+        // whatever the source.
+        let ifaces = dom
+            .interface_addresses(InterfaceAddressSource::Agent, 0)
+            .unwrap();
+        let expected: IpAddr = ifaces[0].addrs[0].addr.parse().unwrap();
+        assert!(expected.is_ipv4());
+        assert_eq!(Ok(Some(expected)), dom.primary_ip_address());
+
+        assert_eq!(Ok("testhost".to_string()), dom.get_hostname(None));
+        assert_eq!(
+            Some(ErrorNumber::InvalidArg),
+            dom.get_hostname(Some(HostnameSource::Lease))
+                .err()
+                .map(|e| e.code())
+        );
+    }
+    tdom(t);
+
+    // A domain without interfaces has no address to report.
+    let c = common::conn();
+    let dom = common::build_test_domain(&c, "no_address", true);
+    assert_eq!(Ok(None), dom.primary_ip_address());
+    common::clean(dom);
+    common::close(c);
+}
+
+#[test]
+fn test_undefine_full() {
+    let opts = UndefineOptions {
+        managed_save: true,
+        remove_nvram: Some(false),
+        ..Default::default()
+    };
+    assert_eq!(
+        DomainUndefineFlags::MANAGED_SAVE | DomainUndefineFlags::KEEP_NVRAM,
+        opts.flags()
+    );
+
+    let c = common::conn();
+    let d = common::build_test_domain(&c, "undefine_full", false);
+    assert_eq!(Ok(()), d.undefine_full(&UndefineOptions::all()));
+    assert!(Domain::lookup_by_name(&c, "libvirt-rs-test-undefine_full").is_err());
+    drop(d);
+    common::close(c);
+}
+
 #[test]
 fn test_name() {
     fn t(dom: Domain) {
@@ -86,6 +244,22 @@ fn test_get_xml_desc() {
     tdom(t);
 }
 
+#[test]
+fn test_get_xml_variants() {
+    fn t(dom: Domain) {
+        let live = dom.get_xml_desc(DomainXmlFlags::SECURE).unwrap();
+        assert!(live.contains("id='1'"));
+        let inactive = dom.get_xml_inactive().unwrap();
+        assert!(inactive.contains("<name>test</name>"));
+        assert!(!inactive.contains("id='1'"));
+        assert!(dom
+            .get_xml_migratable()
+            .unwrap()
+            .contains("<name>test</name>"));
+    }
+    tdom(t);
+}
+
 #[test]
 fn test_get_info() {
     fn t(dom: Domain) {
@@ -97,6 +271,15 @@ fn test_get_info() {
     tdom(t);
 }
 
+#[test]
+fn test_info_display() {
+    fn t(dom: Domain) {
+        let info = dom.get_info().unwrap();
+        assert!(info.to_string().starts_with("state: running,"));
+    }
+    tdom(t);
+}
+
 #[test]
 fn test_get_vcpus_flags() {
     fn t(dom: Domain) {
@@ -105,6 +288,40 @@ fn test_get_vcpus_flags() {
     tdom(t);
 }
 
+#[test]
+fn test_memory_modification() {
+    assert_eq!(
+        DomainVcpuFlags::MAXIMUM | DomainVcpuFlags::CONFIG,
+        MemoryModification::Maximum.into()
+    );
+    assert_eq!(
+        DomainMemoryModFlags::LIVE | DomainMemoryModFlags::CONFIG,
+        MemoryModification::Both.into()
+    );
+
+    let c = common::conn();
+    let d = common::build_test_domain(&c, "memory_modification", false);
+    assert!(d.set_vcpus_flags(4, MemoryModification::Maximum).is_ok());
+    assert_eq!(Ok(4), d.get_vcpus_flags(MemoryModification::Maximum));
+    assert!(d.set_vcpus_flags(2, MemoryModification::Config).is_ok());
+    assert_eq!(Ok(2), d.get_vcpus_flags(MemoryModification::Config));
+    common::clean(d);
+    common::close(c);
+}
+
+#[test]
+fn test_vcpus_max() {
+    let c = common::conn();
+    let d = common::build_test_domain(&c, "vcpus_max", false);
+    assert!(d.set_vcpus_flags(3, MemoryModification::Maximum).is_ok());
+    assert_eq!(Ok(3), d.get_vcpus_max_config());
+    assert!(d.get_vcpus_max_live().is_err());
+    assert_eq!(Ok(0), d.create());
+    assert_eq!(Ok(3), d.get_vcpus_max_live());
+    common::clean(d);
+    common::close(c);
+}
+
 #[test]
 fn test_schedinfo() {
     fn t(dom: Domain) {
@@ -135,59 +352,185 @@ fn test_schedinfo() {
 }
 
 #[test]
-fn test_memory_params() {
+fn test_memory_params_raw() {
     fn t(dom: Domain) {
-        let info = dom.get_memory_parameters(0).unwrap();
-        assert_eq!(info.hard_limit, Some(MemoryParameters::VALUE_UNLIMITED));
-        assert_eq!(info.soft_limit, Some(MemoryParameters::VALUE_UNLIMITED));
+        let params = dom.get_memory_parameters_raw(0).unwrap();
+        assert_eq!(
+            Some(&TypedValue::ULLong(sys::VIR_DOMAIN_MEMORY_PARAM_UNLIMITED)),
+            params.get("hard_limit")
+        );
         assert_eq!(
-            info.swap_hard_limit,
-            Some(MemoryParameters::VALUE_UNLIMITED)
+            dom.get_memory_parameters(0).unwrap().soft_limit,
+            params
+                .get("soft_limit")
+                .and_then(|v| v.as_u64())
+                .map(Limit::from_raw)
         );
+    }
+    tdom(t);
+}
+
+#[test]
+fn test_memory_params() {
+    fn t(dom: Domain) {
+        let info = dom.get_memory_parameters(0).unwrap();
+        assert_eq!(info.hard_limit, Some(Limit::Unlimited));
+        assert_eq!(info.soft_limit, Some(Limit::Unlimited));
+        assert_eq!(info.swap_hard_limit, Some(Limit::Unlimited));
         assert_eq!(info.min_guarantee, None);
 
         let newinfo = MemoryParameters {
-            soft_limit: Some(87539319),
+            soft_limit: Some(Limit::Value(87539319)),
             ..Default::default()
         };
         dom.set_memory_parameters(newinfo, 0).unwrap();
 
         let info = dom.get_memory_parameters(0).unwrap();
-        assert_eq!(info.hard_limit, Some(MemoryParameters::VALUE_UNLIMITED));
-        assert_eq!(info.soft_limit, Some(87539319));
-        assert_eq!(
-            info.swap_hard_limit,
-            Some(MemoryParameters::VALUE_UNLIMITED)
-        );
+        assert_eq!(info.hard_limit, Some(Limit::Unlimited));
+        assert_eq!(info.soft_limit, Some(Limit::Value(87539319)));
+        assert_eq!(info.swap_hard_limit, Some(Limit::Unlimited));
         assert_eq!(info.min_guarantee, None);
+
+        let newinfo = MemoryParameters {
+            soft_limit: Some(Limit::Unlimited),
+            ..Default::default()
+        };
+        dom.set_memory_parameters(newinfo, DomainModificationImpact::LIVE)
+            .unwrap();
+
+        let info = dom
+            .get_memory_parameters(DomainModificationImpact::LIVE)
+            .unwrap();
+        assert_eq!(info.soft_limit, Some(Limit::Unlimited));
     }
     tdom(t);
 }
 
+#[test]
+fn test_limit_raw() {
+    let unlimited = MemoryParameters::VALUE_UNLIMITED;
+    assert_eq!(Limit::Unlimited, Limit::from_raw(unlimited));
+    assert_eq!(Limit::Value(1024), Limit::from_raw(1024));
+    assert_eq!(unlimited, Limit::Unlimited.to_raw());
+    assert_eq!(unlimited, Limit::Value(u64::MAX).to_raw());
+    assert_eq!(None, Limit::Unlimited.value());
+    assert_eq!("unlimited", Limit::Unlimited.to_string());
+}
+
+#[test]
+fn test_dirty_rate_stats_from_vec() {
+    fn param(
+        name: &str,
+        type_: u32,
+        value: sys::_virTypedParameter__bindgen_ty_1,
+    ) -> sys::virTypedParameter {
+        let mut field = [0; 80];
+        for (f, b) in field.iter_mut().zip(name.bytes()) {
+            *f = b as libc::c_char;
+        }
+        sys::virTypedParameter {
+            field,
+            type_: type_ as libc::c_int,
+            value,
+        }
+    }
+
+    let stats = DirtyRateStats::from_vec(vec![
+        param(
+            "dirtyrate.calc_status",
+            sys::VIR_TYPED_PARAM_INT,
+            sys::_virTypedParameter__bindgen_ty_1 {
+                i: sys::VIR_DOMAIN_DIRTYRATE_MEASURED as i32,
+            },
+        ),
+        param(
+            "dirtyrate.megabytes_per_second",
+            sys::VIR_TYPED_PARAM_LLONG,
+            sys::_virTypedParameter__bindgen_ty_1 { l: 42 },
+        ),
+        param(
+            "dirtyrate.vcpu.1.megabytes_per_second",
+            sys::VIR_TYPED_PARAM_LLONG,
+            sys::_virTypedParameter__bindgen_ty_1 { l: 30 },
+        ),
+        param(
+            "dirtyrate.vcpu.0.megabytes_per_second",
+            sys::VIR_TYPED_PARAM_LLONG,
+            sys::_virTypedParameter__bindgen_ty_1 { l: 12 },
+        ),
+    ]);
+    assert_eq!(Some(DirtyRateStatus::Measured), stats.status);
+    assert_eq!(Some(42), stats.megabytes_per_second);
+    assert_eq!(None, stats.period);
+    assert_eq!(vec![(0, 12), (1, 30)], stats.vcpu_megabytes_per_second);
+}
+
 #[test]
 fn test_numa_params() {
     fn t(dom: Domain) {
         let info = dom.get_numa_parameters(0).unwrap();
-        assert_eq!(info.mode, Some(sys::VIR_DOMAIN_NUMATUNE_MEM_STRICT as i32));
-        assert_eq!(info.node_set, Some("".to_string()));
+        assert_eq!(info.mode, Some(NumatuneMemMode::Strict));
+        assert_eq!(info.node_set, Some(NodeSet::new()));
 
         let newinfo = NUMAParameters {
-            node_set: Some("1,2".to_string()),
-            mode: Some(sys::VIR_DOMAIN_NUMATUNE_MEM_PREFERRED as i32),
+            node_set: Some("1,2".parse().unwrap()),
+            mode: Some(NumatuneMemMode::Preferred),
+            ..Default::default()
         };
         dom.set_numa_parameters(newinfo, 0).unwrap();
 
         let newerinfo = dom.get_numa_parameters(0).unwrap();
-        assert_eq!(
-            newerinfo.mode,
-            Some(sys::VIR_DOMAIN_NUMATUNE_MEM_PREFERRED as i32)
-        );
-        // Libvirt canonicalizes the pair of nodes to a range
-        assert_eq!(newerinfo.node_set, Some("1-2".to_string()));
+        assert_eq!(newerinfo.mode, Some(NumatuneMemMode::Preferred));
+        let node_set = newerinfo.node_set.unwrap();
+        assert_eq!(vec![1, 2], node_set.iter().collect::<Vec<_>>());
+        assert_eq!("1-2", node_set.to_string());
     }
     tdom(t);
 }
 
+#[test]
+fn test_numa_params_unparsable_node_set() {
+    let mut map = TypedParamsMap::new();
+    map.insert("numa_nodeset", TypedValue::String("bogus".to_string()));
+    let info = NUMAParameters::from_vec(map.to_vec().unwrap());
+    assert_eq!(None, info.node_set);
+    assert_eq!(
+        Some("bogus"),
+        info.extra.get("numa_nodeset").and_then(|v| v.as_str())
+    );
+}
+
+#[test]
+fn test_numa_params_unknown_mode() {
+    let mut map = TypedParamsMap::new();
+    map.insert("numa_mode", TypedValue::Int(42));
+    let info = NUMAParameters::from_vec(map.to_vec().unwrap());
+    assert_eq!(Some(NumatuneMemMode::Unknown(42)), info.mode);
+
+    let map = TypedParamsMap::from_vec(info.to_vec().unwrap());
+    assert_eq!(Some(42), map.get("numa_mode").and_then(|v| v.as_i64()));
+}
+
+#[test]
+fn test_params_interior_nul() {
+    let params = MigrateParameters {
+        dest_name: Some("bad\0name".to_string()),
+        ..Default::default()
+    };
+    assert_eq!(
+        Some(ErrorNumber::InvalidArg),
+        params.to_vec().err().map(|e| e.code())
+    );
+
+    let mut map = TypedParamsMap::new();
+    map.insert("first", TypedValue::String("good".to_string()));
+    map.insert("second", TypedValue::String("bad\0".to_string()));
+    assert_eq!(
+        Some(ErrorNumber::InvalidArg),
+        map.to_vec().err().map(|e| e.code())
+    );
+}
+
 #[test]
 fn test_lookup_domain_by_id() {
     let c = common::conn();
@@ -211,6 +554,47 @@ fn test_lookup_domain_by_name() {
     common::close(c);
 }
 
+#[test]
+fn test_lookup_domain() {
+    let c = common::conn();
+    let d = common::build_test_domain(&c, "lookup", true);
+    let name = d.get_name().unwrap();
+    let by_id = Domain::lookup(&c, &d.get_id().unwrap().to_string()).unwrap();
+    assert_eq!(Ok(name.clone()), by_id.get_name());
+    let by_uuid = Domain::lookup(&c, &d.get_uuid_string().unwrap()).unwrap();
+    assert_eq!(Ok(name.clone()), by_uuid.get_name());
+    let by_name = Domain::lookup(&c, &name).unwrap();
+    assert_eq!(Ok(name), by_name.get_name());
+    assert!(Domain::lookup(&c, "libvirt-rs-test-missing").is_err());
+    drop((by_id, by_uuid, by_name));
+    common::clean(d);
+    common::close(c);
+}
+
+#[test]
+fn test_domain_identity() {
+    let c = common::conn();
+    let a = Domain::lookup_by_name(&c, "test").unwrap();
+    let b = Domain::lookup_by_name(&c, "test").unwrap();
+    let d = common::build_test_domain(&c, "identity", false);
+    assert_eq!(a, b);
+    assert_ne!(a, d);
+    assert_eq!(Ok(a.get_uuid().unwrap()), Uuid::try_from(&b));
+
+    let set: HashSet<Domain> = vec![a, b, d.clone()].into_iter().collect();
+    assert_eq!(2, set.len());
+    assert!(set.contains(&d));
+
+    // The identity does not depend on the handle being usable.
+    let mut e = d.clone();
+    e.free().unwrap();
+    assert!(set.contains(&e));
+
+    drop(set);
+    common::clean(d);
+    common::close(c);
+}
+
 #[test]
 fn test_create_with_flags() {
     let c = common::conn();
@@ -234,6 +618,32 @@ fn test_shutdown() {
     common::close(c);
 }
 
+#[test]
+fn test_shutdown_and_wait() {
+    let c = common::conn();
+    let d = common::build_test_domain(&c, "shutdown_and_wait", false);
+    assert_eq!(Ok(0), d.create_with_flags(0));
+    assert_eq!(
+        Ok(ShutdownOutcome::ShutOff),
+        d.shutdown_and_wait(Duration::from_secs(5), 0, false)
+    );
+    assert_eq!(Ok(false), d.is_active());
+    common::clean(d);
+    common::close(c);
+}
+
+#[test]
+fn test_reboot_and_reset() {
+    let c = common::conn();
+    let d = common::build_test_domain(&c, "reboot", false);
+    assert_eq!(Ok(0), d.create_with_flags(0));
+    assert_eq!(Ok(()), d.reboot(RebootMethod::empty()));
+    assert_eq!(Ok(0), d.reset_flags(0));
+    assert_eq!(Ok(true), d.is_active());
+    common::clean(d);
+    common::close(c);
+}
+
 #[test]
 fn test_pause_resume() {
     let c = common::conn();
@@ -263,6 +673,23 @@ fn test_screenshot() {
     common::close(c);
 }
 
+#[test]
+fn test_screenshot_to_writer() {
+    let c = common::conn();
+    let d = common::build_test_domain(&c, "screenshot_to_writer", false);
+    assert_eq!(Ok(0), d.create_with_flags(0));
+
+    let mut buf: Vec<u8> = Vec::new();
+    assert_eq!(
+        Ok(String::from("image/png")),
+        d.screenshot_to_writer(&mut buf, 0)
+    );
+    assert!(!buf.is_empty());
+
+    common::clean(d);
+    common::close(c);
+}
+
 #[test]
 fn test_metadata() {
     let c = common::conn();
@@ -386,3 +813,744 @@ fn test_metadata() {
     common::clean(d);
     common::close(c);
 }
+
+#[test]
+fn test_core_dump_params() {
+    let c = common::conn();
+    let d = common::build_test_domain(&c, "core_dump_params", false);
+    assert_eq!(Ok(0), d.create_with_flags(0));
+
+    let path = std::env::temp_dir().join("libvirt-rs-test-core-dump");
+    let options = CoreDumpOptions {
+        format: DumpFormat::Raw,
+        live: true,
+        ..Default::default()
+    };
+    assert_eq!(DomainCoreDumpFlags::LIVE, options.flags());
+    assert_eq!(Ok(()), d.core_dump_params(path.to_str().unwrap(), &options));
+    let _ = std::fs::remove_file(path);
+
+    common::clean(d);
+    common::close(c);
+}
+
+#[test]
+fn test_mac_addr_parse() {
+    let mac = MacAddr::parse("52:54:00:AB:cd:0f").unwrap();
+    assert_eq!([0x52, 0x54, 0x00, 0xab, 0xcd, 0x0f], mac.octets());
+    assert_eq!("52:54:00:ab:cd:0f", mac.to_string());
+
+    assert_eq!(None, MacAddr::parse(""));
+    assert_eq!(None, MacAddr::parse("52:54:00:ab:cd"));
+    assert_eq!(None, MacAddr::parse("52:54:00:ab:cd:0f:00"));
+    assert_eq!(None, MacAddr::parse("52:54:00:ab:cd:0g"));
+}
+
+#[test]
+fn test_memory_stats_from_stats() {
+    let stats = MemoryStats::from_stats(&[
+        MemoryStat {
+            tag: sys::VIR_DOMAIN_MEMORY_STAT_ACTUAL_BALLOON,
+            val: 1024,
+        },
+        MemoryStat {
+            tag: sys::VIR_DOMAIN_MEMORY_STAT_RSS,
+            val: 512,
+        },
+        MemoryStat {
+            tag: sys::VIR_DOMAIN_MEMORY_STAT_NR,
+            val: 1,
+        },
+    ]);
+    assert_eq!(Some(1024), stats.actual_balloon);
+    assert_eq!(Some(512), stats.rss);
+    assert_eq!(None, stats.swap_in);
+}
+
+#[test]
+fn test_memory_stats_with_period() {
+    fn t(dom: Domain) {
+        assert_eq!(
+            ErrorNumber::InvalidArg,
+            dom.memory_stats_with_period(u32::MAX).unwrap_err().code()
+        );
+        assert!(dom.memory_stats_with_period(5).is_ok());
+    }
+    tdom(t);
+}
+
+#[test]
+fn test_snapshot_list_names() {
+    let c = common::conn();
+    let d = common::build_test_domain(&c, "snapshot_names", false);
+    let root =
+        DomainSnapshot::create_xml(&d, "<domainsnapshot><name>root</name></domainsnapshot>", 0)
+            .unwrap();
+    DomainSnapshot::create_xml(&d, "<domainsnapshot><name>leaf</name></domainsnapshot>", 0)
+        .unwrap();
+
+    let mut names = DomainSnapshot::list_names(&d, SnapshotListFlags::empty()).unwrap();
+    names.sort();
+    assert_eq!(vec!["leaf", "root"], names);
+    assert_eq!(Ok(2), DomainSnapshot::num(&d, 0));
+    assert_eq!(
+        vec!["root"],
+        DomainSnapshot::list_names(&d, SnapshotListFlags::ROOTS).unwrap()
+    );
+    assert_eq!(
+        vec!["leaf"],
+        root.list_children_names(SnapshotListFlags::DESCENDANTS | SnapshotListFlags::LEAVES)
+            .unwrap()
+    );
+
+    drop(root);
+    common::clean(d);
+    common::close(c);
+}
+
+#[test]
+fn test_snapshot_tree() {
+    let c = common::conn();
+    let d = common::build_test_domain(&c, "snapshot_tree", false);
+    for name in &["root", "mid", "leaf"] {
+        let xml = format!("<domainsnapshot><name>{}</name></domainsnapshot>", name);
+        DomainSnapshot::create_xml(&d, &xml, 0).unwrap();
+    }
+
+    let tree = d.snapshot_tree().unwrap();
+    assert_eq!(1, tree.len());
+    assert_eq!("root", tree[0].name);
+    assert_eq!(1, tree[0].children.len());
+    let leaf = tree[0].find("leaf").unwrap();
+    assert!(leaf.children.is_empty());
+    assert_eq!(
+        Ok("mid".to_string()),
+        leaf.snapshot.get_parent(0).unwrap().get_name()
+    );
+
+    drop(tree);
+    common::clean(d);
+    common::close(c);
+}
+
+#[test]
+fn test_snapshot_info_xml() {
+    let info = SnapshotInfo::from_xml(
+        "<domainsnapshot>
+  <name>leaf</name>
+  <description>before upgrade</description>
+  <state>disk-snapshot</state>
+  <parent>
+    <name>root</name>
+  </parent>
+  <creationTime>1700000000</creationTime>
+  <domain type='test'><name>test</name></domain>
+</domainsnapshot>",
+    )
+    .unwrap();
+    assert_eq!(
+        SnapshotInfo {
+            name: "leaf".to_string(),
+            description: Some("before upgrade".to_string()),
+            parent: Some("root".to_string()),
+            creation_time: 1700000000,
+            state: SnapshotState::DiskSnapshot,
+        },
+        info
+    );
+}
+
+#[test]
+fn test_snapshot_accessors() {
+    let c = common::conn();
+    let d = common::build_test_domain(&c, "snapshot_accessors", false);
+    DomainSnapshot::create_xml(&d, "<domainsnapshot><name>root</name></domainsnapshot>", 0)
+        .unwrap();
+    let leaf =
+        DomainSnapshot::create_xml(&d, "<domainsnapshot><name>leaf</name></domainsnapshot>", 0)
+            .unwrap();
+
+    assert_eq!(Ok(Some("root".to_string())), leaf.parent_name());
+    assert_eq!(Ok(false), leaf.is_disk_only());
+    assert!(leaf.creation_time().unwrap() > 0);
+    assert_eq!("leaf", leaf.info().unwrap().name);
+
+    drop(leaf);
+    common::clean(d);
+    common::close(c);
+}
+
+#[test]
+fn test_snapshot_create_options() {
+    let opts = SnapshotCreateOptions {
+        name: Some("backup".to_string()),
+        description: Some("before <upgrade>".to_string()),
+        disks: vec![
+            SnapshotDiskSpec::external("vda", "/var/lib/images/vda.backup"),
+            SnapshotDiskSpec::new("vdb", SnapshotDiskMode::No),
+        ],
+        disk_only: true,
+        atomic: true,
+        ..Default::default()
+    };
+    assert_eq!(
+        SnapshotCreateFlags::DISK_ONLY | SnapshotCreateFlags::ATOMIC,
+        opts.flags()
+    );
+    assert_eq!(
+        "<domainsnapshot>\n\
+         \x20 <name>backup</name>\n\
+         \x20 <description>before &lt;upgrade&gt;</description>\n\
+         \x20 <disks>\n\
+         \x20   <disk name='vda' snapshot='external'>\n\
+         \x20     <driver type='qcow2'/>\n\
+         \x20     <source file='/var/lib/images/vda.backup'/>\n\
+         \x20   </disk>\n\
+         \x20   <disk name='vdb' snapshot='no'/>\n\
+         \x20 </disks>\n\
+         </domainsnapshot>\n",
+        opts.to_xml()
+    );
+}
+
+#[test]
+fn test_snapshot_create_and_delete() {
+    let c = common::conn();
+    let d = common::build_test_domain(&c, "snapshot_create", false);
+    let opts = SnapshotCreateOptions {
+        name: Some("snap".to_string()),
+        ..Default::default()
+    };
+    let snap = DomainSnapshot::create(&d, &opts).unwrap();
+    assert_eq!(Ok("snap".to_string()), snap.get_name());
+    assert_eq!(Ok(()), snap.delete(SnapshotDeleteFlags::empty()));
+    assert_eq!(Ok(0), DomainSnapshot::num(&d, 0));
+
+    drop(snap);
+    common::clean(d);
+    common::close(c);
+}
+
+#[test]
+fn test_get_time_std() {
+    fn t(dom: Domain) {
+        let (seconds, _) = dom.get_time(0).unwrap();
+        let time = dom.get_time_std().unwrap();
+        assert_eq!(
+            seconds as u64,
+            time.duration_since(UNIX_EPOCH).unwrap().as_secs()
+        );
+    }
+    tdom(t);
+}
+
+#[test]
+fn test_linux_keycode() {
+    assert_eq!(Some(29), linux_keycode("KEY_LEFTCTRL"));
+    assert_eq!(Some(56), linux_keycode("leftalt"));
+    assert_eq!(Some(111), linux_keycode("Key_Delete"));
+    assert_eq!(Some(30), linux_keycode("a"));
+    assert_eq!(Some(11), linux_keycode("KEY_0"));
+    assert_eq!(None, linux_keycode("KEY_UNKNOWN"));
+}
+
+#[test]
+fn test_send_key() {
+    fn t(dom: Domain) {
+        let keys: Vec<u32> = ["KEY_LEFTCTRL", "KEY_LEFTALT", "KEY_DELETE"]
+            .iter()
+            .map(|k| linux_keycode(k).unwrap())
+            .collect();
+        assert_eq!(Ok(()), dom.send_key(KeycodeSet::Linux, 100, &keys, 0));
+    }
+    tdom(t);
+}
+
+#[test]
+fn test_disk_spec_xml() {
+    let spec = DiskSpec::file("/var/lib/libvirt/images/data.qcow2", "vdb")
+        .bus(DiskBus::Virtio)
+        .format("qcow2")
+        .cache(DiskCache::None);
+    assert_eq!("vdb", spec.target());
+    assert_eq!(
+        "<disk type='file' device='disk'>
+  <driver type='qcow2' cache='none'/>
+  <source file='/var/lib/libvirt/images/data.qcow2'/>
+  <target dev='vdb' bus='virtio'/>
+</disk>
+",
+        spec.to_xml()
+    );
+
+    let spec = DiskSpec::volume("default", "data.img", "sdb").read_only(true);
+    assert_eq!(
+        "<disk type='volume' device='disk'>
+  <source pool='default' volume='data.img'/>
+  <target dev='sdb'/>
+  <readonly/>
+</disk>
+",
+        spec.to_xml()
+    );
+}
+
+#[test]
+fn test_interface_spec_xml() {
+    let spec = InterfaceSpec::bridge("br0")
+        .model("virtio")
+        .mac(MacAddr::parse("52:54:00:12:34:56").unwrap());
+    assert_eq!(
+        "<interface type='bridge'>
+  <mac address='52:54:00:12:34:56'/>
+  <source bridge='br0'/>
+  <model type='virtio'/>
+</interface>
+",
+        spec.to_xml()
+    );
+    assert_eq!(
+        "<interface type='network'>\n  <source network='default'/>\n</interface>\n",
+        InterfaceSpec::network("default").to_xml()
+    );
+}
+
+#[test]
+fn test_memory_device_spec_xml() {
+    assert_eq!(
+        "<memory model='dimm'>
+  <target>
+    <size unit='bytes'>536870912</size>
+    <node>0</node>
+  </target>
+</memory>
+",
+        MemoryDeviceSpec::dimm(512 << 20).node(0).to_xml()
+    );
+    assert_eq!(
+        "<memory model='virtio-mem'>
+  <target>
+    <size unit='bytes'>4294967296</size>
+    <node>1</node>
+    <block unit='bytes'>2097152</block>
+    <requested unit='bytes'>1073741824</requested>
+  </target>
+</memory>
+",
+        MemoryDeviceSpec::virtio_mem(4 << 30, 1, 2 << 20)
+            .requested(1 << 30)
+            .to_xml()
+    );
+}
+
+#[test]
+fn test_memory_devices_from_xml() {
+    let devices = MemoryDevice::list_from_xml(
+        "<domain type='kvm'>
+  <name>guest</name>
+  <devices>
+    <disk type='file' device='disk'/>
+    <memory model='dimm'>
+      <target>
+        <size unit='GiB'>1</size>
+        <node>0</node>
+      </target>
+      <alias name='dimm0'/>
+    </memory>
+    <memory model='virtio-mem'>
+      <target>
+        <size unit='KiB'>4194304</size>
+        <node>1</node>
+        <block unit='KiB'>2048</block>
+        <requested unit='KiB'>1048576</requested>
+        <current unit='KiB'>524288</current>
+      </target>
+      <alias name='virtiomem0'/>
+    </memory>
+  </devices>
+</domain>",
+    )
+    .unwrap();
+    assert_eq!(
+        vec![
+            MemoryDevice {
+                model: "dimm".to_string(),
+                alias: Some("dimm0".to_string()),
+                size: 1 << 30,
+                node: Some(0),
+                requested: None,
+                current: None,
+            },
+            MemoryDevice {
+                model: "virtio-mem".to_string(),
+                alias: Some("virtiomem0".to_string()),
+                size: 4 << 30,
+                node: Some(1),
+                requested: Some(1 << 30),
+                current: Some(512 << 20),
+            }
+        ],
+        devices
+    );
+    fn t(dom: Domain) {
+        assert_eq!(Ok(Vec::new()), dom.memory_devices());
+    }
+    tdom(t);
+}
+
+#[test]
+fn test_detach_device_alias_unknown() {
+    fn t(dom: Domain) {
+        assert!(dom
+            .detach_device_alias("ua-missing", DomainDeviceModifyFlags::CONFIG)
+            .is_err());
+    }
+    tdom(t);
+}
+
+#[test]
+fn test_flags_module() {
+    assert_eq!(
+        DomainUndefineFlags::NVRAM | DomainUndefineFlags::MANAGED_SAVE,
+        DomainUndefineFlags::from(flags::UNDEFINE_NVRAM | flags::UNDEFINE_MANAGED_SAVE)
+    );
+    assert_eq!(
+        DomainXmlFlags::INACTIVE,
+        DomainXmlFlags::from(flags::XML_INACTIVE)
+    );
+}
+
+#[test]
+fn test_device_transaction() {
+    fn t(dom: Domain) {
+        assert_eq!(Ok(5), dom.with_device_transaction(|_| Ok(5)));
+        let ret = dom.with_device_transaction(|txn| {
+            txn.attach_device("<bogus/>", DomainDeviceModifyFlags::CONFIG)?;
+            Ok(())
+        });
+        assert!(ret.is_err());
+    }
+    tdom(t);
+}
+
+#[test]
+fn test_device_transaction_rollback() {
+    let c = common::conn();
+    let dom = common::build_test_domain(&c, "device_transaction", false);
+    let has_disk = |dom: &Domain| {
+        dom.get_xml_desc(DomainXmlFlags::INACTIVE)
+            .unwrap()
+            .contains("dev='vdb'")
+    };
+    let ret = dom.with_device_transaction(|txn| {
+        txn.attach_device(
+            "<disk type='file' device='disk'><source file='/tmp/vdb.img'/>\
+             <target dev='vdb' bus='virtio'/></disk>",
+            DomainDeviceModifyFlags::CONFIG,
+        )?;
+        assert!(has_disk(&dom));
+        txn.attach_device("<bogus/>", DomainDeviceModifyFlags::CONFIG)
+    });
+    assert!(ret.is_err());
+    assert!(!has_disk(&dom));
+    common::clean(dom);
+    common::close(c);
+}
+
+#[test]
+fn test_iothread_params() {
+    let params = IOThreadParameters {
+        poll_max_ns: Some(32768),
+        thread_pool_max: Some(-1),
+        ..Default::default()
+    };
+    let vec = params.to_vec().unwrap();
+    assert_eq!(2, vec.len());
+    let params = IOThreadParameters::from_vec(vec);
+    assert_eq!(Some(32768), params.poll_max_ns);
+    assert_eq!(None, params.poll_grow);
+    assert_eq!(Some(-1), params.thread_pool_max);
+}
+
+#[test]
+fn test_pin_info() {
+    let c = common::conn();
+    let dom = common::build_test_domain(&c, "pin-info", true);
+    let cpumap: CpuMap = [1, 3].iter().copied().collect();
+    dom.pin_vcpu_flags(0, &cpumap, DomainModificationImpact::LIVE)
+        .unwrap();
+    let maps = dom
+        .get_vcpu_pin_info(None, DomainModificationImpact::LIVE)
+        .unwrap();
+    assert_eq!(1, maps.len());
+    assert_eq!(vec![1, 3], maps[0].iter().collect::<Vec<_>>());
+    match dom.get_iothread_info(DomainModificationImpact::LIVE) {
+        Ok(iothreads) => assert!(iothreads.is_empty()),
+        Err(e) => assert!(e.is_unsupported(), "{}", e),
+    }
+    common::clean(dom);
+    common::close(c);
+}
+
+#[test]
+fn test_graphics_info_from_xml() {
+    let xml = "<domain type='kvm'>
+  <name>vm</name>
+  <devices>
+    <graphics type='vnc' port='5901' autoport='yes' listen='127.0.0.1'>
+      <listen type='address' address='127.0.0.1'/>
+    </graphics>
+    <graphics type='spice' port='-1' tlsPort='5902' autoport='yes'>
+      <listen type='address' address='0.0.0.0'/>
+    </graphics>
+    <graphics type='vnc'>
+      <listen type='socket' socket='/run/libvirt/qemu/vnc.sock'/>
+    </graphics>
+  </devices>
+</domain>";
+    let info = GraphicsInfo::from_xml(xml).unwrap();
+    assert_eq!(3, info.len());
+    assert_eq!(
+        GraphicsInfo {
+            graphics_type: "vnc".to_string(),
+            port: Some(5901),
+            tls_port: None,
+            listen: Some("127.0.0.1".to_string()),
+            socket: None,
+        },
+        info[0]
+    );
+    assert_eq!("spice", info[1].graphics_type);
+    assert_eq!(None, info[1].port);
+    assert_eq!(Some(5902), info[1].tls_port);
+    assert_eq!(Some("0.0.0.0".to_string()), info[1].listen);
+    assert_eq!(
+        Some("/run/libvirt/qemu/vnc.sock".to_string()),
+        info[2].socket
+    );
+}
+
+#[test]
+fn test_abort_job_without_job() {
+    fn t(dom: Domain) {
+        assert!(dom.abort_job().is_err());
+        assert!(dom.migrate_start_post_copy(0).is_err());
+    }
+    tdom(t);
+}
+
+#[test]
+fn test_block_copy_options() {
+    assert_eq!(BlockCopyFlags::empty(), BlockCopyOptions::default().flags());
+    let options = BlockCopyOptions {
+        shallow: true,
+        reuse_external: true,
+        parameters: BlockCopyParameters {
+            bandwidth: Some(1 << 20),
+            granularity: Some(65536),
+            buf_size: None,
+        },
+        ..Default::default()
+    };
+    assert_eq!(
+        BlockCopyFlags::SHALLOW | BlockCopyFlags::REUSE_EXT,
+        options.flags()
+    );
+    assert_eq!(
+        options.parameters,
+        BlockCopyParameters::from_vec(options.parameters.to_vec().unwrap())
+    );
+}
+
+#[test]
+fn test_block_copy_to_file() {
+    fn t(dom: Domain) {
+        // The test driver does not implement block jobs.
+        let options = BlockCopyOptions {
+            pivot: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            Some(ErrorNumber::NoSupport),
+            dom.block_copy_to_file("vda", "/tmp/copy.img", &options)
+                .err()
+                .map(|e| e.code())
+        );
+    }
+    tdom(t);
+}
+
+#[test]
+fn test_migrate_max_speed_options() {
+    assert_eq!(0, MigrateMaxSpeedOptions::default().flags());
+    let options = MigrateMaxSpeedOptions { postcopy: true };
+    assert_eq!(sys::VIR_DOMAIN_MIGRATE_MAX_SPEED_POSTCOPY, options.flags());
+    assert_eq!(
+        options,
+        MigrateMaxSpeedOptions::from(sys::VIR_DOMAIN_MIGRATE_MAX_SPEED_POSTCOPY)
+    );
+}
+
+#[test]
+fn test_migration_compression() {
+    let compression = MigrationCompression::new()
+        .xbzrle()
+        .mt()
+        .xbzrle()
+        .level(9)
+        .threads(4)
+        .xbzrle_cache(64 << 20);
+    assert_eq!(
+        &[
+            MigrationCompressionMethod::Xbzrle,
+            MigrationCompressionMethod::Mt
+        ],
+        compression.methods()
+    );
+
+    let mut params = MigrateParameters::default();
+    params.set_compression(&compression);
+    assert_eq!(vec!["xbzrle", "mt"], params.compression);
+    assert_eq!(Some(9), params.compression_mt_level);
+    assert_eq!(None, params.compression_mt_dthreads);
+
+    let params = MigrateParameters::from_vec(params.to_vec().unwrap());
+    assert_eq!(compression, params.get_compression());
+    assert_eq!(Some(64 << 20), params.get_compression().get_xbzrle_cache());
+}
+
+#[test]
+fn test_migration_xbzrle_cache() {
+    fn t(dom: Domain) {
+        // The test driver has no migration support.
+        assert_eq!(Ok(None), dom.migration_xbzrle_cache());
+    }
+    tdom(t);
+}
+
+#[test]
+fn test_get_block_info_or_volume() {
+    let c = common::conn();
+    let pool = common::build_storage_pool(&c, "block-info", true);
+    let vol = common::build_storage_vol(&pool, "block-info.img", 8);
+    let xml = format!(
+        "<domain type='test'>
+           <name>libvirt-rs-test-block-info</name>
+           <memory unit='KiB'>128</memory>
+           <os><type>hvm</type></os>
+           <devices>
+             <disk type='file' device='disk'>
+               <source file='{}'/>
+               <target dev='vda' bus='virtio'/>
+             </disk>
+           </devices>
+         </domain>",
+        vol.get_path().unwrap()
+    );
+    let dom = Domain::define_xml(&c, &xml).unwrap();
+    let info = vol.get_info().unwrap();
+    for disk in ["vda", &vol.get_path().unwrap()] {
+        let block = dom.get_block_info_or_volume(disk).unwrap();
+        assert_eq!(info.capacity, block.capacity);
+        assert_eq!(info.allocation, block.allocation);
+    }
+    common::clean(dom);
+    common::clean_vol(vol);
+    common::clean_pool(pool);
+    common::close(c);
+}
+
+#[test]
+fn test_interface_stats_by_mac() {
+    let c = common::conn();
+    let dom = Domain::lookup_by_name(&c, "test").unwrap();
+    let xml = dom.get_xml_desc(0).unwrap();
+    let start = xml.find("<mac address='").unwrap() + "<mac address='".len();
+    let mac = &xml[start..start + 17];
+    assert!(dom.interface_stats_by_mac(mac).is_ok());
+    assert!(dom.interface_stats_by_mac(&mac.to_uppercase()).is_ok());
+    assert_eq!(
+        ErrorNumber::DeviceMissing,
+        dom.interface_stats_by_mac("02:00:00:00:00:00")
+            .unwrap_err()
+            .code()
+    );
+    assert_eq!(
+        ErrorNumber::InvalidArg,
+        dom.interface_stats_by_mac("vnet0").unwrap_err().code()
+    );
+    drop(dom);
+    common::close(c);
+}
+
+#[test]
+fn test_guest_vcpus() {
+    let c = common::conn();
+    let dom = Domain::lookup_by_name(&c, "test").unwrap();
+    match dom.get_guest_vcpus(0) {
+        Ok(vcpus) => {
+            assert!(vcpus.online.is_subset(&vcpus.vcpus));
+            assert!(vcpus.offlinable.is_subset(&vcpus.vcpus));
+        }
+        Err(e) => assert!(e.is_unsupported(), "{}", e),
+    }
+    drop(dom);
+    common::close(c);
+}
+
+#[test]
+fn test_all_block_stats() {
+    let c = common::conn();
+    let xml = "<domain type='test'>
+                 <name>libvirt-rs-test-block-stats</name>
+                 <memory unit='KiB'>128</memory>
+                 <os><type>hvm</type></os>
+                 <devices>
+                   <disk type='file' device='disk'>
+                     <source file='/var/lib/libvirt/images/a.img'/>
+                     <target dev='vda' bus='virtio'/>
+                   </disk>
+                   <disk type='file' device='disk'>
+                     <source file='/var/lib/libvirt/images/b.img'/>
+                     <target dev='vdb' bus='virtio'/>
+                   </disk>
+                 </devices>
+               </domain>";
+    let d = Domain::create_xml(&c, xml, 0).unwrap();
+    let stats = d.all_block_stats(0).unwrap();
+    let mut targets: Vec<&String> = stats.keys().collect();
+    targets.sort();
+    assert_eq!(vec!["vda", "vdb"], targets);
+    assert!(d.block_stats("vda").is_ok());
+    assert!(d.block_stats("vdc").is_err());
+    common::clean(d);
+    common::close(c);
+}
+
+#[test]
+fn test_block_stats_total() {
+    let a = BlockStats {
+        rd_req: 1,
+        rd_bytes: 512,
+        wr_req: -1,
+        wr_bytes: 1024,
+        errs: -1,
+    };
+    let b = BlockStats {
+        rd_req: 2,
+        rd_bytes: 512,
+        wr_req: 3,
+        wr_bytes: -1,
+        errs: -1,
+    };
+    assert_eq!(
+        BlockStats {
+            rd_req: 3,
+            rd_bytes: 1024,
+            wr_req: 3,
+            wr_bytes: 1024,
+            errs: -1,
+        },
+        BlockStats::total(&[a, b])
+    );
+}
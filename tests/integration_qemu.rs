@@ -44,13 +44,13 @@ fn test_create_storage_pool_and_vols() {
     assert_eq!(Ok(0), p.create(0));
     assert_eq!(Ok(String::from("libvirt-rs-test-create")), p.get_name());
     let v = common::build_storage_vol(&p, "vol1", 8);
-    assert_eq!(Ok(String::from("vol1")), v.get_name());
+    assert_eq!(Ok(String::from("libvirt-rs-test-vol1")), v.get_name());
     assert_eq!(
-        Ok(String::from("/var/lib/libvirt/images/vol1")),
+        Ok(String::from("/var/lib/libvirt/images/libvirt-rs-test-vol1")),
         v.get_path()
     );
     assert_eq!(
-        Ok(String::from("/var/lib/libvirt/images/vol1")),
+        Ok(String::from("/var/lib/libvirt/images/libvirt-rs-test-vol1")),
         v.get_key()
     );
     if let Ok(info) = v.get_info() {
@@ -174,6 +174,9 @@ fn test_domain_memory_stats() {
             _ => assert!(stat.tag <= sys::VIR_DOMAIN_MEMORY_STAT_NR),
         }
     }
+    let stats = d.get_memory_stats(0).unwrap();
+    assert_eq!(Some(1024), stats.actual_balloon);
+    assert!(stats.rss.unwrap_or(0) > 0);
     common::clean(d);
     common::close(c);
 }
@@ -18,6 +18,9 @@
 
 mod common;
 
+use virt::connect::Connect;
+use virt::interface::Interface;
+
 #[test]
 fn test_create() {
     let c = common::conn();
@@ -42,9 +45,47 @@ fn test_active() {
 }
 
 #[test]
+#[allow(deprecated)]
 fn test_lookup_interface_by_name() {
     let c = common::conn();
     let v = c.list_interfaces().unwrap_or_default();
     assert!(!v.is_empty(), "At least one interface should exist");
     common::close(c);
 }
+
+fn private_conn() -> Connect {
+    // Transactions apply to the whole driver: use a private instance
+    // so that other tests running in parallel are not affected.
+    let uri = format!(
+        "test://{}/tests/interface_change.xml",
+        env!("CARGO_MANIFEST_DIR")
+    );
+    Connect::open(Some(&uri)).unwrap()
+}
+
+#[test]
+fn test_change_rollback() {
+    let c = private_conn();
+    assert_eq!(Ok(()), c.interface_change_begin(0));
+    assert!(c.interface_change_begin(0).is_err());
+    let iface = common::build_interface(&c, "rollback");
+    drop(iface);
+    assert_eq!(Ok(()), c.interface_change_rollback(0));
+    assert!(Interface::lookup_by_name(&c, "libvirt-rs-test-rollback").is_err());
+    assert!(c.interface_change_rollback(0).is_err());
+    common::close(c);
+}
+
+#[test]
+fn test_change_commit() {
+    let c = private_conn();
+    assert_eq!(Ok(()), c.interface_change_begin(0));
+    let iface = common::build_interface(&c, "commit");
+    assert_eq!(Ok(()), c.interface_change_commit(0));
+    assert_eq!(
+        Ok(String::from("libvirt-rs-test-commit")),
+        Interface::lookup_by_name(&c, "libvirt-rs-test-commit").and_then(|i| i.get_name())
+    );
+    common::clean_iface(iface);
+    common::close(c);
+}
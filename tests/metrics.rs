@@ -0,0 +1,42 @@
+/*
+ * This library is free software; you can redistribute it and/or
+ * modify it under the terms of the GNU Lesser General Public
+ * License as published by the Free Software Foundation; either
+ * version 2.1 of the License, or (at your option) any later version.
+ *
+ * This library is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+ * Lesser General Public License for more details.
+ *
+ * You should have received a copy of the GNU Lesser General Public
+ * License along with this library.  If not, see
+ * <https://www.gnu.org/licenses/>.
+ *
+ * Sahid Orentino Ferdjaoui <sahid.ferdjaoui@redhat.com>
+ */
+
+mod common;
+
+use virt::metrics;
+use virt::sys;
+
+#[test]
+fn test_collect() {
+    let c = common::conn();
+    let doms = metrics::collect(&c).unwrap();
+    let test = doms.iter().find(|d| d.name == "test").unwrap();
+    assert_eq!(sys::VIR_DOMAIN_RUNNING, test.state);
+    assert!(test.vcpus > 0);
+    common::close(c);
+}
+
+#[test]
+fn test_collect_releases_domains() {
+    let mut c = common::conn();
+    for _ in 0..3 {
+        assert!(!metrics::collect(&c).unwrap().is_empty());
+    }
+    // Every domain reference taken by the statistics records is gone.
+    assert_eq!(Ok(0), c.close());
+}
@@ -18,6 +18,9 @@
 
 mod common;
 
+use virt::error::ErrorNumber;
+use virt::network::{Network, NetworkUpdateFlags};
+
 #[test]
 fn test_create() {
     let c = common::conn();
@@ -55,9 +58,106 @@ fn test_auto_start() {
 }
 
 #[test]
+#[allow(deprecated)]
 fn test_lookup_network_by_name() {
     let c = common::conn();
     let v = c.list_networks().unwrap_or_default();
     assert!(!v.is_empty(), "At least one network should exist");
     common::close(c);
 }
+
+#[test]
+fn test_lookup_network() {
+    let c = common::conn();
+    let n = common::build_network(&c, "lookup", false);
+    let name = n.get_name().unwrap();
+    let by_uuid = Network::lookup(&c, &n.get_uuid_string().unwrap()).unwrap();
+    assert_eq!(Ok(name.clone()), by_uuid.get_name());
+    let by_name = Network::lookup(&c, &name).unwrap();
+    assert_eq!(Ok(name), by_name.get_name());
+    drop((by_uuid, by_name));
+    common::clean_net(n);
+    common::close(c);
+}
+
+#[test]
+#[cfg(libvirt_9_7)]
+fn test_metadata() {
+    use virt::network::{NetworkMetadataType, NetworkUpdateFlags};
+
+    let c = common::conn();
+    let n = common::build_network(&c, "metadata", false);
+    assert_eq!(
+        Ok(()),
+        n.set_metadata(
+            NetworkMetadataType::Title,
+            Some("A title"),
+            None,
+            None,
+            NetworkUpdateFlags::CURRENT
+        )
+    );
+    assert_eq!(
+        Ok(String::from("A title")),
+        n.get_metadata(
+            NetworkMetadataType::Title,
+            None,
+            NetworkUpdateFlags::CURRENT
+        )
+    );
+
+    let uri = "http://example.org/libvirt-rs/";
+    assert_eq!(
+        Ok(()),
+        n.set_metadata(
+            NetworkMetadataType::Element,
+            Some("<tag>value</tag>"),
+            Some("rs"),
+            Some(uri),
+            NetworkUpdateFlags::CURRENT
+        )
+    );
+    assert!(n
+        .get_metadata(
+            NetworkMetadataType::Element,
+            Some(uri),
+            NetworkUpdateFlags::CURRENT
+        )
+        .unwrap()
+        .contains("value"));
+    common::clean_net(n);
+    common::close(c);
+}
+
+#[test]
+fn test_dhcp_host() {
+    let c = common::conn();
+    let n = common::build_network(&c, "dhcphost", false);
+    assert_eq!(Ok(0), n.create());
+    let mac = "52:54:00:00:00:01";
+    let flags = NetworkUpdateFlags::LIVE | NetworkUpdateFlags::CONFIG;
+
+    assert_eq!(
+        Ok(()),
+        n.add_dhcp_host(mac, Some("guest"), "192.168.0.10", flags)
+    );
+    let xml = n.get_xml_desc(0).unwrap();
+    assert!(xml.contains("192.168.0.10"));
+    assert!(xml.contains("guest"));
+
+    assert_eq!(
+        Ok(()),
+        n.modify_dhcp_host(mac, Some("guest"), "192.168.0.11", flags)
+    );
+    assert!(n.get_xml_desc(0).unwrap().contains("192.168.0.11"));
+
+    assert_eq!(Ok(()), n.delete_dhcp_host(mac, flags));
+    assert!(!n.get_xml_desc(0).unwrap().contains(mac));
+
+    match n.get_dhcp_leases(None) {
+        Ok(leases) => assert!(leases.is_empty()),
+        Err(e) => assert_eq!(ErrorNumber::NoSupport, e.code()),
+    }
+    common::clean_net(n);
+    common::close(c);
+}
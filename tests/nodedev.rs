@@ -0,0 +1,190 @@
+/*
+ * This library is free software; you can redistribute it and/or
+ * modify it under the terms of the GNU Lesser General Public
+ * License as published by the Free Software Foundation; either
+ * version 2.1 of the License, or (at your option) any later version.
+ *
+ * This library is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+ * Lesser General Public License for more details.
+ *
+ * You should have received a copy of the GNU Lesser General Public
+ * License along with this library.  If not, see
+ * <https://www.gnu.org/licenses/>.
+ *
+ * Sahid Orentino Ferdjaoui <sahid.ferdjaoui@redhat.com>
+ */
+
+mod common;
+
+use uuid::Uuid;
+use virt::nodedev::{
+    MdevSpec, MdevType, NodeDeviceCap, NodeDeviceCapability, NodeDeviceEvent,
+    NodeDeviceLifecycleEvent, NodeDeviceWatcher,
+};
+use virt::sys;
+
+#[test]
+fn test_capabilities_from_xml() {
+    let xml = r#"
+<device>
+  <name>pci_0000_02_00_0</name>
+  <capability type='pci'>
+    <class>0x030000</class>
+    <domain>0</domain>
+    <bus>2</bus>
+    <slot>0</slot>
+    <function>0</function>
+    <product id='0x13bd'>GM107GL [Tesla M10]</product>
+    <vendor id='0x10de'>NVIDIA Corporation</vendor>
+    <capability type='mdev_types'>
+      <type id='nvidia-35'>
+        <name>GRID M10-0B</name>
+        <deviceAPI>vfio-pci</deviceAPI>
+        <availableInstances>16</availableInstances>
+      </type>
+    </capability>
+    <iommuGroup number='13'>
+      <address domain='0x0000' bus='0x02' slot='0x00' function='0x0'/>
+    </iommuGroup>
+  </capability>
+  <capability type='net'/>
+</device>"#;
+    let caps = NodeDeviceCapability::from_xml(xml).unwrap();
+    assert_eq!(
+        vec![
+            NodeDeviceCapability::Pci {
+                domain: 0,
+                bus: 2,
+                slot: 0,
+                function: 0,
+                vendor_id: Some(0x10de),
+                product_id: Some(0x13bd),
+                iommu_group: Some(13),
+                mdev_types: vec![MdevType {
+                    id: "nvidia-35".to_string(),
+                    name: Some("GRID M10-0B".to_string()),
+                    device_api: "vfio-pci".to_string(),
+                    available_instances: 16,
+                }],
+            },
+            NodeDeviceCapability::Other("net".to_string()),
+        ],
+        caps
+    );
+}
+
+#[test]
+fn test_mdev_capability_from_xml() {
+    let xml = r#"
+<device>
+  <name>mdev_4b20d080_1b54_4048_85b3_a6a62d165c01</name>
+  <capability type='mdev'>
+    <type id='nvidia-35'/>
+    <uuid>4b20d080-1b54-4048-85b3-a6a62d165c01</uuid>
+    <iommuGroup number='12'/>
+  </capability>
+</device>"#;
+    let caps = NodeDeviceCapability::from_xml(xml).unwrap();
+    assert_eq!(
+        vec![NodeDeviceCapability::Mdev {
+            type_id: "nvidia-35".to_string(),
+            uuid: Some("4b20d080-1b54-4048-85b3-a6a62d165c01".to_string()),
+            iommu_group: Some(12),
+        }],
+        caps
+    );
+}
+
+#[test]
+fn test_list_node_devices_with_cap() {
+    let c = common::conn();
+    for cap in [NodeDeviceCap::System, NodeDeviceCap::ScsiHost] {
+        for dev in c.list_node_devices_with_cap(cap, 0).unwrap() {
+            assert!(dev.list_caps().unwrap().iter().any(|c| c == cap.as_str()));
+        }
+    }
+    common::close(c);
+}
+
+#[test]
+fn test_mdev_spec_xml() {
+    let mut spec = MdevSpec::new("pci_0000_02_00_0", "nvidia-35");
+    spec.uuid = Some(Uuid::parse_str("4b20d080-1b54-4048-85b3-a6a62d165c01").unwrap());
+    spec.attributes
+        .push(("assign_adapter".to_string(), "5".to_string()));
+    assert_eq!(
+        "<device>
+  <parent>pci_0000_02_00_0</parent>
+  <capability type='mdev'>
+    <type id='nvidia-35'/>
+    <uuid>4b20d080-1b54-4048-85b3-a6a62d165c01</uuid>
+    <attr name='assign_adapter' value='5'/>
+  </capability>
+</device>
+",
+        spec.to_xml()
+    );
+}
+
+#[test]
+fn test_pci_parent() {
+    let c = common::conn();
+    let hosts = c
+        .list_node_devices_with_cap(NodeDeviceCap::ScsiHost, 0)
+        .unwrap();
+    // The test driver has no PCI devices, the hosts hang off the root.
+    assert!(hosts[0].pci_parent(&c).unwrap().is_none());
+    drop(hosts);
+    common::close(c);
+}
+
+#[test]
+fn test_cap_from_name() {
+    for cap in [
+        NodeDeviceCap::Pci,
+        NodeDeviceCap::UsbInterface,
+        NodeDeviceCap::Mdev,
+    ] {
+        assert_eq!(Some(cap), NodeDeviceCap::from_name(cap.as_str()));
+    }
+    assert_eq!(None, NodeDeviceCap::from_name("bogus"));
+
+    let event = NodeDeviceEvent::Removed {
+        name: "usb_1_2".to_string(),
+        caps: vec![NodeDeviceCap::UsbDevice],
+    };
+    assert_eq!("usb_1_2", event.name());
+    assert!(event.has_cap(NodeDeviceCap::UsbDevice));
+    assert!(!event.has_cap(NodeDeviceCap::Pci));
+}
+
+#[test]
+fn test_lifecycle_event_unknown() {
+    assert_eq!(
+        NodeDeviceLifecycleEvent::Deleted,
+        NodeDeviceLifecycleEvent::from_raw(sys::VIR_NODE_DEVICE_EVENT_DELETED)
+    );
+    let event = NodeDeviceLifecycleEvent::from_raw(42);
+    assert_eq!(NodeDeviceLifecycleEvent::Unknown(42), event);
+    assert_eq!(42, event.to_raw());
+}
+
+#[test]
+fn test_node_device_watcher() {
+    assert_eq!(0, unsafe { sys::virEventRegisterDefaultImpl() });
+    let c = common::conn();
+
+    let id = c
+        .node_device_event_lifecycle_register(None, |_dev, _event| {})
+        .unwrap();
+    assert_eq!(Ok(()), c.node_device_event_deregister_any(id));
+
+    let watcher = NodeDeviceWatcher::new(&c, |_event| {}).unwrap();
+    assert_eq!(Ok(()), watcher.unwatch());
+    let watcher = NodeDeviceWatcher::new(&c, |_event| {}).unwrap();
+    drop(watcher);
+    // The watcher must not keep the connection alive once dropped.
+    common::close(c);
+}
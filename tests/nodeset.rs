@@ -0,0 +1,62 @@
+/*
+ * This library is free software; you can redistribute it and/or
+ * modify it under the terms of the GNU Lesser General Public
+ * License as published by the Free Software Foundation; either
+ * version 2.1 of the License, or (at your option) any later version.
+ *
+ * This library is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+ * Lesser General Public License for more details.
+ *
+ * You should have received a copy of the GNU Lesser General Public
+ * License along with this library.  If not, see
+ * <https://www.gnu.org/licenses/>.
+ *
+ * Sahid Orentino Ferdjaoui <sahid.ferdjaoui@redhat.com>
+ */
+
+use virt::error::ErrorNumber;
+use virt::nodeset::NodeSet;
+
+#[test]
+fn test_nodeset_parse() {
+    let set: NodeSet = "0-3,^2,5".parse().unwrap();
+    assert_eq!(vec![0, 1, 3, 5], set.iter().collect::<Vec<_>>());
+    assert_eq!("0-1,3,5", set.to_string());
+    assert_eq!(vec![(0, 1), (3, 3), (5, 5)], set.ranges());
+
+    assert!("".parse::<NodeSet>().unwrap().is_empty());
+    assert_eq!(" 7 , 2-3 ".parse::<NodeSet>().unwrap().to_string(), "2-3,7");
+
+    for invalid in &["a", "1,,2", "3-1", "1-", "^", "-1"] {
+        assert!(invalid.parse::<NodeSet>().is_err(), "{}", invalid);
+    }
+
+    let max = NodeSet::MAX_ID;
+    let set: NodeSet = format!("0-{}", max).parse().unwrap();
+    assert_eq!(max as usize + 1, set.len());
+    for too_big in &[format!("{}", max + 1), "0-4294967295".to_string()] {
+        let err = too_big.parse::<NodeSet>().unwrap_err();
+        assert_eq!(ErrorNumber::InvalidArg, err.code());
+    }
+}
+
+#[test]
+fn test_nodeset_operations() {
+    let a: NodeSet = vec![0, 1, 2].into_iter().collect();
+    let b: NodeSet = "2-4".parse().unwrap();
+    assert_eq!("0-4", a.union(&b).to_string());
+    assert_eq!("2", a.intersection(&b).to_string());
+    assert_eq!("0-1", a.difference(&b).to_string());
+    assert!(a.intersection(&b).is_subset(&a));
+    assert_eq!((Some(0), Some(2)), (a.first(), a.last()));
+
+    let mut c = NodeSet::new();
+    assert!(c.insert(4));
+    assert!(!c.insert(4));
+    c.extend(&a);
+    assert_eq!(4, c.len());
+    assert!(c.remove(4));
+    assert_eq!(a, c);
+}
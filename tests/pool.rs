@@ -0,0 +1,86 @@
+/*
+ * This library is free software; you can redistribute it and/or
+ * modify it under the terms of the GNU Lesser General Public
+ * License as published by the Free Software Foundation; either
+ * version 2.1 of the License, or (at your option) any later version.
+ *
+ * This library is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+ * Lesser General Public License for more details.
+ *
+ * You should have received a copy of the GNU Lesser General Public
+ * License along with this library.  If not, see
+ * <https://www.gnu.org/licenses/>.
+ *
+ * Sahid Orentino Ferdjaoui <sahid.ferdjaoui@redhat.com>
+ */
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use virt::connect::Connect;
+use virt::error::ErrorNumber;
+use virt::pool::ConnectPool;
+
+#[test]
+fn test_pool_checkout() {
+    let pool = ConnectPool::new(Some("test:///default"), 2);
+    let a = pool.get().unwrap();
+    let b = pool.get().unwrap();
+    assert_eq!(2, pool.in_use());
+    assert!(pool.try_get().is_none());
+    assert_eq!(
+        ErrorNumber::OperationTimeout,
+        pool.get_timeout(Duration::from_millis(10))
+            .unwrap_err()
+            .code()
+    );
+    assert_eq!(Ok(true), a.is_alive());
+
+    drop(a);
+    b.discard();
+    assert_eq!(0, pool.in_use());
+
+    let c = pool.try_get().unwrap().unwrap();
+    assert_eq!(Ok(true), c.is_alive());
+    assert_eq!(1, pool.in_use());
+}
+
+#[test]
+fn test_pool_open_failure() {
+    let pool = ConnectPool::new(Some("nosuchdriver:///"), 1);
+    assert!(pool.get().is_err());
+    assert_eq!(0, pool.in_use());
+}
+
+static HEALTH_CHECKS: AtomicUsize = AtomicUsize::new(0);
+
+// Reports the connection dead on the first check only.
+fn fail_first_check(_conn: &Connect) -> bool {
+    HEALTH_CHECKS.fetch_add(1, Ordering::SeqCst) > 0
+}
+
+#[test]
+fn test_pool_reconnect() {
+    let pool = ConnectPool::new(Some("test:///default"), 1).health_check(fail_first_check);
+    let conn = pool.get().unwrap();
+    // Keep a reference on the first connection so the next one cannot
+    // be allocated at the same address.
+    let mut first = Connect::clone(&conn);
+    drop(conn);
+
+    let conn = pool.get().unwrap();
+    assert_eq!(1, HEALTH_CHECKS.load(Ordering::SeqCst));
+    assert_ne!(first.as_ptr(), conn.as_ptr());
+    assert_eq!(Ok(true), conn.is_alive());
+    assert_eq!(1, pool.in_use());
+    let second = conn.as_ptr();
+    drop(conn);
+
+    let conn = pool.get().unwrap();
+    assert_eq!(2, HEALTH_CHECKS.load(Ordering::SeqCst));
+    assert_eq!(second, conn.as_ptr());
+    drop(conn);
+    first.close().unwrap();
+}
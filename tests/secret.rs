@@ -0,0 +1,36 @@
+/*
+ * This library is free software; you can redistribute it and/or
+ * modify it under the terms of the GNU Lesser General Public
+ * License as published by the Free Software Foundation; either
+ * version 2.1 of the License, or (at your option) any later version.
+ *
+ * This library is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+ * Lesser General Public License for more details.
+ *
+ * You should have received a copy of the GNU Lesser General Public
+ * License along with this library.  If not, see
+ * <https://www.gnu.org/licenses/>.
+ *
+ * Sahid Orentino Ferdjaoui <sahid.ferdjaoui@redhat.com>
+ */
+
+use virt::secret::SecretUsageType;
+use virt::sys;
+
+#[test]
+fn test_usage_type_raw() {
+    let inputs = [
+        (sys::VIR_SECRET_USAGE_TYPE_NONE, SecretUsageType::None),
+        (sys::VIR_SECRET_USAGE_TYPE_VOLUME, SecretUsageType::Volume),
+        (sys::VIR_SECRET_USAGE_TYPE_CEPH, SecretUsageType::Ceph),
+        (sys::VIR_SECRET_USAGE_TYPE_ISCSI, SecretUsageType::Iscsi),
+        (sys::VIR_SECRET_USAGE_TYPE_TLS, SecretUsageType::Tls),
+        (sys::VIR_SECRET_USAGE_TYPE_VTPM, SecretUsageType::Vtpm),
+    ];
+    for &(raw, usage_type) in inputs.iter() {
+        assert_eq!(usage_type, SecretUsageType::from_raw(raw));
+        assert_eq!(raw, usage_type.to_raw());
+    }
+}
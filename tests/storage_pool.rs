@@ -16,12 +16,18 @@
  * Sahid Orentino Ferdjaoui <sahid.ferdjaoui@redhat.com>
  */
 
+use uuid::Uuid;
+
 use virt::connect::Connect;
-use virt::storage_pool::StoragePool;
+use virt::storage_pool::{
+    StoragePool, StoragePoolCapabilities, StoragePoolDefinition, StoragePoolSource,
+};
+use virt::storage_vol::{StorageVol, StorageVolCreateFlags, VolumeSpec};
 
 mod common;
 
 #[test]
+#[allow(deprecated)]
 fn exercices() {
     match Connect::open(Some("test:///default")) {
         Ok(mut conn) => {
@@ -46,6 +52,7 @@ fn exercices() {
 }
 
 #[test]
+#[allow(deprecated)]
 fn test_lookup_storage_pool_by_name() {
     let c = common::conn();
     let v = c.list_storage_pools().unwrap_or_default();
@@ -56,3 +63,147 @@ fn test_lookup_storage_pool_by_name() {
     }
     common::close(c);
 }
+
+#[test]
+fn test_storage_pool_capabilities() {
+    let c = common::conn();
+    let xml = c.get_storage_pool_capabilities(0).unwrap();
+    let caps = StoragePoolCapabilities::from_xml(&xml).unwrap();
+    assert!(caps.is_supported("dir"));
+    common::close(c);
+}
+
+#[test]
+fn test_storage_pool_capabilities_from_xml() {
+    let xml = "<storagepoolCapabilities>
+  <pool type='dir' supported='yes'>
+    <volOptions>
+      <defaultFormat type='raw'/>
+      <enum name='targetFormatType'>
+        <value>raw</value>
+        <value>qcow2</value>
+      </enum>
+    </volOptions>
+  </pool>
+  <pool type='fs' supported='no'>
+    <poolOptions>
+      <defaultFormat type='auto'/>
+      <enum name='sourceFormatType'>
+        <value>auto</value>
+        <value>ext4</value>
+      </enum>
+    </poolOptions>
+  </pool>
+</storagepoolCapabilities>";
+    let caps = StoragePoolCapabilities::from_xml(xml).unwrap();
+    assert_eq!(2, caps.pools.len());
+    assert!(caps.is_supported("dir"));
+    assert!(!caps.is_supported("fs"));
+    assert!(!caps.is_supported("rbd"));
+
+    let dir = caps.pool("dir").unwrap();
+    assert_eq!(Some("raw".to_string()), dir.default_volume_format);
+    assert_eq!(vec!["raw", "qcow2"], dir.volume_formats);
+    assert!(dir.pool_formats.is_empty());
+
+    let fs = caps.pool("fs").unwrap();
+    assert_eq!(Some("auto".to_string()), fs.default_pool_format);
+    assert_eq!(vec!["auto", "ext4"], fs.pool_formats);
+}
+
+#[test]
+fn test_storage_pool_definition_xml() {
+    let def = StoragePoolDefinition::logical("vms", "vg0", &["/dev/sdb", "/dev/sdc"]);
+    assert_eq!("logical", def.pool_type());
+    assert_eq!(
+        "<pool type='logical'>
+  <name>vms</name>
+  <source>
+    <name>vg0</name>
+    <device path='/dev/sdb'/>
+    <device path='/dev/sdc'/>
+  </source>
+  <target>
+    <path>/dev/vg0</path>
+  </target>
+</pool>
+",
+        def.to_xml()
+    );
+
+    let mut def = StoragePoolDefinition::rbd("ceph", &["mon1", "mon2"], "rbd");
+    if let StoragePoolSource::Rbd { auth, .. } = &mut def.source {
+        *auth = Some((
+            "libvirt".to_string(),
+            Uuid::parse_str("2a5a1cbb-4d2e-44c6-bb62-2b7b1e0e7d6c").unwrap(),
+        ));
+    }
+    assert_eq!(
+        "<pool type='rbd'>
+  <name>ceph</name>
+  <source>
+    <name>rbd</name>
+    <host name='mon1'/>
+    <host name='mon2'/>
+    <auth type='ceph' username='libvirt'>
+      <secret uuid='2a5a1cbb-4d2e-44c6-bb62-2b7b1e0e7d6c'/>
+    </auth>
+  </source>
+</pool>
+",
+        def.to_xml()
+    );
+}
+
+#[test]
+fn test_define_storage_pool_definition() {
+    let c = common::conn();
+    let def = StoragePoolDefinition::dir("libvirt-rs-test-definition", "/var/lib/libvirt/images");
+    let caps =
+        StoragePoolCapabilities::from_xml(&c.get_storage_pool_capabilities(0).unwrap()).unwrap();
+    assert!(def.is_supported_by(&caps));
+
+    let pool = StoragePool::define(&c, &def).unwrap();
+    assert_eq!(
+        Ok("libvirt-rs-test-definition".to_string()),
+        pool.get_name()
+    );
+    assert_eq!(Ok(false), pool.is_active());
+    common::clean_pool(pool);
+    common::close(c);
+}
+
+#[test]
+fn test_volume_spec() {
+    let spec = VolumeSpec::new("disk.qcow2", 1 << 30)
+        .allocation(0)
+        .format("qcow2")
+        .backing_store("/var/lib/libvirt/images/base.qcow2", "qcow2")
+        .prealloc_metadata(true);
+    assert_eq!(StorageVolCreateFlags::PREALLOC_METADATA, spec.flags());
+    assert_eq!(
+        "<volume>
+  <name>disk.qcow2</name>
+  <capacity unit='bytes'>1073741824</capacity>
+  <allocation unit='bytes'>0</allocation>
+  <target>
+    <format type='qcow2'/>
+  </target>
+  <backingStore>
+    <path>/var/lib/libvirt/images/base.qcow2</path>
+    <format type='qcow2'/>
+  </backingStore>
+</volume>
+",
+        spec.to_xml()
+    );
+
+    let c = common::conn();
+    let pool = common::build_storage_pool(&c, "volume-spec", true);
+    let vol = StorageVol::create(&pool, &VolumeSpec::new("spec.img", 1 << 20)).unwrap();
+    assert_eq!(Ok("spec.img".to_string()), vol.get_name());
+    assert_eq!(1 << 20, vol.get_info().unwrap().capacity);
+    common::clean_vol(vol);
+    common::clean_pool(pool);
+    common::close(c);
+}
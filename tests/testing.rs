@@ -0,0 +1,67 @@
+/*
+ * This library is free software; you can redistribute it and/or
+ * modify it under the terms of the GNU Lesser General Public
+ * License as published by the Free Software Foundation; either
+ * version 2.1 of the License, or (at your option) any later version.
+ *
+ * This library is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+ * Lesser General Public License for more details.
+ *
+ * You should have received a copy of the GNU Lesser General Public
+ * License along with this library.  If not, see
+ * <https://www.gnu.org/licenses/>.
+ *
+ * Sahid Orentino Ferdjaoui <sahid.ferdjaoui@redhat.com>
+ */
+
+use virt::domain::Domain;
+use virt::testing;
+
+#[test]
+fn test_domain_fixture() {
+    let c = testing::conn();
+    {
+        let dom = testing::build_test_domain(&c, "fixture", false);
+        assert_eq!(Ok(false), dom.is_active());
+        assert_eq!(Ok("libvirt-rs-test-fixture".to_string()), dom.get_name());
+    }
+    assert!(Domain::lookup_by_name(&c, "libvirt-rs-test-fixture").is_err());
+
+    let dom = testing::build_test_domain(&c, "fixture-kept", true).into_inner();
+    assert_eq!(Ok(true), dom.is_active());
+    assert!(Domain::lookup_by_name(&c, "libvirt-rs-test-fixture-kept").is_ok());
+    dom.destroy().unwrap();
+}
+
+#[test]
+fn test_storage_fixtures() {
+    let c = testing::conn();
+    let pool = testing::build_storage_pool(&c, "fixture", true);
+    let vol = testing::build_storage_vol(&pool, "fixture.img", 1024);
+    assert_eq!(1024 * 1024, vol.get_info().unwrap().capacity);
+    assert_eq!(
+        Ok("libvirt-rs-test-fixture.img".to_string()),
+        vol.get_name()
+    );
+    let net = testing::build_network(&c, "fixture", true);
+    assert_eq!(Ok(true), net.is_active());
+}
+
+#[test]
+fn test_network_fixtures_coexist() {
+    let c = testing::conn();
+    let first = testing::build_network(&c, "fixture-first", true);
+    let second = testing::build_network(&c, "fixture-second", true);
+    assert_eq!(Ok(true), first.is_active());
+    assert_eq!(Ok(true), second.is_active());
+    assert_eq!(
+        Ok("libvirt-rs-test-fixture-first-br".to_string()),
+        first.get_bridge_name()
+    );
+    assert_eq!(
+        Ok("libvirt-rs-test-fixture-second-br".to_string()),
+        second.get_bridge_name()
+    );
+}
@@ -7627,3 +7627,284 @@ extern "C" {
         callbackID: ::libc::c_int,
     ) -> ::libc::c_int;
 }
+#[allow(unsafe_code)]
+pub const VIR_THREADPOOL_WORKERS_MIN: &::std::ffi::CStr =
+    unsafe { ::std::ffi::CStr::from_bytes_with_nul_unchecked(b"minWorkers\0") };
+#[allow(unsafe_code)]
+pub const VIR_THREADPOOL_WORKERS_MAX: &::std::ffi::CStr =
+    unsafe { ::std::ffi::CStr::from_bytes_with_nul_unchecked(b"maxWorkers\0") };
+#[allow(unsafe_code)]
+pub const VIR_THREADPOOL_WORKERS_PRIORITY: &::std::ffi::CStr =
+    unsafe { ::std::ffi::CStr::from_bytes_with_nul_unchecked(b"prioWorkers\0") };
+#[allow(unsafe_code)]
+pub const VIR_THREADPOOL_WORKERS_FREE: &::std::ffi::CStr =
+    unsafe { ::std::ffi::CStr::from_bytes_with_nul_unchecked(b"freeWorkers\0") };
+#[allow(unsafe_code)]
+pub const VIR_THREADPOOL_WORKERS_CURRENT: &::std::ffi::CStr =
+    unsafe { ::std::ffi::CStr::from_bytes_with_nul_unchecked(b"nWorkers\0") };
+#[allow(unsafe_code)]
+pub const VIR_THREADPOOL_JOB_QUEUE_DEPTH: &::std::ffi::CStr =
+    unsafe { ::std::ffi::CStr::from_bytes_with_nul_unchecked(b"jobQueueDepth\0") };
+#[allow(unsafe_code)]
+pub const VIR_CLIENT_INFO_READONLY: &::std::ffi::CStr =
+    unsafe { ::std::ffi::CStr::from_bytes_with_nul_unchecked(b"readonly\0") };
+#[allow(unsafe_code)]
+pub const VIR_CLIENT_INFO_SOCKET_ADDR: &::std::ffi::CStr =
+    unsafe { ::std::ffi::CStr::from_bytes_with_nul_unchecked(b"sock_addr\0") };
+#[allow(unsafe_code)]
+pub const VIR_CLIENT_INFO_SASL_USER_NAME: &::std::ffi::CStr =
+    unsafe { ::std::ffi::CStr::from_bytes_with_nul_unchecked(b"sasl_user_name\0") };
+#[allow(unsafe_code)]
+pub const VIR_CLIENT_INFO_X509_DISTINGUISHED_NAME: &::std::ffi::CStr =
+    unsafe { ::std::ffi::CStr::from_bytes_with_nul_unchecked(b"tls_x509_dname\0") };
+#[allow(unsafe_code)]
+pub const VIR_CLIENT_INFO_UNIX_USER_ID: &::std::ffi::CStr =
+    unsafe { ::std::ffi::CStr::from_bytes_with_nul_unchecked(b"unix_user_id\0") };
+#[allow(unsafe_code)]
+pub const VIR_CLIENT_INFO_UNIX_USER_NAME: &::std::ffi::CStr =
+    unsafe { ::std::ffi::CStr::from_bytes_with_nul_unchecked(b"unix_user_name\0") };
+#[allow(unsafe_code)]
+pub const VIR_CLIENT_INFO_UNIX_GROUP_ID: &::std::ffi::CStr =
+    unsafe { ::std::ffi::CStr::from_bytes_with_nul_unchecked(b"unix_group_id\0") };
+#[allow(unsafe_code)]
+pub const VIR_CLIENT_INFO_UNIX_GROUP_NAME: &::std::ffi::CStr =
+    unsafe { ::std::ffi::CStr::from_bytes_with_nul_unchecked(b"unix_group_name\0") };
+#[allow(unsafe_code)]
+pub const VIR_CLIENT_INFO_UNIX_PROCESS_ID: &::std::ffi::CStr =
+    unsafe { ::std::ffi::CStr::from_bytes_with_nul_unchecked(b"unix_process_id\0") };
+#[allow(unsafe_code)]
+pub const VIR_CLIENT_INFO_SELINUX_CONTEXT: &::std::ffi::CStr =
+    unsafe { ::std::ffi::CStr::from_bytes_with_nul_unchecked(b"selinux_context\0") };
+#[allow(unsafe_code)]
+pub const VIR_SERVER_CLIENTS_MAX: &::std::ffi::CStr =
+    unsafe { ::std::ffi::CStr::from_bytes_with_nul_unchecked(b"nclients_max\0") };
+#[allow(unsafe_code)]
+pub const VIR_SERVER_CLIENTS_CURRENT: &::std::ffi::CStr =
+    unsafe { ::std::ffi::CStr::from_bytes_with_nul_unchecked(b"nclients\0") };
+#[allow(unsafe_code)]
+pub const VIR_SERVER_CLIENTS_UNAUTH_MAX: &::std::ffi::CStr =
+    unsafe { ::std::ffi::CStr::from_bytes_with_nul_unchecked(b"nclients_unauth_max\0") };
+#[allow(unsafe_code)]
+pub const VIR_SERVER_CLIENTS_UNAUTH_CURRENT: &::std::ffi::CStr =
+    unsafe { ::std::ffi::CStr::from_bytes_with_nul_unchecked(b"nclients_unauth\0") };
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct _virAdmConnect {
+    _unused: [u8; 0],
+}
+pub type virAdmConnect = _virAdmConnect;
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct _virAdmServer {
+    _unused: [u8; 0],
+}
+pub type virAdmServer = _virAdmServer;
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct _virAdmClient {
+    _unused: [u8; 0],
+}
+pub type virAdmClient = _virAdmClient;
+pub type virAdmConnectPtr = *mut virAdmConnect;
+pub type virAdmServerPtr = *mut virAdmServer;
+pub type virAdmClientPtr = *mut virAdmClient;
+extern "C" {
+    pub fn virAdmInitialize() -> ::libc::c_int;
+}
+extern "C" {
+    pub fn virAdmConnectOpen(
+        name: *const ::libc::c_char,
+        flags: ::libc::c_uint,
+    ) -> virAdmConnectPtr;
+}
+extern "C" {
+    pub fn virAdmConnectClose(conn: virAdmConnectPtr) -> ::libc::c_int;
+}
+extern "C" {
+    pub fn virAdmConnectRef(conn: virAdmConnectPtr) -> ::libc::c_int;
+}
+extern "C" {
+    pub fn virAdmConnectIsAlive(conn: virAdmConnectPtr) -> ::libc::c_int;
+}
+extern "C" {
+    pub fn virAdmServerFree(srv: virAdmServerPtr) -> ::libc::c_int;
+}
+extern "C" {
+    pub fn virAdmConnectListServers(
+        dmn: virAdmConnectPtr,
+        servers: *mut *mut virAdmServerPtr,
+        flags: ::libc::c_uint,
+    ) -> ::libc::c_int;
+}
+extern "C" {
+    pub fn virAdmGetVersion(libVer: *mut ::libc::c_ulonglong) -> ::libc::c_int;
+}
+extern "C" {
+    pub fn virAdmConnectGetURI(conn: virAdmConnectPtr) -> *mut ::libc::c_char;
+}
+extern "C" {
+    pub fn virAdmConnectGetLibVersion(
+        conn: virAdmConnectPtr,
+        libVer: *mut ::libc::c_ulonglong,
+    ) -> ::libc::c_int;
+}
+pub type virAdmConnectCloseFunc = ::std::option::Option<
+    unsafe extern "C" fn(conn: virAdmConnectPtr, reason: ::libc::c_int, opaque: *mut ::libc::c_void),
+>;
+extern "C" {
+    pub fn virAdmConnectRegisterCloseCallback(
+        conn: virAdmConnectPtr,
+        cb: virAdmConnectCloseFunc,
+        opaque: *mut ::libc::c_void,
+        freecb: virFreeCallback,
+    ) -> ::libc::c_int;
+}
+extern "C" {
+    pub fn virAdmConnectUnregisterCloseCallback(
+        conn: virAdmConnectPtr,
+        cb: virAdmConnectCloseFunc,
+    ) -> ::libc::c_int;
+}
+extern "C" {
+    pub fn virAdmServerGetName(srv: virAdmServerPtr) -> *const ::libc::c_char;
+}
+extern "C" {
+    pub fn virAdmConnectLookupServer(
+        conn: virAdmConnectPtr,
+        name: *const ::libc::c_char,
+        flags: ::libc::c_uint,
+    ) -> virAdmServerPtr;
+}
+extern "C" {
+    pub fn virAdmServerGetThreadPoolParameters(
+        srv: virAdmServerPtr,
+        params: *mut virTypedParameterPtr,
+        nparams: *mut ::libc::c_int,
+        flags: ::libc::c_uint,
+    ) -> ::libc::c_int;
+}
+extern "C" {
+    pub fn virAdmServerSetThreadPoolParameters(
+        srv: virAdmServerPtr,
+        params: virTypedParameterPtr,
+        nparams: ::libc::c_int,
+        flags: ::libc::c_uint,
+    ) -> ::libc::c_int;
+}
+extern "C" {
+    pub fn virAdmServerListClients(
+        srv: virAdmServerPtr,
+        clients: *mut *mut virAdmClientPtr,
+        flags: ::libc::c_uint,
+    ) -> ::libc::c_int;
+}
+extern "C" {
+    pub fn virAdmServerLookupClient(
+        srv: virAdmServerPtr,
+        id: ::libc::c_ulonglong,
+        flags: ::libc::c_uint,
+    ) -> virAdmClientPtr;
+}
+pub const VIR_CLIENT_TRANS_UNIX: virClientTransport = 0;
+pub const VIR_CLIENT_TRANS_TCP: virClientTransport = 1;
+pub const VIR_CLIENT_TRANS_TLS: virClientTransport = 2;
+pub type virClientTransport = ::libc::c_uint;
+extern "C" {
+    pub fn virAdmClientFree(client: virAdmClientPtr) -> ::libc::c_int;
+}
+extern "C" {
+    pub fn virAdmClientGetID(client: virAdmClientPtr) -> ::libc::c_ulonglong;
+}
+extern "C" {
+    pub fn virAdmClientGetTimestamp(client: virAdmClientPtr) -> ::libc::c_longlong;
+}
+extern "C" {
+    pub fn virAdmClientGetTransport(client: virAdmClientPtr) -> ::libc::c_int;
+}
+extern "C" {
+    pub fn virAdmClientGetInfo(
+        client: virAdmClientPtr,
+        params: *mut virTypedParameterPtr,
+        nparams: *mut ::libc::c_int,
+        flags: ::libc::c_uint,
+    ) -> ::libc::c_int;
+}
+extern "C" {
+    pub fn virAdmClientClose(client: virAdmClientPtr, flags: ::libc::c_uint) -> ::libc::c_int;
+}
+extern "C" {
+    pub fn virAdmServerGetClientLimits(
+        srv: virAdmServerPtr,
+        params: *mut virTypedParameterPtr,
+        nparams: *mut ::libc::c_int,
+        flags: ::libc::c_uint,
+    ) -> ::libc::c_int;
+}
+extern "C" {
+    pub fn virAdmServerSetClientLimits(
+        srv: virAdmServerPtr,
+        params: virTypedParameterPtr,
+        nparams: ::libc::c_int,
+        flags: ::libc::c_uint,
+    ) -> ::libc::c_int;
+}
+extern "C" {
+    pub fn virAdmServerUpdateTlsFiles(srv: virAdmServerPtr, flags: ::libc::c_uint)
+        -> ::libc::c_int;
+}
+extern "C" {
+    pub fn virAdmConnectGetLoggingOutputs(
+        conn: virAdmConnectPtr,
+        outputs: *mut *mut ::libc::c_char,
+        flags: ::libc::c_uint,
+    ) -> ::libc::c_int;
+}
+extern "C" {
+    pub fn virAdmConnectGetLoggingFilters(
+        conn: virAdmConnectPtr,
+        filters: *mut *mut ::libc::c_char,
+        flags: ::libc::c_uint,
+    ) -> ::libc::c_int;
+}
+extern "C" {
+    pub fn virAdmConnectSetLoggingOutputs(
+        conn: virAdmConnectPtr,
+        outputs: *const ::libc::c_char,
+        flags: ::libc::c_uint,
+    ) -> ::libc::c_int;
+}
+extern "C" {
+    pub fn virAdmConnectSetLoggingFilters(
+        conn: virAdmConnectPtr,
+        filters: *const ::libc::c_char,
+        flags: ::libc::c_uint,
+    ) -> ::libc::c_int;
+}
+extern "C" {
+    pub fn virDomainLxcOpenNamespace(
+        domain: virDomainPtr,
+        fdlist: *mut *mut ::libc::c_int,
+        flags: ::libc::c_uint,
+    ) -> ::libc::c_int;
+}
+extern "C" {
+    pub fn virDomainLxcEnterNamespace(
+        domain: virDomainPtr,
+        nfdlist: ::libc::c_uint,
+        fdlist: *mut ::libc::c_int,
+        noldfdlist: *mut ::libc::c_uint,
+        oldfdlist: *mut *mut ::libc::c_int,
+        flags: ::libc::c_uint,
+    ) -> ::libc::c_int;
+}
+extern "C" {
+    pub fn virDomainLxcEnterSecurityLabel(
+        model: virSecurityModelPtr,
+        label: virSecurityLabelPtr,
+        oldlabel: virSecurityLabelPtr,
+        flags: ::libc::c_uint,
+    ) -> ::libc::c_int;
+}
+extern "C" {
+    pub fn virDomainLxcEnterCGroup(domain: virDomainPtr, flags: ::libc::c_uint) -> ::libc::c_int;
+}
@@ -19,10 +19,16 @@ fn main() {
 fn bindgen_regenerate(bindgen_out_file: &PathBuf) -> Result<(), Box<dyn Error>> {
 
     // We want to make sure that the generated bindings.rs file includes all libvirt APIs,
-    // including the ones that are QEMU-specific
+    // including the ones that are QEMU and LXC specific and the admin ones
     if !cfg!(feature = "qemu") {
         return Err("qemu must be enabled along with bindgen_regenerate".into())
     }
+    if !cfg!(feature = "lxc") {
+        return Err("lxc must be enabled along with bindgen_regenerate".into())
+    }
+    if !cfg!(feature = "admin") {
+        return Err("admin must be enabled along with bindgen_regenerate".into())
+    }
 
     let bindings = bindgen::builder()
         .header("wrapper.h")
@@ -51,6 +57,16 @@ fn bindgen_regenerate(_: &PathBuf) -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
+// Converts a "major.minor.micro" version into the number used by libvirt,
+// major * 1,000,000 + minor * 1,000 + micro.
+fn version_number(version: &str) -> Option<u64> {
+    let mut parts = version.split('.').map(|p| p.parse::<u64>());
+    let major = parts.next()?.ok()?;
+    let minor = parts.next().unwrap_or(Ok(0)).ok()?;
+    let micro = parts.next().unwrap_or(Ok(0)).ok()?;
+    Some(major * 1_000_000 + minor * 1_000 + micro)
+}
+
 fn run() -> Result<(), Box<dyn Error>> {
     println!("cargo:rerun-if-changed=wrapper.h");
 
@@ -61,16 +77,38 @@ fn run() -> Result<(), Box<dyn Error>> {
     // files are not present. This is necessary so that docs.rs can build and publish the API
     // documentation for libvirt-rust. If any of these calls fail, then we'll still get an error
     // when attempting to link against libvirt (e.g. when building the test suite).
-    let _ = config
+    let lib = config
         .atleast_version(LIBVIRT_VERSION)
         .probe("libvirt");
 
+    // Export the version of the installed library to the crates depending on
+    // this one, as DEP_VIRT_VERSION_NUMBER, so that they can leave out the
+    // wrappers of the APIs it lacks. Nothing is exported when the library was
+    // not found, in which case every API should be assumed available.
+    if let Ok(lib) = &lib {
+        if let Some(number) = version_number(&lib.version) {
+            println!("cargo:version_number={}", number);
+        }
+    }
+
     if cfg!(feature = "qemu") {
         let _ = config
             .atleast_version(LIBVIRT_VERSION)
             .probe("libvirt-qemu");
     }
 
+    if cfg!(feature = "lxc") {
+        let _ = config
+            .atleast_version(LIBVIRT_VERSION)
+            .probe("libvirt-lxc");
+    }
+
+    if cfg!(feature = "admin") {
+        let _ = config
+            .atleast_version(LIBVIRT_VERSION)
+            .probe("libvirt-admin");
+    }
+
     let bindgen_in_dir = PathBuf::from("bindgen");
     let bindgen_in_file = bindgen_in_dir.join("bindings.rs");
     let bindgen_out_dir = PathBuf::from(env::var("OUT_DIR")?);
@@ -2,3 +2,5 @@
 #include <libvirt/virterror.h>
 
 #include <libvirt/libvirt-qemu.h>
+#include <libvirt/libvirt-lxc.h>
+#include <libvirt/libvirt-admin.h>